@@ -1,10 +1,20 @@
 //! Authentication flow interface
-use crate::{launcher::auth as inner, State};
-use chrono::Utc;
+use crate::event::emit::emit_account;
+use crate::event::AccountPayloadType;
+use crate::{launcher::auth as inner, RecoveryAction, State};
+use chrono::{Duration, Utc};
 use tokio::sync::oneshot;
 
 use crate::state::AuthTask;
-pub use inner::Credentials;
+pub use inner::{AuthBackend, Credentials};
+
+/// How long before expiring the tokens of an account are refreshed by
+/// account validation, in minutes
+const VALIDATION_MARGIN: i64 = 60;
+/// Pause between refreshing two accounts, so a launcher holding many of them
+/// does not run into rate limits
+const VALIDATION_SPACING: std::time::Duration =
+    std::time::Duration::from_secs(2);
 
 /// Authenticate a user with Hydra - part 1
 /// This begins the authentication flow quasi-synchronously, returning a URL
@@ -26,6 +36,14 @@ pub async fn authenticate_await_complete_flow(
     Ok(credentials)
 }
 
+/// Signs in again with an account whose tokens can't be refreshed until it
+/// grants permissions again, as marked by `needs_consent`. Works like
+/// `authenticate_begin_flow`, finished by `authenticate_await_complete_flow`,
+/// but updates the account rather than adding one
+pub async fn reconsent_begin_flow(user: uuid::Uuid) -> crate::Result<url::Url> {
+    AuthTask::begin_reconsent(user).await
+}
+
 /// Cancels the active authentication flow
 pub async fn cancel_flow() -> crate::Result<()> {
     AuthTask::cancel().await
@@ -39,6 +57,24 @@ pub async fn cancel_flow() -> crate::Result<()> {
 #[theseus_macros::debug_pin]
 pub async fn authenticate(
     browser_url: oneshot::Sender<url::Url>,
+) -> crate::Result<(Credentials, Option<String>)> {
+    run_flow(browser_url, None).await
+}
+
+/// Signs in again with an account that needs to consent, see
+/// `reconsent_begin_flow`. Signing in with another account fails
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn reconsent(
+    browser_url: oneshot::Sender<url::Url>,
+    user: uuid::Uuid,
+) -> crate::Result<(Credentials, Option<String>)> {
+    run_flow(browser_url, Some(user)).await
+}
+
+async fn run_flow(
+    browser_url: oneshot::Sender<url::Url>,
+    reconsent: Option<uuid::Uuid>,
 ) -> crate::Result<(Credentials, Option<String>)> {
     let mut flow = inner::HydraAuthFlow::new().await?;
     let state = State::get().await?;
@@ -53,6 +89,30 @@ pub async fn authenticate(
     let credentials = flow.extract_credentials(&state.fetch_semaphore).await?;
     {
         let mut users = state.users.write().await;
+        if let Some(user) = reconsent {
+            let previous = users.get(user).ok_or_else(|| {
+                crate::ErrorKind::InputError(format!(
+                    "Tried to consent again with nonexistent user {user}"
+                ))
+            })?;
+            if credentials.0.id != user {
+                return Err(crate::ErrorKind::InputError(format!(
+                    "Signed in as {}, rather than {}",
+                    credentials.0.username, previous.username
+                ))
+                .into());
+            }
+
+            users.insert(&credentials.0).await?;
+            emit_account(
+                user,
+                &credentials.0.username,
+                &previous.username,
+                AccountPayloadType::Updated,
+            )
+            .await?;
+            return Ok(credentials);
+        }
         users.insert(&credentials.0).await?;
     }
 
@@ -64,11 +124,59 @@ pub async fn authenticate(
     Ok(credentials)
 }
 
+/// Log in to a third-party Yggdrasil-compatible authentication server.
+/// The account is launched through authlib-injector, pointed at the server
+#[tracing::instrument(skip(password))]
+#[theseus_macros::debug_pin]
+pub async fn authenticate_yggdrasil(
+    server_url: &str,
+    username: &str,
+    password: &str,
+) -> crate::Result<Credentials> {
+    let state = State::get().await?;
+    let credentials = inner::yggdrasil_authenticate(
+        server_url,
+        username,
+        password,
+        &state.fetch_semaphore,
+    )
+    .await?;
+    {
+        let mut users = state.users.write().await;
+        users.insert(&credentials).await?;
+    }
+
+    if state.settings.read().await.default_user.is_none() {
+        let mut settings = state.settings.write().await;
+        settings.default_user = Some(credentials.id);
+    }
+
+    Ok(credentials)
+}
+
 /// Refresh some credentials using Hydra, if needed
 /// This is the primary desired way to get credentials, as it will also refresh them.
+/// The name and skins of the account are fetched again once they are outdated
 #[tracing::instrument]
 #[theseus_macros::debug_pin]
 pub async fn refresh(user: uuid::Uuid) -> crate::Result<Credentials> {
+    refresh_account(user, false).await
+}
+
+/// Refresh some credentials, always fetching the current name and skins so
+/// the game is launched with them
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn refresh_for_launch(
+    user: uuid::Uuid,
+) -> crate::Result<Credentials> {
+    refresh_account(user, true).await
+}
+
+async fn refresh_account(
+    user: uuid::Uuid,
+    force_profile: bool,
+) -> crate::Result<Credentials> {
     let state = State::get().await?;
     let mut users = state.users.write().await;
 
@@ -80,23 +188,165 @@ pub async fn refresh(user: uuid::Uuid) -> crate::Result<Credentials> {
     })?;
 
     let fetch_semaphore = &state.fetch_semaphore;
-    if Utc::now() > credentials.expires
-        && inner::refresh_credentials(&mut credentials, fetch_semaphore)
-            .await
-            .is_err()
-    {
-        users.remove(credentials.id).await?;
+    if Utc::now() > credentials.expires {
+        if let Err(err) =
+            inner::refresh_credentials(&mut credentials, fetch_semaphore).await
+        {
+            // Logging in again would only add the account back, so it is
+            // kept for `reconsent_begin_flow`
+            if credentials.needs_consent {
+                credentials.needs_reauth = true;
+                users.insert(&credentials).await?;
+                emit_account(
+                    credentials.id,
+                    &credentials.username,
+                    &credentials.username,
+                    AccountPayloadType::NeedsConsent,
+                )
+                .await?;
+                return Err(err);
+            }
 
-        return Err(crate::ErrorKind::OtherError(
-            "Please re-authenticate with your Minecraft account!".to_string(),
-        )
-        .as_error());
+            users.remove(credentials.id).await?;
+
+            return Err(crate::ErrorKind::OtherError(
+                "Please re-authenticate with your Minecraft account!"
+                    .to_string(),
+            )
+            .as_error());
+        }
+    }
+
+    if force_profile || credentials.profile_outdated() {
+        let previous_username = credentials.username.clone();
+        match inner::refresh_profile(&mut credentials, fetch_semaphore).await {
+            Ok(true) => {
+                emit_account(
+                    credentials.id,
+                    &credentials.username,
+                    &previous_username,
+                    AccountPayloadType::Updated,
+                )
+                .await?;
+            }
+            Ok(false) => {}
+            // Keep the cached name, it is better than not launching at all
+            Err(err) => tracing::warn!(
+                "Unable to refresh Minecraft profile of {}: {err}",
+                credentials.id
+            ),
+        }
     }
     users.insert(&credentials).await?;
 
     Ok(credentials)
 }
 
+/// Refreshes the tokens of every stored account that is about to expire, so
+/// switching to one does not fail. Accounts whose tokens cannot be refreshed
+/// are marked as needing to log in again instead of being removed.
+/// Returns the accounts that need to log in again
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn validate_accounts() -> crate::Result<Vec<uuid::Uuid>> {
+    let state = State::get().await?;
+    let ids = {
+        let users = state.users.read().await;
+        users.0.keys().cloned().collect::<Vec<_>>()
+    };
+
+    let mut refreshed_any = false;
+    for id in ids {
+        if *state.offline.read().await {
+            break;
+        }
+
+        let Some(mut credentials) = state.users.read().await.get(id) else {
+            continue;
+        };
+        if credentials.needs_reauth
+            || credentials.expires - Duration::minutes(VALIDATION_MARGIN)
+                > Utc::now()
+        {
+            continue;
+        }
+
+        if refreshed_any {
+            tokio::time::sleep(VALIDATION_SPACING).await;
+        }
+        refreshed_any = true;
+
+        // Refreshed without holding the accounts, which launching needs.
+        // Tokens can only be used once, so the result is dropped if the
+        // account was refreshed in the meantime
+        let (access_token, refresh_token) = (
+            credentials.access_token.clone(),
+            credentials.refresh_token.clone(),
+        );
+        let result = inner::refresh_credentials(
+            &mut credentials,
+            &state.fetch_semaphore,
+        )
+        .await;
+        if let Err(err) = &result {
+            // Losing the connection or a failing server is not the
+            // account's fault
+            if err.details().recovery != Some(RecoveryAction::SignIn) {
+                tracing::warn!(
+                    "Unable to reach the server to refresh account {}: {err}",
+                    credentials.id
+                );
+                state.refresh_offline().await?;
+                if *state.offline.read().await {
+                    break;
+                }
+                continue;
+            }
+
+            tracing::warn!(
+                "Unable to refresh tokens of account {}: {err}",
+                credentials.id
+            );
+            credentials.needs_reauth = true;
+        }
+
+        {
+            let mut users = state.users.write().await;
+            match users.get(id) {
+                Some(current)
+                    if current.access_token == access_token
+                        && current.refresh_token == refresh_token =>
+                {
+                    users.insert(&credentials).await?;
+                }
+                _ => continue,
+            }
+        }
+
+        if result.is_err() {
+            emit_account(
+                credentials.id,
+                &credentials.username,
+                &credentials.username,
+                if credentials.needs_consent {
+                    AccountPayloadType::NeedsConsent
+                } else {
+                    AccountPayloadType::NeedsReauth
+                },
+            )
+            .await?;
+        }
+    }
+
+    let users = state.users.read().await;
+    Ok(users
+        .0
+        .values()
+        .filter(|x| x.needs_reauth)
+        .map(|x| x.id)
+        .collect())
+}
+
 /// Remove a user account from the database
 #[tracing::instrument]
 pub async fn remove_user(user: uuid::Uuid) -> crate::Result<()> {
@@ -0,0 +1,1011 @@
+//! Theseus shared library and asset cache management
+use crate::{
+    launcher::{download, lwjgl, prepare, processors, session},
+    state::{CredentialsStore, ProfileInstallStage, ProfilePathId},
+    util::{
+        fetch::{fetch, link_or_copy, read_json, sha1_async, write},
+        io,
+    },
+    State,
+};
+use async_recursion::async_recursion;
+use daedalus::{
+    self as d,
+    minecraft::{AssetsIndex, VersionInfo as GameVersionInfo},
+};
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// Number of cache entries hashed or repaired at once
+const CACHE_CONCURRENCY: usize = 16;
+
+/// Summary of a cache verification or garbage collection pass
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CacheReport {
+    /// Number of cached files that were inspected
+    pub checked: usize,
+    /// Files that were missing or corrupt and have been downloaded again
+    pub repaired: Vec<PathBuf>,
+    /// Files that were no longer referenced by any profile and were deleted
+    pub removed: Vec<PathBuf>,
+    /// Total size of the removed files, in bytes
+    pub reclaimed_bytes: u64,
+    /// Whether nothing was deleted, so the removed files are the ones that
+    /// would be and the reclaimed bytes what deleting them would free
+    pub dry_run: bool,
+}
+
+/// An asset index in the shared cache, and the objects it references
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AssetIndexUsage {
+    pub id: String,
+    /// Whether a version of a profile uses the index
+    pub in_use: bool,
+    pub objects: usize,
+    /// Size of its objects that no index in use references, which pruning
+    /// frees
+    pub reclaimable_bytes: u64,
+}
+
+/// Asset indexes and objects no profile uses, removed or reported
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AssetPruneReport {
+    pub indexes: Vec<AssetIndexUsage>,
+    /// Number of objects that were inspected
+    pub checked: usize,
+    /// Indexes, their virtual asset folders, and objects
+    pub removed: Vec<PathBuf>,
+    pub reclaimed_bytes: u64,
+    pub dry_run: bool,
+}
+
+// Instance folders whose contents the game only ever reads, so identical
+// files in them can safely share storage
+const DEDUP_FOLDERS: [&str; 4] =
+    ["mods", "resourcepacks", "shaderpacks", ".fabric/processedMods"];
+
+/// A set of byte-identical files inside a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DuplicateGroup {
+    pub sha1: String,
+    /// Size of a single copy, in bytes
+    pub size: u64,
+    /// Paths relative to the profile folder
+    pub paths: Vec<PathBuf>,
+}
+
+/// Duplicated files found in a profile, and the space merging them frees
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// Bytes taken up by redundant copies
+    pub duplicated_bytes: u64,
+    /// Bytes freed by hard linking redundant copies
+    pub reclaimed_bytes: u64,
+}
+
+/// Temporary files removed for game sessions that are no longer running
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SessionCleanupReport {
+    /// Number of session directories that were removed
+    pub sessions: usize,
+    pub reclaimed_bytes: u64,
+}
+
+/// Space taken up by the files the launcher keeps outside of profiles, in
+/// bytes, along with the profiles themselves
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StorageReport {
+    pub libraries: u64,
+    /// Asset objects and indexes, and the virtual asset folders of old
+    /// versions
+    pub assets: u64,
+    /// Version jars and their metadata
+    pub versions: u64,
+    pub natives: u64,
+    /// Java installs the launcher downloaded
+    pub java: u64,
+    pub caches: u64,
+    /// Part of `caches` taken up by the temporary files of game sessions
+    pub sessions: u64,
+    /// Every profile folder, which `profile::get_disk_usage` breaks down
+    pub profiles: u64,
+    pub total: u64,
+}
+
+// A single content-addressed entry in the shared cache
+struct CacheEntry {
+    path: PathBuf,
+    url: String,
+    sha1: Option<String>,
+    // Whether the entry applies to this platform, so a missing file should
+    // be fetched rather than ignored
+    required: bool,
+}
+
+/// Checks the SHA1 of every library and asset used by installed profiles,
+/// re-downloading anything that is missing or corrupt
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn verify_and_repair() -> crate::Result<CacheReport> {
+    let state = State::get().await?;
+    let versions = profile_version_infos(&state, false).await?;
+
+    let mut entries = Vec::new();
+    let mut legacy_assets = Vec::new();
+    let mut seen = HashSet::new();
+    let game_dirs = state.directories.shared_game_dirs().await;
+    for ProfileVersion { info: version, .. } in &versions {
+        for entry in version_entries(&state, version).await? {
+            if seen.insert(entry.path.clone()) {
+                entries.push(entry);
+            }
+        }
+
+        if let Some(virtual_dir) =
+            download::virtual_assets_dir(&game_dirs, &version.asset_index.id)
+        {
+            let index = download::download_assets_index(
+                &state, &game_dirs, version, None,
+            )
+            .await?;
+            for (name, asset) in index.objects {
+                legacy_assets.push((
+                    state.directories.object_dir(&asset.hash).await,
+                    virtual_dir.join(name.replace(
+                        '/',
+                        &String::from(std::path::MAIN_SEPARATOR),
+                    )),
+                ));
+            }
+        }
+    }
+
+    // Launches prepared before checked files that may be replaced now
+    prepare::forget_all();
+
+    let checked = entries.len();
+    let state_ref = &state;
+    let repaired = stream::iter(entries)
+        .map(|entry| async move {
+            if !entry.required && !entry.path.exists() {
+                return Ok::<_, crate::Error>(None);
+            }
+            if verify_file(&entry.path, entry.sha1.as_deref()).await? {
+                return Ok::<_, crate::Error>(None);
+            }
+
+            tracing::info!("Repairing cached file {}", entry.path.display());
+            let bytes = fetch(
+                &entry.url,
+                entry.sha1.as_deref(),
+                &state_ref.fetch_semaphore,
+                &CredentialsStore(None),
+            )
+            .await?;
+            write(&entry.path, &bytes, &state_ref.io_semaphore).await?;
+            Ok(Some(entry.path))
+        })
+        .buffer_unordered(CACHE_CONCURRENCY)
+        .try_collect::<Vec<_>>()
+        .await?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // Legacy asset layouts are views of the object store, so relink any
+    // whose backing object was just replaced
+    let repaired_set = repaired.iter().collect::<HashSet<_>>();
+    for (object, legacy) in legacy_assets {
+        if !legacy.exists() || repaired_set.contains(&object) {
+            link_or_copy(&object, &legacy, &state.io_semaphore).await?;
+        }
+    }
+
+    Ok(CacheReport {
+        checked,
+        repaired,
+        ..Default::default()
+    })
+}
+
+/// Removes libraries, natives and asset objects that are not referenced by
+/// any profile, counting the LWJGL overrides of profiles, and cached
+/// processor runs whose outputs are gone. Fails while a profile is being
+/// installed. A dry run only reports what would be removed
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn gc(dry_run: bool) -> crate::Result<CacheReport> {
+    let state = State::get().await?;
+    if !dry_run {
+        ensure_no_install(&state).await?;
+        // Launches prepared before checked files that may be removed now
+        prepare::forget_all();
+    }
+    // Profiles that aren't installed yet, or failed to, keep what they
+    // already downloaded
+    let versions = profile_version_infos(&state, true).await?;
+
+    let mut referenced = HashSet::new();
+    for ProfileVersion { info: version, .. } in &versions {
+        for entry in version_entries(&state, version).await? {
+            referenced.insert(entry.path);
+        }
+
+        // Processor outputs are not part of the library list, but live in
+        // the libraries directory and are required to launch
+        let libraries_dir = state.directories.libraries_dir().await;
+        for processor in version.processors.iter().flatten() {
+            for artifact in processor
+                .classpath
+                .iter()
+                .chain(std::iter::once(&processor.jar))
+            {
+                referenced.insert(
+                    libraries_dir.join(d::get_path_from_artifact(artifact)?),
+                );
+            }
+        }
+        for entry in version.data.iter().flat_map(|data| data.values()) {
+            if let Some(artifact) = entry
+                .client
+                .strip_prefix('[')
+                .and_then(|x| x.strip_suffix(']'))
+            {
+                referenced.insert(
+                    libraries_dir.join(d::get_path_from_artifact(artifact)?),
+                );
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect_files(&state.directories.libraries_dir().await, &mut files)
+        .await?;
+    let checked = files.len();
+
+    let mut removed = Vec::new();
+    let mut reclaimed_bytes = 0;
+    for file in files {
+        if referenced.contains(&file) {
+            continue;
+        }
+
+        let size = file_size(&file).await;
+        if !dry_run {
+            io::remove_file(&file).await?;
+            tracing::debug!("Removed unreferenced library {}", file.display());
+        }
+        reclaimed_bytes += size;
+        removed.push(file);
+    }
+
+    // Natives are extracted per version, and apart for each LWJGL override
+    let natives_dir = state.directories.natives_dir().await;
+    let natives_in_use = versions
+        .iter()
+        .map(|x| {
+            natives_dir
+                .join(lwjgl::natives_version(&x.info.id, x.lwjgl.as_deref()))
+        })
+        .collect::<HashSet<_>>();
+    let mut checked_natives = 0;
+    if natives_dir.exists() {
+        let mut read_dir = io::read_dir(&natives_dir).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| io::IOError::with_path(e, &natives_dir))?
+        {
+            let path = entry.path();
+            checked_natives += 1;
+            if natives_in_use.contains(&path) || !path.is_dir() {
+                continue;
+            }
+
+            reclaimed_bytes += dir_size(&path).await?;
+            if !dry_run {
+                io::remove_dir_all(&path).await?;
+                tracing::debug!("Removed unused natives {}", path.display());
+            }
+            removed.push(path);
+        }
+    }
+
+    // Cached processor runs are recorded by where their outputs are, so
+    // runs whose outputs are gone can't be reused
+    let removed_files = removed.iter().cloned().collect::<HashSet<_>>();
+    let (checked_runs, stale_runs) =
+        processors::stale_runs(&state, &removed_files).await?;
+    for run in stale_runs {
+        if run.is_dir() {
+            reclaimed_bytes += dir_size(&run).await?;
+            if !dry_run {
+                io::remove_dir_all(&run).await?;
+            }
+        } else {
+            reclaimed_bytes += file_size(&run).await;
+            if !dry_run {
+                io::remove_file(&run).await?;
+            }
+        }
+        if !dry_run {
+            tracing::debug!("Removed stale processor run {}", run.display());
+        }
+        removed.push(run);
+    }
+
+    // Asset objects are pruned along with the indexes referencing them
+    let assets = prune_assets(dry_run).await?;
+    removed.extend(assets.removed);
+
+    Ok(CacheReport {
+        checked: checked + checked_natives + checked_runs + assets.checked,
+        removed,
+        reclaimed_bytes: reclaimed_bytes + assets.reclaimed_bytes,
+        dry_run,
+        ..Default::default()
+    })
+}
+
+/// Removes the asset indexes of versions no profile uses, along with their
+/// virtual asset folders and every object only they reference. Fails while a
+/// profile is being installed. A dry run only reports what would be removed
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn prune_assets(dry_run: bool) -> crate::Result<AssetPruneReport> {
+    let state = State::get().await?;
+    if !dry_run {
+        ensure_no_install(&state).await?;
+        prepare::forget_all();
+    }
+    let versions = profile_version_infos(&state, true).await?;
+    let game_dirs = state.directories.shared_game_dirs().await;
+
+    // Indexes of installed versions are fetched again if they went missing,
+    // or their objects would look unreferenced
+    let mut in_use = HashSet::from([download::EMPTY_ASSETS_INDEX.to_string()]);
+    for ProfileVersion { info: version, .. } in &versions {
+        download::download_assets_index(&state, &game_dirs, version, None)
+            .await?;
+        in_use.insert(version.asset_index.id.clone());
+    }
+
+    let references = asset_references(&game_dirs.assets_index_dir()).await?;
+    let referenced = references
+        .iter()
+        .filter(|(id, _)| in_use.contains(*id))
+        .flat_map(|(_, objects)| objects.iter().map(|x| x.as_str()))
+        .collect::<HashSet<_>>();
+
+    let mut files = Vec::new();
+    collect_files(&game_dirs.objects_dir(), &mut files).await?;
+    let mut report = AssetPruneReport {
+        checked: files.len(),
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut unreferenced = HashMap::new();
+    for file in files {
+        let hash = file
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if referenced.contains(hash.as_str()) {
+            continue;
+        }
+
+        let size = file_size(&file).await;
+        if !dry_run {
+            io::remove_file(&file).await?;
+        }
+        unreferenced.insert(hash, size);
+        report.reclaimed_bytes += size;
+        report.removed.push(file);
+    }
+
+    for (id, objects) in &references {
+        let in_use = in_use.contains(id);
+        report.indexes.push(AssetIndexUsage {
+            id: id.clone(),
+            in_use,
+            objects: objects.len(),
+            reclaimable_bytes: objects
+                .iter()
+                .filter_map(|x| unreferenced.get(x))
+                .sum(),
+        });
+        if in_use {
+            continue;
+        }
+
+        let index_path =
+            game_dirs.assets_index_dir().join(format!("{id}.json"));
+        report.reclaimed_bytes += file_size(&index_path).await;
+        if !dry_run {
+            io::remove_file(&index_path).await?;
+        }
+        report.removed.push(index_path);
+
+        // Virtual assets are links to the objects, so removing them frees
+        // little beyond the objects themselves
+        let virtual_dir = game_dirs.virtual_assets_dir(id);
+        if virtual_dir.exists() {
+            if !dry_run {
+                io::remove_dir_all(&virtual_dir).await?;
+            }
+            report.removed.push(virtual_dir);
+        }
+        tracing::debug!("Pruned unused asset index {id}");
+    }
+    report.indexes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(report)
+}
+
+/// Removes the temporary files of game sessions whose process is gone, such
+/// as the natives extracted by a game that crashed along with the launcher
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn sweep_sessions() -> crate::Result<SessionCleanupReport> {
+    let state = State::get().await?;
+    let sessions_dir = state.directories.sessions_dir();
+
+    let mut report = SessionCleanupReport::default();
+    if !sessions_dir.exists() {
+        return Ok(report);
+    }
+
+    let mut read_dir = io::read_dir(&sessions_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| io::IOError::with_path(e, &sessions_dir))?
+    {
+        let session_dir = entry.path();
+        let uuid = session_dir
+            .file_name()
+            .and_then(|x| uuid::Uuid::parse_str(&x.to_string_lossy()).ok());
+
+        // Sessions of this run are cleaned up when their game exits
+        if let Some(uuid) = uuid {
+            if state.children.read().await.get(&uuid).is_some() {
+                continue;
+            }
+        }
+
+        let record: Option<session::SessionRecord> = read_json(
+            &session_dir.join(session::SESSION_FILE),
+            &state.io_semaphore,
+        )
+        .await
+        .ok();
+        if record.as_ref().map(|x| x.is_running()).unwrap_or(false) {
+            continue;
+        }
+        // The game exited while the launcher was closed
+        if let Some(record) = &record {
+            session::restore_game_options(record).await;
+        }
+
+        let mut files = Vec::new();
+        collect_files(&session_dir, &mut files).await?;
+        for file in files {
+            report.reclaimed_bytes += tokio::fs::metadata(&file)
+                .await
+                .map(|x| x.len())
+                .unwrap_or(0);
+        }
+
+        if session_dir.is_dir() {
+            io::remove_dir_all(&session_dir).await?;
+        } else {
+            io::remove_file(&session_dir).await?;
+        }
+        report.sessions += 1;
+    }
+
+    Ok(report)
+}
+
+/// Sums up the space taken by shared libraries, assets, Java installs,
+/// caches and profiles. What profiles no longer use is freed by `gc`,
+/// `prune_assets` and `sweep_sessions`
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn get_storage_report() -> crate::Result<StorageReport> {
+    let state = State::get().await?;
+    let dirs = &state.directories;
+
+    let mut report = StorageReport {
+        libraries: dir_size(&dirs.libraries_dir().await).await?,
+        assets: dir_size(&dirs.assets_dir().await).await?,
+        versions: dir_size(&dirs.versions_dir().await).await?,
+        natives: dir_size(&dirs.natives_dir().await).await?,
+        java: dir_size(&dirs.java_versions_dir().await).await?,
+        caches: dir_size(&dirs.caches_dir()).await?,
+        sessions: dir_size(&dirs.sessions_dir()).await?,
+        profiles: dir_size(&dirs.profiles_dir().await).await?,
+        total: 0,
+    };
+    report.total = report.libraries
+        + report.assets
+        + report.versions
+        + report.natives
+        + report.java
+        + report.caches
+        + report.profiles;
+
+    Ok(report)
+}
+
+/// Finds byte-identical files across a profile's mods, resource packs and
+/// shader packs. Nothing is reported on platforms that can't tell which
+/// files are already hard linked
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn find_duplicates(
+    profile_path: &ProfilePathId,
+) -> crate::Result<DuplicateReport> {
+    let full_path = profile_path.get_full_path().await?;
+
+    let mut files = Vec::new();
+    for folder in DEDUP_FOLDERS {
+        collect_files(&full_path.join(folder), &mut files).await?;
+    }
+
+    // Only files sharing a size can be identical, so avoid hashing the rest
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut linked = HashSet::new();
+    for file in files {
+        let metadata = tokio::fs::metadata(&file)
+            .await
+            .map_err(|e| io::IOError::with_path(e, &file))?;
+        match file_id(&file, &metadata).await? {
+            Some(id) => {
+                if !linked.insert(id) {
+                    continue;
+                }
+            }
+            // Files already linked to each other would be reported again
+            // on every run
+            None => continue,
+        }
+        if metadata.len() > 0 {
+            by_size.entry(metadata.len()).or_default().push(file);
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut duplicated_bytes = 0;
+    for (size, paths) in by_size.into_iter().filter(|(_, x)| x.len() > 1) {
+        let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            let bytes = io::read(&path).await?;
+            let hash = sha1_async(bytes.into()).await?;
+            by_hash.entry(hash).or_default().push(path);
+        }
+
+        for (sha1, paths) in by_hash.into_iter().filter(|(_, x)| x.len() > 1)
+        {
+            duplicated_bytes += size * (paths.len() as u64 - 1);
+            groups.push(DuplicateGroup {
+                sha1,
+                size,
+                paths: paths
+                    .into_iter()
+                    .map(|x| {
+                        x.strip_prefix(&full_path)
+                            .map(Path::to_path_buf)
+                            .unwrap_or(x)
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(DuplicateReport {
+        groups,
+        duplicated_bytes,
+        ..Default::default()
+    })
+}
+
+/// Replaces duplicated files in a profile with hard links to a single copy,
+/// reporting how much space was reclaimed
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn merge_duplicates(
+    profile_path: &ProfilePathId,
+) -> crate::Result<DuplicateReport> {
+    let full_path = profile_path.get_full_path().await?;
+    let mut report = find_duplicates(profile_path).await?;
+
+    for group in &report.groups {
+        let mut paths = group.paths.iter().map(|x| full_path.join(x));
+        let Some(original) = paths.next() else { continue };
+
+        for path in paths {
+            // Link next to the duplicate first so a failed link never loses
+            // the file, then swap it into place
+            let temp_path = path.with_extension("theseus-link");
+            if let Err(err) = io::hard_link(&original, &temp_path).await {
+                tracing::warn!(
+                    "Could not hard link {}: {err}",
+                    path.display()
+                );
+                continue;
+            }
+            io::rename(&temp_path, &path).await?;
+            report.reclaimed_bytes += group.size;
+        }
+    }
+
+    Ok(report)
+}
+
+// Identifies the data a file points at, so existing hard links are not
+// counted as duplicates
+#[cfg(unix)]
+async fn file_id(
+    _path: &Path,
+    metadata: &std::fs::Metadata,
+) -> crate::Result<Option<(u64, u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(Some((metadata.dev(), metadata.ino())))
+}
+
+// Windows only tells the volume and file index of an open file
+#[cfg(windows)]
+async fn file_id(
+    path: &Path,
+    _metadata: &std::fs::Metadata,
+) -> crate::Result<Option<(u64, u64)>> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    let path = path.to_path_buf();
+    let id = tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path)
+            .map_err(|e| io::IOError::with_path(e, &path))?;
+        let mut info: BY_HANDLE_FILE_INFORMATION =
+            unsafe { std::mem::zeroed() };
+        let res = unsafe {
+            GetFileInformationByHandle(
+                file.as_raw_handle() as HANDLE,
+                &mut info,
+            )
+        };
+        if res == 0 {
+            return Err(io::IOError::with_path(
+                std::io::Error::last_os_error(),
+                &path,
+            )
+            .into());
+        }
+        Ok::<_, crate::Error>((
+            info.dwVolumeSerialNumber as u64,
+            (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64,
+        ))
+    })
+    .await??;
+    Ok(Some(id))
+}
+
+// Platforms without a file id can't tell hard links apart, so nothing is
+// merged there
+#[cfg(not(any(unix, windows)))]
+async fn file_id(
+    _path: &Path,
+    _metadata: &std::fs::Metadata,
+) -> crate::Result<Option<(u64, u64)>> {
+    Ok(None)
+}
+
+// Refuses to remove files while a profile is being installed, as the files
+// it just downloaded aren't referenced until it is done
+async fn ensure_no_install(state: &State) -> crate::Result<()> {
+    let profiles = state.profiles.read().await;
+    if let Some(profile) = profiles.0.values().find(|x| {
+        matches!(
+            x.install_stage,
+            ProfileInstallStage::Installing
+                | ProfileInstallStage::PackInstalling
+        )
+    }) {
+        return Err(crate::ErrorKind::ProfileInstalling {
+            profile_path: profile.profile_id().to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+// A game version a profile uses, with the LWJGL version it overrides the
+// bundled one with
+struct ProfileVersion {
+    info: GameVersionInfo,
+    lwjgl: Option<String>,
+}
+
+// Resolves the version info of every distinct game version used by a
+// profile, once for each LWJGL override profiles apply to it, only counting
+// installed profiles unless `all_profiles` is set
+async fn profile_version_infos(
+    state: &State,
+    all_profiles: bool,
+) -> crate::Result<Vec<ProfileVersion>> {
+    let wanted = state
+        .profiles
+        .read()
+        .await
+        .0
+        .values()
+        .filter(|x| {
+            all_profiles || x.install_stage == ProfileInstallStage::Installed
+        })
+        .map(|x| {
+            let mut lwjgl = x
+                .lwjgl_arch_versions
+                .values()
+                .chain(x.lwjgl_version.as_ref())
+                .cloned()
+                .map(Some)
+                .collect::<Vec<_>>();
+            // Architectures without an override use the bundled version
+            lwjgl.push(None);
+            (
+                x.metadata.game_version.clone(),
+                x.metadata.loader_version.clone(),
+                lwjgl,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let metadata = state.metadata.read().await;
+    let mut seen = HashSet::new();
+    let mut infos = Vec::new();
+    for (game_version, loader_version, lwjgl_versions) in wanted {
+        let version = match metadata
+            .minecraft
+            .versions
+            .iter()
+            .find(|it| it.id == game_version)
+        {
+            Some(version) => version,
+            None => {
+                tracing::warn!("Skipping unknown game version {game_version}");
+                continue;
+            }
+        };
+
+        let version_id = loader_version.as_ref().map_or(
+            version.id.clone(),
+            |it| format!("{}-{}", version.id, it.id),
+        );
+        let lwjgl_versions = lwjgl_versions
+            .into_iter()
+            .filter(|x| seen.insert((version_id.clone(), x.clone())))
+            .collect::<Vec<_>>();
+        if lwjgl_versions.is_empty() {
+            continue;
+        }
+
+        let info = download::download_version_info(
+            state,
+            version,
+            loader_version.as_ref(),
+            None,
+            None,
+        )
+        .await?;
+        for lwjgl in lwjgl_versions {
+            let mut info = info.clone();
+            let lwjgl = match lwjgl {
+                Some(lwjgl)
+                    if lwjgl::apply_override(state, &mut info, &lwjgl)
+                        .await? =>
+                {
+                    Some(lwjgl)
+                }
+                // Versions on LWJGL 2 aren't overridden
+                Some(_) => continue,
+                None => None,
+            };
+            infos.push(ProfileVersion { info, lwjgl });
+        }
+    }
+
+    Ok(infos)
+}
+
+// Lists the client jar, libraries and asset objects a version depends on
+async fn version_entries(
+    state: &State,
+    version: &GameVersionInfo,
+) -> crate::Result<Vec<CacheEntry>> {
+    let mut entries = Vec::new();
+
+    if let Some(client) =
+        version.downloads.get(&d::minecraft::DownloadType::Client)
+    {
+        entries.push(CacheEntry {
+            path: state
+                .directories
+                .version_dir(&version.id)
+                .await
+                .join(format!("{}.jar", version.id)),
+            url: client.url.clone(),
+            sha1: Some(client.sha1.clone()),
+            required: true,
+        });
+    }
+
+    let libraries_dir = state.directories.libraries_dir().await;
+    for library in &version.libraries {
+        let artifact_path = d::get_path_from_artifact(&library.name)?;
+        let path = libraries_dir.join(&artifact_path);
+        let required = library.rules.as_ref().map_or(true, |rules| {
+            rules.iter().any(|x| {
+                crate::launcher::parse_rule(
+                    x,
+                    std::env::consts::ARCH,
+                    &crate::launcher::FeatureContext::default(),
+                )
+            })
+        });
+
+        let entry = match library.downloads {
+            Some(d::minecraft::LibraryDownloads {
+                artifact: Some(ref artifact),
+                ..
+            }) => CacheEntry {
+                path,
+                url: artifact.url.clone(),
+                sha1: Some(artifact.sha1.clone()),
+                required,
+            },
+            _ => CacheEntry {
+                path,
+                url: [
+                    library
+                        .url
+                        .as_deref()
+                        .unwrap_or("https://libraries.minecraft.net/"),
+                    &artifact_path,
+                ]
+                .concat(),
+                sha1: None,
+                required,
+            },
+        };
+        entries.push(entry);
+    }
+
+    let index = download::download_assets_index(
+        state,
+        &state.directories.shared_game_dirs().await,
+        version,
+        None,
+    )
+    .await?;
+    for asset in index.objects.values() {
+        entries.push(CacheEntry {
+            path: state.directories.object_dir(&asset.hash).await,
+            url: format!(
+                "https://resources.download.minecraft.net/{}/{}",
+                &asset.hash[..2],
+                asset.hash
+            ),
+            sha1: Some(asset.hash.clone()),
+            required: true,
+        });
+    }
+
+    Ok(entries)
+}
+
+// Maps every asset index on disk to the hashes of the objects it references
+async fn asset_references(
+    indexes_dir: &Path,
+) -> crate::Result<HashMap<String, HashSet<String>>> {
+    let mut references = HashMap::new();
+    if !indexes_dir.exists() {
+        return Ok(references);
+    }
+
+    let mut read_dir = io::read_dir(indexes_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| io::IOError::with_path(e, indexes_dir))?
+    {
+        let path = entry.path();
+        let Some(id) = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.strip_suffix(".json"))
+        else {
+            continue;
+        };
+
+        let index = io::read(&path).await?;
+        match serde_json::from_slice::<AssetsIndex>(&index) {
+            Ok(index) => {
+                references.insert(
+                    id.to_string(),
+                    index.objects.into_values().map(|x| x.hash).collect(),
+                );
+            }
+            Err(err) => {
+                tracing::warn!("Skipping unreadable asset index {id}: {err}")
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+async fn file_size(path: &Path) -> u64 {
+    tokio::fs::metadata(path)
+        .await
+        .map(|x| x.len())
+        .unwrap_or(0)
+}
+
+// Returns whether the file exists and, when a hash is known, matches it
+async fn verify_file(path: &Path, sha1: Option<&str>) -> crate::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    if let Some(sha1) = sha1 {
+        let bytes = io::read(path).await?;
+        Ok(sha1_async(bytes.into()).await? == sha1)
+    } else {
+        Ok(true)
+    }
+}
+
+async fn dir_size(dir: &Path) -> crate::Result<u64> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files).await?;
+
+    let mut size = 0;
+    for file in files {
+        size += file_size(&file).await;
+    }
+    Ok(size)
+}
+
+#[async_recursion]
+async fn collect_files(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> crate::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let mut read_dir = io::read_dir(dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| io::IOError::with_path(e, dir))?
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files).await?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,9 @@
+//! API for the display server of the desktop session
+pub use crate::util::platform::SessionType;
+
+/// Gets the display server of the desktop session, so the display server of
+/// profiles is only offered where there is a choice
+#[tracing::instrument]
+pub async fn get_session_type() -> crate::Result<SessionType> {
+    Ok(crate::util::platform::session_type())
+}
@@ -0,0 +1,28 @@
+//! API for injecting failures into downloads and processors, for testing
+use crate::util::fault::{self, FaultRule, InjectedFault};
+use uuid::Uuid;
+
+/// Starts injecting a failure into the downloads or processors the rule
+/// matches, returning the id to remove it by. Rules can also be given at
+/// startup as a JSON array in the `THESEUS_FAULTS` environment variable
+#[tracing::instrument]
+pub fn inject_fault(rule: FaultRule) -> Uuid {
+    fault::inject(rule)
+}
+
+/// Stops injecting a failure
+#[tracing::instrument]
+pub fn remove_fault(id: Uuid) {
+    fault::remove(id)
+}
+
+/// Stops injecting every failure
+#[tracing::instrument]
+pub fn clear_faults() {
+    fault::clear()
+}
+
+/// Lists the failures being injected, with how many times each was so far
+pub fn get_faults() -> Vec<InjectedFault> {
+    fault::list()
+}
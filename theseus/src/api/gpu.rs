@@ -0,0 +1,20 @@
+//! API for the graphics cards of the system
+use crate::state::ProfilePathId;
+pub use crate::util::platform::Gpu;
+
+/// Lists the graphics cards of the system, so a GPU preference is only
+/// offered where there is a choice
+#[tracing::instrument]
+pub async fn get_gpus() -> crate::Result<Vec<Gpu>> {
+    Ok(tokio::task::spawn_blocking(crate::util::platform::gpus).await?)
+}
+
+/// Removes the GPU preference registered for the Java executable of a
+/// profile, for when the preference is turned back to the default. Launching
+/// registers it again otherwise
+#[tracing::instrument]
+pub async fn release_preference(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    crate::launcher::gpu::release_preference(profile_path).await
+}
@@ -0,0 +1,12 @@
+//! Theseus background jobs
+use crate::state::{JobInfo, Scheduler};
+
+/// Lists the background jobs, with their next run in local time
+pub async fn get_jobs() -> crate::Result<Vec<JobInfo>> {
+    Ok(Scheduler::jobs())
+}
+
+/// Runs a background job now, rather than at its next scheduled time
+pub async fn run_job(name: &str) -> crate::Result<()> {
+    Scheduler::run_now(name)
+}
@@ -9,7 +9,8 @@ use crate::util::fetch::{fetch_advanced, fetch_json};
 use crate::util::io;
 use crate::util::jre::extract_java_majorminor_version;
 use crate::{
-    state::JavaGlobals,
+    prelude::ProfilePathId,
+    state::{JavaGlobals, MemorySettings, ModLoader, Profile, ProjectType},
     util::jre::{self, JavaVersion},
     LoadingBarType, State,
 };
@@ -194,3 +195,78 @@ pub async fn get_max_memory() -> crate::Result<u64> {
         })?
         .total)
 }
+
+/// Suggests heap sizes for a profile, based on the memory of the system and
+/// the mods the profile loads
+#[tracing::instrument]
+pub async fn recommend_memory(
+    profile_path: &ProfilePathId,
+) -> crate::Result<MemorySettings> {
+    let profile = crate::profile::get(profile_path, Some(true))
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?;
+
+    Ok(get_recommended_memory(&profile, get_max_memory().await?))
+}
+
+// Heap sizes in MB, each enabled mod adds to a base depending on the loader.
+// A measured heap can raise that, but never lower it. The heap is capped to
+// leave the rest of the system enough memory
+pub(crate) fn get_recommended_memory(
+    profile: &Profile,
+    system_memory_kib: u64,
+) -> MemorySettings {
+    const MB_PER_MOD: u64 = 16;
+    const MB_FOR_SHADERS: u64 = 512;
+    const MAX_HEAP: u64 = 16384;
+    const MB_STARVED_GROWTH: u64 = 1024;
+
+    let mut mods = 0;
+    let mut shaders = false;
+    for (path, project) in &profile.projects {
+        if project.disabled {
+            continue;
+        }
+        match ProjectType::get_from_parent_folder(path.0.clone()) {
+            Some(ProjectType::Mod) => mods += 1,
+            Some(ProjectType::ShaderPack) => shaders = true,
+            _ => {}
+        }
+    }
+
+    let mut maximum = match profile.metadata.loader {
+        ModLoader::Vanilla => 2048,
+        _ => 3072,
+    } + mods * MB_PER_MOD;
+    if shaders {
+        maximum += MB_FOR_SHADERS;
+    }
+    if let Some(usage) = &profile.heap_usage {
+        let (live, heap) = (usage.live_mb as u64, usage.heap_mb as u64);
+        let measured = if live * 10 >= heap * 9 {
+            // The heap was nearly full even after collecting, so it was
+            // too small to tell how much more the game wanted
+            heap + MB_STARVED_GROWTH
+        } else {
+            live * 3 / 2
+        };
+        maximum = maximum.max(measured);
+    }
+
+    let system_memory = system_memory_kib / 1024;
+    let cap = (system_memory / 2)
+        .max(system_memory.saturating_sub(4096))
+        .min(MAX_HEAP);
+    // Rounded up to a multiple of 512 MB, but never below 1 GB
+    let maximum = (((maximum + 511) / 512) * 512).min(cap).max(1024);
+    let minimum = (maximum / 2 / 256) * 256;
+
+    MemorySettings {
+        maximum: maximum as u32,
+        minimum: Some(minimum.max(512) as u32),
+        auto: false,
+        sample_heap: false,
+    }
+}
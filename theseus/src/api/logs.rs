@@ -1,14 +1,89 @@
 use crate::{
+    config::MCLOGS_API_URL,
+    state::{LogRetention, Schedule, Scheduler},
+    util::censor::Censor,
     util::io::{self, IOError},
     {state::ProfilePathId, State},
 };
+use chrono::{
+    DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+
+const STDOUT_LOG: &str = "stdout.log";
+const COMPRESSED_STDOUT_LOG: &str = "stdout.log.gz";
+// Where the game writes its own logs, in the profile folder
+const GAME_LOGS_FOLDER: &str = "logs";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Logs {
     pub datetime_string: String,
+    /// UUID of the process that wrote the log, unset for logs from before
+    /// folders were named after it
+    pub session_uuid: Option<Uuid>,
     pub output: Option<String>,
 }
+
+/// A launch of a profile that left logs behind
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogSession {
+    pub session_uuid: Uuid,
+    pub datetime_string: String,
+}
+
+/// A crash report the game wrote for a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashReport {
+    pub file_name: String,
+    pub output: String,
+}
+
+/// A log the game wrote itself, rather than the launcher
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameLogFile {
+    pub file_name: String,
+    /// The game gzips its logs once they are rotated
+    pub compressed: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Which logs a line of the merged logs was read from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    Launcher,
+    Game,
+}
+
+/// A line of the merged logs, along with the lines after it that have no
+/// time of their own, such as stack traces
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogLine {
+    /// Local time. Most formats only record the time of day, so the date is
+    /// worked out from the log's name or when it was written
+    pub timestamp: NaiveDateTime,
+    pub source: LogSource,
+    /// Launcher log folder, or file name of the game's log
+    pub origin: String,
+    pub thread: Option<String>,
+    pub level: Option<String>,
+    pub message: String,
+}
+
+/// Result of applying the log retention policy to every profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogCleanupReport {
+    pub compressed: usize,
+    pub deleted: usize,
+    pub reclaimed_bytes: u64,
+}
+
 impl Logs {
     async fn build(
         profile_subpath: &ProfilePathId,
@@ -24,6 +99,7 @@ impl Logs {
                         .await?,
                 )
             },
+            session_uuid: parse_session_uuid(&datetime_string),
             datetime_string,
         })
     }
@@ -92,10 +168,94 @@ pub async fn get_logs_by_datetime(
         output: Some(
             get_output_by_datetime(&profile_path, &datetime_string).await?,
         ),
+        session_uuid: parse_session_uuid(&datetime_string),
         datetime_string,
     })
 }
 
+/// Lists the launches of a profile that have logs, oldest first
+#[tracing::instrument]
+pub async fn get_sessions(
+    profile_path: ProfilePathId,
+) -> crate::Result<Vec<LogSession>> {
+    let profile_path = get_profile_id(&profile_path).await?;
+    let state = State::get().await?;
+    let logs_folder = state.directories.profile_logs_dir(&profile_path).await?;
+
+    let mut sessions = get_log_dirs(&logs_folder)
+        .await?
+        .into_iter()
+        .filter_map(|path| {
+            let datetime_string =
+                path.file_name()?.to_string_lossy().to_string();
+            Some(LogSession {
+                session_uuid: parse_session_uuid(&datetime_string)?,
+                datetime_string,
+            })
+        })
+        .collect::<Vec<_>>();
+    sessions.sort_by(|a, b| a.datetime_string.cmp(&b.datetime_string));
+
+    Ok(sessions)
+}
+
+/// Gets the logs written by a process, by its UUID
+#[tracing::instrument]
+pub async fn get_logs_by_session(
+    profile_path: ProfilePathId,
+    session_uuid: Uuid,
+) -> crate::Result<Logs> {
+    let datetime_string =
+        get_session_dir_name(&profile_path, session_uuid).await?;
+    get_logs_by_datetime(profile_path, datetime_string).await
+}
+
+/// Deletes the logs written by a process, by its UUID
+#[tracing::instrument]
+pub async fn delete_logs_by_session(
+    profile_path: ProfilePathId,
+    session_uuid: Uuid,
+) -> crate::Result<()> {
+    let datetime_string =
+        get_session_dir_name(&profile_path, session_uuid).await?;
+    delete_logs_by_datetime(profile_path, &datetime_string).await
+}
+
+// Log folders are named "<datetime>_<uuid>", see Children::log_dir_name
+fn parse_session_uuid(dir_name: &str) -> Option<Uuid> {
+    let uuid = dir_name.splitn(3, '_').nth(2)?;
+    Uuid::parse_str(uuid).ok()
+}
+
+async fn get_session_dir_name(
+    profile_path: &ProfilePathId,
+    session_uuid: Uuid,
+) -> crate::Result<String> {
+    get_sessions(profile_path.clone())
+        .await?
+        .into_iter()
+        .find(|x| x.session_uuid == session_uuid)
+        .map(|x| x.datetime_string)
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "No logs found for session {session_uuid}"
+            ))
+            .as_error()
+        })
+}
+
+async fn get_profile_id(
+    profile_path: &ProfilePathId,
+) -> crate::Result<ProfilePathId> {
+    crate::profile::get(profile_path, None)
+        .await?
+        .map(|x| x.profile_id())
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+                .as_error()
+        })
+}
+
 #[tracing::instrument]
 pub async fn get_output_by_datetime(
     profile_subpath: &ProfilePathId,
@@ -104,8 +264,640 @@ pub async fn get_output_by_datetime(
     let state = State::get().await?;
     let logs_folder =
         state.directories.profile_logs_dir(profile_subpath).await?;
-    let path = logs_folder.join(datetime_string).join("stdout.log");
-    Ok(io::read_to_string(&path).await?)
+    let log_dir = logs_folder.join(datetime_string);
+
+    // Older logs are only kept compressed
+    let path = log_dir.join(STDOUT_LOG);
+    let compressed_path = log_dir.join(COMPRESSED_STDOUT_LOG);
+    let output = if !path.exists() && compressed_path.exists() {
+        read_log_file(&compressed_path).await?
+    } else {
+        io::read_to_string(&path).await?
+    };
+
+    // Logs are censored as they are written, but those written before a
+    // rule was added still need it
+    Ok(Censor::from_settings().await?.censor(&output))
+}
+
+/// Gets the crash reports the game wrote for a profile, newest first. They
+/// are censored like game logs, so they are safe to share
+#[tracing::instrument]
+pub async fn get_crash_reports(
+    profile_path: ProfilePathId,
+) -> crate::Result<Vec<CrashReport>> {
+    let profile_path = get_profile_id(&profile_path).await?;
+    let reports_dir = profile_path.get_full_path().await?.join("crash-reports");
+    if !reports_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let censor = Censor::from_settings().await?;
+    let mut reports = Vec::new();
+    let mut read_dir = io::read_dir(&reports_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &reports_dir))?
+    {
+        let path = entry.path();
+        if path.extension().map_or(true, |x| x != "txt") {
+            continue;
+        }
+        reports.push(CrashReport {
+            file_name: entry.file_name().to_string_lossy().to_string(),
+            output: censor.censor(&io::read_to_string(&path).await?),
+        });
+    }
+    // Reports are named after the time they were written
+    reports.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+
+    Ok(reports)
+}
+
+/// Lists the logs the game wrote itself in the logs folder of a profile,
+/// newest first. These include launches from other launchers
+#[tracing::instrument]
+pub async fn get_game_log_files(
+    profile_path: ProfilePathId,
+) -> crate::Result<Vec<GameLogFile>> {
+    let profile_path = get_profile_id(&profile_path).await?;
+    let logs_dir = profile_path.get_full_path().await?.join(GAME_LOGS_FOLDER);
+    let mut files = Vec::new();
+    if !logs_dir.exists() {
+        return Ok(files);
+    }
+
+    let mut read_dir = io::read_dir(&logs_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &logs_dir))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let compressed = file_name.ends_with(".log.gz");
+        if !compressed && !file_name.ends_with(".log") {
+            continue;
+        }
+        let metadata = entry
+            .metadata()
+            .await
+            .map_err(|e| IOError::with_path(e, entry.path()))?;
+        if !metadata.is_file() {
+            continue;
+        }
+
+        files.push(GameLogFile {
+            file_name,
+            compressed,
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        });
+    }
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    Ok(files)
+}
+
+/// Gets a log the game wrote, by its name in the logs folder of the profile.
+/// Rotated logs are read decompressed
+#[tracing::instrument]
+pub async fn get_game_log(
+    profile_path: ProfilePathId,
+    file_name: &str,
+) -> crate::Result<String> {
+    let profile_path = get_profile_id(&profile_path).await?;
+
+    let mut components = Path::new(file_name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{file_name} is not a log of the game"
+        ))
+        .into());
+    }
+    let path = profile_path
+        .get_full_path()
+        .await?
+        .join(GAME_LOGS_FOLDER)
+        .join(file_name);
+
+    let output = read_log_file(&path).await?;
+    Ok(Censor::from_settings().await?.censor(&output))
+}
+
+/// Gets the launcher's logs of a profile and the logs the game wrote itself
+/// as one timeline, oldest first. Lines the game logged to both are only
+/// kept from the launcher's logs. `since` leaves out earlier lines
+#[tracing::instrument]
+pub async fn get_merged_logs(
+    profile_path: ProfilePathId,
+    since: Option<NaiveDateTime>,
+) -> crate::Result<Vec<LogLine>> {
+    let profile_path = get_profile_id(&profile_path).await?;
+    let state = State::get().await?;
+    let censor = Censor::from_settings().await?;
+    // Logs last written to before `since` have nothing to show
+    let is_stale = |modified: Option<NaiveDateTime>| {
+        since.zip(modified).map_or(false, |(since, x)| x < since)
+    };
+
+    let mut lines = Vec::new();
+    let logs_folder = state.directories.profile_logs_dir(&profile_path).await?;
+    for log_dir in get_log_dirs(&logs_folder).await? {
+        let Some(dir_name) = log_dir.file_name() else {
+            continue;
+        };
+        let dir_name = dir_name.to_string_lossy().to_string();
+        let path = [STDOUT_LOG, COMPRESSED_STDOUT_LOG]
+            .into_iter()
+            .map(|x| log_dir.join(x))
+            .find(|x| x.exists());
+        let Some(path) = path else {
+            continue;
+        };
+
+        let modified = get_modified(&path).await;
+        if is_stale(modified) {
+            continue;
+        }
+        let anchor = match parse_session_start(&dir_name) {
+            Some(start) => LogAnchor::Start(start),
+            None => LogAnchor::End(modified.unwrap_or_else(now)),
+        };
+        let output = censor.censor(&read_log_file(&path).await?);
+        lines.extend(parse_log(
+            &output,
+            anchor,
+            LogSource::Launcher,
+            &dir_name,
+        ));
+    }
+
+    let logs_dir = profile_path.get_full_path().await?.join(GAME_LOGS_FOLDER);
+    let mut game_lines = Vec::new();
+    for file in get_game_log_files(profile_path.clone()).await? {
+        let path = logs_dir.join(&file.file_name);
+        let modified = file
+            .modified
+            .map(|x| DateTime::<Local>::from(x).naive_local());
+        if is_stale(modified) {
+            continue;
+        }
+        // Rotated logs are named after the day they were last written to
+        let anchor = match file
+            .file_name
+            .get(..10)
+            .and_then(|x| NaiveDate::parse_from_str(x, "%Y-%m-%d").ok())
+        {
+            Some(date) => LogAnchor::EndDate(date),
+            None => LogAnchor::End(modified.unwrap_or_else(now)),
+        };
+        let output = censor.censor(&read_log_file(&path).await?);
+        game_lines.extend(parse_log(
+            &output,
+            anchor,
+            LogSource::Game,
+            &file.file_name,
+        ));
+    }
+
+    let launched = lines
+        .iter()
+        .map(|x| (x.timestamp, x.message.as_str()))
+        .collect::<HashSet<_>>();
+    game_lines
+        .retain(|x| !launched.contains(&(x.timestamp, x.message.as_str())));
+    lines.extend(game_lines);
+
+    if let Some(since) = since {
+        lines.retain(|x| x.timestamp >= since);
+    }
+    lines.sort_by_key(|x| x.timestamp);
+
+    Ok(lines)
+}
+
+// What the times of day in a log are placed relative to
+#[derive(Clone, Copy)]
+enum LogAnchor {
+    // When the log was started
+    Start(NaiveDateTime),
+    // When the log was last written to
+    End(NaiveDateTime),
+    // The day the log was last written to
+    EndDate(NaiveDate),
+}
+
+enum LineTime {
+    TimeOfDay(NaiveTime),
+    // Forge logs record the date as well
+    Full(NaiveDateTime),
+}
+
+struct ParsedLine<'a> {
+    time: LineTime,
+    thread: Option<&'a str>,
+    level: Option<&'a str>,
+    message: &'a str,
+}
+
+fn parse_log(
+    output: &str,
+    anchor: LogAnchor,
+    source: LogSource,
+    origin: &str,
+) -> Vec<LogLine> {
+    // Times of day are first placed on consecutive days counted from the
+    // epoch, moving to the next day when the time goes back, then the whole
+    // log is moved to its date. Whether each line needs moving is kept
+    // alongside it
+    let epoch = NaiveDateTime::default();
+    let midnight = NaiveTime::default();
+    let mut lines: Vec<(bool, LogLine)> = Vec::new();
+    let mut preamble: Vec<&str> = Vec::new();
+    let mut day = 0;
+    let mut last_time: Option<NaiveTime> = None;
+
+    for line in output.lines() {
+        let Some(parsed) = parse_log_line(line) else {
+            match lines.last_mut() {
+                Some((_, last)) => {
+                    last.message.push('\n');
+                    last.message.push_str(line);
+                }
+                None => preamble.push(line),
+            }
+            continue;
+        };
+
+        let (relative, timestamp) = match parsed.time {
+            LineTime::Full(timestamp) => (false, timestamp),
+            LineTime::TimeOfDay(time) => {
+                // Threads can log slightly out of order, so only a large
+                // jump back is taken as midnight
+                if last_time.map_or(false, |x| x - time > Duration::hours(12)) {
+                    day += 1;
+                }
+                last_time = Some(time);
+                (true, epoch + Duration::days(day) + (time - midnight))
+            }
+        };
+        let mut message = parsed.message.to_string();
+        if lines.is_empty() && !preamble.is_empty() {
+            message = format!("{}\n{message}", preamble.join("\n"));
+        }
+        lines.push((
+            relative,
+            LogLine {
+                timestamp,
+                source,
+                origin: origin.to_string(),
+                thread: parsed.thread.map(str::to_string),
+                level: parsed.level.map(str::to_string),
+                message,
+            },
+        ));
+    }
+
+    let start_date = match anchor {
+        LogAnchor::Start(start) => start.date(),
+        LogAnchor::End(end) => {
+            let mut date = end.date() - Duration::days(day);
+            // Written to until just before midnight, then left until after
+            if last_time.map_or(false, |x| x > end.time()) {
+                date -= Duration::days(1);
+            }
+            date
+        }
+        LogAnchor::EndDate(date) => date - Duration::days(day),
+    };
+    let offset = start_date.and_time(midnight) - epoch;
+
+    if lines.is_empty() && !preamble.is_empty() {
+        let timestamp = match anchor {
+            LogAnchor::Start(x) | LogAnchor::End(x) => x,
+            LogAnchor::EndDate(date) => date.and_time(midnight),
+        };
+        return vec![LogLine {
+            timestamp,
+            source,
+            origin: origin.to_string(),
+            thread: None,
+            level: None,
+            message: preamble.join("\n"),
+        }];
+    }
+
+    lines
+        .into_iter()
+        .map(|(relative, mut line)| {
+            if relative {
+                line.timestamp += offset;
+            }
+            line
+        })
+        .collect()
+}
+
+// Reads the lines of the formats the game and loaders log in:
+// "[12:34:56] [Render thread/INFO]: message" by the game,
+// "[12:34:56] [main/INFO] (FabricLoader) message" by Fabric, and
+// "[12Jan2024 12:34:56.789] [main/INFO] [cpw.mods.modlauncher.Launcher/]: ..."
+// by Forge, which keeps the logger in the message
+fn parse_log_line(line: &str) -> Option<ParsedLine> {
+    let (time, rest) = line.strip_prefix('[')?.split_once(']')?;
+    let time = if let Some(time) = ["%H:%M:%S", "%H:%M:%S%.f"]
+        .into_iter()
+        .find_map(|x| NaiveTime::parse_from_str(time, x).ok())
+    {
+        LineTime::TimeOfDay(time)
+    } else {
+        LineTime::Full(
+            NaiveDateTime::parse_from_str(time, "%d%b%Y %H:%M:%S%.f").ok()?,
+        )
+    };
+
+    let rest = rest.trim_start();
+    let (thread, level, rest) =
+        match rest.strip_prefix('[').and_then(|x| x.split_once(']')) {
+            Some((source, rest)) => match source.rsplit_once('/') {
+                Some((thread, level)) => (Some(thread), Some(level), rest),
+                None => (Some(source), None, rest),
+            },
+            None => (None, None, rest),
+        };
+    let rest = rest.trim_start();
+
+    Some(ParsedLine {
+        time,
+        thread,
+        level,
+        message: rest.strip_prefix(':').unwrap_or(rest).trim_start(),
+    })
+}
+
+// Launcher log folders are named after the local time of the launch
+fn parse_session_start(dir_name: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(dir_name.get(..15)?, "%Y%m%d_%H%M%S").ok()
+}
+
+async fn get_modified(path: &Path) -> Option<NaiveDateTime> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+// Response of a paste service with the API of mclo.gs
+#[derive(Deserialize)]
+struct PasteResponse {
+    success: bool,
+    url: Option<String>,
+    error: Option<String>,
+}
+
+/// Shares a log or crash report of a profile, by its path in the profile
+/// folder, returning the URL it can be viewed at. It is censored, then
+/// uploaded to mclo.gs or the paste service set in the settings
+#[tracing::instrument]
+pub async fn upload(
+    profile_path: ProfilePathId,
+    path: &Path,
+) -> crate::Result<String> {
+    let profile_path = get_profile_id(&profile_path).await?;
+
+    // Nothing outside the profile can be shared through it
+    if path.is_absolute()
+        || path.components().any(|x| matches!(x, Component::ParentDir))
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{} is not a file of the profile",
+            path.display()
+        ))
+        .into());
+    }
+    let mut full_path = profile_path.get_full_path().await?.join(path);
+
+    // The game gzips its older logs, and so does the log retention policy,
+    // so a log may only be left compressed
+    let mut compressed = full_path.as_os_str().to_owned();
+    compressed.push(".gz");
+    if !full_path.exists() && Path::new(&compressed).exists() {
+        full_path = PathBuf::from(compressed);
+    }
+    let content = read_log_file(&full_path).await?;
+    let content = Censor::from_settings().await?.censor(&content);
+
+    let state = State::get().await?;
+    let url = state
+        .settings
+        .read()
+        .await
+        .paste_service_url
+        .clone()
+        .unwrap_or_else(|| MCLOGS_API_URL.to_string());
+    let response = crate::util::fetch::client()
+        .post(&url)
+        .form(&[("content", content)])
+        .send()
+        .await?
+        .json::<PasteResponse>()
+        .await?;
+
+    match response {
+        PasteResponse {
+            success: true,
+            url: Some(share_url),
+            ..
+        } => {
+            tracing::info!("Shared {} at {share_url}", path.display());
+            Ok(share_url)
+        }
+        PasteResponse { error, .. } => {
+            Err(crate::ErrorKind::InvalidResponseError(
+                url,
+                error.unwrap_or_else(|| "no URL returned".to_string()),
+            )
+            .into())
+        }
+    }
+}
+
+/// Compresses and deletes old logs of every profile, as configured by the
+/// log retention settings. Logs of running profiles are left alone
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn clean_logs() -> crate::Result<LogCleanupReport> {
+    let state = State::get().await?;
+    let retention = state.settings.read().await.log_retention;
+    let running = state.children.read().await.running_profile_paths().await?;
+    let profile_paths = state
+        .profiles
+        .read()
+        .await
+        .0
+        .keys()
+        .filter(|x| !running.contains(x))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut report = LogCleanupReport::default();
+    for profile_path in profile_paths {
+        let logs_folder =
+            state.directories.profile_logs_dir(&profile_path).await?;
+        if logs_folder.exists() {
+            clean_logs_folder(&logs_folder, retention, &mut report).await?;
+        }
+    }
+
+    Ok(report)
+}
+
+// Cleans logs on startup, and every night for launchers left open
+pub(crate) fn schedule_clean_logs() {
+    Scheduler::register(
+        "clean_logs",
+        Schedule::Daily {
+            // While computers left on are least likely to be in use
+            time: NaiveTime::from_hms_opt(4, 0, 0).unwrap_or_default(),
+        },
+        true,
+        || {
+            Box::pin(async {
+                match clean_logs().await {
+                    Ok(report)
+                        if report.compressed > 0 || report.deleted > 0 =>
+                    {
+                        tracing::info!(
+                            "Compressed {} and deleted {} logs, reclaiming {} bytes",
+                            report.compressed,
+                            report.deleted,
+                            report.reclaimed_bytes
+                        )
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::warn!("Unable to clean up logs: {err}")
+                    }
+                };
+            })
+        },
+    );
+}
+
+async fn clean_logs_folder(
+    logs_folder: &Path,
+    retention: LogRetention,
+    report: &mut LogCleanupReport,
+) -> crate::Result<()> {
+    let mut log_dirs = get_log_dirs(logs_folder).await?;
+    // Folders are named after their launch time, so this is oldest first
+    log_dirs.sort();
+
+    if let Some(days) = retention.compress_after_days {
+        let cutoff = std::time::SystemTime::now()
+            - std::time::Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+        for log_dir in &log_dirs {
+            let path = log_dir.join(STDOUT_LOG);
+            let modified =
+                tokio::fs::metadata(&path).await.and_then(|x| x.modified());
+            if matches!(modified, Ok(modified) if modified < cutoff) {
+                report.reclaimed_bytes += compress_log(&path).await?;
+                report.compressed += 1;
+            }
+        }
+    }
+
+    let mut sizes = Vec::new();
+    for log_dir in &log_dirs {
+        sizes.push(get_dir_size(log_dir).await?);
+    }
+    let mut total_size = sizes.iter().sum::<u64>();
+    let max_size = retention.max_size.map(|x| x * 1024 * 1024);
+    let mut count = log_dirs.len();
+
+    for (log_dir, size) in log_dirs.iter().zip(sizes) {
+        let over_count = retention.max_count.map_or(false, |x| count > x);
+        let over_size = max_size.map_or(false, |x| total_size > x);
+        if !over_count && !over_size {
+            break;
+        }
+
+        io::remove_dir_all(log_dir).await?;
+        count -= 1;
+        total_size -= size;
+        report.deleted += 1;
+        report.reclaimed_bytes += size;
+    }
+
+    Ok(())
+}
+
+async fn get_log_dirs(logs_folder: &Path) -> crate::Result<Vec<PathBuf>> {
+    let mut log_dirs = Vec::new();
+    if !logs_folder.exists() {
+        return Ok(log_dirs);
+    }
+
+    let mut read_dir = io::read_dir(logs_folder).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, logs_folder))?
+    {
+        if entry.path().is_dir() {
+            log_dirs.push(entry.path());
+        }
+    }
+    Ok(log_dirs)
+}
+
+// Replaces a log with a gzipped copy, returning the bytes saved
+async fn compress_log(path: &Path) -> crate::Result<u64> {
+    let bytes = io::read(path).await?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&bytes)
+        .map_err(|e| IOError::with_path(e, path))?;
+    let compressed =
+        encoder.finish().map_err(|e| IOError::with_path(e, path))?;
+
+    io::write(path.with_file_name(COMPRESSED_STDOUT_LOG), &compressed).await?;
+    io::remove_file(path).await?;
+
+    Ok((bytes.len() as u64).saturating_sub(compressed.len() as u64))
+}
+
+// Reads a log, gunzipping it if it was compressed
+async fn read_log_file(path: &Path) -> crate::Result<String> {
+    if path.extension().map_or(true, |x| x != "gz") {
+        return Ok(io::read_to_string(path).await?);
+    }
+
+    let bytes = io::read(path).await?;
+    let mut output = String::new();
+    GzDecoder::new(&*bytes)
+        .read_to_string(&mut output)
+        .map_err(|e| IOError::with_path(e, path))?;
+    Ok(output)
+}
+
+async fn get_dir_size(dir: &Path) -> crate::Result<u64> {
+    let mut size = 0;
+    let mut read_dir = io::read_dir(dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, dir))?
+    {
+        size += entry.metadata().await.map_or(0, |x| x.len());
+    }
+    Ok(size)
 }
 
 #[tracing::instrument]
@@ -26,6 +26,14 @@ pub async fn get_forge_versions() -> crate::Result<Manifest> {
     Ok(tags)
 }
 
+#[tracing::instrument]
+pub async fn get_neoforge_versions() -> crate::Result<Manifest> {
+    let state = State::get().await?;
+    let tags = state.metadata.read().await.neoforge.clone();
+
+    Ok(tags)
+}
+
 #[tracing::instrument]
 pub async fn get_quilt_versions() -> crate::Result<Manifest> {
     let state = State::get().await?;
@@ -33,3 +41,10 @@ pub async fn get_quilt_versions() -> crate::Result<Manifest> {
 
     Ok(tags)
 }
+
+/// Downloads a game version, such as an upcoming snapshot, before any
+/// profile uses it, so that creating one later is quick
+#[tracing::instrument]
+pub async fn prefetch_version(version_id: &str) -> crate::Result<()> {
+    crate::launcher::download::prefetch_version(version_id).await
+}
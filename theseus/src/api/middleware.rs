@@ -0,0 +1,32 @@
+//! API for extending launches with middlewares
+use crate::state::LaunchMiddleware;
+use crate::State;
+use std::sync::Arc;
+
+/// Lists the ids of every registered middleware, in the order they run
+#[tracing::instrument]
+pub async fn get_middlewares() -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+    let middlewares = state.launch_middlewares.read().await;
+    Ok(middlewares.ids())
+}
+
+/// Registers a middleware, replacing any with the same id. New middlewares
+/// run after the ones already registered
+pub async fn register_middleware(
+    middleware: Arc<dyn LaunchMiddleware>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let mut middlewares = state.launch_middlewares.write().await;
+    middlewares.register(middleware);
+    Ok(())
+}
+
+/// Removes a middleware, so later launches no longer run it
+#[tracing::instrument]
+pub async fn unregister_middleware(id: &str) -> crate::Result<()> {
+    let state = State::get().await?;
+    let mut middlewares = state.launch_middlewares.write().await;
+    middlewares.unregister(id);
+    Ok(())
+}
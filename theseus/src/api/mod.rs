@@ -1,24 +1,58 @@
 //! API for interacting with Theseus
 pub mod auth;
+pub mod cache;
+pub mod display;
+#[cfg(feature = "fault_injection")]
+pub mod faults;
+pub mod gpu;
 pub mod handler;
+pub mod jobs;
 pub mod jre;
 pub mod logs;
 pub mod metadata;
+#[cfg(feature = "launch_middleware")]
+pub mod middleware;
 pub mod mr_auth;
 pub mod pack;
 pub mod process;
 pub mod profile;
 pub mod safety;
+pub mod screenshots;
+pub mod servers;
 pub mod settings;
+pub mod snapshot;
+pub mod sources;
+pub mod status;
 pub mod tags;
+pub mod user;
+pub mod worlds;
 
 pub mod data {
     pub use crate::state::{
-        DirectoryInfo, Hooks, JavaSettings, LinkedData, MemorySettings,
+        BlockReason, BlockedContent, CloudSync, ContentFile, ContentPolicy,
+        ContentProject, ContentQuery, ContentSource, ContentVersion,
+        DevelopmentSettings, DirectoryInfo, DisabledProject, DisplayServer,
+        DownloadMirror, GameOptionOverrides, GameOptions, GpuPreference,
+        HeapUsage, Hooks, InstalledLoader, JavaSettings, JobInfo,
+        LaunchOverrides, LaunchPlan, LinkedData, LoaderCacheCleanup,
+        LoaderPins, LogLevel, LoggingSettings, MemorySettings, MirrorPrefix,
         ModLoader, ModrinthCredentials, ModrinthCredentialsResult,
         ModrinthProject, ModrinthTeamMember, ModrinthUser, ModrinthVersion,
-        ProfileMetadata, ProjectMetadata, Settings, Theme, WindowSize,
+        NetworkOverrides, OptionValue, PlaySession, Playtime,
+        PostLaunchBehavior, PostLaunchPolicy, PrimaryServer, ProfileMetadata,
+        ProjectMetadata, RunningDownloads, Schedule, ServerPackData,
+        ServerRedirect, ServerResourcePack, Settings, StateComponent,
+        StateSnapshot, StorageMode, Theme, UpdateChannel, VersionFilter,
+        WindowAction, WindowSize, WriteAccessIssue,
     };
+
+    pub use crate::launcher::args::{JavaArg, JavaArgSource};
+
+    #[cfg(feature = "launch_middleware")]
+    pub use crate::state::LaunchMiddleware;
+
+    #[cfg(feature = "fault_injection")]
+    pub use crate::util::fault::{Fault, FaultRule, InjectedFault};
 }
 
 pub mod prelude {
@@ -30,9 +64,10 @@ pub mod prelude {
         profile::{self, create, Profile},
         settings,
         state::JavaGlobals,
-        state::{Dependency, ProfilePathId, ProjectPathId},
+        state::{Dependency, ProfilePathId, ProfileSelector, ProjectPathId},
         util::{
-            io::{canonicalize, IOError},
+            fetch::{ConnectionMetrics, DownloadOverride, DownloadStatus},
+            io::{canonicalize, IOError, PermissionCause},
             jre::JavaVersion,
         },
         State,
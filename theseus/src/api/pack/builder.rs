@@ -0,0 +1,377 @@
+//! Assembling a .mrpack from scratch for pack authoring tools, rather than
+//! exporting a profile
+use crate::pack::install_from::{
+    EnvType, PackDependency, PackFile, PackFileHash, PackFormat, PackServer,
+};
+use crate::state::{ModrinthVersion, SideType};
+use crate::util::io::{self, IOError};
+use async_zip::tokio::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+// Version of the .mrpack format written
+const FORMAT_VERSION: i32 = 1;
+
+/// Which sides an override is copied to when the pack is installed
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideSide {
+    #[default]
+    Both,
+    Client,
+    Server,
+}
+
+impl OverrideSide {
+    fn folder(self) -> &'static str {
+        match self {
+            OverrideSide::Both => "overrides",
+            OverrideSide::Client => "client-overrides",
+            OverrideSide::Server => "server-overrides",
+        }
+    }
+}
+
+/// A file or folder on disk, copied into the pack
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackOverride {
+    /// Where it ends up in the instance, such as `config/sodium.json`
+    pub path: String,
+    pub source: PathBuf,
+    #[serde(default)]
+    pub side: OverrideSide,
+}
+
+/// A pack being assembled. Its files are downloaded when the pack is
+/// installed, while overrides are stored in it
+#[derive(Serialize, Deserialize)]
+pub struct PackBuilder {
+    pub name: String,
+    /// Version of the pack, such as `1.2.0`
+    pub version_id: String,
+    pub summary: Option<String>,
+    pub dependencies: HashMap<PackDependency, String>,
+    pub files: Vec<PackFile>,
+    pub overrides: Vec<PackOverride>,
+    /// Servers added to the server list of the instance
+    #[serde(default)]
+    pub servers: Vec<PackServer>,
+    /// JVM arguments recommended to players
+    #[serde(default)]
+    pub java_args: Vec<String>,
+}
+
+impl PackBuilder {
+    pub fn new(name: &str, version_id: &str, game_version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version_id: version_id.to_string(),
+            summary: None,
+            dependencies: HashMap::from([(
+                PackDependency::Minecraft,
+                game_version.to_string(),
+            )]),
+            files: Vec::new(),
+            overrides: Vec::new(),
+            servers: Vec::new(),
+            java_args: Vec::new(),
+        }
+    }
+
+    /// Sets the mod loader of the pack, replacing any other
+    pub fn set_loader(&mut self, loader: PackDependency, version: &str) {
+        self.dependencies
+            .retain(|x, _| *x == PackDependency::Minecraft);
+        if loader != PackDependency::Minecraft {
+            self.dependencies.insert(loader, version.to_string());
+        }
+    }
+
+    pub fn add_file(&mut self, file: PackFile) {
+        self.files.push(file);
+    }
+
+    /// Adds the primary file of a Modrinth version, in a folder such as
+    /// `mods`. Sides left unset make it required on both
+    pub fn add_modrinth_file(
+        &mut self,
+        version: &ModrinthVersion,
+        folder: &str,
+        env: Option<(SideType, SideType)>,
+    ) -> crate::Result<()> {
+        let file = version
+            .files
+            .iter()
+            .find(|x| x.primary)
+            .or_else(|| version.files.first())
+            .ok_or_else(|| {
+                crate::ErrorKind::InputError(format!(
+                    "Version {} has no files",
+                    version.id
+                ))
+            })?;
+
+        let folder = folder.trim_end_matches('/');
+        self.files.push(PackFile {
+            path: format!("{folder}/{}", file.filename),
+            hashes: file
+                .hashes
+                .iter()
+                .map(|(k, v)| (PackFileHash::from(k.clone()), v.clone()))
+                .collect(),
+            env: env.map(|(client, server)| {
+                HashMap::from([
+                    (EnvType::Client, client),
+                    (EnvType::Server, server),
+                ])
+            }),
+            downloads: vec![file.url.clone()],
+            file_size: file.size,
+        });
+        Ok(())
+    }
+
+    /// Adds a file or folder to copy into the instance at `path`
+    pub fn add_override(
+        &mut self,
+        path: &str,
+        source: PathBuf,
+        side: OverrideSide,
+    ) {
+        self.overrides.push(PackOverride {
+            path: path.to_string(),
+            source,
+            side,
+        });
+    }
+
+    /// Adds a server to the server list of the instance
+    pub fn add_server(&mut self, name: &str, address: &str) {
+        self.servers.push(PackServer {
+            name: name.to_string(),
+            address: address.to_string(),
+        });
+    }
+
+    /// Recommends JVM arguments, such as `-XX:+UseG1GC`, which the ones
+    /// players set override
+    pub fn add_java_args(&mut self, args: &[String]) {
+        self.java_args.extend(args.iter().cloned());
+    }
+
+    /// Checks the pack against the .mrpack format, returning every problem
+    /// found. Packs with problems can't be written
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if self.name.trim().is_empty() {
+            issues.push("The pack has no name".to_string());
+        }
+        if self.version_id.trim().is_empty() {
+            issues.push("The pack has no version".to_string());
+        }
+        if !self.dependencies.contains_key(&PackDependency::Minecraft) {
+            issues.push("The pack has no Minecraft version".to_string());
+        }
+        let loaders = self
+            .dependencies
+            .keys()
+            .filter(|x| **x != PackDependency::Minecraft)
+            .count();
+        if loaders > 1 {
+            issues.push("The pack has more than one mod loader".to_string());
+        }
+
+        let mut paths = HashSet::new();
+        for file in &self.files {
+            if !is_instance_path(&file.path) {
+                issues.push(format!(
+                    "{} is not a path inside the instance",
+                    file.path
+                ));
+            }
+            if !paths.insert(file.path.as_str()) {
+                issues.push(format!("{} is in the pack twice", file.path));
+            }
+            for hash in [PackFileHash::Sha1, PackFileHash::Sha512] {
+                if !file.hashes.contains_key(&hash) {
+                    issues.push(format!(
+                        "{} is missing its {} hash",
+                        file.path,
+                        match hash {
+                            PackFileHash::Sha1 => "SHA-1",
+                            _ => "SHA-512",
+                        }
+                    ));
+                }
+            }
+            if file.downloads.is_empty() {
+                issues.push(format!("{} has no downloads", file.path));
+            }
+            for url in &file.downloads {
+                if !url.starts_with("https://") {
+                    issues.push(format!(
+                        "{} has a download that isn't HTTPS: {url}",
+                        file.path
+                    ));
+                }
+            }
+            if let Some(env) = &file.env {
+                if env.values().any(|x| *x == SideType::Unknown) {
+                    issues.push(format!(
+                        "{} has a side that is neither required, optional \
+                         nor unsupported",
+                        file.path
+                    ));
+                }
+            }
+        }
+
+        for override_ in &self.overrides {
+            if !is_instance_path(&override_.path) {
+                issues.push(format!(
+                    "Override {} is not a path inside the instance",
+                    override_.path
+                ));
+            }
+            if !override_.source.exists() {
+                issues.push(format!(
+                    "Override {} comes from {}, which doesn't exist",
+                    override_.path,
+                    override_.source.display()
+                ));
+            }
+        }
+
+        for server in &self.servers {
+            if server.name.trim().is_empty() {
+                issues.push(format!("Server {} has no name", server.address));
+            }
+            if server.address.trim().is_empty() {
+                issues.push(format!("Server {} has no address", server.name));
+            }
+        }
+
+        issues
+    }
+
+    // Fails with every problem found, as invalid packs aren't written
+    fn check(&self) -> crate::Result<()> {
+        let issues = self.validate();
+        if !issues.is_empty() {
+            return Err(crate::ErrorKind::InputError(format!(
+                "Invalid pack: {}",
+                issues.join(", ")
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Writes the pack as a .mrpack file, once it is valid. The file is only
+    /// replaced once the whole pack is written
+    #[tracing::instrument(skip_all)]
+    #[theseus_macros::debug_pin]
+    pub async fn write(self, path: &Path) -> crate::Result<()> {
+        self.check()?;
+
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+
+        let mut file = File::create(&partial)
+            .await
+            .map_err(|e| IOError::with_path(e, &partial))?;
+        let written = self.write_to_writer(&mut file).await;
+        drop(file);
+        if let Err(err) = written {
+            let _ = io::remove_file(&partial).await;
+            return Err(err);
+        }
+
+        io::rename(&partial, path).await?;
+        Ok(())
+    }
+
+    /// Streams the pack as a .mrpack into a writer, once it is valid
+    #[tracing::instrument(skip_all)]
+    #[theseus_macros::debug_pin]
+    pub async fn write_to_writer<W>(self, out: &mut W) -> crate::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.check()?;
+
+        let mut writer = ZipFileWriter::new(&mut *out);
+        for override_ in &self.overrides {
+            let prefix = format!(
+                "{}/{}",
+                override_.side.folder(),
+                override_.path.trim_matches('/')
+            );
+
+            let mut sources = Vec::new();
+            if override_.source.is_dir() {
+                crate::profile::build_folder(&override_.source, &mut sources)
+                    .await?;
+            } else {
+                sources.push(override_.source.clone());
+            }
+            for source in sources {
+                if !source.is_file() {
+                    continue;
+                }
+                let relative = source
+                    .strip_prefix(&override_.source)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let name = if relative.is_empty() {
+                    prefix.clone()
+                } else {
+                    format!("{prefix}/{relative}")
+                };
+
+                let data = io::read(&source).await?;
+                let builder = ZipEntryBuilder::new(name, Compression::Deflate);
+                writer.write_entry_whole(builder, &data).await?;
+            }
+        }
+
+        let index = PackFormat {
+            game: "minecraft".to_string(),
+            format_version: FORMAT_VERSION,
+            version_id: self.version_id,
+            name: self.name,
+            summary: self.summary,
+            files: self.files,
+            dependencies: self.dependencies,
+            servers: self.servers,
+            java_args: self.java_args,
+        };
+        let data = serde_json::to_vec_pretty(&index)?;
+        let builder = ZipEntryBuilder::new(
+            "modrinth.index.json".to_string(),
+            Compression::Deflate,
+        );
+        writer.write_entry_whole(builder, &data).await?;
+
+        writer.close().await?;
+        out.flush().await.map_err(IOError::from)?;
+
+        Ok(())
+    }
+}
+
+// Whether a path stays inside the instance it is installed to
+fn is_instance_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && Path::new(path)
+            .components()
+            .all(|x| matches!(x, Component::Normal(_)))
+}
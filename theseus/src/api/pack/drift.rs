@@ -0,0 +1,267 @@
+//! Comparing a managed pack instance against the pack version it installed
+use crate::config::MODRINTH_API_URL;
+use crate::pack::install_from::{PackFileHash, PackFormat};
+use crate::pack::install_mrpack::is_client_file;
+use crate::prelude::ProfilePathId;
+use crate::state::ModrinthVersion;
+use crate::util::fetch::{fetch, fetch_json, sha1_async};
+use crate::util::io::{self, IOError};
+use crate::State;
+use async_zip::tokio::read::seek::ZipFileReader;
+use bytes::Bytes;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// Folders the game fills on its own, which pack overrides may seed
+const GAME_FOLDERS: [&str; 4] =
+    ["saves", "logs", "crash-reports", "screenshots"];
+
+/// Files of a profile that differ from the pack version it has installed,
+/// all relative to the profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DriftReport {
+    /// Files in the folders the pack installs to, that the pack doesn't have
+    pub added: Vec<PathBuf>,
+    /// Files of the pack that are gone
+    pub removed: Vec<PathBuf>,
+    /// Files of the pack whose contents changed
+    pub modified: Vec<PathBuf>,
+    /// Files of the pack that were disabled
+    pub disabled: Vec<PathBuf>,
+}
+
+/// Lists what was added, removed, modified or disabled in a managed pack
+/// since it was installed, which updating or repairing it would undo.
+/// Packs installed before file hashes were recorded are compared against
+/// the installed version, downloaded again
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn drift_report(
+    profile_path: &ProfilePathId,
+) -> crate::Result<DriftReport> {
+    let profile = crate::api::profile::get(profile_path, None)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?;
+    let unmanaged_err = || {
+        crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is not a managed modrinth pack"
+        ))
+    };
+    let linked_data = profile
+        .metadata
+        .linked_data
+        .as_ref()
+        .ok_or_else(unmanaged_err)?;
+
+    let file_hashes = if linked_data.file_hashes.is_empty() {
+        let version_id =
+            linked_data.version_id.as_ref().ok_or_else(unmanaged_err)?;
+        pack_file_hashes(&fetch_pack(version_id).await?).await?
+    } else {
+        linked_data.file_hashes.clone()
+    };
+
+    let full_path = profile_path.get_full_path().await?;
+    let mut report = DriftReport::default();
+    for (path, hash) in &file_hashes {
+        let relative_path = Path::new(path).components().collect::<PathBuf>();
+        let file = full_path.join(&relative_path);
+        if !file.exists() {
+            if disabled_path(&file).exists() {
+                report.disabled.push(relative_path);
+            } else {
+                report.removed.push(relative_path);
+            }
+            continue;
+        }
+
+        let bytes = Bytes::from(io::read(&file).await?);
+        if sha1_async(bytes).await? != *hash {
+            report.modified.push(relative_path);
+        }
+    }
+
+    // Only the folders the pack installs to are searched for added files,
+    // leaving out what the game creates on its own, like saves and logs
+    let owned = file_hashes
+        .keys()
+        .map(|x| Path::new(x).components().collect::<PathBuf>())
+        .collect::<HashSet<_>>();
+    let folders = owned
+        .iter()
+        .filter(|x| x.components().count() > 1)
+        .filter_map(|x| x.components().next())
+        .map(|x| PathBuf::from(x.as_os_str()))
+        .filter(|x| !GAME_FOLDERS.iter().any(|folder| x == Path::new(folder)))
+        .collect::<HashSet<_>>();
+    for folder in folders {
+        let mut files = Vec::new();
+        collect_files(&full_path, &full_path.join(folder), &mut files).await?;
+        report.added.extend(files.into_iter().filter(|x| {
+            !owned.contains(x) && !owned.contains(&enabled_path(x))
+        }));
+    }
+
+    report.added.sort();
+    report.removed.sort();
+    report.modified.sort();
+    report.disabled.sort();
+
+    Ok(report)
+}
+
+/// SHA-1 of every file an mrpack installs on the client, its overrides
+/// included, by path relative to the profile
+pub(crate) async fn pack_file_hashes(
+    file: &Bytes,
+) -> crate::Result<HashMap<String, String>> {
+    let reader: Cursor<&Bytes> = Cursor::new(file);
+    let mut zip_reader = ZipFileReader::new(reader).await.map_err(|_| {
+        crate::ErrorKind::InputError(
+            "Failed to read input modpack zip".to_string(),
+        )
+    })?;
+
+    let Some(manifest_index) = zip_reader
+        .file()
+        .entries()
+        .iter()
+        .position(|f| f.entry().filename() == "modrinth.index.json")
+    else {
+        return Err(crate::ErrorKind::InputError(
+            "No pack manifest found in mrpack".to_string(),
+        )
+        .into());
+    };
+    let entry = zip_reader
+        .file()
+        .entries()
+        .get(manifest_index)
+        .unwrap()
+        .entry()
+        .clone();
+    let mut manifest = String::new();
+    zip_reader
+        .entry(manifest_index)
+        .await?
+        .read_to_string_checked(&mut manifest, &entry)
+        .await?;
+    let pack: PackFormat = serde_json::from_str(&manifest)?;
+
+    let mut hashes = HashMap::new();
+    for file in pack.files.iter().filter(|x| is_client_file(x)) {
+        if let Some(sha1) = file.hashes.get(&PackFileHash::Sha1) {
+            hashes.insert(file.path.clone(), sha1.clone());
+        }
+    }
+
+    // Client overrides are extracted over the common ones
+    for prefix in ["overrides/", "client_overrides/"] {
+        for index in 0..zip_reader.file().entries().len() {
+            let entry = zip_reader
+                .file()
+                .entries()
+                .get(index)
+                .unwrap()
+                .entry()
+                .clone();
+            let Some(path) = entry.filename().strip_prefix(prefix) else {
+                continue;
+            };
+            if path.is_empty() || path.ends_with('/') {
+                continue;
+            }
+
+            let path = path.to_string();
+            let mut content = Vec::new();
+            zip_reader
+                .entry(index)
+                .await?
+                .read_to_end_checked(&mut content, &entry)
+                .await?;
+            hashes.insert(path, sha1_async(Bytes::from(content)).await?);
+        }
+    }
+
+    Ok(hashes)
+}
+
+// Downloads the mrpack of a pack version
+async fn fetch_pack(version_id: &str) -> crate::Result<Bytes> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let version: ModrinthVersion = fetch_json(
+        Method::GET,
+        &format!("{MODRINTH_API_URL}version/{version_id}"),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    let file = version
+        .files
+        .iter()
+        .find(|x| x.primary)
+        .or_else(|| version.files.first())
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Pack version {version_id} has no files"
+            ))
+        })?;
+    fetch(
+        &file.url,
+        file.hashes.get("sha1").map(|x| &**x),
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await
+}
+
+#[async_recursion::async_recursion]
+async fn collect_files(
+    base: &Path,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> crate::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut read_dir = io::read_dir(dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, dir))?
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(base, &path, files).await?;
+        } else if let Ok(relative_path) = path.strip_prefix(base) {
+            files.push(relative_path.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn disabled_path(path: &Path) -> PathBuf {
+    path.with_file_name(format!(
+        "{}.disabled",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+fn enabled_path(path: &Path) -> PathBuf {
+    match path.file_name().map(|x| x.to_string_lossy()) {
+        Some(name) if name.ends_with(".disabled") => {
+            path.with_file_name(name.trim_end_matches(".disabled"))
+        }
+        _ => path.to_path_buf(),
+    }
+}
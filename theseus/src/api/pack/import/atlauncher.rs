@@ -10,7 +10,7 @@ use crate::{
         install_from::CreatePackDescription,
     },
     prelude::{ModLoader, ProfilePathId},
-    state::{LinkedData, ProfileInstallStage},
+    state::{LinkedData, ProfileInstallStage, UpdateChannel},
     util::io,
     State,
 };
@@ -218,6 +218,22 @@ async fn import_atlauncher_unmanaged(
         prof.metadata.linked_data = Some(LinkedData {
             project_id: description.project_id.clone(),
             version_id: description.version_id.clone(),
+            pinned: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map_or(false, |x| x.pinned),
+            locked: false,
+            pack_files: Vec::new(),
+            modifications: Vec::new(),
+            file_hashes: HashMap::new(),
+            update_channel: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map_or_else(UpdateChannel::default, |x| x.update_channel),
+            pack_servers: Vec::new(),
+            pack_java_args: Vec::new(),
         });
         prof.metadata.icon = description.icon.clone();
         prof.metadata.game_version = game_version.clone();
@@ -1,8 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::state::CredentialsStore;
+use crate::state::{CredentialsStore, ServerPackData};
 use crate::{
     prelude::{ModLoader, ProfilePathId},
     state::ProfileInstallStage,
@@ -125,7 +126,7 @@ pub async fn import_curseforge(
 
         let game_version = cf_manifest.minecraft.version;
 
-        // CF allows Forge, Fabric, and Vanilla
+        // CF allows Forge, NeoForge, Fabric, and Vanilla
         let mut mod_loader = None;
         let mut loader_version = None;
         for loader in cf_manifest.minecraft.mod_loaders {
@@ -134,6 +135,10 @@ pub async fn import_curseforge(
                     mod_loader = Some(ModLoader::Forge);
                     loader_version = Some(version.to_string());
                 }
+                Some(("neoforge", version)) => {
+                    mod_loader = Some(ModLoader::NeoForge);
+                    loader_version = Some(version.to_string());
+                }
                 Some(("fabric", version)) => {
                     mod_loader = Some(ModLoader::Fabric);
                     loader_version = Some(version.to_string());
@@ -204,3 +209,235 @@ pub async fn import_curseforge(
 
     Ok(())
 }
+
+// Game and loader versions detected from the files of a server pack
+struct ServerPackLayout {
+    root: PathBuf,
+    game_version: String,
+    mod_loader: ModLoader,
+    loader_version: Option<String>,
+    start_scripts: Vec<PathBuf>,
+}
+
+/// Imports a CurseForge server pack, either a zip or an extracted folder,
+/// into a profile. Server packs already contain resolved mods and configs, so
+/// the files are copied as they are rather than looked up through the API
+pub async fn import_curseforge_server_pack(
+    server_pack: PathBuf,        // zip file or folder of the server pack
+    profile_path: ProfilePathId, // path to profile
+) -> crate::Result<()> {
+    let backup_name = server_pack
+        .file_stem()
+        .map(|a| a.to_string_lossy().to_string())
+        .unwrap_or("Server Pack".to_string());
+
+    // Zipped packs are extracted to a temporary folder, removed on drop
+    let extracted = if server_pack.is_file() {
+        let tempdir = tempfile::tempdir()
+            .map_err(|e| io::IOError::with_path(e, &server_pack))?;
+        let archive_path = server_pack.clone();
+        let extract_path = tempdir.path().to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&archive_path)
+                .map_err(|e| io::IOError::with_path(e, &archive_path))?;
+            zip::ZipArchive::new(file)
+                .and_then(|mut archive| archive.extract(&extract_path))
+                .map_err(|e| {
+                    crate::ErrorKind::InputError(format!(
+                        "Invalid server pack archive: {e}"
+                    ))
+                })?;
+            Ok::<_, crate::Error>(())
+        })
+        .await??;
+        Some(tempdir)
+    } else {
+        None
+    };
+    let folder = extracted
+        .as_ref()
+        .map_or(server_pack.clone(), |x| x.path().to_path_buf());
+
+    let layout = detect_server_pack(&folder).await?;
+    // The mods are copied as they are, so they are checked against the
+    // content policy by hash
+    crate::state::policy::check_folder(&layout.root).await?;
+    let loader_version = if layout.mod_loader != ModLoader::Vanilla {
+        crate::profile::create::get_loader_version_from_loader(
+            layout.game_version.clone(),
+            layout.mod_loader,
+            layout.loader_version.clone(),
+        )
+        .await?
+    } else {
+        None
+    };
+
+    crate::api::profile::edit(&profile_path, |prof| {
+        prof.metadata.name = backup_name.clone();
+        prof.install_stage = ProfileInstallStage::PackInstalling;
+        prof.metadata.game_version = layout.game_version.clone();
+        prof.metadata.loader_version = loader_version.clone();
+        prof.metadata.loader = layout.mod_loader;
+        prof.metadata.server_pack = Some(ServerPackData {
+            start_scripts: layout.start_scripts.clone(),
+        });
+
+        async { Ok(()) }
+    })
+    .await?;
+
+    // Everything, including the start scripts, is kept so the profile folder
+    // can also be run as the server root
+    let state = State::get().await?;
+    copy_dotminecraft(profile_path.clone(), layout.root, &state.io_semaphore)
+        .await?;
+
+    if let Some(profile_val) =
+        crate::api::profile::get(&profile_path, None).await?
+    {
+        crate::launcher::install_minecraft(&profile_val, None).await?;
+
+        State::sync().await?;
+    }
+
+    Ok(())
+}
+
+// Server packs carry no manifest, so the versions are read from the server
+// jars, the libraries folder or a variables.txt, whichever is present
+async fn detect_server_pack(folder: &Path) -> crate::Result<ServerPackLayout> {
+    // Packs are often zipped with a single top-level folder
+    let mut root = folder.to_path_buf();
+    let mut file_names = list_file_names(&root).await?;
+    if let [(name, true)] = file_names.as_slice() {
+        root = root.join(name);
+        file_names = list_file_names(&root).await?;
+    }
+
+    let start_scripts = file_names
+        .iter()
+        .filter(|(name, is_dir)| {
+            let name = name.to_lowercase();
+            !is_dir
+                && (name.starts_with("start") || name.starts_with("run"))
+                && [".sh", ".bat", ".ps1", ".command"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+        })
+        .map(|(name, _)| PathBuf::from(name))
+        .collect();
+
+    let mut game_version = None;
+    let mut mod_loader = ModLoader::Vanilla;
+    let mut loader_version = None;
+
+    let forge_jar = Regex::new(
+        r"^forge-(\d+\.\d+(?:\.\d+)?)-([\d.]+)(?:-installer|-universal)?\.jar$",
+    )?;
+    let fabric_jar = Regex::new(
+        r"^fabric-server-mc\.(\d+\.\d+(?:\.\d+)?)-loader\.([\d.]+)-launcher",
+    )?;
+    let vanilla_jar =
+        Regex::new(r"^minecraft_server\.(\d+\.\d+(?:\.\d+)?)\.jar$")?;
+    for (name, _) in &file_names {
+        if let Some(captures) = forge_jar.captures(name) {
+            game_version = Some(captures[1].to_string());
+            mod_loader = ModLoader::Forge;
+            loader_version = Some(captures[2].to_string());
+        } else if let Some(captures) = fabric_jar.captures(name) {
+            game_version = Some(captures[1].to_string());
+            mod_loader = ModLoader::Fabric;
+            loader_version = Some(captures[2].to_string());
+        } else if let Some(captures) = vanilla_jar.captures(name) {
+            game_version.get_or_insert_with(|| captures[1].to_string());
+        }
+    }
+
+    // Newer Forge installs only leave their version in the libraries folder
+    let forge_libraries = root.join("libraries/net/minecraftforge/forge");
+    if game_version.is_none() && forge_libraries.exists() {
+        for (name, _) in list_file_names(&forge_libraries).await? {
+            if let Some((game, loader)) = name.split_once('-') {
+                game_version = Some(game.to_string());
+                mod_loader = ModLoader::Forge;
+                loader_version = Some(loader.to_string());
+            }
+        }
+    }
+
+    // NeoForge is numbered after the game version it is for, 20.4.x being
+    // for 1.20.4
+    let neoforge_libraries = root.join("libraries/net/neoforged/neoforge");
+    if game_version.is_none() && neoforge_libraries.exists() {
+        for (name, _) in list_file_names(&neoforge_libraries).await? {
+            let mut parts = name.split('.');
+            if let (Some(major), Some(minor)) = (parts.next(), parts.next()) {
+                game_version = Some(match minor {
+                    "0" => format!("1.{major}"),
+                    _ => format!("1.{major}.{minor}"),
+                });
+                mod_loader = ModLoader::NeoForge;
+                loader_version = Some(name.to_string());
+            }
+        }
+    }
+
+    // Written by the start scripts of newer CurseForge server packs
+    let variables = root.join("variables.txt");
+    if game_version.is_none() && variables.exists() {
+        let variables = io::read_to_string(&variables).await?;
+        for (key, value) in variables
+            .lines()
+            .filter_map(|x| x.split_once('='))
+            .map(|(k, v)| (k.trim(), v.trim().trim_matches('"')))
+        {
+            match key {
+                "MINECRAFT_VERSION" => game_version = Some(value.to_string()),
+                "MODLOADER" => {
+                    mod_loader = match value.to_lowercase().as_str() {
+                        "forge" => ModLoader::Forge,
+                        "neoforge" => ModLoader::NeoForge,
+                        "fabric" => ModLoader::Fabric,
+                        "quilt" => ModLoader::Quilt,
+                        _ => ModLoader::Vanilla,
+                    }
+                }
+                "MODLOADER_VERSION" => loader_version = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    let game_version = game_version.ok_or_else(|| {
+        crate::ErrorKind::InputError(
+            "Could not detect the Minecraft version of this server pack"
+                .to_string(),
+        )
+    })?;
+
+    Ok(ServerPackLayout {
+        root,
+        game_version,
+        mod_loader,
+        loader_version,
+        start_scripts,
+    })
+}
+
+// Lists the entries of a folder as (name, is directory) pairs
+async fn list_file_names(folder: &Path) -> crate::Result<Vec<(String, bool)>> {
+    let mut names = Vec::new();
+    let mut dir = io::read_dir(folder).await?;
+    while let Some(entry) = dir
+        .next_entry()
+        .await
+        .map_err(|e| io::IOError::with_path(e, folder))?
+    {
+        names.push((
+            entry.file_name().to_string_lossy().to_string(),
+            entry.path().is_dir(),
+        ));
+    }
+    Ok(names)
+}
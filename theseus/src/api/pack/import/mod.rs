@@ -156,6 +156,31 @@ pub async fn import_instance(
     Ok(())
 }
 
+/// Import a CurseForge server pack (zip or extracted folder) into a profile
+/// Note: this *deletes* the submitted empty profile if the import fails
+#[theseus_macros::debug_pin]
+#[tracing::instrument]
+pub async fn import_server_pack(
+    profile_path: ProfilePathId, // This should be a blank profile
+    server_pack: PathBuf,
+) -> crate::Result<()> {
+    tracing::debug!("Importing server pack from {}", server_pack.display());
+    let res = curseforge::import_curseforge_server_pack(
+        server_pack,
+        profile_path.clone(),
+    )
+    .await;
+
+    if let Err(e) = res {
+        tracing::warn!("Import failed: {:?}", e);
+        let _ = crate::api::profile::remove(&profile_path).await;
+        return Err(e);
+    }
+
+    tracing::debug!("Completed import.");
+    Ok(())
+}
+
 /// Returns the default path for the given launcher type
 /// None if it can't be found or doesn't exist
 pub fn get_default_launcher_path(
@@ -273,7 +298,7 @@ async fn copy_dotminecraft(
 #[theseus_macros::debug_pin]
 #[async_recursion::async_recursion]
 #[tracing::instrument]
-async fn copy_dir_to(
+pub(crate) async fn copy_dir_to(
     src: &Path,
     dst: &Path,
     io_semaphore: &IoSemaphore,
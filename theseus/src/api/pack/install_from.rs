@@ -4,7 +4,8 @@ use crate::event::emit::{emit_loading, init_loading};
 use crate::event::{LoadingBarId, LoadingBarType};
 use crate::prelude::ProfilePathId;
 use crate::state::{
-    LinkedData, ModrinthProject, ModrinthVersion, ProfileInstallStage, SideType,
+    LinkedData, ModrinthProject, ModrinthVersion, ProfileInstallStage,
+    SideType, UpdateChannel,
 };
 use crate::util::fetch::{
     fetch, fetch_advanced, fetch_json, write_cached_icon,
@@ -28,6 +29,24 @@ pub struct PackFormat {
     pub summary: Option<String>,
     pub files: Vec<PackFile>,
     pub dependencies: HashMap<PackDependency, String>,
+    /// Servers added to the server list of the profile, an extension of the
+    /// format other launchers ignore
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<PackServer>,
+    /// JVM arguments the pack recommends, which the ones the user sets
+    /// override flag by flag. Also an extension of the format
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub java_args: Vec<String>,
+}
+
+/// A server a pack adds to the server list when installed. Updates only add
+/// servers the pack didn't ship before, so entries removed by the user stay
+/// removed
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct PackServer {
+    pub name: String,
+    /// Address as typed in game, `host` or `host:port`
+    pub address: String,
 }
 
 #[derive(Serialize, Deserialize, Eq, PartialEq)]
@@ -69,12 +88,14 @@ pub enum EnvType {
 #[serde(rename_all = "kebab-case")]
 pub enum PackDependency {
     Forge,
+    #[serde(rename = "neoforge")]
+    NeoForge,
     FabricLoader,
     QuiltLoader,
     Minecraft,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum CreatePackLocation {
     // Create a pack from a modrinth version ID (such as a modpack)
@@ -150,6 +171,14 @@ pub fn get_profile_from_pack(
             linked_data: Some(LinkedData {
                 project_id: Some(project_id),
                 version_id: Some(version_id),
+                pinned: false,
+                locked: false,
+                pack_files: Vec::new(),
+                modifications: Vec::new(),
+                file_hashes: HashMap::new(),
+                update_channel: UpdateChannel::default(),
+                pack_servers: Vec::new(),
+                pack_java_args: Vec::new(),
             }),
             ..Default::default()
         },
@@ -168,6 +197,64 @@ pub fn get_profile_from_pack(
     }
 }
 
+/// Finds the pack to install for a Modrinth version id, or the newest
+/// version of a Modrinth project given by id or slug
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn location_from_modrinth_id(
+    id: &str,
+) -> crate::Result<CreatePackLocation> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+
+    let version = match fetch_json::<ModrinthVersion>(
+        Method::GET,
+        &format!("{MODRINTH_API_URL}version/{id}"),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await
+    {
+        Ok(version) => version,
+        // Versions are listed newest first
+        Err(_) => fetch_json::<Vec<ModrinthVersion>>(
+            Method::GET,
+            &format!("{MODRINTH_API_URL}project/{id}/version"),
+            None,
+            None,
+            &state.fetch_semaphore,
+            &creds,
+        )
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "No Modrinth version or project with versions found for {id}"
+            ))
+        })?,
+    };
+
+    let project: ModrinthProject = fetch_json(
+        Method::GET,
+        &format!("{}project/{}", MODRINTH_API_URL, version.project_id),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    Ok(CreatePackLocation::FromVersionId {
+        project_id: project.id,
+        version_id: version.id,
+        title: project.title,
+        icon_url: project.icon_url,
+    })
+}
+
 #[tracing::instrument]
 #[theseus_macros::debug_pin]
 pub async fn generate_pack_from_version_id(
@@ -321,6 +408,10 @@ pub async fn set_profile_information(
                 mod_loader = Some(ModLoader::Forge);
                 loader_version = Some(value);
             }
+            PackDependency::NeoForge => {
+                mod_loader = Some(ModLoader::NeoForge);
+                loader_version = Some(value);
+            }
             PackDependency::FabricLoader => {
                 mod_loader = Some(ModLoader::Fabric);
                 loader_version = Some(value);
@@ -363,6 +454,33 @@ pub async fn set_profile_information(
         prof.metadata.linked_data = Some(LinkedData {
             project_id: description.project_id.clone(),
             version_id: description.version_id.clone(),
+            pinned: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map_or(false, |x| x.pinned),
+            locked: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map_or(false, |x| x.locked),
+            // Set once the files of the pack are installed
+            pack_files: Vec::new(),
+            modifications: Vec::new(),
+            file_hashes: HashMap::new(),
+            update_channel: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map_or_else(UpdateChannel::default, |x| x.update_channel),
+            pack_servers: prof
+                .metadata
+                .linked_data
+                .as_ref()
+                .map(|x| x.pack_servers.clone())
+                .unwrap_or_default(),
+            // Set once the pack is read
+            pack_java_args: Vec::new(),
         });
         prof.metadata.icon = description.icon.clone();
         prof.metadata.game_version = game_version.clone();
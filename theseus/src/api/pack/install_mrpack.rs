@@ -1,20 +1,28 @@
 use crate::event::emit::{
-    emit_loading, init_or_edit_loading, loading_try_for_each_concurrent,
+    add_loading_bytes, emit_loading, init_or_edit_loading,
+    loading_try_for_each_concurrent, record_loading_bytes,
 };
 use crate::event::LoadingBarType;
 use crate::pack::install_from::{
     set_profile_information, EnvType, PackFile, PackFileHash,
 };
-use crate::prelude::ProfilePathId;
-use crate::state::{ProfileInstallStage, Profiles, SideType};
+use crate::prelude::{ProfilePathId, ProjectPathId};
+use crate::servers::{self, Server, SERVERS_FILE};
+use crate::state::policy::{self, PolicyFile};
+use crate::state::{
+    Journal, OperationKind, ProfileInstallStage, Profiles, ProjectType,
+    SideType,
+};
 use crate::util::fetch::{fetch_mirrors, write};
 use crate::util::io;
 use crate::{profile, State};
 use async_zip::tokio::read::seek::ZipFileReader;
 
+use std::collections::HashSet;
 use std::io::Cursor;
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
+use super::drift::pack_file_hashes;
 use super::install_from::{
     generate_pack_from_file, generate_pack_from_version_id, CreatePack,
     CreatePackLocation, PackFormat,
@@ -52,7 +60,12 @@ pub async fn install_zipped_mrpack(
     };
 
     // Install pack files, and if it fails, fail safely by removing the profile
-    let result = install_zipped_mrpack_files(create_pack).await;
+    let result = Journal::run(
+        &profile_path,
+        OperationKind::PackInstall,
+        install_zipped_mrpack_files(create_pack),
+    )
+    .await;
 
     // Check existing managed packs for potential updates
     tokio::task::spawn(Profiles::update_modrinth_versions());
@@ -119,6 +132,32 @@ pub async fn install_zipped_mrpack_files(
             )
             .into());
         }
+        let file_hashes = pack_file_hashes(&file).await?;
+
+        // Nothing is installed if the pack, or any file it downloads, is
+        // blocked by the content policy
+        let mut policy_files = pack
+            .files
+            .iter()
+            .filter(|x| is_client_file(x))
+            .map(|x| PolicyFile {
+                path: x.path.clone(),
+                project_id: x
+                    .downloads
+                    .iter()
+                    .find_map(|x| modrinth_project_id(x))
+                    .map(String::from),
+                sha1: x.hashes.get(&PackFileHash::Sha1).cloned(),
+            })
+            .collect::<Vec<_>>();
+        if let Some(project_id) = &project_id {
+            policy_files.push(PolicyFile {
+                path: pack.name.clone(),
+                project_id: Some(project_id.clone()),
+                sha1: None,
+            });
+        }
+        policy::check_files(&policy_files).await?;
 
         // Sets generated profile attributes to the pack ones (using profile::edit)
         set_profile_information(
@@ -144,6 +183,68 @@ pub async fn install_zipped_mrpack_files(
         )
         .await?;
 
+        // Projects the pack installs, which a locked pack is held to
+        let mut pack_files = pack
+            .files
+            .iter()
+            .filter(|x| is_client_file(x))
+            .map(|x| Path::new(&x.path).components().collect::<PathBuf>())
+            .filter(|x| {
+                ProjectType::get_from_parent_folder(x.clone()).is_some()
+            })
+            .map(|x| ProjectPathId::new(&x))
+            .collect::<Vec<_>>();
+
+        // Projects the user disabled are installed disabled again, following
+        // Modrinth projects to their new file names
+        let mut disabled_projects = profile::get(&profile_path, None)
+            .await?
+            .map(|x| x.disabled_projects)
+            .unwrap_or_default();
+        let mut disabled_files = HashSet::new();
+        for file in pack.files.iter().filter(|x| is_client_file(x)) {
+            let path = ProjectPathId::new(
+                &Path::new(&file.path).components().collect::<PathBuf>(),
+            );
+            let project_id =
+                file.downloads.iter().find_map(|x| modrinth_project_id(x));
+            if let Some(disabled) = disabled_projects.iter_mut().find(|x| {
+                x.path == path
+                    || (x.project_id.is_some()
+                        && x.project_id.as_deref() == project_id)
+            }) {
+                disabled.path = path;
+                disabled_files.insert(file.path.clone());
+            }
+        }
+
+        // Servers the pack added before stay removed if the user removed them
+        let mut added_servers = profile::get(&profile_path, None)
+            .await?
+            .and_then(|x| x.metadata.linked_data)
+            .map(|x| x.pack_servers)
+            .unwrap_or_default();
+        let mut pack_servers = pack
+            .servers
+            .iter()
+            .map(|x| Server {
+                name: x.name.clone(),
+                address: x.address.clone(),
+                icon: None,
+                accept_resource_pack: None,
+                hidden: false,
+            })
+            .collect::<Vec<_>>();
+        let pack_java_args = pack.java_args;
+
+        let total_bytes = pack
+            .files
+            .iter()
+            .filter(|x| is_client_file(x))
+            .map(|x| x.file_size as u64)
+            .sum();
+        add_loading_bytes(&loading_bar, total_bytes).await?;
+
         let num_files = pack.files.len();
         use futures::StreamExt;
         loading_try_for_each_concurrent(
@@ -156,16 +257,12 @@ pub async fn install_zipped_mrpack_files(
             None,
             |project| {
                 let profile_path = profile_path.clone();
+                let loading_bar = &loading_bar;
+                let disabled_files = &disabled_files;
                 async move {
                     //TODO: Future update: prompt user for optional files in a modpack
-                    if let Some(env) = project.env {
-                        if env
-                            .get(&EnvType::Client)
-                            .map(|x| x == &SideType::Unsupported)
-                            .unwrap_or(false)
-                        {
-                            return Ok(());
-                        }
+                    if !is_client_file(&project) {
+                        return Ok(());
                     }
 
                     let creds = state.credentials.read().await;
@@ -176,21 +273,36 @@ pub async fn install_zipped_mrpack_files(
                             .map(|x| &**x)
                             .collect::<Vec<&str>>(),
                         project.hashes.get(&PackFileHash::Sha1).map(|x| &**x),
+                        Some(project.file_size as u64),
                         &state.fetch_semaphore,
                         &creds,
                     )
                     .await?;
                     drop(creds);
+                    record_loading_bytes(
+                        loading_bar,
+                        file.len() as u64,
+                        &project.path,
+                    )
+                    .await?;
 
                     let path =
                         std::path::Path::new(&project.path).components().next();
                     if let Some(path) = path {
                         match path {
                             Component::CurDir | Component::Normal(_) => {
-                                let path = profile_path
+                                let mut path = profile_path
                                     .get_full_path()
                                     .await?
                                     .join(&project.path);
+                                if disabled_files.contains(&project.path) {
+                                    path = path.with_file_name(format!(
+                                        "{}.disabled",
+                                        path.file_name()
+                                            .unwrap_or_default()
+                                            .to_string_lossy()
+                                    ));
+                                }
                                 write(&path, &file, &state.io_semaphore)
                                     .await?;
                             }
@@ -244,7 +356,21 @@ pub async fn install_zipped_mrpack_files(
                     new_path.push(component);
                 }
 
-                if new_path.file_name().is_some() {
+                if ProjectType::get_from_parent_folder(new_path.clone())
+                    .is_some()
+                {
+                    pack_files.push(ProjectPathId::new(&new_path));
+                }
+                if new_path == Path::new(SERVERS_FILE) {
+                    // Merged into the server list rather than replacing the
+                    // servers the user added
+                    match servers::parse_servers(&content) {
+                        Ok(entries) => pack_servers.extend(entries),
+                        Err(err) => tracing::warn!(
+                            "Unable to read servers shipped by the pack: {err}"
+                        ),
+                    }
+                } else if new_path.file_name().is_some() {
                     write(
                         &profile_path.get_full_path().await?.join(new_path),
                         &content,
@@ -265,6 +391,29 @@ pub async fn install_zipped_mrpack_files(
             }
         }
 
+        let new_servers = servers::add_pack_servers(
+            &profile_path,
+            pack_servers,
+            &added_servers,
+        )
+        .await?;
+        added_servers.extend(new_servers);
+
+        pack_files.sort_by(|a, b| a.0.cmp(&b.0));
+        pack_files.dedup();
+        profile::edit(&profile_path, |prof| {
+            if let Some(linked_data) = &mut prof.metadata.linked_data {
+                linked_data.pack_files = pack_files.clone();
+                linked_data.file_hashes = file_hashes.clone();
+                linked_data.pack_servers = added_servers.clone();
+                linked_data.pack_java_args = pack_java_args.clone();
+            }
+            prof.disabled_projects = disabled_projects.clone();
+            prof.flag_locked_modifications();
+            async { Ok(()) }
+        })
+        .await?;
+
         // If the icon doesn't exist, we expect icon.png to be a potential icon.
         // If it doesn't exist, and an override to icon.png exists, cache and use that
         let potential_icon =
@@ -358,6 +507,16 @@ pub async fn remove_all_related_files(
                     if existing_file.exists() {
                         io::remove_file(&existing_file).await?;
                     }
+                    let disabled_file = existing_file.with_file_name(format!(
+                        "{}.disabled",
+                        existing_file
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                    ));
+                    if disabled_file.exists() {
+                        io::remove_file(&disabled_file).await?;
+                    }
 
                     Ok(())
                 }
@@ -387,6 +546,11 @@ pub async fn remove_all_related_files(
                     new_path.push(component);
                 }
 
+                // The server list holds servers the user added too
+                if new_path == Path::new(SERVERS_FILE) {
+                    continue;
+                }
+
                 // Remove this file if a corresponding one exists in the filesystem
                 let existing_file =
                     profile_path.get_full_path().await?.join(&new_path);
@@ -402,3 +566,16 @@ pub async fn remove_all_related_files(
         )))
     }
 }
+
+// Project id out of a Modrinth CDN url, which looks like
+// `https://cdn.modrinth.com/data/<project>/versions/<version>/<file>`
+fn modrinth_project_id(url: &str) -> Option<&str> {
+    let path = url.strip_prefix("https://cdn.modrinth.com/data/")?;
+    path.split('/').next().filter(|x| !x.is_empty())
+}
+
+pub(crate) fn is_client_file(file: &PackFile) -> bool {
+    file.env.as_ref().map_or(true, |env| {
+        env.get(&EnvType::Client) != Some(&SideType::Unsupported)
+    })
+}
@@ -1,3 +1,5 @@
+pub mod builder;
+pub mod drift;
 pub mod import;
 pub mod install_from;
 pub mod install_mrpack;
@@ -1,17 +1,47 @@
 //! Theseus process management interface
 
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
+pub use crate::util::process_usage::ProcessUsage;
+use crate::{
+    auth::refresh_for_launch,
+    state::{MinecraftChild, ProfilePathId},
+    util::{io::IOError, process_usage},
+};
 pub use crate::{
     state::{
         Hooks, JavaSettings, MemorySettings, Profile, Settings, WindowSize,
     },
     State,
 };
-use crate::{
-    state::{MinecraftChild, ProfilePathId},
-    util::io::IOError,
-};
+
+/// A game launched by the launcher, running or not, as listed on the
+/// dashboard
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessInfo {
+    pub uuid: Uuid,
+    pub profile_path: ProfilePathId,
+    /// Unset if the profile has been removed since
+    pub profile_name: Option<String>,
+    pub pid: Option<u32>,
+    pub started: DateTime<Utc>,
+    pub running: bool,
+    /// Seconds since launch, while running
+    pub uptime: Option<i64>,
+    /// Unset while running, or if the process was killed by a signal
+    pub exit_code: Option<i32>,
+    /// Unset once exited
+    pub usage: Option<ProcessUsage>,
+}
+
+// How long a restart waits for the previous game to exit
+const RESTART_EXIT_TIMEOUT: Duration = Duration::from_secs(10);
 
 // Gets whether a child process stored in the state by UUID has finished
 #[tracing::instrument]
@@ -26,7 +56,11 @@ pub async fn get_exit_status_by_uuid(
 ) -> crate::Result<Option<i32>> {
     let state = State::get().await?;
     let children = state.children.read().await;
-    Ok(children.exit_status(uuid).await?.and_then(|f| f.code()))
+    Ok(children
+        .exit_status(uuid)
+        .await?
+        .flatten()
+        .and_then(|f| f.code()))
 }
 
 // Gets the UUID of each stored process in the state
@@ -92,6 +126,124 @@ pub async fn get_output_by_uuid(uuid: &Uuid) -> crate::Result<String> {
     }
 }
 
+/// Gets every process launched since the launcher started, newest first,
+/// with the memory and CPU use of the running ones
+#[tracing::instrument]
+pub async fn get_all_info() -> crate::Result<Vec<ProcessInfo>> {
+    let state = State::get().await?;
+    let mut infos = Vec::new();
+    {
+        let children = state.children.read().await;
+        for uuid in children.keys() {
+            let Some(child) = children.get(&uuid) else {
+                continue;
+            };
+            let child = child.read().await;
+            let mut current_child = child.current_child.write().await;
+            let status = current_child.try_wait().map_err(IOError::from)?;
+            let running = status.is_none();
+
+            infos.push(ProcessInfo {
+                uuid,
+                profile_path: child.profile_relative_path.clone(),
+                profile_name: None,
+                pid: current_child.id(),
+                started: child.started,
+                running,
+                uptime: running
+                    .then(|| (Utc::now() - child.started).num_seconds()),
+                exit_code: status.and_then(|x| x.code()),
+                usage: None,
+            });
+        }
+    }
+
+    let pids = infos
+        .iter()
+        .filter(|x| x.running)
+        .filter_map(|x| x.pid)
+        .collect::<Vec<_>>();
+    let usage =
+        tokio::task::spawn_blocking(move || process_usage::sample(&pids))
+            .await?;
+
+    for info in &mut infos {
+        info.profile_name = crate::profile::get(&info.profile_path, None)
+            .await?
+            .map(|x| x.metadata.name);
+        if info.running {
+            info.usage = info.pid.and_then(|x| usage.get(&x)).copied();
+        }
+    }
+    infos.sort_by(|a, b| b.started.cmp(&a.started));
+
+    Ok(infos)
+}
+
+/// Asks a child process stored in the state by UUID to exit, leaving the
+/// game time to save, where `kill_by_uuid` stops it right away
+#[tracing::instrument]
+pub async fn terminate_by_uuid(uuid: &Uuid) -> crate::Result<()> {
+    let state = State::get().await?;
+    let children = state.children.read().await;
+    if let Some(mchild) = children.get(uuid) {
+        let mchild = mchild.read().await;
+        let current_child = mchild.current_child.read().await;
+        current_child.terminate().map_err(IOError::from)?;
+    }
+    // No error returned for already finished process
+    Ok(())
+}
+
+/// Launches the profile of a child process stored in the state by UUID
+/// again, with the same account, as after a crash. A game still running is
+/// stopped first
+#[tracing::instrument]
+pub async fn restart_by_uuid(
+    uuid: &Uuid,
+) -> crate::Result<Arc<RwLock<MinecraftChild>>> {
+    let state = State::get().await?;
+    let (profile_path, account) = {
+        let children = state.children.read().await;
+        let Some(mchild) = children.get(uuid) else {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "No child process by UUID {uuid}"
+            ))
+            .as_error());
+        };
+        let mchild = mchild.read().await;
+        (mchild.profile_relative_path.clone(), mchild.account)
+    };
+
+    if !has_finished_by_uuid(uuid).await? {
+        kill_by_uuid(uuid).await?;
+        let waited = tokio::time::timeout(RESTART_EXIT_TIMEOUT, async {
+            while !has_finished_by_uuid(uuid).await? {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            Ok::<(), crate::Error>(())
+        })
+        .await;
+        match waited {
+            Ok(res) => res?,
+            Err(_) => {
+                return Err(crate::ErrorKind::LauncherError(format!(
+                    "Process {uuid} did not exit to be restarted"
+                ))
+                .as_error())
+            }
+        }
+    }
+
+    match account {
+        Some(account) => {
+            let credentials = refresh_for_launch(account).await?;
+            crate::profile::run_credentials(&profile_path, &credentials).await
+        }
+        None => crate::profile::run(&profile_path).await,
+    }
+}
+
 // Kill a child process stored in the state by UUID, as a string
 #[tracing::instrument]
 pub async fn kill_by_uuid(uuid: &Uuid) -> crate::Result<()> {
@@ -0,0 +1,183 @@
+//! What changed in the updates available to a profile's modpack and mods
+use crate::config::MODRINTH_API_URL;
+use crate::prelude::{ProfilePathId, ProjectPathId};
+use crate::profile::get;
+use crate::state::{ModrinthVersion, ProjectMetadata};
+use crate::util::fetch::fetch_cached;
+use crate::State;
+use chrono::{DateTime, Utc};
+use futures::future;
+use serde::{Deserialize, Serialize};
+
+/// A version listed in the changelog of an update
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangelogEntry {
+    pub version_id: String,
+    pub version_number: String,
+    pub name: String,
+    pub date_published: DateTime<Utc>,
+    /// Markdown, with line endings and blank lines normalized. Unset for
+    /// versions published without one
+    pub changelog: Option<String>,
+    pub changelog_url: Option<String>,
+}
+
+/// What changed between the installed version of a modpack or mod and the
+/// version it can update to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChangelog {
+    /// Path of the mod in the profile, unset for the modpack itself
+    pub project_path: Option<ProjectPathId>,
+    pub project_id: String,
+    pub title: String,
+    pub current_version: String,
+    pub update_version: String,
+    /// Versions after the installed one up to the update, newest first
+    pub versions: Vec<ChangelogEntry>,
+}
+
+/// Gets the changelogs of every update found for a profile, the modpack's
+/// first. Version lists go through the HTTP cache, so opening the update
+/// dialog again doesn't download them again. Projects whose versions can't
+/// be fetched are left out
+#[tracing::instrument]
+pub async fn get_update_changelogs(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<UpdateChangelog>> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            .as_error()
+    })?;
+
+    let mut pending = Vec::new();
+    if let (Some(linked_data), Some(update_id)) = (
+        &profile.metadata.linked_data,
+        &profile.modrinth_update_version,
+    ) {
+        if let (Some(project_id), Some(version_id)) =
+            (&linked_data.project_id, &linked_data.version_id)
+        {
+            if version_id != update_id {
+                pending.push((
+                    None,
+                    project_id.clone(),
+                    profile.metadata.name.clone(),
+                    version_id.clone(),
+                    update_id.clone(),
+                ));
+            }
+        }
+    }
+
+    let mut mods = Vec::new();
+    for (path, project) in &profile.projects {
+        if let ProjectMetadata::Modrinth {
+            project,
+            version,
+            update_version: Some(update_version),
+            ..
+        } = &project.metadata
+        {
+            mods.push((
+                Some(path.clone()),
+                project.id.clone(),
+                project.title.clone(),
+                version.id.clone(),
+                update_version.id.clone(),
+            ));
+        }
+    }
+    mods.sort_by(|a, b| a.2.to_lowercase().cmp(&b.2.to_lowercase()));
+    pending.extend(mods);
+
+    let game_version = &profile.metadata.game_version;
+    let changelogs = future::join_all(pending.into_iter().map(
+        |(project_path, project_id, title, current_id, update_id)| async move {
+            let versions = match fetch_project_versions(&project_id).await {
+                Ok(versions) => versions,
+                Err(err) => {
+                    tracing::warn!(
+                        "Unable to fetch the changelog of {title}: {err}"
+                    );
+                    return None;
+                }
+            };
+            let current = versions.iter().find(|x| x.id == current_id);
+            let update = versions.iter().find(|x| x.id == update_id)?;
+
+            // Other branches of a project, such as versions for other game
+            // versions or loaders, aren't part of the update
+            let entries = versions
+                .iter()
+                .filter(|x| {
+                    x.date_published <= update.date_published
+                        && current.map_or(x.id == update.id, |current| {
+                            x.date_published > current.date_published
+                        })
+                        && (project_path.is_none()
+                            || x.game_versions.contains(game_version)
+                                && x.loaders
+                                    .iter()
+                                    .any(|x| update.loaders.contains(x)))
+                })
+                .map(|x| ChangelogEntry {
+                    version_id: x.id.clone(),
+                    version_number: x.version_number.clone(),
+                    name: x.name.clone(),
+                    date_published: x.date_published,
+                    changelog: normalize_markdown(&x.changelog),
+                    changelog_url: x.changelog_url.clone(),
+                })
+                .collect::<Vec<_>>();
+
+            Some(UpdateChangelog {
+                project_path,
+                project_id,
+                title,
+                current_version: current
+                    .map_or(current_id, |x| x.version_number.clone()),
+                update_version: update.version_number.clone(),
+                versions: entries,
+            })
+        },
+    ))
+    .await;
+
+    Ok(changelogs.into_iter().flatten().collect())
+}
+
+// Every version of a project, newest first as labrinth sorts them
+async fn fetch_project_versions(
+    project_id: &str,
+) -> crate::Result<Vec<ModrinthVersion>> {
+    let state = State::get().await?;
+    let bytes = fetch_cached(
+        &format!("{MODRINTH_API_URL}project/{project_id}/version"),
+        None,
+        &state.directories.http_cache_dir(),
+        &state.fetch_semaphore,
+        &state.io_semaphore,
+    )
+    .await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+// Changelogs are stored as their authors wrote them, with any line endings
+// and as many blank lines as they liked
+fn normalize_markdown(changelog: &str) -> Option<String> {
+    let changelog = changelog.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut normalized = String::new();
+    let mut previous_blank = false;
+    for line in changelog.lines().map(|x| x.trim_end()) {
+        if line.is_empty() && previous_blank {
+            continue;
+        }
+        previous_blank = line.is_empty();
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+
+    let normalized = normalized.trim();
+    (!normalized.is_empty()).then(|| normalized.to_string())
+}
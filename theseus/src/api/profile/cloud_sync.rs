@@ -0,0 +1,562 @@
+//! Two-way syncing of the worlds, settings and screenshots of profiles with a
+//! folder that a file syncing service keeps up to date between computers
+use crate::event::emit::emit_sync;
+use crate::event::SyncPayloadType;
+use crate::prelude::ProfilePathId;
+use crate::state::{CloudSync, Profile};
+use crate::util::fetch::sha1_async;
+use crate::util::io::{self, IOError};
+use crate::State;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+lazy_static! {
+    // Profiles being synced, as two syncs of a profile would copy over each
+    // other
+    static ref SYNCING: Mutex<HashSet<ProfilePathId>> =
+        Mutex::new(HashSet::new());
+}
+
+/// A file that changed both in the profile and in the sync folder since the
+/// last sync. Neither side is touched until the conflict is resolved
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncConflict {
+    /// Path of the file in the profile, with `/` separators
+    pub path: String,
+    /// Unset if the file was deleted from the profile
+    pub local_modified: Option<DateTime<Utc>>,
+    /// Unset if the file was deleted from the sync folder
+    pub remote_modified: Option<DateTime<Utc>>,
+}
+
+/// What a sync changed, by path in the profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SyncReport {
+    /// Files copied to, or deleted from, the sync folder
+    pub uploaded: Vec<String>,
+    /// Files copied to, or deleted from, the profile
+    pub downloaded: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// A side of a sync, the profile or the sync folder
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+// A file as it was on both sides after it was last synced. Modification
+// times tell whether the file changed since without hashing it again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SyncedFile {
+    sha1: String,
+    size: u64,
+    local_modified: DateTime<Utc>,
+    remote_modified: DateTime<Utc>,
+}
+
+// What a profile looked like when it was last synced from this computer,
+// kept apart from the profile so it isn't synced itself
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SyncState {
+    // Folder the profile was synced with, the state doesn't apply to others
+    remote: PathBuf,
+    files: HashMap<String, SyncedFile>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FoundFile {
+    size: u64,
+    modified: DateTime<Utc>,
+}
+
+// Removes a profile from those being synced once its sync ends
+struct Syncing(ProfilePathId);
+
+impl Syncing {
+    fn start(profile_path: &ProfilePathId) -> crate::Result<Self> {
+        if !SYNCING.lock().unwrap().insert(profile_path.clone()) {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "{profile_path} is already being synced"
+            ))
+            .into());
+        }
+        Ok(Syncing(profile_path.clone()))
+    }
+}
+
+impl Drop for Syncing {
+    fn drop(&mut self) {
+        SYNCING.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Opts a profile in to syncing, or out if unset. The remote name must be the
+/// same on every computer, and the folders are relative to the profile
+#[tracing::instrument]
+pub async fn set(
+    profile_path: &ProfilePathId,
+    sync: Option<CloudSync>,
+) -> crate::Result<()> {
+    if let Some(sync) = &sync {
+        sync.validate()?;
+    }
+
+    super::edit(profile_path, |prof| {
+        prof.cloud_sync = sync.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
+
+/// Syncs a profile with the sync folder both ways. Files changed on one side
+/// since the last sync are copied, or deleted, on the other. Files changed on
+/// both are reported as conflicts and left alone
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn sync(profile_path: &ProfilePathId) -> crate::Result<SyncReport> {
+    sync_profile(profile_path, None).await
+}
+
+/// Resolves a conflict by copying the file, or its deletion, from the side
+/// that is kept to the other
+#[tracing::instrument]
+pub async fn resolve_conflict(
+    profile_path: &ProfilePathId,
+    path: &str,
+    keep: SyncSide,
+) -> crate::Result<()> {
+    check_not_running(profile_path, None).await?;
+    let (profile, sync, local, remote) = resolve(profile_path).await?;
+    if !is_synced(path, &sync.folders) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{path} isn't synced"
+        ))
+        .into());
+    }
+    let _syncing = Syncing::start(profile_path)?;
+
+    let state = State::get().await?;
+    let mut sync_state = read_state(&state, &profile, &remote).await?;
+    let (from, to) = match keep {
+        SyncSide::Local => (&local, &remote),
+        SyncSide::Remote => (&remote, &local),
+    };
+    let source = from.join(path);
+    let sha1 = if source.is_file() {
+        Some(sha1_async(io::read(&source).await?.into()).await?)
+    } else {
+        None
+    };
+    apply(&state, path, from, to).await?;
+    record(&mut sync_state, path, sha1, &local, &remote).await?;
+    write_state(&state, &profile, &sync_state).await
+}
+
+/// Syncs a profile around its game running, if it opted in. Failures are only
+/// logged, so they don't keep the game from launching
+pub(crate) async fn sync_if_enabled(
+    profile_path: &ProfilePathId,
+    exited: Option<Uuid>,
+) {
+    let enabled = async {
+        let state = State::get().await?;
+        let folder_set = state.settings.read().await.sync_folder.is_some();
+        let profile = super::get(profile_path, None).await?;
+        Ok::<_, crate::Error>(
+            folder_set && profile.map_or(false, |x| x.cloud_sync.is_some()),
+        )
+    }
+    .await;
+
+    match enabled {
+        Ok(true) => {
+            if let Err(err) = sync_profile(profile_path, exited).await {
+                tracing::warn!("Unable to sync {profile_path}: {err}");
+            }
+        }
+        Ok(false) => {}
+        Err(err) => {
+            tracing::warn!("Unable to sync {profile_path}: {err}");
+        }
+    }
+}
+
+async fn sync_profile(
+    profile_path: &ProfilePathId,
+    exited: Option<Uuid>,
+) -> crate::Result<SyncReport> {
+    check_not_running(profile_path, exited).await?;
+    let (profile, sync, local, remote) = resolve(profile_path).await?;
+    let _syncing = Syncing::start(profile_path)?;
+    emit_sync(profile_path, SyncPayloadType::Started).await?;
+
+    let state = State::get().await?;
+    let mut sync_state = read_state(&state, &profile, &remote).await?;
+    sync_state
+        .files
+        .retain(|path, _| is_synced(path, &sync.folders));
+
+    // A missing folder would look like every file in it was deleted, so the
+    // files would be deleted on the other side too
+    if !remote.exists() && !sync_state.files.is_empty() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{} is missing from the sync folder",
+            sync.remote_name
+        ))
+        .into());
+    }
+
+    let mut local_files = HashMap::new();
+    let mut remote_files = HashMap::new();
+    for folder in &sync.folders {
+        collect_files(&local, &local.join(folder), &mut local_files).await?;
+        collect_files(&remote, &remote.join(folder), &mut remote_files).await?;
+    }
+
+    let paths = local_files
+        .keys()
+        .chain(remote_files.keys())
+        .chain(sync_state.files.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    let mut report = SyncReport::default();
+    // Files to copy or delete, with the side they are copied from
+    let mut changes = Vec::new();
+    for path in paths {
+        let base = sync_state.files.get(&path);
+        let local_file = local_files.get(&path).copied();
+        let remote_file = remote_files.get(&path).copied();
+        let local_sha1 = current_sha1(
+            &local,
+            &path,
+            local_file,
+            base.map(|x| (x.size, x.local_modified, x.sha1.as_str())),
+        )
+        .await?;
+        let remote_sha1 = current_sha1(
+            &remote,
+            &path,
+            remote_file,
+            base.map(|x| (x.size, x.remote_modified, x.sha1.as_str())),
+        )
+        .await?;
+        let base_sha1 = base.map(|x| x.sha1.clone());
+
+        if local_sha1 == remote_sha1 {
+            record(&mut sync_state, &path, local_sha1, &local, &remote).await?;
+        } else if local_sha1 == base_sha1 {
+            changes.push((path, SyncSide::Remote, remote_sha1));
+        } else if remote_sha1 == base_sha1 {
+            changes.push((path, SyncSide::Local, local_sha1));
+        } else {
+            emit_sync(
+                profile_path,
+                SyncPayloadType::Conflict { path: path.clone() },
+            )
+            .await?;
+            report.conflicts.push(SyncConflict {
+                path,
+                local_modified: local_file.map(|x| x.modified),
+                remote_modified: remote_file.map(|x| x.modified),
+            });
+        }
+    }
+
+    let total = changes.len();
+    for (done, (path, from, sha1)) in changes.into_iter().enumerate() {
+        match from {
+            SyncSide::Local => {
+                apply(&state, &path, &local, &remote).await?;
+                report.uploaded.push(path.clone());
+            }
+            SyncSide::Remote => {
+                apply(&state, &path, &remote, &local).await?;
+                report.downloaded.push(path.clone());
+            }
+        }
+        record(&mut sync_state, &path, sha1, &local, &remote).await?;
+        emit_sync(
+            profile_path,
+            SyncPayloadType::Progress {
+                done: done + 1,
+                total,
+            },
+        )
+        .await?;
+    }
+
+    write_state(&state, &profile, &sync_state).await?;
+    emit_sync(
+        profile_path,
+        SyncPayloadType::Finished {
+            uploaded: report.uploaded.len(),
+            downloaded: report.downloaded.len(),
+            conflicts: report.conflicts.len(),
+        },
+    )
+    .await?;
+    tracing::info!(
+        "Synced {profile_path}: {} uploaded, {} downloaded, {} conflicts",
+        report.uploaded.len(),
+        report.downloaded.len(),
+        report.conflicts.len()
+    );
+
+    Ok(report)
+}
+
+// Files the game has open could be copied halfway through being written, so
+// a profile isn't synced while its game runs. The game that just exited is
+// still counted as running while its session finishes
+async fn check_not_running(
+    profile_path: &ProfilePathId,
+    exited: Option<Uuid>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let running = state
+        .children
+        .read()
+        .await
+        .running_keys_with_profile(profile_path.clone())
+        .await?;
+    if running.iter().any(|x| Some(*x) != exited) {
+        return Err(crate::ErrorKind::LauncherError(format!(
+            "{profile_path} can't be synced while its game is running"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+// The profile, its sync settings, and the folders synced with each other
+async fn resolve(
+    profile_path: &ProfilePathId,
+) -> crate::Result<(Profile, CloudSync, PathBuf, PathBuf)> {
+    let profile = super::get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let sync = profile.cloud_sync.clone().ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "{profile_path} isn't set to sync"
+        ))
+    })?;
+
+    let state = State::get().await?;
+    let sync_folder = state
+        .settings
+        .read()
+        .await
+        .sync_folder
+        .clone()
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError("No sync folder is set".to_string())
+        })?;
+    if !sync_folder.is_dir() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Sync folder {} doesn't exist",
+            sync_folder.display()
+        ))
+        .into());
+    }
+
+    let local = profile_path.get_full_path().await?;
+    let remote = sync_folder.join(&sync.remote_name);
+    Ok((profile, sync, local, remote))
+}
+
+fn is_synced(path: &str, folders: &[String]) -> bool {
+    Path::new(path)
+        .components()
+        .all(|x| matches!(x, Component::Normal(_)))
+        && folders
+            .iter()
+            .any(|x| Path::new(path).starts_with(x.trim_end_matches('/')))
+}
+
+// The hash of a file on one side, or none if it's missing there. Files with
+// the size and modification time they were last synced with aren't hashed
+async fn current_sha1(
+    root: &Path,
+    path: &str,
+    found: Option<FoundFile>,
+    base: Option<(u64, DateTime<Utc>, &str)>,
+) -> crate::Result<Option<String>> {
+    let Some(found) = found else {
+        return Ok(None);
+    };
+    if let Some((size, modified, sha1)) = base {
+        if found.size == size && found.modified == modified {
+            return Ok(Some(sha1.to_string()));
+        }
+    }
+
+    let bytes = io::read(root.join(path)).await?;
+    Ok(Some(sha1_async(bytes.into()).await?))
+}
+
+// Copies a file from one side to the other, or deletes it from the other if
+// it is missing. Copies are written next to the file first, so the syncing
+// service never picks up half of one
+async fn apply(
+    state: &State,
+    path: &str,
+    from: &Path,
+    to: &Path,
+) -> crate::Result<()> {
+    let source = from.join(path);
+    let target = to.join(path);
+
+    if !source.is_file() {
+        if target.is_file() {
+            io::remove_file(&target).await?;
+        }
+        return Ok(());
+    }
+
+    let partial = target.with_file_name(format!(
+        ".{}.part",
+        target.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    crate::util::fetch::copy(&source, &partial, &state.io_semaphore).await?;
+    io::rename(&partial, &target).await?;
+    Ok(())
+}
+
+// Records a file as synced with the given hash, as it now is on both sides
+async fn record(
+    sync_state: &mut SyncState,
+    path: &str,
+    sha1: Option<String>,
+    local: &Path,
+    remote: &Path,
+) -> crate::Result<()> {
+    let found = (
+        sha1,
+        found_file(&local.join(path)).await?,
+        found_file(&remote.join(path)).await?,
+    );
+    match found {
+        (Some(sha1), Some(local_file), Some(remote_file)) => {
+            sync_state.files.insert(
+                path.to_string(),
+                SyncedFile {
+                    sha1,
+                    size: local_file.size,
+                    local_modified: local_file.modified,
+                    remote_modified: remote_file.modified,
+                },
+            );
+        }
+        _ => {
+            sync_state.files.remove(path);
+        }
+    }
+    Ok(())
+}
+
+async fn found_file(path: &Path) -> crate::Result<Option<FoundFile>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| IOError::with_path(e, path))?;
+    Ok(Some(FoundFile {
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .map_err(|e| IOError::with_path(e, path))?
+            .into(),
+    }))
+}
+
+#[async_recursion::async_recursion]
+async fn collect_files(
+    base: &Path,
+    dir: &Path,
+    files: &mut HashMap<String, FoundFile>,
+) -> crate::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut read_dir = io::read_dir(dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, dir))?
+    {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            collect_files(base, &path, files).await?;
+        } else if name.starts_with('.') && name.ends_with(".part") {
+            // Left by a copy that didn't finish
+            continue;
+        } else if let (Ok(relative_path), Some(found)) =
+            (path.strip_prefix(base), found_file(&path).await?)
+        {
+            let relative_path = relative_path
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative_path, found);
+        }
+    }
+    Ok(())
+}
+
+fn state_file(state: &State, profile: &Profile) -> PathBuf {
+    state
+        .directories
+        .sync_state_dir()
+        .join(format!("{}.json", profile.uuid))
+}
+
+// The state of the last sync with the folder. Syncing with another folder
+// starts over, as if the profile was never synced
+async fn read_state(
+    state: &State,
+    profile: &Profile,
+    remote: &Path,
+) -> crate::Result<SyncState> {
+    let path = state_file(state, profile);
+    let sync_state = if path.exists() {
+        serde_json::from_slice::<SyncState>(&io::read(&path).await?)?
+    } else {
+        SyncState::default()
+    };
+
+    if sync_state.remote == remote {
+        Ok(sync_state)
+    } else {
+        Ok(SyncState {
+            remote: remote.to_path_buf(),
+            files: HashMap::new(),
+        })
+    }
+}
+
+async fn write_state(
+    state: &State,
+    profile: &Profile,
+    sync_state: &SyncState,
+) -> crate::Result<()> {
+    crate::util::fetch::write(
+        &state_file(state, profile),
+        &serde_json::to_vec(sync_state)?,
+        &state.io_semaphore,
+    )
+    .await
+}
@@ -0,0 +1,747 @@
+//! Pre-launch compatibility checks between the mods of a profile
+use crate::prelude::{ProfilePathId, ProjectPathId};
+use crate::state::{
+    DependencyType, ModLoader, Profile, ProjectMetadata, ProjectType,
+};
+use async_zip::tokio::read::fs::ZipFileReader;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A problem found between the enabled mods of a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompatibilityIssue {
+    /// Several files declare the same mod id
+    DuplicateModId {
+        mod_id: String,
+        paths: Vec<ProjectPathId>,
+    },
+    /// A mod was built for a different loader than the profile uses
+    LoaderMismatch {
+        path: ProjectPathId,
+        mod_loader: ModLoader,
+        profile_loader: ModLoader,
+    },
+    /// A mod declares that it breaks or conflicts with a present mod, whose
+    /// version is in the declared range
+    Conflict {
+        path: ProjectPathId,
+        mod_id: String,
+        conflicts_with: String,
+        versions: String,
+        /// Whether the game is expected to refuse to start ("breaks"), rather
+        /// than merely misbehave ("conflicts")
+        breaking: bool,
+    },
+    /// A mod declares that it breaks or conflicts with some versions of a
+    /// present mod, but the present mod's version or the declared range
+    /// couldn't be read to tell whether it's one of them. Never breaking
+    UnverifiedConflict {
+        path: ProjectPathId,
+        mod_id: String,
+        conflicts_with: String,
+        versions: String,
+    },
+}
+
+/// Result of checking every enabled mod of a profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompatibilityReport {
+    pub issues: Vec<CompatibilityIssue>,
+}
+
+// A declared incompatibility with another mod
+struct DeclaredConflict {
+    mod_id: String,
+    versions: VersionRange,
+    breaking: bool,
+}
+
+// Metadata read from the loader manifest of a mod jar
+struct ModDescriptor {
+    path: ProjectPathId,
+    mod_ids: Vec<String>,
+    // Unknown when the manifest leaves it to be filled in at build time
+    version: Option<String>,
+    loader: ModLoader,
+    conflicts: Vec<DeclaredConflict>,
+    // Mod ids this mod refuses to load without
+    depends: Vec<String>,
+}
+
+/// Reads the loader manifests of every enabled mod in a profile and reports
+/// duplicated mod ids, mods built for another loader, and declared conflicts
+#[tracing::instrument(skip(profile))]
+#[theseus_macros::debug_pin]
+pub async fn check_profile(
+    profile: &Profile,
+) -> crate::Result<CompatibilityReport> {
+    let profile_path: ProfilePathId = profile.profile_id();
+    let full_path = profile_path.get_full_path().await?;
+
+    let mut mods = Vec::new();
+    for (project_path, project) in &profile.projects {
+        let is_mod =
+            ProjectType::get_from_parent_folder(project_path.0.clone())
+                .map_or(false, |x| matches!(x, ProjectType::Mod));
+        if project.disabled || !is_mod {
+            continue;
+        }
+
+        if let Some(descriptor) =
+            read_descriptor(&full_path.join(&project_path.0), project_path)
+                .await?
+        {
+            mods.push(descriptor);
+        }
+    }
+
+    let mut report = CompatibilityReport::default();
+
+    let mut by_id: HashMap<&str, Vec<&ModDescriptor>> = HashMap::new();
+    for descriptor in &mods {
+        for mod_id in &descriptor.mod_ids {
+            by_id.entry(mod_id.as_str()).or_default().push(descriptor);
+        }
+    }
+
+    let mut duplicates = by_id
+        .iter()
+        .filter(|(_, x)| x.len() > 1)
+        .map(|(mod_id, x)| CompatibilityIssue::DuplicateModId {
+            mod_id: mod_id.to_string(),
+            paths: x.iter().map(|x| x.path.clone()).collect(),
+        })
+        .collect::<Vec<_>>();
+    duplicates.sort_by_key(|x| match x {
+        CompatibilityIssue::DuplicateModId { mod_id, .. } => mod_id.clone(),
+        _ => String::new(),
+    });
+    report.issues.extend(duplicates);
+
+    let profile_loader = profile.metadata.loader;
+    for descriptor in &mods {
+        // Quilt loads Fabric mods as well as its own, and NeoForge still
+        // loads Forge mods
+        let supported = descriptor.loader == profile_loader
+            || (profile_loader == ModLoader::Quilt
+                && descriptor.loader == ModLoader::Fabric)
+            || (profile_loader == ModLoader::NeoForge
+                && descriptor.loader == ModLoader::Forge);
+        if !supported {
+            report.issues.push(CompatibilityIssue::LoaderMismatch {
+                path: descriptor.path.clone(),
+                mod_loader: descriptor.loader,
+                profile_loader,
+            });
+        }
+
+        let mod_id = descriptor.mod_ids.first().cloned().unwrap_or_default();
+        for conflict in &descriptor.conflicts {
+            let Some(present) = by_id.get(conflict.mod_id.as_str()) else {
+                continue;
+            };
+            // Any of the present mods providing the id being in the range
+            // is a conflict, while one that can't be told is unverified
+            let matches = present
+                .iter()
+                .map(|x| {
+                    x.version
+                        .as_deref()
+                        .and_then(|version| conflict.versions.matches(version))
+                })
+                .collect::<Vec<_>>();
+            let issue = if matches.contains(&Some(true)) {
+                CompatibilityIssue::Conflict {
+                    path: descriptor.path.clone(),
+                    mod_id: mod_id.clone(),
+                    conflicts_with: conflict.mod_id.clone(),
+                    versions: conflict.versions.to_string(),
+                    breaking: conflict.breaking,
+                }
+            } else if matches.contains(&None) {
+                CompatibilityIssue::UnverifiedConflict {
+                    path: descriptor.path.clone(),
+                    mod_id: mod_id.clone(),
+                    conflicts_with: conflict.mod_id.clone(),
+                    versions: conflict.versions.to_string(),
+                }
+            } else {
+                continue;
+            };
+            report.issues.push(issue);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Maps every mod of a profile, enabled or not, to the other installed mods
+/// it requires, going by its loader manifest and its Modrinth version
+pub(super) async fn required_projects(
+    profile: &Profile,
+) -> crate::Result<HashMap<ProjectPathId, Vec<ProjectPathId>>> {
+    let full_path = profile.get_profile_full_path().await?;
+
+    let mut descriptors = HashMap::new();
+    for project_path in profile.projects.keys() {
+        let is_mod =
+            ProjectType::get_from_parent_folder(project_path.0.clone())
+                .map_or(false, |x| matches!(x, ProjectType::Mod));
+        if !is_mod {
+            continue;
+        }
+
+        if let Some(descriptor) =
+            read_descriptor(&full_path.join(&project_path.0), project_path)
+                .await?
+        {
+            descriptors.insert(project_path.clone(), descriptor);
+        }
+    }
+
+    let by_mod_id = descriptors
+        .values()
+        .flat_map(|x| x.mod_ids.iter().map(move |id| (id.as_str(), &x.path)))
+        .collect::<HashMap<_, _>>();
+    let by_project_id = profile
+        .projects
+        .iter()
+        .filter_map(|(path, project)| match &project.metadata {
+            ProjectMetadata::Modrinth { project, .. } => {
+                Some((project.id.as_str(), path))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut required = HashMap::new();
+    for (project_path, project) in &profile.projects {
+        let mut dependencies = descriptors
+            .get(project_path)
+            .into_iter()
+            .flat_map(|x| x.depends.iter())
+            .filter_map(|x| by_mod_id.get(x.as_str()))
+            .copied()
+            .collect::<Vec<_>>();
+        if let ProjectMetadata::Modrinth { version, .. } = &project.metadata {
+            dependencies.extend(
+                version
+                    .dependencies
+                    .iter()
+                    .filter(|x| {
+                        matches!(x.dependency_type, DependencyType::Required)
+                    })
+                    .filter_map(|x| x.project_id.as_deref())
+                    .filter_map(|x| by_project_id.get(x))
+                    .copied(),
+            );
+        }
+
+        let mut dependencies = dependencies
+            .into_iter()
+            .filter(|x| *x != project_path)
+            .cloned()
+            .collect::<Vec<_>>();
+        if dependencies.is_empty() {
+            continue;
+        }
+        dependencies.sort_by(|a, b| a.0.cmp(&b.0));
+        dependencies.dedup();
+        required.insert(project_path.clone(), dependencies);
+    }
+
+    Ok(required)
+}
+
+// Reads a manifest file out of a jar, if present
+async fn read_zip_entry(
+    reader: &ZipFileReader,
+    name: &str,
+) -> crate::Result<Option<String>> {
+    let Some(index) = reader
+        .file()
+        .entries()
+        .iter()
+        .position(|f| f.entry().filename() == name) else {
+        return Ok(None);
+    };
+
+    let file = reader.file().entries().get(index).unwrap();
+    let mut file_str = String::new();
+    if reader
+        .entry(index)
+        .await?
+        .read_to_string_checked(&mut file_str, file.entry())
+        .await
+        .is_ok()
+    {
+        Ok(Some(file_str))
+    } else {
+        Ok(None)
+    }
+}
+
+async fn read_descriptor(
+    path: &Path,
+    project_path: &ProjectPathId,
+) -> crate::Result<Option<ModDescriptor>> {
+    let Ok(reader) = ZipFileReader::new(path.to_path_buf()).await else {
+        return Ok(None);
+    };
+
+    // Quilt
+    if let Some(file_str) = read_zip_entry(&reader, "quilt.mod.json").await? {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&file_str) {
+            let loader = &json["quilt_loader"];
+            let mut conflicts = Vec::new();
+            for (key, breaking) in [("breaks", true), ("conflicts", false)] {
+                for entry in loader[key].as_array().into_iter().flatten() {
+                    let (mod_id, versions) = match entry {
+                        serde_json::Value::String(id) => {
+                            (id.clone(), serde_json::json!("*"))
+                        }
+                        value => (
+                            value["id"]
+                                .as_str()
+                                .unwrap_or_default()
+                                .to_string(),
+                            value
+                                .get("versions")
+                                .cloned()
+                                .unwrap_or_else(|| serde_json::json!("*")),
+                        ),
+                    };
+                    conflicts.push(DeclaredConflict {
+                        mod_id,
+                        versions: VersionRange::Predicates(versions),
+                        breaking,
+                    });
+                }
+            }
+
+            let depends = loader["depends"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| match entry {
+                    serde_json::Value::String(id) => Some(id.clone()),
+                    value if value["optional"].as_bool() != Some(true) => {
+                        value["id"].as_str().map(|x| x.to_string())
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            if let Some(id) = loader["id"].as_str() {
+                return Ok(Some(ModDescriptor {
+                    path: project_path.clone(),
+                    mod_ids: vec![id.to_string()],
+                    version: loader["version"].as_str().map(|x| x.to_string()),
+                    loader: ModLoader::Quilt,
+                    conflicts,
+                    depends,
+                }));
+            }
+        }
+    }
+
+    // Fabric
+    if let Some(file_str) = read_zip_entry(&reader, "fabric.mod.json").await? {
+        #[derive(Deserialize)]
+        struct FabricMod {
+            id: String,
+            version: Option<String>,
+            #[serde(default)]
+            provides: Vec<String>,
+            #[serde(default)]
+            breaks: HashMap<String, serde_json::Value>,
+            #[serde(default)]
+            conflicts: HashMap<String, serde_json::Value>,
+            #[serde(default)]
+            depends: HashMap<String, serde_json::Value>,
+        }
+
+        if let Ok(fabric) = serde_json::from_str::<FabricMod>(&file_str) {
+            let conflicts = fabric
+                .breaks
+                .iter()
+                .map(|x| (x, true))
+                .chain(fabric.conflicts.iter().map(|x| (x, false)))
+                .map(|((mod_id, versions), breaking)| DeclaredConflict {
+                    mod_id: mod_id.clone(),
+                    versions: VersionRange::Predicates(versions.clone()),
+                    breaking,
+                })
+                .collect();
+
+            return Ok(Some(ModDescriptor {
+                path: project_path.clone(),
+                mod_ids: std::iter::once(fabric.id)
+                    .chain(fabric.provides)
+                    .collect(),
+                version: fabric.version,
+                loader: ModLoader::Fabric,
+                conflicts,
+                depends: fabric.depends.into_keys().collect(),
+            }));
+        }
+    }
+
+    // Forge, and NeoForge which renamed the file in later versions
+    for (file_name, loader) in [
+        ("META-INF/neoforge.mods.toml", ModLoader::NeoForge),
+        ("META-INF/mods.toml", ModLoader::Forge),
+    ] {
+        let Some(file_str) = read_zip_entry(&reader, file_name).await? else {
+            continue;
+        };
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeModInfo {
+            mods: Vec<ForgeMod>,
+            #[serde(default)]
+            dependencies: HashMap<String, Vec<ForgeDependency>>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeMod {
+            mod_id: String,
+            version: Option<String>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeDependency {
+            mod_id: String,
+            version_range: Option<String>,
+            #[serde(rename = "type")]
+            kind: Option<String>,
+            // Used before `type` replaced it
+            mandatory: Option<bool>,
+        }
+
+        if let Ok(forge) = toml::from_str::<ForgeModInfo>(&file_str) {
+            let dependencies = forge
+                .dependencies
+                .into_values()
+                .flatten()
+                .collect::<Vec<_>>();
+            let depends = dependencies
+                .iter()
+                .filter(|x| match x.kind.as_deref() {
+                    Some(kind) => kind == "required",
+                    None => x.mandatory.unwrap_or(false),
+                })
+                .map(|x| x.mod_id.clone())
+                .collect();
+            let conflicts = dependencies
+                .into_iter()
+                .filter_map(|x| {
+                    let breaking = match x.kind.as_deref() {
+                        Some("incompatible") => true,
+                        Some("discouraged") => false,
+                        _ => return None,
+                    };
+                    Some(DeclaredConflict {
+                        mod_id: x.mod_id,
+                        versions: VersionRange::Maven(
+                            x.version_range.unwrap_or_default(),
+                        ),
+                        breaking,
+                    })
+                })
+                .collect();
+
+            // The version is usually filled in from the jar's manifest
+            let version = forge.mods.first().and_then(|x| x.version.clone());
+            let version = match version {
+                Some(version) if version == "${file.jarVersion}" => {
+                    let manifest =
+                        read_zip_entry(&reader, "META-INF/MANIFEST.MF").await?;
+                    manifest.and_then(|manifest| {
+                        manifest.lines().find_map(|line| {
+                            line.strip_prefix("Implementation-Version:")
+                                .map(|x| x.trim().to_string())
+                        })
+                    })
+                }
+                Some(version) if version.contains("${") => None,
+                version => version,
+            };
+
+            return Ok(Some(ModDescriptor {
+                path: project_path.clone(),
+                mod_ids: forge.mods.into_iter().map(|x| x.mod_id).collect(),
+                version,
+                loader,
+                conflicts,
+                depends,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+// The versions of a mod a declared conflict applies to
+enum VersionRange {
+    // Fabric and Quilt predicates, such as `>=1.2 <2`. A list matches if
+    // any of its predicates does
+    Predicates(serde_json::Value),
+    // A Maven range used by Forge, such as `[1.2,2)`
+    Maven(String),
+}
+
+impl VersionRange {
+    // Whether a version is in the range, or None if the range couldn't be
+    // read
+    fn matches(&self, version: &str) -> Option<bool> {
+        match self {
+            VersionRange::Predicates(value) => predicates_match(value, version),
+            VersionRange::Maven(range) => maven_range_matches(range, version),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionRange::Predicates(serde_json::Value::String(x)) => {
+                write!(f, "{x}")
+            }
+            VersionRange::Predicates(value) => write!(f, "{value}"),
+            VersionRange::Maven(range) if range.is_empty() => write!(f, "*"),
+            VersionRange::Maven(range) => write!(f, "{range}"),
+        }
+    }
+}
+
+// Compares versions by their dot separated parts, numerically where both
+// are numbers. Build metadata is ignored, and pre-releases come before their
+// release
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.split('+').next().unwrap_or_default();
+        match version.split_once('-') {
+            Some((release, pre)) => (release, Some(pre)),
+            None => (version, None),
+        }
+    }
+
+    let (a_release, a_pre) = split(a.trim());
+    let (b_release, b_pre) = split(b.trim());
+    let mut a_parts = a_release.split('.');
+    let mut b_parts = b_release.split('.');
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => break,
+            (a, b) => {
+                let (a, b) = (a.unwrap_or("0"), b.unwrap_or("0"));
+                match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.cmp(b),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(b),
+    }
+}
+
+// Evaluates Fabric and Quilt version predicates, a string of space separated
+// predicates that must all match, or a list of which any must
+fn predicates_match(value: &serde_json::Value, version: &str) -> Option<bool> {
+    match value {
+        serde_json::Value::String(predicates) => {
+            for predicate in predicates.split_whitespace() {
+                if !predicate_matches(predicate, version)? {
+                    return Some(false);
+                }
+            }
+            Some(true)
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                if predicates_match(value, version)? {
+                    return Some(true);
+                }
+            }
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+fn predicate_matches(predicate: &str, version: &str) -> Option<bool> {
+    if predicate == "*" {
+        return Some(true);
+    }
+
+    let ordering = |x: &str| compare_versions(version, x);
+    for (operator, accepts) in [
+        (">=", &[Ordering::Greater, Ordering::Equal][..]),
+        ("<=", &[Ordering::Less, Ordering::Equal][..]),
+        (">", &[Ordering::Greater][..]),
+        ("<", &[Ordering::Less][..]),
+        ("=", &[Ordering::Equal][..]),
+    ] {
+        if let Some(bound) = predicate.strip_prefix(operator) {
+            return Some(accepts.contains(&ordering(bound)));
+        }
+    }
+
+    // `~1.2.3` allows later patches and `^1.2.3` later minor versions
+    for (operator, kept) in [("~", 2), ("^", 1)] {
+        if let Some(bound) = predicate.strip_prefix(operator) {
+            let mut upper = bound
+                .split('.')
+                .take(kept)
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>();
+            let last = upper.last_mut()?;
+            *last = (last.parse::<u64>().ok()? + 1).to_string();
+            return Some(
+                ordering(bound) != Ordering::Less
+                    && ordering(&upper.join(".")) == Ordering::Less,
+            );
+        }
+    }
+
+    // `1.2.x` matches any version starting with `1.2`
+    if let Some(prefix) = predicate
+        .strip_suffix(".x")
+        .or_else(|| predicate.strip_suffix(".X"))
+        .or_else(|| predicate.strip_suffix(".*"))
+    {
+        let version = version.split(['-', '+']).next().unwrap_or_default();
+        return Some(
+            version == prefix || version.starts_with(&format!("{prefix}.")),
+        );
+    }
+
+    Some(ordering(predicate) == Ordering::Equal)
+}
+
+// Evaluates a Maven range, such as `[1.2,2)` or `(,1.0],[1.2,)`. A version
+// outside brackets is only a recommendation, which any version satisfies
+fn maven_range_matches(range: &str, version: &str) -> Option<bool> {
+    let range = range.trim();
+    if range.is_empty() || !range.starts_with(['[', '(']) {
+        return Some(true);
+    }
+
+    let mut rest = range;
+    while !rest.is_empty() {
+        let end = rest.find([']', ')'])?;
+        let (restriction, tail) = rest.split_at(end + 1);
+        let inclusive_lower = restriction.starts_with('[');
+        let inclusive_upper = restriction.ends_with(']');
+        let bounds = &restriction[1..restriction.len() - 1];
+
+        let matches = match bounds.split_once(',') {
+            Some((lower, upper)) => {
+                let (lower, upper) = (lower.trim(), upper.trim());
+                let above = lower.is_empty()
+                    || match compare_versions(version, lower) {
+                        Ordering::Greater => true,
+                        Ordering::Equal => inclusive_lower,
+                        Ordering::Less => false,
+                    };
+                let below = upper.is_empty()
+                    || match compare_versions(version, upper) {
+                        Ordering::Less => true,
+                        Ordering::Equal => inclusive_upper,
+                        Ordering::Greater => false,
+                    };
+                above && below
+            }
+            None => compare_versions(version, bounds) == Ordering::Equal,
+        };
+        if matches {
+            return Some(true);
+        }
+
+        rest = tail.trim_start().strip_prefix(',').unwrap_or(tail).trim();
+        if !rest.is_empty() && !rest.starts_with(['[', '(']) {
+            return None;
+        }
+    }
+    Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn predicates(range: serde_json::Value, version: &str) -> Option<bool> {
+        VersionRange::Predicates(range).matches(version)
+    }
+
+    fn maven(range: &str, version: &str) -> Option<bool> {
+        VersionRange::Maven(range.to_string()).matches(version)
+    }
+
+    #[test]
+    fn versions_compare_by_parts() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.0+mc1.20", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.0-beta.1", "1.2.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn fabric_predicates() {
+        let cases = [
+            (serde_json::json!("*"), "0.1.0", Some(true)),
+            (serde_json::json!(">=1.2.0"), "1.2.0", Some(true)),
+            (serde_json::json!(">=1.2.0"), "1.1.9", Some(false)),
+            (serde_json::json!(">=1.2 <2"), "1.9", Some(true)),
+            (serde_json::json!(">=1.2 <2"), "2.0.1", Some(false)),
+            (serde_json::json!("~1.2.3"), "1.2.9", Some(true)),
+            (serde_json::json!("~1.2.3"), "1.3.0", Some(false)),
+            (serde_json::json!("^1.2.3"), "1.9.0", Some(true)),
+            (serde_json::json!("^1.2.3"), "2.0.0", Some(false)),
+            (serde_json::json!("1.2.x"), "1.2.7", Some(true)),
+            (serde_json::json!("1.2.x"), "1.20.0", Some(false)),
+            (serde_json::json!("1.2.3"), "1.2.3", Some(true)),
+            (serde_json::json!(["<1", ">=3"]), "3.1", Some(true)),
+            (serde_json::json!(["<1", ">=3"]), "2", Some(false)),
+            (serde_json::json!({ "any": ["<1"] }), "0.5", None),
+        ];
+        for (range, version, expected) in cases {
+            assert_eq!(predicates(range.clone(), version), expected, "{range}");
+        }
+    }
+
+    #[test]
+    fn maven_ranges() {
+        let cases = [
+            ("", "1.0", Some(true)),
+            ("1.0", "0.1", Some(true)),
+            ("[1.0,2.0)", "1.0", Some(true)),
+            ("[1.0,2.0)", "2.0", Some(false)),
+            ("(1.0,2.0]", "1.0", Some(false)),
+            ("(1.0,2.0]", "2.0", Some(true)),
+            ("[1.2,)", "5", Some(true)),
+            ("(,1.0]", "1.1", Some(false)),
+            ("[1.5]", "1.5", Some(true)),
+            ("(,1.0],[1.2,)", "1.3", Some(true)),
+            ("(,1.0],[1.2,)", "1.1", Some(false)),
+            ("[1.0,2.0", "1.5", None),
+        ];
+        for (range, version, expected) in cases {
+            assert_eq!(maven(range, version), expected, "{range}");
+        }
+    }
+}
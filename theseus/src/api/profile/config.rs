@@ -0,0 +1,399 @@
+//! Reading and writing the config files of a profile in the format they are
+//! in, so front ends can build settings editors without their own parsers
+use crate::prelude::ProfilePathId;
+use crate::state::game_options::{apply_options, read_options};
+use crate::state::OptionValue;
+use crate::util::io::{self, IOError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+const CONFIG_FOLDER: &str = "config";
+
+/// Formats config files are read and written in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    /// Java properties, read as a flat table of strings
+    Properties,
+    Yaml,
+    /// Any other format, read and written as plain text
+    Text,
+}
+
+impl ConfigFormat {
+    /// Format of a config file, from its extension
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            Some("properties") => ConfigFormat::Properties,
+            Some("yml" | "yaml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Text,
+        }
+    }
+
+    /// Parses the contents of a file in this format
+    pub fn parse(self, text: &str) -> crate::Result<Value> {
+        let value =
+            match self {
+                ConfigFormat::Toml => {
+                    let value = toml::from_str::<toml::Value>(text)
+                        .map_err(|e| config_error(self, e))?;
+                    serde_json::to_value(value)?
+                }
+                ConfigFormat::Json => serde_json::from_str(text)
+                    .map_err(|e| config_error(self, e))?,
+                ConfigFormat::Properties => Value::Object(
+                    parse_properties(text)
+                        .into_iter()
+                        .map(|(key, value)| (key, Value::String(value)))
+                        .collect(),
+                ),
+                ConfigFormat::Yaml => serde_yaml::from_str(text)
+                    .map_err(|e| config_error(self, e))?,
+                ConfigFormat::Text => Value::String(text.to_string()),
+            };
+        Ok(value)
+    }
+
+    /// Writes a value out in this format. Properties files keep the comments
+    /// and order of keys of their previous contents
+    pub fn write(self, value: &Value, previous: &str) -> crate::Result<String> {
+        let text = match (self, value) {
+            (ConfigFormat::Toml, _) => toml::to_string_pretty(value)
+                .map_err(|e| config_error(self, e))?,
+            (ConfigFormat::Json, _) => serde_json::to_string_pretty(value)?,
+            (ConfigFormat::Properties, Value::Object(values)) => {
+                write_properties(previous, values)
+            }
+            (ConfigFormat::Yaml, _) => serde_yaml::to_string(value)
+                .map_err(|e| config_error(self, e))?,
+            (ConfigFormat::Text, Value::String(text)) => text.clone(),
+            _ => {
+                return Err(crate::ErrorKind::ConfigError(format!(
+                    "{self:?} files can't hold {value}"
+                ))
+                .into())
+            }
+        };
+        Ok(text)
+    }
+}
+
+fn config_error(
+    format: ConfigFormat,
+    err: impl std::fmt::Display,
+) -> crate::Error {
+    crate::ErrorKind::ConfigError(format!("{format:?}: {err}")).into()
+}
+
+/// A file in the config folder of a profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigFile {
+    /// Path inside the config folder, separated by `/`
+    pub path: String,
+    pub format: ConfigFormat,
+    pub size: u64,
+}
+
+/// The contents of a config file, parsed as its format. Text files are a
+/// string, properties files an object of strings
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigDocument {
+    pub format: ConfigFormat,
+    pub value: Value,
+}
+
+/// Lists every file in the config folder of a profile
+#[tracing::instrument]
+pub async fn list(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<ConfigFile>> {
+    let config_dir = profile_path.get_full_path().await?.join(CONFIG_FOLDER);
+    if !config_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    super::build_folder(&config_dir, &mut paths).await?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let size = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| IOError::with_path(e, &path))?
+            .len();
+        files.push(ConfigFile {
+            path: path
+                .strip_prefix(&config_dir)?
+                .to_string_lossy()
+                .replace('\\', "/"),
+            format: ConfigFormat::from_path(&path),
+            size,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(files)
+}
+
+/// Reads a file of the config folder of a profile, parsed as its format
+#[tracing::instrument]
+pub async fn read(
+    profile_path: &ProfilePathId,
+    path: &str,
+) -> crate::Result<ConfigDocument> {
+    let full_path = config_file_path(profile_path, path).await?;
+    let format = ConfigFormat::from_path(&full_path);
+    let text = io::read_to_string(&full_path).await?;
+
+    Ok(ConfigDocument {
+        format,
+        value: format.parse(&text)?,
+    })
+}
+
+/// Writes a file of the config folder of a profile in its format, creating
+/// it if it doesn't exist
+#[tracing::instrument(skip(value))]
+pub async fn write(
+    profile_path: &ProfilePathId,
+    path: &str,
+    value: &Value,
+) -> crate::Result<()> {
+    let full_path = config_file_path(profile_path, path).await?;
+    let previous = if full_path.exists() {
+        io::read_to_string(&full_path).await?
+    } else {
+        String::new()
+    };
+
+    let text = ConfigFormat::from_path(&full_path).write(value, &previous)?;
+    if let Some(parent) = full_path.parent() {
+        io::create_dir_all(parent).await?;
+    }
+    io::write(&full_path, text).await?;
+
+    Ok(())
+}
+
+/// Reads the game's settings in the options.txt of a profile, as written
+#[tracing::instrument]
+pub async fn get_game_options(
+    profile_path: &ProfilePathId,
+) -> crate::Result<HashMap<String, String>> {
+    let options = read_options(&profile_path.get_full_path().await?).await?;
+    Ok(options
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// Reads the game's settings in the options.txt of a profile as typed values
+#[tracing::instrument]
+pub async fn get_typed_game_options(
+    profile_path: &ProfilePathId,
+) -> crate::Result<BTreeMap<String, OptionValue>> {
+    Ok(read_options(&profile_path.get_full_path().await?)
+        .await?
+        .typed())
+}
+
+/// Sets some of the game's settings in the options.txt of a profile, leaving
+/// the others as they are
+#[tracing::instrument]
+pub async fn set_game_options(
+    profile_path: &ProfilePathId,
+    options: HashMap<String, String>,
+) -> crate::Result<()> {
+    let options = options.into_iter().collect::<Vec<_>>();
+    apply_options(&profile_path.get_full_path().await?, &options).await?;
+    Ok(())
+}
+
+// Resolves a path inside the config folder of a profile, refusing any that
+// would lead out of it
+async fn config_file_path(
+    profile_path: &ProfilePathId,
+    path: &str,
+) -> crate::Result<PathBuf> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|x| matches!(x, Component::Normal(_)))
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{path} is not a path inside the config folder"
+        ))
+        .into());
+    }
+
+    Ok(profile_path
+        .get_full_path()
+        .await?
+        .join(CONFIG_FOLDER)
+        .join(relative))
+}
+
+/// Reads the keys and values of a Java properties file, in order
+pub fn parse_properties(text: &str) -> Vec<(String, String)> {
+    logical_lines(text)
+        .into_iter()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with(['#', '!']) {
+                return None;
+            }
+            split_property(line)
+                .map(|(key, value)| (key, unescape_property(value)))
+        })
+        .collect()
+}
+
+// Rewrites a properties file with new values, keeping its comments and the
+// place of the keys it already had. Keys missing from the values are removed
+fn write_properties(previous: &str, values: &Map<String, Value>) -> String {
+    let mut written = Vec::new();
+    let mut lines = Vec::new();
+    for line in logical_lines(previous) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+            lines.push(line);
+            continue;
+        }
+        let Some((key, _)) = split_property(trimmed) else {
+            continue;
+        };
+        if let Some(value) = values.get(&key) {
+            if !written.contains(&key) {
+                lines.push(property_line(&key, value));
+                written.push(key);
+            }
+        }
+    }
+    for (key, value) in values {
+        if !written.contains(key) {
+            lines.push(property_line(key, value));
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+fn property_line(key: &str, value: &Value) -> String {
+    let value = match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+    format!(
+        "{}={}",
+        escape_property(key, true),
+        escape_property(&value, false)
+    )
+}
+
+// Joins the lines of a properties file continued by a trailing backslash
+fn logical_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        let line = match current.take() {
+            Some(previous) => previous + line.trim_start(),
+            None => line.to_string(),
+        };
+        let trailing = line.len() - line.trim_end_matches('\\').len();
+        if trailing % 2 == 1 {
+            current = Some(line[..line.len() - 1].to_string());
+        } else {
+            lines.push(line);
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+// Keys end at the first unescaped `=`, `:` or whitespace
+fn split_property(line: &str) -> Option<(String, &str)> {
+    let mut key = String::new();
+    let mut chars = line.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => key.extend(chars.next().map(|(_, c)| c)),
+            '=' | ':' => return Some((key, line[index + 1..].trim())),
+            c if c.is_whitespace() => {
+                let value = line[index..].trim_start();
+                let value = value.strip_prefix(['=', ':']).unwrap_or(value);
+                return Some((key, value.trim()));
+            }
+            c => key.push(c),
+        }
+    }
+    (!key.is_empty()).then_some((key, ""))
+}
+
+// Undoes the escaping of Java properties files, which writes URLs as
+// `https\://example.com`
+fn unescape_property(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('u') => {
+                let code = chars.by_ref().take(4).collect::<String>();
+                if let Some(c) =
+                    u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)
+                {
+                    unescaped.push(c);
+                }
+            }
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+// Escapes what Java would read differently, in keys also the characters
+// ending them
+fn escape_property(text: &str, key: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '=' | ':' if key => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' ' if key || index == 0 => escaped.push_str("\\ "),
+            '#' | '!' if key && index == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
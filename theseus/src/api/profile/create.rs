@@ -13,7 +13,7 @@ pub use crate::{
     State,
 };
 use daedalus::modded::LoaderVersion;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use tracing::{info, trace};
 use uuid::Uuid;
@@ -39,29 +39,9 @@ pub async fn profile_create(
     let state = State::get().await?;
     let uuid = Uuid::new_v4();
 
-    let mut path = state.directories.profiles_dir().await.join(&name);
-
-    if path.exists() {
-        let mut new_name;
-        let mut new_path;
-        let mut which = 1;
-        loop {
-            new_name = format!("{name} ({which})");
-            new_path = state.directories.profiles_dir().await.join(&new_name);
-            if !new_path.exists() {
-                break;
-            }
-            which += 1;
-        }
-
-        tracing::debug!(
-            "Folder collision: {}, renaming to: {}",
-            path.display(),
-            new_path.display()
-        );
-        path = new_path;
-        name = new_name;
-    }
+    let profiles_dir = state.directories.profiles_dir().await;
+    let (unused_name, path) = get_unused_profile_path(name, &profiles_dir);
+    name = unused_name;
     io::create_dir_all(&path).await?;
 
     info!(
@@ -134,6 +114,33 @@ pub async fn profile_create(
     }
 }
 
+// Finds a profile folder that is not already taken, appending a counter to
+// the name on collisions. Returns the final name and its full path
+pub(crate) fn get_unused_profile_path(
+    name: String,
+    profiles_dir: &Path,
+) -> (String, PathBuf) {
+    let path = profiles_dir.join(&name);
+    if !path.exists() {
+        return (name, path);
+    }
+
+    let mut which = 1;
+    loop {
+        let new_name = format!("{name} ({which})");
+        let new_path = profiles_dir.join(&new_name);
+        if !new_path.exists() {
+            tracing::debug!(
+                "Folder collision: {}, renaming to: {}",
+                path.display(),
+                new_path.display()
+            );
+            return (new_name, new_path);
+        }
+        which += 1;
+    }
+}
+
 pub async fn profile_create_from_creator(
     profile: CreatePackProfile,
 ) -> crate::Result<ProfilePathId> {
@@ -176,6 +183,7 @@ pub(crate) async fn get_loader_version_from_loader(
         ModLoader::Forge => &metadata.forge,
         ModLoader::Fabric => &metadata.fabric,
         ModLoader::Quilt => &metadata.quilt,
+        ModLoader::NeoForge => &metadata.neoforge,
         _ => {
             return Err(
                 ProfileCreationError::NoManifest(loader.to_string()).into()
@@ -0,0 +1,243 @@
+//! Profile definitions: a small JSON file, hosted anywhere, describing a
+//! profile by its game version, loader and Modrinth projects, so it can be
+//! shared without packing the files into a modpack
+use crate::config::MODRINTH_API_URL;
+use crate::prelude::{ModLoader, ProfilePathId};
+use crate::state::{
+    fetch_projects, GameOptionOverrides, JavaSettings, MemorySettings,
+    ModrinthProject, ModrinthVersion, OptionValue, Profile, ProjectMetadata,
+    WindowSize,
+};
+use crate::util::fetch::fetch_json;
+use crate::State;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A profile to create, with the Modrinth projects to install into it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileDefinition {
+    pub name: String,
+    pub game_version: String,
+    #[serde(default)]
+    pub loader: ModLoader,
+    /// `latest`, `stable` or a loader version id. Defaults to latest
+    #[serde(default)]
+    pub loader_version: Option<String>,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    /// Modrinth projects, by id or slug. Their newest version compatible with
+    /// the profile is installed, along with its dependencies
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// Whether optional dependencies are installed too
+    #[serde(default)]
+    pub include_optional: bool,
+    #[serde(default)]
+    pub settings: DefinitionSettings,
+}
+
+/// Settings the definition suggests for the profile, all optional
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DefinitionSettings {
+    /// Maximum memory, in megabytes
+    pub memory: Option<u32>,
+    pub java_args: Vec<String>,
+    pub resolution: Option<WindowSize>,
+    /// Options forced into the game's options.txt
+    pub game_options: BTreeMap<String, OptionValue>,
+}
+
+/// Fetches a profile definition, without installing it
+#[tracing::instrument]
+pub async fn get_definition(url: &str) -> crate::Result<ProfileDefinition> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    fetch_json(Method::GET, url, None, None, &state.fetch_semaphore, &creds)
+        .await
+}
+
+/// Creates a profile from the definition at a URL and installs its projects
+/// with their dependencies. The projects are looked up before the profile is
+/// created, and the profile is removed again if any of them can't be
+/// installed. Returns the path of the new profile
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn install_from_definition(
+    url: &str,
+) -> crate::Result<ProfilePathId> {
+    let definition = get_definition(url).await?;
+    let versions = resolve_versions(&definition).await?;
+
+    let profile_path = super::create::profile_create(
+        definition.name.clone(),
+        definition.game_version.clone(),
+        definition.loader,
+        definition.loader_version.clone(),
+        None,
+        definition.icon_url.clone(),
+        None,
+        None,
+    )
+    .await?;
+
+    match materialize(&profile_path, &definition, versions).await {
+        Ok(()) => Ok(profile_path),
+        Err(err) => {
+            let _ = super::remove(&profile_path).await;
+            Err(err)
+        }
+    }
+}
+
+// Finds the newest version of each project of a definition compatible with
+// its game version and loader. Projects are checked against the content
+// policy by their ids, as the definition may name them by slug
+async fn resolve_versions(
+    definition: &ProfileDefinition,
+) -> crate::Result<Vec<(ModrinthProject, ModrinthVersion)>> {
+    let state = State::get().await?;
+    let projects = {
+        let creds = state.credentials.read().await;
+        fetch_projects(&definition.projects, &state.fetch_semaphore, &creds)
+            .await?
+    };
+
+    let mut resolved = Vec::new();
+    for id in &definition.projects {
+        // Projects are fetched by id or slug, so both are looked for
+        let project = projects
+            .values()
+            .find(|x| x.id == *id || x.slug.as_ref() == Some(id))
+            .ok_or_else(|| {
+                crate::ErrorKind::InputError(format!(
+                    "No Modrinth project found for {id}"
+                ))
+            })?;
+        if project.project_type == "modpack" {
+            return Err(crate::ErrorKind::InputError(format!(
+                "{} is a modpack, it can't be part of a profile definition",
+                project.title
+            ))
+            .into());
+        }
+        resolved.push(project.clone());
+    }
+    let ids = resolved.iter().map(|x| x.id.clone()).collect::<Vec<_>>();
+    crate::state::policy::check_projects(&ids).await?;
+
+    let mut versions = Vec::new();
+    for project in resolved {
+        let version = newest_version(&state, definition, &project).await?;
+        versions.push((project, version));
+    }
+    Ok(versions)
+}
+
+// Applies the settings of a definition to its new profile and installs the
+// versions resolved for its projects
+async fn materialize(
+    profile_path: &ProfilePathId,
+    definition: &ProfileDefinition,
+    versions: Vec<(ModrinthProject, ModrinthVersion)>,
+) -> crate::Result<()> {
+    let settings = &definition.settings;
+    super::edit(profile_path, |prof| {
+        if let Some(maximum) = settings.memory {
+            prof.memory = Some(MemorySettings {
+                maximum,
+                minimum: None,
+                auto: false,
+                sample_heap: false,
+            });
+        }
+        if !settings.java_args.is_empty() {
+            prof.java = Some(JavaSettings {
+                override_version: None,
+                extra_arguments: Some(settings.java_args.clone()),
+                custom_env_args: None,
+            });
+        }
+        if settings.resolution.is_some() {
+            prof.resolution = settings.resolution;
+        }
+        if !settings.game_options.is_empty() {
+            prof.game_options = Some(GameOptionOverrides {
+                options: settings.game_options.clone(),
+                restore_after_exit: false,
+            });
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    for (project, version) in versions {
+        // An earlier project may already have brought it in as a dependency
+        let profile = get_profile(profile_path).await?;
+        let installed = profile.projects.values().any(|x| {
+            matches!(
+                &x.metadata,
+                ProjectMetadata::Modrinth { project: installed, .. }
+                    if installed.id == project.id
+            )
+        });
+        if !installed {
+            super::add_project_with_dependencies(
+                profile_path,
+                version.id,
+                definition.include_optional,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// The newest version of a project for the game version and loader of a
+// definition
+async fn newest_version(
+    state: &State,
+    definition: &ProfileDefinition,
+    project: &ModrinthProject,
+) -> crate::Result<ModrinthVersion> {
+    let mut url = format!(
+        "{MODRINTH_API_URL}project/{}/version?game_versions={}",
+        project.id,
+        serde_json::to_string(&[&definition.game_version])?
+    );
+    if project.project_type == "mod" && definition.loader != ModLoader::Vanilla
+    {
+        url.push_str(&format!(
+            "&loaders={}",
+            serde_json::to_string(&[definition.loader.as_api_str()])?
+        ));
+    }
+    let creds = state.credentials.read().await;
+    let versions: Vec<ModrinthVersion> = fetch_json(
+        Method::GET,
+        &url,
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    // Versions are sorted newest first by labrinth
+    versions.into_iter().next().ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "{} has no version for {} {}",
+            project.title, definition.game_version, definition.loader
+        ))
+        .into()
+    })
+}
+
+async fn get_profile(profile_path: &ProfilePathId) -> crate::Result<Profile> {
+    super::get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string()).into()
+    })
+}
@@ -0,0 +1,236 @@
+//! Modrinth dependency resolution for projects added to a profile
+use crate::config::MODRINTH_API_URL;
+use crate::state::{
+    fetch_versions, DependencyType, ModLoader, ModrinthVersion, Profile,
+    ProjectMetadata, ProjectType,
+};
+use crate::util::fetch::fetch_json;
+use crate::{prelude::ProjectPathId, State};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// Why a project was added while resolving dependencies
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DependencyReason {
+    /// The project that was explicitly asked for
+    Requested,
+    /// A dependency declared by another version
+    Dependency {
+        required_by: String,
+        dependency_type: DependencyType,
+    },
+}
+
+/// A project installed as part of a dependency resolution
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolvedProject {
+    pub project_id: String,
+    pub version_id: String,
+    pub path: ProjectPathId,
+    pub reason: DependencyReason,
+}
+
+/// A dependency that could not be satisfied automatically
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnresolvedDependency {
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub required_by: String,
+    pub dependency_type: DependencyType,
+}
+
+/// Everything that happened while installing a project and its dependencies
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DependencyReport {
+    /// Path of the project that was explicitly requested
+    pub project_path: ProjectPathId,
+    pub added: Vec<ResolvedProject>,
+    /// Project ids of dependencies that the profile already had
+    pub already_installed: Vec<String>,
+    /// Dependencies with no version compatible with the profile
+    pub unresolved: Vec<UnresolvedDependency>,
+    /// Installed projects declared incompatible by an added version
+    pub incompatible: Vec<String>,
+}
+
+/// Installs a Modrinth version into a profile along with the closure of its
+/// dependencies, picking versions compatible with the profile's game version
+/// and loader
+#[tracing::instrument(skip(profile))]
+#[theseus_macros::debug_pin]
+pub async fn install_with_dependencies(
+    profile: &Profile,
+    version_id: String,
+    include_optional: bool,
+) -> crate::Result<DependencyReport> {
+    let mut installed = profile
+        .projects
+        .values()
+        .filter_map(|x| match &x.metadata {
+            ProjectMetadata::Modrinth { project, .. } => {
+                Some(project.id.clone())
+            }
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let root = fetch_version(&version_id).await?;
+    let mut report = DependencyReport {
+        project_path: profile.add_project_modrinth_version(&root).await?,
+        added: Vec::new(),
+        already_installed: Vec::new(),
+        unresolved: Vec::new(),
+        incompatible: Vec::new(),
+    };
+    report.added.push(ResolvedProject {
+        project_id: root.project_id.clone(),
+        version_id: root.id.clone(),
+        path: report.project_path.clone(),
+        reason: DependencyReason::Requested,
+    });
+    installed.insert(root.project_id.clone());
+
+    let mut queue = VecDeque::from([root]);
+    let state = State::get().await?;
+    while let Some(parent) = queue.pop_front() {
+        // The versions the dependencies pin are fetched in one request
+        let pinned_ids = parent
+            .dependencies
+            .iter()
+            .filter(|x| match x.dependency_type {
+                DependencyType::Required => true,
+                DependencyType::Optional => include_optional,
+                _ => false,
+            })
+            .filter_map(|x| x.version_id.clone())
+            .collect::<Vec<_>>();
+        let mut pinned = {
+            let creds = state.credentials.read().await;
+            fetch_versions(&pinned_ids, &state.fetch_semaphore, &creds).await?
+        };
+
+        for dependency in &parent.dependencies {
+            let dependency_type = dependency.dependency_type;
+            match dependency_type {
+                DependencyType::Required => {}
+                DependencyType::Optional if include_optional => {}
+                DependencyType::Incompatible => {
+                    if let Some(project_id) = &dependency.project_id {
+                        if installed.contains(project_id) {
+                            report.incompatible.push(project_id.clone());
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+
+            if let Some(project_id) = &dependency.project_id {
+                if installed.contains(project_id) {
+                    report.already_installed.push(project_id.clone());
+                    continue;
+                }
+            }
+
+            let version = match (&dependency.version_id, &dependency.project_id)
+            {
+                (Some(version_id), _) => pinned.remove(version_id),
+                (None, Some(project_id)) => {
+                    fetch_compatible_version(profile, &parent, project_id)
+                        .await?
+                }
+                (None, None) => None,
+            };
+
+            let Some(version) = version else {
+                report.unresolved.push(UnresolvedDependency {
+                    project_id: dependency.project_id.clone(),
+                    version_id: dependency.version_id.clone(),
+                    required_by: parent.id.clone(),
+                    dependency_type,
+                });
+                continue;
+            };
+
+            // A pinned version id may belong to a project we already have
+            if !installed.insert(version.project_id.clone()) {
+                report.already_installed.push(version.project_id.clone());
+                continue;
+            }
+
+            let path = profile.add_project_modrinth_version(&version).await?;
+            tracing::info!(
+                "Added dependency {} required by {}",
+                version.project_id,
+                parent.id
+            );
+            report.added.push(ResolvedProject {
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+                path,
+                reason: DependencyReason::Dependency {
+                    required_by: parent.id.clone(),
+                    dependency_type,
+                },
+            });
+            queue.push_back(version);
+        }
+    }
+
+    Ok(report)
+}
+
+async fn fetch_version(version_id: &str) -> crate::Result<ModrinthVersion> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    fetch_json(
+        Method::GET,
+        &format!("{MODRINTH_API_URL}version/{version_id}"),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await
+}
+
+// Finds the newest version of a project matching the profile's game version,
+// and its loader when the dependency is a mod
+async fn fetch_compatible_version(
+    profile: &Profile,
+    parent: &ModrinthVersion,
+    project_id: &str,
+) -> crate::Result<Option<ModrinthVersion>> {
+    let mut url = format!(
+        "{MODRINTH_API_URL}project/{project_id}/version?game_versions={}",
+        serde_json::to_string(&[&profile.metadata.game_version])?
+    );
+    if profile.metadata.loader != ModLoader::Vanilla
+        && matches!(
+            ProjectType::get_from_loaders(parent.loaders.clone()),
+            Some(ProjectType::Mod)
+        )
+    {
+        url.push_str(&format!(
+            "&loaders={}",
+            serde_json::to_string(&[profile.metadata.loader.as_api_str()])?
+        ));
+    }
+
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let versions: Vec<ModrinthVersion> = fetch_json(
+        Method::GET,
+        &url,
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    // Versions are sorted newest first by labrinth
+    Ok(versions.into_iter().next())
+}
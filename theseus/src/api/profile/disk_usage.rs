@@ -0,0 +1,118 @@
+//! Space taken up by a profile, broken down by what is using it, for
+//! finding what to clean up
+use crate::state::{ProfilePathId, ISOLATED_STORAGE_FOLDER_NAME};
+use crate::util::io::{self, IOError};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+// How long a computed usage is reused for, walking a profile with many
+// worlds takes a while
+const CACHE_MINUTES: i64 = 10;
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<ProfilePathId, DiskUsage>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Size in bytes of each part of a profile folder
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DiskUsage {
+    pub mods: u64,
+    pub saves: u64,
+    pub resourcepacks: u64,
+    pub shaderpacks: u64,
+    /// The game's logs, crash reports and the launcher's logs of the profile
+    pub logs: u64,
+    pub screenshots: u64,
+    pub config: u64,
+    /// Libraries and assets of a profile with isolated storage
+    pub game_files: u64,
+    pub other: u64,
+    pub total: u64,
+    pub computed_at: DateTime<Utc>,
+}
+
+impl DiskUsage {
+    fn add(&mut self, folder: &str, size: u64) {
+        let category = match folder {
+            "mods" => &mut self.mods,
+            "saves" => &mut self.saves,
+            "resourcepacks" | "texturepacks" => &mut self.resourcepacks,
+            "shaderpacks" => &mut self.shaderpacks,
+            "logs" | "crash-reports" | "modrinth_logs" => &mut self.logs,
+            "screenshots" => &mut self.screenshots,
+            "config" => &mut self.config,
+            ISOLATED_STORAGE_FOLDER_NAME => &mut self.game_files,
+            _ => &mut self.other,
+        };
+        *category += size;
+        self.total += size;
+    }
+}
+
+pub(super) async fn get(
+    profile_path: &ProfilePathId,
+    refresh: bool,
+) -> crate::Result<DiskUsage> {
+    if !refresh {
+        let cached = CACHE.lock().await.get(profile_path).cloned();
+        if let Some(usage) = cached {
+            if Utc::now() - usage.computed_at < Duration::minutes(CACHE_MINUTES)
+            {
+                return Ok(usage);
+            }
+        }
+    }
+
+    // The cache isn't held while walking, so other profiles can be walked
+    // at the same time
+    let usage = compute(&profile_path.get_full_path().await?).await?;
+    CACHE
+        .lock()
+        .await
+        .insert(profile_path.clone(), usage.clone());
+    Ok(usage)
+}
+
+async fn compute(full_path: &Path) -> crate::Result<DiskUsage> {
+    let mut usage = DiskUsage {
+        computed_at: Utc::now(),
+        ..Default::default()
+    };
+    if !full_path.exists() {
+        return Ok(usage);
+    }
+
+    // Files are counted towards the top level folder they are in
+    let mut stack: Vec<(PathBuf, Option<String>)> =
+        vec![(full_path.to_path_buf(), None)];
+    while let Some((dir, folder)) = stack.pop() {
+        let mut read_dir = io::read_dir(&dir).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &dir))?
+        {
+            // Symlinks aren't followed, what they point at is counted
+            // where it lives
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|e| IOError::with_path(e, entry.path()))?;
+            let folder = folder.clone().unwrap_or_else(|| {
+                entry.file_name().to_string_lossy().to_string()
+            });
+            if metadata.is_dir() {
+                stack.push((entry.path(), Some(folder)));
+            } else {
+                usage.add(&folder, metadata.len());
+            }
+        }
+    }
+
+    Ok(usage)
+}
@@ -0,0 +1,180 @@
+//! Machine-readable listing of every profile, for tracking many instances
+//! in external tools
+use crate::state::Profile;
+use crate::util::io::{self, IOError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum InventoryFormat {
+    Json,
+    Csv,
+}
+
+/// A row of the inventory, one per profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InventoryEntry {
+    pub name: String,
+    pub path: String,
+    pub game_version: String,
+    pub loader: String,
+    pub loader_version: Option<String>,
+    pub modpack_project_id: Option<String>,
+    pub modpack_version_id: Option<String>,
+    pub groups: Vec<String>,
+    pub mods: usize,
+    pub disabled_mods: usize,
+    pub projects: usize,
+    /// Size of the profile folder in bytes
+    pub size: u64,
+    pub total_playtime: u64,
+    pub launches: u64,
+    pub crashes: u64,
+    pub date_created: DateTime<Utc>,
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+const CSV_HEADER: &[&str] = &[
+    "name",
+    "path",
+    "game_version",
+    "loader",
+    "loader_version",
+    "modpack_project_id",
+    "modpack_version_id",
+    "groups",
+    "mods",
+    "disabled_mods",
+    "projects",
+    "size",
+    "total_playtime",
+    "launches",
+    "crashes",
+    "date_created",
+    "last_played",
+];
+
+pub(super) async fn get_entry(
+    profile: &Profile,
+) -> crate::Result<InventoryEntry> {
+    let mods = profile
+        .projects
+        .keys()
+        .filter(|x| x.0.starts_with("mods"))
+        .count();
+    let disabled_mods = profile
+        .projects
+        .iter()
+        .filter(|(path, project)| {
+            path.0.starts_with("mods") && project.disabled
+        })
+        .count();
+
+    let full_path = profile.get_profile_full_path().await?;
+    let size = if full_path.exists() {
+        get_dir_size(full_path).await?
+    } else {
+        0
+    };
+
+    let linked_data = profile.metadata.linked_data.as_ref();
+    Ok(InventoryEntry {
+        name: profile.metadata.name.clone(),
+        path: profile.profile_id().to_string(),
+        game_version: profile.metadata.game_version.clone(),
+        loader: profile.metadata.loader.to_string(),
+        loader_version: profile
+            .metadata
+            .loader_version
+            .as_ref()
+            .map(|x| x.id.clone()),
+        modpack_project_id: linked_data.and_then(|x| x.project_id.clone()),
+        modpack_version_id: linked_data.and_then(|x| x.version_id.clone()),
+        groups: profile.metadata.groups.clone(),
+        mods,
+        disabled_mods,
+        projects: profile.projects.len(),
+        size,
+        total_playtime: profile.playtime.total_seconds,
+        launches: profile.playtime.launches,
+        crashes: profile.playtime.crashes,
+        date_created: profile.metadata.date_created,
+        last_played: profile.metadata.last_played,
+    })
+}
+
+pub(super) fn serialize(
+    entries: &[InventoryEntry],
+    format: InventoryFormat,
+) -> crate::Result<String> {
+    match format {
+        InventoryFormat::Json => Ok(serde_json::to_string_pretty(entries)?),
+        InventoryFormat::Csv => {
+            let mut csv = CSV_HEADER.join(",");
+            csv.push('\n');
+            for entry in entries {
+                let row = [
+                    entry.name.clone(),
+                    entry.path.clone(),
+                    entry.game_version.clone(),
+                    entry.loader.clone(),
+                    entry.loader_version.clone().unwrap_or_default(),
+                    entry.modpack_project_id.clone().unwrap_or_default(),
+                    entry.modpack_version_id.clone().unwrap_or_default(),
+                    entry.groups.join(";"),
+                    entry.mods.to_string(),
+                    entry.disabled_mods.to_string(),
+                    entry.projects.to_string(),
+                    entry.size.to_string(),
+                    entry.total_playtime.to_string(),
+                    entry.launches.to_string(),
+                    entry.crashes.to_string(),
+                    entry.date_created.to_rfc3339(),
+                    entry
+                        .last_played
+                        .map(|x| x.to_rfc3339())
+                        .unwrap_or_default(),
+                ];
+                let row = row.iter().map(|x| escape_csv(x)).collect::<Vec<_>>();
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+            Ok(csv)
+        }
+    }
+}
+
+// Quotes fields containing separators, doubling any quotes inside them
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+async fn get_dir_size(dir: PathBuf) -> crate::Result<u64> {
+    let mut size = 0;
+    let mut stack = vec![dir];
+    while let Some(dir) = stack.pop() {
+        let mut read_dir = io::read_dir(&dir).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &dir))?
+        {
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|e| IOError::with_path(e, entry.path()))?;
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                size += metadata.len();
+            }
+        }
+    }
+    Ok(size)
+}
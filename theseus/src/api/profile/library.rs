@@ -0,0 +1,320 @@
+//! Organizing profiles into groups and tags, and the order they are listed
+//! in, for library views with many instances
+use crate::event::emit::emit_profile;
+use crate::event::ProfilePayloadType;
+use crate::prelude::ProfilePathId;
+use crate::state::Profile;
+use crate::State;
+use serde::{Deserialize, Serialize};
+
+/// A group of profiles
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileGroup {
+    pub name: String,
+    pub profiles: usize,
+}
+
+/// Which profiles of the library to list. Profiles have to match everything
+/// that is set
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LibraryFilter {
+    pub group: Option<String>,
+    pub tag: Option<String>,
+    /// Only profiles in no group, for an "ungrouped" section
+    pub ungrouped: bool,
+}
+
+/// Gets every group, in the order they were created
+#[tracing::instrument]
+pub async fn get_groups() -> crate::Result<Vec<ProfileGroup>> {
+    let state = State::get().await?;
+    let profiles = state.profiles.read().await;
+    let library = state.library.read().await;
+
+    Ok(library
+        .groups
+        .iter()
+        .map(|group| ProfileGroup {
+            name: group.clone(),
+            profiles: profiles
+                .0
+                .values()
+                .filter(|x| x.metadata.groups.contains(group))
+                .count(),
+        })
+        .collect())
+}
+
+/// Creates an empty group
+#[tracing::instrument]
+pub async fn create_group(name: &str) -> crate::Result<()> {
+    let name = validate_name(name)?;
+    let state = State::get().await?;
+    let mut library = state.library.write().await;
+    if library.groups.contains(&name) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "A group named {name} already exists"
+        ))
+        .into());
+    }
+
+    library.groups.push(name);
+    library.save().await
+}
+
+/// Renames a group, moving its profiles along. Renaming it to an existing
+/// group merges the two
+#[tracing::instrument]
+pub async fn rename_group(name: &str, new_name: &str) -> crate::Result<()> {
+    let new_name = validate_name(new_name)?;
+    let state = State::get().await?;
+    {
+        let mut library = state.library.write().await;
+        let Some(index) = library.groups.iter().position(|x| x == name) else {
+            return Err(unknown_group(name));
+        };
+        if library.groups.contains(&new_name) {
+            library.groups.remove(index);
+        } else {
+            library.groups[index] = new_name.clone();
+        }
+        library.save().await?;
+    }
+
+    edit_groups(|groups| {
+        if !groups.iter().any(|x| x == name) {
+            return false;
+        }
+        groups.retain(|x| x != name);
+        if !groups.contains(&new_name) {
+            groups.push(new_name.clone());
+        }
+        true
+    })
+    .await
+}
+
+/// Deletes a group. Its profiles are kept, outside of it
+#[tracing::instrument]
+pub async fn delete_group(name: &str) -> crate::Result<()> {
+    let state = State::get().await?;
+    {
+        let mut library = state.library.write().await;
+        if !library.groups.iter().any(|x| x == name) {
+            return Err(unknown_group(name));
+        }
+        library.groups.retain(|x| x != name);
+        library.save().await?;
+    }
+
+    edit_groups(|groups| {
+        let len = groups.len();
+        groups.retain(|x| x != name);
+        groups.len() != len
+    })
+    .await
+}
+
+/// Moves groups into an order. Groups left out keep their relative order,
+/// after the ones given
+#[tracing::instrument]
+pub async fn set_group_order(groups: Vec<String>) -> crate::Result<()> {
+    let state = State::get().await?;
+    let mut library = state.library.write().await;
+    library.groups.sort_by_key(|group| {
+        groups
+            .iter()
+            .position(|x| x == group)
+            .unwrap_or(groups.len())
+    });
+    library.save().await
+}
+
+/// Sets the groups a profile is in, creating those that don't exist
+#[tracing::instrument]
+pub async fn set_profile_groups(
+    path: &ProfilePathId,
+    groups: Vec<String>,
+) -> crate::Result<()> {
+    let groups = normalize(groups)?;
+    let state = State::get().await?;
+    {
+        let mut library = state.library.write().await;
+        let missing = groups
+            .iter()
+            .filter(|x| !library.groups.contains(x))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            library.groups.extend(missing);
+            library.save().await?;
+        }
+    }
+
+    crate::profile::edit(path, |prof| {
+        prof.metadata.groups = groups.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
+
+/// Sets the tags of a profile. Tags aren't stored apart from the profiles
+/// they are on, and go away with the last of them
+#[tracing::instrument]
+pub async fn set_profile_tags(
+    path: &ProfilePathId,
+    tags: Vec<String>,
+) -> crate::Result<()> {
+    let tags = normalize(tags)?;
+    crate::profile::edit(path, |prof| {
+        prof.metadata.tags = tags.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
+
+/// Gets every tag on a profile, sorted
+#[tracing::instrument]
+pub async fn get_tags() -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+    let profiles = state.profiles.read().await;
+    let mut tags = profiles
+        .0
+        .values()
+        .flat_map(|x| x.metadata.tags.iter().cloned())
+        .collect::<Vec<_>>();
+    tags.sort_by_key(|x| x.to_lowercase());
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Moves profiles into an order. Profiles left out are listed after them
+#[tracing::instrument]
+pub async fn set_order(paths: Vec<ProfilePathId>) -> crate::Result<()> {
+    let state = State::get().await?;
+    let profiles = state.profiles.read().await;
+    let mut library = state.library.write().await;
+
+    let mut order = Vec::new();
+    for path in &paths {
+        let Some(profile) = profiles.0.get(path) else {
+            return Err(crate::ErrorKind::UnmanagedProfileError(
+                path.to_string(),
+            )
+            .into());
+        };
+        if !order.contains(&profile.uuid) {
+            order.push(profile.uuid);
+        }
+    }
+    // Removed profiles are dropped from the order along the way
+    let rest = library
+        .order
+        .iter()
+        .filter(|uuid| {
+            !order.contains(uuid)
+                && profiles.0.values().any(|x| &x.uuid == *uuid)
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    order.extend(rest);
+
+    library.order = order;
+    library.save().await
+}
+
+/// Lists the profiles matching a filter, in the order of the library
+#[tracing::instrument]
+pub async fn list(filter: LibraryFilter) -> crate::Result<Vec<Profile>> {
+    let state = State::get().await?;
+    let profiles = state.profiles.read().await;
+    let library = state.library.read().await;
+
+    let mut profiles = profiles
+        .0
+        .values()
+        .filter(|x| {
+            filter
+                .group
+                .as_ref()
+                .map_or(true, |group| x.metadata.groups.contains(group))
+                && filter
+                    .tag
+                    .as_ref()
+                    .map_or(true, |tag| x.metadata.tags.contains(tag))
+                && (!filter.ungrouped || x.metadata.groups.is_empty())
+        })
+        .cloned()
+        .map(|mut x| {
+            // Front ends only need what is shown in the library
+            x.projects.clear();
+            x
+        })
+        .collect::<Vec<_>>();
+    profiles.sort_by(|a, b| {
+        library
+            .position(&a.uuid)
+            .cmp(&library.position(&b.uuid))
+            .then_with(|| {
+                a.metadata
+                    .name
+                    .to_lowercase()
+                    .cmp(&b.metadata.name.to_lowercase())
+            })
+    });
+
+    Ok(profiles)
+}
+
+// Changes the groups of every profile, saving those that changed
+async fn edit_groups(
+    mut action: impl FnMut(&mut Vec<String>) -> bool,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    {
+        let mut profiles = state.profiles.write().await;
+        for (path, profile) in profiles.0.iter_mut() {
+            if action(&mut profile.metadata.groups) {
+                emit_profile(
+                    profile.uuid,
+                    path,
+                    &profile.metadata.name,
+                    ProfilePayloadType::Edited,
+                )
+                .await?;
+            }
+        }
+    }
+    State::sync().await
+}
+
+fn validate_name(name: &str) -> crate::Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(crate::ErrorKind::InputError(
+            "Names of groups and tags can't be empty".to_string(),
+        )
+        .into());
+    }
+    Ok(name.to_string())
+}
+
+// Trims names and drops duplicates, keeping the order they were given in
+fn normalize(names: Vec<String>) -> crate::Result<Vec<String>> {
+    let mut normalized = Vec::new();
+    for name in names {
+        let name = validate_name(&name)?;
+        if !normalized.contains(&name) {
+            normalized.push(name);
+        }
+    }
+    Ok(normalized)
+}
+
+fn unknown_group(name: &str) -> crate::Error {
+    crate::ErrorKind::InputError(format!("There is no group named {name}"))
+        .into()
+}
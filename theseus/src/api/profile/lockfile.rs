@@ -0,0 +1,420 @@
+//! Lockfile of the exact Modrinth versions installed in a profile, so the
+//! same mods can be installed again elsewhere or after a repair
+use crate::event::emit::emit_profile;
+use crate::event::ProfilePayloadType;
+use crate::prelude::{ModLoader, ProfilePathId, ProjectPathId};
+use crate::state::{Profile, ProjectMetadata};
+use crate::util::fetch::{fetch, write};
+use crate::util::io;
+use crate::State;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path};
+
+/// File the lockfile is kept in, in the profile folder
+pub const LOCKFILE_NAME: &str = "projects.lock.json";
+
+// Folders of a profile locked projects can be installed into
+const PROJECT_FOLDERS: [&str; 4] =
+    ["mods", "datapacks", "resourcepacks", "shaderpacks"];
+
+/// Exact versions of the Modrinth projects of a profile, by the path of
+/// their file relative to the profile while enabled
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub loader_version: Option<String>,
+    pub projects: BTreeMap<String, LockedProject>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedProject {
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    pub file_name: String,
+    pub sha512: String,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// A project whose locked version differs between two lockfiles. Unset on
+/// the side the project is missing from
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockChange {
+    pub project_id: String,
+    pub from: Option<LockedProject>,
+    pub to: Option<LockedProject>,
+}
+
+// Whether a locked project is installed inside one of the project folders
+// of a profile, under a plain file name
+fn is_project_path(path: &str, file_name: &str) -> bool {
+    let is_plain =
+        |x: &Path| x.components().all(|x| matches!(x, Component::Normal(_)));
+    let path = Path::new(path);
+    is_plain(path)
+        && PROJECT_FOLDERS
+            .iter()
+            .any(|x| path.starts_with(x) && path != Path::new(x))
+        && is_plain(Path::new(file_name))
+        && Path::new(file_name).components().count() == 1
+}
+
+// Path of a project as locked, without the suffix of disabled projects and
+// with `/` separators on every platform
+fn locked_path(path: &ProjectPathId) -> String {
+    let path = path
+        .0
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    path.strip_suffix(".disabled")
+        .map_or(path.clone(), String::from)
+}
+
+impl Lockfile {
+    /// The lockfile of the projects a profile has installed
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self::of(profile, false)
+    }
+
+    /// The lockfile a profile would have with every available update applied
+    pub fn with_updates(profile: &Profile) -> Self {
+        Self::of(profile, true)
+    }
+
+    fn of(profile: &Profile, updated: bool) -> Self {
+        let mut projects = BTreeMap::new();
+        for (path, project) in &profile.projects {
+            let ProjectMetadata::Modrinth {
+                project: modrinth_project,
+                version,
+                update_version,
+                ..
+            } = &project.metadata
+            else {
+                continue;
+            };
+
+            let path = locked_path(path);
+            let update =
+                update_version.as_ref().filter(|_| updated).and_then(|x| {
+                    x.files
+                        .iter()
+                        .find(|x| x.primary)
+                        .or_else(|| x.files.first())
+                        .map(|file| (x, file))
+                });
+            let (path, locked) = match update {
+                Some((version, file)) => (
+                    match path.rsplit_once('/') {
+                        Some((folder, _)) => {
+                            format!("{folder}/{}", file.filename)
+                        }
+                        None => file.filename.clone(),
+                    },
+                    LockedProject {
+                        project_id: modrinth_project.id.clone(),
+                        version_id: version.id.clone(),
+                        version_number: version.version_number.clone(),
+                        file_name: file.filename.clone(),
+                        sha512: file
+                            .hashes
+                            .get("sha512")
+                            .cloned()
+                            .unwrap_or_default(),
+                        disabled: project.disabled,
+                    },
+                ),
+                None => (
+                    path,
+                    LockedProject {
+                        project_id: modrinth_project.id.clone(),
+                        version_id: version.id.clone(),
+                        version_number: version.version_number.clone(),
+                        file_name: project
+                            .file_name
+                            .strip_suffix(".disabled")
+                            .unwrap_or(&project.file_name)
+                            .to_string(),
+                        sha512: project.sha512.clone(),
+                        disabled: project.disabled,
+                    },
+                ),
+            };
+            projects.insert(path, locked);
+        }
+
+        Self {
+            game_version: profile.metadata.game_version.clone(),
+            loader: profile.metadata.loader,
+            loader_version: profile
+                .metadata
+                .loader_version
+                .as_ref()
+                .map(|x| x.id.clone()),
+            projects,
+        }
+    }
+
+    /// Projects whose version differs from this lockfile to another, by
+    /// Modrinth project so a project that changed file name is one change
+    pub fn diff(&self, other: &Lockfile) -> Vec<LockChange> {
+        let by_project = |lockfile: &Lockfile| {
+            lockfile
+                .projects
+                .values()
+                .map(|x| (x.project_id.clone(), x.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let from = by_project(self);
+        let to = by_project(other);
+
+        from.keys()
+            .chain(to.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|project_id| {
+                let from = from.get(project_id);
+                let to = to.get(project_id);
+                if from.map(|x| &x.version_id) == to.map(|x| &x.version_id) {
+                    return None;
+                }
+                Some(LockChange {
+                    project_id: project_id.clone(),
+                    from: from.cloned(),
+                    to: to.cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+async fn get_profile(profile_path: &ProfilePathId) -> crate::Result<Profile> {
+    super::get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string()).into()
+    })
+}
+
+/// Gets the lockfile of a profile, if it has one
+#[tracing::instrument]
+pub async fn get(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Option<Lockfile>> {
+    let path = profile_path.get_full_path().await?.join(LOCKFILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&io::read(&path).await?)?))
+}
+
+/// Writes the lockfile of a profile from the projects it has installed. It is
+/// only rewritten if it changed, and not created for profiles without any
+/// Modrinth projects
+pub(crate) async fn write_lockfile(profile: &Profile) -> crate::Result<()> {
+    let lockfile = Lockfile::from_profile(profile);
+    let existing = get(&profile.profile_id()).await?;
+    if existing.as_ref() == Some(&lockfile)
+        || (existing.is_none() && lockfile.projects.is_empty())
+    {
+        return Ok(());
+    }
+
+    let state = State::get().await?;
+    write(
+        &profile.get_profile_full_path().await?.join(LOCKFILE_NAME),
+        &serde_json::to_vec_pretty(&lockfile)?,
+        &state.io_semaphore,
+    )
+    .await
+}
+
+/// Writes the lockfile of a profile, logging rather than failing, as the
+/// change it follows already went through
+pub(crate) async fn update_lockfile(profile_path: &ProfilePathId) {
+    let res =
+        async { write_lockfile(&get_profile(profile_path).await?).await }.await;
+    if let Err(err) = res {
+        tracing::warn!("Unable to write lockfile of {profile_path}: {err}");
+    }
+}
+
+/// Lists the version changes updating every project of a profile would make
+/// to its lockfile, to review before updating
+#[tracing::instrument]
+pub async fn preview_updates(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<LockChange>> {
+    let profile = get_profile(profile_path).await?;
+    let current = match get(profile_path).await? {
+        Some(lockfile) => lockfile,
+        None => Lockfile::from_profile(&profile),
+    };
+    Ok(current.diff(&Lockfile::with_updates(&profile)))
+}
+
+/// Lists the version changes installing from the lockfile would make to the
+/// projects of a profile
+#[tracing::instrument]
+pub async fn diff_lockfile(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<LockChange>> {
+    let profile = get_profile(profile_path).await?;
+    let lockfile = get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::InputError(format!("{profile_path} has no lockfile"))
+    })?;
+    Ok(Lockfile::from_profile(&profile).diff(&lockfile))
+}
+
+/// Installs the exact Modrinth projects of a profile's lockfile, removing
+/// the Modrinth projects it doesn't list. Files are checked against the
+/// hashes it recorded. Returns the version changes made
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn install_from_lockfile(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<LockChange>> {
+    let profile = get_profile(profile_path).await?;
+    let lockfile = get(profile_path).await?.ok_or_else(|| {
+        crate::ErrorKind::InputError(format!("{profile_path} has no lockfile"))
+    })?;
+
+    let current = Lockfile::from_profile(&profile);
+    if (
+        &lockfile.game_version,
+        lockfile.loader,
+        &lockfile.loader_version,
+    ) != (
+        &current.game_version,
+        current.loader,
+        &current.loader_version,
+    ) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "The lockfile is for {} {} {}, change the loader of the profile first",
+            lockfile.game_version,
+            lockfile.loader,
+            lockfile.loader_version.as_deref().unwrap_or_default()
+        ))
+        .into());
+    }
+    // The lockfile may come from another machine, so none of its paths are
+    // written to unless they stay in a project folder of the profile
+    if let Some((path, _)) = lockfile
+        .projects
+        .iter()
+        .find(|(path, x)| !is_project_path(path, &x.file_name))
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "The lockfile installs to {path}, outside the project folders"
+        ))
+        .into());
+    }
+    // Nothing is installed if any of the locked projects is blocked by the
+    // content policy
+    let project_ids = lockfile
+        .projects
+        .values()
+        .map(|x| x.project_id.clone())
+        .collect::<Vec<_>>();
+    crate::state::policy::check_projects(&project_ids).await?;
+    let changes = current.diff(&lockfile);
+
+    // Projects that differ from the lockfile in any way are replaced
+    let up_to_date = |path: &ProjectPathId, sha512: &str, disabled: bool| {
+        lockfile
+            .projects
+            .get(&locked_path(path))
+            .map_or(false, |x| x.sha512 == sha512 && x.disabled == disabled)
+    };
+    let mut kept = BTreeSet::new();
+    let mut outdated = Vec::new();
+    for (path, project) in &profile.projects {
+        if !matches!(project.metadata, ProjectMetadata::Modrinth { .. }) {
+            continue;
+        }
+        if up_to_date(path, &project.sha512, project.disabled) {
+            kept.insert(locked_path(path));
+        } else {
+            outdated.push(path.clone());
+        }
+    }
+
+    let missing = lockfile
+        .projects
+        .iter()
+        .filter(|(path, _)| !kept.contains(*path))
+        .collect::<Vec<_>>();
+    let state = State::get().await?;
+    let ids = missing
+        .iter()
+        .map(|(_, x)| x.version_id.clone())
+        .collect::<Vec<_>>();
+    let creds = state.credentials.read().await;
+    let versions =
+        crate::state::fetch_versions(&ids, &state.fetch_semaphore, &creds)
+            .await?;
+
+    // Every file is downloaded and checked before the profile is touched, so
+    // a version that is gone doesn't leave the profile without its mods
+    let mut downloaded = Vec::new();
+    for (path, locked) in missing {
+        let file = versions
+            .get(&locked.version_id)
+            .and_then(|x| {
+                x.files.iter().find(|x| x.filename == locked.file_name)
+            })
+            .ok_or_else(|| {
+                crate::ErrorKind::InputError(format!(
+                    "{} of {} is no longer available",
+                    locked.file_name, locked.version_number
+                ))
+            })?;
+        let bytes = fetch(
+            &file.url,
+            file.hashes.get("sha1").map(|x| &**x),
+            &state.fetch_semaphore,
+            &creds,
+        )
+        .await?;
+        if daedalus::get_hash(bytes.clone()).await? != locked.sha512 {
+            return Err(crate::ErrorKind::OtherError(format!(
+                "{} doesn't match the hash in the lockfile",
+                locked.file_name
+            ))
+            .into());
+        }
+
+        let path = if locked.disabled {
+            format!("{path}.disabled")
+        } else {
+            path.clone()
+        };
+        downloaded.push((path, bytes));
+    }
+    drop(creds);
+
+    for path in &outdated {
+        profile.remove_project(path, None).await?;
+    }
+    let profile_full_path = profile.get_profile_full_path().await?;
+    for (path, bytes) in downloaded {
+        write(&profile_full_path.join(path), &bytes, &state.io_semaphore)
+            .await?;
+    }
+
+    // The files are looked up again to fill in their Modrinth metadata
+    Profile::sync_projects_task(profile_path.clone());
+    emit_profile(
+        profile.uuid,
+        profile_path,
+        &profile.metadata.name,
+        ProfilePayloadType::Edited,
+    )
+    .await?;
+    State::sync().await?;
+
+    Ok(changes)
+}
@@ -4,15 +4,23 @@ use crate::event::emit::{
     emit_loading, init_loading, loading_try_for_each_concurrent,
 };
 use crate::event::LoadingBarType;
+use crate::launcher::args::JavaArg;
 use crate::pack::install_from::{
     EnvType, PackDependency, PackFile, PackFileHash, PackFormat,
 };
-use crate::prelude::{JavaVersion, ProfilePathId, ProjectPathId};
-use crate::state::ProjectMetadata;
+use crate::prelude::{
+    JavaVersion, ProfilePathId, ProfileSelector, ProjectPathId,
+};
+use crate::state::{
+    game_options, policy, DevelopmentSettings, GameOptionOverrides,
+    LaunchOverrides, ModLoader, ProfileInstallStage, Profiles, ProjectMetadata,
+    Settings, StorageMode, UpdateChannel, ISOLATED_STORAGE_FOLDER_NAME,
+};
+use daedalus::modded::LoaderVersion;
 
 use crate::util::io::{self, IOError};
 use crate::{
-    auth::{self, refresh},
+    auth::{self, refresh_for_launch},
     event::{emit::emit_profile, ProfilePayloadType},
     state::MinecraftChild,
 };
@@ -22,6 +30,7 @@ pub use crate::{
 };
 use async_zip::tokio::write::ZipFileWriter;
 use async_zip::{Compression, ZipEntryBuilder};
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 
@@ -30,15 +39,33 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::{fs::File, process::Command, sync::RwLock};
 
+pub mod changelog;
+pub mod cloud_sync;
+pub mod compatibility;
+pub mod config;
 pub mod create;
+pub mod definition;
+pub mod dependencies;
+pub mod disk_usage;
+pub mod inventory;
+pub mod library;
+pub mod lockfile;
+pub mod portable;
+pub mod safe_mode;
+pub mod server;
+pub mod smoke_test;
 pub mod update;
 
 /// Remove a profile
 #[tracing::instrument]
 pub async fn remove(path: &ProfilePathId) -> crate::Result<()> {
+    if let Err(err) = crate::launcher::gpu::release_preference(path).await {
+        tracing::warn!("Unable to remove GPU preference of {path}: {err}");
+    }
+
     let state = State::get().await?;
     let mut profiles = state.profiles.write().await;
 
@@ -94,6 +121,24 @@ pub async fn get_by_uuid(
     Ok(profile)
 }
 
+/// Resolve a profile selector (uuid, name or path) to its path id.
+/// Fails if the selector matches no profile or, for names, several
+#[tracing::instrument]
+pub async fn resolve(
+    selector: &ProfileSelector,
+) -> crate::Result<ProfilePathId> {
+    selector.resolve().await
+}
+
+/// Get a profile by selector
+#[tracing::instrument]
+pub async fn get_by_selector(
+    selector: &ProfileSelector,
+    clear_projects: Option<bool>,
+) -> crate::Result<Option<Profile>> {
+    get(&selector.resolve().await?, clear_projects).await
+}
+
 /// Get profile's full path in the filesystem
 #[tracing::instrument]
 pub async fn get_full_path(path: &ProfilePathId) -> crate::Result<PathBuf> {
@@ -206,10 +251,9 @@ pub async fn get_optimal_jre_key(
             .iter()
             .find(|it| it.id == profile.metadata.game_version)
             .ok_or_else(|| {
-                crate::ErrorKind::LauncherError(format!(
-                    "Invalid or unknown Minecraft version: {}",
-                    profile.metadata.game_version
-                ))
+                crate::ErrorKind::InvalidGameVersion(
+                    profile.metadata.game_version.clone(),
+                )
             })?;
 
         // Get detailed manifest info from Daedalus
@@ -235,6 +279,113 @@ pub async fn get_optimal_jre_key(
     }
 }
 
+/// Parts of an instance that can be left out when duplicating it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DuplicateOptions {
+    pub exclude_saves: bool,
+    pub exclude_logs: bool,
+    pub exclude_screenshots: bool,
+}
+
+/// Duplicates a profile under a new name, deep copying its files
+/// The clone gets its own id and stays linked to the same pack version, so
+/// it can be modified or updated without touching the original
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn duplicate(
+    path: &ProfilePathId,
+    new_name: String,
+    options: DuplicateOptions,
+) -> crate::Result<ProfilePathId> {
+    let state = State::get().await?;
+    let mut profile = get(path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(path.to_string()).as_error()
+    })?;
+    if profile.archived.is_some() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {path} is archived, restore it before duplicating"
+        ))
+        .as_error());
+    }
+    let source_path = profile.get_profile_full_path().await?;
+
+    let (name, full_path) = create::get_unused_profile_path(
+        sanitize_profile_name(&new_name),
+        &state.directories.profiles_dir().await,
+    );
+    io::create_dir_all(&full_path).await?;
+    let new_path = name.clone();
+
+    let mut excluded = Vec::new();
+    if options.exclude_saves {
+        excluded.push("saves");
+    }
+    if options.exclude_logs {
+        excluded.extend(["logs", "crash-reports", "modrinth_logs"]);
+    }
+    if options.exclude_screenshots {
+        excluded.push("screenshots");
+    }
+
+    let result = async {
+        let mut entries = io::read_dir(&source_path).await?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &source_path))?
+        {
+            let file_name = entry.file_name();
+            if excluded.iter().any(|x| file_name == *x) {
+                continue;
+            }
+
+            crate::pack::import::copy_dir_to(
+                &entry.path(),
+                &full_path.join(&file_name),
+                &state.io_semaphore,
+            )
+            .await?;
+        }
+
+        let now = chrono::Utc::now();
+        profile.uuid = uuid::Uuid::new_v4();
+        profile.path = PathBuf::from(&name);
+        profile.metadata.name = name;
+        profile.metadata.date_created = now;
+        profile.metadata.date_modified = now;
+        profile.metadata.last_played = None;
+        profile.modrinth_update_version = None;
+        profile.playtime = Default::default();
+        profile.heap_usage = None;
+        profile
+            .projects
+            .retain(|project_path, _| full_path.join(&project_path.0).exists());
+
+        emit_profile(
+            profile.uuid,
+            &profile.profile_id(),
+            &profile.metadata.name,
+            ProfilePayloadType::Created,
+        )
+        .await?;
+
+        {
+            let mut profiles = state.profiles.write().await;
+            profiles.insert(profile.clone()).await?;
+        }
+        State::sync().await?;
+
+        Ok::<_, crate::Error>(profile.profile_id())
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = remove(&ProfilePathId::new(Path::new(&new_path))).await;
+    }
+    result
+}
+
 /// Get a copy of the profile set
 #[tracing::instrument]
 pub async fn list(
@@ -269,6 +420,27 @@ pub async fn install(path: &ProfilePathId) -> crate::Result<()> {
     Ok(())
 }
 
+/// Verifies the client jar, libraries, natives and assets of a profile,
+/// downloading again any that are missing or fail their hash check and
+/// running loader processors again. A profile stuck installing is marked
+/// installed once everything checks out. Returns the files that were corrupt
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn repair(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<PathBuf>> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+
+    let corrupt = crate::launcher::remove_corrupt_files(&profile).await?;
+    // Natives are extracted again and processors run again on every install
+    crate::launcher::install_minecraft(&profile, None).await?;
+    State::sync().await?;
+
+    Ok(corrupt)
+}
+
 #[tracing::instrument]
 #[theseus_macros::debug_pin]
 pub async fn update_all_projects(
@@ -336,6 +508,7 @@ pub async fn update_all_projects(
         )
         .await?;
         State::sync().await?;
+        lockfile::update_lockfile(profile_path).await;
 
         Ok(Arc::try_unwrap(map).unwrap().into_inner())
     } else {
@@ -386,6 +559,7 @@ pub async fn update_project(
                         }
                         profile.projects.insert(path.clone(), project);
                     }
+                    profile.flag_locked_modifications();
                 }
                 drop(profiles);
 
@@ -398,6 +572,7 @@ pub async fn update_project(
                     )
                     .await?;
                     State::sync().await?;
+                    lockfile::update_lockfile(profile_path).await;
                 }
 
                 return Ok(path);
@@ -416,15 +591,35 @@ pub async fn update_project(
     }
 }
 
-/// Add a project from a version
+/// Add a project from a version, along with its required dependencies
 /// Returns the relative path to the project as a ProjectPathId
 #[tracing::instrument]
 pub async fn add_project_from_version(
     profile_path: &ProfilePathId,
     version_id: String,
 ) -> crate::Result<ProjectPathId> {
+    let report =
+        add_project_with_dependencies(profile_path, version_id, false).await?;
+    Ok(report.project_path)
+}
+
+/// Add a project from a version and resolve its dependencies against the
+/// profile's game version and loader, optionally including optional ones
+/// Returns a report of every project added and why
+#[tracing::instrument]
+pub async fn add_project_with_dependencies(
+    profile_path: &ProfilePathId,
+    version_id: String,
+    include_optional: bool,
+) -> crate::Result<dependencies::DependencyReport> {
     if let Some(profile) = get(profile_path, None).await? {
-        let (project_path, _) = profile.add_project_version(version_id).await?;
+        let report = dependencies::install_with_dependencies(
+            &profile,
+            version_id,
+            include_optional,
+        )
+        .await?;
+        flag_locked_modifications(profile_path).await?;
 
         emit_profile(
             profile.uuid,
@@ -433,7 +628,87 @@ pub async fn add_project_from_version(
             ProfilePayloadType::Edited,
         )
         .await?;
-        Ok(project_path)
+        Ok(report)
+    } else {
+        Err(
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+                .as_error(),
+        )
+    }
+}
+
+/// Playtime statistics of a profile, with durations in seconds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileStatistics {
+    pub total_playtime: u64,
+    pub last_week_playtime: u64,
+    pub launches: u64,
+    pub crashes: u64,
+    pub last_played: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Gets the playtime statistics of a profile
+#[tracing::instrument]
+pub async fn get_statistics(
+    profile_path: &ProfilePathId,
+) -> crate::Result<ProfileStatistics> {
+    let profile = get(profile_path, Some(true)).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+
+    let last_week = chrono::Utc::now() - chrono::Duration::days(7);
+    Ok(ProfileStatistics {
+        total_playtime: profile.playtime.total_seconds,
+        last_week_playtime: profile.playtime.seconds_since(last_week),
+        launches: profile.playtime.launches,
+        crashes: profile.playtime.crashes,
+        last_played: profile.metadata.last_played,
+    })
+}
+
+/// Lists every profile with its versions, content counts, size on disk and
+/// play statistics, as JSON or CSV
+#[tracing::instrument]
+pub async fn export_inventory(
+    format: inventory::InventoryFormat,
+) -> crate::Result<String> {
+    let mut profiles =
+        list(Some(false)).await?.into_values().collect::<Vec<_>>();
+    profiles.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+
+    let mut entries = Vec::with_capacity(profiles.len());
+    for profile in &profiles {
+        entries.push(inventory::get_entry(profile).await?);
+    }
+
+    inventory::serialize(&entries, format)
+}
+
+/// Gets how much space a profile takes up, by mods, worlds, resource packs,
+/// logs, screenshots and configs. Walking a profile is slow, so results are
+/// reused for a few minutes unless `refresh` is set
+#[tracing::instrument]
+pub async fn get_disk_usage(
+    profile_path: &ProfilePathId,
+    refresh: bool,
+) -> crate::Result<disk_usage::DiskUsage> {
+    let profile_path = get(profile_path, Some(true))
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?
+        .profile_id();
+    disk_usage::get(&profile_path, refresh).await
+}
+
+/// Checks the enabled mods of a profile for duplicated mod ids, mods built for
+/// another loader, and declared conflicts, so the game doesn't crash on launch
+#[tracing::instrument]
+pub async fn check_compatibility(
+    profile_path: &ProfilePathId,
+) -> crate::Result<compatibility::CompatibilityReport> {
+    if let Some(profile) = get(profile_path, None).await? {
+        compatibility::check_profile(&profile).await
     } else {
         Err(
             crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
@@ -457,6 +732,15 @@ pub async fn add_project_from_path(
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
+        let sha1 =
+            crate::util::fetch::sha1_async(bytes::Bytes::from(file.clone()))
+                .await?;
+        policy::check_files(&[policy::PolicyFile {
+            path: file_name.clone(),
+            project_id: None,
+            sha1: Some(sha1),
+        }])
+        .await?;
 
         let path = profile
             .add_project_bytes(
@@ -465,6 +749,7 @@ pub async fn add_project_from_path(
                 project_type.and_then(|x| serde_json::from_str(&x).ok()),
             )
             .await?;
+        flag_locked_modifications(profile_path).await?;
 
         emit_profile(
             profile.uuid,
@@ -487,6 +772,7 @@ pub async fn add_project_from_path(
 /// Toggle whether a project is disabled or not
 /// Project path should be relative to the profile
 /// returns the new state, relative to the profile
+/// Disabled projects are remembered, so pack updates keep them disabled
 #[tracing::instrument]
 pub async fn toggle_disable_project(
     profile_path: &ProfilePathId,
@@ -494,6 +780,7 @@ pub async fn toggle_disable_project(
 ) -> crate::Result<ProjectPathId> {
     if let Some(profile) = get(profile_path, None).await? {
         let res = profile.toggle_disable_project(project).await?;
+        flag_locked_modifications(profile_path).await?;
 
         emit_profile(
             profile.uuid,
@@ -513,6 +800,107 @@ pub async fn toggle_disable_project(
     }
 }
 
+/// Projects affected by toggling a project with `toggle_project`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ToggleReport {
+    /// New paths of every toggled project, the requested one first
+    pub toggled: Vec<ProjectPathId>,
+    /// Mods left in place whose requirements are no longer met: enabled mods
+    /// requiring a disabled one, or disabled mods an enabled one requires
+    pub unmet: Vec<ProjectPathId>,
+}
+
+/// Enables or disables a project, taking the mods it is linked to along
+/// with `cascade`. Disabling a library cascades to the mods requiring it,
+/// enabling a mod cascades to the disabled mods it requires. Without
+/// `cascade` those are only reported.
+/// Disabled projects are remembered, so pack updates keep them disabled
+#[tracing::instrument]
+pub async fn toggle_project(
+    profile_path: &ProfilePathId,
+    project: &ProjectPathId,
+    cascade: bool,
+) -> crate::Result<ToggleReport> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+            .as_error()
+    })?;
+    let Some(disabling) = profile.projects.get(project).map(|x| !x.disabled)
+    else {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Project path does not exist: {:?}",
+            project.0
+        ))
+        .into());
+    };
+
+    let required = compatibility::required_projects(&profile).await?;
+    let is_disabled = |path: &ProjectPathId| {
+        profile.projects.get(path).map_or(false, |x| x.disabled)
+    };
+
+    // Walks the graph outwards from the toggled project, towards dependents
+    // of a disabled library or dependencies of an enabled mod
+    let mut linked: Vec<ProjectPathId> = Vec::new();
+    let mut queue = vec![project.clone()];
+    while let Some(current) = queue.pop() {
+        let next = if disabling {
+            required
+                .iter()
+                .filter(|(path, x)| !is_disabled(path) && x.contains(&current))
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>()
+        } else {
+            required
+                .get(&current)
+                .into_iter()
+                .flatten()
+                .filter(|path| is_disabled(path))
+                .cloned()
+                .collect()
+        };
+        for path in next {
+            if path != *project && !linked.contains(&path) {
+                linked.push(path.clone());
+                queue.push(path);
+            }
+        }
+    }
+    linked.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut report = ToggleReport::default();
+    let mut toggled = vec![project.clone()];
+    if cascade {
+        toggled.extend(linked);
+    } else {
+        if !linked.is_empty() {
+            tracing::warn!(
+                "Toggling {:?} leaves requirements unmet for {:?}",
+                project.0,
+                linked
+            );
+        }
+        report.unmet = linked;
+    }
+
+    for path in toggled {
+        let new_path = profile.toggle_disable_project(&path).await?;
+        report.toggled.push(new_path);
+    }
+    flag_locked_modifications(profile_path).await?;
+
+    emit_profile(
+        profile.uuid,
+        profile_path,
+        &profile.metadata.name,
+        ProfilePayloadType::Edited,
+    )
+    .await?;
+    State::sync().await?;
+
+    Ok(report)
+}
+
 /// Remove a project from a profile
 /// Uses and returns the relative path to the project
 #[tracing::instrument]
@@ -522,6 +910,7 @@ pub async fn remove_project(
 ) -> crate::Result<()> {
     if let Some(profile) = get(profile_path, None).await? {
         profile.remove_project(project, None).await?;
+        flag_locked_modifications(profile_path).await?;
 
         emit_profile(
             profile.uuid,
@@ -541,103 +930,1216 @@ pub async fn remove_project(
     }
 }
 
-/// Gets whether project is a managed modrinth pack
+/// Pins or unpins a managed modrinth pack to its installed version
+/// Pinned packs are skipped by update checks, so several profiles can keep
+/// different versions of the same pack side by side
 #[tracing::instrument]
-pub async fn is_managed_modrinth_pack(
+pub async fn set_managed_modrinth_pinned(
     profile: &ProfilePathId,
-) -> crate::Result<bool> {
-    if let Some(profile) = get(profile, None).await? {
-        if let Some(linked_data) = profile.metadata.linked_data {
-            return Ok(linked_data.project_id.is_some()
-                && linked_data.version_id.is_some());
-        }
-        Ok(false)
-    } else {
-        Err(crate::ErrorKind::UnmanagedProfileError(profile.to_string())
-            .as_error())
+    pinned: bool,
+) -> crate::Result<()> {
+    if !is_managed_modrinth_pack(profile).await? {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile} is not a managed modrinth pack"
+        ))
+        .as_error());
     }
+
+    edit(profile, |prof| {
+        if let Some(linked_data) = &mut prof.metadata.linked_data {
+            linked_data.pinned = pinned;
+        }
+        if pinned {
+            prof.modrinth_update_version = None;
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
 }
 
-/// Exports the profile to a Modrinth-formatted .mrpack file
-// Version ID of uploaded version (ie 1.1.5), not the unique identifying ID of the version (nvrqJg44)
-#[tracing::instrument(skip_all)]
-#[theseus_macros::debug_pin]
-pub async fn export_mrpack(
-    profile_path: &ProfilePathId,
-    export_path: PathBuf,
-    included_overrides: Vec<String>, // which folders to include in the overrides
-    version_id: Option<String>,
+/// Sets which versions of a managed modrinth pack are offered as updates,
+/// and checks for updates on the new channel
+#[tracing::instrument]
+pub async fn set_managed_modrinth_update_channel(
+    profile: &ProfilePathId,
+    update_channel: UpdateChannel,
 ) -> crate::Result<()> {
-    let state = State::get().await?;
-    let io_semaphore = state.io_semaphore.0.read().await;
-    let _permit: tokio::sync::SemaphorePermit = io_semaphore.acquire().await?;
-    let profile = get(profile_path, None).await?.ok_or_else(|| {
-        crate::ErrorKind::OtherError(format!(
-            "Tried to export a nonexistent or unloaded profile at path {}!",
-            profile_path
+    if !is_managed_modrinth_pack(profile).await? {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile} is not a managed modrinth pack"
         ))
-    })?;
+        .as_error());
+    }
 
-    // remove .DS_Store files from included_overrides
-    let included_overrides = included_overrides
-        .into_iter()
-        .filter(|x| {
-            if let Some(f) = PathBuf::from(x).file_name() {
-                if f.to_string_lossy().starts_with(".DS_Store") {
-                    return false;
-                }
-            }
-            true
-        })
-        .collect::<Vec<_>>();
+    edit(profile, |prof| {
+        if let Some(linked_data) = &mut prof.metadata.linked_data {
+            linked_data.update_channel = update_channel;
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
 
-    let profile_base_path = &profile.get_profile_full_path().await?;
+    tokio::task::spawn(Profiles::update_modrinth_versions());
+    Ok(())
+}
 
-    let mut file = File::create(&export_path)
-        .await
-        .map_err(|e| IOError::with_path(e, &export_path))?;
-    let mut writer = ZipFileWriter::new(&mut file);
+/// Locks or unlocks a managed modrinth pack to the projects it installed
+/// Projects added or removed by hand while locked are flagged, and the next
+/// update of the pack restores the ones it installed
+#[tracing::instrument]
+pub async fn set_managed_modrinth_locked(
+    profile: &ProfilePathId,
+    locked: bool,
+) -> crate::Result<()> {
+    if !is_managed_modrinth_pack(profile).await? {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile} is not a managed modrinth pack"
+        ))
+        .as_error());
+    }
 
-    // Create mrpack json configuration file
-    let version_id = version_id.unwrap_or("1.0.0".to_string());
-    let packfile = create_mrpack_json(&profile, version_id).await?;
-    let modrinth_path_list = get_modrinth_pack_list(&packfile);
+    edit(profile, |prof| {
+        if let Some(linked_data) = &mut prof.metadata.linked_data {
+            linked_data.locked = locked;
+        }
+        prof.flag_locked_modifications();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
 
-    // Build vec of all files in the folder
-    let mut path_list = Vec::new();
-    build_folder(profile_base_path, &mut path_list).await?;
+    Ok(())
+}
 
-    // Initialize loading bar
-    let loading_bar = init_loading(
-        LoadingBarType::ZipExtract {
-            profile_path: profile.get_profile_full_path().await?,
-            profile_name: profile.metadata.name.clone(),
-        },
-        path_list.len() as f64,
-        "Exporting profile to .mrpack",
-    )
+/// Converts a managed modrinth pack to a regular profile, keeping every file
+/// it has. The profile no longer receives updates of the pack
+#[tracing::instrument]
+pub async fn unlock(profile: &ProfilePathId) -> crate::Result<()> {
+    if !is_managed_modrinth_pack(profile).await? {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile} is not a managed modrinth pack"
+        ))
+        .as_error());
+    }
+
+    edit(profile, |prof| {
+        prof.metadata.linked_data = None;
+        prof.modrinth_update_version = None;
+        async { Ok(()) }
+    })
     .await?;
+    State::sync().await?;
 
-    // Iterate over every file in the folder
-    // Every file that is NOT in the config file is added to the zip, in overrides
-    for path in path_list {
-        emit_loading(&loading_bar, 1.0, None).await?;
+    Ok(())
+}
 
-        // Get local path of file, relative to profile folder
-        let relative_path = path.strip_prefix(profile_base_path)?;
+// Records changes made through the API to the projects of a locked pack
+async fn flag_locked_modifications(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let mut profiles = state.profiles.write().await;
+    if let Some(profile) = profiles.0.get_mut(profile_path) {
+        profile.flag_locked_modifications();
+    }
+    Ok(())
+}
 
-        // Get highest level folder pair ('a/b' in 'a/b/c', 'a' in 'a')
-        // We only go one layer deep for the sake of not having a huge list of overrides
-        let topmost_two = relative_path.iter().take(2).collect::<Vec<_>>();
+/// Projects changed by syncing a profile with its linked collection
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CollectionSyncReport {
+    pub added: Vec<ProjectPathId>,
+    pub removed: Vec<ProjectPathId>,
+    /// Projects of the collection without a version for the profile
+    pub unavailable: Vec<String>,
+    /// Projects that could not be added or removed, with the error
+    pub failed: HashMap<String, String>,
+}
 
-        // a,b => a/b
-        // a => a
-        let topmost = match topmost_two.len() {
-            2 => PathBuf::from(topmost_two[0]).join(topmost_two[1]),
-            1 => PathBuf::from(topmost_two[0]),
-            _ => {
-                return Err(crate::ErrorKind::OtherError(
-                    "No topmost folder found".to_string(),
+/// Links a profile to a Modrinth collection and installs its projects
+#[tracing::instrument]
+pub async fn link_collection(
+    profile_path: &ProfilePathId,
+    collection_id: String,
+) -> crate::Result<CollectionSyncReport> {
+    edit(profile_path, |prof| {
+        // Relinking the same collection keeps track of what it installed
+        if prof.collection.as_ref().map(|x| &x.id) != Some(&collection_id) {
+            prof.collection = Some(crate::state::LinkedCollection {
+                id: collection_id.clone(),
+                name: String::new(),
+                projects: HashMap::new(),
+            });
+        }
+        async { Ok(()) }
+    })
+    .await?;
+
+    sync_collection(profile_path).await
+}
+
+/// Unlinks a profile from its collection, keeping the installed projects
+#[tracing::instrument]
+pub async fn unlink_collection(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    edit(profile_path, |prof| {
+        prof.collection = None;
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Installs compatible versions of projects added to a profile's linked
+/// collection, and removes the ones that were taken out of it
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn sync_collection(
+    profile_path: &ProfilePathId,
+) -> crate::Result<CollectionSyncReport> {
+    #[derive(Deserialize)]
+    struct Collection {
+        name: String,
+        projects: Vec<String>,
+    }
+
+    let state = State::get().await?;
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let Some(mut linked) = profile.collection.clone() else {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is not linked to a collection"
+        ))
+        .as_error());
+    };
+
+    let creds = state.credentials.read().await;
+    let collection = crate::util::fetch::fetch_json::<Collection>(
+        reqwest::Method::GET,
+        &format!(
+            "{}collection/{}",
+            crate::config::MODRINTH_API_URL_V3,
+            linked.id
+        ),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+    drop(creds);
+
+    let mut report = CollectionSyncReport::default();
+    let removed = linked
+        .projects
+        .keys()
+        .filter(|x| !collection.projects.contains(x))
+        .cloned()
+        .collect::<Vec<_>>();
+    // A project that fails is reported and retried on the next sync, without
+    // holding back the rest of the collection
+    for project_id in removed {
+        let Some(path) = linked.projects.get(&project_id).cloned() else {
+            continue;
+        };
+        if profile.projects.contains_key(&path) {
+            if let Err(err) = profile.remove_project(&path, None).await {
+                report.failed.insert(project_id, err.to_string());
+                continue;
+            }
+            report.removed.push(path);
+        }
+        linked.projects.remove(&project_id);
+    }
+
+    let mut targets = Vec::new();
+    for project_id in &collection.projects {
+        if linked.projects.contains_key(project_id) {
+            continue;
+        }
+
+        let versions = match crate::sources::get_versions(
+            crate::state::MODRINTH_SOURCE,
+            project_id,
+            Some(profile_path),
+        )
+        .await
+        {
+            Ok(versions) => versions,
+            Err(err) => {
+                report.failed.insert(project_id.clone(), err.to_string());
+                continue;
+            }
+        };
+        match versions.first() {
+            Some(version) => targets.push((project_id, version.id.clone())),
+            None => report.unavailable.push(project_id.clone()),
+        }
+    }
+
+    // The full versions are fetched together, rather than one per project
+    let ids = targets.iter().map(|(_, x)| x.clone()).collect::<Vec<_>>();
+    let creds = state.credentials.read().await;
+    let fetched =
+        crate::state::fetch_versions(&ids, &state.fetch_semaphore, &creds)
+            .await;
+    drop(creds);
+    let mut versions = match fetched {
+        Ok(versions) => versions,
+        Err(err) => {
+            let err = err.to_string();
+            for (project_id, _) in targets.drain(..) {
+                report.failed.insert(project_id.clone(), err.clone());
+            }
+            HashMap::new()
+        }
+    };
+    for (project_id, version_id) in targets {
+        let Some(version) = versions.remove(&version_id) else {
+            report.unavailable.push(project_id.clone());
+            continue;
+        };
+        match profile.add_project_modrinth_version(&version).await {
+            Ok(path) => {
+                linked.projects.insert(project_id.clone(), path.clone());
+                report.added.push(path);
+            }
+            Err(err) => {
+                report.failed.insert(project_id.clone(), err.to_string());
+            }
+        }
+    }
+    for (project_id, err) in &report.failed {
+        tracing::warn!("Failed to sync collection project {project_id}: {err}");
+    }
+
+    linked.name = collection.name;
+    edit(profile_path, |prof| {
+        prof.collection = Some(linked.clone());
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(report)
+}
+
+/// Marks a Modrinth project as critical for a profile, so it is updated, or
+/// installed at the given version, before every launch
+#[tracing::instrument]
+pub async fn set_critical_project(
+    profile_path: &ProfilePathId,
+    project_id: String,
+    version_id: Option<String>,
+) -> crate::Result<()> {
+    edit(profile_path, |prof| {
+        prof.critical_projects
+            .retain(|x| x.project_id != project_id);
+        prof.critical_projects.push(crate::state::CriticalProject {
+            project_id: project_id.clone(),
+            version_id: version_id.clone(),
+        });
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Stops treating a project as critical, leaving it installed
+#[tracing::instrument]
+pub async fn remove_critical_project(
+    profile_path: &ProfilePathId,
+    project_id: &str,
+) -> crate::Result<()> {
+    edit(profile_path, |prof| {
+        prof.critical_projects
+            .retain(|x| x.project_id != project_id);
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Sets the server a profile is made for, or clears it
+#[tracing::instrument]
+pub async fn set_primary_server(
+    profile_path: &ProfilePathId,
+    server: Option<crate::state::PrimaryServer>,
+) -> crate::Result<()> {
+    edit(profile_path, |prof| {
+        prof.primary_server = server.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Sets whether reinstalling a profile fails when its loader libraries or
+/// processor outputs no longer match the hashes recorded for them. A profile
+/// without recorded hashes is repaired first, to record them
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn set_reproducible_install(
+    profile_path: &ProfilePathId,
+    reproducible: bool,
+) -> crate::Result<()> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    if reproducible && profile.loader_pins.is_none() {
+        install(profile_path).await?;
+    }
+
+    edit(profile_path, |prof| {
+        if let Some(pins) = &mut prof.loader_pins {
+            pins.reproducible = reproducible;
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Reads the resource pack of a profile's primary server from the server's
+/// server.properties, given as a file path or URL
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn load_server_properties(
+    profile_path: &ProfilePathId,
+    source: &str,
+) -> crate::Result<Option<crate::state::ServerResourcePack>> {
+    let is_url =
+        source.starts_with("http://") || source.starts_with("https://");
+    let properties = if is_url {
+        let state = State::get().await?;
+        let bytes = crate::util::fetch::fetch(
+            source,
+            None,
+            &state.fetch_semaphore,
+            &crate::state::CredentialsStore(None),
+        )
+        .await?;
+        String::from_utf8_lossy(&bytes).to_string()
+    } else {
+        io::read_to_string(source).await?
+    };
+    let resource_pack = server::parse_server_properties(&properties);
+
+    edit(profile_path, |prof| {
+        let res = match &mut prof.primary_server {
+            Some(server) => {
+                server.resource_pack = resource_pack.clone();
+                Ok(())
+            }
+            None => Err(crate::ErrorKind::InputError(format!(
+                "Profile {profile_path} has no primary server"
+            ))
+            .as_error()),
+        };
+        async { res }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(resource_pack)
+}
+
+/// Installs the required versions of the critical projects of a profile,
+/// returning the paths of the projects that changed. Fails if one of them
+/// can't be installed, as the profile isn't playable without it
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn update_critical_projects(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<ProjectPathId>> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+
+    let mut updated = Vec::new();
+    for critical in &profile.critical_projects {
+        let installed =
+            profile
+                .projects
+                .iter()
+                .find_map(|(path, project)| match &project.metadata {
+                    ProjectMetadata::Modrinth {
+                        project, version, ..
+                    } if project.id == critical.project_id => {
+                        Some((path.clone(), version.id.clone()))
+                    }
+                    _ => None,
+                });
+
+        let target = match &critical.version_id {
+            Some(version_id) => Ok(Some(version_id.clone())),
+            None => crate::sources::get_versions(
+                crate::state::MODRINTH_SOURCE,
+                &critical.project_id,
+                Some(profile_path),
+            )
+            .await
+            .map(|x| x.first().map(|x| x.id.clone())),
+        };
+        let target = match (target, &installed) {
+            (Ok(Some(target)), _) => target,
+            (Ok(None), _) => {
+                return Err(crate::ErrorKind::CriticalProjectError(
+                    critical.project_id.clone(),
+                    "no version is compatible with this profile".to_string(),
+                )
+                .as_error())
+            }
+            // Playing with the installed version beats not playing offline
+            (Err(err), Some(_)) => {
+                tracing::warn!(
+                    "Unable to check {} for updates: {err}",
+                    critical.project_id
+                );
+                continue;
+            }
+            (Err(err), None) => {
+                return Err(crate::ErrorKind::CriticalProjectError(
+                    critical.project_id.clone(),
+                    err.to_string(),
+                )
+                .as_error())
+            }
+        };
+
+        if installed.as_ref().map(|x| &x.1) == Some(&target) {
+            continue;
+        }
+
+        let (path, _) =
+            profile.add_project_version(target).await.map_err(|err| {
+                crate::ErrorKind::CriticalProjectError(
+                    critical.project_id.clone(),
+                    err.to_string(),
+                )
+            })?;
+        if let Some((old_path, _)) = installed {
+            if old_path != path {
+                profile.remove_project(&old_path, None).await?;
+            }
+        }
+        updated.push(path);
+    }
+
+    if !updated.is_empty() {
+        emit_profile(
+            profile.uuid,
+            profile_path,
+            &profile.metadata.name,
+            ProfilePayloadType::Edited,
+        )
+        .await?;
+        State::sync().await?;
+    }
+
+    Ok(updated)
+}
+
+/// File the contents of an archived profile are compressed into
+pub const ARCHIVE_FILE_NAME: &str = "archive.zip";
+// The archive while it is written, renamed once complete
+const ARCHIVE_PARTIAL_FILE_NAME: &str = "archive.zip.part";
+
+/// Compresses the contents of an unused profile into a single zstd archive
+/// inside its folder, keeping only its metadata. The profile stays listed,
+/// but can't be launched until it is unarchived. The profile is marked
+/// archived before its contents are removed, so archiving it again removes
+/// whatever a failed removal left behind
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn archive(profile_path: &ProfilePathId) -> crate::Result<()> {
+    let state = State::get().await?;
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let full_path = profile.get_profile_full_path().await?;
+    if profile.archived.is_some() {
+        return remove_archived_contents(&full_path).await;
+    }
+    let running = state.children.read().await.running_profile_paths().await?;
+    if running.contains(profile_path) {
+        return Err(crate::ErrorKind::ProfileRunning {
+            profile_path: profile_path.to_string(),
+        }
+        .as_error());
+    }
+
+    // An archive left without the profile being marked archived is never
+    // written over, it may be the only copy of contents since removed
+    let archive_path = full_path.join(ARCHIVE_FILE_NAME);
+    if archive_path.exists() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} already holds an archive at {}, move it \
+             away to archive the profile again",
+            archive_path.display()
+        ))
+        .as_error());
+    }
+
+    let partial_path = full_path.join(ARCHIVE_PARTIAL_FILE_NAME);
+    let (original_size, archive_size) = {
+        let full_path = full_path.clone();
+        let partial_path = partial_path.clone();
+        tokio::task::spawn_blocking(move || {
+            write_archive(&full_path, &partial_path)
+        })
+        .await?
+        .map_err(|e| IOError::with_path(e, &partial_path))?
+    };
+    io::rename(&partial_path, &archive_path).await?;
+
+    edit(profile_path, |prof| {
+        prof.archived = Some(crate::state::ArchiveInfo {
+            date_archived: chrono::Utc::now(),
+            original_size,
+            archive_size,
+        });
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    // Only remove the contents once the archive is complete and recorded
+    remove_archived_contents(&full_path).await
+}
+
+// Removes everything the archive of a profile holds from its folder
+async fn remove_archived_contents(full_path: &Path) -> crate::Result<()> {
+    let mut read_dir = io::read_dir(full_path).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, full_path))?
+    {
+        let path = entry.path();
+        if is_archive_excluded(full_path, &path) {
+            continue;
+        }
+        if path.is_dir() {
+            io::remove_dir_all(&path).await?;
+        } else {
+            io::remove_file(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores the contents of an archived profile and removes its archive
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn unarchive(profile_path: &ProfilePathId) -> crate::Result<()> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    if profile.archived.is_none() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is not archived"
+        ))
+        .as_error());
+    }
+
+    let full_path = profile.get_profile_full_path().await?;
+    let archive_path = full_path.join(ARCHIVE_FILE_NAME);
+    {
+        let full_path = full_path.clone();
+        let archive_path = archive_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&archive_path)
+                .map_err(|e| IOError::with_path(e, &archive_path))?;
+            zip::ZipArchive::new(file)
+                .and_then(|mut x| x.extract(&full_path))
+                .map_err(|err| {
+                    crate::ErrorKind::InputError(format!(
+                        "Failed to extract profile archive: {err}"
+                    ))
+                })?;
+            Ok::<(), crate::Error>(())
+        })
+        .await??;
+    }
+    io::remove_file(&archive_path).await?;
+
+    edit(profile_path, |prof| {
+        prof.archived = None;
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+    Profile::sync_projects_task(profile_path.clone());
+
+    Ok(())
+}
+
+// The profile metadata stays readable while archived
+fn is_archive_excluded(full_path: &Path, path: &Path) -> bool {
+    path == full_path.join(crate::state::PROFILE_JSON_PATH)
+        || path == full_path.join(ARCHIVE_FILE_NAME)
+        || path == full_path.join(ARCHIVE_PARTIAL_FILE_NAME)
+}
+
+// Streams every file of a profile into a zstd compressed zip, returning the
+// size of the contents and of the archive
+fn write_archive(
+    full_path: &Path,
+    archive_path: &Path,
+) -> std::io::Result<(u64, u64)> {
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Zstd)
+        .large_file(true);
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(archive_path)?);
+
+    let mut original_size = 0;
+    let mut dirs = vec![full_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if is_archive_excluded(full_path, &path) {
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(full_path)
+                .unwrap_or(&path)
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            // Directories are added too, so empty ones are restored
+            if path.is_dir() {
+                writer.add_directory(name, options)?;
+                dirs.push(path);
+                continue;
+            }
+
+            writer.start_file(name, options)?;
+            original_size +=
+                std::io::copy(&mut std::fs::File::open(&path)?, &mut writer)?;
+        }
+    }
+    writer.finish()?;
+
+    Ok((original_size, std::fs::metadata(archive_path)?.len()))
+}
+
+/// Switches where a profile keeps its libraries, assets and natives
+/// The game files are downloaded again to the new location on next launch
+#[tracing::instrument]
+pub async fn set_storage_mode(
+    profile: &ProfilePathId,
+    storage_mode: StorageMode,
+) -> crate::Result<()> {
+    edit(profile, |prof| {
+        if prof.storage_mode != storage_mode {
+            prof.storage_mode = storage_mode;
+            if prof.install_stage == ProfileInstallStage::Installed {
+                prof.install_stage = ProfileInstallStage::NotInstalled;
+            }
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Lists the LWJGL versions a profile can be launched with, newest first.
+/// Empty for game versions shipping LWJGL 2, which can't be overridden
+#[tracing::instrument]
+pub async fn get_lwjgl_versions(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+    let version_info = lwjgl_version_info(&state, profile_path).await?;
+    crate::launcher::lwjgl::compatible_versions(&state, &version_info).await
+}
+
+/// Sets the LWJGL version a profile is launched with, replacing the one its
+/// game ships with, or goes back to that one with `None`. The libraries are
+/// downloaded on next launch
+#[tracing::instrument]
+pub async fn set_lwjgl_version(
+    profile_path: &ProfilePathId,
+    version: Option<String>,
+) -> crate::Result<()> {
+    if let Some(version) = &version {
+        let state = State::get().await?;
+        let version_info = lwjgl_version_info(&state, profile_path).await?;
+        crate::launcher::lwjgl::validate(&state, &version_info, version)
+            .await?;
+    }
+
+    edit(profile_path, |prof| {
+        if prof.lwjgl_version != version {
+            prof.lwjgl_version = version.clone();
+            if prof.install_stage == ProfileInstallStage::Installed {
+                prof.install_stage = ProfileInstallStage::NotInstalled;
+            }
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Sets the LWJGL version a profile is launched with on Java runtimes of an
+/// architecture, such as `arm64` or `x86_64`, or clears it with `None`. It
+/// takes precedence over the one set for every architecture, for natives
+/// only newer builds have
+#[tracing::instrument]
+pub async fn set_lwjgl_arch_version(
+    profile_path: &ProfilePathId,
+    java_arch: &str,
+    version: Option<String>,
+) -> crate::Result<()> {
+    let java_arch =
+        crate::util::platform::normalize_arch(java_arch).to_string();
+    if let Some(version) = &version {
+        let state = State::get().await?;
+        let version_info = lwjgl_version_info(&state, profile_path).await?;
+        crate::launcher::lwjgl::validate(&state, &version_info, version)
+            .await?;
+    }
+
+    edit(profile_path, |prof| {
+        let previous = prof.lwjgl_arch_versions.get(&java_arch);
+        if previous != version.as_ref() {
+            match &version {
+                Some(version) => {
+                    prof.lwjgl_arch_versions
+                        .insert(java_arch.clone(), version.clone());
+                }
+                None => {
+                    prof.lwjgl_arch_versions.remove(&java_arch);
+                }
+            }
+            if prof.install_stage == ProfileInstallStage::Installed {
+                prof.install_stage = ProfileInstallStage::NotInstalled;
+            }
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+// Version info of the game and loader of a profile, as shipped
+async fn lwjgl_version_info(
+    state: &State,
+    profile_path: &ProfilePathId,
+) -> crate::Result<daedalus::minecraft::VersionInfo> {
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    let version = state
+        .metadata
+        .read()
+        .await
+        .minecraft
+        .versions
+        .iter()
+        .find(|it| it.id == profile.metadata.game_version)
+        .cloned()
+        .ok_or_else(|| {
+            crate::ErrorKind::InvalidGameVersion(
+                profile.metadata.game_version.clone(),
+            )
+        })?;
+
+    crate::launcher::download::download_version_info(
+        state,
+        &version,
+        profile.metadata.loader_version.as_ref(),
+        None,
+        None,
+    )
+    .await
+}
+
+/// Lists the Fabric or Quilt loader versions available for a game version,
+/// newest first, to pick one to pin a development profile to
+#[tracing::instrument]
+pub async fn get_development_loader_versions(
+    game_version: String,
+    loader: ModLoader,
+) -> crate::Result<Vec<LoaderVersion>> {
+    let state = State::get().await?;
+    let metadata = state.metadata.read().await;
+
+    let loader_data = match loader {
+        ModLoader::Fabric => &metadata.fabric,
+        ModLoader::Quilt => &metadata.quilt,
+        _ => {
+            return Err(crate::ErrorKind::InputError(format!(
+                "Development profiles are not supported for {loader}"
+            ))
+            .as_error())
+        }
+    };
+
+    Ok(loader_data
+        .game_versions
+        .iter()
+        .find(|it| {
+            it.id
+                .replace(daedalus::modded::DUMMY_REPLACE_STRING, &game_version)
+                == game_version
+        })
+        .map(|x| x.loaders.clone())
+        .unwrap_or_default())
+}
+
+/// Sets or clears the main class and game argument overrides of a profile,
+/// failing if they are invalid
+#[tracing::instrument]
+pub async fn set_launch_overrides(
+    profile_path: &ProfilePathId,
+    overrides: Option<LaunchOverrides>,
+) -> crate::Result<()> {
+    let overrides = overrides.filter(|x| !x.is_empty());
+    if let Some(overrides) = &overrides {
+        overrides.validate()?;
+    }
+
+    edit(profile_path, |prof| {
+        prof.launch_overrides = overrides.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
+
+/// Sets or clears the settings a profile forces into the game's options.txt
+/// on every launch, failing if any can't be written to the file
+#[tracing::instrument]
+pub async fn set_game_option_overrides(
+    profile_path: &ProfilePathId,
+    overrides: Option<GameOptionOverrides>,
+) -> crate::Result<()> {
+    let overrides = overrides.filter(|x| !x.options.is_empty());
+    if let Some(overrides) = &overrides {
+        let options = overrides
+            .options
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_string()))
+            .collect::<Vec<_>>();
+        game_options::validate_options(&options)?;
+    }
+
+    edit(profile_path, |prof| {
+        prof.game_options = overrides.clone();
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
+
+/// Sets or clears the development settings of a Fabric or Quilt profile.
+/// If a loader version is given, the profile is pinned to it
+#[tracing::instrument]
+pub async fn set_development(
+    profile_path: &ProfilePathId,
+    development: Option<DevelopmentSettings>,
+    loader_version: Option<String>,
+) -> crate::Result<()> {
+    let profile = get(profile_path, Some(true)).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    if development.is_some()
+        && !matches!(
+            profile.metadata.loader,
+            ModLoader::Fabric | ModLoader::Quilt
+        )
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Development profiles are not supported for {}",
+            profile.metadata.loader
+        ))
+        .as_error());
+    }
+
+    let loader_version = match loader_version {
+        Some(version) => {
+            create::get_loader_version_from_loader(
+                profile.metadata.game_version.clone(),
+                profile.metadata.loader,
+                Some(version),
+            )
+            .await?
+        }
+        None => None,
+    };
+
+    edit(profile_path, |prof| {
+        // Assets that were skipped are downloaded by reinstalling
+        let skipped_assets = prof.skips_assets();
+        prof.development = development.clone();
+        if skipped_assets && !prof.skips_assets() {
+            prof.install_stage = ProfileInstallStage::NotInstalled;
+        }
+        if let Some(loader_version) = &loader_version {
+            if prof.metadata.loader_version.as_ref().map(|x| &x.id)
+                != Some(&loader_version.id)
+            {
+                prof.metadata.loader_version = Some(loader_version.clone());
+                prof.install_stage = ProfileInstallStage::NotInstalled;
+            }
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    Ok(())
+}
+
+/// Switches a profile to another mod loader, or another version of its
+/// current one, and reinstalls it.
+/// Mods are kept, and the ones built for a different loader are returned
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn change_loader(
+    profile_path: &ProfilePathId,
+    loader: ModLoader,
+    loader_version: Option<String>,
+) -> crate::Result<Vec<ProjectPathId>> {
+    let state = State::get().await?;
+    let profile = get(profile_path, Some(true)).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+
+    let loader_version = if loader != ModLoader::Vanilla {
+        create::get_loader_version_from_loader(
+            profile.metadata.game_version.clone(),
+            loader,
+            loader_version,
+        )
+        .await?
+    } else {
+        None
+    };
+    if loader != ModLoader::Vanilla && loader_version.is_none() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "No {loader} version is available for {}",
+            profile.metadata.game_version
+        ))
+        .as_error());
+    }
+
+    if profile.metadata.loader == loader
+        && profile.metadata.loader_version.as_ref().map(|x| &x.id)
+            == loader_version.as_ref().map(|x| &x.id)
+    {
+        return Ok(Vec::new());
+    }
+
+    // The version jar and natives of the previous loader are removed, unless
+    // another profile still uses them. Vanilla ones are kept, as they are
+    // shared by every profile of that game version
+    let old_version_jar = get_version_jar(&profile);
+    let in_use = state
+        .profiles
+        .read()
+        .await
+        .0
+        .values()
+        .filter(|x| x.profile_id() != profile.profile_id())
+        .any(|x| get_version_jar(x) == old_version_jar);
+    if profile.metadata.loader != ModLoader::Vanilla && !in_use {
+        let game_dirs = state.directories.game_dirs(&profile).await?;
+        for dir in [
+            state.directories.version_dir(&old_version_jar).await,
+            game_dirs.version_natives_dir(&old_version_jar),
+        ] {
+            if dir.exists() {
+                io::remove_dir_all(&dir).await?;
+            }
+        }
+    }
+
+    edit(profile_path, |prof| {
+        prof.metadata.loader = loader;
+        prof.metadata.loader_version = loader_version.clone();
+        prof.install_stage = ProfileInstallStage::NotInstalled;
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await?;
+
+    let profile = get(profile_path, Some(true)).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    crate::launcher::install_minecraft(&profile, None).await?;
+
+    let report = compatibility::check_profile(&profile).await?;
+    Ok(report
+        .issues
+        .into_iter()
+        .filter_map(|x| match x {
+            compatibility::CompatibilityIssue::LoaderMismatch {
+                path, ..
+            } => Some(path),
+            _ => None,
+        })
+        .collect())
+}
+
+// Id of the version folder a profile's game is installed into
+fn get_version_jar(profile: &Profile) -> String {
+    let game_version = &profile.metadata.game_version;
+    profile
+        .metadata
+        .loader_version
+        .as_ref()
+        .map_or(game_version.clone(), |x| format!("{game_version}-{}", x.id))
+}
+
+/// Gets whether project is a managed modrinth pack
+#[tracing::instrument]
+pub async fn is_managed_modrinth_pack(
+    profile: &ProfilePathId,
+) -> crate::Result<bool> {
+    if let Some(profile) = get(profile, None).await? {
+        if let Some(linked_data) = profile.metadata.linked_data {
+            return Ok(linked_data.project_id.is_some()
+                && linked_data.version_id.is_some());
+        }
+        Ok(false)
+    } else {
+        Err(crate::ErrorKind::UnmanagedProfileError(profile.to_string())
+            .as_error())
+    }
+}
+
+/// Exports the profile to a Modrinth-formatted .mrpack file
+// Version ID of uploaded version (ie 1.1.5), not the unique identifying ID of the version (nvrqJg44)
+#[tracing::instrument(skip_all)]
+#[theseus_macros::debug_pin]
+pub async fn export_mrpack(
+    profile_path: &ProfilePathId,
+    export_path: PathBuf,
+    included_overrides: Vec<String>, // which folders to include in the overrides
+    version_id: Option<String>,
+) -> crate::Result<()> {
+    let mut file = File::create(&export_path)
+        .await
+        .map_err(|e| IOError::with_path(e, &export_path))?;
+    export_mrpack_to_writer(
+        profile_path,
+        &mut file,
+        included_overrides,
+        version_id,
+    )
+    .await
+}
+
+/// Streams a Modrinth-formatted .mrpack of the profile into a writer, like
+/// the body of an upload or stdout, so it never has to be written to disk.
+/// Progress is reported on a loading bar as files are added
+#[tracing::instrument(skip_all)]
+#[theseus_macros::debug_pin]
+pub async fn export_mrpack_to_writer<W>(
+    profile_path: &ProfilePathId,
+    out: &mut W,
+    included_overrides: Vec<String>, // which folders to include in the overrides
+    version_id: Option<String>,
+) -> crate::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let state = State::get().await?;
+    let io_semaphore = state.io_semaphore.0.read().await;
+    let _permit: tokio::sync::SemaphorePermit = io_semaphore.acquire().await?;
+    let profile = get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::OtherError(format!(
+            "Tried to export a nonexistent or unloaded profile at path {}!",
+            profile_path
+        ))
+    })?;
+
+    // remove .DS_Store files from included_overrides
+    let included_overrides = included_overrides
+        .into_iter()
+        .filter(|x| {
+            if let Some(f) = PathBuf::from(x).file_name() {
+                if f.to_string_lossy().starts_with(".DS_Store") {
+                    return false;
+                }
+            }
+            true
+        })
+        .collect::<Vec<_>>();
+
+    let profile_base_path = &profile.get_profile_full_path().await?;
+
+    let mut writer = ZipFileWriter::new(&mut *out);
+
+    // Create mrpack json configuration file
+    let version_id = version_id.unwrap_or("1.0.0".to_string());
+    let packfile = create_mrpack_json(&profile, version_id).await?;
+    let modrinth_path_list = get_modrinth_pack_list(&packfile);
+
+    // Build vec of all files in the folder
+    let mut path_list = Vec::new();
+    build_folder(profile_base_path, &mut path_list).await?;
+
+    // Initialize loading bar
+    let loading_bar = init_loading(
+        LoadingBarType::ZipExtract {
+            profile_path: profile.get_profile_full_path().await?,
+            profile_name: profile.metadata.name.clone(),
+        },
+        path_list.len() as f64,
+        "Exporting profile to .mrpack",
+    )
+    .await?;
+
+    // Iterate over every file in the folder
+    // Every file that is NOT in the config file is added to the zip, in overrides
+    for path in path_list {
+        emit_loading(&loading_bar, 1.0, None).await?;
+
+        // Get local path of file, relative to profile folder
+        let relative_path = path.strip_prefix(profile_base_path)?;
+
+        // Get highest level folder pair ('a/b' in 'a/b/c', 'a' in 'a')
+        // We only go one layer deep for the sake of not having a huge list of overrides
+        let topmost_two = relative_path.iter().take(2).collect::<Vec<_>>();
+
+        // a,b => a/b
+        // a => a
+        let topmost = match topmost_two.len() {
+            2 => PathBuf::from(topmost_two[0]).join(topmost_two[1]),
+            1 => PathBuf::from(topmost_two[0]),
+            _ => {
+                return Err(crate::ErrorKind::OtherError(
+                    "No topmost folder found".to_string(),
                 )
                 .into())
             }
@@ -684,6 +2186,7 @@ pub async fn export_mrpack(
     writer.write_entry_whole(builder, &data).await?;
 
     writer.close().await?;
+    out.flush().await.map_err(IOError::from)?;
 
     Ok(())
 }
@@ -721,6 +2224,10 @@ pub async fn get_potential_override_folders(
         .map_err(|e| IOError::with_path(e, &profile_base_dir))?
     {
         let path: PathBuf = entry.path();
+        // Game files of isolated profiles are never part of a pack
+        if path.ends_with(ISOLATED_STORAGE_FOLDER_NAME) {
+            continue;
+        }
         if path.is_dir() {
             // Two layers of files/folders if its a folder
             let mut read_dir = io::read_dir(&path).await?;
@@ -746,6 +2253,34 @@ pub async fn get_potential_override_folders(
     Ok(path_list)
 }
 
+/// Does everything launching a profile needs ahead of time, installing it,
+/// downloading its versions and checking its Java, so that running it right
+/// after starts the game at once
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn prepare(path: &ProfilePathId) -> crate::Result<()> {
+    let profile = get(path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(path.to_string()).as_error()
+    })?;
+    // Game options are left to the launch, which can restore them after
+    crate::launcher::prepare::prepare_launch(&profile).await
+}
+
+/// The JVM arguments a profile launches with, merged from those its pack
+/// recommends and those the user set, with where each came from. Arguments
+/// the launcher adds itself, such as memory, aren't included
+#[tracing::instrument]
+pub async fn get_java_args(
+    path: &ProfilePathId,
+) -> crate::Result<Vec<JavaArg>> {
+    let profile = get(path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(path.to_string()).as_error()
+    })?;
+    let state = State::get().await?;
+    let settings = state.settings.read().await;
+    Ok(merge_java_args(&profile, &settings))
+}
+
 /// Run Minecraft using a profile and the default credentials, logged in credentials,
 /// failing with an error if no credentials are available
 #[tracing::instrument]
@@ -757,13 +2292,13 @@ pub async fn run(
     // Get default account and refresh credentials (preferred way to log in)
     let default_account = state.settings.read().await.default_user;
     let credentials = if let Some(default_account) = default_account {
-        refresh(default_account).await?
+        refresh_for_launch(default_account).await?
     } else {
         // If no default account, try to use a logged in account
         let users = auth::users().await?;
         let last_account = users.first();
         if let Some(last_account) = last_account {
-            refresh(last_account.id).await?
+            refresh_for_launch(last_account.id).await?
         } else {
             return Err(crate::ErrorKind::NoCredentialsError.as_error());
         }
@@ -771,6 +2306,24 @@ pub async fn run(
     run_credentials(path, &credentials).await
 }
 
+/// Run Minecraft using a profile with all of its mods disabled, to tell
+/// whether a crash is caused by them. The mods are enabled again once the
+/// game exits, or on the next start of the launcher if it is closed first
+#[tracing::instrument]
+pub async fn launch_safe_mode(
+    path: &ProfilePathId,
+) -> crate::Result<Arc<RwLock<MinecraftChild>>> {
+    safe_mode::disable_mods(path).await?;
+
+    let result = run(path).await;
+    if result.is_err() {
+        if let Err(err) = safe_mode::restore_mods(path).await {
+            tracing::warn!("Unable to restore mods of {path}: {err}");
+        }
+    }
+    result
+}
+
 /// Run Minecraft using a profile, and credentials for authentication
 /// Returns Arc pointer to RwLock to Child
 #[tracing::instrument(skip(credentials))]
@@ -779,6 +2332,13 @@ pub async fn run_credentials(
     path: &ProfilePathId,
     credentials: &auth::Credentials,
 ) -> crate::Result<Arc<RwLock<MinecraftChild>>> {
+    // Servers may require exact versions, so these are in place before the
+    // pre-launch hook runs
+    update_critical_projects(path).await?;
+
+    // Picks up worlds and settings changed on other computers
+    cloud_sync::sync_if_enabled(path, None).await;
+
     let state = State::get().await?;
     let settings = state.settings.read().await;
     let profile = get(path, None).await?.ok_or_else(|| {
@@ -788,15 +2348,28 @@ pub async fn run_credentials(
         ))
     })?;
 
+    // Joining the server shouldn't stall on a slow resource pack download,
+    // but the game can still fetch it itself if this fails
+    if let Err(err) = server::prefetch_resource_pack(&profile).await {
+        tracing::warn!("Unable to prefetch server resource pack: {err}");
+    }
+
+    if settings.check_gpu_drivers {
+        crate::launcher::gpu::check_gpu_drivers(&profile.metadata.game_version)
+            .await?;
+    }
+
+    let full_path = path.get_full_path().await?;
+    let hook_env_args = get_hook_env_args(&profile, &full_path);
+
     let pre_launch_hooks =
         &profile.hooks.as_ref().unwrap_or(&settings.hooks).pre_launch;
     if let Some(hook) = pre_launch_hooks {
-        // TODO: hook parameters
         let mut cmd = hook.split(' ');
         if let Some(command) = cmd.next() {
-            let full_path = path.get_full_path().await?;
             let result = Command::new(command)
                 .args(&cmd.collect::<Vec<&str>>())
+                .envs(hook_env_args.clone())
                 .current_dir(&full_path)
                 .spawn()
                 .map_err(|e| IOError::with_path(e, &full_path))?
@@ -805,20 +2378,16 @@ pub async fn run_credentials(
                 .map_err(IOError::from)?;
 
             if !result.success() {
-                return Err(crate::ErrorKind::LauncherError(format!(
-                    "Non-zero exit code for pre-launch hook: {}",
-                    result.code().unwrap_or(-1)
-                ))
+                return Err(crate::ErrorKind::HookFailed {
+                    hook: "pre-launch".to_string(),
+                    exit_code: result.code().unwrap_or(-1),
+                }
                 .as_error());
             }
         }
     }
 
-    let java_args = profile
-        .java
-        .as_ref()
-        .and_then(|it| it.extra_arguments.as_ref())
-        .unwrap_or(&settings.custom_java_args);
+    let java_args = merge_java_args(&profile, &settings);
 
     let wrapper = profile
         .hooks
@@ -828,11 +2397,14 @@ pub async fn run_credentials(
     let memory = profile.memory.unwrap_or(settings.memory);
     let resolution = profile.resolution.unwrap_or(settings.game_resolution);
 
-    let env_args = profile
+    // The wrapper is spawned with the game's environment, so the hook
+    // variables are exposed to it (and to the game) here
+    let mut env_args = profile
         .java
         .as_ref()
-        .and_then(|x| x.custom_env_args.as_ref())
-        .unwrap_or(&settings.custom_env_args);
+        .and_then(|x| x.custom_env_args.clone())
+        .unwrap_or_else(|| settings.custom_env_args.clone());
+    env_args.extend(hook_env_args.clone());
 
     // Post post exit hooks
     let post_exit_hook =
@@ -844,7 +2416,8 @@ pub async fn run_credentials(
             let mut command = Command::new(command);
             command
                 .args(&cmd.collect::<Vec<&str>>())
-                .current_dir(path.get_full_path().await?);
+                .envs(hook_env_args)
+                .current_dir(&full_path);
             Some(command)
         } else {
             None
@@ -853,19 +2426,10 @@ pub async fn run_credentials(
         None
     };
 
-    // Any options.txt settings that we want set, add here
-    let mut mc_set_options: Vec<(String, String)> = vec![];
-    if let Some(fullscreen) = profile.fullscreen {
-        // Profile fullscreen setting takes priority
-        mc_set_options.push(("fullscreen".to_string(), fullscreen.to_string()));
-    } else if settings.force_fullscreen {
-        // If global settings wants to force a fullscreen, do it
-        mc_set_options.push(("fullscreen".to_string(), "true".to_string()));
-    }
-
+    let mc_set_options = get_mc_set_options(&profile, &settings);
     let mc_process = crate::launcher::launch_minecraft(
-        java_args,
-        env_args,
+        &java_args,
+        &env_args,
         &mc_set_options,
         wrapper,
         &memory,
@@ -878,6 +2442,79 @@ pub async fn run_credentials(
     Ok(mc_process)
 }
 
+// The JVM arguments of the profile's pack, overridden by the user's
+fn merge_java_args(profile: &Profile, settings: &Settings) -> Vec<JavaArg> {
+    let pack_args = profile
+        .metadata
+        .linked_data
+        .as_ref()
+        .map_or(&[][..], |x| x.pack_java_args.as_slice());
+    let user_args = profile
+        .java
+        .as_ref()
+        .and_then(|it| it.extra_arguments.as_ref())
+        .unwrap_or(&settings.custom_java_args);
+    crate::launcher::args::merge_java_args(pack_args, user_args)
+}
+
+// Any options.txt settings that we want set, add here
+fn get_mc_set_options(
+    profile: &Profile,
+    settings: &Settings,
+) -> Vec<(String, String)> {
+    let mut mc_set_options: Vec<(String, String)> = profile
+        .game_options
+        .iter()
+        .flat_map(|x| &x.options)
+        .map(|(key, value)| (key.clone(), value.to_string()))
+        .collect();
+    // Profile fullscreen setting takes priority, then global settings may
+    // force a fullscreen
+    let fullscreen = profile
+        .fullscreen
+        .or_else(|| settings.force_fullscreen.then_some(true));
+    if let Some(fullscreen) = fullscreen {
+        mc_set_options.retain(|(key, _)| key != "fullscreen");
+        mc_set_options.push(("fullscreen".to_string(), fullscreen.to_string()));
+    }
+    mc_set_options
+}
+
+// Environment variables passed to every launch hook, named after the ones
+// MultiMC exposes so existing hook scripts keep working
+fn get_hook_env_args(
+    profile: &Profile,
+    full_path: &Path,
+) -> Vec<(String, String)> {
+    let mut env_args = vec![
+        ("INST_NAME".to_string(), profile.metadata.name.clone()),
+        ("INST_ID".to_string(), profile.profile_id().to_string()),
+        (
+            "INST_DIR".to_string(),
+            full_path.to_string_lossy().to_string(),
+        ),
+        (
+            "INST_MC_DIR".to_string(),
+            full_path.to_string_lossy().to_string(),
+        ),
+        (
+            "INST_MC_VERSION".to_string(),
+            profile.metadata.game_version.clone(),
+        ),
+        (
+            "INST_LOADER".to_string(),
+            profile.metadata.loader.as_api_str().to_string(),
+        ),
+    ];
+    if let Some(loader_version) = &profile.metadata.loader_version {
+        env_args.push((
+            "INST_LOADER_VERSION".to_string(),
+            loader_version.id.clone(),
+        ));
+    }
+    env_args
+}
+
 fn get_modrinth_pack_list(packfile: &PackFormat) -> Vec<String> {
     packfile
         .files
@@ -913,6 +2550,9 @@ pub async fn create_mrpack_json(
         (crate::prelude::ModLoader::Quilt, Some(v)) => {
             dependencies.insert(PackDependency::QuiltLoader, v.id)
         }
+        (crate::prelude::ModLoader::NeoForge, Some(v)) => {
+            dependencies.insert(PackDependency::NeoForge, v.id)
+        }
         (crate::prelude::ModLoader::Vanilla, _) => None,
         _ => {
             return Err(crate::ErrorKind::OtherError(
@@ -996,6 +2636,15 @@ pub async fn create_mrpack_json(
         summary: None,
         files,
         dependencies,
+        servers: Vec::new(),
+        // Carried over, so exporting an installed pack keeps what it
+        // recommends
+        java_args: profile
+            .metadata
+            .linked_data
+            .as_ref()
+            .map(|x| x.pack_java_args.clone())
+            .unwrap_or_default(),
     })
 }
 
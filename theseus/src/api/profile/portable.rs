@@ -0,0 +1,443 @@
+//! Moving a whole instance to another machine as a single zip
+use crate::config::MODRINTH_API_URL;
+use crate::event::emit::emit_warning;
+use crate::prelude::ProfilePathId;
+use crate::state::policy;
+use crate::state::{
+    LinkedData, ModLoader, ModrinthVersion, Profile, ProjectMetadata,
+    ISOLATED_STORAGE_FOLDER_NAME, PROFILE_JSON_PATH,
+};
+use crate::util::fetch::{fetch, fetch_json, write};
+use crate::util::io::{self, IOError};
+use crate::State;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// File describing the instance, at the root of a portable zip
+pub const DESCRIPTOR_FILE_NAME: &str = "theseus_instance.json";
+
+// Bumped whenever the descriptor changes in a way older versions can't read
+const DESCRIPTOR_FORMAT: u32 = 1;
+
+/// Parts of an instance that can be left out of a portable zip
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub exclude_saves: bool,
+    pub exclude_logs: bool,
+    /// Leaves out the files downloaded from Modrinth, which are downloaded
+    /// again by their hash on import
+    pub exclude_modrinth_files: bool,
+}
+
+/// What a portable zip holds, beyond the files of the instance
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstanceDescriptor {
+    pub format: u32,
+    pub name: String,
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub loader_version: Option<String>,
+    /// Major Java version the instance runs on, when it could be found
+    pub java_version: Option<u32>,
+    pub groups: Vec<String>,
+    pub linked_data: Option<LinkedData>,
+    /// Name of the icon in the zip
+    pub icon: Option<String>,
+    /// SHA-512 hashes of the files downloaded from Modrinth, by path
+    pub modrinth_files: HashMap<String, String>,
+    /// Whether those files are in the zip, or only their hashes
+    pub modrinth_files_included: bool,
+}
+
+/// Zips a whole instance, with a descriptor of what it needs to run, so
+/// it can be recreated elsewhere with [`import_full`]
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn export_full(
+    profile_path: &ProfilePathId,
+    export_path: PathBuf,
+    options: ExportOptions,
+) -> crate::Result<()> {
+    let profile = super::get(profile_path, None).await?.ok_or_else(|| {
+        crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+    })?;
+    if profile.archived.is_some() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is archived, restore it before exporting"
+        ))
+        .as_error());
+    }
+
+    let modrinth_files = modrinth_hashes(&profile);
+    let icon = profile.metadata.icon.clone().filter(|x| x.exists());
+    let descriptor = InstanceDescriptor {
+        format: DESCRIPTOR_FORMAT,
+        name: profile.metadata.name.clone(),
+        game_version: profile.metadata.game_version.clone(),
+        loader: profile.metadata.loader,
+        loader_version: profile
+            .metadata
+            .loader_version
+            .as_ref()
+            .map(|x| x.id.clone()),
+        java_version: required_java(&profile).await,
+        groups: profile.metadata.groups.clone(),
+        linked_data: profile.metadata.linked_data.clone(),
+        icon: icon.as_ref().and_then(|x| {
+            let extension = x.extension()?.to_string_lossy();
+            Some(format!("icon.{extension}"))
+        }),
+        modrinth_files,
+        modrinth_files_included: !options.exclude_modrinth_files,
+    };
+
+    let mut excluded = vec![
+        PathBuf::from(PROFILE_JSON_PATH),
+        PathBuf::from(ISOLATED_STORAGE_FOLDER_NAME),
+        PathBuf::from(super::ARCHIVE_FILE_NAME),
+    ];
+    if options.exclude_saves {
+        excluded.push(PathBuf::from("saves"));
+    }
+    if options.exclude_logs {
+        excluded.extend(
+            ["logs", "crash-reports", "modrinth_logs"].map(PathBuf::from),
+        );
+    }
+    if options.exclude_modrinth_files {
+        excluded.extend(descriptor.modrinth_files.keys().map(PathBuf::from));
+    }
+
+    let full_path = profile.get_profile_full_path().await?;
+    tokio::task::spawn_blocking(move || {
+        write_instance_zip(
+            &full_path,
+            &export_path,
+            &descriptor,
+            icon.as_deref(),
+            &excluded,
+        )
+        .map_err(|e| IOError::with_path(e, &export_path))
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Recreates an instance from a zip made by [`export_full`]. Files that were
+/// left out are downloaded again from Modrinth by their hash, and every
+/// project is linked back to Modrinth when its files are scanned
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn import_full(zip_path: PathBuf) -> crate::Result<ProfilePathId> {
+    let descriptor = {
+        let zip_path = zip_path.clone();
+        tokio::task::spawn_blocking(move || read_descriptor(&zip_path))
+            .await??
+    };
+    if descriptor.format > DESCRIPTOR_FORMAT {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{} was exported by a newer version of the launcher",
+            zip_path.display()
+        ))
+        .as_error());
+    }
+
+    let profile_path = super::create::profile_create(
+        descriptor.name.clone(),
+        descriptor.game_version.clone(),
+        descriptor.loader,
+        descriptor.loader_version.clone(),
+        None,
+        None,
+        descriptor.linked_data.clone(),
+        Some(true),
+    )
+    .await?;
+
+    let result = async {
+        let full_path = super::get_full_path(&profile_path).await?;
+        {
+            let full_path = full_path.clone();
+            tokio::task::spawn_blocking(move || {
+                extract_instance_zip(&zip_path, &full_path)
+            })
+            .await??;
+        }
+        // The files were exported elsewhere, so they are checked against
+        // this machine's content policy
+        policy::check_folder(&full_path).await?;
+
+        if let Some(icon) = &descriptor.icon {
+            let icon_path = full_path.join(icon);
+            super::edit_icon(&profile_path, Some(&icon_path)).await?;
+            io::remove_file(&icon_path).await?;
+        }
+
+        if !descriptor.modrinth_files_included {
+            download_modrinth_files(&full_path, &descriptor.modrinth_files)
+                .await?;
+        }
+
+        let groups = descriptor.groups.clone();
+        super::edit(&profile_path, |prof| {
+            prof.metadata.groups = groups.clone();
+            async { Ok(()) }
+        })
+        .await?;
+        State::sync().await?;
+        Profile::sync_projects_task(profile_path.clone());
+
+        if let Some(java_version) = descriptor.java_version {
+            tracing::info!(
+                "Imported {} runs on Java {java_version}",
+                descriptor.name
+            );
+        }
+        super::install(&profile_path).await?;
+
+        Ok::<_, crate::Error>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => Ok(profile_path),
+        Err(err) => {
+            let _ = super::remove(&profile_path).await;
+            Err(err)
+        }
+    }
+}
+
+// Hashes of the project files that Modrinth knows, by their path in the
+// profile
+fn modrinth_hashes(profile: &Profile) -> HashMap<String, String> {
+    profile
+        .projects
+        .iter()
+        .filter_map(|(path, project)| {
+            let ProjectMetadata::Modrinth { version, .. } = &project.metadata
+            else {
+                return None;
+            };
+            let file_name = path.0.file_name()?.to_string_lossy();
+            let file_name = file_name.trim_end_matches(".disabled");
+            let file = version
+                .files
+                .iter()
+                .find(|x| x.filename == file_name)
+                .or_else(|| version.files.iter().find(|x| x.primary))?;
+            let path = path
+                .0
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((path, file.hashes.get("sha512")?.clone()))
+        })
+        .collect()
+}
+
+// The Java version needs the version manifest, which may not be reachable,
+// so it is left out rather than failing the export
+async fn required_java(profile: &Profile) -> Option<u32> {
+    let state = State::get().await.ok()?;
+    let metadata = state.metadata.read().await;
+    let version = metadata
+        .minecraft
+        .versions
+        .iter()
+        .find(|x| x.id == profile.metadata.game_version)?;
+    let version_info = crate::launcher::download::download_version_info(
+        &state,
+        version,
+        profile.metadata.loader_version.as_ref(),
+        None,
+        None,
+    )
+    .await
+    .ok()?;
+
+    version_info.java_version.map(|x| x.major_version)
+}
+
+fn write_instance_zip(
+    full_path: &Path,
+    export_path: &Path,
+    descriptor: &InstanceDescriptor,
+    icon: Option<&Path>,
+    excluded: &[PathBuf],
+) -> std::io::Result<()> {
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(export_path)?);
+
+    writer.start_file(DESCRIPTOR_FILE_NAME, options)?;
+    writer.write_all(&serde_json::to_vec_pretty(descriptor)?)?;
+    if let (Some(icon), Some(name)) = (icon, &descriptor.icon) {
+        writer.start_file(name, options)?;
+        std::io::copy(&mut std::fs::File::open(icon)?, &mut writer)?;
+    }
+
+    let mut dirs = vec![full_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let relative = path.strip_prefix(full_path).unwrap_or(&path);
+            if excluded.iter().any(|x| relative == x) {
+                continue;
+            }
+
+            let name = relative
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if path.is_dir() {
+                writer.add_directory(name, options)?;
+                dirs.push(path);
+                continue;
+            }
+
+            writer.start_file(name, options)?;
+            std::io::copy(&mut std::fs::File::open(&path)?, &mut writer)?;
+        }
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+fn open_zip(zip_path: &Path) -> crate::Result<zip::ZipArchive<std::fs::File>> {
+    let file = std::fs::File::open(zip_path)
+        .map_err(|e| IOError::with_path(e, zip_path))?;
+    zip::ZipArchive::new(file).map_err(|err| {
+        crate::ErrorKind::InputError(format!(
+            "{} is not a valid instance zip: {err}",
+            zip_path.display()
+        ))
+        .as_error()
+    })
+}
+
+fn read_descriptor(zip_path: &Path) -> crate::Result<InstanceDescriptor> {
+    let mut archive = open_zip(zip_path)?;
+    let mut descriptor = String::new();
+    archive
+        .by_name(DESCRIPTOR_FILE_NAME)
+        .map_err(|_| {
+            crate::ErrorKind::InputError(format!(
+                "{} has no {DESCRIPTOR_FILE_NAME}",
+                zip_path.display()
+            ))
+        })?
+        .read_to_string(&mut descriptor)
+        .map_err(|e| IOError::with_path(e, zip_path))?;
+    Ok(serde_json::from_str(&descriptor)?)
+}
+
+fn extract_instance_zip(
+    zip_path: &Path,
+    full_path: &Path,
+) -> crate::Result<()> {
+    let mut archive = open_zip(zip_path)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|err| {
+            crate::ErrorKind::InputError(format!(
+                "Failed to read {}: {err}",
+                zip_path.display()
+            ))
+        })?;
+        // Entries escaping the profile folder are skipped
+        let Some(relative) = entry.enclosed_name().map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        if relative == Path::new(DESCRIPTOR_FILE_NAME) {
+            continue;
+        }
+
+        let path = full_path.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)
+                .map_err(|e| IOError::with_path(e, &path))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| IOError::with_path(e, parent))?;
+        }
+        let mut file = std::fs::File::create(&path)
+            .map_err(|e| IOError::with_path(e, &path))?;
+        std::io::copy(&mut entry, &mut file)
+            .map_err(|e| IOError::with_path(e, &path))?;
+    }
+
+    Ok(())
+}
+
+// Downloads the files left out of the zip, looking their versions up by hash
+async fn download_modrinth_files(
+    full_path: &Path,
+    files: &HashMap<String, String>,
+) -> crate::Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let versions: HashMap<String, ModrinthVersion> = fetch_json(
+        Method::POST,
+        &format!("{MODRINTH_API_URL}version_files"),
+        None,
+        Some(json!({
+            "hashes": files.values().collect::<Vec<_>>(),
+            "algorithm": "sha512",
+        })),
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    policy::check_versions(&versions.values().collect::<Vec<_>>()).await?;
+
+    let mut missing = Vec::new();
+    for (path, hash) in files {
+        let file = versions.get(hash).and_then(|version| {
+            version
+                .files
+                .iter()
+                .find(|x| x.hashes.get("sha512") == Some(hash))
+        });
+        let Some(file) = file else {
+            missing.push(path.clone());
+            continue;
+        };
+
+        let bytes = fetch(
+            &file.url,
+            file.hashes.get("sha1").map(|x| &**x),
+            &state.fetch_semaphore,
+            &creds,
+        )
+        .await?;
+        write(&full_path.join(path), &bytes, &state.io_semaphore).await?;
+    }
+
+    if !missing.is_empty() {
+        emit_warning(&format!(
+            "Some files of the instance are no longer on Modrinth: {}",
+            missing.join(", ")
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
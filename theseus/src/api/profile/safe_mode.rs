@@ -0,0 +1,131 @@
+//! Launching profiles with their mods temporarily disabled
+use crate::state::ProfilePathId;
+use crate::util::io::{self, IOError};
+use crate::State;
+
+const MODS_FOLDER: &str = "mods";
+const DISABLED_EXTENSION: &str = ".disabled";
+// Lists the mods safe mode disabled, so only those are enabled again and the
+// ones the user disabled stay that way
+const SAFE_MODE_FILE: &str = ".theseus-safe-mode.json";
+
+/// Disables the enabled mods of a profile by renaming them in place, so the
+/// next launch starts without any mods and nothing has to be downloaded
+pub(crate) async fn disable_mods(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let running = state.children.read().await.running_profile_paths().await?;
+    if running.contains(profile_path) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is already running"
+        ))
+        .into());
+    }
+
+    let full_path = profile_path.get_full_path().await?;
+    if full_path.join(SAFE_MODE_FILE).exists() {
+        // Left behind by a session the launcher didn't see end
+        restore_mods(profile_path).await?;
+    }
+
+    let mods = full_path.join(MODS_FOLDER);
+    let mut disabled = Vec::new();
+    if mods.exists() {
+        let mut read_dir = io::read_dir(&mods).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &mods))?
+        {
+            let Some(name) = entry.file_name().to_str().map(String::from)
+            else {
+                continue;
+            };
+            if !entry.path().is_file() || name.ends_with(DISABLED_EXTENSION) {
+                continue;
+            }
+            if mods.join(format!("{name}{DISABLED_EXTENSION}")).exists() {
+                tracing::warn!(
+                    "Leaving {name} of {profile_path} enabled, a disabled \
+                     copy of it is in the way"
+                );
+                continue;
+            }
+            disabled.push(name);
+        }
+    }
+
+    // Written first, so the mods are found again if the launcher stops
+    // midway
+    io::write(
+        full_path.join(SAFE_MODE_FILE),
+        serde_json::to_vec(&disabled)?,
+    )
+    .await?;
+    for name in &disabled {
+        io::rename(
+            mods.join(name),
+            mods.join(format!("{name}{DISABLED_EXTENSION}")),
+        )
+        .await?;
+    }
+    tracing::info!(
+        "Disabled {} mods of {profile_path} for safe mode",
+        disabled.len()
+    );
+
+    Ok(())
+}
+
+/// Enables the mods safe mode disabled again. Mods removed or enabled while
+/// in safe mode are left as they are
+pub(crate) async fn restore_mods(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    let full_path = profile_path.get_full_path().await?;
+    let marker = full_path.join(SAFE_MODE_FILE);
+    if !marker.exists() {
+        return Ok(());
+    }
+
+    let disabled: Vec<String> =
+        serde_json::from_slice(&io::read(&marker).await?)?;
+    let mods = full_path.join(MODS_FOLDER);
+    for name in disabled {
+        let from = mods.join(format!("{name}{DISABLED_EXTENSION}"));
+        let to = mods.join(&name);
+        if from.exists() && !to.exists() {
+            io::rename(from, to).await?;
+        }
+    }
+
+    io::remove_file(&marker).await?;
+    tracing::info!("Restored mods of {profile_path} after safe mode");
+
+    Ok(())
+}
+
+/// Restores the mods of a profile once none of its games are running
+pub(crate) async fn restore_if_idle(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let running = state.children.read().await.running_profile_paths().await?;
+    if !running.contains(profile_path) {
+        restore_mods(profile_path).await?;
+    }
+    Ok(())
+}
+
+/// Restores the mods of every profile still in safe mode from a previous
+/// run of the launcher whose game is no longer running
+pub(crate) async fn restore_all() -> crate::Result<()> {
+    let profiles = super::list(Some(true)).await?;
+    for profile_path in profiles.keys() {
+        if let Err(err) = restore_if_idle(profile_path).await {
+            tracing::warn!("Unable to restore mods of {profile_path}: {err}");
+        }
+    }
+    Ok(())
+}
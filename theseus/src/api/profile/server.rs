@@ -0,0 +1,71 @@
+//! Preparing a profile for the server it is made for
+use super::config::parse_properties;
+use crate::state::{CredentialsStore, Profile, ServerResourcePack};
+use crate::util::fetch::{fetch, sha1_async, write};
+use crate::util::io;
+use crate::State;
+use bytes::Bytes;
+
+// Where Minecraft caches the resource packs servers send, up to 1.20.2.
+// Later versions keep them elsewhere and download the pack as usual
+const SERVER_RESOURCE_PACKS_FOLDER: &str = "server-resource-packs";
+
+/// Reads the resource pack a server declares from the contents of its
+/// server.properties, if it has one
+pub fn parse_server_properties(properties: &str) -> Option<ServerResourcePack> {
+    let mut url = None;
+    let mut sha1 = None;
+    for (key, value) in parse_properties(properties) {
+        match &*key {
+            "resource-pack" if !value.is_empty() => url = Some(value),
+            "resource-pack-sha1" if !value.is_empty() => {
+                sha1 = Some(value.to_lowercase())
+            }
+            _ => {}
+        }
+    }
+
+    url.map(|url| ServerResourcePack { url, sha1 })
+}
+
+/// Downloads the resource pack of a profile's primary server into the
+/// game's server resource pack cache, unless it's already there
+pub(crate) async fn prefetch_resource_pack(
+    profile: &Profile,
+) -> crate::Result<()> {
+    let Some(pack) = profile
+        .primary_server
+        .as_ref()
+        .filter(|x| x.prefetch_resource_pack)
+        .and_then(|x| x.resource_pack.as_ref())
+    else {
+        return Ok(());
+    };
+
+    // The game names cached packs after the SHA-1 of their URL
+    let path = profile
+        .get_profile_full_path()
+        .await?
+        .join(SERVER_RESOURCE_PACKS_FOLDER)
+        .join(sha1::Sha1::from(&pack.url).hexdigest());
+    if path.exists() {
+        let cached = Bytes::from(io::read(&path).await?);
+        match &pack.sha1 {
+            Some(sha1) if sha1_async(cached).await? != *sha1 => {}
+            _ => return Ok(()),
+        }
+    }
+
+    let state = State::get().await?;
+    let bytes = fetch(
+        &pack.url,
+        pack.sha1.as_deref(),
+        &state.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+    write(&path, &bytes, &state.io_semaphore).await?;
+    tracing::info!("Prefetched server resource pack {}", pack.url);
+
+    Ok(())
+}
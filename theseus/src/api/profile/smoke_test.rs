@@ -0,0 +1,380 @@
+//! Launching a profile unattended to check that the game starts, such as
+//! after updating a pack
+use crate::launcher::auth::Credentials;
+use crate::logs::{self, CrashReport};
+use crate::prelude::ProfilePathId;
+use crate::util::io::{self, IOError};
+use crate::State;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Lines the game logs once it finished loading: the sound engine starting
+// in the client, and the server being ready for players
+const READY_MARKERS: [&str; 2] = ["Sound engine started", "Done ("];
+// Lines the game logs as it crashes
+const CRASH_MARKERS: [&str; 3] = [
+    "---- Minecraft Crash Report ----",
+    "#@!@# Game crashed!",
+    "Exception in thread \"main\"",
+];
+// Lines of output kept with a failed test
+const OUTPUT_TAIL_LINES: usize = 60;
+// How long the game is given to quit once it loaded or crashed, before it
+// is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    // Profiles being tested, whose games the launcher doesn't count as
+    // played, move its window for, or warn about crashing, with the virtual
+    // display program of headless tests
+    static ref TESTING: Mutex<HashMap<ProfilePathId, Option<PathBuf>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// How a smoke test ended
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmokeTestOutcome {
+    /// The game finished loading
+    Passed,
+    /// The game crashed while loading
+    Crashed,
+    /// The game exited before it finished loading, without a crash logged
+    Exited,
+    /// The game was still loading when the test ran out of time
+    TimedOut,
+}
+
+/// What a smoke test found
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SmokeTestResult {
+    pub outcome: SmokeTestOutcome,
+    /// How long the game took to load, or to fail
+    pub seconds: f64,
+    /// The line telling the game loaded or crashed
+    pub marker: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Set unless the test passed
+    pub failure: Option<FailureAnalysis>,
+}
+
+impl SmokeTestResult {
+    pub fn passed(&self) -> bool {
+        self.outcome == SmokeTestOutcome::Passed
+    }
+}
+
+/// Why the game didn't load, from its crash report and output
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailureAnalysis {
+    /// What the game was doing, from the `Description:` of its crash report
+    pub description: Option<String>,
+    /// The first line of the last exception the game logged
+    pub exception: Option<String>,
+    /// The crash report written during the test, censored like game logs
+    pub crash_report: Option<CrashReport>,
+    /// The last lines of the game's output
+    pub output_tail: Vec<String>,
+}
+
+// Removes a profile from those being tested once its test ends. A test
+// ending early, from an error or its future being dropped, leaves its game
+// running, which is killed first so its exit is still known to be a test's
+struct Testing {
+    profile_path: ProfilePathId,
+    game: Option<Uuid>,
+}
+
+impl Drop for Testing {
+    fn drop(&mut self) {
+        let profile_path = self.profile_path.clone();
+        match self.game.take() {
+            Some(uuid) => {
+                tokio::spawn(async move {
+                    let _ = crate::process::kill_by_uuid(&uuid).await;
+                    let _ = crate::process::wait_for_by_uuid(&uuid).await;
+                    TESTING.lock().unwrap().remove(&profile_path);
+                });
+            }
+            None => {
+                TESTING.lock().unwrap().remove(&profile_path);
+            }
+        }
+    }
+}
+
+/// Whether the game of a profile was launched by a smoke test
+pub(crate) fn is_testing(profile_path: &ProfilePathId) -> bool {
+    TESTING.lock().unwrap().contains_key(profile_path)
+}
+
+/// The virtual display program a headless smoke test runs the game of a
+/// profile under, if it's being tested headlessly
+pub(crate) fn headless_display(
+    profile_path: &ProfilePathId,
+) -> Option<PathBuf> {
+    TESTING.lock().unwrap().get(profile_path).cloned().flatten()
+}
+
+// Finds xvfb-run, which runs the game on a virtual display so headless tests
+// work on machines without one
+fn find_xvfb_run() -> crate::Result<PathBuf> {
+    let found = if cfg!(target_os = "linux") {
+        std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|x| x.join("xvfb-run"))
+                .find(|x| x.is_file())
+        })
+    } else {
+        None
+    };
+    found.ok_or_else(|| {
+        crate::ErrorKind::LauncherError(
+            "Headless smoke tests need xvfb-run, which wasn't found"
+                .to_string(),
+        )
+        .into()
+    })
+}
+
+/// Launches a profile with the default account and waits for the game to
+/// finish loading, crash, or run out of time, then stops it. The launcher's
+/// window is left alone, and the session isn't counted as played. Headless
+/// tests run the game on a virtual display, which needs `xvfb-run` on Linux
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn test_launch(
+    profile_path: &ProfilePathId,
+    timeout: Duration,
+    headless: bool,
+) -> crate::Result<SmokeTestResult> {
+    smoke_test(profile_path, None, timeout, headless).await
+}
+
+/// Smoke tests a profile with the given account rather than the default one,
+/// such as the test account of integration tests
+#[tracing::instrument(skip(credentials))]
+#[theseus_macros::debug_pin]
+pub async fn test_launch_credentials(
+    profile_path: &ProfilePathId,
+    credentials: &Credentials,
+    timeout: Duration,
+    headless: bool,
+) -> crate::Result<SmokeTestResult> {
+    smoke_test(profile_path, Some(credentials), timeout, headless).await
+}
+
+async fn smoke_test(
+    profile_path: &ProfilePathId,
+    credentials: Option<&Credentials>,
+    timeout: Duration,
+    headless: bool,
+) -> crate::Result<SmokeTestResult> {
+    let display = if headless {
+        Some(find_xvfb_run()?)
+    } else {
+        None
+    };
+    {
+        let mut testing = TESTING.lock().unwrap();
+        if testing.contains_key(profile_path) {
+            return Err(crate::ErrorKind::LauncherError(format!(
+                "{profile_path} is already being tested"
+            ))
+            .into());
+        }
+        testing.insert(profile_path.clone(), display);
+    }
+    let mut testing = Testing {
+        profile_path: profile_path.clone(),
+        game: None,
+    };
+
+    let started = Utc::now();
+    let mchild = match credentials {
+        Some(credentials) => {
+            super::run_credentials(profile_path, credentials).await?
+        }
+        None => super::run(profile_path).await?,
+    };
+    let (uuid, output) = {
+        let mchild = mchild.read().await;
+        (mchild.uuid, mchild.output.clone())
+    };
+    testing.game = Some(uuid);
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let (mut outcome, marker) = loop {
+        let text = output.get_output().await?;
+        if let Some(line) = find_marker(&text, &CRASH_MARKERS) {
+            break (SmokeTestOutcome::Crashed, Some(line));
+        }
+        if let Some(line) = find_marker(&text, &READY_MARKERS) {
+            break (SmokeTestOutcome::Passed, Some(line));
+        }
+        if exit_code(uuid).await?.is_some() {
+            break (SmokeTestOutcome::Exited, None);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            break (SmokeTestOutcome::TimedOut, None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    let seconds = (Utc::now() - started).num_milliseconds() as f64 / 1000.0;
+
+    // Crashing games are left to write their crash report and exit
+    stop(uuid, outcome != SmokeTestOutcome::Crashed).await?;
+    testing.game = None;
+    let exit_code = exit_code(uuid).await?.flatten();
+    // The exit code tells of crashes that logged nothing the test knows
+    if outcome == SmokeTestOutcome::Exited && exit_code != Some(0) {
+        outcome = SmokeTestOutcome::Crashed;
+    }
+
+    let failure = if outcome == SmokeTestOutcome::Passed {
+        None
+    } else {
+        Some(analyze(profile_path, started, &output.get_output().await?).await?)
+    };
+    tracing::info!("Smoke test of {profile_path} ended as {outcome:?}");
+
+    Ok(SmokeTestResult {
+        outcome,
+        seconds,
+        marker,
+        exit_code,
+        failure,
+    })
+}
+
+// The first line of the output holding one of the markers
+fn find_marker(output: &str, markers: &[&str]) -> Option<String> {
+    output
+        .lines()
+        .find(|line| markers.iter().any(|x| line.contains(x)))
+        .map(|line| line.trim().to_string())
+}
+
+// The exit code of a game once it exited, which is unknown for games killed
+// by a signal
+async fn exit_code(uuid: Uuid) -> crate::Result<Option<Option<i32>>> {
+    let state = State::get().await?;
+    let children = state.children.read().await;
+    Ok(children
+        .exit_status(&uuid)
+        .await?
+        .map(|x| x.and_then(|x| x.code())))
+}
+
+// Waits for the game to quit, asking it to first if it should, and kills it
+// if it doesn't in time. Then waits for the launcher to be done with it
+async fn stop(uuid: Uuid, ask_to_quit: bool) -> crate::Result<()> {
+    if exit_code(uuid).await?.is_none() {
+        if ask_to_quit {
+            crate::process::terminate_by_uuid(&uuid).await?;
+        }
+        let exited = tokio::time::timeout(STOP_TIMEOUT, async {
+            while exit_code(uuid).await?.is_none() {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            Ok::<(), crate::Error>(())
+        })
+        .await;
+        match exited {
+            Ok(res) => res?,
+            Err(_) => crate::process::kill_by_uuid(&uuid).await?,
+        }
+    }
+
+    // Failing exit codes are what the test reports, not an error
+    let _ = crate::process::wait_for_by_uuid(&uuid).await;
+    Ok(())
+}
+
+// Gathers why the game failed from its output and any crash report it
+// wrote since the test started
+async fn analyze(
+    profile_path: &ProfilePathId,
+    started: DateTime<Utc>,
+    output: &str,
+) -> crate::Result<FailureAnalysis> {
+    let crash_report = newest_crash_report(profile_path, started).await?;
+
+    let description = crash_report.as_ref().and_then(|report| {
+        report
+            .output
+            .lines()
+            .find_map(|line| line.strip_prefix("Description:"))
+            .map(|x| x.trim().to_string())
+    });
+    let exception = crash_report
+        .as_ref()
+        .map_or(output, |x| x.output.as_str())
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("at ")
+                && (line.contains("Exception") || line.contains("Error:"))
+        })
+        .last()
+        .map(|x| x.trim().to_string());
+
+    let lines = output.lines().collect::<Vec<_>>();
+    let output_tail = lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..]
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+
+    Ok(FailureAnalysis {
+        description,
+        exception,
+        crash_report,
+        output_tail,
+    })
+}
+
+// The crash report the game wrote last, if it did since a point in time
+async fn newest_crash_report(
+    profile_path: &ProfilePathId,
+    since: DateTime<Utc>,
+) -> crate::Result<Option<CrashReport>> {
+    let reports_dir = profile_path.get_full_path().await?.join("crash-reports");
+    if !reports_dir.exists() {
+        return Ok(None);
+    }
+
+    let mut newest = None;
+    let mut read_dir = io::read_dir(&reports_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &reports_dir))?
+    {
+        let modified = entry
+            .metadata()
+            .await
+            .and_then(|x| x.modified())
+            .map(DateTime::<Utc>::from)
+            .map_err(|e| IOError::with_path(e, entry.path()))?;
+        if modified >= since
+            && newest.as_ref().map_or(true, |(x, _)| modified > *x)
+        {
+            newest = Some((modified, entry.file_name()));
+        }
+    }
+
+    let Some((_, file_name)) = newest else {
+        return Ok(None);
+    };
+    let reports = logs::get_crash_reports(profile_path.clone()).await?;
+    Ok(reports
+        .into_iter()
+        .find(|x| x.file_name == file_name.to_string_lossy()))
+}
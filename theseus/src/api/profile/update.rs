@@ -6,7 +6,7 @@ use crate::{
     pack::{self, install_from::generate_pack_from_version_id},
     prelude::{ProfilePathId, ProjectPathId},
     profile::get,
-    state::Project,
+    state::{Journal, OperationKind, Project},
     State,
 };
 use futures::try_join;
@@ -40,18 +40,28 @@ pub async fn update_managed_modrinth(
         linked_data.version_id.as_ref().ok_or_else(unmanaged_err)?;
 
     // extract modrinth_update_version, returning Ok(()) if it is none
+    // Locked packs changed by hand are restored even without a new version
     let modrinth_update_version = match profile.modrinth_update_version {
-        Some(ref x) if x != version_id => x,
+        Some(ref x) if x != version_id => Some(x),
+        _ if linked_data.locked && !linked_data.modifications.is_empty() => {
+            None
+        }
         _ => return Ok(()), // No update version, or no update needed, return Ok(())
     };
 
+    // Projects added by hand are not part of either pack, so they would
+    // survive replacing it
+    if linked_data.locked {
+        remove_all_projects(&profile).await?;
+    }
+
     // Replace the pack with the new version
     replace_managed_modrinth(
         profile_path,
         &profile,
         project_id,
         version_id,
-        Some(modrinth_update_version),
+        modrinth_update_version,
     )
     .await?;
 
@@ -87,28 +97,7 @@ pub async fn repair_managed_modrinth(
     // For repairing specifically, first we remove all installed projects (to ensure we do remove ones that aren't in the pack)
     // We do a project removal followed by removing everything in the .mrpack, to ensure we only
     // remove relevant projects and not things like save files
-    let projects_map = profile.projects.clone();
-    let stream = futures::stream::iter(
-        projects_map
-            .into_iter()
-            .map(Ok::<(ProjectPathId, Project), crate::Error>),
-    );
-    loading_try_for_each_concurrent(
-        stream,
-        None,
-        None,
-        0.0,
-        0,
-        None,
-        |(project_id, _)| {
-            let profile = profile.clone();
-            async move {
-                profile.remove_project(&project_id, Some(true)).await?;
-                Ok(())
-            }
-        },
-    )
-    .await?;
+    remove_all_projects(&profile).await?;
 
     // Extract modrinth pack information, if appropriate
     let linked_data = profile
@@ -143,6 +132,33 @@ pub async fn repair_managed_modrinth(
     Ok(())
 }
 
+async fn remove_all_projects(
+    profile: &crate::state::Profile,
+) -> crate::Result<()> {
+    let projects_map = profile.projects.clone();
+    let stream = futures::stream::iter(
+        projects_map
+            .into_iter()
+            .map(Ok::<(ProjectPathId, Project), crate::Error>),
+    );
+    loading_try_for_each_concurrent(
+        stream,
+        None,
+        None,
+        0.0,
+        0,
+        None,
+        |(project_id, _)| {
+            let profile = profile.clone();
+            async move {
+                profile.remove_project(&project_id, Some(true)).await?;
+                Ok(())
+            }
+        },
+    )
+    .await
+}
+
 /// Replace a managed modrinth pack with a new version
 /// If new_version_id is None, the pack is 'reinstalled' in-place
 #[tracing::instrument(skip(profile))]
@@ -183,21 +199,27 @@ async fn replace_managed_modrinth(
             (old_pack_creator.clone(), old_pack_creator)
         };
 
-    // Removal - remove all files that were added by the old pack
-    // - remove all installed projects
-    // - remove all overrides
-    pack::install_mrpack::remove_all_related_files(
-        profile_path.clone(),
-        old_pack_creator.file,
-    )
+    // Journaled, as stopping between the removal and the reinstall leaves
+    // the pack missing its files
+    Journal::run(profile_path, OperationKind::PackUpdate, async {
+        // Removal - remove all files that were added by the old pack
+        // - remove all installed projects
+        // - remove all overrides
+        pack::install_mrpack::remove_all_related_files(
+            profile_path.clone(),
+            old_pack_creator.file,
+        )
+        .await?;
+
+        // Reinstallation - install all files that are added by the new pack
+        // - install all projects
+        // - install all overrides
+        // - edits the profile to update the new data
+        // - (functionals almost identically to rteinstalling the pack 'in-place')
+        pack::install_mrpack::install_zipped_mrpack_files(new_pack_creator)
+            .await
+    })
     .await?;
 
-    // Reinstallation - install all files that are added by the new pack
-    // - install all projects
-    // - install all overrides
-    // - edits the profile to update the new data
-    // - (functionals almost identically to rteinstalling the pack 'in-place')
-    pack::install_mrpack::install_zipped_mrpack_files(new_pack_creator).await?;
-
     Ok(())
 }
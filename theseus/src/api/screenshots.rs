@@ -0,0 +1,215 @@
+//! API for browsing the screenshots of a profile
+use crate::event::emit::emit_screenshot;
+use crate::event::ScreenshotPayloadType;
+use crate::{
+    util::io::{self, IOError},
+    {state::ProfilePathId, State},
+};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+pub const SCREENSHOTS_FOLDER: &str = "screenshots";
+
+// Minecraft names screenshots after the local time they were taken at,
+// eg: 2023-06-01_12.34.56.png, or 2023-06-01_12.34.56_2.png for duplicates
+const SCREENSHOT_NAME_FORMAT: &str = "%Y-%m-%d_%H.%M.%S";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Screenshot {
+    /// File name, used to identify the screenshot within the profile
+    pub name: String,
+    /// Full path of the screenshot, eg: for opening it in a file explorer
+    pub path: PathBuf,
+    pub size: u64,
+    pub captured: DateTime<Utc>,
+    /// Dimensions of the image, if they could be read from its header
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Lists the screenshots of a profile, newest first
+#[tracing::instrument]
+pub async fn get_screenshots(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<Screenshot>> {
+    let folder = get_screenshots_dir(profile_path).await?;
+
+    let mut screenshots = Vec::new();
+    if folder.exists() {
+        let mut read_dir = io::read_dir(&folder).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &folder))?
+        {
+            let path = entry.path();
+            if is_screenshot(&path) {
+                screenshots.push(read_screenshot(&path).await?);
+            }
+        }
+    }
+
+    screenshots.sort_by(|a, b| b.captured.cmp(&a.captured));
+    Ok(screenshots)
+}
+
+/// Gets a single screenshot of a profile by file name
+#[tracing::instrument]
+pub async fn get_screenshot(
+    profile_path: &ProfilePathId,
+    name: &str,
+) -> crate::Result<Screenshot> {
+    let path = get_screenshot_path(profile_path, name).await?;
+    read_screenshot(&path).await
+}
+
+/// Reads the raw image bytes of a screenshot, eg: for copying it to the
+/// clipboard
+#[tracing::instrument]
+pub async fn get_screenshot_data(
+    profile_path: &ProfilePathId,
+    name: &str,
+) -> crate::Result<Vec<u8>> {
+    let path = get_screenshot_path(profile_path, name).await?;
+    Ok(io::read(&path).await?)
+}
+
+/// Deletes a screenshot of a profile by file name
+#[tracing::instrument]
+pub async fn delete_screenshot(
+    profile_path: &ProfilePathId,
+    name: &str,
+) -> crate::Result<()> {
+    let path = get_screenshot_path(profile_path, name).await?;
+    io::remove_file(&path).await?;
+    Ok(())
+}
+
+/// Gets the screenshots folder of a profile
+#[tracing::instrument]
+pub async fn get_screenshots_dir(
+    profile_path: &ProfilePathId,
+) -> crate::Result<PathBuf> {
+    let profile = crate::profile::get(profile_path, Some(true))
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?;
+
+    Ok(profile.get_profile_full_path().await?.join(SCREENSHOTS_FOLDER))
+}
+
+// Called by the file watcher when a file in a screenshots folder changes.
+// Only screenshots taken while the profile is running are reported
+pub(crate) fn screenshot_task(profile_path: ProfilePathId, path: PathBuf) {
+    if !is_screenshot(&path) {
+        return;
+    }
+
+    tokio::task::spawn(async move {
+        let res = async {
+            let state = State::get().await?;
+            let running = state
+                .children
+                .read()
+                .await
+                .running_profile_paths()
+                .await?;
+            if !running.contains(&profile_path) {
+                return Ok(());
+            }
+
+            let event = if path.exists() {
+                ScreenshotPayloadType::Added
+            } else {
+                ScreenshotPayloadType::Removed
+            };
+            emit_screenshot(&profile_path, &path, event).await?;
+
+            Ok::<(), crate::Error>(())
+        }
+        .await;
+
+        match res {
+            Ok(()) => {}
+            Err(err) => {
+                tracing::warn!("Unable to emit screenshot event: {err}")
+            }
+        };
+    });
+}
+
+fn is_screenshot(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |x| x.eq_ignore_ascii_case("png"))
+}
+
+// Resolves a screenshot by name, rejecting anything outside of the folder
+async fn get_screenshot_path(
+    profile_path: &ProfilePathId,
+    name: &str,
+) -> crate::Result<PathBuf> {
+    let file_name = Path::new(name).file_name();
+    if file_name.map_or(true, |x| x != name) || !is_screenshot(Path::new(name))
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Invalid screenshot name: {name}"
+        ))
+        .into());
+    }
+
+    Ok(get_screenshots_dir(profile_path).await?.join(name))
+}
+
+async fn read_screenshot(path: &Path) -> crate::Result<Screenshot> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| IOError::with_path(e, path))?;
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let captured = name
+        .get(..19)
+        .and_then(|x| {
+            NaiveDateTime::parse_from_str(x, SCREENSHOT_NAME_FORMAT).ok()
+        })
+        .and_then(|x| x.and_local_timezone(Local).earliest())
+        .map(|x| x.with_timezone(&Utc))
+        .or_else(|| metadata.modified().ok().map(DateTime::<Utc>::from))
+        .unwrap_or_else(Utc::now);
+
+    let (width, height) = read_png_dimensions(path).await.unzip();
+
+    Ok(Screenshot {
+        name,
+        path: path.to_path_buf(),
+        size: metadata.len(),
+        captured,
+        width,
+        height,
+    })
+}
+
+// Width and height are the first fields of the IHDR chunk, which always
+// directly follows the 8 byte PNG signature
+async fn read_png_dimensions(path: &Path) -> Option<(u32, u32)> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    let mut header = [0u8; 24];
+    let mut file = tokio::fs::File::open(path).await.ok()?;
+    file.read_exact(&mut header).await.ok()?;
+
+    if &header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    Some((width, height))
+}
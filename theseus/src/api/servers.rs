@@ -0,0 +1,483 @@
+//! API for managing the multiplayer server list of a profile
+use crate::state::ProfilePathId;
+use crate::util::fetch::write;
+use crate::util::io;
+use crate::util::nbt::{self, Tag};
+use crate::State;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const SERVERS_FILE: &str = "servers.dat";
+// Copy of the list the game keeps before saving it, written here too
+const SERVERS_BACKUP_FILE: &str = "servers.dat_old";
+const DEFAULT_PORT: u16 = 25565;
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+// Status responses carry a favicon, but nothing near this
+const MAX_PACKET_SIZE: usize = 1 << 21;
+
+/// An entry of the multiplayer server list
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Server {
+    pub name: String,
+    /// Address as typed in game, `host` or `host:port`
+    pub address: String,
+    /// Base64 encoded PNG the game saved from the last ping
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Whether the resource pack the server sends is accepted, or declined.
+    /// The game asks when joining if unset
+    #[serde(default)]
+    pub accept_resource_pack: Option<bool>,
+    /// Hidden servers were joined through direct connect and aren't listed
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// Status of a server, as answered to a Server List Ping
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerStatus {
+    pub version: Option<String>,
+    pub protocol: Option<i32>,
+    pub players_online: Option<i64>,
+    pub players_max: Option<i64>,
+    /// Message of the day, with its formatting stripped
+    pub motd: String,
+    /// Data URL of the server icon
+    pub favicon: Option<String>,
+    /// Round trip time of the ping in milliseconds
+    pub latency: u64,
+}
+
+/// Lists the servers of a profile, in the order the game shows them.
+/// Servers are identified by their index in this list
+#[tracing::instrument]
+pub async fn get_servers(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<Server>> {
+    let root = read_servers(profile_path).await?;
+    Ok(server_entries(&root).iter().map(to_server).collect())
+}
+
+/// Adds a server to a profile, at the end of the list unless an index is
+/// given
+#[tracing::instrument]
+pub async fn add_server(
+    profile_path: &ProfilePathId,
+    server: Server,
+    index: Option<usize>,
+) -> crate::Result<()> {
+    edit_servers(profile_path, |servers| {
+        let index = index.unwrap_or(servers.len()).min(servers.len());
+        servers.insert(index, from_server(&server, nbt::compound([])));
+        Ok(())
+    })
+    .await
+}
+
+/// Replaces a server of a profile, keeping what the launcher doesn't edit
+#[tracing::instrument]
+pub async fn edit_server(
+    profile_path: &ProfilePathId,
+    index: usize,
+    server: Server,
+) -> crate::Result<()> {
+    edit_servers(profile_path, |servers| {
+        let entry = servers.get_mut(index).ok_or_else(|| no_server(index))?;
+        *entry = from_server(&server, entry.clone());
+        Ok(())
+    })
+    .await
+}
+
+/// Removes a server from a profile
+#[tracing::instrument]
+pub async fn remove_server(
+    profile_path: &ProfilePathId,
+    index: usize,
+) -> crate::Result<()> {
+    edit_servers(profile_path, |servers| {
+        if index >= servers.len() {
+            return Err(no_server(index));
+        }
+        servers.remove(index);
+        Ok(())
+    })
+    .await
+}
+
+/// Moves a server of a profile to another place in the list
+#[tracing::instrument]
+pub async fn move_server(
+    profile_path: &ProfilePathId,
+    from: usize,
+    to: usize,
+) -> crate::Result<()> {
+    edit_servers(profile_path, |servers| {
+        if from >= servers.len() {
+            return Err(no_server(from));
+        }
+        let server = servers.remove(from);
+        servers.insert(to.min(servers.len()), server);
+        Ok(())
+    })
+    .await
+}
+
+/// Sets whether the resource pack a server sends is accepted, declined, or
+/// asked about when joining
+#[tracing::instrument]
+pub async fn set_resource_pack_acceptance(
+    profile_path: &ProfilePathId,
+    index: usize,
+    accept: Option<bool>,
+) -> crate::Result<()> {
+    edit_servers(profile_path, |servers| {
+        let entry = servers.get_mut(index).ok_or_else(|| no_server(index))?;
+        match accept {
+            Some(accept) => {
+                entry.set("acceptTextures", Tag::Byte(accept as i8))
+            }
+            None => entry.remove("acceptTextures"),
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Pings a server with the Server List Ping protocol, to show whether it is
+/// online before launching. SRV records are not looked up, so addresses
+/// relying on one need their port
+#[tracing::instrument]
+pub async fn ping_server(address: &str) -> crate::Result<ServerStatus> {
+    tokio::time::timeout(PING_TIMEOUT, ping(address))
+        .await
+        .map_err(|_| {
+            crate::ErrorKind::OtherError(format!(
+                "Server {address} did not answer in time"
+            ))
+        })?
+}
+
+/// Get the servers.dat file of a profile
+#[tracing::instrument]
+pub async fn get_servers_file(
+    profile_path: &ProfilePathId,
+) -> crate::Result<PathBuf> {
+    Ok(profile_path.get_full_path().await?.join(SERVERS_FILE))
+}
+
+/// Adds the servers a pack ships to the end of the server list of a profile.
+/// Servers the pack added before are skipped, as are servers already listed.
+/// Returns the addresses to record as added by the pack
+pub(crate) async fn add_pack_servers(
+    profile_path: &ProfilePathId,
+    servers: Vec<Server>,
+    added_before: &[String],
+) -> crate::Result<Vec<String>> {
+    let servers = servers
+        .into_iter()
+        .filter(|x| !added_before.iter().any(|y| same_address(&x.address, y)))
+        .collect::<Vec<_>>();
+    if servers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut added = Vec::new();
+    edit_servers(profile_path, |entries| {
+        for server in servers {
+            let listed = entries.iter().any(|x| {
+                x.get("ip")
+                    .and_then(Tag::as_str)
+                    .map_or(false, |x| same_address(x, &server.address))
+            });
+            if !listed {
+                entries.push(from_server(&server, nbt::compound([])));
+            }
+            added.push(server.address);
+        }
+        Ok(())
+    })
+    .await?;
+
+    Ok(added)
+}
+
+/// Reads the servers of a servers.dat file, such as one shipped in a pack
+pub(crate) fn parse_servers(bytes: &[u8]) -> crate::Result<Vec<Server>> {
+    let root = nbt::read(bytes)?;
+    Ok(server_entries(&root).iter().map(to_server).collect())
+}
+
+async fn read_servers(profile_path: &ProfilePathId) -> crate::Result<Tag> {
+    let path = get_servers_file(profile_path).await?;
+    if !path.exists() {
+        return Ok(nbt::compound([("servers", Tag::List(Vec::new()))]));
+    }
+    nbt::read(&io::read(&path).await?)
+}
+
+// Edits the server list while the game isn't running, since it writes the
+// list back when closing
+async fn edit_servers(
+    profile_path: &ProfilePathId,
+    edit: impl FnOnce(&mut Vec<Tag>) -> crate::Result<()>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let running = state.children.read().await.running_profile_paths().await?;
+    if running.contains(profile_path) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Profile {profile_path} is running, close it to edit its servers"
+        ))
+        .into());
+    }
+
+    let mut root = read_servers(profile_path).await?;
+    let mut servers = server_entries(&root).to_vec();
+    edit(&mut servers)?;
+    root.set("servers", Tag::List(servers));
+
+    let path = get_servers_file(profile_path).await?;
+    if path.exists() {
+        io::copy(&path, path.with_file_name(SERVERS_BACKUP_FILE)).await?;
+    }
+    write(&path, &nbt::write(&root), &state.io_semaphore).await?;
+
+    Ok(())
+}
+
+fn server_entries(root: &Tag) -> &[Tag] {
+    match root.get("servers") {
+        Some(Tag::List(servers)) => servers,
+        _ => &[],
+    }
+}
+
+fn to_server(entry: &Tag) -> Server {
+    let string = |name: &str| entry.get(name).and_then(Tag::as_str);
+    Server {
+        name: string("name").unwrap_or_default().to_string(),
+        address: string("ip").unwrap_or_default().to_string(),
+        icon: string("icon").map(|x| x.to_string()),
+        accept_resource_pack: entry
+            .get("acceptTextures")
+            .and_then(Tag::as_byte)
+            .map(|x| x != 0),
+        hidden: entry
+            .get("hidden")
+            .and_then(Tag::as_byte)
+            .map_or(false, |x| x != 0),
+    }
+}
+
+// Writes a server over an entry, so tags the launcher doesn't know about
+// are kept
+fn from_server(server: &Server, mut entry: Tag) -> Tag {
+    entry.set("name", Tag::String(server.name.clone()));
+    entry.set("ip", Tag::String(server.address.clone()));
+    match &server.icon {
+        Some(icon) => entry.set("icon", Tag::String(icon.clone())),
+        None => entry.remove("icon"),
+    }
+    match server.accept_resource_pack {
+        Some(accept) => entry.set("acceptTextures", Tag::Byte(accept as i8)),
+        None => entry.remove("acceptTextures"),
+    }
+    entry.set("hidden", Tag::Byte(server.hidden as i8));
+    entry
+}
+
+fn no_server(index: usize) -> crate::Error {
+    crate::ErrorKind::InputError(format!("No server at index {index}")).into()
+}
+
+async fn ping(address: &str) -> crate::Result<ServerStatus> {
+    let (host, port) = split_address(address).ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "Invalid server address {address}"
+        ))
+    })?;
+
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(io::IOError::from)?;
+
+    // Handshake with the next state set to status, then a status request.
+    // -1 stands for no protocol version in particular
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend(host.as_bytes());
+    handshake.extend(port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    send_packet(&mut stream, &handshake).await?;
+    send_packet(&mut stream, &[0x00]).await?;
+
+    let response = read_packet(&mut stream).await?;
+    let mut cursor = response.as_slice();
+    if read_varint(&mut cursor)? != 0x00 {
+        return Err(ping_error(address));
+    }
+    let len = read_varint(&mut cursor)? as usize;
+    let json = cursor.get(..len).ok_or_else(|| ping_error(address))?;
+    let status: serde_json::Value = serde_json::from_slice(json)?;
+
+    let start = Instant::now();
+    let mut ping = Vec::new();
+    write_varint(&mut ping, 0x01);
+    ping.extend(0i64.to_be_bytes());
+    send_packet(&mut stream, &ping).await?;
+    read_packet(&mut stream).await?;
+    let latency = start.elapsed().as_millis() as u64;
+
+    let mut motd = String::new();
+    flatten_text(&status["description"], &mut motd);
+    Ok(ServerStatus {
+        version: status["version"]["name"].as_str().map(|x| x.to_string()),
+        protocol: status["version"]["protocol"].as_i64().map(|x| x as i32),
+        players_online: status["players"]["online"].as_i64(),
+        players_max: status["players"]["max"].as_i64(),
+        motd: strip_formatting(&motd),
+        favicon: status["favicon"].as_str().map(|x| x.to_string()),
+        latency,
+    })
+}
+
+// Splits an address into its host and port, which may be left out. IPv6
+// addresses need brackets to be given a port
+fn split_address(address: &str) -> Option<(&str, u16)> {
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        let port = match port.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None if port.is_empty() => DEFAULT_PORT,
+            None => return None,
+        };
+        return Some((host, port));
+    }
+
+    match address.split_once(':') {
+        Some((host, port)) if !port.contains(':') => {
+            Some((host, port.parse().ok()?))
+        }
+        _ => Some((address, DEFAULT_PORT)),
+    }
+}
+
+// Whether two addresses point to the same server, `host` being short for
+// `host:25565`
+fn same_address(a: &str, b: &str) -> bool {
+    let (a, b) = (a.trim(), b.trim());
+    match (split_address(a), split_address(b)) {
+        (Some((a, a_port)), Some((b, b_port))) => {
+            a.eq_ignore_ascii_case(b) && a_port == b_port
+        }
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+// Collects the text of a chat component and its children
+fn flatten_text(component: &serde_json::Value, text: &mut String) {
+    match component {
+        serde_json::Value::String(x) => text.push_str(x),
+        serde_json::Value::Array(x) => {
+            x.iter().for_each(|x| flatten_text(x, text))
+        }
+        serde_json::Value::Object(x) => {
+            if let Some(value) = x.get("text").and_then(|x| x.as_str()) {
+                text.push_str(value);
+            }
+            if let Some(extra) = x.get("extra") {
+                flatten_text(extra, text);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Removes legacy `§` formatting codes
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+async fn send_packet(stream: &mut TcpStream, data: &[u8]) -> crate::Result<()> {
+    let mut packet = Vec::with_capacity(data.len() + 5);
+    write_varint(&mut packet, data.len() as i32);
+    packet.extend(data);
+    stream.write_all(&packet).await.map_err(io::IOError::from)?;
+    Ok(())
+}
+
+async fn read_packet(stream: &mut TcpStream) -> crate::Result<Vec<u8>> {
+    let mut len: u32 = 0;
+    for i in 0..5 {
+        let byte = stream.read_u8().await.map_err(io::IOError::from)?;
+        len |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let len = len as usize;
+    if len > MAX_PACKET_SIZE {
+        return Err(crate::ErrorKind::OtherError(
+            "Server sent an oversized packet".to_string(),
+        )
+        .into());
+    }
+
+    let mut packet = vec![0; len];
+    stream
+        .read_exact(&mut packet)
+        .await
+        .map_err(io::IOError::from)?;
+    Ok(packet)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            bytes.push(value as u8);
+            return;
+        }
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> crate::Result<i32> {
+    let mut value: u32 = 0;
+    for i in 0..5 {
+        let (&byte, rest) = bytes.split_first().ok_or_else(|| {
+            crate::ErrorKind::OtherError("Truncated server packet".to_string())
+        })?;
+        *bytes = rest;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(
+        crate::ErrorKind::OtherError("Invalid server packet".to_string())
+            .into(),
+    )
+}
+
+fn ping_error(address: &str) -> crate::Error {
+    crate::ErrorKind::OtherError(format!(
+        "Server {address} sent an invalid status"
+    ))
+    .into()
+}
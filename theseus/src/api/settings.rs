@@ -1,5 +1,6 @@
 //! Theseus profile management interface
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use io::IOError;
@@ -12,8 +13,12 @@ use crate::{
     util::io,
 };
 pub use crate::{
+    logger::DebugCapture,
     state::{
-        Hooks, JavaSettings, MemorySettings, Profile, Settings, WindowSize,
+        ContentPolicy, DownloadMirror, Hooks, JavaSettings, LoggingSettings,
+        MemorySettings, MirrorPrefix, PostLaunchBehavior, PostLaunchPolicy,
+        Profile, RunningDownloads, Settings, WindowAction, WindowSize,
+        WriteAccessIssue,
     },
     State,
 };
@@ -26,6 +31,52 @@ pub async fn get() -> crate::Result<Settings> {
     Ok(settings.clone())
 }
 
+/// Gets what frontends should do around a game's lifetime
+#[tracing::instrument]
+pub async fn get_post_launch_policy() -> crate::Result<PostLaunchPolicy> {
+    let state = State::get().await?;
+    let settings = state.settings.read().await;
+    Ok(settings.post_launch_policy())
+}
+
+/// Gets the content policy installs are held to, if the computer is managed
+/// and one was deployed
+#[tracing::instrument]
+pub async fn get_content_policy() -> crate::Result<Option<ContentPolicy>> {
+    ContentPolicy::get().await
+}
+
+/// Gets request counters of the shared HTTP client
+#[tracing::instrument]
+pub async fn get_connection_metrics(
+) -> crate::Result<crate::util::fetch::ConnectionMetrics> {
+    Ok(crate::util::fetch::connection_metrics())
+}
+
+/// Gets whether downloads are paused or throttled for running games
+#[tracing::instrument]
+pub async fn get_download_status(
+) -> crate::Result<crate::util::fetch::DownloadStatus> {
+    Ok(crate::util::fetch::BANDWIDTH_LIMITER.status())
+}
+
+/// Gets how long each download host took to answer on average, in
+/// milliseconds. Mirrors of a download are tried fastest first
+#[tracing::instrument]
+pub async fn get_mirror_latencies() -> crate::Result<HashMap<String, u64>> {
+    Ok(crate::util::fetch::host_latencies())
+}
+
+/// Pauses or resumes installs by hand. Resuming while games run lifts the
+/// limits of `downloads_while_running` until the last of them exits
+#[tracing::instrument]
+pub async fn set_download_override(
+    manual: crate::util::fetch::DownloadOverride,
+) -> crate::Result<crate::util::fetch::DownloadStatus> {
+    crate::util::fetch::BANDWIDTH_LIMITER.set_override(manual);
+    Ok(crate::util::fetch::BANDWIDTH_LIMITER.status())
+}
+
 /// Sets entire settings
 #[tracing::instrument]
 pub async fn set(settings: Settings) -> crate::Result<()> {
@@ -40,15 +91,38 @@ pub async fn set(settings: Settings) -> crate::Result<()> {
         .as_error());
     }
 
-    let (reset_io, reset_fetch) = async {
+    for custom in &settings.log_censoring.custom_patterns {
+        crate::util::censor::Censor::validate_pattern(&custom.pattern)?;
+    }
+
+    if settings.logging != state.settings.read().await.logging {
+        crate::logger::set_filter(&settings.logging.directives())?;
+    }
+
+    let (reset_io, reset_fetch, reset_client, reset_update_checks) = async {
         let read = state.settings.read().await;
         (
             settings.max_concurrent_writes != read.max_concurrent_writes,
             settings.max_concurrent_downloads != read.max_concurrent_downloads,
+            settings.http != read.http,
+            settings.update_checks != read.update_checks,
         )
     }
     .await;
 
+    if reset_client {
+        crate::util::fetch::configure_client(&settings.http)?;
+    }
+    if reset_update_checks {
+        Profiles::schedule_update_checks(&settings.update_checks);
+    }
+
+    crate::util::fetch::BANDWIDTH_LIMITER.set_limits(
+        settings.max_download_speed,
+        settings.max_download_speed_while_running,
+    );
+    crate::util::fetch::BANDWIDTH_LIMITER
+        .set_running_policy(settings.downloads_while_running);
     {
         *state.settings.write().await = settings;
     }
@@ -64,6 +138,62 @@ pub async fn set(settings: Settings) -> crate::Result<()> {
     Ok(())
 }
 
+/// Sets the verbosity of the launcher's logs, applying it right away
+#[tracing::instrument]
+pub async fn set_logging(logging: LoggingSettings) -> crate::Result<()> {
+    crate::logger::set_filter(&logging.directives())?;
+    let state = State::get().await?;
+    state.settings.write().await.logging = logging;
+    State::sync().await
+}
+
+/// Records a debug log of the next install, modpack install or modpack
+/// update, going back to the logging settings once it ends. Asked of users
+/// whose installs fail, so the launcher doesn't need restarting with
+/// `RUST_LOG`
+#[tracing::instrument]
+pub async fn capture_debug_log() -> crate::Result<DebugCapture> {
+    Ok(crate::logger::arm_capture().await)
+}
+
+/// Gets the state of the last debug log capture
+#[tracing::instrument]
+pub async fn get_debug_capture() -> crate::Result<DebugCapture> {
+    Ok(crate::logger::get_capture().await)
+}
+
+/// Checks the launcher can still write to its folders, listing the ones it
+/// can't. Relocatable ones are fixed by moving the config dir
+#[tracing::instrument]
+pub async fn check_write_access() -> crate::Result<Vec<WriteAccessIssue>> {
+    let state = State::get().await?;
+    Ok(state.directories.check_write_access().await)
+}
+
+/// Suggests a folder to move the config dir to when it can't be written
+/// to. It is one the launcher can write to, outside any cloud synced folder
+#[tracing::instrument]
+pub async fn suggest_config_dir() -> crate::Result<Option<PathBuf>> {
+    let state = State::get().await?;
+    let config_dir = state.directories.config_dir.read().await.clone();
+
+    // The local data dir comes first, it isn't roamed on Windows
+    let candidates =
+        [dirs::data_local_dir(), dirs::data_dir(), dirs::home_dir()]
+            .into_iter()
+            .flatten()
+            .map(|x| x.join("com.modrinth.theseus"));
+    for candidate in candidates {
+        if candidate == config_dir || io::is_cloud_synced(&candidate) {
+            continue;
+        }
+        if io::check_write_access(&candidate).await.is_ok() {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
 /// Sets the new config dir, the location of all Theseus data except for the settings.json and caches
 /// Takes control of the entire state and blocks until completion
 pub async fn set_config_dir(new_config_dir: PathBuf) -> crate::Result<()> {
@@ -74,6 +204,7 @@ pub async fn set_config_dir(new_config_dir: PathBuf) -> crate::Result<()> {
         ))
         .as_error());
     }
+    io::check_write_access(&new_config_dir).await?;
 
     let loading_bar = init_loading(
         crate::LoadingBarType::ConfigChange {
@@ -151,7 +282,17 @@ pub async fn set_config_dir(new_config_dir: PathBuf) -> crate::Result<()> {
             }
 
             let new_path = new_config_dir.join(file_name);
-            io::rename(entry_path, new_path).await?;
+            // Moving out of a folder that can't be written to, or onto
+            // another drive, fails. Those are copied and the originals left
+            if let Err(err) = io::rename(&entry_path, &new_path).await {
+                tracing::warn!("Copying rather than moving: {err}");
+                crate::pack::import::copy_dir_to(
+                    &entry_path,
+                    &new_path,
+                    &state_write.io_semaphore,
+                )
+                .await?;
+            }
 
             i += 1.0;
             emit_loading(&loading_bar, 90.0 * (i / total_entries), None)
@@ -0,0 +1,8 @@
+//! Consistent copies of the launcher's state, for backup and sync tools
+pub use crate::state::StateSnapshot;
+
+/// Copies the profiles, settings and in-flight operations of the launcher
+/// all at once, with secrets redacted
+pub async fn get() -> crate::Result<StateSnapshot> {
+    crate::state::snapshot().await
+}
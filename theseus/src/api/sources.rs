@@ -0,0 +1,141 @@
+//! API for finding and installing content through the registered sources
+use crate::event::emit::emit_profile;
+use crate::event::ProfilePayloadType;
+use crate::state::{
+    ContentProject, ContentQuery, ContentSource, ContentVersion, Profile,
+    ProfilePathId, ProjectPathId, VersionFilter,
+};
+use crate::util::io;
+use crate::State;
+use sha2::Digest;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Lists the ids of every registered source
+#[tracing::instrument]
+pub async fn get_sources() -> crate::Result<Vec<String>> {
+    let state = State::get().await?;
+    let sources = state.content_sources.read().await;
+    Ok(sources.ids())
+}
+
+/// Registers a source, replacing any with the same id
+pub async fn register_source(
+    source: Arc<dyn ContentSource>,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let mut sources = state.content_sources.write().await;
+    sources.register(source);
+    Ok(())
+}
+
+/// Searches a source for projects
+#[tracing::instrument]
+pub async fn search(
+    source: &str,
+    query: &ContentQuery,
+) -> crate::Result<Vec<ContentProject>> {
+    get_source(source).await?.search(query).await
+}
+
+/// Lists the versions of a project from a source. If a profile is given,
+/// only versions for its game version and loader are returned
+#[tracing::instrument]
+pub async fn get_versions(
+    source: &str,
+    project_id: &str,
+    profile_path: Option<&ProfilePathId>,
+) -> crate::Result<Vec<ContentVersion>> {
+    let filter = match profile_path {
+        Some(profile_path) => get_filter(&get_profile(profile_path).await?),
+        None => VersionFilter::default(),
+    };
+
+    get_source(source)
+        .await?
+        .get_versions(project_id, &filter)
+        .await
+}
+
+/// Downloads a version through its source and adds it to a profile
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn install_version(
+    profile_path: &ProfilePathId,
+    version: &ContentVersion,
+) -> crate::Result<ProjectPathId> {
+    let profile = get_profile(profile_path).await?;
+    let file = version.primary_file().ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "Version {} has no files",
+            version.id
+        ))
+    })?;
+
+    let bytes = get_source(&version.source).await?.fetch_file(file).await?;
+    let path = profile
+        .add_project_bytes(&file.filename, bytes, None)
+        .await?;
+
+    emit_profile(
+        profile.uuid,
+        profile_path,
+        &profile.metadata.name,
+        ProfilePayloadType::Edited,
+    )
+    .await?;
+    State::sync().await?;
+
+    Ok(path)
+}
+
+/// Asks a source for newer versions of the projects in a profile
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn check_updates(
+    profile_path: &ProfilePathId,
+    source: &str,
+) -> crate::Result<HashMap<ProjectPathId, ContentVersion>> {
+    let profile = get_profile(profile_path).await?;
+    let full_path = profile.get_profile_full_path().await?;
+
+    let mut hashes = HashMap::new();
+    for project_path in profile.projects.keys() {
+        let bytes = io::read(full_path.join(&project_path.0)).await?;
+        let hash = format!("{:x}", sha2::Sha512::digest(&bytes));
+        hashes.insert(hash, project_path.clone());
+    }
+
+    let keys = hashes.keys().cloned().collect::<Vec<_>>();
+    let updates = get_source(source)
+        .await?
+        .check_updates(&keys, &get_filter(&profile))
+        .await?;
+
+    Ok(updates
+        .into_iter()
+        .filter_map(|(hash, version)| Some((hashes.remove(&hash)?, version)))
+        .collect())
+}
+
+async fn get_source(id: &str) -> crate::Result<Arc<dyn ContentSource>> {
+    let state = State::get().await?;
+    let sources = state.content_sources.read().await;
+    sources.get(id)
+}
+
+async fn get_profile(profile_path: &ProfilePathId) -> crate::Result<Profile> {
+    crate::profile::get(profile_path, None)
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+                .as_error()
+        })
+}
+
+fn get_filter(profile: &Profile) -> VersionFilter {
+    VersionFilter {
+        game_version: Some(profile.metadata.game_version.clone()),
+        loader: Some(profile.metadata.loader),
+    }
+}
@@ -0,0 +1,127 @@
+//! Summary of what the launcher is doing, for tray icons and menu bars
+use crate::state::ProjectMetadata;
+use crate::util::fetch::BANDWIDTH_LIMITER;
+use crate::{EventState, State};
+use serde::{Deserialize, Serialize};
+
+/// How often the status is checked for changes to push
+#[cfg(feature = "tauri")]
+const STATUS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Everything a tray icon shows, gathered in one call
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LauncherStatus {
+    pub offline: bool,
+    pub running_instances: usize,
+    pub downloads: DownloadsStatus,
+    pub pending_updates: PendingUpdates,
+    pub accounts: AccountsStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DownloadsStatus {
+    /// Loading bars still in progress
+    pub active: usize,
+    /// Progress of every active bar together, from 0 to 1
+    pub fraction: Option<f64>,
+    /// Combined download speed, in bytes per second
+    pub throughput: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingUpdates {
+    /// Managed modpacks with a newer version
+    pub packs: usize,
+    /// Modrinth projects with a newer version, across all profiles
+    pub projects: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccountsStatus {
+    pub total: usize,
+    /// Accounts whose tokens could not be refreshed, which can't launch
+    /// the game until they log in again
+    pub needs_reauth: usize,
+}
+
+/// Gets the status of the launcher. It only reads what is already in
+/// memory, so it is cheap enough to poll
+#[tracing::instrument]
+pub async fn get_status() -> crate::Result<LauncherStatus> {
+    let state = State::get().await?;
+    let running_instances = state.children.read().await.running_count().await;
+
+    let mut packs = 0;
+    let mut projects = 0;
+    for profile in state.profiles.read().await.0.values() {
+        if profile.modrinth_update_version.is_some() {
+            packs += 1;
+        }
+        projects += profile
+            .projects
+            .values()
+            .filter(|x| {
+                matches!(
+                    &x.metadata,
+                    ProjectMetadata::Modrinth {
+                        update_version: Some(_),
+                        ..
+                    }
+                )
+            })
+            .count();
+    }
+
+    let users = state.users.read().await;
+    let accounts = AccountsStatus {
+        total: users.0.len(),
+        needs_reauth: users.0.values().filter(|x| x.needs_reauth).count(),
+    };
+    drop(users);
+
+    let event_state = EventState::get().await?;
+    let bars = event_state.loading_bars.read().await;
+    let (current, total) = bars.values().filter(|x| x.total > 0.0).fold(
+        (0.0, 0.0),
+        |(current, total), x| {
+            (current + x.current.min(x.total), total + x.total)
+        },
+    );
+    let downloads = DownloadsStatus {
+        active: bars.len(),
+        fraction: (total > 0.0).then(|| current / total),
+        throughput: BANDWIDTH_LIMITER.throughput(),
+    };
+
+    Ok(LauncherStatus {
+        offline: *state.offline.read().await,
+        running_instances,
+        downloads,
+        pending_updates: PendingUpdates { packs, projects },
+        accounts,
+    })
+}
+
+/// Pushes the status as a `status` event whenever it changes, so frontends
+/// don't need to poll it
+#[cfg(feature = "tauri")]
+pub(crate) fn start_status_events() {
+    tokio::task::spawn(async {
+        let mut last = None;
+        loop {
+            match get_status().await {
+                Ok(status) if last.as_ref() != Some(&status) => {
+                    if let Err(err) =
+                        crate::event::emit::emit_status(&status).await
+                    {
+                        tracing::warn!("Unable to send status: {err}");
+                    }
+                    last = Some(status);
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("Unable to get status: {err}"),
+            }
+            tokio::time::sleep(STATUS_INTERVAL).await;
+        }
+    });
+}
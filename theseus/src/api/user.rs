@@ -0,0 +1,271 @@
+//! The Modrinth user's library: the projects they follow, own or have
+//! installed, each ready to install with one call
+use crate::config::MODRINTH_API_URL;
+use crate::pack::install_from::{
+    get_profile_from_pack, location_from_modrinth_id,
+};
+use crate::pack::install_mrpack::install_zipped_mrpack;
+use crate::prelude::{ModLoader, ProfilePathId};
+use crate::profile::create::profile_create_from_creator;
+use crate::state::{
+    fetch_projects, CredentialsStore, ModrinthProject, ModrinthVersion,
+    ProjectMetadata,
+};
+use crate::util::fetch::{fetch_advanced, fetch_json};
+use crate::State;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Why a project is in the library
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LibrarySource {
+    Followed,
+    /// Published by the user, or a team they are in
+    Owned,
+    /// Installed in one of the user's profiles
+    Installed,
+}
+
+/// What installing a library entry does
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallTarget {
+    /// A modpack, installed as a new profile
+    NewProfile,
+    /// Content added to a profile the user picks
+    Profile,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LibraryEntry {
+    pub project: ModrinthProject,
+    pub sources: Vec<LibrarySource>,
+    /// Profiles the project is installed in, or linked to for modpacks
+    pub installed_in: Vec<ProfilePathId>,
+    pub target: InstallTarget,
+}
+
+// Projects of a user, with the session sent along as the projects they
+// follow are only listed to them
+async fn fetch_user_projects(
+    user_id: &str,
+    route: &str,
+) -> crate::Result<Vec<ModrinthProject>> {
+    let state = State::get().await?;
+    let session = state
+        .credentials
+        .read()
+        .await
+        .0
+        .as_ref()
+        .map(|x| x.session.clone())
+        .ok_or(crate::ErrorKind::NoCredentialsError)?;
+    let bytes = fetch_advanced(
+        Method::GET,
+        &format!("{MODRINTH_API_URL}user/{user_id}/{route}"),
+        None,
+        None,
+        Some(("Authorization", &session)),
+        None,
+        &state.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+// Adds a project to the library, or the source to its entry if it is
+// already in it
+fn add_entry(
+    entries: &mut BTreeMap<String, LibraryEntry>,
+    project: ModrinthProject,
+    source: LibrarySource,
+) {
+    let entry =
+        entries
+            .entry(project.id.clone())
+            .or_insert_with(|| LibraryEntry {
+                target: if project.project_type == "modpack" {
+                    InstallTarget::NewProfile
+                } else {
+                    InstallTarget::Profile
+                },
+                project,
+                sources: Vec::new(),
+                installed_in: Vec::new(),
+            });
+    if !entry.sources.contains(&source) {
+        entry.sources.push(source);
+    }
+}
+
+/// Lists the projects the logged in Modrinth user follows or owns, and those
+/// installed in their profiles, sorted by title
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn library() -> crate::Result<Vec<LibraryEntry>> {
+    let state = State::get().await?;
+    let user_id = state
+        .credentials
+        .read()
+        .await
+        .0
+        .as_ref()
+        .map(|x| x.user.id.clone())
+        .ok_or(crate::ErrorKind::NoCredentialsError)?;
+
+    let mut entries = BTreeMap::<String, LibraryEntry>::new();
+    for project in fetch_user_projects(&user_id, "follows").await? {
+        add_entry(&mut entries, project, LibrarySource::Followed);
+    }
+    for project in fetch_user_projects(&user_id, "projects").await? {
+        add_entry(&mut entries, project, LibrarySource::Owned);
+    }
+
+    // Projects installed in profiles, with the profiles they are in
+    let mut installed = BTreeMap::<String, Vec<ProfilePathId>>::new();
+    for (profile_path, profile) in crate::profile::list(None).await? {
+        let pack = profile
+            .metadata
+            .linked_data
+            .as_ref()
+            .and_then(|x| x.project_id.clone());
+        let projects =
+            profile.projects.values().filter_map(|x| match &x.metadata {
+                ProjectMetadata::Modrinth { project, .. } => {
+                    Some(project.id.clone())
+                }
+                _ => None,
+            });
+        for project_id in pack.into_iter().chain(projects) {
+            let profiles = installed.entry(project_id).or_default();
+            if !profiles.contains(&profile_path) {
+                profiles.push(profile_path.clone());
+            }
+        }
+    }
+
+    let missing = installed
+        .keys()
+        .filter(|x| !entries.contains_key(*x))
+        .cloned()
+        .collect::<Vec<_>>();
+    let fetched = {
+        let creds = state.credentials.read().await;
+        fetch_projects(&missing, &state.fetch_semaphore, &creds).await?
+    };
+    for project in fetched.into_values() {
+        add_entry(&mut entries, project, LibrarySource::Installed);
+    }
+    for (project_id, profiles) in installed {
+        if let Some(entry) = entries.get_mut(&project_id) {
+            if !entry.sources.contains(&LibrarySource::Installed) {
+                entry.sources.push(LibrarySource::Installed);
+            }
+            entry.installed_in = profiles;
+            entry.installed_in.sort_by_key(|x| x.to_string());
+        }
+    }
+
+    let mut entries = entries.into_values().collect::<Vec<_>>();
+    entries.sort_by_key(|x| x.project.title.to_lowercase());
+    Ok(entries)
+}
+
+/// Installs a project of the library: modpacks as a new profile, other
+/// projects into the given profile at their newest version for its game
+/// version and loader. Returns the profile installed to
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn install(
+    project_id: &str,
+    profile_path: Option<&ProfilePathId>,
+) -> crate::Result<ProfilePathId> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let project = fetch_projects(
+        &[project_id.to_string()],
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?
+    .into_values()
+    .next()
+    .ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "No Modrinth project found for {project_id}"
+        ))
+    })?;
+
+    let Some(profile_path) = profile_path else {
+        drop(creds);
+        if project.project_type != "modpack" {
+            return Err(crate::ErrorKind::InputError(format!(
+                "{} isn't a modpack, it is installed into a profile",
+                project.title
+            ))
+            .into());
+        }
+        let location = location_from_modrinth_id(&project.id).await?;
+        let profile = profile_create_from_creator(get_profile_from_pack(
+            location.clone(),
+        ))
+        .await?;
+        return install_zipped_mrpack(location, profile).await;
+    };
+    if project.project_type == "modpack" {
+        return Err(crate::ErrorKind::InputError(format!(
+            "{} is a modpack, it is installed as a new profile",
+            project.title
+        ))
+        .into());
+    }
+
+    let profile =
+        crate::profile::get(profile_path, None)
+            .await?
+            .ok_or_else(|| {
+                crate::ErrorKind::UnmanagedProfileError(
+                    profile_path.to_string(),
+                )
+            })?;
+
+    let mut url = format!(
+        "{MODRINTH_API_URL}project/{}/version?game_versions={}",
+        project.id,
+        serde_json::to_string(&[&profile.metadata.game_version])?
+    );
+    if project.project_type == "mod"
+        && profile.metadata.loader != ModLoader::Vanilla
+    {
+        url.push_str(&format!(
+            "&loaders={}",
+            serde_json::to_string(&[profile.metadata.loader.as_api_str()])?
+        ));
+    }
+    let versions: Vec<ModrinthVersion> = fetch_json(
+        Method::GET,
+        &url,
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+    drop(creds);
+
+    // Versions are sorted newest first by labrinth
+    let version = versions.into_iter().next().ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "{} has no version for {} {}",
+            project.title,
+            profile.metadata.game_version,
+            profile.metadata.loader
+        ))
+    })?;
+    crate::profile::add_project_from_version(profile_path, version.id).await?;
+
+    Ok(profile_path.clone())
+}
@@ -0,0 +1,376 @@
+//! API for managing the worlds of a profile and the datapacks inside them
+use crate::config::MODRINTH_API_URL;
+use crate::state::{ModrinthVersion, ProfilePathId};
+use crate::util::fetch::{fetch, fetch_json, write};
+use crate::util::io::{self, IOError};
+use crate::State;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Digest;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const SAVES_FOLDER: &str = "saves";
+pub const DATAPACKS_FOLDER: &str = "datapacks";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct World {
+    /// Folder name, used to identify the world within the profile
+    pub name: String,
+    pub path: PathBuf,
+    /// When the world was last saved, taken from its level.dat
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Datapack {
+    /// File or folder name, used to identify the datapack within the world
+    pub name: String,
+    pub path: PathBuf,
+    pub disabled: bool,
+    /// Unzipped datapacks are folders, which can't be installed, toggled or
+    /// updated through the launcher
+    pub folder: bool,
+    /// SHA-512 hash of the file, used to match it against Modrinth
+    pub sha512: Option<String>,
+}
+
+/// Lists the worlds of a profile, most recently played first
+#[tracing::instrument]
+pub async fn get_worlds(
+    profile_path: &ProfilePathId,
+) -> crate::Result<Vec<World>> {
+    let saves = get_saves_dir(profile_path).await?;
+
+    let mut worlds = Vec::new();
+    if saves.exists() {
+        let mut read_dir = io::read_dir(&saves).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &saves))?
+        {
+            let path = entry.path();
+            let level = path.join("level.dat");
+            if !level.exists() {
+                continue;
+            }
+
+            let last_played = tokio::fs::metadata(&level)
+                .await
+                .and_then(|x| x.modified())
+                .ok()
+                .map(DateTime::<Utc>::from);
+            worlds.push(World {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path,
+                last_played,
+            });
+        }
+    }
+
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played));
+    Ok(worlds)
+}
+
+/// Lists the datapacks installed in a world, enabled or not
+#[tracing::instrument]
+pub async fn get_datapacks(
+    profile_path: &ProfilePathId,
+    world: &str,
+) -> crate::Result<Vec<Datapack>> {
+    let folder = get_datapacks_dir(profile_path, world).await?;
+
+    let mut datapacks = Vec::new();
+    if folder.exists() {
+        let mut read_dir = io::read_dir(&folder).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &folder))?
+        {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                datapacks.push(Datapack {
+                    name,
+                    path,
+                    disabled: false,
+                    folder: true,
+                    sha512: None,
+                });
+            } else if is_datapack(&name) {
+                let bytes = io::read(&path).await?;
+                let sha512 = format!("{:x}", sha2::Sha512::digest(&bytes));
+                datapacks.push(Datapack {
+                    disabled: name.ends_with(".disabled"),
+                    sha512: Some(sha512),
+                    name,
+                    path,
+                    folder: false,
+                });
+            }
+        }
+    }
+
+    datapacks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(datapacks)
+}
+
+/// Downloads a Modrinth version of a datapack into a world, returning the
+/// name of the installed file
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn install_datapack(
+    profile_path: &ProfilePathId,
+    world: &str,
+    version_id: &str,
+) -> crate::Result<String> {
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let version = fetch_json::<ModrinthVersion>(
+        Method::GET,
+        &format!("{MODRINTH_API_URL}version/{version_id}"),
+        None,
+        None,
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+    drop(creds);
+
+    install_datapack_version(profile_path, world, &version).await
+}
+
+/// Enables or disables a datapack, returning its new file name
+#[tracing::instrument]
+pub async fn toggle_disable_datapack(
+    profile_path: &ProfilePathId,
+    world: &str,
+    name: &str,
+) -> crate::Result<String> {
+    let path = get_datapack_path(profile_path, world, name).await?;
+    if path.is_dir() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Datapack {name} is a folder and can't be disabled"
+        ))
+        .into());
+    }
+
+    let new_name = match name.strip_suffix(".disabled") {
+        Some(enabled) => enabled.to_string(),
+        None => format!("{name}.disabled"),
+    };
+    io::rename(&path, path.with_file_name(&new_name)).await?;
+
+    Ok(new_name)
+}
+
+/// Deletes a datapack from a world
+#[tracing::instrument]
+pub async fn remove_datapack(
+    profile_path: &ProfilePathId,
+    world: &str,
+    name: &str,
+) -> crate::Result<()> {
+    let path = get_datapack_path(profile_path, world, name).await?;
+    if path.is_dir() {
+        io::remove_dir_all(&path).await?;
+    } else {
+        io::remove_file(&path).await?;
+    }
+    Ok(())
+}
+
+/// Asks Modrinth for newer versions of the datapacks in a world, keyed by
+/// the name of the datapack they would replace
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn check_datapack_updates(
+    profile_path: &ProfilePathId,
+    world: &str,
+) -> crate::Result<HashMap<String, ModrinthVersion>> {
+    let profile = crate::profile::get(profile_path, Some(true))
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?;
+
+    let mut hashes = HashMap::new();
+    for datapack in get_datapacks(profile_path, world).await? {
+        if let Some(sha512) = datapack.sha512 {
+            hashes.insert(sha512, datapack.name);
+        }
+    }
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let updates = fetch_json::<HashMap<String, ModrinthVersion>>(
+        Method::POST,
+        &format!("{MODRINTH_API_URL}version_files/update"),
+        None,
+        Some(json!({
+            "hashes": hashes.keys().collect::<Vec<_>>(),
+            "algorithm": "sha512",
+            "loaders": ["datapack"],
+            "game_versions": [profile.metadata.game_version]
+        })),
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+
+    Ok(updates
+        .into_iter()
+        .filter_map(|(hash, version)| {
+            let name = hashes.remove(&hash)?;
+            let current = version
+                .files
+                .iter()
+                .any(|x| x.hashes.get("sha512").map_or(false, |x| *x == hash));
+            (!current).then_some((name, version))
+        })
+        .collect())
+}
+
+/// Replaces a datapack with its newest version, returning the name of the
+/// new file. A disabled datapack stays disabled
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn update_datapack(
+    profile_path: &ProfilePathId,
+    world: &str,
+    name: &str,
+) -> crate::Result<String> {
+    let mut updates = check_datapack_updates(profile_path, world).await?;
+    let version = updates.remove(name).ok_or_else(|| {
+        crate::ErrorKind::InputError(format!(
+            "Datapack {name} has no update available"
+        ))
+    })?;
+
+    let mut new_name =
+        install_datapack_version(profile_path, world, &version).await?;
+    if new_name != name.trim_end_matches(".disabled") {
+        remove_datapack(profile_path, world, name).await?;
+    }
+    if name.ends_with(".disabled") {
+        new_name =
+            toggle_disable_datapack(profile_path, world, &new_name).await?;
+    }
+
+    Ok(new_name)
+}
+
+/// Gets the saves folder of a profile
+#[tracing::instrument]
+pub async fn get_saves_dir(
+    profile_path: &ProfilePathId,
+) -> crate::Result<PathBuf> {
+    let profile = crate::profile::get(profile_path, Some(true))
+        .await?
+        .ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(profile_path.to_string())
+        })?;
+
+    Ok(profile.get_profile_full_path().await?.join(SAVES_FOLDER))
+}
+
+/// Gets the datapacks folder of a world
+#[tracing::instrument]
+pub async fn get_datapacks_dir(
+    profile_path: &ProfilePathId,
+    world: &str,
+) -> crate::Result<PathBuf> {
+    validate_name(world, "world")?;
+    let world_path = get_saves_dir(profile_path).await?.join(world);
+    if !world_path.join("level.dat").exists() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "World {world} does not exist"
+        ))
+        .into());
+    }
+
+    Ok(world_path.join(DATAPACKS_FOLDER))
+}
+
+async fn install_datapack_version(
+    profile_path: &ProfilePathId,
+    world: &str,
+    version: &ModrinthVersion,
+) -> crate::Result<String> {
+    if !version.loaders.iter().any(|x| x == "datapack") {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Version {} is not a datapack",
+            version.id
+        ))
+        .into());
+    }
+    let file = version
+        .files
+        .iter()
+        .find(|x| x.primary)
+        .or_else(|| version.files.first())
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Version {} has no files",
+                version.id
+            ))
+        })?;
+    validate_name(&file.filename, "datapack")?;
+
+    let folder = get_datapacks_dir(profile_path, world).await?;
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+    let bytes = fetch(
+        &file.url,
+        file.hashes.get("sha1").map(|x| &**x),
+        &state.fetch_semaphore,
+        &creds,
+    )
+    .await?;
+    drop(creds);
+
+    write(&folder.join(&file.filename), &bytes, &state.io_semaphore).await?;
+    Ok(file.filename.clone())
+}
+
+fn is_datapack(name: &str) -> bool {
+    let name = name.trim_end_matches(".disabled");
+    Path::new(name)
+        .extension()
+        .map_or(false, |x| x.eq_ignore_ascii_case("zip"))
+}
+
+// Resolves a datapack by name, rejecting anything outside of the world
+async fn get_datapack_path(
+    profile_path: &ProfilePathId,
+    world: &str,
+    name: &str,
+) -> crate::Result<PathBuf> {
+    validate_name(name, "datapack")?;
+    let path = get_datapacks_dir(profile_path, world).await?.join(name);
+    if !path.exists() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Datapack {name} does not exist"
+        ))
+        .into());
+    }
+    Ok(path)
+}
+
+fn validate_name(name: &str, kind: &str) -> crate::Result<()> {
+    let file_name = Path::new(name).file_name();
+    if file_name.map_or(true, |x| x != name) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Invalid {kind} name: {name}"
+        ))
+        .into());
+    }
+    Ok(())
+}
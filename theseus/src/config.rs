@@ -1,3 +1,5 @@
 //! Configuration structs
 
 pub const MODRINTH_API_URL: &str = "https://api.modrinth.com/v2/";
+pub const MODRINTH_API_URL_V3: &str = "https://api.modrinth.com/v3/";
+pub const MCLOGS_API_URL: &str = "https://api.mclo.gs/1/log";
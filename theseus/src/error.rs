@@ -1,5 +1,6 @@
 //! Theseus error type
 use crate::{profile, util};
+use serde::Serialize;
 use tracing_error::InstrumentError;
 
 #[derive(thiserror::Error, Debug)]
@@ -28,15 +29,64 @@ pub enum ErrorKind {
     #[error("Minecraft authentication Hydra error: {0}")]
     HydraError(String),
 
+    #[error("Microsoft account needs to grant permissions again: {0}")]
+    ConsentRequired(String),
+
+    #[error("Authentication server error: {0}")]
+    YggdrasilError(String),
+
     #[error("Minecraft authentication task error: {0}")]
     AuthTaskError(#[from] crate::state::AuthTaskError),
 
     #[error("I/O error: {0}")]
-    IOError(#[from] util::io::IOError),
+    IOError(util::io::IOError),
+
+    #[error("Permission denied for {0}: {1}")]
+    PermissionError(String, util::io::PermissionCause),
+
+    #[error("Not enough disk space or quota left to write {0}")]
+    DiskFullError(String),
 
     #[error("Error launching Minecraft: {0}")]
     LauncherError(String),
 
+    #[error("Invalid or unknown Minecraft version: {0}")]
+    InvalidGameVersion(String),
+
+    #[error("No Java installation found for {profile_path}")]
+    JavaMissing { profile_path: String },
+
+    #[error("Java path invalid or non-functional: {java_path}")]
+    InvalidJava { java_path: String },
+
+    #[error("Profile {profile_path} is already running")]
+    ProfileRunning { profile_path: String },
+
+    #[error("Profile {profile_path} is still installing")]
+    ProfileInstalling { profile_path: String },
+
+    #[error("Profile {profile_path} is archived, restore it before launching")]
+    ProfileArchived { profile_path: String },
+
+    #[error("Processor {jar} failed: {output}")]
+    ProcessorFailed {
+        jar: String,
+        exit_code: Option<i32>,
+        output: String,
+    },
+
+    #[error("Non-zero exit code for {hook} hook: {exit_code}")]
+    HookFailed { hook: String, exit_code: i32 },
+
+    #[error("Required project {0} could not be installed: {1}")]
+    CriticalProjectError(String, String),
+
+    #[error("Native libraries failed to extract: {0}")]
+    MissingNatives(crate::launcher::download::MissingNatives),
+
+    #[error("Blocked by the content policy: {0}")]
+    PolicyViolation(crate::state::PolicyViolation),
+
     #[error("Error fetching URL: {0}")]
     FetchError(#[from] reqwest::Error),
 
@@ -49,15 +99,24 @@ pub enum ErrorKind {
     #[error("Incorrect Sha1 hash for download: {0} != {1}")]
     HashError(String, String),
 
+    #[error("Invalid response from {0}: {1}")]
+    InvalidResponseError(String, String),
+
     #[error("Regex error: {0}")]
     RegexError(#[from] regex::Error),
 
     #[error("Paths stored in the database need to be valid UTF-8: {0}")]
     UTFError(std::path::PathBuf),
 
+    #[error("Unsupported format: {0}")]
+    FormatVersionError(String),
+
     #[error("Invalid input: {0}")]
     InputError(String),
 
+    #[error("Invalid config file: {0}")]
+    ConfigError(String),
+
     #[error("Join handle error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
 
@@ -97,14 +156,202 @@ pub enum ErrorKind {
     #[error("Error: {0}")]
     OtherError(String),
 
+    #[cfg(feature = "fault_injection")]
+    #[error("Injected failure: {0}")]
+    InjectedFault(String),
+
     #[cfg(feature = "tauri")]
     #[error("Tauri error: {0}")]
     TauriError(#[from] tauri::Error),
 }
 
+/// What the user can do about an error, for front ends to offer
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    Retry,
+    CheckConnection,
+    SignIn,
+    InstallJava,
+    SelectJava,
+    RepairProfile,
+    StopGame,
+    WaitForInstall,
+    RestoreProfile,
+    FreeDiskSpace,
+    CheckPermissions,
+    ChangeInput,
+    UpdateLauncher,
+}
+
+/// Machine readable description of an error, sent along with its message
+/// to front ends. Fields that don't apply to the error are unset
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ErrorDetails {
+    /// Identifier of the kind of error, which doesn't change between
+    /// releases unlike the message
+    pub code: &'static str,
+    pub recovery: Option<RecoveryAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl ErrorKind {
+    /// Stable identifier of the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::FSError(_) => "fs",
+            ErrorKind::INIError(_) => "ini",
+            ErrorKind::JSONError(_) => "json",
+            ErrorKind::UUIDError(_) => "uuid",
+            ErrorKind::URLError(_) => "url",
+            ErrorKind::NoValueFor(_) => "no_value",
+            ErrorKind::MetadataError(_) => "metadata",
+            ErrorKind::HydraError(_) => "hydra",
+            ErrorKind::ConsentRequired(_) => "consent_required",
+            ErrorKind::YggdrasilError(_) => "yggdrasil",
+            ErrorKind::AuthTaskError(_) => "auth_task",
+            ErrorKind::IOError(_) => "io",
+            ErrorKind::PermissionError(..) => "permission_denied",
+            ErrorKind::DiskFullError(_) => "disk_full",
+            ErrorKind::LauncherError(_) => "launcher",
+            ErrorKind::InvalidGameVersion(_) => "invalid_game_version",
+            ErrorKind::JavaMissing { .. } => "java_missing",
+            ErrorKind::InvalidJava { .. } => "invalid_java",
+            ErrorKind::ProfileRunning { .. } => "profile_running",
+            ErrorKind::ProfileInstalling { .. } => "profile_installing",
+            ErrorKind::ProfileArchived { .. } => "profile_archived",
+            ErrorKind::ProcessorFailed { .. } => "processor_failed",
+            ErrorKind::HookFailed { .. } => "hook_failed",
+            ErrorKind::CriticalProjectError(..) => "critical_project",
+            ErrorKind::MissingNatives(_) => "missing_natives",
+            ErrorKind::PolicyViolation(_) => "policy_violation",
+            ErrorKind::FetchError(_) => "fetch",
+            ErrorKind::WSError(_) => "websocket",
+            ErrorKind::WSClosedError(_) => "websocket_closed",
+            ErrorKind::HashError(..) => "hash_mismatch",
+            ErrorKind::InvalidResponseError(..) => "invalid_response",
+            ErrorKind::RegexError(_) => "regex",
+            ErrorKind::UTFError(_) => "utf8",
+            ErrorKind::FormatVersionError(_) => "unsupported_format",
+            ErrorKind::InputError(_) => "invalid_input",
+            ErrorKind::ConfigError(_) => "invalid_config",
+            ErrorKind::JoinError(_) => "join",
+            ErrorKind::RecvError(_) => "recv",
+            ErrorKind::AcquireError(_) => "acquire",
+            ErrorKind::UnmanagedProfileError(_) => "unmanaged_profile",
+            ErrorKind::ProfileCreationError(_) => "profile_creation",
+            ErrorKind::NoCredentialsError => "no_credentials",
+            ErrorKind::JREError(_) => "jre",
+            ErrorKind::ChronoParseError(_) => "date",
+            ErrorKind::EventError(_) => "event",
+            ErrorKind::ZipError(_) => "zip",
+            ErrorKind::NotifyError(_) => "file_watch",
+            ErrorKind::StripPrefixError(_) => "strip_prefix",
+            ErrorKind::OtherError(_) => "other",
+            #[cfg(feature = "fault_injection")]
+            ErrorKind::InjectedFault(_) => "injected_fault",
+            #[cfg(feature = "tauri")]
+            ErrorKind::TauriError(_) => "tauri",
+        }
+    }
+
+    /// What the user can do about the error, if anything
+    pub fn recovery(&self) -> Option<RecoveryAction> {
+        let action = match self {
+            ErrorKind::FetchError(_)
+            | ErrorKind::WSError(_)
+            | ErrorKind::WSClosedError(_)
+            | ErrorKind::InvalidResponseError(..) => {
+                RecoveryAction::CheckConnection
+            }
+            ErrorKind::HashError(..) | ErrorKind::MetadataError(_) => {
+                RecoveryAction::Retry
+            }
+            ErrorKind::HydraError(_)
+            | ErrorKind::ConsentRequired(_)
+            | ErrorKind::YggdrasilError(_)
+            | ErrorKind::NoCredentialsError => RecoveryAction::SignIn,
+            ErrorKind::JavaMissing { .. } => RecoveryAction::InstallJava,
+            ErrorKind::InvalidJava { .. } | ErrorKind::JREError(_) => {
+                RecoveryAction::SelectJava
+            }
+            ErrorKind::ProcessorFailed { .. }
+            | ErrorKind::MissingNatives(_)
+            | ErrorKind::CriticalProjectError(..) => {
+                RecoveryAction::RepairProfile
+            }
+            ErrorKind::ProfileRunning { .. } => RecoveryAction::StopGame,
+            ErrorKind::ProfileInstalling { .. } => {
+                RecoveryAction::WaitForInstall
+            }
+            ErrorKind::ProfileArchived { .. } => RecoveryAction::RestoreProfile,
+            ErrorKind::DiskFullError(_) => RecoveryAction::FreeDiskSpace,
+            ErrorKind::PermissionError(..) => RecoveryAction::CheckPermissions,
+            ErrorKind::InputError(_)
+            | ErrorKind::ConfigError(_)
+            | ErrorKind::HookFailed { .. }
+            | ErrorKind::InvalidGameVersion(_) => RecoveryAction::ChangeInput,
+            ErrorKind::FormatVersionError(_) => RecoveryAction::UpdateLauncher,
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    /// The code, recovery action and structured fields of the error
+    pub fn details(&self) -> ErrorDetails {
+        let mut details = ErrorDetails {
+            code: self.code(),
+            recovery: self.recovery(),
+            ..Default::default()
+        };
+        match self {
+            ErrorKind::JavaMissing { profile_path }
+            | ErrorKind::ProfileRunning { profile_path }
+            | ErrorKind::ProfileInstalling { profile_path }
+            | ErrorKind::ProfileArchived { profile_path }
+            | ErrorKind::UnmanagedProfileError(profile_path) => {
+                details.profile_path = Some(profile_path.clone());
+            }
+            ErrorKind::InvalidJava { java_path } => {
+                details.java_path = Some(java_path.clone());
+            }
+            ErrorKind::ProcessorFailed { exit_code, .. } => {
+                details.exit_code = *exit_code;
+            }
+            ErrorKind::HookFailed { exit_code, .. } => {
+                details.exit_code = Some(*exit_code);
+            }
+            ErrorKind::FetchError(err) => {
+                details.url = err.url().map(|x| x.to_string());
+            }
+            ErrorKind::InvalidResponseError(url, _) => {
+                details.url = Some(url.clone());
+            }
+            _ => {}
+        }
+        details
+    }
+}
+
 #[derive(Debug)]
 pub struct Error {
     source: tracing_error::TracedError<ErrorKind>,
+    // Taken before the kind is wrapped in the span trace, which hides it
+    details: ErrorDetails,
+}
+
+impl Error {
+    /// The code, recovery action and structured fields of the error
+    pub fn details(&self) -> &ErrorDetails {
+        &self.details
+    }
 }
 
 impl std::error::Error for Error {
@@ -121,8 +368,10 @@ impl std::fmt::Display for Error {
 
 impl<E: Into<ErrorKind>> From<E> for Error {
     fn from(source: E) -> Self {
+        let kind = Into::<ErrorKind>::into(source);
         Self {
-            source: Into::<ErrorKind>::into(source).in_current_span(),
+            details: kind.details(),
+            source: kind.in_current_span(),
         }
     }
 }
@@ -133,4 +382,14 @@ impl ErrorKind {
     }
 }
 
+// Refused access is told apart from other I/O errors, with its likely cause
+impl From<util::io::IOError> for ErrorKind {
+    fn from(err: util::io::IOError) -> Self {
+        match err.permission_denied() {
+            Some((path, cause)) => ErrorKind::PermissionError(path, cause),
+            None => ErrorKind::IOError(err),
+        }
+    }
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
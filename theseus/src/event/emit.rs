@@ -1,17 +1,19 @@
 use super::LoadingBarId;
 use crate::{
     event::{
-        CommandPayload, EventError, LoadingBar, LoadingBarType,
-        ProcessPayloadType, ProfilePayloadType,
+        AccountPayloadType, CommandPayload, EventError, LoadingBar,
+        LoadingBarType, ProcessPayloadType, ProfilePayloadType,
+        ScreenshotPayloadType, SyncPayloadType, UpdatesAvailablePayload,
     },
     prelude::ProfilePathId,
-    state::{ProcessType, SafeProcesses},
+    state::{ProcessType, SafeProcesses, StateComponent},
 };
 use futures::prelude::*;
 
 #[cfg(feature = "tauri")]
 use crate::event::{
-    LoadingPayload, ProcessPayload, ProfilePayload, WarningPayload,
+    AccountPayload, LoadingPayload, ProcessPayload, ProfilePayload,
+    ScreenshotPayload, SyncPayload, WarningPayload,
 };
 #[cfg(feature = "tauri")]
 use tauri::Manager;
@@ -80,6 +82,8 @@ pub async fn init_loading_unsafe(
             current: 0.0,
             last_sent: 0.0,
             bar_type,
+            transfer: None,
+            transfer_started: None,
             #[cfg(feature = "cli")]
             cli_progress_bar: {
                 let pb = indicatif::ProgressBar::new(CLI_PROGRESS_BAR_TOTAL);
@@ -175,6 +179,12 @@ pub async fn emit_loading(
         Some(display_frac)
     };
 
+    if let (Some(transfer), Some(started)) =
+        (&mut loading_bar.transfer, loading_bar.transfer_started)
+    {
+        transfer.update_rate(started.elapsed());
+    }
+
     if f64::abs(display_frac - loading_bar.last_sent) > 0.005 {
         // Emit event to indicatif progress bar
         #[cfg(feature = "cli")]
@@ -202,6 +212,9 @@ pub async fn emit_loading(
                         .to_string(),
                     event: loading_bar.bar_type.clone(),
                     loader_uuid: loading_bar.loading_bar_uuid,
+                    throughput: crate::util::fetch::BANDWIDTH_LIMITER
+                        .throughput(),
+                    transfer: loading_bar.transfer.clone(),
                 },
             )
             .map_err(EventError::from)?;
@@ -212,10 +225,63 @@ pub async fn emit_loading(
     Ok(())
 }
 
+// Adds bytes the loading bar still has to download, so its progress events
+// carry byte counts and an ETA from now on
+pub async fn add_loading_bytes(
+    key: &LoadingBarId,
+    total_bytes: u64,
+) -> crate::Result<()> {
+    let event_state = crate::EventState::get().await?;
+    let mut loading_bars = event_state.loading_bars.write().await;
+    let loading_bar = loading_bars
+        .get_mut(&key.0)
+        .ok_or(EventError::NoLoadingBar(key.0))?;
+
+    loading_bar
+        .transfer_started
+        .get_or_insert_with(std::time::Instant::now);
+    loading_bar
+        .transfer
+        .get_or_insert_with(Default::default)
+        .total_bytes += total_bytes;
+    Ok(())
+}
+
+// Records a downloaded file of a loading bar. It is sent with the next
+// emit_loading, as files usually complete a step of the bar anyway
+pub async fn record_loading_bytes(
+    key: &LoadingBarId,
+    bytes: u64,
+    file_name: &str,
+) -> crate::Result<()> {
+    let event_state = crate::EventState::get().await?;
+    let mut loading_bars = event_state.loading_bars.write().await;
+    let loading_bar = loading_bars
+        .get_mut(&key.0)
+        .ok_or(EventError::NoLoadingBar(key.0))?;
+
+    if let Some(transfer) = &mut loading_bar.transfer {
+        transfer.downloaded_bytes += bytes;
+        transfer.current_file = Some(file_name.to_string());
+    }
+    Ok(())
+}
+
 // emit_warning(message)
 #[allow(dead_code)]
 #[allow(unused_variables)]
 pub async fn emit_warning(message: &str) -> crate::Result<()> {
+    emit_coded_warning(message, None).await
+}
+
+// emit_coded_warning(message, code)
+// For warnings about known problems, with a code the frontend links to help
+#[allow(dead_code)]
+#[allow(unused_variables)]
+pub async fn emit_coded_warning(
+    message: &str,
+    code: Option<&str>,
+) -> crate::Result<()> {
     #[cfg(feature = "tauri")]
     {
         let event_state = crate::EventState::get().await?;
@@ -225,11 +291,15 @@ pub async fn emit_warning(message: &str) -> crate::Result<()> {
                 "warning",
                 WarningPayload {
                     message: message.to_string(),
+                    code: code.map(String::from),
                 },
             )
             .map_err(EventError::from)?;
     }
-    tracing::warn!("{}", message);
+    match code {
+        Some(code) => tracing::warn!("{} ({})", message, code),
+        None => tracing::warn!("{}", message),
+    }
     Ok(())
 }
 
@@ -323,7 +393,162 @@ pub async fn emit_profile(
     Ok(())
 }
 
-// loading_join! macro
+// emit_profile_loaded(uuid, profile_path_id, path, name)
+// Profiles are sent as they are read at startup, before the state exists to
+// resolve their full path
+#[allow(unused_variables)]
+pub async fn emit_profile_loaded(
+    uuid: Uuid,
+    profile_path_id: &ProfilePathId,
+    path: &std::path::Path,
+    name: &str,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all(
+                "profile",
+                ProfilePayload {
+                    uuid,
+                    profile_path_id: profile_path_id.clone(),
+                    path: path.to_path_buf(),
+                    name: name.to_string(),
+                    event: ProfilePayloadType::Loaded,
+                },
+            )
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_screenshot(path, event)
+#[allow(unused_variables)]
+pub async fn emit_screenshot(
+    profile_path_id: &ProfilePathId,
+    path: &std::path::Path,
+    event: ScreenshotPayloadType,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all(
+                "screenshot",
+                ScreenshotPayload {
+                    profile_path_id: profile_path_id.clone(),
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    path: path.to_path_buf(),
+                    event,
+                },
+            )
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_sync(profile_path_id, event)
+#[allow(unused_variables)]
+pub async fn emit_sync(
+    profile_path_id: &ProfilePathId,
+    event: SyncPayloadType,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all(
+                "sync",
+                SyncPayload {
+                    profile_path_id: profile_path_id.clone(),
+                    event,
+                },
+            )
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_account(id, username, previous_username, event)
+#[allow(unused_variables)]
+pub async fn emit_account(
+    id: Uuid,
+    username: &str,
+    previous_username: &str,
+    event: AccountPayloadType,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all(
+                "account",
+                AccountPayload {
+                    id,
+                    username: username.to_string(),
+                    previous_username: previous_username.to_string(),
+                    event,
+                },
+            )
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_updates_available(payload)
+// New versions found by the background update checks
+#[allow(unused_variables)]
+pub async fn emit_updates_available(
+    payload: &UpdatesAvailablePayload,
+) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all("updates_available", payload)
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_ready(component)
+// A part of the state loaded in the background after startup is ready
+#[allow(unused_variables)]
+pub async fn emit_ready(component: StateComponent) -> crate::Result<()> {
+    #[cfg(feature = "tauri")]
+    {
+        let event_state = crate::EventState::get().await?;
+        event_state
+            .app
+            .emit_all("ready", component)
+            .map_err(EventError::from)?;
+    }
+    Ok(())
+}
+
+// emit_status(status)
+// Consolidated launcher status, for tray icons and menu bars
+#[cfg(feature = "tauri")]
+pub async fn emit_status(
+    status: &crate::status::LauncherStatus,
+) -> crate::Result<()> {
+    let event_state = crate::EventState::get().await?;
+    event_state
+        .app
+        .emit_all("status", status)
+        .map_err(EventError::from)?;
+    Ok(())
+}
+
 // loading_join!(key: Option<&LoadingBarId>, total: f64, message: Option<&str>; task1, task2, task3...)
 // This will submit a loading event with the given message for each task as they complete
 // task1, task2, task3 are async tasks that yuo want to to join on await on
@@ -5,7 +5,7 @@ use tokio::sync::OnceCell;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use crate::prelude::ProfilePathId;
+use crate::prelude::{ProfilePathId, ProjectPathId};
 use crate::state::SafeProcesses;
 
 pub mod emit;
@@ -77,6 +77,47 @@ impl EventState {
         let value = Self::get().await?;
         Ok(value.app.get_window("main"))
     }
+
+    /// Applies a post-launch window action to the main window. Restoring a
+    /// closed window opens it again from the app config
+    #[cfg(feature = "tauri")]
+    pub async fn apply_window_action(
+        action: crate::state::WindowAction,
+    ) -> crate::Result<()> {
+        use crate::state::WindowAction;
+        use tauri::Manager;
+
+        let value = Self::get().await?;
+        let window = value.app.get_window("main");
+        match (action, window) {
+            (WindowAction::None, _) => {}
+            (WindowAction::Minimize, Some(window)) => window.minimize()?,
+            (WindowAction::Hide, Some(window)) => window.hide()?,
+            (WindowAction::Close, Some(window)) => window.close()?,
+            (WindowAction::Restore, Some(window)) => {
+                window.show()?;
+                window.unminimize()?;
+                window.set_focus()?;
+            }
+            (WindowAction::Restore, None) => {
+                let config = value
+                    .app
+                    .config()
+                    .tauri
+                    .windows
+                    .iter()
+                    .find(|x| x.label == "main")
+                    .cloned();
+                if let Some(config) = config {
+                    tauri::WindowBuilder::from_config(&value.app, config)
+                        .build()?;
+                }
+            }
+            (_, None) => {}
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -89,11 +130,42 @@ pub struct LoadingBar {
     #[serde(skip)]
     pub last_sent: f64,
     pub bar_type: LoadingBarType,
+    /// Set once the bar starts tracking the bytes it downloads
+    pub transfer: Option<TransferProgress>,
+    #[serde(skip)]
+    pub transfer_started: Option<std::time::Instant>,
     #[cfg(feature = "cli")]
     #[serde(skip)]
     pub cli_progress_bar: indicatif::ProgressBar,
 }
 
+/// Byte counts of a loading bar that downloads files
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TransferProgress {
+    /// Bytes of every file the bar has to download, which can grow while
+    /// more files are found to be missing
+    pub total_bytes: u64,
+    pub downloaded_bytes: u64,
+    /// Last file that finished downloading
+    pub current_file: Option<String>,
+    /// Average download speed of the bar, in bytes per second
+    pub rate: u64,
+    /// Estimated seconds left, once a rate is known
+    pub eta: Option<u64>,
+}
+
+impl TransferProgress {
+    fn update_rate(&mut self, elapsed: std::time::Duration) {
+        let elapsed = elapsed.as_secs_f64();
+        if elapsed > 0.0 {
+            self.rate = (self.downloaded_bytes as f64 / elapsed) as u64;
+        }
+        self.eta = (self.rate > 0).then(|| {
+            self.total_bytes.saturating_sub(self.downloaded_bytes) / self.rate
+        });
+    }
+}
+
 #[derive(Serialize, Debug, Clone)]
 pub struct LoadingBarId(Uuid);
 
@@ -121,6 +193,8 @@ impl Drop for LoadingBarId {
                                 message: "Completed".to_string(),
                                 event,
                                 loader_uuid,
+                                throughput: 0,
+                                transfer: bar.transfer.clone(),
                             },
                         );
                         tracing::trace!(
@@ -193,6 +267,8 @@ pub struct LoadingPayload {
     pub loader_uuid: Uuid,
     pub fraction: Option<f64>, // by convention, if optional, it means the loading is done
     pub message: String,
+    pub throughput: u64, // combined download speed of all fetches, in bytes per second
+    pub transfer: Option<TransferProgress>, // only for bars that track their downloaded bytes
 }
 
 #[derive(Serialize, Clone)]
@@ -203,6 +279,8 @@ pub struct OfflinePayload {
 #[derive(Serialize, Clone)]
 pub struct WarningPayload {
     pub message: String,
+    /// Short code of a known problem, to link to help for it
+    pub code: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
@@ -251,11 +329,93 @@ pub struct ProfilePayload {
 pub enum ProfilePayloadType {
     Created,
     Added, // also triggered when Created
+    /// Read from disk while the launcher starts, before the state is ready
+    Loaded,
     Synced,
     Edited,
     Removed,
 }
 
+#[derive(Serialize, Clone)]
+pub struct ScreenshotPayload {
+    pub profile_path_id: ProfilePathId,
+    pub name: String,
+    pub path: PathBuf,
+    pub event: ScreenshotPayloadType,
+}
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotPayloadType {
+    Added,
+    Removed,
+}
+
+/// Progress of syncing a profile with the sync folder
+#[derive(Serialize, Clone)]
+pub struct SyncPayload {
+    pub profile_path_id: ProfilePathId,
+    pub event: SyncPayloadType,
+}
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncPayloadType {
+    Started,
+    /// Files copied or deleted so far, out of those that changed
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    /// A file changed on both sides since the last sync, and was left as is
+    Conflict {
+        path: String,
+    },
+    Finished {
+        uploaded: usize,
+        downloaded: usize,
+        conflicts: usize,
+    },
+}
+
+#[derive(Serialize, Clone)]
+pub struct AccountPayload {
+    pub id: Uuid,
+    pub username: String,
+    pub previous_username: String,
+    pub event: AccountPayloadType,
+}
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountPayloadType {
+    Updated,
+    /// The tokens of the account could not be refreshed
+    NeedsReauth,
+    /// Microsoft wants the account to grant permissions again before its
+    /// tokens can be refreshed
+    NeedsConsent,
+}
+
+/// Managed packs and Modrinth projects with newer compatible versions,
+/// gathered across every profile by the background update checks
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct UpdatesAvailablePayload {
+    pub profiles: Vec<ProfileUpdates>,
+}
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProfileUpdates {
+    pub profile_path_id: ProfilePathId,
+    pub name: String,
+    /// Version ID of a newer version of the modpack
+    pub pack_version: Option<String>,
+    pub projects: Vec<ProjectUpdate>,
+}
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProjectUpdate {
+    pub project_path: ProjectPathId,
+    pub title: String,
+    pub version_id: String,
+    pub version_number: String,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum EventError {
     #[error("Event state was not properly initialized")]
@@ -1,6 +1,6 @@
 //! Minecraft CLI argument logic
 // TODO: Rafactor this section
-use super::{auth::Credentials, parse_rule};
+use super::{auth::Credentials, parse_rule, FeatureContext};
 use crate::{
     state::{MemorySettings, WindowSize},
     util::{io::IOError, platform::classpath_separator},
@@ -11,13 +11,43 @@ use daedalus::{
     modded::SidedDataEntry,
 };
 use dunce::canonicalize;
+use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader};
 use std::{collections::HashMap, path::Path};
-use uuid::Uuid;
 
 // Replaces the space separator with a newline character, as to not split the arguments
 const TEMPORARY_REPLACE_CHAR: &str = "\n";
 
+// JVM flags taking their value as the next argument, such as `-cp <path>`
+const SEPARATE_VALUE_FLAGS: [&str; 12] = [
+    "-cp",
+    "-classpath",
+    "--class-path",
+    "-p",
+    "--module-path",
+    "--upgrade-module-path",
+    "--limit-modules",
+    "--add-modules",
+    "--add-opens",
+    "--add-exports",
+    "--add-reads",
+    "--patch-module",
+];
+// JVM flags that add to what they were given before instead of replacing it
+const REPEATABLE_FLAGS: [&str; 9] = [
+    "-javaagent:",
+    "-agentlib:",
+    "-agentpath:",
+    "-Xlog:",
+    "--add-modules",
+    "--add-opens",
+    "--add-exports",
+    "--add-reads",
+    "--patch-module",
+];
+// JVM flags with a size appended to them, such as `-Xmx4G`
+const SIZE_FLAGS: [&str; 4] = ["-Xmx", "-Xms", "-Xss", "-Xmn"];
+
 pub fn get_class_paths(
     libraries_path: &Path,
     libraries: &[Library],
@@ -28,7 +58,9 @@ pub fn get_class_paths(
         .iter()
         .filter_map(|library| {
             if let Some(rules) = &library.rules {
-                if !rules.iter().any(|x| parse_rule(x, java_arch)) {
+                if !rules.iter().any(|x| {
+                    parse_rule(x, java_arch, &FeatureContext::default())
+                }) {
                     return None;
                 }
             }
@@ -94,6 +126,41 @@ pub fn get_lib_path(
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Variables that user-provided JVM arguments and environment variables can
+/// refer to as `${NAME}`, so launch settings don't hardcode paths
+#[derive(Debug, Default, Clone)]
+pub struct TemplateVariables(HashMap<&'static str, String>);
+
+impl TemplateVariables {
+    pub fn insert(&mut self, name: &'static str, value: impl Into<String>) {
+        self.0.insert(name, value.into());
+    }
+
+    pub fn insert_path(&mut self, name: &'static str, path: &Path) {
+        self.insert(name, path.to_string_lossy());
+    }
+
+    /// Replaces every known variable, leaving unknown ones untouched
+    pub fn expand(&self, value: &str) -> String {
+        let mut value = value.to_string();
+        for (name, replacement) in &self.0 {
+            value = value.replace(&format!("${{{name}}}"), replacement);
+        }
+        value
+    }
+}
+
+/// Expands the template variables in custom environment variables
+pub fn get_env_arguments(
+    env_args: &[(String, String)],
+    template: &TemplateVariables,
+) -> Vec<(String, String)> {
+    env_args
+        .iter()
+        .map(|(key, value)| (key.clone(), template.expand(value)))
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn get_jvm_arguments(
     arguments: Option<&[Argument]>,
@@ -103,7 +170,10 @@ pub fn get_jvm_arguments(
     version_name: &str,
     memory: MemorySettings,
     custom_args: Vec<String>,
+    template: &TemplateVariables,
+    authlib_injector: Option<(&Path, &str)>,
     java_arch: &str,
+    features: &FeatureContext,
 ) -> crate::Result<Vec<String>> {
     let mut parsed_arguments = Vec::new();
 
@@ -122,6 +192,7 @@ pub fn get_jvm_arguments(
                 )
             },
             java_arch,
+            features,
         )?;
     } else {
         parsed_arguments.push(format!(
@@ -137,10 +208,19 @@ pub fn get_jvm_arguments(
         parsed_arguments.push("-cp".to_string());
         parsed_arguments.push(class_paths.to_string());
     }
+    if let Some((agent_path, server)) = authlib_injector {
+        parsed_arguments.push(format!(
+            "-javaagent:{}={server}",
+            agent_path.to_string_lossy()
+        ));
+    }
     parsed_arguments.push(format!("-Xmx{}M", memory.maximum));
+    if let Some(minimum) = memory.minimum {
+        parsed_arguments.push(format!("-Xms{}M", minimum.min(memory.maximum)));
+    }
     for arg in custom_args {
         if !arg.is_empty() {
-            parsed_arguments.push(arg);
+            parsed_arguments.push(template.expand(&arg));
         }
     }
     parsed_arguments.push("-Dorg.lwjgl.util.Debug=true".to_string());
@@ -189,6 +269,117 @@ fn parse_jvm_argument(
         .replace("${classpath}", class_paths))
 }
 
+/// JVM arguments of versions before 1.13, which don't list any. Applet era
+/// versions save to `.minecraft` in the home directory unless given a
+/// target directory, and sort with comparators newer Java rejects
+pub fn get_legacy_jvm_arguments(game_directory: &Path) -> Vec<String> {
+    vec![
+        format!(
+            "-Dminecraft.applet.TargetDirectory={}",
+            game_directory.to_string_lossy()
+        ),
+        "-Djava.util.Arrays.useLegacyMergeSort=true".to_string(),
+    ]
+}
+
+/// Where a JVM argument of a launch comes from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JavaArgSource {
+    /// Recommended by the modpack the profile was installed from
+    Pack,
+    /// Set in the profile, or in the settings if the profile sets none
+    User,
+}
+
+/// A JVM flag given to the game, with its value if passed as the next
+/// argument
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JavaArg {
+    pub flag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub source: JavaArgSource,
+}
+
+impl JavaArg {
+    /// The arguments the flag is passed to Java as
+    pub fn args(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.flag).chain(self.value.iter())
+    }
+
+    // What the flag sets, which two flags setting the same thing share:
+    // `-Dname` for `-Dname=value`, `-XX:Name` for `-XX:+Name`, `-XX:-Name`
+    // and `-XX:Name=value`, `-Xmx` for `-Xmx4G`, and `-Xname` for
+    // `-Xname:value`. Repeatable flags are only the same if given the same
+    // value
+    fn key(&self) -> String {
+        let flag = self.flag.as_str();
+        if REPEATABLE_FLAGS.iter().any(|x| flag.starts_with(x)) {
+            return match &self.value {
+                Some(value) => format!("{flag} {value}"),
+                None => flag.to_string(),
+            };
+        }
+
+        if let Some(option) = flag.strip_prefix("-XX:") {
+            let option = option.trim_start_matches(['+', '-']);
+            let name = option.split_once('=').map_or(option, |(x, _)| x);
+            format!("-XX:{name}")
+        } else if flag.starts_with("-D") || flag.starts_with("--") {
+            flag.split_once('=').map_or(flag, |(x, _)| x).to_string()
+        } else if let Some(size) =
+            SIZE_FLAGS.iter().find(|x| flag.starts_with(*x))
+        {
+            size.to_string()
+        } else if flag.starts_with("-X") {
+            flag.split_once(':').map_or(flag, |(x, _)| x).to_string()
+        } else {
+            flag.to_string()
+        }
+    }
+}
+
+/// Merges the JVM arguments a pack recommends with the ones the user set,
+/// flag by flag:
+///
+/// 1. Arguments are read into flags, joining flags such as `-cp` with the
+///    value that follows them, and skipping empty arguments
+/// 2. Pack flags come first, then user flags, each in the order given
+/// 3. A flag setting the same thing as one before it, such as `-Xss2M` after
+///    `-Xss1M` or `-XX:-UseG1GC` after `-XX:+UseG1GC`, replaces it and
+///    moves to its own place. User flags so override pack flags, and later
+///    flags override earlier ones of the same source, as Java would
+/// 4. Flags that add up rather than replace, such as `-javaagent:` and
+///    `--add-opens`, are all kept, unless given twice with the same value
+///
+/// The result only depends on the two lists given
+pub fn merge_java_args(pack: &[String], user: &[String]) -> Vec<JavaArg> {
+    let mut merged: Vec<JavaArg> = Vec::new();
+    for (args, source) in
+        [(pack, JavaArgSource::Pack), (user, JavaArgSource::User)]
+    {
+        let mut args = args.iter().filter(|x| !x.is_empty());
+        while let Some(flag) = args.next() {
+            let value = if SEPARATE_VALUE_FLAGS.contains(&flag.as_str()) {
+                args.next().cloned()
+            } else {
+                None
+            };
+            let arg = JavaArg {
+                flag: flag.clone(),
+                value,
+                source,
+            };
+
+            let key = arg.key();
+            merged.retain(|x| x.key() != key);
+            merged.push(arg);
+        }
+    }
+    merged
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn get_minecraft_arguments(
     arguments: Option<&[Argument]>,
@@ -198,9 +389,55 @@ pub fn get_minecraft_arguments(
     asset_index_name: &str,
     game_directory: &Path,
     assets_directory: &Path,
+    game_assets_directory: &Path,
     version_type: &VersionType,
     resolution: WindowSize,
     java_arch: &str,
+    features: &FeatureContext,
+    hide_identifiers: bool,
+) -> crate::Result<Vec<String>> {
+    let arguments = parse_minecraft_arguments(
+        arguments,
+        legacy_arguments,
+        credentials,
+        version,
+        asset_index_name,
+        game_directory,
+        assets_directory,
+        game_assets_directory,
+        version_type,
+        resolution,
+        java_arch,
+        features,
+    )?;
+
+    // Without them the game still launches, only leaving its telemetry and
+    // some multiplayer services unlinked from the Xbox account
+    if hide_identifiers {
+        let identifiers = IDENTIFIER_ARGS.map(String::from);
+        Ok(remove_game_arguments(arguments, &identifiers))
+    } else {
+        Ok(arguments)
+    }
+}
+
+// Game arguments identifying the account and launcher to Mojang's services
+const IDENTIFIER_ARGS: [&str; 2] = ["--xuid", "--clientId"];
+
+#[allow(clippy::too_many_arguments)]
+fn parse_minecraft_arguments(
+    arguments: Option<&[Argument]>,
+    legacy_arguments: Option<&str>,
+    credentials: &Credentials,
+    version: &str,
+    asset_index_name: &str,
+    game_directory: &Path,
+    assets_directory: &Path,
+    game_assets_directory: &Path,
+    version_type: &VersionType,
+    resolution: WindowSize,
+    java_arch: &str,
+    features: &FeatureContext,
 ) -> crate::Result<Vec<String>> {
     if let Some(arguments) = arguments {
         let mut parsed_arguments = Vec::new();
@@ -211,18 +448,18 @@ pub fn get_minecraft_arguments(
             |arg| {
                 parse_minecraft_argument(
                     arg,
-                    &credentials.access_token,
-                    &credentials.username,
-                    &credentials.id,
+                    credentials,
                     version,
                     asset_index_name,
                     game_directory,
                     assets_directory,
+                    game_assets_directory,
                     version_type,
                     resolution,
                 )
             },
             java_arch,
+            features,
         )?;
 
         Ok(parsed_arguments)
@@ -231,13 +468,12 @@ pub fn get_minecraft_arguments(
         for x in legacy_arguments.split(' ') {
             parsed_arguments.push(parse_minecraft_argument(
                 &x.replace(' ', TEMPORARY_REPLACE_CHAR),
-                &credentials.access_token,
-                &credentials.username,
-                &credentials.id,
+                credentials,
                 version,
                 asset_index_name,
                 game_directory,
                 assets_directory,
+                game_assets_directory,
                 version_type,
                 resolution,
             )?);
@@ -248,29 +484,54 @@ pub fn get_minecraft_arguments(
     }
 }
 
+/// Leaves flags out of game arguments, along with the values following them
+pub fn remove_game_arguments(
+    arguments: Vec<String>,
+    removed: &[String],
+) -> Vec<String> {
+    let mut kept = Vec::with_capacity(arguments.len());
+    let mut arguments = arguments.into_iter().peekable();
+    while let Some(arg) = arguments.next() {
+        if !removed.contains(&arg) {
+            kept.push(arg);
+            continue;
+        }
+        // Flags without a value, such as `--demo`, are followed by a flag
+        if arguments.peek().map_or(false, |x| !x.starts_with("--")) {
+            arguments.next();
+        }
+    }
+    kept
+}
+
 #[allow(clippy::too_many_arguments)]
 fn parse_minecraft_argument(
     argument: &str,
-    access_token: &str,
-    username: &str,
-    uuid: &Uuid,
+    credentials: &Credentials,
     version: &str,
     asset_index_name: &str,
     game_directory: &Path,
     assets_directory: &Path,
+    game_assets_directory: &Path,
     version_type: &VersionType,
     resolution: WindowSize,
 ) -> crate::Result<String> {
+    let access_token = &credentials.access_token;
+    let uuid = &credentials.id;
     Ok(argument
         .replace("${accessToken}", access_token)
         .replace("${auth_access_token}", access_token)
-        .replace("${auth_session}", access_token)
-        .replace("${auth_player_name}", username)
-        // TODO: add auth xuid eventually
-        .replace("${auth_xuid}", "0")
+        // Older versions take the token and the UUID together as a session
+        .replace(
+            "${auth_session}",
+            &format!("token:{access_token}:{}", uuid.simple()),
+        )
+        .replace("${auth_player_name}", &credentials.username)
+        // Accounts of third-party servers have no Xbox user id
+        .replace("${auth_xuid}", credentials.xuid.as_deref().unwrap_or("0"))
         .replace("${auth_uuid}", &uuid.hyphenated().to_string())
         .replace("${uuid}", &uuid.hyphenated().to_string())
-        .replace("${clientid}", "c4502edb-87c6-40cb-b595-64a280cf8906")
+        .replace("${clientid}", &credentials.client_id)
         .replace("${user_properties}", "{}")
         .replace("${user_type}", "msa")
         .replace("${version_name}", version)
@@ -301,11 +562,11 @@ fn parse_minecraft_argument(
         )
         .replace(
             "${game_assets}",
-            &canonicalize(assets_directory)
+            &canonicalize(game_assets_directory)
                 .map_err(|_| {
                     crate::ErrorKind::LauncherError(format!(
                         "Specified assets directory {} does not exist",
-                        assets_directory.to_string_lossy()
+                        game_assets_directory.to_string_lossy()
                     ))
                     .as_error()
                 })?
@@ -321,6 +582,7 @@ fn parse_arguments<F>(
     parsed_arguments: &mut Vec<String>,
     parse_function: F,
     java_arch: &str,
+    features: &FeatureContext,
 ) -> crate::Result<()>
 where
     F: Fn(&str) -> crate::Result<String>,
@@ -335,7 +597,7 @@ where
                 }
             }
             Argument::Ruled { rules, value } => {
-                if rules.iter().any(|x| parse_rule(x, java_arch)) {
+                if rules.iter().any(|x| parse_rule(x, java_arch, features)) {
                     match value {
                         ArgumentValue::Single(arg) => {
                             parsed_arguments.push(parse_function(
@@ -430,3 +692,141 @@ pub async fn get_processor_main_class(
 
     Ok(main_class)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_java_args, JavaArg, JavaArgSource};
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|x| x.to_string()).collect()
+    }
+
+    // The arguments Java is given for merged flags
+    fn flatten(merged: &[JavaArg]) -> Vec<String> {
+        merged.iter().flat_map(|x| x.args()).cloned().collect()
+    }
+
+    fn merge(pack: &[&str], user: &[&str]) -> Vec<String> {
+        flatten(&merge_java_args(&strings(pack), &strings(user)))
+    }
+
+    #[test]
+    fn xx_flags_override_each_other() {
+        assert_eq!(
+            merge(&["-XX:+UseG1GC"], &["-XX:-UseG1GC"]),
+            ["-XX:-UseG1GC"]
+        );
+        assert_eq!(
+            merge(&["-XX:-UseG1GC"], &["-XX:+UseG1GC"]),
+            ["-XX:+UseG1GC"]
+        );
+        assert_eq!(
+            merge(
+                &["-XX:MaxGCPauseMillis=50", "-XX:+UseG1GC"],
+                &["-XX:MaxGCPauseMillis=200"]
+            ),
+            ["-XX:+UseG1GC", "-XX:MaxGCPauseMillis=200"]
+        );
+    }
+
+    #[test]
+    fn size_flags_override_each_other() {
+        assert_eq!(merge(&["-Xss1M"], &["-Xss2M"]), ["-Xss2M"]);
+        assert_eq!(
+            merge(&["-Xmx2G", "-Xms1G"], &["-Xmx4G"]),
+            ["-Xms1G", "-Xmx4G"]
+        );
+        // Later flags of the same source win, as they would in Java
+        assert_eq!(merge(&[], &["-Xss1M", "-Xss2M"]), ["-Xss2M"]);
+    }
+
+    #[test]
+    fn separate_values_are_paired() {
+        let merged = merge_java_args(
+            &strings(&["-cp", "pack.jar", "-Xss1M"]),
+            &strings(&["-cp", "user.jar"]),
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].flag, "-Xss1M");
+        assert_eq!(merged[1].flag, "-cp");
+        assert_eq!(merged[1].value.as_deref(), Some("user.jar"));
+        assert_eq!(flatten(&merged), ["-Xss1M", "-cp", "user.jar"]);
+    }
+
+    #[test]
+    fn repeatable_flags_are_kept() {
+        assert_eq!(
+            merge(
+                &[
+                    "--add-opens",
+                    "java.base/java.lang=ALL-UNNAMED",
+                    "-javaagent:a.jar"
+                ],
+                &[
+                    "--add-opens",
+                    "java.base/java.util=ALL-UNNAMED",
+                    "-javaagent:b.jar"
+                ],
+            ),
+            [
+                "--add-opens",
+                "java.base/java.lang=ALL-UNNAMED",
+                "-javaagent:a.jar",
+                "--add-opens",
+                "java.base/java.util=ALL-UNNAMED",
+                "-javaagent:b.jar",
+            ]
+        );
+    }
+
+    #[test]
+    fn exact_duplicates_are_removed() {
+        assert_eq!(
+            merge(
+                &[
+                    "-javaagent:a.jar",
+                    "--add-opens",
+                    "java.base/java.lang=ALL-UNNAMED"
+                ],
+                &[
+                    "-javaagent:a.jar",
+                    "--add-opens",
+                    "java.base/java.lang=ALL-UNNAMED"
+                ],
+            ),
+            [
+                "-javaagent:a.jar",
+                "--add-opens",
+                "java.base/java.lang=ALL-UNNAMED"
+            ]
+        );
+        assert_eq!(merge(&["-Dfoo=1", "-Dfoo=1"], &[]), ["-Dfoo=1"]);
+    }
+
+    #[test]
+    fn empty_arguments_are_skipped() {
+        assert_eq!(merge(&["", "-Xss1M", ""], &[""]), ["-Xss1M"]);
+        // An empty argument isn't taken as the value of a flag either
+        assert_eq!(merge(&["-cp", "", "a.jar"], &[]), ["-cp", "a.jar"]);
+    }
+
+    #[test]
+    fn user_flags_override_pack_flags() {
+        let merged = merge_java_args(
+            &strings(&["-Xmx2G", "-Dfml.readTimeout=90", "-XX:+UseG1GC"]),
+            &strings(&["-Xmx6G", "-Dfml.readTimeout=180"]),
+        );
+        let flags = merged
+            .iter()
+            .map(|x| (x.flag.as_str(), x.source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            [
+                ("-XX:+UseG1GC", JavaArgSource::Pack),
+                ("-Xmx6G", JavaArgSource::User),
+                ("-Dfml.readTimeout=180", JavaArgSource::User),
+            ]
+        );
+    }
+}
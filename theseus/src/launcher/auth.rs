@@ -1,12 +1,14 @@
 //! Authentication flow based on Hydra
 use crate::config::MODRINTH_API_URL;
 use crate::state::CredentialsStore;
-use crate::util::fetch::{fetch_advanced, fetch_json, FetchSemaphore};
+use crate::util::fetch::{
+    client, connect_websocket, fetch_advanced, FetchSemaphore,
+};
 use async_tungstenite as ws;
 use chrono::{prelude::*, Duration};
 use futures::prelude::*;
 use lazy_static::lazy_static;
-use reqwest::Method;
+use reqwest::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -32,6 +34,23 @@ impl ErrorJSON {
     }
 }
 
+// Errors Microsoft gives once the launcher asks for permissions an account
+// hasn't granted, such as after the scopes it needs changed. Signing in
+// through the browser again is enough to grant them
+const CONSENT_ERRORS: [&str; 6] = [
+    "consent_required",
+    "interaction_required",
+    "invalid_scope",
+    "aadsts65001",
+    "aadsts65004",
+    "aadsts70000",
+];
+
+fn is_consent_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    CONSENT_ERRORS.iter().any(|x| error.contains(x))
+}
+
 #[derive(Deserialize)]
 struct LoginCodeJSON {
     login_code: String,
@@ -49,6 +68,53 @@ struct TokenJSON {
 struct ProfileInfoJSON {
     id: uuid::Uuid,
     name: String,
+    #[serde(default)]
+    skins: Vec<MinecraftSkin>,
+}
+
+// Yggdrasil messages
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YggdrasilErrorJSON {
+    error: String,
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YggdrasilTokenJSON {
+    access_token: String,
+    client_token: String,
+    selected_profile: Option<ProfileInfoJSON>,
+}
+
+impl YggdrasilErrorJSON {
+    pub fn unwrap<'a, T: Deserialize<'a>>(data: &'a [u8]) -> crate::Result<T> {
+        if let Ok(err) = serde_json::from_slice::<Self>(data) {
+            Err(crate::ErrorKind::YggdrasilError(
+                err.error_message.unwrap_or(err.error),
+            )
+            .as_error())
+        } else {
+            Ok(serde_json::from_slice::<T>(data)?)
+        }
+    }
+}
+
+/// How long a Yggdrasil access token is used before it is refreshed
+const YGGDRASIL_TOKEN_LIFETIME: i64 = 60 * 60;
+
+/// How long the cached Minecraft profile of an account is trusted for
+pub const PROFILE_REFRESH_INTERVAL: i64 = 60 * 60;
+
+/// A skin of a Minecraft account
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MinecraftSkin {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    #[serde(default)]
+    pub variant: Option<String>,
 }
 
 // Login information
@@ -59,20 +125,72 @@ pub struct Credentials {
     pub access_token: String,
     pub refresh_token: String,
     pub expires: DateTime<Utc>,
+    #[serde(default)]
+    pub skins: Vec<MinecraftSkin>,
+    /// When the name and skins were last fetched from Minecraft
+    #[serde(default)]
+    pub profile_refreshed: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub backend: AuthBackend,
+    /// Set once the tokens could not be refreshed, until the account is
+    /// logged in again
+    #[serde(default)]
+    pub needs_reauth: bool,
+    /// Set along with `needs_reauth` when Microsoft wants the account to
+    /// grant permissions again. Signing in again keeps the account, see
+    /// `auth::reconsent_begin_flow`
+    #[serde(default)]
+    pub needs_consent: bool,
+    /// Xbox user id of a Microsoft account, read from its access token. The
+    /// game sends it to Mojang's telemetry and multiplayer services
+    #[serde(default)]
+    pub xuid: Option<String>,
+    /// Identifies the launcher to the game's services, generated once for
+    /// each account. Accounts stored without one get it when loaded
+    #[serde(default)]
+    pub client_id: String,
     _ctor_scope: std::marker::PhantomData<()>,
 }
 
+/// Where an account logs in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthBackend {
+    Microsoft,
+    /// A third-party Yggdrasil-compatible server. The game is pointed at it
+    /// through authlib-injector
+    Yggdrasil {
+        server: String,
+        client_token: String,
+    },
+}
+
+impl Default for AuthBackend {
+    fn default() -> Self {
+        Self::Microsoft
+    }
+}
+
+impl AuthBackend {
+    /// The server authlib-injector has to redirect the game to, if any
+    pub fn authlib_injector_server(&self) -> Option<&str> {
+        match self {
+            Self::Microsoft => None,
+            Self::Yggdrasil { server, .. } => Some(server),
+        }
+    }
+}
+
 // Implementation
 pub struct HydraAuthFlow<S: AsyncRead + AsyncWrite + Unpin> {
     socket: ws::WebSocketStream<S>,
 }
 
-impl HydraAuthFlow<ws::tokio::ConnectStream> {
+impl HydraAuthFlow<ws::tokio::TokioAdapter<reqwest::Upgraded>> {
     pub async fn new() -> crate::Result<Self> {
-        let (socket, _) = ws::tokio::connect_async(
-            "wss://api.modrinth.com/v2/auth/minecraft/ws",
-        )
-        .await?;
+        let socket =
+            connect_websocket("wss://api.modrinth.com/v2/auth/minecraft/ws")
+                .await?;
         Ok(Self { socket })
     }
 
@@ -112,6 +230,7 @@ impl HydraAuthFlow<ws::tokio::ConnectStream> {
             )?
             .into_data();
         let token = ErrorJSON::unwrap::<TokenJSON>(&token_resp)?;
+        let xuid = token_xuid(&token.token);
         let expires =
             Utc::now() + Duration::seconds(token.expires_after.into());
 
@@ -126,6 +245,13 @@ impl HydraAuthFlow<ws::tokio::ConnectStream> {
                 refresh_token: token.refresh_token,
                 access_token: token.token,
                 expires,
+                skins: info.skins,
+                profile_refreshed: Some(Utc::now()),
+                backend: AuthBackend::Microsoft,
+                needs_reauth: false,
+                needs_consent: false,
+                xuid,
+                client_id: new_client_id(),
                 _ctor_scope: std::marker::PhantomData,
             },
             token.flow,
@@ -137,25 +263,237 @@ pub async fn refresh_credentials(
     credentials: &mut Credentials,
     semaphore: &FetchSemaphore,
 ) -> crate::Result<()> {
-    let resp = fetch_json::<TokenJSON>(
-        Method::POST,
+    if let AuthBackend::Yggdrasil { .. } = credentials.backend {
+        return yggdrasil_refresh(credentials, semaphore).await;
+    }
+
+    let resp = post_refresh(
         &format!("{MODRINTH_API_URL}auth/minecraft/refresh"),
-        None,
-        Some(serde_json::json!({ "refresh_token": credentials.refresh_token })),
+        serde_json::json!({ "refresh_token": credentials.refresh_token }),
         semaphore,
-        &CredentialsStore(None),
     )
     .await?;
+    let resp = match serde_json::from_slice::<ErrorJSON>(&resp) {
+        // Told apart so the account is kept, refreshing fails for everyone
+        // until they consent again
+        Ok(err) if is_consent_error(&err.error) => {
+            credentials.needs_consent = true;
+            return Err(crate::ErrorKind::ConsentRequired(err.error).into());
+        }
+        _ => ErrorJSON::unwrap::<TokenJSON>(&resp)?,
+    };
 
+    if let Some(xuid) = token_xuid(&resp.token) {
+        credentials.xuid = Some(xuid);
+    }
     credentials.access_token = resp.token;
     credentials.refresh_token = resp.refresh_token;
     credentials.expires =
         Utc::now() + Duration::seconds(resp.expires_after.into());
+    credentials.needs_reauth = false;
+    credentials.needs_consent = false;
+
+    Ok(())
+}
+
+impl Credentials {
+    /// Whether the cached name and skins are old enough to be fetched again
+    pub fn profile_outdated(&self) -> bool {
+        self.profile_refreshed.map_or(true, |refreshed| {
+            Utc::now() - refreshed > Duration::seconds(PROFILE_REFRESH_INTERVAL)
+        })
+    }
+
+    /// An account that never logged in, to launch the game with in
+    /// integration tests. The game starts with it, but can't join servers
+    #[cfg(feature = "integration_test")]
+    pub fn test_account(username: &str) -> Self {
+        Credentials {
+            id: uuid::Uuid::new_v4(),
+            username: username.to_string(),
+            access_token: "integration_test".to_string(),
+            refresh_token: String::new(),
+            expires: Utc::now() + Duration::days(1),
+            skins: Vec::new(),
+            profile_refreshed: Some(Utc::now()),
+            backend: AuthBackend::Microsoft,
+            needs_reauth: false,
+            needs_consent: false,
+            xuid: None,
+            client_id: new_client_id(),
+            _ctor_scope: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Fetches the current name and skins of an account, returning whether
+/// either of them changed
+pub async fn refresh_profile(
+    credentials: &mut Credentials,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<bool> {
+    // Third-party servers only report the name, which refreshing updates
+    if credentials.backend != AuthBackend::Microsoft {
+        return Ok(false);
+    }
+
+    let info = fetch_info(&credentials.access_token, semaphore).await?;
+    let changed =
+        info.name != credentials.username || info.skins != credentials.skins;
+
+    credentials.username = info.name;
+    credentials.skins = info.skins;
+    credentials.profile_refreshed = Some(Utc::now());
+
+    Ok(changed)
+}
+
+/// Logs in to a Yggdrasil-compatible server with a username and password
+pub async fn yggdrasil_authenticate(
+    server: &str,
+    username: &str,
+    password: &str,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<Credentials> {
+    let server = server.trim_end_matches('/').to_string();
+    let resp = fetch_advanced(
+        Method::POST,
+        &format!("{server}/authserver/authenticate"),
+        None,
+        Some(serde_json::json!({
+            "agent": { "name": "Minecraft", "version": 1 },
+            "username": username,
+            "password": password,
+            "requestUser": false,
+        })),
+        None,
+        None,
+        semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+    let token = YggdrasilErrorJSON::unwrap::<YggdrasilTokenJSON>(&resp)?;
+    let profile = token.selected_profile.ok_or_else(|| {
+        crate::ErrorKind::YggdrasilError(
+            "This account does not have a Minecraft profile".to_string(),
+        )
+    })?;
+
+    Ok(Credentials {
+        id: profile.id,
+        username: profile.name,
+        access_token: token.access_token,
+        refresh_token: String::new(),
+        expires: Utc::now() + Duration::seconds(YGGDRASIL_TOKEN_LIFETIME),
+        skins: Vec::new(),
+        profile_refreshed: Some(Utc::now()),
+        backend: AuthBackend::Yggdrasil {
+            server,
+            client_token: token.client_token,
+        },
+        needs_reauth: false,
+        needs_consent: false,
+        xuid: None,
+        client_id: new_client_id(),
+        _ctor_scope: std::marker::PhantomData,
+    })
+}
+
+async fn yggdrasil_refresh(
+    credentials: &mut Credentials,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<()> {
+    let AuthBackend::Yggdrasil {
+        server,
+        client_token,
+    } = &credentials.backend
+    else {
+        return Ok(());
+    };
+
+    let resp = post_refresh(
+        &format!("{server}/authserver/refresh"),
+        serde_json::json!({
+            "accessToken": credentials.access_token,
+            "clientToken": client_token,
+            "requestUser": false,
+        }),
+        semaphore,
+    )
+    .await?;
+    let token = YggdrasilErrorJSON::unwrap::<YggdrasilTokenJSON>(&resp)?;
+
+    credentials.access_token = token.access_token;
+    credentials.expires =
+        Utc::now() + Duration::seconds(YGGDRASIL_TOKEN_LIFETIME);
+    credentials.needs_reauth = false;
+    if let Some(profile) = token.selected_profile {
+        credentials.username = profile.name;
+    }
 
     Ok(())
 }
 
 // Helpers
+
+// Sends a refresh request once, as its token can only be used once. Failures
+// of the server are errors of their own, so only a rejected token reads as
+// an account that has to log in again
+async fn post_refresh(
+    url: &str,
+    body: serde_json::Value,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<bytes::Bytes> {
+    let io_semaphore = semaphore.0.read().await;
+    let _permit = io_semaphore.acquire().await?;
+
+    let resp = client().post(url).json(&body).send().await?;
+    let status = resp.status();
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(crate::ErrorKind::InvalidResponseError(
+            url.to_string(),
+            format!("server responded with {status}"),
+        )
+        .into());
+    }
+    Ok(resp.bytes().await?)
+}
+
+pub(crate) fn new_client_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// Minecraft access tokens are JWTs, whose claims include the Xbox user id
+fn token_xuid(token: &str) -> Option<String> {
+    let claims = decode_base64_url(token.split('.').nth(1)?)?;
+    let claims = serde_json::from_slice::<serde_json::Value>(&claims).ok()?;
+    claims.get("xuid")?.as_str().map(String::from)
+}
+
+// Decodes the URL-safe base64 JWTs are written in, padded or not
+fn decode_base64_url(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        buffer = ((buffer << 6) | u32::from(value)) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
 async fn fetch_info(
     token: &str,
     semaphore: &FetchSemaphore,
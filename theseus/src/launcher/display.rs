@@ -0,0 +1,103 @@
+//! Opening the game's window on the display server a profile asks for, so
+//! Wayland users don't need environment hacks for flickering or crashes
+use crate::event::emit::emit_coded_warning;
+use crate::state::DisplayServer;
+use crate::util::platform::{session_type, SessionType};
+
+/// Where distributions install the GLFW library
+#[cfg(target_os = "linux")]
+const GLFW_DIRS: [&str; 6] = [
+    "/usr/lib64",
+    "/usr/lib",
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+    "/usr/local/lib",
+    "/run/current-system/sw/lib",
+];
+
+/// Changes to the game's environment and arguments for a display server
+#[derive(Debug, Default)]
+pub struct DisplayOverrides {
+    pub env: Vec<(String, String)>,
+    pub env_remove: Vec<String>,
+    pub java_args: Vec<String>,
+}
+
+/// Makes the game open its window on the display server a profile prefers.
+/// Both options only apply to Wayland sessions, which run X11 programs
+/// through XWayland. Native Wayland needs a GLFW from the system, as the
+/// one Minecraft ships only speaks X11, so it is limited to LWJGL 3
+#[tracing::instrument]
+pub async fn apply_preference(
+    display_server: DisplayServer,
+    lwjgl3: bool,
+) -> crate::Result<DisplayOverrides> {
+    if display_server == DisplayServer::Default {
+        return Ok(DisplayOverrides::default());
+    }
+
+    let session = session_type();
+    if session != SessionType::Wayland {
+        tracing::debug!("Session is {session:?}, ignoring display server");
+        return Ok(DisplayOverrides::default());
+    }
+
+    match display_server {
+        DisplayServer::Default => Ok(DisplayOverrides::default()),
+        DisplayServer::Xwayland => {
+            if std::env::var_os("DISPLAY").is_none() {
+                emit_coded_warning(
+                    "XWayland isn't running, so the game can't be forced \
+                     to use it",
+                    Some("xwayland_unavailable"),
+                )
+                .await?;
+                return Ok(DisplayOverrides::default());
+            }
+
+            // GLFW only considers Wayland when it can connect to it
+            Ok(DisplayOverrides {
+                env: vec![("XDG_SESSION_TYPE".to_string(), "x11".to_string())],
+                env_remove: vec!["WAYLAND_DISPLAY".to_string()],
+                java_args: Vec::new(),
+            })
+        }
+        DisplayServer::Wayland => {
+            let glfw = if lwjgl3 { system_glfw() } else { None };
+            let Some(glfw) = glfw else {
+                let message = if lwjgl3 {
+                    "GLFW isn't installed on the system, so the game runs \
+                     through XWayland"
+                } else {
+                    "Versions before 1.13 can't run on Wayland, so the game \
+                     runs through XWayland"
+                };
+                emit_coded_warning(message, Some("wayland_unavailable"))
+                    .await?;
+                return Ok(DisplayOverrides::default());
+            };
+
+            Ok(DisplayOverrides {
+                java_args: vec![format!(
+                    "-Dorg.lwjgl.glfw.libname={}",
+                    glfw.display()
+                )],
+                ..Default::default()
+            })
+        }
+    }
+}
+
+// The GLFW library of the system, which distributions build with Wayland
+#[cfg(target_os = "linux")]
+fn system_glfw() -> Option<std::path::PathBuf> {
+    GLFW_DIRS
+        .iter()
+        .map(|x| std::path::Path::new(x).join("libglfw.so.3"))
+        .find(|x| x.exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn system_glfw() -> Option<std::path::PathBuf> {
+    None
+}
@@ -1,9 +1,13 @@
 //! Downloader for Minecraft data
 
-use crate::state::CredentialsStore;
+use super::FeatureContext;
+use crate::state::{CredentialsStore, GameDirectories};
 use crate::{
     event::{
-        emit::{emit_loading, loading_try_for_each_concurrent},
+        emit::{
+            add_loading_bytes, emit_coded_warning, emit_loading,
+            loading_try_for_each_concurrent, record_loading_bytes,
+        },
         LoadingBarId,
     },
     state::State,
@@ -18,19 +22,75 @@ use daedalus::{
     modded::LoaderVersion,
 };
 use futures::prelude::*;
-use tokio::sync::OnceCell;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+// Records the files each native library extracted into a natives directory
+const NATIVES_MANIFEST_FILE: &str = "natives.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NativesManifest {
+    // Identifies the native jars the files were extracted from, see
+    // natives_key
+    key: String,
+    libraries: HashMap<String, Vec<NativeFile>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NativeFile {
+    path: String,
+    size: u64,
+    // Unset in manifests from before natives were checked before launch
+    #[serde(default)]
+    sha1: Option<String>,
+}
+
+// Records the asset objects known to match their hash, so that they aren't
+// hashed again to tell whether they need downloading
+const OBJECTS_MANIFEST_FILE: &str = "objects.json";
+
+lazy_static! {
+    // Held while the objects manifest is read and written, as installs of
+    // different versions share it
+    static ref OBJECTS_MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct ObjectsManifest {
+    // Size of each object, by hash
+    objects: HashMap<String, u64>,
+}
+
+/// Id of the asset index without any objects, used by profiles launched
+/// without assets
+pub const EMPTY_ASSETS_INDEX: &str = "theseus-empty";
+
+/// Asset index of 1.6, whose assets are read by name from a virtual
+/// directory
+const VIRTUAL_ASSETS_INDEX: &str = "legacy";
+/// Asset index of versions before 1.6, whose assets are read by name from
+/// the `resources` folder of the game directory
+const RESOURCES_ASSETS_INDEX: &str = "pre-1.6";
+const RESOURCES_FOLDER_NAME: &str = "resources";
+
+const AUTHLIB_INJECTOR_META_URL: &str =
+    "https://authlib-injector.yushi.moe/artifact/latest.json";
+const AUTHLIB_INJECTOR_FILE: &str = "authlib-injector.jar";
 
 #[tracing::instrument(skip(st, version))]
 pub async fn download_minecraft(
     st: &State,
+    dirs: &GameDirectories,
     version: &GameVersionInfo,
     loading_bar: &LoadingBarId,
     java_arch: &str,
+    natives_version: &str,
+    skip_assets: bool,
 ) -> crate::Result<()> {
     tracing::info!("Downloading Minecraft version {}", version.id);
-    // 5
-    let assets_index =
-        download_assets_index(st, version, Some(loading_bar)).await?;
 
     let amount = if version
         .processors
@@ -43,11 +103,31 @@ pub async fn download_minecraft(
         40.0
     };
 
+    let assets = async {
+        if skip_assets {
+            tracing::info!("Skipping assets of Minecraft {}", version.id);
+            return write_empty_assets_index(st, dirs).await;
+        }
+
+        // 5
+        let assets_index =
+            download_assets_index(st, dirs, version, Some(loading_bar)).await?;
+        download_assets(
+            st,
+            dirs,
+            virtual_assets_dir(dirs, &version.asset_index.id).as_deref(),
+            &assets_index,
+            Some(loading_bar),
+            amount,
+        )
+        .await
+    };
+
     tokio::try_join! {
         // Total loading sums to 90/60
         download_client(st, version, Some(loading_bar)), // 10
-        download_assets(st, version.assets == "legacy", &assets_index, Some(loading_bar), amount), // 40
-        download_libraries(st, version.libraries.as_slice(), &version.id, Some(loading_bar), amount, java_arch) // 40
+        assets, // 45
+        download_libraries(st, dirs, version.libraries.as_slice(), natives_version, Some(loading_bar), amount, java_arch) // 40
     }?;
 
     tracing::info!("Done downloading Minecraft!");
@@ -79,10 +159,27 @@ pub async fn download_version_info(
             .and_then(|ref it| Ok(serde_json::from_slice(it)?))
     } else {
         tracing::info!("Downloading version info for version {}", &version.id);
-        let mut info = d::minecraft::fetch_version_info(version).await?;
+        let cache_dir = st.directories.http_cache_dir();
+        let bytes = fetch_cached(
+            &version.url,
+            Some(&version.sha1),
+            &cache_dir,
+            &st.fetch_semaphore,
+            &st.io_semaphore,
+        )
+        .await?;
+        let mut info: GameVersionInfo = serde_json::from_slice(&bytes)?;
 
         if let Some(loader) = loader {
-            let partial = d::modded::fetch_partial_version(&loader.url).await?;
+            let bytes = fetch_cached(
+                &loader.url,
+                None,
+                &cache_dir,
+                &st.fetch_semaphore,
+                &st.io_semaphore,
+            )
+            .await?;
+            let partial = serde_json::from_slice(&bytes)?;
             info = d::modded::merge_partial_version(partial, info);
         }
         info.id = version_id.clone();
@@ -124,14 +221,26 @@ pub async fn download_client(
         .join(format!("{version}.jar"));
 
     if !path.exists() {
-        let bytes = fetch(
-            &client_download.url,
+        if let Some(loading_bar) = loading_bar {
+            add_loading_bytes(loading_bar, client_download.size as u64).await?;
+        }
+        let bytes = fetch_mirrors(
+            &[&client_download.url],
             Some(&client_download.sha1),
+            Some(client_download.size as u64),
             &st.fetch_semaphore,
             &CredentialsStore(None),
         )
         .await?;
         write(&path, &bytes, &st.io_semaphore).await?;
+        if let Some(loading_bar) = loading_bar {
+            record_loading_bytes(
+                loading_bar,
+                bytes.len() as u64,
+                &format!("{version}.jar"),
+            )
+            .await?;
+        }
         tracing::trace!("Fetched client version {version}");
     }
     if let Some(loading_bar) = loading_bar {
@@ -146,14 +255,13 @@ pub async fn download_client(
 #[theseus_macros::debug_pin]
 pub async fn download_assets_index(
     st: &State,
+    dirs: &GameDirectories,
     version: &GameVersionInfo,
     loading_bar: Option<&LoadingBarId>,
 ) -> crate::Result<AssetsIndex> {
     tracing::debug!("Loading assets index");
-    let path = st
-        .directories
+    let path = dirs
         .assets_index_dir()
-        .await
         .join(format!("{}.json", &version.asset_index.id));
 
     let res = if path.exists() {
@@ -162,7 +270,15 @@ pub async fn download_assets_index(
             .await
             .and_then(|ref it| Ok(serde_json::from_slice(it)?))
     } else {
-        let index = d::minecraft::fetch_assets_index(version).await?;
+        let bytes = fetch_cached(
+            &version.asset_index.url,
+            Some(&version.asset_index.sha1),
+            &st.directories.http_cache_dir(),
+            &st.fetch_semaphore,
+            &st.io_semaphore,
+        )
+        .await?;
+        let index: AssetsIndex = serde_json::from_slice(&bytes)?;
         write(&path, &serde_json::to_vec(&index)?, &st.io_semaphore).await?;
         tracing::info!("Fetched assets index");
         Ok(index)
@@ -175,16 +291,180 @@ pub async fn download_assets_index(
     Ok(res)
 }
 
-#[tracing::instrument(skip(st, index))]
+/// Writes the asset index without any objects, so the game can start without
+/// its sounds and translations being downloaded
+#[tracing::instrument(skip_all)]
+pub async fn write_empty_assets_index(
+    st: &State,
+    dirs: &GameDirectories,
+) -> crate::Result<()> {
+    let path = dirs
+        .assets_index_dir()
+        .join(format!("{EMPTY_ASSETS_INDEX}.json"));
+    if !path.exists() {
+        write(&path, br#"{"objects":{}}"#, &st.io_semaphore).await?;
+    }
+
+    Ok(())
+}
+
+/// Downloads authlib-injector, which accounts of third-party authentication
+/// servers are launched with. A copy that was already downloaded is reused
+#[tracing::instrument(skip(st))]
+#[theseus_macros::debug_pin]
+pub async fn download_authlib_injector(st: &State) -> crate::Result<PathBuf> {
+    #[derive(serde::Deserialize)]
+    struct Artifact {
+        version: String,
+        download_url: String,
+        checksums: Checksums,
+    }
+    #[derive(serde::Deserialize)]
+    struct Checksums {
+        sha256: String,
+    }
+
+    let path = st.directories.caches_dir().join(AUTHLIB_INJECTOR_FILE);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let artifact = fetch_json::<Artifact>(
+        reqwest::Method::GET,
+        AUTHLIB_INJECTOR_META_URL,
+        None,
+        None,
+        &st.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+    let bytes = fetch(
+        &artifact.download_url,
+        None,
+        &st.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+
+    use sha2::Digest;
+    let hash = format!("{:x}", sha2::Sha256::digest(&bytes));
+    if hash != artifact.checksums.sha256 {
+        return Err(crate::ErrorKind::HashError(
+            artifact.checksums.sha256,
+            hash,
+        )
+        .into());
+    }
+
+    write(&path, &bytes, &st.io_semaphore).await?;
+    tracing::info!("Downloaded authlib-injector {}", artifact.version);
+
+    Ok(path)
+}
+
+/// Virtual directory the assets of an index are laid out in by name, for
+/// versions that don't read the objects directory
+pub fn virtual_assets_dir(
+    dirs: &GameDirectories,
+    asset_index_id: &str,
+) -> Option<PathBuf> {
+    match asset_index_id {
+        VIRTUAL_ASSETS_INDEX | RESOURCES_ASSETS_INDEX => {
+            Some(dirs.virtual_assets_dir(asset_index_id))
+        }
+        _ => None,
+    }
+}
+
+/// Directory the game is told its assets are in, which is the virtual one
+/// for legacy versions
+pub fn game_assets_dir(
+    dirs: &GameDirectories,
+    asset_index_id: &str,
+) -> PathBuf {
+    virtual_assets_dir(dirs, asset_index_id)
+        .unwrap_or_else(|| dirs.assets_dir.clone())
+}
+
+/// Lays out the assets of legacy versions the way they read them, by name
+/// in their virtual directory, and for versions before 1.6 also in the
+/// `resources` folder of the game directory. Only missing files are linked,
+/// so profiles installed before the layout existed are caught up at launch
+#[tracing::instrument(skip_all)]
+pub async fn link_legacy_assets(
+    st: &State,
+    dirs: &GameDirectories,
+    version: &GameVersionInfo,
+    game_dir: &Path,
+) -> crate::Result<()> {
+    let Some(virtual_dir) = virtual_assets_dir(dirs, &version.asset_index.id)
+    else {
+        return Ok(());
+    };
+
+    let mut targets = vec![virtual_dir];
+    if version.asset_index.id == RESOURCES_ASSETS_INDEX {
+        targets.push(game_dir.join(RESOURCES_FOLDER_NAME));
+    }
+
+    let index = download_assets_index(st, dirs, version, None).await?;
+    let targets = &targets;
+    stream::iter(index.objects.iter())
+        .map(Ok::<_, crate::Error>)
+        .try_for_each_concurrent(None, |(name, asset)| async move {
+            let object = dirs.object_dir(&asset.hash);
+            let name =
+                name.replace('/', &String::from(std::path::MAIN_SEPARATOR));
+            for target in targets {
+                let path = target.join(&name);
+                if !path.exists() && object.exists() {
+                    link_or_copy(&object, &path, &st.io_semaphore).await?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+    tracing::debug!("Linked legacy assets of {}", version.asset_index.id);
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(st, dirs, index))]
 #[theseus_macros::debug_pin]
 pub async fn download_assets(
     st: &State,
-    with_legacy: bool,
+    dirs: &GameDirectories,
+    virtual_dir: Option<&Path>,
     index: &AssetsIndex,
     loading_bar: Option<&LoadingBarId>,
     loading_amount: f64,
 ) -> crate::Result<()> {
     tracing::debug!("Loading assets");
+    // Only the objects the directory lacks are downloaded, which after an
+    // update of the index is usually a few of them
+    let present = &present_objects(st, dirs, index).await?;
+    // Objects are shared by hash, so each one is only counted once
+    let missing = index
+        .objects
+        .values()
+        .filter(|x| !present.contains(&x.hash))
+        .map(|x| (&x.hash, x.size as u64))
+        .collect::<HashMap<_, _>>();
+    tracing::debug!(
+        "{} of {} assets need downloading",
+        missing.len(),
+        index.objects.len()
+    );
+    if let Some(loading_bar) = loading_bar {
+        add_loading_bytes(loading_bar, missing.values().sum()).await?;
+    }
+
+    // Objects are shared by hash, so names sharing one only fetch it once.
+    // Only fetched objects are recorded as matching their hash
+    let claimed = &Mutex::new(HashSet::new());
+    let fetched = &Mutex::new(Vec::new());
+    let missing = &missing;
+
     let num_futs = index.objects.len();
     let assets = stream::iter(index.objects.iter())
         .map(Ok::<(&String, &Asset), crate::Error>);
@@ -197,50 +477,213 @@ pub async fn download_assets(
             None,
             |(name, asset)| async move {
                 let hash = &asset.hash;
-                let resource_path = st.directories.object_dir(hash).await;
+                let resource_path = dirs.object_dir(hash);
                 let url = format!(
                     "https://resources.download.minecraft.net/{sub_hash}/{hash}",
                     sub_hash = &hash[..2]
                 );
 
-                let fetch_cell = OnceCell::<bytes::Bytes>::new();
-                tokio::try_join! {
-                    async {
-                        if !resource_path.exists() {
-                            let resource = fetch_cell
-                                .get_or_try_init(|| fetch(&url, Some(hash), &st.fetch_semaphore, &CredentialsStore(None)))
-                                .await?;
-                            write(&resource_path, resource, &st.io_semaphore).await?;
-                            tracing::trace!("Fetched asset with hash {hash}");
-                        }
-                        Ok::<_, crate::Error>(())
-                    },
-                    async {
-                        if with_legacy {
-                            let resource = fetch_cell
-                                .get_or_try_init(|| fetch(&url, Some(hash), &st.fetch_semaphore, &CredentialsStore(None)))
-                                .await?;
-                            let resource_path = st.directories.legacy_assets_dir().await.join(
-                                name.replace('/', &String::from(std::path::MAIN_SEPARATOR))
-                            );
-                            write(&resource_path, resource, &st.io_semaphore).await?;
-                            tracing::trace!("Fetched legacy asset with hash {hash}");
-                        }
-                        Ok::<_, crate::Error>(())
-                    },
-                }?;
+                // Objects that are there but cut short or not matching their
+                // hash aren't present, so they are fetched again
+                let claim = missing.contains_key(hash)
+                    && claimed.lock().await.insert(hash.clone());
+                if claim {
+                    let resource = fetch_mirrors(&[&url], Some(hash), Some(asset.size as u64), &st.fetch_semaphore, &CredentialsStore(None)).await?;
+                    write(&resource_path, &resource, &st.io_semaphore).await?;
+                    fetched.lock().await.push(hash.clone());
+                    if let Some(loading_bar) = loading_bar {
+                        record_loading_bytes(loading_bar, resource.len() as u64, name).await?;
+                    }
+                    tracing::trace!("Fetched asset with hash {hash}");
+                }
 
                 tracing::trace!("Loaded asset with hash {hash}");
                 Ok(())
             }).await?;
+
+    // The objects directory is content addressed, so legacy layouts are
+    // linked from it rather than downloaded twice. Done once every object
+    // is written, as names can share an object
+    if let Some(virtual_dir) = virtual_dir {
+        stream::iter(index.objects.iter())
+            .map(Ok::<_, crate::Error>)
+            .try_for_each_concurrent(None, |(name, asset)| async move {
+                let legacy_path = virtual_dir.join(
+                    name.replace('/', &String::from(std::path::MAIN_SEPARATOR)),
+                );
+                if !legacy_path.exists() {
+                    link_or_copy(
+                        &dirs.object_dir(&asset.hash),
+                        &legacy_path,
+                        &st.io_semaphore,
+                    )
+                    .await?;
+                    tracing::trace!(
+                        "Linked legacy asset with hash {}",
+                        asset.hash
+                    );
+                }
+                Ok(())
+            })
+            .await?;
+    }
+
+    // Downloads are checked against their hash, so fetched objects are now
+    // known to match it
+    let fetched = fetched.lock().await;
+    if !fetched.is_empty() {
+        let _lock = OBJECTS_MANIFEST_LOCK.lock().await;
+        let mut manifest = read_objects_manifest(st, dirs).await;
+        manifest
+            .objects
+            .extend(fetched.iter().map(|hash| (hash.clone(), missing[hash])));
+        write_objects_manifest(st, dirs, &manifest).await?;
+    }
     tracing::debug!("Done loading assets!");
     Ok(())
 }
 
-#[tracing::instrument(skip(st, libraries))]
+// The objects of an index already in the objects directory. Objects listed
+// in the manifest are trusted at the size it records, while ones it doesn't
+// list, such as objects from before the manifest, are hashed once and added
+async fn present_objects(
+    st: &State,
+    dirs: &GameDirectories,
+    index: &AssetsIndex,
+) -> crate::Result<HashSet<String>> {
+    let _lock = OBJECTS_MANIFEST_LOCK.lock().await;
+    let mut manifest = read_objects_manifest(st, dirs).await;
+
+    let objects = index
+        .objects
+        .values()
+        .map(|x| (x.hash.as_str(), x.size as u64))
+        .collect::<HashMap<_, _>>();
+    let mut present = HashSet::new();
+    let mut unknown = Vec::new();
+    for (hash, size) in &objects {
+        let path = dirs.object_dir(hash);
+        match tokio::fs::metadata(&path).await {
+            Ok(meta) if meta.len() == *size => {
+                if manifest.objects.get(*hash) == Some(size) {
+                    present.insert(hash.to_string());
+                } else {
+                    unknown.push((*hash, path));
+                }
+            }
+            // Missing, or cut short by an interrupted write
+            _ => {}
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(present);
+    }
+
+    let verified = stream::iter(unknown)
+        .map(|(hash, path)| async move {
+            let bytes = io::read(&path).await?;
+            let actual = sha1_async(bytes.into()).await?;
+            Ok::<_, crate::Error>((actual == hash).then_some(hash))
+        })
+        .buffer_unordered(16)
+        .try_filter_map(|x| async move { Ok(x) })
+        .try_collect::<Vec<_>>()
+        .await?;
+    tracing::debug!(
+        "Found {} assets matching their hash outside the manifest",
+        verified.len()
+    );
+    for hash in verified {
+        manifest.objects.insert(hash.to_string(), objects[hash]);
+        present.insert(hash.to_string());
+    }
+    write_objects_manifest(st, dirs, &manifest).await?;
+
+    Ok(present)
+}
+
+// A manifest that can't be read is started over, which only costs hashing
+// the objects again
+async fn read_objects_manifest(
+    st: &State,
+    dirs: &GameDirectories,
+) -> ObjectsManifest {
+    let path = dirs.assets_dir.join(OBJECTS_MANIFEST_FILE);
+    read_json(&path, &st.io_semaphore).await.unwrap_or_default()
+}
+
+async fn write_objects_manifest(
+    st: &State,
+    dirs: &GameDirectories,
+    manifest: &ObjectsManifest,
+) -> crate::Result<()> {
+    let path = dirs.assets_dir.join(OBJECTS_MANIFEST_FILE);
+    write(&path, &serde_json::to_vec(manifest)?, &st.io_semaphore).await
+}
+
+/// Downloads a version of the game with its libraries and assets into the
+/// shared directories before any profile uses it, so that creating one is
+/// quick. Meant to be run in the background for upcoming snapshots
+#[tracing::instrument]
+#[theseus_macros::debug_pin]
+pub async fn prefetch_version(version_id: &str) -> crate::Result<()> {
+    let state = State::get().await?;
+    let version = state
+        .metadata
+        .read()
+        .await
+        .minecraft
+        .versions
+        .iter()
+        .find(|x| x.id == version_id)
+        .cloned()
+        .ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Unknown game version: {version_id}"
+            ))
+        })?;
+
+    let dirs = state.directories.shared_game_dirs().await;
+    let version_info =
+        download_version_info(&state, &version, None, None, None).await?;
+    let assets = async {
+        let index =
+            download_assets_index(&state, &dirs, &version_info, None).await?;
+        download_assets(
+            &state,
+            &dirs,
+            virtual_assets_dir(&dirs, &version_info.asset_index.id).as_deref(),
+            &index,
+            None,
+            0.0,
+        )
+        .await
+    };
+    tokio::try_join! {
+        download_client(&state, &version_info, None),
+        assets,
+        // The Java the version ends up launched with isn't known yet, so
+        // natives are extracted for the architecture of the launcher
+        download_libraries(
+            &state,
+            &dirs,
+            &version_info.libraries,
+            &version_info.id,
+            None,
+            0.0,
+            std::env::consts::ARCH,
+        )
+    }?;
+
+    tracing::info!("Prefetched Minecraft {version_id}");
+    Ok(())
+}
+
+#[tracing::instrument(skip(st, dirs, libraries))]
 #[theseus_macros::debug_pin]
 pub async fn download_libraries(
     st: &State,
+    dirs: &GameDirectories,
     libraries: &[Library],
     version: &str,
     loading_bar: Option<&LoadingBarId>,
@@ -249,16 +692,52 @@ pub async fn download_libraries(
 ) -> crate::Result<()> {
     tracing::debug!("Loading libraries");
 
+    // Natives extracted from the same jars by an earlier install are reused
+    let natives_dir = &dirs.version_natives_dir(version).join(java_arch);
+    let natives_key = natives_key(libraries, java_arch);
+    let natives_cached = natives_cached(st, natives_dir, &natives_key).await;
+    if natives_cached {
+        tracing::debug!("Reusing natives of {version} for {java_arch}");
+    } else if natives_dir.exists() {
+        // Leftovers of other jars would end up on the library path too
+        io::remove_dir_all(natives_dir).await?;
+    }
+
     tokio::try_join! {
-        io::create_dir_all(st.directories.libraries_dir().await),
-        io::create_dir_all(st.directories.version_natives_dir(version).await)
+        io::create_dir_all(&dirs.libraries_dir),
+        io::create_dir_all(natives_dir)
     }?;
+    if let Some(loading_bar) = loading_bar {
+        let mut missing = 0;
+        for library in libraries {
+            if let Some(rules) = &library.rules {
+                if !rules.iter().any(|x| {
+                    super::parse_rule(x, java_arch, &FeatureContext::default())
+                }) {
+                    continue;
+                }
+            }
+            if let Some(artifact) = library
+                .downloads
+                .as_ref()
+                .and_then(|x| x.artifact.as_ref())
+            {
+                let path = d::get_path_from_artifact(&library.name)?;
+                if !dirs.libraries_dir.join(path).exists() {
+                    missing += artifact.size as u64;
+                }
+            }
+        }
+        add_loading_bytes(loading_bar, missing).await?;
+    }
+
     let num_files = libraries.len();
+    let natives_manifest = &Mutex::new(HashMap::new());
     loading_try_for_each_concurrent(
         stream::iter(libraries.iter())
             .map(Ok::<&Library, crate::Error>), None, loading_bar,loading_amount,num_files, None,|library| async move {
                 if let Some(rules) = &library.rules {
-                    if !rules.iter().any(|x| super::parse_rule(x, java_arch)) {
+                    if !rules.iter().any(|x| super::parse_rule(x, java_arch, &FeatureContext::default())) {
                         tracing::trace!("Skipped library {}", &library.name);
                         return Ok(());
                     }
@@ -267,7 +746,7 @@ pub async fn download_libraries(
                 tokio::try_join! {
                     async {
                         let artifact_path = d::get_path_from_artifact(&library.name)?;
-                        let path = st.directories.libraries_dir().await.join(&artifact_path);
+                        let path = dirs.libraries_dir.join(&artifact_path);
 
                         match library.downloads {
                             _ if path.exists() => Ok(()),
@@ -275,9 +754,12 @@ pub async fn download_libraries(
                                 artifact: Some(ref artifact),
                                 ..
                             }) => {
-                                let bytes = fetch(&artifact.url, Some(&artifact.sha1), &st.fetch_semaphore, &CredentialsStore(None))
+                                let bytes = fetch_mirrors(&[&artifact.url], Some(&artifact.sha1), None, &st.fetch_semaphore, &CredentialsStore(None))
                                     .await?;
                                 write(&path, &bytes, &st.io_semaphore).await?;
+                                if let Some(loading_bar) = loading_bar {
+                                    record_loading_bytes(loading_bar, bytes.len() as u64, &library.name).await?;
+                                }
                                 tracing::trace!("Fetched library {} to path {:?}", &library.name, &path);
                                 Ok::<_, crate::Error>(())
                             }
@@ -290,7 +772,7 @@ pub async fn download_libraries(
                                     &artifact_path
                                 ].concat();
 
-                                let bytes = fetch(&url, None, &st.fetch_semaphore, &CredentialsStore(None)).await?;
+                                let bytes = fetch_mirrors(&[&url], None, None, &st.fetch_semaphore, &CredentialsStore(None)).await?;
                                 write(&path, &bytes, &st.io_semaphore).await?;
                                 tracing::trace!("Fetched library {} to path {:?}", &library.name, &path);
                                 Ok::<_, crate::Error>(())
@@ -298,6 +780,10 @@ pub async fn download_libraries(
                         }
                     },
                     async {
+                        if natives_cached {
+                            return Ok(());
+                        }
+
                         // HACK: pseudo try block using or else
                         if let Some((os_key, classifiers)) = None.or_else(|| Some((
                             library
@@ -312,14 +798,34 @@ pub async fn download_libraries(
                         ))) {
                             let parsed_key = os_key.replace(
                                 "${arch}",
-                                crate::util::platform::ARCH_WIDTH,
+                                crate::util::platform::arch_width(java_arch),
                             );
 
                             if let Some(native) = classifiers.get(&parsed_key) {
-                                let data = fetch(&native.url, Some(&native.sha1), &st.fetch_semaphore, &CredentialsStore(None)).await?;
+                                let data = fetch_mirrors(&[&native.url], Some(&native.sha1), None, &st.fetch_semaphore, &CredentialsStore(None)).await?;
                                 let reader = std::io::Cursor::new(&data);
                                 if let Ok(mut archive) = zip::ZipArchive::new(reader) {
-                                    match archive.extract(st.directories.version_natives_dir(version).await) {
+                                    // Remember what the archive holds, so the launch can
+                                    // check that extraction actually produced these files,
+                                    // unchanged
+                                    let files = (0..archive.len())
+                                        .filter_map(|x| archive.by_index(x).ok())
+                                        .filter(|x| x.is_file())
+                                        .map(|mut x| {
+                                            let mut bytes = Vec::new();
+                                            let sha1 = std::io::Read::read_to_end(&mut x, &mut bytes)
+                                                .ok()
+                                                .map(|_| sha1::Sha1::from(&bytes).hexdigest());
+                                            NativeFile {
+                                                path: x.name().to_string(),
+                                                size: x.size(),
+                                                sha1,
+                                            }
+                                        })
+                                        .collect::<Vec<_>>();
+                                    natives_manifest.lock().await.insert(library.name.clone(), files);
+
+                                    match archive.extract(natives_dir) {
                                         Ok(_) => tracing::info!("Fetched native {}", &library.name),
                                         Err(err) => tracing::error!("Failed extracting native {}. err: {}", &library.name, err)
                                     }
@@ -338,6 +844,365 @@ pub async fn download_libraries(
             }
         ).await?;
 
+    if !natives_cached {
+        warn_unavailable_natives(libraries, java_arch).await?;
+
+        let manifest = NativesManifest {
+            key: natives_key,
+            libraries: std::mem::take(&mut *natives_manifest.lock().await),
+        };
+        write(
+            &natives_dir.join(NATIVES_MANIFEST_FILE),
+            &serde_json::to_vec(&manifest)?,
+            &st.io_semaphore,
+        )
+        .await?;
+    }
+
     tracing::debug!("Done loading libraries!");
     Ok(())
 }
+
+// Identifies the native jars of a version for an architecture by their
+// hashes, which change whenever a loader or the game swaps one out
+fn natives_key(libraries: &[Library], java_arch: &str) -> String {
+    let mut natives = libraries
+        .iter()
+        .filter(|library| {
+            library.rules.as_ref().map_or(true, |rules| {
+                rules.iter().any(|x| {
+                    super::parse_rule(x, java_arch, &FeatureContext::default())
+                })
+            })
+        })
+        .filter_map(|library| {
+            let os_key = library
+                .natives
+                .as_ref()?
+                .get(&Os::native_arch(java_arch))?
+                .replace(
+                    "${arch}",
+                    crate::util::platform::arch_width(java_arch),
+                );
+            let native = library
+                .downloads
+                .as_ref()?
+                .classifiers
+                .as_ref()?
+                .get(&os_key)?;
+            Some(format!("{} {}", library.name, native.sha1))
+        })
+        .collect::<Vec<_>>();
+    natives.sort();
+    sha1::Sha1::from(format!("{java_arch}\n{}", natives.join("\n"))).hexdigest()
+}
+
+// Libraries for the OS without natives for the architecture of the Java
+// runtime, as with old LWJGL builds on ARM, which would crash the game
+async fn warn_unavailable_natives(
+    libraries: &[Library],
+    java_arch: &str,
+) -> crate::Result<()> {
+    let os = Os::native_arch(java_arch);
+    let unavailable = libraries
+        .iter()
+        .filter(|library| {
+            library.rules.as_ref().map_or(true, |rules| {
+                rules.iter().any(|x| {
+                    super::parse_rule(x, java_arch, &FeatureContext::default())
+                })
+            })
+        })
+        .filter(|library| {
+            library.natives.as_ref().map_or(false, |natives| {
+                !natives.contains_key(&os)
+                    && natives.contains_key(&Os::native())
+            })
+        })
+        .map(|x| x.name.as_str())
+        .collect::<Vec<_>>();
+
+    if !unavailable.is_empty() {
+        let message = format!(
+            "No natives for {java_arch} were found for {}, the game may \
+             crash on launch. Overriding LWJGL with a newer version can fix \
+             this",
+            unavailable.join(", ")
+        );
+        tracing::warn!("{message}");
+        emit_coded_warning(&message, Some("natives_unavailable")).await?;
+    }
+    Ok(())
+}
+
+// Whether a natives directory was extracted from the same jars and still
+// holds every file it was extracted with, at its original size
+async fn natives_cached(st: &State, natives_dir: &Path, key: &str) -> bool {
+    let manifest_path = natives_dir.join(NATIVES_MANIFEST_FILE);
+    let Ok(manifest) =
+        read_json::<NativesManifest>(&manifest_path, &st.io_semaphore).await
+    else {
+        return false;
+    };
+
+    if manifest.key != key {
+        return false;
+    }
+    for file in manifest.libraries.values().flatten() {
+        let extracted = tokio::fs::metadata(natives_dir.join(&file.path))
+            .await
+            .map_or(false, |meta| meta.len() == file.size);
+        if !extracted {
+            return false;
+        }
+    }
+    true
+}
+
+/// Directory the natives of a version are extracted to for an architecture.
+/// Versions installed before natives were kept per architecture have theirs
+/// in the natives folder of the version itself
+pub fn natives_dir(
+    dirs: &GameDirectories,
+    version: &str,
+    java_arch: &str,
+) -> PathBuf {
+    let version_dir = dirs.version_natives_dir(version);
+    let arch_dir = version_dir.join(java_arch);
+    if !arch_dir.exists() && version_dir.join(NATIVES_MANIFEST_FILE).exists() {
+        version_dir
+    } else {
+        arch_dir
+    }
+}
+
+/// Removes the files of a version that fail their hash check, so installing
+/// it again downloads them anew. Files without a hash from Mojang or the
+/// loader are checked against `pinned`, the hashes recorded at install time
+/// by path relative to the libraries folder. Returns the removed files
+#[tracing::instrument(skip(st, dirs, version, pinned))]
+#[theseus_macros::debug_pin]
+pub async fn remove_corrupt_files(
+    st: &State,
+    dirs: &GameDirectories,
+    version: &GameVersionInfo,
+    java_arch: &str,
+    skip_assets: bool,
+    pinned: Option<&HashMap<String, String>>,
+) -> crate::Result<Vec<PathBuf>> {
+    let mut expected = HashMap::new();
+    if let Some(client) =
+        version.downloads.get(&d::minecraft::DownloadType::Client)
+    {
+        let path = st
+            .directories
+            .version_dir(&version.id)
+            .await
+            .join(format!("{}.jar", version.id));
+        expected.insert(path, client.sha1.clone());
+    }
+
+    for library in &version.libraries {
+        if let Some(rules) = &library.rules {
+            if !rules.iter().any(|x| {
+                super::parse_rule(x, java_arch, &FeatureContext::default())
+            }) {
+                continue;
+            }
+        }
+        let path = d::get_path_from_artifact(&library.name)?;
+        let sha1 = library
+            .downloads
+            .as_ref()
+            .and_then(|x| x.artifact.as_ref())
+            .map(|x| x.sha1.clone())
+            .or_else(|| pinned.and_then(|x| x.get(&path)).cloned());
+        if let Some(sha1) = sha1 {
+            expected.insert(dirs.libraries_dir.join(path), sha1);
+        }
+    }
+    // Processor outputs are only known by their recorded hashes
+    for (path, sha1) in pinned.into_iter().flatten() {
+        expected
+            .entry(dirs.libraries_dir.join(path))
+            .or_insert_with(|| sha1.clone());
+    }
+
+    let mut removed = Vec::new();
+    if !skip_assets {
+        // The index is stored reserialized, so it can only be checked by
+        // reading it
+        let index_path = dirs
+            .assets_index_dir()
+            .join(format!("{}.json", version.asset_index.id));
+        match read_json::<AssetsIndex>(&index_path, &st.io_semaphore).await {
+            Ok(index) => expected.extend(
+                index
+                    .objects
+                    .into_values()
+                    .map(|x| (dirs.object_dir(&x.hash), x.hash)),
+            ),
+            Err(_) if index_path.exists() => {
+                io::remove_file(&index_path).await?;
+                removed.push(index_path);
+            }
+            Err(_) => {}
+        }
+    }
+
+    let corrupt = stream::iter(expected)
+        .map(|(path, sha1)| async move {
+            if !path.exists() {
+                return Ok(None);
+            }
+            let bytes = io::read(&path).await?;
+            let hash = sha1_async(bytes.into()).await?;
+            Ok::<_, crate::Error>((hash != sha1).then_some(path))
+        })
+        .buffer_unordered(16)
+        .try_filter_map(|x| async move { Ok(x) })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    for path in corrupt {
+        tracing::warn!("Removing corrupt file {}", path.display());
+        io::remove_file(&path).await?;
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// A library whose native files are missing or changed after extraction
+#[derive(Debug, Clone)]
+pub struct MissingNative {
+    pub library: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Every library with missing or changed native files for a version
+#[derive(Debug, Clone)]
+pub struct MissingNatives(pub Vec<MissingNative>);
+
+impl std::fmt::Display for MissingNatives {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let libraries = self
+            .0
+            .iter()
+            .map(|x| {
+                let files = x
+                    .files
+                    .iter()
+                    .map(|x| x.display().to_string())
+                    .collect::<Vec<_>>();
+                format!("{} ({})", x.library, files.join(", "))
+            })
+            .collect::<Vec<_>>();
+        write!(fmt, "{}", libraries.join("; "))
+    }
+}
+
+/// Checks that every native file extracted for a version is still present
+/// and unchanged, extracting them again if not, so a broken extraction fails
+/// here instead of inside the JVM
+#[tracing::instrument(skip(st, dirs, libraries))]
+pub async fn verify_natives(
+    st: &State,
+    dirs: &GameDirectories,
+    libraries: &[Library],
+    version: &str,
+    java_arch: &str,
+) -> crate::Result<()> {
+    let broken = broken_natives(st, dirs, version, java_arch).await?;
+    if broken.is_empty() {
+        return Ok(());
+    }
+
+    tracing::warn!(
+        "Natives of {version} are broken, extracting them again: {}",
+        MissingNatives(broken)
+    );
+    // Without a manifest, the natives directory isn't reused
+    let natives_dir = natives_dir(dirs, version, java_arch);
+    io::remove_file(natives_dir.join(NATIVES_MANIFEST_FILE)).await?;
+    download_libraries(st, dirs, libraries, version, None, 0.0, java_arch)
+        .await?;
+
+    let broken = broken_natives(st, dirs, version, java_arch).await?;
+    if broken.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::ErrorKind::MissingNatives(MissingNatives(broken)).into())
+    }
+}
+
+// Native files of a version that are missing, or differ from the ones
+// extracted
+async fn broken_natives(
+    st: &State,
+    dirs: &GameDirectories,
+    version: &str,
+    java_arch: &str,
+) -> crate::Result<Vec<MissingNative>> {
+    let natives_dir = natives_dir(dirs, version, java_arch);
+    let manifest_path = natives_dir.join(NATIVES_MANIFEST_FILE);
+
+    // Versions installed before natives were tracked have nothing to check
+    if !manifest_path.exists() {
+        tracing::debug!("No natives manifest for version {version}");
+        return Ok(Vec::new());
+    }
+
+    // Manifests from before natives were cached only list the paths
+    let manifest: HashMap<String, Vec<(String, Option<String>)>> =
+        match read_json::<NativesManifest>(&manifest_path, &st.io_semaphore)
+            .await
+        {
+            Ok(manifest) => manifest
+                .libraries
+                .into_iter()
+                .map(|(library, files)| {
+                    let files =
+                        files.into_iter().map(|x| (x.path, x.sha1)).collect();
+                    (library, files)
+                })
+                .collect(),
+            Err(_) => read_json::<HashMap<String, Vec<String>>>(
+                &manifest_path,
+                &st.io_semaphore,
+            )
+            .await?
+            .into_iter()
+            .map(|(library, files)| {
+                (library, files.into_iter().map(|x| (x, None)).collect())
+            })
+            .collect(),
+        };
+
+    let mut broken = Vec::new();
+    for (library, files) in manifest {
+        let mut broken_files = Vec::new();
+        for (file, sha1) in files {
+            let path = natives_dir.join(file);
+            let intact = match (path.exists(), sha1) {
+                (false, _) => false,
+                (true, None) => true,
+                (true, Some(sha1)) => {
+                    let bytes = io::read(&path).await?;
+                    sha1::Sha1::from(&bytes).hexdigest() == sha1
+                }
+            };
+            if !intact {
+                broken_files.push(path);
+            }
+        }
+        if !broken_files.is_empty() {
+            broken.push(MissingNative {
+                library,
+                files: broken_files,
+            });
+        }
+    }
+
+    Ok(broken)
+}
@@ -0,0 +1,241 @@
+//! Checks for GPU drivers known to crash the game, and running the game on
+//! the graphics card a profile prefers
+use crate::event::emit::emit_coded_warning;
+use crate::state::{GpuPreference, ProfilePathId};
+use crate::util::platform::{gpu_drivers, gpus, GpuDriver};
+use crate::State;
+use serde::Deserialize;
+
+// Shipped with the launcher, as no service hosts such a list
+const GPU_BLOCKLIST: &str = include_str!("../../library/gpu_blocklist.json");
+
+/// A combination of GPU, driver and game versions that crashes the game
+#[derive(Deserialize, Debug)]
+struct BlockedDriver {
+    /// Code shown with the warning, for looking up the problem
+    code: String,
+    message: String,
+    /// Matched case-insensitively against part of the GPU name
+    gpu: String,
+    /// Affected driver versions, where a trailing `*` matches any version
+    /// starting with the rest
+    driver_versions: Vec<String>,
+    /// Affected game versions, all of them if empty. A trailing `*` matches
+    /// any version starting with the rest
+    #[serde(default)]
+    game_versions: Vec<String>,
+    /// Affected operating system, as in `std::env::consts::OS`, or all of
+    /// them if unset
+    #[serde(default)]
+    os: Option<String>,
+}
+
+impl BlockedDriver {
+    fn matches(&self, driver: &GpuDriver, game_version: &str) -> bool {
+        self.os
+            .as_deref()
+            .map_or(true, |x| x == std::env::consts::OS)
+            && (self.game_versions.is_empty()
+                || self
+                    .game_versions
+                    .iter()
+                    .any(|x| version_matches(x, game_version)))
+            && driver
+                .name
+                .to_lowercase()
+                .contains(&self.gpu.to_lowercase())
+            && self
+                .driver_versions
+                .iter()
+                .any(|x| version_matches(x, &driver.version))
+    }
+}
+
+// Whether a version is the one of a pattern, or starts with it if the
+// pattern ends with `*`
+fn version_matches(pattern: &str, version: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => version.starts_with(prefix),
+        None => version == pattern,
+    }
+}
+
+/// Warns about every installed GPU driver on the blocklist for a game
+/// version
+#[tracing::instrument]
+pub async fn check_gpu_drivers(game_version: &str) -> crate::Result<()> {
+    let drivers = tokio::task::spawn_blocking(gpu_drivers).await?;
+    if drivers.is_empty() {
+        return Ok(());
+    }
+
+    let blocklist: Vec<BlockedDriver> = serde_json::from_str(GPU_BLOCKLIST)?;
+
+    for driver in &drivers {
+        for blocked in
+            blocklist.iter().filter(|x| x.matches(driver, game_version))
+        {
+            emit_coded_warning(
+                &format!(
+                    "{} driver {} is known to crash Minecraft {}: {}",
+                    driver.name, driver.version, game_version, blocked.message
+                ),
+                Some(&blocked.code),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes the game run on the card a profile prefers, returning environment
+/// variables to launch it with. Windows picks the card of a process by its
+/// executable, so the preference is registered for the Java executable
+/// instead, and recorded in the profile. Systems with a single card are left
+/// alone
+#[tracing::instrument]
+pub async fn apply_preference(
+    profile_path: &ProfilePathId,
+    preference: GpuPreference,
+    java_path: &str,
+) -> crate::Result<Vec<(String, String)>> {
+    if preference == GpuPreference::Default {
+        release_preference(profile_path).await?;
+        return Ok(Vec::new());
+    }
+
+    let gpus = tokio::task::spawn_blocking(gpus).await?;
+    if gpus.len() < 2 {
+        tracing::debug!("Only found {} GPUs, ignoring preference", gpus.len());
+        return Ok(Vec::new());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let registered = crate::api::profile::get(profile_path, None)
+            .await?
+            .and_then(|x| x.gpu_registered_java);
+        if registered.as_deref() != Some(java_path) {
+            release_preference(profile_path).await?;
+        }
+
+        register_preference(java_path, preference)
+            .map_err(crate::util::io::IOError::from)?;
+        let java_path = java_path.to_string();
+        crate::api::profile::edit(profile_path, |prof| {
+            prof.gpu_registered_java = Some(java_path.clone());
+            async { Ok(()) }
+        })
+        .await?;
+        State::sync().await?;
+        Ok(Vec::new())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = java_path;
+        Ok(prime_env(preference, &gpus))
+    }
+}
+
+/// Removes the GPU preference a profile registered on Windows, unless
+/// another profile registered one for the same Java executable
+#[tracing::instrument]
+pub(crate) async fn release_preference(
+    profile_path: &ProfilePathId,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    let released = {
+        let mut profiles = state.profiles.write().await;
+        let Some(java_path) = profiles
+            .0
+            .get_mut(profile_path)
+            .and_then(|x| x.gpu_registered_java.take())
+        else {
+            return Ok(());
+        };
+        let shared = profiles.0.values().any(|x| {
+            x.gpu_registered_java.as_deref() == Some(java_path.as_str())
+        });
+        (!shared).then_some(java_path)
+    };
+    State::sync().await?;
+
+    #[cfg(target_os = "windows")]
+    if let Some(java_path) = released {
+        unregister_preference(&java_path)
+            .map_err(crate::util::io::IOError::from)?;
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = released;
+
+    Ok(())
+}
+
+// PRIME offloading is opt-in per process. The proprietary NVIDIA driver has
+// its own variables, every Mesa driver follows DRI_PRIME
+#[cfg(not(target_os = "windows"))]
+fn prime_env(
+    preference: GpuPreference,
+    gpus: &[crate::util::platform::Gpu],
+) -> Vec<(String, String)> {
+    let env = |vars: &[(&str, &str)]| {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+
+    match preference {
+        GpuPreference::Default => Vec::new(),
+        GpuPreference::Discrete
+            if gpus.iter().any(|x| x.discrete && x.vendor == "NVIDIA") =>
+        {
+            env(&[
+                ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+                ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+                ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+            ])
+        }
+        GpuPreference::Discrete => env(&[("DRI_PRIME", "1")]),
+        GpuPreference::Integrated => env(&[("DRI_PRIME", "0")]),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn register_preference(
+    java_path: &str,
+    preference: GpuPreference,
+) -> std::io::Result<()> {
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+
+    // Values of the graphics settings page, 1 saves power and 2 performs
+    let value = match preference {
+        GpuPreference::Default => return Ok(()),
+        GpuPreference::Integrated => "GpuPreference=1;",
+        GpuPreference::Discrete => "GpuPreference=2;",
+    };
+    let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey("Software\\Microsoft\\DirectX\\UserGpuPreferences")?;
+    key.set_value(java_path, &value)
+}
+
+#[cfg(target_os = "windows")]
+fn unregister_preference(java_path: &str) -> std::io::Result<()> {
+    use std::io::ErrorKind;
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_SET_VALUE};
+    use winreg::RegKey;
+
+    let key = match RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(
+        "Software\\Microsoft\\DirectX\\UserGpuPreferences",
+        KEY_SET_VALUE,
+    ) {
+        Ok(key) => key,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    match key.delete_value(java_path) {
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
@@ -0,0 +1,158 @@
+//! Measuring the heap a game really uses, from the log of its garbage
+//! collector, so memory sized automatically follows what a profile needs
+use crate::state::{HeapUsage, ProfilePathId};
+use crate::util::io;
+use crate::util::jre::extract_java_majorminor_version;
+use crate::State;
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const GC_LOG_FILE: &str = "gc.log";
+// Shorter sessions rarely load a world long enough to show what it needs
+const MIN_SESSION_MINUTES: i64 = 5;
+
+lazy_static! {
+    // Heap before and after a collection, and its size, as logged by every
+    // collector: `120M->40M(512M)` from Java 9, `33280K->5112K(125952K)`
+    // from Java 8
+    static ref HEAP_CHANGE: Regex =
+        Regex::new(r"(\d+)([KMG])->(\d+)([KMG])\((\d+)([KMG])\)").unwrap();
+}
+
+/// Heap usage measured over one session, in MB
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeapSample {
+    /// Most heap in use right after a collection
+    pub live_mb: u32,
+    /// Most heap in use right before a collection
+    pub peak_mb: u32,
+    /// Largest size the heap grew to
+    pub heap_mb: u32,
+}
+
+/// Where the collector of a session logs to
+pub(crate) fn gc_log_path(state: &State, uuid: Uuid) -> PathBuf {
+    state
+        .directories
+        .sessions_dir()
+        .join(uuid.to_string())
+        .join(GC_LOG_FILE)
+}
+
+/// JVM arguments logging each collection to a file, in the syntax of the
+/// Java version
+pub(crate) fn gc_log_args(java_version: &str, path: &Path) -> Vec<String> {
+    let unified = extract_java_majorminor_version(java_version)
+        .map_or(true, |(_, major)| major >= 9);
+    if unified {
+        // Quoted for the colon of Windows drives, and never rotated as
+        // collections are logged in one short line each
+        vec![format!(
+            "-Xlog:gc:file=\"{}\":uptime:filecount=0",
+            path.display()
+        )]
+    } else {
+        vec![format!("-Xloggc:{}", path.display())]
+    }
+}
+
+/// Reads the heap usage of a session from its GC log, if enough collections
+/// happened to tell
+pub(crate) fn parse_gc_log(log: &str) -> Option<HeapSample> {
+    let mut sample: Option<HeapSample> = None;
+    for line in log.lines() {
+        // The whole heap comes last, after the generations on Java 8
+        let Some(change) = HEAP_CHANGE.captures_iter(line).last() else {
+            continue;
+        };
+        let size = |value: usize, unit: usize| {
+            let value = change[value].parse::<u64>().ok()?;
+            let mb = match &change[unit] {
+                "K" => value / 1024,
+                "M" => value,
+                _ => value * 1024,
+            };
+            u32::try_from(mb).ok()
+        };
+        let (Some(before), Some(after), Some(heap)) =
+            (size(1, 2), size(3, 4), size(5, 6))
+        else {
+            continue;
+        };
+
+        let sample = sample.get_or_insert(HeapSample {
+            live_mb: 0,
+            peak_mb: 0,
+            heap_mb: 0,
+        });
+        sample.live_mb = sample.live_mb.max(after);
+        sample.peak_mb = sample.peak_mb.max(before);
+        sample.heap_mb = sample.heap_mb.max(heap);
+    }
+
+    sample.filter(|x| x.heap_mb > 0)
+}
+
+/// Folds the usage of a session into what was measured before. Higher usage
+/// is taken at once, so the next launch gets enough memory, while lower
+/// usage is only eased towards, so one light session doesn't shrink the heap
+pub(crate) fn update_usage(
+    previous: Option<HeapUsage>,
+    sample: HeapSample,
+) -> HeapUsage {
+    let live_mb = match previous {
+        Some(previous) if previous.live_mb > sample.live_mb => {
+            (previous.live_mb * 3 + sample.live_mb) / 4
+        }
+        _ => sample.live_mb,
+    };
+
+    HeapUsage {
+        live_mb,
+        peak_mb: sample.peak_mb,
+        heap_mb: sample.heap_mb,
+        sessions: previous.map_or(0, |x| x.sessions) + 1,
+        recorded: Utc::now(),
+    }
+}
+
+/// Records the heap usage of a session that exited into its profile, unless
+/// it was too short to tell
+#[tracing::instrument]
+pub(crate) async fn record(
+    uuid: Uuid,
+    profile_path: &ProfilePathId,
+    started: DateTime<Utc>,
+) -> crate::Result<()> {
+    if Utc::now() - started < Duration::minutes(MIN_SESSION_MINUTES) {
+        tracing::debug!("Session {uuid} was too short to measure its heap");
+        return Ok(());
+    }
+
+    let state = State::get().await?;
+    let path = gc_log_path(&state, uuid);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let log = io::read(&path).await?;
+    let Some(sample) = parse_gc_log(&String::from_utf8_lossy(&log)) else {
+        tracing::debug!("No collections logged by session {uuid}");
+        return Ok(());
+    };
+    tracing::info!(
+        "Session {uuid} of {profile_path} kept {}M live of a {}M heap",
+        sample.live_mb,
+        sample.heap_mb
+    );
+
+    crate::api::profile::edit(profile_path, |prof| {
+        prof.heap_usage = Some(update_usage(prof.heap_usage, sample));
+        async { Ok(()) }
+    })
+    .await?;
+    State::sync().await
+}
@@ -0,0 +1,89 @@
+//! Removal of the caches loaders leave in a profile, once it is installed
+//! with another loader or loader version
+use crate::state::{InstalledLoader, Profile};
+use crate::util::io;
+use crate::State;
+use std::path::{Component, Path};
+
+// Folders loaders and common mods cache remapped or transformed classes in:
+// Fabric and Quilt's remapped mods, Mixin's exported classes, and the mods
+// Sinytra Connector converted
+const LOADER_CACHE_FOLDERS: [&str; 4] =
+    [".fabric", ".quilt", ".mixin.out", "mods/.connector"];
+
+fn describe(loader: &InstalledLoader) -> String {
+    match &loader.loader_version {
+        Some(version) => format!("{} {version}", loader.loader),
+        None => loader.loader.to_string(),
+    }
+}
+
+/// Removes the loader caches of a profile if the loader it is being installed
+/// with differs from the one it was last installed with. Profiles whose game
+/// is running are skipped, as the game has the caches open
+pub(super) async fn clean_if_changed(profile: &Profile) -> crate::Result<()> {
+    // Profiles installed before the loader was recorded are left alone, as
+    // it isn't known whether it changed
+    let Some(installed) = &profile.installed_loader else {
+        return Ok(());
+    };
+    let current = InstalledLoader::of(profile);
+    if *installed == current {
+        return Ok(());
+    }
+
+    let state = State::get().await?;
+    let cleanup = state.settings.read().await.loader_cache_cleanup.clone();
+    if !cleanup.enabled {
+        return Ok(());
+    }
+
+    let running = state
+        .children
+        .read()
+        .await
+        .running_keys_with_profile(profile.profile_id())
+        .await?;
+    if !running.is_empty() {
+        tracing::info!(
+            "Not removing loader caches of {}, as its game is running",
+            profile.profile_id()
+        );
+        return Ok(());
+    }
+
+    let profile_path = profile.get_profile_full_path().await?;
+    let folders = LOADER_CACHE_FOLDERS
+        .iter()
+        .map(|x| x.to_string())
+        .chain(cleanup.extra_folders);
+    for folder in folders {
+        if folder.is_empty()
+            || !Path::new(&folder)
+                .components()
+                .all(|x| matches!(x, Component::Normal(_)))
+        {
+            tracing::warn!(
+                "Skipping loader cache outside the profile: {folder}"
+            );
+            continue;
+        }
+
+        let path = profile_path.join(&folder);
+        if path.is_dir() {
+            io::remove_dir_all(&path).await?;
+        } else if path.is_file() {
+            io::remove_file(&path).await?;
+        } else {
+            continue;
+        }
+        tracing::info!(
+            "Removed {folder} from {} as its loader changed from {} to {}",
+            profile.profile_id(),
+            describe(installed),
+            describe(&current)
+        );
+    }
+
+    Ok(())
+}
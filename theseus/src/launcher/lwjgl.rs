@@ -0,0 +1,217 @@
+//! Swapping the LWJGL version a game ships with for another from Maven
+//! Central, for systems that need fixes from newer builds
+use crate::state::CredentialsStore;
+use crate::util::fetch::{fetch, read_json, write};
+use crate::State;
+use daedalus as d;
+use daedalus::minecraft::VersionInfo;
+use futures::future;
+use regex::Regex;
+use std::collections::HashMap;
+
+const MAVEN_CENTRAL_URL: &str = "https://repo1.maven.org/maven2/";
+const LWJGL_GROUP: &str = "org.lwjgl";
+
+/// Version of LWJGL 3 a game ships with. Versions before 1.13 ship LWJGL 2,
+/// which can't be swapped
+pub fn bundled_version(version_info: &VersionInfo) -> Option<String> {
+    version_info.libraries.iter().find_map(|library| {
+        let mut parts = library.name.split(':');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(LWJGL_GROUP), Some("lwjgl"), Some(version), None)
+                if version.starts_with("3.") =>
+            {
+                Some(version.to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Versions of LWJGL a game can be launched with: those on Maven Central of
+/// the same major version, no older than the one it ships with
+pub async fn compatible_versions(
+    st: &State,
+    version_info: &VersionInfo,
+) -> crate::Result<Vec<String>> {
+    let Some(bundled) = bundled_version(version_info) else {
+        return Ok(Vec::new());
+    };
+
+    let metadata = fetch(
+        &format!("{MAVEN_CENTRAL_URL}org/lwjgl/lwjgl/maven-metadata.xml"),
+        None,
+        &st.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await?;
+    let metadata = String::from_utf8_lossy(&metadata);
+
+    let version_tag = Regex::new(r"<version>([^<]+)</version>")?;
+    let mut versions = version_tag
+        .captures_iter(&metadata)
+        .map(|x| x[1].to_string())
+        .filter(|x| {
+            x.starts_with("3.") && version_parts(x) >= version_parts(&bundled)
+        })
+        .collect::<Vec<_>>();
+    versions.sort_by_key(|x| std::cmp::Reverse(version_parts(x)));
+    versions.dedup();
+    Ok(versions)
+}
+
+/// Checks a game can be launched with a version of LWJGL
+pub async fn validate(
+    st: &State,
+    version_info: &VersionInfo,
+    version: &str,
+) -> crate::Result<()> {
+    if bundled_version(version_info).is_none() {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Minecraft {} uses LWJGL 2, its version can't be overridden",
+            version_info.id
+        ))
+        .into());
+    }
+
+    if !compatible_versions(st, version_info)
+        .await?
+        .iter()
+        .any(|x| x == version)
+    {
+        return Err(crate::ErrorKind::InputError(format!(
+            "LWJGL {version} can't be used with Minecraft {}",
+            version_info.id
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Name of the folder the natives of a version are extracted to, which is
+/// kept apart for each LWJGL override so profiles don't share the wrong ones
+pub fn natives_version(version: &str, lwjgl: Option<&str>) -> String {
+    match lwjgl {
+        Some(lwjgl) => format!("{version}-lwjgl-{lwjgl}"),
+        None => version.to_string(),
+    }
+}
+
+/// Points every LWJGL library of a version at the artifacts of another
+/// version on Maven Central, returning whether it did. Their hashes are
+/// kept in the caches, so launching again doesn't need a connection
+#[tracing::instrument(skip(st, version_info))]
+pub async fn apply_override(
+    st: &State,
+    version_info: &mut VersionInfo,
+    version: &str,
+) -> crate::Result<bool> {
+    if bundled_version(version_info).is_none() {
+        tracing::warn!("{} uses LWJGL 2, not overriding it", version_info.id);
+        return Ok(false);
+    }
+
+    let mut downloads = Vec::new();
+    for library in &mut version_info.libraries {
+        let mut parts = library
+            .name
+            .split(':')
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        if parts.len() < 3
+            || parts[0] != LWJGL_GROUP
+            || !parts[2].starts_with("3.")
+        {
+            continue;
+        }
+        parts[2] = version.to_string();
+        library.name = parts.join(":");
+        library.url = Some(MAVEN_CENTRAL_URL.to_string());
+
+        if let Some(library_downloads) = &mut library.downloads {
+            if let Some(artifact) = &mut library_downloads.artifact {
+                let path = d::get_path_from_artifact(&library.name)?;
+                downloads.push((path, artifact));
+            }
+            // Natives of versions before 1.19 are classifiers of the library
+            if let Some(classifiers) = &mut library_downloads.classifiers {
+                for (classifier, native) in classifiers.iter_mut() {
+                    let path = d::get_path_from_artifact(&format!(
+                        "{}:{classifier}",
+                        library.name
+                    ))?;
+                    downloads.push((path, native));
+                }
+            }
+        }
+    }
+
+    let hashes_path = st
+        .directories
+        .caches_dir()
+        .join("lwjgl")
+        .join(format!("{version}.json"));
+    let mut hashes: HashMap<String, String> =
+        read_json(&hashes_path, &st.io_semaphore)
+            .await
+            .unwrap_or_default();
+
+    let missing = downloads
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|x| !hashes.contains_key(*x))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        let fetched = future::try_join_all(
+            missing.into_iter().map(|path| fetch_sha1(st, path)),
+        )
+        .await?;
+        hashes.extend(fetched);
+        write(
+            &hashes_path,
+            &serde_json::to_vec(&hashes)?,
+            &st.io_semaphore,
+        )
+        .await?;
+    }
+
+    for (path, download) in downloads {
+        download.url = format!("{MAVEN_CENTRAL_URL}{path}");
+        download.sha1 = hashes.get(&path).cloned().unwrap_or_default();
+    }
+
+    tracing::info!("Overrode LWJGL of {} with {version}", version_info.id);
+    Ok(true)
+}
+
+async fn fetch_sha1(st: &State, path: &str) -> crate::Result<(String, String)> {
+    let sha1 = fetch(
+        &format!("{MAVEN_CENTRAL_URL}{path}.sha1"),
+        None,
+        &st.fetch_semaphore,
+        &CredentialsStore(None),
+    )
+    .await
+    .map_err(|_| {
+        crate::ErrorKind::LauncherError(format!(
+            "{path} is not on Maven Central"
+        ))
+    })?;
+
+    // Some hash files have the file name after the hash
+    let sha1 = String::from_utf8_lossy(&sha1)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Ok((path.to_string(), sha1))
+}
+
+// Numeric parts of a version, for comparing them
+fn version_parts(version: &str) -> Vec<u32> {
+    version
+        .split(|x: char| !x.is_ascii_digit())
+        .filter_map(|x| x.parse().ok())
+        .collect()
+}
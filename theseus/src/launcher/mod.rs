@@ -4,46 +4,96 @@ use crate::event::{LoadingBarId, LoadingBarType};
 use crate::jre::{self, JAVA_17_KEY, JAVA_18PLUS_KEY, JAVA_8_KEY};
 use crate::launcher::io::IOError;
 use crate::prelude::JavaVersion;
-use crate::state::ProfileInstallStage;
+use crate::state::{Journal, OperationKind, ProfileInstallStage};
+use crate::util::censor::Censor;
 use crate::util::io;
 use crate::{
     process,
-    state::{self as st, MinecraftChild},
+    state::{self as st, Children, LaunchPlan, MinecraftChild},
     State,
 };
 use chrono::Utc;
 use daedalus as d;
 use daedalus::minecraft::VersionInfo;
 use st::Profile;
-use std::collections::HashMap;
 use std::{process::Stdio, sync::Arc};
 use tokio::process::Command;
 use uuid::Uuid;
 
-mod args;
+mod loader_cache;
+mod pins;
 
+pub(crate) mod args;
 pub mod auth;
+pub(crate) mod display;
 pub mod download;
+pub(crate) mod gpu;
+pub(crate) mod heap;
+pub(crate) mod lwjgl;
+pub mod prepare;
+pub(crate) mod processors;
+pub(crate) mod session;
+
+/// The launcher features a game is started with, which argument rules are
+/// evaluated against. Rules on anything not listed here never apply
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeatureContext {
+    pub is_demo_user: bool,
+    pub has_custom_resolution: bool,
+    pub has_quick_plays_support: bool,
+    pub is_quick_play_singleplayer: bool,
+    pub is_quick_play_multiplayer: bool,
+    pub is_quick_play_realms: bool,
+}
+
+impl FeatureContext {
+    /// Whether every feature a rule asks about has the value it requires
+    pub fn matches(&self, rule: &d::minecraft::FeatureRule) -> bool {
+        let features = [
+            (rule.is_demo_user, self.is_demo_user),
+            (rule.has_custom_resolution, self.has_custom_resolution),
+            (rule.has_quick_plays_support, self.has_quick_plays_support),
+            (
+                rule.is_quick_play_singleplayer,
+                self.is_quick_play_singleplayer,
+            ),
+            (
+                rule.is_quick_play_multiplayer,
+                self.is_quick_play_multiplayer,
+            ),
+            (rule.is_quick_play_realms, self.is_quick_play_realms),
+        ];
+
+        // A rule without any known feature is on one added after this
+        // launcher, whose arguments it can't provide
+        features.iter().any(|(required, _)| required.is_some())
+            && features.iter().all(|(required, enabled)| {
+                required.map_or(true, |x| x == *enabled)
+            })
+    }
+}
 
 #[tracing::instrument]
-pub fn parse_rule(rule: &d::minecraft::Rule, java_version: &str) -> bool {
+pub fn parse_rule(
+    rule: &d::minecraft::Rule,
+    java_version: &str,
+    features: &FeatureContext,
+) -> bool {
     use d::minecraft::{Rule, RuleAction};
 
     let res = match rule {
         Rule {
-            os: Some(ref os), ..
-        } => crate::util::platform::os_rule(os, java_version),
-        Rule {
-            features: Some(ref features),
+            os: Some(ref os),
+            features: ref rule_features,
             ..
         } => {
-            !features.is_demo_user.unwrap_or(true)
-                || features.has_custom_resolution.unwrap_or(false)
-                || !features.has_quick_plays_support.unwrap_or(true)
-                || !features.is_quick_play_multiplayer.unwrap_or(true)
-                || !features.is_quick_play_realms.unwrap_or(true)
-                || !features.is_quick_play_singleplayer.unwrap_or(true)
+            crate::util::platform::os_rule(os, java_version)
+                && rule_features.as_ref().map_or(true, |x| features.matches(x))
         }
+        Rule {
+            features: Some(ref rule_features),
+            ..
+        } => features.matches(rule_features),
         _ => false,
     };
 
@@ -97,11 +147,121 @@ pub async fn get_java_version_from_profile(
     }
 }
 
+/// Applies the LWJGL override of a profile for the architecture of its Java
+/// runtime to its version, returning the LWJGL version it was overridden with
+async fn override_lwjgl(
+    state: &State,
+    version_info: &mut VersionInfo,
+    profile: &Profile,
+    java_arch: &str,
+) -> crate::Result<Option<String>> {
+    match profile.lwjgl_version_for(java_arch) {
+        Some(version)
+            if lwjgl::apply_override(state, version_info, version).await? =>
+        {
+            Ok(Some(version.clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Removes the files of a profile's game and loader version that fail their
+/// hash check, for a following install to download again
+#[tracing::instrument(skip(profile))]
+#[theseus_macros::debug_pin]
+pub async fn remove_corrupt_files(
+    profile: &Profile,
+) -> crate::Result<Vec<std::path::PathBuf>> {
+    // The files are shared, so launches prepared for other profiles may
+    // have checked them too
+    prepare::forget_all();
+    let state = State::get().await?;
+    let version = state
+        .metadata
+        .read()
+        .await
+        .minecraft
+        .versions
+        .iter()
+        .find(|it| it.id == profile.metadata.game_version)
+        .cloned()
+        .ok_or_else(|| {
+            crate::ErrorKind::InvalidGameVersion(
+                profile.metadata.game_version.clone(),
+            )
+        })?;
+    let loader_version = profile.metadata.loader_version.as_ref();
+
+    // A version file that can't be read is fetched again
+    let mut version_info = match download::download_version_info(
+        &state,
+        &version,
+        loader_version,
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(info) => info,
+        Err(_) => {
+            download::download_version_info(
+                &state,
+                &version,
+                loader_version,
+                Some(true),
+                None,
+            )
+            .await?
+        }
+    };
+    let java_arch = get_java_version_from_profile(profile, &version_info)
+        .await?
+        .map(|x| x.architecture)
+        .unwrap_or_default();
+    override_lwjgl(&state, &mut version_info, profile, &java_arch).await?;
+    let game_dirs = state.directories.game_dirs(profile).await?;
+
+    // Recorded hashes are only of use for the version they were recorded for
+    let pinned = profile
+        .loader_pins
+        .as_ref()
+        .filter(|x| {
+            x.game_version == profile.metadata.game_version
+                && x.loader_version.as_ref() == loader_version.map(|x| &x.id)
+        })
+        .map(|x| &x.files);
+
+    download::remove_corrupt_files(
+        &state,
+        &game_dirs,
+        &version_info,
+        &java_arch,
+        profile.skips_assets(),
+        pinned,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(profile))]
 #[theseus_macros::debug_pin]
 pub async fn install_minecraft(
     profile: &Profile,
     existing_loading_bar: Option<LoadingBarId>,
+) -> crate::Result<()> {
+    // Files the last prepared launch checked are being replaced
+    prepare::forget(&profile.profile_id());
+    Journal::run(
+        &profile.profile_id(),
+        OperationKind::Install,
+        install_minecraft_files(profile, existing_loading_bar),
+    )
+    .await
+}
+
+#[theseus_macros::debug_pin]
+async fn install_minecraft_files(
+    profile: &Profile,
+    existing_loading_bar: Option<LoadingBarId>,
 ) -> crate::Result<()> {
     let loading_bar = init_or_edit_loading(
         existing_loading_bar,
@@ -123,6 +283,8 @@ pub async fn install_minecraft(
     .await?;
     State::sync().await?;
 
+    loader_cache::clean_if_changed(profile).await?;
+
     let state = State::get().await?;
     let instance_path =
         &io::canonicalize(&profile.get_profile_full_path().await?)?;
@@ -133,10 +295,11 @@ pub async fn install_minecraft(
         .versions
         .iter()
         .find(|it| it.id == profile.metadata.game_version)
-        .ok_or(crate::ErrorKind::LauncherError(format!(
-            "Invalid game version: {}",
-            profile.metadata.game_version
-        )))?;
+        .ok_or_else(|| {
+            crate::ErrorKind::InvalidGameVersion(
+                profile.metadata.game_version.clone(),
+            )
+        })?;
 
     let version_jar = profile
         .metadata
@@ -155,31 +318,37 @@ pub async fn install_minecraft(
         Some(&loading_bar),
     )
     .await?;
-
     let java_version = get_java_version_from_profile(profile, &version_info)
         .await?
-        .ok_or_else(|| {
-            crate::ErrorKind::OtherError(
-                "Missing correct java installation".to_string(),
-            )
+        .ok_or_else(|| crate::ErrorKind::JavaMissing {
+            profile_path: profile.profile_id().to_string(),
         })?;
 
     // Test jre version
     let java_version = jre::check_jre(java_version.path.clone().into())
         .await?
-        .ok_or_else(|| {
-            crate::ErrorKind::LauncherError(format!(
-                "Java path invalid or non-functional: {}",
-                java_version.path
-            ))
+        .ok_or_else(|| crate::ErrorKind::InvalidJava {
+            java_path: java_version.path.clone(),
         })?;
+    let lwjgl_override = override_lwjgl(
+        &state,
+        &mut version_info,
+        profile,
+        &java_version.architecture,
+    )
+    .await?;
+
+    let game_dirs = state.directories.game_dirs(profile).await?;
 
     // Download minecraft (5-90)
     download::download_minecraft(
         &state,
+        &game_dirs,
         &version_info,
         &loading_bar,
         &java_version.architecture,
+        &lwjgl::natives_version(&version_info.id, lwjgl_override.as_deref()),
+        profile.skips_assets(),
     )
     .await?;
 
@@ -190,7 +359,7 @@ pub async fn install_minecraft(
             .await
             .join(format!("{version_jar}.jar"));
 
-        let libraries_dir = state.directories.libraries_dir().await;
+        let libraries_dir = &game_dirs.libraries_dir;
 
         if let Some(ref mut data) = version_info.data {
             processor_rules! {
@@ -212,11 +381,16 @@ pub async fn install_minecraft(
                     server => "";
             }
 
-            emit_loading(&loading_bar, 0.0, Some("Running forge processors"))
-                .await?;
+            let loader = profile.metadata.loader;
+            emit_loading(
+                &loading_bar,
+                0.0,
+                Some(&format!("Running {loader} processors")),
+            )
+            .await?;
             let total_length = processors.len();
 
-            // Forge processors (90-100)
+            // Forge and NeoForge processors (90-100)
             for (index, processor) in processors.iter().enumerate() {
                 if let Some(sides) = &processor.sides {
                     if !sides.contains(&String::from("client")) {
@@ -224,6 +398,46 @@ pub async fn install_minecraft(
                     }
                 }
 
+                #[cfg(feature = "fault_injection")]
+                if let Some(code) =
+                    crate::util::fault::processor_exit(&processor.jar)
+                {
+                    return Err(crate::ErrorKind::ProcessorFailed {
+                        jar: processor.jar.clone(),
+                        exit_code: Some(code),
+                        output: format!("exited with code {code}"),
+                    }
+                    .as_error());
+                }
+
+                // Outputs of the same run for another profile are copied in
+                let cache_key =
+                    processors::cache_key(&version_info.id, processor, data);
+                if let Some(cache_key) = &cache_key {
+                    if processors::restore(&state, libraries_dir, cache_key)
+                        .await?
+                    {
+                        tracing::info!(
+                            "Reused cached outputs of processor {}",
+                            processor.jar
+                        );
+                        emit_loading(
+                            &loading_bar,
+                            30.0 / total_length as f64,
+                            Some(&format!(
+                                "Running {loader} processor {}/{}",
+                                index, total_length
+                            )),
+                        )
+                        .await?;
+                        continue;
+                    }
+                }
+                let outputs = processors::snapshot(
+                    libraries_dir,
+                    &processors::output_candidates(processor, data),
+                );
+
                 let cp = wrap_ref_builder!(cp = processor.classpath.clone() => {
                     cp.push(processor.jar.clone())
                 });
@@ -231,13 +445,13 @@ pub async fn install_minecraft(
                 let child = Command::new(&java_version.path)
                     .arg("-cp")
                     .arg(args::get_class_paths_jar(
-                        &libraries_dir,
+                        libraries_dir,
                         &cp,
                         &java_version.architecture,
                     )?)
                     .arg(
                         args::get_processor_main_class(args::get_lib_path(
-                            &libraries_dir,
+                            libraries_dir,
                             &processor.jar,
                             false,
                         )?)
@@ -250,7 +464,7 @@ pub async fn install_minecraft(
                         })?,
                     )
                     .args(args::get_processor_arguments(
-                        &libraries_dir,
+                        libraries_dir,
                         &processor.args,
                         data,
                     )?)
@@ -264,18 +478,35 @@ pub async fn install_minecraft(
                     })?;
 
                 if !child.status.success() {
-                    return Err(crate::ErrorKind::LauncherError(format!(
-                        "Processor error: {}",
-                        String::from_utf8_lossy(&child.stderr)
-                    ))
+                    return Err(crate::ErrorKind::ProcessorFailed {
+                        jar: processor.jar.clone(),
+                        exit_code: child.status.code(),
+                        output: String::from_utf8_lossy(&child.stderr)
+                            .to_string(),
+                    }
                     .as_error());
                 }
 
+                if let Some(cache_key) = &cache_key {
+                    if let Err(err) = processors::store(
+                        &state,
+                        libraries_dir,
+                        cache_key,
+                        &outputs,
+                    )
+                    .await
+                    {
+                        tracing::warn!(
+                            "Unable to cache processor outputs: {err}"
+                        );
+                    }
+                }
+
                 emit_loading(
                     &loading_bar,
                     30.0 / total_length as f64,
                     Some(&format!(
-                        "Running forge processor {}/{}",
+                        "Running {loader} processor {}/{}",
                         index, total_length
                     )),
                 )
@@ -284,8 +515,18 @@ pub async fn install_minecraft(
         }
     }
 
+    let files = pins::hash_loader_files(
+        &version_info,
+        &game_dirs.libraries_dir,
+        &java_version.architecture,
+    )
+    .await?;
+    pins::pin_loader_files(profile, files).await?;
+
+    let installed_loader = st::InstalledLoader::of(profile);
     crate::api::profile::edit(&profile.profile_id(), |prof| {
         prof.install_stage = ProfileInstallStage::Installed;
+        prof.installed_loader = Some(installed_loader.clone());
 
         async { Ok(()) }
     })
@@ -300,7 +541,7 @@ pub async fn install_minecraft(
 #[theseus_macros::debug_pin]
 #[allow(clippy::too_many_arguments)]
 pub async fn launch_minecraft(
-    java_args: &[String],
+    merged_java_args: &[args::JavaArg],
     env_args: &[(String, String)],
     mc_set_options: &[(String, String)],
     wrapper: &Option<String>,
@@ -313,135 +554,249 @@ pub async fn launch_minecraft(
     if profile.install_stage == ProfileInstallStage::PackInstalling
         || profile.install_stage == ProfileInstallStage::Installing
     {
-        return Err(crate::ErrorKind::LauncherError(
-            "Profile is still installing".to_string(),
-        )
+        return Err(crate::ErrorKind::ProfileInstalling {
+            profile_path: profile.profile_id().to_string(),
+        }
         .into());
     }
 
-    if profile.install_stage != ProfileInstallStage::Installed {
-        install_minecraft(profile, None).await?;
+    if profile.archived.is_some() {
+        return Err(crate::ErrorKind::ProfileArchived {
+            profile_path: profile.profile_id().to_string(),
+        }
+        .into());
     }
 
-    let state = State::get().await?;
-    let metadata = state.metadata.read().await;
+    // Overrides may have been edited into the profile by hand
+    let overrides = profile.launch_overrides.clone().unwrap_or_default();
+    overrides.validate()?;
+
+    let prepared = prepare::prepare_launch(profile).await?;
+    let version = &prepared.version;
+    let version_info = &prepared.version_info;
+    let java_version = &prepared.java;
+    let natives_version = &prepared.natives_version;
+    let assets_index_id = prepared.assets_index_id.as_str();
 
+    let state = State::get().await?;
     let instance_path = profile.get_profile_full_path().await?;
     let instance_path = &io::canonicalize(instance_path)?;
 
-    let version = metadata
-        .minecraft
-        .versions
-        .iter()
-        .find(|it| it.id == profile.metadata.game_version)
-        .ok_or(crate::ErrorKind::LauncherError(format!(
-            "Invalid game version: {}",
-            profile.metadata.game_version
-        )))?;
-
-    let version_jar = profile
-        .metadata
-        .loader_version
-        .as_ref()
-        .map_or(version.id.clone(), |it| {
-            format!("{}-{}", version.id.clone(), it.id.clone())
-        });
+    let game_dirs = state.directories.game_dirs(profile).await?;
+    let game_assets_dir =
+        download::game_assets_dir(&game_dirs, assets_index_id);
 
-    let version_info = download::download_version_info(
-        &state,
-        version,
-        profile.metadata.loader_version.as_ref(),
-        None,
-        None,
-    )
-    .await?;
-
-    let java_version = get_java_version_from_profile(profile, &version_info)
-        .await?
-        .ok_or_else(|| {
-            crate::ErrorKind::LauncherError(
-                "Missing correct java installation".to_string(),
-            )
-        })?;
+    let args = version_info.arguments.clone().unwrap_or_default();
+    let (program, mut launch_args) = match wrapper {
+        Some(hook) => (hook.clone(), vec![java_version.path.clone()]),
+        None => (java_version.path.clone(), Vec::new()),
+    };
+    // Headless smoke tests run the game, wrapper included, on a virtual
+    // display
+    let (program, mut launch_args) =
+        match crate::api::profile::smoke_test::headless_display(
+            &profile.profile_id(),
+        ) {
+            Some(display) => {
+                let mut args = vec!["--auto-servernum".to_string(), program];
+                args.append(&mut launch_args);
+                (display.to_string_lossy().to_string(), args)
+            }
+            None => (program, launch_args),
+        };
 
-    // Test jre version
-    let java_version = jre::check_jre(java_version.path.clone().into())
-        .await?
-        .ok_or_else(|| {
-            crate::ErrorKind::LauncherError(format!(
-                "Java path invalid or non-functional: {}",
-                java_version.path
-            ))
-        })?;
+    let sample_heap = memory.auto && memory.sample_heap;
+    let memory = if memory.auto {
+        let memory =
+            jre::get_recommended_memory(profile, jre::get_max_memory().await?);
+        tracing::info!(
+            "Using recommended memory of {}M for {}",
+            memory.maximum,
+            profile.profile_id()
+        );
+        memory
+    } else {
+        *memory
+    };
 
-    let client_path = state
-        .directories
-        .version_dir(&version_jar)
-        .await
-        .join(format!("{version_jar}.jar"));
+    let natives_dir = download::natives_dir(
+        &game_dirs,
+        natives_version,
+        &java_version.architecture,
+    );
+    let mut template = args::TemplateVariables::default();
+    template.insert_path("PROFILE_DIR", instance_path);
+    template.insert("PROFILE_NAME", profile.metadata.name.clone());
+    template.insert("MC_VERSION", version.id.clone());
+    template.insert("LOADER", profile.metadata.loader.as_api_str());
+    template.insert(
+        "LOADER_VERSION",
+        profile
+            .metadata
+            .loader_version
+            .as_ref()
+            .map(|x| x.id.clone())
+            .unwrap_or_default(),
+    );
+    template.insert_path("NATIVES_DIR", &natives_dir);
+    template.insert_path("LIBRARIES_DIR", &game_dirs.libraries_dir);
+    template.insert_path("ASSETS_DIR", &game_dirs.assets_dir);
+    template.insert("JAVA_PATH", java_version.path.clone());
 
-    let args = version_info.arguments.clone().unwrap_or_default();
-    let mut command = match wrapper {
-        Some(hook) => {
-            wrap_ref_builder!(it = Command::new(hook) => {it.arg(&java_version.path)})
+    // Custom environment variables come last, so they can override the ones
+    // picking the GPU and display server
+    let gpu_preference = gpu::apply_preference(
+        &profile.profile_id(),
+        profile.gpu_preference,
+        &java_version.path,
+    );
+    let mut launch_env = match gpu_preference.await {
+        Ok(env) => env,
+        Err(err) => {
+            tracing::warn!("Unable to apply GPU preference: {err}");
+            Vec::new()
         }
-        None => Command::new(&java_version.path),
     };
+    let display_server = display::apply_preference(
+        profile.display_server,
+        lwjgl::bundled_version(version_info).is_some(),
+    );
+    let display = match display_server.await {
+        Ok(display) => display,
+        Err(err) => {
+            tracing::warn!("Unable to apply display server: {err}");
+            display::DisplayOverrides::default()
+        }
+    };
+    launch_env.extend(display.env);
+    launch_env.extend(args::get_env_arguments(env_args, &template));
+    let env_args = launch_env;
 
-    let env_args = Vec::from(env_args);
+    // Accounts of third-party servers need the game's auth redirected
+    let authlib_injector = match credentials.backend.authlib_injector_server() {
+        Some(server) => Some((
+            download::download_authlib_injector(&state).await?,
+            server.to_string(),
+        )),
+        None => None,
+    };
+    let mut java_args = merged_java_args
+        .iter()
+        .flat_map(|x| x.args())
+        .cloned()
+        .collect::<Vec<_>>();
+    if version_info.arguments.is_none() {
+        java_args.splice(0..0, args::get_legacy_jvm_arguments(instance_path));
+    }
+    if let Some(network) = &profile.network {
+        java_args.extend(network.get_java_args());
+    }
+    if let Some(development) = &profile.development {
+        java_args.extend(development.get_java_args(profile.metadata.loader));
+    }
+    java_args.extend(display.java_args);
 
     // Check if profile has a running profile, and reject running the command if it does
     // Done late so a quick double call doesn't launch two instances
     let existing_processes =
         process::get_uuids_by_profile_path(profile.profile_id()).await?;
-    if let Some(uuid) = existing_processes.first() {
-        return Err(crate::ErrorKind::LauncherError(format!(
-            "Profile {} is already running at UUID: {uuid}",
-            profile.profile_id()
-        ))
+    if !existing_processes.is_empty() {
+        return Err(crate::ErrorKind::ProfileRunning {
+            profile_path: profile.profile_id().to_string(),
+        }
         .as_error());
     }
 
+    // Libraries extracting natives to the temp directory do so per session,
+    // so that they can be cleaned up once the game exits
+    let session_uuid = Uuid::new_v4();
+    let session_tmp_dir = session::create(
+        &state,
+        session_uuid,
+        profile.profile_id(),
+        credentials.id,
+    )
+    .await?;
+    java_args.push(format!("-Djava.io.tmpdir={}", session_tmp_dir.display()));
+    if sample_heap {
+        java_args.extend(heap::gc_log_args(
+            &java_version.version,
+            &heap::gc_log_path(&state, session_uuid),
+        ));
+    }
+
+    // A window size is always given, and quick play isn't supported yet
+    let features = FeatureContext {
+        has_custom_resolution: true,
+        ..Default::default()
+    };
+    launch_args.extend(args::get_jvm_arguments(
+        args.get(&d::minecraft::ArgumentType::Jvm)
+            .map(|x| x.as_slice()),
+        &natives_dir,
+        &game_dirs.libraries_dir,
+        &prepared.class_paths,
+        &prepared.version_jar,
+        memory,
+        java_args,
+        &template,
+        authlib_injector
+            .as_ref()
+            .map(|(path, server)| (path.as_path(), server.as_str())),
+        &java_version.architecture,
+        &features,
+    )?);
+    let main_class = overrides
+        .main_class
+        .clone()
+        .unwrap_or_else(|| version_info.main_class.clone());
+    launch_args.push(main_class.clone());
+    let game_args = args::get_minecraft_arguments(
+        args.get(&d::minecraft::ArgumentType::Game)
+            .map(|x| x.as_slice()),
+        version_info.minecraft_arguments.as_deref(),
+        credentials,
+        &version.id,
+        assets_index_id,
+        instance_path,
+        &game_dirs.assets_dir,
+        &game_assets_dir,
+        &version.type_,
+        *resolution,
+        &java_version.architecture,
+        &features,
+        state.settings.read().await.hide_launch_identifiers,
+    )?;
+    launch_args.extend(args::remove_game_arguments(
+        game_args,
+        &overrides.removed_game_args,
+    ));
+
+    let plan = LaunchPlan {
+        profile_path: profile.profile_id(),
+        uuid: session_uuid,
+        program,
+        args: launch_args,
+        env: env_args,
+        env_remove: display.env_remove,
+        working_dir: instance_path.clone(),
+        lwjgl_override: prepared.lwjgl_override.clone(),
+        java_args: merged_java_args.to_vec(),
+        main_class,
+        removed_game_args: overrides.removed_game_args,
+    };
+    #[cfg(feature = "launch_middleware")]
+    let plan = {
+        let mut plan = plan;
+        let middlewares = state.launch_middlewares.read().await;
+        middlewares.before_launch(&mut plan).await?;
+        plan
+    };
+
+    let mut command = Command::new(&plan.program);
     command
-        .args(
-            args::get_jvm_arguments(
-                args.get(&d::minecraft::ArgumentType::Jvm)
-                    .map(|x| x.as_slice()),
-                &state.directories.version_natives_dir(&version_jar).await,
-                &state.directories.libraries_dir().await,
-                &args::get_class_paths(
-                    &state.directories.libraries_dir().await,
-                    version_info.libraries.as_slice(),
-                    &client_path,
-                    &java_version.architecture,
-                )?,
-                &version_jar,
-                *memory,
-                Vec::from(java_args),
-                &java_version.architecture,
-            )?
-            .into_iter()
-            .collect::<Vec<_>>(),
-        )
-        .arg(version_info.main_class.clone())
-        .args(
-            args::get_minecraft_arguments(
-                args.get(&d::minecraft::ArgumentType::Game)
-                    .map(|x| x.as_slice()),
-                version_info.minecraft_arguments.as_deref(),
-                credentials,
-                &version.id,
-                &version_info.asset_index.id,
-                instance_path,
-                &state.directories.assets_dir().await,
-                &version.type_,
-                *resolution,
-                &java_version.architecture,
-            )?
-            .into_iter()
-            .collect::<Vec<_>>(),
-        )
-        .current_dir(instance_path.clone())
+        .args(&plan.args)
+        .current_dir(&plan.working_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
@@ -450,99 +805,89 @@ pub async fn launch_minecraft(
     if std::env::var("CARGO").is_ok() {
         command.env_remove("DYLD_FALLBACK_LIBRARY_PATH");
     }
-    command.envs(env_args);
-
-    // Overwrites the minecraft options.txt file with the settings from the profile
-    // Uses 'a:b' syntax which is not quite yaml
-    use regex::Regex;
-
-    let options_path = instance_path.join("options.txt");
-    let mut options_string = String::new();
-
-    if options_path.exists() {
-        options_string = io::read_to_string(&options_path).await?;
+    for key in &plan.env_remove {
+        command.env_remove(key);
     }
+    command.envs(plan.env.iter().cloned());
 
-    for (key, value) in mc_set_options {
-        let re = Regex::new(&format!(r"(?m)^{}:.*$", regex::escape(key)))?;
-        // check if the regex exists in the file
-        if !re.is_match(&options_string) {
-            // The key was not found in the file, so append it
-            options_string.push_str(&format!("\n{}:{}", key, value));
-        } else {
-            let replaced_string = re
-                .replace_all(&options_string, &format!("{}:{}", key, value))
-                .to_string();
-            options_string = replaced_string;
-        }
+    // Written in one step before the game starts, so it never reads a half
+    // written file
+    let forced_options = crate::state::game_options::apply_options(
+        instance_path,
+        mc_set_options,
+    )
+    .await?;
+    if profile
+        .game_options
+        .as_ref()
+        .map_or(false, |x| x.restore_after_exit)
+    {
+        session::set_forced_options(&state, session_uuid, forced_options)
+            .await?;
     }
 
-    io::write(&options_path, options_string).await?;
-
     // Get Modrinth logs directories
-    let datetime_string =
-        chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
     let logs_dir = {
         let st = State::get().await?;
         st.directories
             .profile_logs_dir(&profile.profile_id())
             .await?
-            .join(&datetime_string)
+            .join(Children::log_dir_name(session_uuid))
     };
     io::create_dir_all(&logs_dir).await?;
 
     let stdout_log_path = logs_dir.join("stdout.log");
 
-    crate::api::profile::edit(&profile.profile_id(), |prof| {
-        prof.metadata.last_played = Some(Utc::now());
+    // Smoke tests launch unattended, so the game isn't played and the
+    // launcher's window stays where it is
+    let smoke_test =
+        crate::api::profile::smoke_test::is_testing(&profile.profile_id());
+    if !smoke_test {
+        crate::api::profile::edit(&profile.profile_id(), |prof| {
+            prof.metadata.last_played = Some(Utc::now());
 
-        async { Ok(()) }
-    })
-    .await?;
-    State::sync().await?;
+            async { Ok(()) }
+        })
+        .await?;
+        State::sync().await?;
+    }
 
-    let mut censor_strings = HashMap::new();
-    let username = whoami::username();
-    censor_strings.insert(
-        format!("/{}/", username),
-        "/{COMPUTER_USERNAME}/".to_string(),
-    );
-    censor_strings.insert(
-        format!("\\{}\\", username),
-        "\\{COMPUTER_USERNAME}\\".to_string(),
-    );
-    censor_strings.insert(
-        credentials.access_token.clone(),
-        "{MINECRAFT_ACCESS_TOKEN}".to_string(),
-    );
-    censor_strings.insert(
-        credentials.username.clone(),
-        "{MINECRAFT_USERNAME}".to_string(),
-    );
-    censor_strings.insert(
-        credentials.id.as_simple().to_string(),
-        "{MINECRAFT_UUID}".to_string(),
-    );
-    censor_strings.insert(
+    let mut censor = Censor::new(&state.settings.read().await.log_censoring);
+    censor
+        .literal(credentials.access_token.clone(), "{MINECRAFT_ACCESS_TOKEN}");
+    censor.literal(credentials.username.clone(), "{MINECRAFT_USERNAME}");
+    censor.literal(credentials.id.as_simple().to_string(), "{MINECRAFT_UUID}");
+    censor.literal(
         credentials.id.as_hyphenated().to_string(),
-        "{MINECRAFT_UUID}".to_string(),
+        "{MINECRAFT_UUID}",
     );
+    if let Some(xuid) = &credentials.xuid {
+        censor.literal(xuid.clone(), "{MINECRAFT_XUID}");
+    }
 
-    // If in tauri, and the 'minimize on launch' setting is enabled, minimize the window
-    #[cfg(feature = "tauri")]
-    {
-        use crate::EventState;
+    let report = session::SessionReport::build(
+        &state,
+        &plan,
+        profile,
+        java_version,
+        memory,
+        *resolution,
+        mc_set_options,
+        &censor,
+    )
+    .await;
+    if let Err(err) = report.write(&state, &logs_dir).await {
+        tracing::warn!("Unable to write the session report: {err}");
+    }
 
-        let window = EventState::get_main_window().await?;
-        if let Some(window) = window {
-            let settings = state.settings.read().await;
-            if settings.hide_on_process {
-                window.minimize()?;
-            }
-        }
+    // If in tauri, get the window out of the way as the post-launch behavior says
+    #[cfg(feature = "tauri")]
+    if !smoke_test {
+        let policy = state.settings.read().await.post_launch_policy();
+        crate::EventState::apply_window_action(policy.on_launch).await?;
     }
 
-    if !*state.offline.read().await {
+    if !smoke_test && !*state.offline.read().await {
         // Add game played to discord rich presence
         let _ = state
             .discord_rpc
@@ -550,17 +895,186 @@ pub async fn launch_minecraft(
             .await;
     }
 
+    let redirects = profile
+        .network
+        .as_ref()
+        .map(|x| x.server_redirects.as_slice())
+        .unwrap_or_default();
+    #[cfg(feature = "server_proxy")]
+    let proxy_guard =
+        crate::util::proxy::start_redirects(redirects).await?;
+    #[cfg(not(feature = "server_proxy"))]
+    if !redirects.is_empty() {
+        tracing::warn!(
+            "Server redirects are not supported in this build, ignoring them"
+        );
+    }
+
     // Create Minecraft child by inserting it into the state
     // This also spawns the process and prepares the subsequent processes
     let mut state_children = state.children.write().await;
-    state_children
+    let mchild = state_children
         .insert_process(
-            Uuid::new_v4(),
+            session_uuid,
             profile.profile_id(),
-            stdout_log_path,
+            credentials.id,
+            stdout_log_path.clone(),
             command,
             post_exit_hook,
-            censor_strings,
+            censor,
         )
-        .await
+        .await?;
+    drop(state_children);
+    let pid = mchild.read().await.current_child.read().await.id();
+    if let Some(pid) = pid {
+        if let Err(err) =
+            session::set_process(&state, session_uuid, pid, &stdout_log_path)
+                .await
+        {
+            tracing::warn!("Unable to record session process: {err}");
+        }
+    }
+
+    // Redirects are torn down once the game process exits
+    #[cfg(feature = "server_proxy")]
+    if !redirects.is_empty() {
+        let current_child = mchild.read().await.current_child.clone();
+        tokio::task::spawn(async move {
+            let _proxy_guard = proxy_guard;
+            loop {
+                if !matches!(current_child.write().await.try_wait(), Ok(None))
+                {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+        });
+    }
+
+    #[cfg(feature = "launch_middleware")]
+    if let Some(pid) = pid {
+        let middlewares = state.launch_middlewares.read().await;
+        middlewares.launched(&plan, pid).await;
+    }
+
+    Ok(mchild)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rule, FeatureContext};
+    use daedalus::minecraft::Rule;
+
+    // Whether a list of rules, as found on arguments and libraries in
+    // version manifests, lets its value through
+    fn allowed(
+        rules: &str,
+        java_arch: &str,
+        features: &FeatureContext,
+    ) -> bool {
+        serde_json::from_str::<Vec<Rule>>(rules)
+            .unwrap()
+            .iter()
+            .any(|x| parse_rule(x, java_arch, features))
+    }
+
+    #[test]
+    fn os_rules() {
+        let os = std::env::consts::OS;
+        let arch = std::env::consts::ARCH;
+        let cases = [
+            // -XstartOnFirstThread of 1.19 and later
+            (
+                r#"[{"action": "allow", "os": {"name": "osx"}}]"#,
+                os == "macos",
+            ),
+            // -XX:HeapDumpPath of the same manifests
+            (
+                r#"[{"action": "allow", "os": {"name": "windows"}}]"#,
+                os == "windows",
+            ),
+            (
+                r#"[{"action": "allow", "os": {"name": "linux"}}]"#,
+                os == "linux",
+            ),
+            // LWJGL 2 libraries, replaced by another version on macOS
+            (
+                r#"[{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]"#,
+                os != "macos",
+            ),
+        ];
+        for (rules, expected) in cases {
+            assert_eq!(
+                allowed(rules, arch, &FeatureContext::default()),
+                expected,
+                "{rules}"
+            );
+        }
+    }
+
+    #[test]
+    fn arch_rules() {
+        // -Xss1M, only given to 32-bit x86 Java
+        let rules = r#"[{"action": "allow", "os": {"arch": "x86"}}]"#;
+        let cases = [
+            ("x86", true),
+            ("i686", true),
+            ("amd64", false),
+            ("x86_64", false),
+            ("aarch64", false),
+        ];
+        for (java_arch, expected) in cases {
+            assert_eq!(
+                allowed(rules, java_arch, &FeatureContext::default()),
+                expected,
+                "{java_arch}"
+            );
+        }
+    }
+
+    #[test]
+    fn feature_rules() {
+        let none = FeatureContext::default();
+        let demo = FeatureContext {
+            is_demo_user: true,
+            ..Default::default()
+        };
+        let resolution = FeatureContext {
+            has_custom_resolution: true,
+            ..Default::default()
+        };
+        let quick_play = FeatureContext {
+            has_quick_plays_support: true,
+            is_quick_play_singleplayer: true,
+            ..Default::default()
+        };
+
+        let demo_rules =
+            r#"[{"action": "allow", "features": {"is_demo_user": true}}]"#;
+        let resolution_rules = r#"[{"action": "allow", "features": {"has_custom_resolution": true}}]"#;
+        let singleplayer_rules = r#"[{"action": "allow", "features": {"is_quick_play_singleplayer": true}}]"#;
+        // A feature added to the manifests after this launcher
+        let unknown_rules =
+            r#"[{"action": "allow", "features": {"is_future_feature": true}}]"#;
+
+        let cases = [
+            (demo_rules, none, false),
+            (demo_rules, demo, true),
+            (demo_rules, resolution, false),
+            (resolution_rules, none, false),
+            (resolution_rules, resolution, true),
+            (resolution_rules, demo, false),
+            (singleplayer_rules, none, false),
+            (singleplayer_rules, quick_play, true),
+            (unknown_rules, none, false),
+            (unknown_rules, resolution, false),
+        ];
+        for (rules, features, expected) in cases {
+            assert_eq!(
+                allowed(rules, std::env::consts::ARCH, &features),
+                expected,
+                "{rules} with {features:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,170 @@
+//! Hashes of the files a loader installs, so a profile can be reinstalled
+//! bit for bit
+use super::FeatureContext;
+use crate::event::emit::emit_warning;
+use crate::state::{LoaderPins, Profile, ProfileInstallStage};
+use crate::util::{fetch::sha1_async, io};
+use crate::State;
+use chrono::Utc;
+use daedalus as d;
+use daedalus::minecraft::VersionInfo;
+use daedalus::modded::SidedDataEntry;
+use std::collections::HashMap;
+use std::path::Path;
+
+// Most changed files listed in the error of a reproducible reinstall
+const MAX_LISTED: usize = 5;
+
+/// Hashes the libraries and processor outputs of an installed version, by
+/// path relative to the libraries folder
+pub(super) async fn hash_loader_files(
+    version_info: &VersionInfo,
+    libraries_dir: &Path,
+    java_arch: &str,
+) -> crate::Result<HashMap<String, String>> {
+    let mut paths = Vec::new();
+    for library in &version_info.libraries {
+        if let Some(rules) = &library.rules {
+            if !rules.iter().any(|x| {
+                super::parse_rule(x, java_arch, &FeatureContext::default())
+            }) {
+                continue;
+            }
+        }
+        paths.push(d::get_path_from_artifact(&library.name)?);
+    }
+
+    if let (Some(processors), Some(data)) =
+        (&version_info.processors, &version_info.data)
+    {
+        for processor in processors {
+            paths.extend(
+                processor
+                    .outputs
+                    .iter()
+                    .flat_map(|x| x.keys())
+                    .filter_map(|x| output_path(x, data)),
+            );
+        }
+    }
+
+    let mut hashes = HashMap::new();
+    for path in paths {
+        let full_path = libraries_dir.join(&path);
+        // Libraries without a download for this platform, and outputs of
+        // processors for the server
+        if !full_path.exists() {
+            continue;
+        }
+        let bytes = io::read(&full_path).await?;
+        hashes.insert(path, sha1_async(bytes.into()).await?);
+    }
+
+    Ok(hashes)
+}
+
+/// Records the hashes of an install. Reproducible profiles instead have
+/// them checked against the recorded ones, failing if anything changed
+/// since, unless the game or loader version was changed on purpose
+pub(super) async fn pin_loader_files(
+    profile: &Profile,
+    files: HashMap<String, String>,
+) -> crate::Result<()> {
+    let loader_version = profile
+        .metadata
+        .loader_version
+        .as_ref()
+        .map(|x| x.id.clone());
+
+    let reproducible = match &profile.loader_pins {
+        Some(pins)
+            if pins.reproducible
+                && pins.game_version == profile.metadata.game_version
+                && pins.loader_version == loader_version =>
+        {
+            let drift = drifted_files(&pins.files, &files);
+            if drift.is_empty() {
+                return Ok(());
+            }
+
+            let mut listed = drift
+                .iter()
+                .take(MAX_LISTED)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if drift.len() > MAX_LISTED {
+                listed.push_str(&format!(
+                    " and {} more",
+                    drift.len() - MAX_LISTED
+                ));
+            }
+            let message = format!(
+                "{} installed loader files that differ from the ones \
+                 recorded for {}: {listed}",
+                drift.len(),
+                profile.metadata.name
+            );
+            emit_warning(&message).await?;
+
+            // Left to be installed, so it isn't launched with these files
+            crate::api::profile::edit(&profile.profile_id(), |prof| {
+                prof.install_stage = ProfileInstallStage::NotInstalled;
+                async { Ok(()) }
+            })
+            .await?;
+            State::sync().await?;
+            return Err(crate::ErrorKind::LauncherError(message).as_error());
+        }
+        Some(pins) => pins.reproducible,
+        None => false,
+    };
+
+    let pins = LoaderPins {
+        reproducible,
+        game_version: profile.metadata.game_version.clone(),
+        loader_version,
+        recorded: Utc::now(),
+        files,
+    };
+    crate::api::profile::edit(&profile.profile_id(), |prof| {
+        prof.loader_pins = Some(pins.clone());
+        async { Ok(()) }
+    })
+    .await
+}
+
+// Files that were added, removed or changed, sorted
+fn drifted_files(
+    recorded: &HashMap<String, String>,
+    installed: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut drift = recorded
+        .iter()
+        .filter(|(path, hash)| installed.get(*path) != Some(hash))
+        .map(|(path, _)| path.clone())
+        .chain(
+            installed
+                .keys()
+                .filter(|x| !recorded.contains_key(*x))
+                .cloned(),
+        )
+        .collect::<Vec<_>>();
+    drift.sort();
+    drift
+}
+
+// Path of a processor output given as a maven coordinate, directly or
+// through a data entry
+pub(super) fn output_path(
+    output: &str,
+    data: &HashMap<String, SidedDataEntry>,
+) -> Option<String> {
+    let output =
+        match output.strip_prefix('{').and_then(|x| x.strip_suffix('}')) {
+            Some(name) => &data.get(name)?.client,
+            None => output,
+        };
+    let artifact = output.strip_prefix('[')?.strip_suffix(']')?;
+    d::get_path_from_artifact(artifact).ok()
+}
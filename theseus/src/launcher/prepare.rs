@@ -0,0 +1,229 @@
+//! Work done ahead of a launch, so that starting the game right after is
+//! quick even on a profile that was just installed
+use super::{
+    args, download, get_java_version_from_profile, install_minecraft, lwjgl,
+    override_lwjgl,
+};
+use crate::jre;
+use crate::prelude::JavaVersion;
+use crate::state::{Profile, ProfileInstallStage, ProfilePathId};
+use crate::util::io;
+use crate::State;
+use chrono::{DateTime, Duration, Utc};
+use daedalus::minecraft::{Version, VersionInfo};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Files a prepared launch checked could be removed from under it, so it is
+// only trusted for so long
+const PREPARED_FOR_MINUTES: i64 = 15;
+
+lazy_static! {
+    static ref PREPARED: Mutex<HashMap<ProfilePathId, Arc<PreparedLaunch>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// What a launch of a profile resolves from the network and disk before the
+/// game can start. Launches reuse it while the profile's versions, Java and
+/// LWJGL stay the same
+#[derive(Debug)]
+pub struct PreparedLaunch {
+    pub prepared: DateTime<Utc>,
+    pub version: Version,
+    /// Version of the game and loader, with the LWJGL override applied
+    pub version_info: VersionInfo,
+    /// Name of the client jar and its version folder
+    pub version_jar: String,
+    /// Java runtime, as checked to be working
+    pub java: JavaVersion,
+    /// Path of the Java runtime before it was checked, as the profile or
+    /// settings pick it
+    java_path: String,
+    loader_version: Option<String>,
+    /// LWJGL version the profile asked for, which is only overridden when
+    /// the game doesn't already ship it
+    lwjgl_version: Option<String>,
+    pub lwjgl_override: Option<String>,
+    pub natives_version: String,
+    pub assets_index_id: String,
+    pub class_paths: String,
+}
+
+impl PreparedLaunch {
+    // Whether the launch was prepared for the profile as it is now
+    async fn applies_to(&self, profile: &Profile) -> crate::Result<bool> {
+        let loader_version = profile
+            .metadata
+            .loader_version
+            .as_ref()
+            .map(|x| x.id.as_str());
+        if profile.install_stage != ProfileInstallStage::Installed
+            || Utc::now() - self.prepared
+                > Duration::minutes(PREPARED_FOR_MINUTES)
+            || self.version.id != profile.metadata.game_version
+            || self.loader_version.as_deref() != loader_version
+            || self.lwjgl_version.as_ref()
+                != profile.lwjgl_version_for(&self.java.architecture)
+        {
+            return Ok(false);
+        }
+
+        // Java settings may have changed, which takes no checking to see
+        let java =
+            get_java_version_from_profile(profile, &self.version_info).await?;
+        Ok(java.map_or(false, |x| x.path == self.java_path))
+    }
+}
+
+/// Resolves everything the launch of a profile needs ahead of it, installing
+/// the profile if it isn't yet. The launch prepared before is returned if it
+/// still applies
+#[tracing::instrument(skip(profile))]
+#[theseus_macros::debug_pin]
+pub async fn prepare_launch(
+    profile: &Profile,
+) -> crate::Result<Arc<PreparedLaunch>> {
+    let profile_path = profile.profile_id();
+    let previous = PREPARED.lock().unwrap().get(&profile_path).cloned();
+    if let Some(previous) = previous {
+        if previous.applies_to(profile).await? {
+            return Ok(previous);
+        }
+    }
+
+    let prepared = Arc::new(resolve(profile).await?);
+    PREPARED
+        .lock()
+        .unwrap()
+        .insert(profile_path, prepared.clone());
+    Ok(prepared)
+}
+
+/// Drops the launch prepared for a profile, for its files to be checked
+/// again on the next launch
+pub fn forget(profile_path: &ProfilePathId) {
+    PREPARED.lock().unwrap().remove(profile_path);
+}
+
+/// Drops every prepared launch, after shared files they checked may have
+/// been removed or replaced
+pub fn forget_all() {
+    PREPARED.lock().unwrap().clear();
+}
+
+async fn resolve(profile: &Profile) -> crate::Result<PreparedLaunch> {
+    if profile.install_stage != ProfileInstallStage::Installed {
+        install_minecraft(profile, None).await?;
+    }
+
+    let state = State::get().await?;
+    let metadata = state.metadata.read().await;
+
+    let instance_path = profile.get_profile_full_path().await?;
+    let instance_path = &io::canonicalize(instance_path)?;
+
+    let version = metadata
+        .minecraft
+        .versions
+        .iter()
+        .find(|it| it.id == profile.metadata.game_version)
+        .ok_or_else(|| {
+            crate::ErrorKind::InvalidGameVersion(
+                profile.metadata.game_version.clone(),
+            )
+        })?;
+
+    let version_jar = profile
+        .metadata
+        .loader_version
+        .as_ref()
+        .map_or(version.id.clone(), |it| {
+            format!("{}-{}", version.id.clone(), it.id.clone())
+        });
+
+    let mut version_info = download::download_version_info(
+        &state,
+        version,
+        profile.metadata.loader_version.as_ref(),
+        None,
+        None,
+    )
+    .await?;
+    let java_path = get_java_version_from_profile(profile, &version_info)
+        .await?
+        .ok_or_else(|| crate::ErrorKind::JavaMissing {
+            profile_path: profile.profile_id().to_string(),
+        })?
+        .path;
+
+    // Test jre version
+    let java =
+        jre::check_jre(java_path.clone().into())
+            .await?
+            .ok_or_else(|| crate::ErrorKind::InvalidJava {
+                java_path: java_path.clone(),
+            })?;
+    let lwjgl_override =
+        override_lwjgl(&state, &mut version_info, profile, &java.architecture)
+            .await?;
+    let natives_version =
+        lwjgl::natives_version(&version_jar, lwjgl_override.as_deref());
+
+    let client_path = state
+        .directories
+        .version_dir(&version_jar)
+        .await
+        .join(format!("{version_jar}.jar"));
+
+    let game_dirs = state.directories.game_dirs(profile).await?;
+    download::verify_natives(
+        &state,
+        &game_dirs,
+        &version_info.libraries,
+        &natives_version,
+        &java.architecture,
+    )
+    .await?;
+
+    // Profiles testing server-side mods can start without any assets
+    let assets_index_id = if profile.skips_assets() {
+        download::write_empty_assets_index(&state, &game_dirs).await?;
+        download::EMPTY_ASSETS_INDEX.to_string()
+    } else {
+        download::link_legacy_assets(
+            &state,
+            &game_dirs,
+            &version_info,
+            instance_path,
+        )
+        .await?;
+        version_info.asset_index.id.clone()
+    };
+
+    let class_paths = args::get_class_paths(
+        &game_dirs.libraries_dir,
+        version_info.libraries.as_slice(),
+        &client_path,
+        &java.architecture,
+    )?;
+
+    Ok(PreparedLaunch {
+        prepared: Utc::now(),
+        version: version.clone(),
+        version_info,
+        version_jar,
+        java_path,
+        loader_version: profile
+            .metadata
+            .loader_version
+            .as_ref()
+            .map(|x| x.id.clone()),
+        lwjgl_version: profile.lwjgl_version_for(&java.architecture).cloned(),
+        java,
+        lwjgl_override,
+        natives_version,
+        assets_index_id,
+        class_paths,
+    })
+}
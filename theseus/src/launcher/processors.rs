@@ -0,0 +1,233 @@
+//! Shared cache of the files Forge and NeoForge processors produce. Every
+//! profile of the same game and loader version runs the same processors on
+//! the same inputs, so the outputs of a run are recorded where they were
+//! written and linked in instead of running them again
+use super::pins::output_path;
+use crate::util::fetch::{link_or_copy, read_json, sha1_async, write};
+use crate::util::io::{self, IOError};
+use crate::State;
+use daedalus::modded::{Processor, SidedDataEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const PROCESSORS_CACHE_FOLDER: &str = "processors";
+
+// Data entries set for each profile, which don't change what processors
+// produce in the libraries folder
+const PROFILE_DATA: [&str; 4] =
+    ["SIDE", "MINECRAFT_JAR", "ROOT", "LIBRARY_DIR"];
+
+// Outputs of a processor run, by path relative to the libraries folder they
+// were written to, with their hashes
+#[derive(Serialize, Deserialize, Debug)]
+struct ProcessorRun {
+    libraries_dir: PathBuf,
+    outputs: HashMap<String, String>,
+}
+
+/// Identifies a run of a processor by the loader version, the processor and
+/// its arguments. `None` for processors writing into the profile, whose
+/// outputs can't be shared
+pub(super) fn cache_key(
+    version_id: &str,
+    processor: &Processor,
+    data: &HashMap<String, SidedDataEntry>,
+) -> Option<String> {
+    let mut parts = vec![
+        version_id.to_string(),
+        processor.jar.clone(),
+        processor.classpath.join(";"),
+    ];
+    for arg in &processor.args {
+        parts.push(arg.clone());
+        if let Some(name) = data_name(arg) {
+            if name == "ROOT" {
+                return None;
+            }
+            if !PROFILE_DATA.contains(&name) {
+                if let Some(entry) = data.get(name) {
+                    parts.push(entry.client.clone());
+                }
+            }
+        }
+    }
+
+    Some(sha1::Sha1::from(parts.join("\n")).hexdigest())
+}
+
+/// Library paths a processor may write to: its declared outputs and the
+/// artifacts passed to it
+pub(super) fn output_candidates(
+    processor: &Processor,
+    data: &HashMap<String, SidedDataEntry>,
+) -> Vec<String> {
+    let mut candidates = processor
+        .outputs
+        .iter()
+        .flat_map(|x| x.keys())
+        .chain(processor.args.iter())
+        .filter_map(|x| output_path(x, data))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// When each candidate output was last modified, to tell which ones a run
+/// wrote to
+pub(super) fn snapshot(
+    libraries_dir: &Path,
+    candidates: &[String],
+) -> HashMap<String, Option<SystemTime>> {
+    candidates
+        .iter()
+        .map(|path| {
+            let modified = std::fs::metadata(libraries_dir.join(path))
+                .and_then(|x| x.modified())
+                .ok();
+            (path.clone(), modified)
+        })
+        .collect()
+}
+
+/// Puts the outputs of a cached processor run into the libraries folder,
+/// returning whether there were any. Outputs another libraries folder holds
+/// are linked from it. Runs whose outputs are gone or changed are dropped,
+/// for the processor to run again
+#[tracing::instrument(skip(st))]
+pub(super) async fn restore(
+    st: &State,
+    libraries_dir: &Path,
+    key: &str,
+) -> crate::Result<bool> {
+    let run_file = run_file(st, key);
+    let Ok(run) = read_json::<ProcessorRun>(&run_file, &st.io_semaphore).await
+    else {
+        return Ok(false);
+    };
+
+    for (path, sha1) in &run.outputs {
+        // Profiles sharing the libraries folder already have them
+        let dest = libraries_dir.join(path);
+        if is_intact(&dest, sha1).await? {
+            continue;
+        }
+
+        let source = run.libraries_dir.join(path);
+        if source == dest || !is_intact(&source, sha1).await? {
+            tracing::warn!("Cached processor output {path} is gone or changed");
+            io::remove_file(&run_file).await?;
+            return Ok(false);
+        }
+        link_or_copy(&source, &dest, &st.io_semaphore).await?;
+    }
+
+    Ok(true)
+}
+
+/// Records the outputs of a processor run, along with the libraries folder
+/// they were written to
+#[tracing::instrument(skip(st, before))]
+pub(super) async fn store(
+    st: &State,
+    libraries_dir: &Path,
+    key: &str,
+    before: &HashMap<String, Option<SystemTime>>,
+) -> crate::Result<()> {
+    let written =
+        snapshot(libraries_dir, &before.keys().cloned().collect::<Vec<_>>())
+            .into_iter()
+            .filter(|(path, modified)| {
+                modified.is_some() && before.get(path) != Some(modified)
+            })
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+    // Nothing is known of what the processor did, so it runs every time
+    if written.is_empty() {
+        return Ok(());
+    }
+
+    let mut run = ProcessorRun {
+        libraries_dir: libraries_dir.to_path_buf(),
+        outputs: HashMap::new(),
+    };
+    for path in written {
+        let source = libraries_dir.join(&path);
+        let sha1 = sha1_async(io::read(&source).await?.into()).await?;
+        run.outputs.insert(path, sha1);
+    }
+    write(
+        &run_file(st, key),
+        &serde_json::to_vec(&run)?,
+        &st.io_semaphore,
+    )
+    .await?;
+    tracing::debug!("Cached {} processor outputs", run.outputs.len());
+    Ok(())
+}
+
+/// Cached runs whose outputs are gone from the libraries folder they were
+/// recorded in, or about to be removed, along with the number of runs
+/// checked
+pub(crate) async fn stale_runs(
+    st: &State,
+    removed: &HashSet<PathBuf>,
+) -> crate::Result<(usize, Vec<PathBuf>)> {
+    let cache_dir = st.directories.caches_dir().join(PROCESSORS_CACHE_FOLDER);
+    let mut checked = 0;
+    let mut stale = Vec::new();
+    if !cache_dir.exists() {
+        return Ok((checked, stale));
+    }
+
+    let mut read_dir = io::read_dir(&cache_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &cache_dir))?
+    {
+        let path = entry.path();
+        checked += 1;
+        // Runs used to be cached as folders of copies of their outputs
+        let run = if path.is_dir() {
+            None
+        } else {
+            read_json::<ProcessorRun>(&path, &st.io_semaphore)
+                .await
+                .ok()
+        };
+        let gone = run.map_or(true, |run| {
+            run.outputs.keys().any(|x| {
+                let output = run.libraries_dir.join(x);
+                !output.exists() || removed.contains(&output)
+            })
+        });
+        if gone {
+            stale.push(path);
+        }
+    }
+
+    Ok((checked, stale))
+}
+
+// Whether a file exists with the given hash
+async fn is_intact(path: &Path, sha1: &str) -> crate::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    Ok(sha1_async(io::read(path).await?.into()).await? == sha1)
+}
+
+fn run_file(st: &State, key: &str) -> PathBuf {
+    st.directories
+        .caches_dir()
+        .join(PROCESSORS_CACHE_FOLDER)
+        .join(format!("{key}.json"))
+}
+
+// Name of the data entry an argument refers to, as in `{MAPPINGS}`
+fn data_name(arg: &str) -> Option<&str> {
+    arg.strip_prefix('{')?.strip_suffix('}')
+}
@@ -0,0 +1,435 @@
+//! Temporary files of a single game session
+use super::args::JavaArg;
+use crate::prelude::{JavaVersion, ProfilePathId};
+use crate::state::game_options::{restore_options, ForcedOption};
+use crate::state::{
+    redact_env, LaunchPlan, MemorySettings, ModLoader, Profile, WindowSize,
+};
+use crate::util::censor::Censor;
+use crate::util::fetch::{read_json, write};
+use crate::util::io;
+use crate::util::platform::{is_process_running, process_start_time};
+use crate::State;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub(crate) const SESSION_FILE: &str = "session.json";
+/// Report of how the game was launched, in the log directory of a session
+pub(crate) const REPORT_FILE: &str = "session.json";
+const TMP_FOLDER: &str = "tmp";
+
+/// What a session directory belongs to, so it can be told apart from the
+/// sessions of games that are still running
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SessionRecord {
+    pub profile_path: ProfilePathId,
+    /// Unset until the game process has been spawned
+    pub pid: Option<u32>,
+    /// Start time of the game process as reported by the OS, so a process
+    /// that later reuses the PID is not mistaken for the game
+    #[serde(default)]
+    pub process_started: Option<String>,
+    /// File the output of the game is written to
+    #[serde(default)]
+    pub log_path: Option<PathBuf>,
+    pub started: DateTime<Utc>,
+    /// Account the game was launched with, to launch it again
+    #[serde(default)]
+    pub account: Option<Uuid>,
+    /// Options.txt settings forced for the session, put back once it ends
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forced_options: Vec<ForcedOption>,
+}
+
+impl SessionRecord {
+    /// Whether the game process of the session is still running
+    pub fn is_running(&self) -> bool {
+        let Some(pid) = self.pid else {
+            return false;
+        };
+        if !is_process_running(pid) {
+            return false;
+        }
+
+        match &self.process_started {
+            Some(started) => process_start_time(pid).as_ref() == Some(started),
+            None => true,
+        }
+    }
+}
+
+/// Creates the directory of a new session, returning the temp directory the
+/// game should use instead of the system one
+pub(crate) async fn create(
+    state: &State,
+    uuid: Uuid,
+    profile_path: ProfilePathId,
+    account: Uuid,
+) -> crate::Result<PathBuf> {
+    let session_dir = state.directories.sessions_dir().join(uuid.to_string());
+    let tmp_dir = session_dir.join(TMP_FOLDER);
+    io::create_dir_all(&tmp_dir).await?;
+
+    let record = SessionRecord {
+        profile_path,
+        pid: None,
+        process_started: None,
+        log_path: None,
+        started: Utc::now(),
+        account: Some(account),
+        forced_options: Vec::new(),
+    };
+    write(
+        &session_dir.join(SESSION_FILE),
+        &serde_json::to_vec(&record)?,
+        &state.io_semaphore,
+    )
+    .await?;
+
+    Ok(tmp_dir)
+}
+
+/// Records the game process of a session and where its output goes, so the
+/// launcher can reattach to it after a restart
+pub(crate) async fn set_process(
+    state: &State,
+    uuid: Uuid,
+    pid: u32,
+    log_path: &Path,
+) -> crate::Result<()> {
+    let path = state
+        .directories
+        .sessions_dir()
+        .join(uuid.to_string())
+        .join(SESSION_FILE);
+    let mut record: SessionRecord =
+        read_json(&path, &state.io_semaphore).await?;
+    record.pid = Some(pid);
+    record.process_started = process_start_time(pid);
+    record.log_path = Some(log_path.to_path_buf());
+    write(&path, &serde_json::to_vec(&record)?, &state.io_semaphore).await?;
+
+    Ok(())
+}
+
+/// Records the options.txt settings forced for a session, so they are put
+/// back when it ends, even by a later run of the launcher
+pub(crate) async fn set_forced_options(
+    state: &State,
+    uuid: Uuid,
+    forced_options: Vec<ForcedOption>,
+) -> crate::Result<()> {
+    let path = state
+        .directories
+        .sessions_dir()
+        .join(uuid.to_string())
+        .join(SESSION_FILE);
+    let mut record: SessionRecord =
+        read_json(&path, &state.io_semaphore).await?;
+    record.forced_options = forced_options;
+    write(&path, &serde_json::to_vec(&record)?, &state.io_semaphore).await?;
+
+    Ok(())
+}
+
+/// Puts back the options.txt settings forced for a session, unless they were
+/// changed in game
+pub(crate) async fn restore_game_options(record: &SessionRecord) {
+    if record.forced_options.is_empty() {
+        return;
+    }
+    let res = async {
+        let instance_path = record.profile_path.get_full_path().await?;
+        restore_options(&instance_path, &record.forced_options).await
+    }
+    .await;
+    if let Err(err) = res {
+        tracing::warn!(
+            "Unable to restore game options of {}: {err}",
+            record.profile_path
+        );
+    }
+}
+
+/// How a game was launched, written next to its output so a shared log
+/// answers the usual questions about the setup on its own
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct SessionReport {
+    pub session: Uuid,
+    pub started: DateTime<Utc>,
+    pub launcher_version: String,
+    pub os: String,
+    pub game_version: String,
+    pub loader: ModLoader,
+    pub loader_version: Option<String>,
+    pub lwjgl_override: Option<String>,
+    pub java: ReportJava,
+    pub program: String,
+    /// Class the game was started from, overridden by the profile if it
+    /// differs from the version's
+    #[serde(default)]
+    pub main_class: String,
+    pub args: Vec<String>,
+    /// Standard game arguments the profile left out of `args`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_game_args: Vec<String>,
+    /// Variables set for the game, with their values redacted
+    pub env: Vec<(String, String)>,
+    pub mods: ReportMods,
+    pub settings: ReportSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReportJava {
+    pub version: String,
+    pub architecture: String,
+    pub path: String,
+}
+
+/// Enabled projects of the profile. Two logs with the same hash were made
+/// with the same files
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReportMods {
+    pub count: usize,
+    pub hash: String,
+}
+
+/// Settings the launch was resolved from, after profile overrides
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ReportSettings {
+    pub memory: MemorySettings,
+    pub resolution: WindowSize,
+    pub wrapper: Option<String>,
+    /// Options written to options.txt before launching
+    pub game_options: Vec<(String, String)>,
+    /// JVM arguments of the pack and user, with where each came from
+    #[serde(default)]
+    pub java_args: Vec<JavaArg>,
+    pub advanced_rendering: bool,
+    pub developer_mode: bool,
+}
+
+impl SessionReport {
+    /// Builds the report of a launch, with every censored string, such as
+    /// the access token and the computer username, replaced
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build(
+        state: &State,
+        plan: &LaunchPlan,
+        profile: &Profile,
+        java: &JavaVersion,
+        memory: MemorySettings,
+        resolution: WindowSize,
+        game_options: &[(String, String)],
+        censor: &Censor,
+    ) -> Self {
+        let mut mods = profile
+            .projects
+            .iter()
+            .filter(|(_, project)| !project.disabled)
+            .map(|(path, project)| {
+                format!("{} {}", path.0.display(), project.sha512)
+            })
+            .collect::<Vec<_>>();
+        mods.sort();
+
+        // Values are redacted like in state snapshots, as variables set for
+        // the game can hold tokens
+        let mut env = plan.env.clone();
+        redact_env(&mut env);
+
+        let settings = state.settings.read().await;
+        SessionReport {
+            session: plan.uuid,
+            started: Utc::now(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            game_version: profile.metadata.game_version.clone(),
+            loader: profile.metadata.loader,
+            loader_version: profile
+                .metadata
+                .loader_version
+                .as_ref()
+                .map(|x| x.id.clone()),
+            lwjgl_override: plan.lwjgl_override.clone(),
+            java: ReportJava {
+                version: java.version.clone(),
+                architecture: java.architecture.clone(),
+                path: censor.censor(&java.path),
+            },
+            program: censor.censor(&plan.program),
+            main_class: plan.main_class.clone(),
+            args: plan.args.iter().map(|x| censor.censor(x)).collect(),
+            removed_game_args: plan.removed_game_args.clone(),
+            env,
+            mods: ReportMods {
+                count: mods.len(),
+                hash: sha1::Sha1::from(mods.join("\n")).hexdigest(),
+            },
+            settings: ReportSettings {
+                memory,
+                resolution,
+                wrapper: profile
+                    .hooks
+                    .as_ref()
+                    .and_then(|x| x.wrapper.clone())
+                    .or_else(|| settings.hooks.wrapper.clone())
+                    .map(|x| censor.censor(&x)),
+                game_options: game_options.to_vec(),
+                java_args: plan
+                    .java_args
+                    .iter()
+                    .map(|x| JavaArg {
+                        flag: censor.censor(&x.flag),
+                        value: x.value.as_ref().map(|x| censor.censor(x)),
+                        source: x.source,
+                    })
+                    .collect(),
+                advanced_rendering: settings.advanced_rendering,
+                developer_mode: settings.developer_mode,
+            },
+        }
+    }
+
+    /// Writes the report into the log directory of its session
+    pub async fn write(
+        &self,
+        state: &State,
+        logs_dir: &Path,
+    ) -> crate::Result<()> {
+        write(
+            &logs_dir.join(REPORT_FILE),
+            &serde_json::to_vec_pretty(self)?,
+            &state.io_semaphore,
+        )
+        .await
+    }
+}
+
+/// Deletes everything a session left behind, once its game has exited, and
+/// puts back the game options it forced
+pub(crate) async fn remove(state: &State, uuid: Uuid) -> crate::Result<()> {
+    let session_dir = state.directories.sessions_dir().join(uuid.to_string());
+    if let Ok(record) = read_json::<SessionRecord>(
+        &session_dir.join(SESSION_FILE),
+        &state.io_semaphore,
+    )
+    .await
+    {
+        restore_game_options(&record).await;
+    }
+    if session_dir.exists() {
+        io::remove_dir_all(&session_dir).await?;
+        tracing::debug!("Removed session directory {}", session_dir.display());
+    }
+
+    Ok(())
+}
+
+/// Tracks the games of a previous run that are still running again, and
+/// returns how many were found
+#[tracing::instrument]
+pub(crate) async fn reattach() -> crate::Result<usize> {
+    let state = State::get().await?;
+    let sessions_dir = state.directories.sessions_dir();
+    if !sessions_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut reattached = 0;
+    let mut read_dir = io::read_dir(&sessions_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| io::IOError::with_path(e, &sessions_dir))?
+    {
+        let session_dir = entry.path();
+        let Some(uuid) = session_dir
+            .file_name()
+            .and_then(|x| Uuid::parse_str(&x.to_string_lossy()).ok())
+        else {
+            continue;
+        };
+        if state.children.read().await.get(&uuid).is_some() {
+            continue;
+        }
+
+        let record: SessionRecord = match read_json(
+            &session_dir.join(SESSION_FILE),
+            &state.io_semaphore,
+        )
+        .await
+        {
+            Ok(record) => record,
+            Err(_) => continue,
+        };
+        if !record.is_running() {
+            continue;
+        }
+
+        let profile_path = record.profile_path.clone();
+        state
+            .children
+            .write()
+            .await
+            .reattach_process(uuid, record)
+            .await?;
+        reattached += 1;
+
+        if !*state.offline.read().await {
+            if let Some(profile) =
+                crate::api::profile::get(&profile_path, None).await?
+            {
+                let _ = state
+                    .discord_rpc
+                    .set_activity(
+                        &format!("Playing {}", profile.metadata.name),
+                        true,
+                    )
+                    .await;
+            }
+        }
+    }
+
+    Ok(reattached)
+}
+
+/// Reattaches to the games of a previous run that are still running, then
+/// cleans up after the ones that exited, in the background
+pub(crate) fn startup_task() {
+    tokio::task::spawn(async {
+        match reattach().await {
+            Ok(count) if count > 0 => {
+                tracing::info!("Reattached to {count} running games")
+            }
+            Ok(_) => {}
+            Err(err) => {
+                tracing::warn!("Unable to reattach to running games: {err}")
+            }
+        };
+
+        // Games launched in safe mode that exited while the launcher was
+        // closed left their mods disabled
+        if let Err(err) = crate::api::profile::safe_mode::restore_all().await {
+            tracing::warn!("Unable to restore mods after safe mode: {err}")
+        }
+
+        if let Err(err) = crate::util::fetch::sweep_staging().await {
+            tracing::warn!("Unable to clean up unfinished writes: {err}")
+        }
+
+        match crate::cache::sweep_sessions().await {
+            Ok(report) if report.sessions > 0 => tracing::info!(
+                "Cleaned up {} stale game sessions, reclaiming {} bytes",
+                report.sessions,
+                report.reclaimed_bytes
+            ),
+            Ok(_) => {}
+            Err(err) => {
+                tracing::warn!("Unable to clean up stale sessions: {err}")
+            }
+        };
+    });
+}
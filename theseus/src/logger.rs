@@ -13,9 +13,194 @@
 
     The default is theseus=show, meaning only logs from theseus will be displayed, and at the info or higher level.
 
+    Without RUST_LOG, the filter is set from the logging settings once they are loaded, and can be changed at runtime.
+    A debug log can also be captured for the next install, raising the filter to trace until it ends.
+
 */
 
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+const DEFAULT_DIRECTIVES: &str = "theseus=info";
+// Everything the launcher logs, along with the requests it makes
+const CAPTURE_DIRECTIVES: &str = "theseus=trace,reqwest=debug";
+
+lazy_static! {
+    static ref FILTER: Mutex<FilterState> = Mutex::new(FilterState {
+        handle: None,
+        directives: DEFAULT_DIRECTIVES.to_string(),
+        capturing: false,
+    });
+    static ref CAPTURE: tokio::sync::Mutex<DebugCapture> =
+        tokio::sync::Mutex::new(DebugCapture::default());
+}
+
+struct FilterState {
+    // Swaps the filter of the running subscriber
+    handle: Option<reload::Handle<EnvFilter, Registry>>,
+    // Filter applied outside of captures
+    directives: String,
+    capturing: bool,
+}
+
+impl FilterState {
+    fn reload(&self, directives: &str) -> crate::Result<()> {
+        // Nothing is logged when the logger wasn't started
+        if let Some(handle) = &self.handle {
+            handle.reload(parse_filter(directives)?).map_err(|err| {
+                crate::ErrorKind::OtherError(format!(
+                    "Unable to change the log filter: {err}"
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A debug log captured for a single operation, so it can be shared without
+/// restarting the launcher with `RUST_LOG`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DebugCapture {
+    /// Whether the next operation will be captured
+    pub armed: bool,
+    /// The operation captured last, or being captured
+    pub operation: Option<String>,
+    pub started: Option<DateTime<Utc>>,
+    /// Unset while the operation runs
+    pub finished: Option<DateTime<Utc>>,
+    pub succeeded: Option<bool>,
+    /// Log file the capture was written to. Unset in development builds,
+    /// which log to the console
+    pub log_file: Option<PathBuf>,
+}
+
+fn parse_filter(directives: &str) -> crate::Result<EnvFilter> {
+    EnvFilter::try_new(directives).map_err(|err| {
+        crate::ErrorKind::InputError(format!(
+            "Invalid log filter {directives}: {err}"
+        ))
+        .into()
+    })
+}
+
+// Filter of the subscriber, reloadable for the settings and captures
+fn filter_layer() -> reload::Layer<EnvFilter, Registry> {
+    let directives = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|x| EnvFilter::try_new(x).is_ok())
+        .unwrap_or_else(|| DEFAULT_DIRECTIVES.to_string());
+    let (layer, handle) = reload::Layer::new(EnvFilter::new(&directives));
+    if let Ok(mut state) = FILTER.lock() {
+        state.handle = Some(handle);
+        state.directives = directives;
+    }
+    layer
+}
+
+/// Whether `RUST_LOG` sets the filter, which the settings don't override on
+/// startup
+pub(crate) fn filter_from_env() -> bool {
+    std::env::var_os("RUST_LOG").is_some()
+}
+
+/// Changes which logs are recorded, given in the syntax of `RUST_LOG`. A
+/// capture in progress keeps its filter, and this one applies when it ends
+pub(crate) fn set_filter(directives: &str) -> crate::Result<()> {
+    parse_filter(directives)?;
+    let mut state = FILTER.lock().map_err(|_| {
+        crate::ErrorKind::OtherError("Log filter is poisoned".to_string())
+    })?;
+    state.directives = directives.to_string();
+    if !state.capturing {
+        state.reload(directives)?;
+    }
+    Ok(())
+}
+
+/// Captures a debug log of the next operation that runs
+pub(crate) async fn arm_capture() -> DebugCapture {
+    let mut capture = CAPTURE.lock().await;
+    capture.armed = true;
+    capture.clone()
+}
+
+pub(crate) async fn get_capture() -> DebugCapture {
+    CAPTURE.lock().await.clone()
+}
+
+/// Runs an operation, capturing a debug log of it if one was asked for
+pub(crate) async fn capture<T, F>(
+    operation: String,
+    future: F,
+) -> crate::Result<T>
+where
+    F: Future<Output = crate::Result<T>>,
+{
+    let captured = {
+        let mut capture = CAPTURE.lock().await;
+        if capture.armed {
+            let started = Utc::now();
+            *capture = DebugCapture {
+                armed: false,
+                operation: Some(operation.clone()),
+                started: Some(started),
+                finished: None,
+                succeeded: None,
+                log_file: log_file(started),
+            };
+            set_capturing(true);
+            tracing::info!("Capturing a debug log of {operation}");
+            true
+        } else {
+            false
+        }
+    };
+    let result = future.await;
+
+    if captured {
+        tracing::info!("Finished capturing a debug log of {operation}");
+        let mut capture = CAPTURE.lock().await;
+        capture.finished = Some(Utc::now());
+        capture.succeeded = Some(result.is_ok());
+        set_capturing(false);
+    }
+
+    result
+}
+
+fn set_capturing(capturing: bool) {
+    let Ok(mut state) = FILTER.lock() else {
+        return;
+    };
+    state.capturing = capturing;
+    let directives = if capturing {
+        CAPTURE_DIRECTIVES.to_string()
+    } else {
+        state.directives.clone()
+    };
+    if let Err(err) = state.reload(&directives) {
+        tracing::warn!("Unable to change the log filter: {err}");
+    }
+}
+
+#[cfg(debug_assertions)]
+fn log_file(_started: DateTime<Utc>) -> Option<PathBuf> {
+    None
+}
+
+// The appender rolls over daily, naming files after the UTC date
+#[cfg(not(debug_assertions))]
+fn log_file(started: DateTime<Utc>) -> Option<PathBuf> {
+    let file_name = format!("theseus.log.{}", started.format("%Y-%m-%d"));
+    crate::prelude::DirectoryInfo::launcher_logs_dir()
+        .map(|x| x.join(file_name))
+}
 
 // Handling for the live development logging
 // This will log to the console, and will not log to a file
@@ -23,11 +208,9 @@ use tracing_appender::non_blocking::WorkerGuard;
 pub fn start_logger() -> Option<WorkerGuard> {
     use tracing_subscriber::prelude::*;
 
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("theseus=info"));
     let subscriber = tracing_subscriber::registry()
+        .with(filter_layer())
         .with(tracing_subscriber::fmt::layer())
-        .with(filter)
         .with(tracing_error::ErrorLayer::default());
     tracing::subscriber::set_global_default(subscriber)
         .expect("setting default subscriber failed");
@@ -51,21 +234,18 @@ pub fn start_logger() -> Option<WorkerGuard> {
         return None;
     };
 
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("theseus=info"));
-
     let file_appender =
         RollingFileAppender::new(Rotation::DAILY, logs_dir, "theseus.log");
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
     let subscriber = tracing_subscriber::registry()
+        .with(filter_layer())
         .with(
             tracing_subscriber::fmt::layer()
                 .with_writer(non_blocking)
                 .with_ansi(false) // disable ANSI escape codes
                 .with_timer(ChronoLocal::rfc3339()),
         )
-        .with(filter)
         .with(tracing_error::ErrorLayer::default());
 
     tracing::subscriber::set_global_default(subscriber)
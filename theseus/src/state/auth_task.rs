@@ -1,6 +1,7 @@
 use crate::launcher::auth::Credentials;
 
 use tokio::task::JoinHandle;
+use uuid::Uuid;
 
 // Authentication task
 // A wrapper over the authentication task that allows it to be called from the frontend
@@ -17,11 +18,24 @@ impl AuthTask {
     }
 
     pub async fn begin_auth() -> crate::Result<url::Url> {
+        Self::begin(None).await
+    }
+
+    /// Begins signing in again with an account that has to grant
+    /// permissions again, updating it once done
+    pub async fn begin_reconsent(user: Uuid) -> crate::Result<url::Url> {
+        Self::begin(Some(user)).await
+    }
+
+    async fn begin(reconsent: Option<Uuid>) -> crate::Result<url::Url> {
         let state = crate::State::get().await?;
 
         // Creates a channel to receive the URL
         let (tx, rx) = tokio::sync::oneshot::channel::<url::Url>();
-        let task = tokio::spawn(crate::auth::authenticate(tx));
+        let task = match reconsent {
+            Some(user) => tokio::spawn(crate::auth::reconsent(tx, user)),
+            None => tokio::spawn(crate::auth::authenticate(tx)),
+        };
 
         // If receiver is dropped, try to get Hydra error
         let url = rx.await;
@@ -1,18 +1,27 @@
-use super::{Profile, ProfilePathId};
+use super::{PlaySession, Profile, ProfilePathId};
+use chrono::{DateTime, Utc};
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
 use std::{collections::HashMap, sync::Arc};
-use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{
+    AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader,
+};
 use tokio::process::Child;
 use tokio::process::Command;
 use tokio::process::{ChildStderr, ChildStdout};
 use tokio::sync::RwLock;
 use tracing::error;
 
+use crate::api::profile::smoke_test;
 use crate::event::emit::emit_process;
 use crate::event::ProcessPayloadType;
+use crate::launcher::session::SessionRecord;
+use crate::util::censor::Censor;
+use crate::util::fetch::BANDWIDTH_LIMITER;
 use crate::util::io::IOError;
+use crate::util::platform;
+use crate::util::process_tree::{self, ProcessTree};
 
 use tokio::task::JoinHandle;
 use uuid::Uuid;
@@ -26,16 +35,108 @@ pub struct Children(HashMap<Uuid, Arc<RwLock<MinecraftChild>>>);
 pub struct MinecraftChild {
     pub uuid: Uuid,
     pub profile_relative_path: ProfilePathId,
-    pub manager: Option<JoinHandle<crate::Result<ExitStatus>>>, // None when future has completed and been handled
-    pub current_child: Arc<RwLock<Child>>,
+    pub started: DateTime<Utc>,
+    // Account the game was launched with, unknown for games reattached to from before it was recorded
+    pub account: Option<Uuid>,
+    pub manager: Option<JoinHandle<crate::Result<Option<ExitStatus>>>>, // None when future has completed and been handled
+    pub current_child: Arc<RwLock<ChildProcess>>,
     pub output: SharedOutput,
 }
 
+// The process a MinecraftChild tracks
+// Games reattached to after a launcher restart are not children of this process, so they can only be followed by PID
+// and the start time the OS gave the process, so a process reusing the PID isn't mistaken for the game
+#[derive(Debug)]
+pub enum ChildProcess {
+    Spawned(Child, ProcessTree),
+    Reattached {
+        pid: u32,
+        process_started: Option<String>,
+    },
+}
+
+impl ChildProcess {
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            ChildProcess::Spawned(child, _) => child.id(),
+            ChildProcess::Reattached { pid, .. } => Some(*pid),
+        }
+    }
+
+    // Returns None while the process is still running, then its exit status
+    // The exit status of a reattached process cannot be known, so it is None once it exited
+    pub fn try_wait(&mut self) -> std::io::Result<Option<Option<ExitStatus>>> {
+        match self {
+            ChildProcess::Spawned(child, _) => Ok(child.try_wait()?.map(Some)),
+            ChildProcess::Reattached {
+                pid,
+                process_started,
+            } => {
+                let running = match process_started {
+                    Some(started) => {
+                        platform::process_start_time(*pid).as_ref()
+                            == Some(started)
+                    }
+                    None => platform::is_process_running(*pid),
+                };
+                if running {
+                    Ok(None)
+                } else {
+                    Ok(Some(None))
+                }
+            }
+        }
+    }
+
+    // Stops the whole tree, as wrappers start the game as their own child
+    pub async fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            ChildProcess::Spawned(child, tree) => {
+                if let Err(err) = tree.kill() {
+                    tracing::warn!("Unable to stop process tree: {err}");
+                }
+                // Reaps the child, which is also all that's left to do if
+                // stopping the tree failed
+                match child.kill().await {
+                    Err(err)
+                        if err.kind() == std::io::ErrorKind::InvalidInput =>
+                    {
+                        Ok(())
+                    }
+                    res => res,
+                }
+            }
+            ChildProcess::Reattached { pid, .. } => {
+                process_tree::kill_tree(*pid)
+            }
+        }
+    }
+
+    // Asks the whole tree to exit, leaving the game time to save
+    pub fn terminate(&self) -> std::io::Result<()> {
+        match self.id() {
+            Some(pid) => process_tree::terminate_tree(pid),
+            // Spawned processes no longer have a PID once reaped
+            None => Ok(()),
+        }
+    }
+}
+
 impl Children {
     pub fn new() -> Children {
         Children(HashMap::new())
     }
 
+    /// Name of the log folder of a process. The launch time keeps folders
+    /// sorted, the UUID keeps two launches in the same second apart
+    pub fn log_dir_name(uuid: Uuid) -> String {
+        format!(
+            "{}_{}",
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            uuid.as_hyphenated()
+        )
+    }
+
     // Runs the command in process, inserts a child process to keep track of, and returns a reference to the container struct MinecraftChild
     // The threads for stdout and stderr are spawned here
     // Unlike a Hashmap's 'insert', this directly returns the reference to the MinecraftChild rather than any previously stored MinecraftChild that may exist
@@ -43,10 +144,11 @@ impl Children {
     #[tracing::instrument(skip(
         self,
         uuid,
+        account,
         log_path,
         mc_command,
         post_command,
-        censor_strings
+        censor
     ))]
     #[tracing::instrument(level = "trace", skip(self))]
     #[theseus_macros::debug_pin]
@@ -54,17 +156,18 @@ impl Children {
         &mut self,
         uuid: Uuid,
         profile_relative_path: ProfilePathId,
+        account: Uuid,
         log_path: PathBuf,
         mut mc_command: Command,
         post_command: Option<Command>, // Command to run after minecraft.
-        censor_strings: HashMap<String, String>,
+        censor: Censor,
     ) -> crate::Result<Arc<RwLock<MinecraftChild>>> {
         // Takes the first element of the commands vector and spawns it
+        process_tree::isolate(&mut mc_command);
         let mut child = mc_command.spawn().map_err(IOError::from)?;
 
         // Create std watcher threads for stdout and stderr
-        let shared_output =
-            SharedOutput::build(&log_path, censor_strings).await?;
+        let shared_output = SharedOutput::build(&log_path, censor).await?;
         if let Some(child_stdout) = child.stdout.take() {
             let stdout_clone = shared_output.clone();
             tokio::spawn(async move {
@@ -88,9 +191,12 @@ impl Children {
                 "Process immediately failed, could not get PID".to_string(),
             )
         })?;
-        let current_child = Arc::new(RwLock::new(child));
+        let tree = ProcessTree::attach(&child, pid);
+        let current_child =
+            Arc::new(RwLock::new(ChildProcess::Spawned(child, tree)));
         let manager = Some(tokio::spawn(Self::sequential_process_manager(
             uuid,
+            profile_relative_path.clone(),
             post_command,
             pid,
             current_child.clone(),
@@ -108,6 +214,93 @@ impl Children {
         let mchild = MinecraftChild {
             uuid,
             profile_relative_path,
+            started: Utc::now(),
+            account: Some(account),
+            current_child,
+            output: shared_output,
+            manager,
+        };
+
+        let mchild = Arc::new(RwLock::new(mchild));
+        self.0.insert(uuid, mchild.clone());
+        Ok(mchild)
+    }
+
+    // Tracks a game launched by a previous run of the launcher again
+    // Its output pipes died with that run, so the log Minecraft writes itself is followed instead
+    // Post-exit hooks of the original launch are not run
+    #[tracing::instrument(skip(self, record))]
+    #[theseus_macros::debug_pin]
+    pub(crate) async fn reattach_process(
+        &mut self,
+        uuid: Uuid,
+        record: SessionRecord,
+    ) -> crate::Result<Arc<RwLock<MinecraftChild>>> {
+        let pid = record.pid.ok_or_else(|| {
+            crate::ErrorKind::LauncherError(format!(
+                "Session {uuid} has no recorded process"
+            ))
+        })?;
+
+        let log_path = match record.log_path {
+            Some(log_path) => log_path,
+            None => {
+                let state = crate::State::get().await?;
+                let logs_dir = state
+                    .directories
+                    .profile_logs_dir(&record.profile_path)
+                    .await?
+                    .join(Self::log_dir_name(uuid));
+                crate::util::io::create_dir_all(&logs_dir).await?;
+                logs_dir.join("stdout.log")
+            }
+        };
+        let shared_output =
+            SharedOutput::resume(&log_path, Censor::from_settings().await?)
+                .await?;
+
+        let current_child = Arc::new(RwLock::new(ChildProcess::Reattached {
+            pid,
+            process_started: record.process_started.clone(),
+        }));
+        let game_log_path = record
+            .profile_path
+            .get_full_path()
+            .await?
+            .join("logs")
+            .join("latest.log");
+        let game_log_output = shared_output.clone();
+        let game_log_child = current_child.clone();
+        tokio::spawn(async move {
+            if let Err(e) = game_log_output
+                .follow_file(&game_log_path, game_log_child)
+                .await
+            {
+                error!("Following game log died with error: {}", e);
+            }
+        });
+
+        let manager = Some(tokio::spawn(Self::reattached_process_manager(
+            uuid,
+            record.profile_path.clone(),
+            pid,
+            record.started,
+            current_child.clone(),
+        )));
+
+        emit_process(
+            uuid,
+            pid,
+            ProcessPayloadType::Launched,
+            "Reattached to Minecraft",
+        )
+        .await?;
+
+        let mchild = MinecraftChild {
+            uuid,
+            profile_relative_path: record.profile_path,
+            started: record.started,
+            account: record.account,
             current_child,
             output: shared_output,
             manager,
@@ -125,11 +318,32 @@ impl Children {
     #[theseus_macros::debug_pin]
     async fn sequential_process_manager(
         uuid: Uuid,
+        profile_path: ProfilePathId,
         post_command: Option<Command>,
         mut current_pid: u32,
-        current_child: Arc<RwLock<Child>>,
-    ) -> crate::Result<ExitStatus> {
+        current_child: Arc<RwLock<ChildProcess>>,
+    ) -> crate::Result<Option<ExitStatus>> {
         let current_child = current_child.clone();
+        let game_running = BANDWIDTH_LIMITER.game_running();
+
+        let started = Utc::now();
+        // Smoke tests don't count as playing
+        if !smoke_test::is_testing(&profile_path) {
+            let res = async {
+                crate::api::profile::edit(&profile_path, |prof| {
+                    prof.playtime.launches += 1;
+                    async { Ok(()) }
+                })
+                .await?;
+                crate::State::sync().await
+            }
+            .await;
+            if let Err(err) = res {
+                tracing::warn!(
+                    "Unable to record launch of {profile_path}: {err}"
+                );
+            }
+        }
 
         // Wait on current Minecraft Child
         let mut mc_exit_status;
@@ -146,24 +360,17 @@ impl Children {
             // sleep for 10ms
             tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
         }
+        drop(game_running);
 
-        {
-            // Clear game played for Discord RPC
-            // May have other active processes, so we clear to the next running process
-            let state = crate::State::get().await?;
-            let _ = state.discord_rpc.clear_to_default(true).await;
-        }
-
-        // If in tauri, window should show itself again after process exists if it was hidden
-        #[cfg(feature = "tauri")]
-        {
-            let window = crate::EventState::get_main_window().await?;
-            if let Some(window) = window {
-                window.unminimize()?;
-            }
-        }
+        Self::finish_session(
+            uuid,
+            &profile_path,
+            started,
+            mc_exit_status.as_ref(),
+        )
+        .await?;
 
-        if !mc_exit_status.success() {
+        if !mc_exit_status.map_or(false, |x| x.success()) {
             emit_process(
                 uuid,
                 current_pid,
@@ -179,6 +386,14 @@ impl Children {
         if let Some(mut m_command) = post_command {
             {
                 let mut current_child = current_child.write().await;
+                m_command.env("INST_PID", current_pid.to_string()).env(
+                    "INST_EXIT_CODE",
+                    mc_exit_status
+                        .and_then(|x| x.code())
+                        .unwrap_or(-1)
+                        .to_string(),
+                );
+                process_tree::isolate(&mut m_command);
                 let new_child = m_command.spawn().map_err(IOError::from)?;
                 current_pid = new_child.id().ok_or_else(|| {
                     crate::ErrorKind::LauncherError(
@@ -186,7 +401,8 @@ impl Children {
                             .to_string(),
                     )
                 })?;
-                *current_child = new_child;
+                let tree = ProcessTree::attach(&new_child, current_pid);
+                *current_child = ChildProcess::Spawned(new_child, tree);
             }
             emit_process(
                 uuid,
@@ -223,6 +439,137 @@ impl Children {
         Ok(mc_exit_status)
     }
 
+    // Waits on a reattached game, which has no post-exit hooks to run
+    #[tracing::instrument(skip(current_child))]
+    #[theseus_macros::debug_pin]
+    async fn reattached_process_manager(
+        uuid: Uuid,
+        profile_path: ProfilePathId,
+        pid: u32,
+        started: DateTime<Utc>,
+        current_child: Arc<RwLock<ChildProcess>>,
+    ) -> crate::Result<Option<ExitStatus>> {
+        let game_running = BANDWIDTH_LIMITER.game_running();
+
+        // Polled less often than a spawned child, as checking on a PID means reading the process table
+        let exit_status = loop {
+            if let Some(t) = current_child
+                .write()
+                .await
+                .try_wait()
+                .map_err(IOError::from)?
+            {
+                break t;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        };
+        drop(game_running);
+
+        Self::finish_session(
+            uuid,
+            &profile_path,
+            started,
+            exit_status.as_ref(),
+        )
+        .await?;
+
+        emit_process(uuid, pid, ProcessPayloadType::Finished, "Exited process")
+            .await?;
+
+        Ok(exit_status)
+    }
+
+    // Records the playtime and heap usage of a game that exited, and cleans
+    // up after it. The exit status of reattached games is unknown
+    async fn finish_session(
+        uuid: Uuid,
+        profile_path: &ProfilePathId,
+        started: DateTime<Utc>,
+        exit_status: Option<&ExitStatus>,
+    ) -> crate::Result<()> {
+        // Games stopped by a smoke test weren't played, and were stopped
+        // before they could use the heap they would
+        let smoke_test = smoke_test::is_testing(profile_path);
+        if !smoke_test {
+            let session = PlaySession {
+                started,
+                ended: Utc::now(),
+                crashed: exit_status.map(|x| !x.success()),
+            };
+            let res = async {
+                crate::api::profile::edit(profile_path, |prof| {
+                    prof.playtime.record_session(session.clone());
+                    async { Ok(()) }
+                })
+                .await?;
+                crate::State::sync().await
+            }
+            .await;
+            if let Err(err) = res {
+                tracing::warn!(
+                    "Unable to record playtime of {profile_path}: {err}"
+                );
+            }
+
+            // Read from the session, so before it is removed
+            if let Err(err) =
+                crate::launcher::heap::record(uuid, profile_path, started).await
+            {
+                tracing::warn!(
+                    "Unable to record heap usage of {profile_path}: {err}"
+                );
+            }
+
+            crate::api::profile::cloud_sync::sync_if_enabled(
+                profile_path,
+                Some(uuid),
+            )
+            .await;
+        }
+
+        {
+            // Clear game played for Discord RPC
+            // May have other active processes, so we clear to the next running process
+            let state = crate::State::get().await?;
+            let _ = state.discord_rpc.clear_to_default(true).await;
+
+            // Temp files of the game are no longer in use
+            if let Err(err) =
+                crate::launcher::session::remove(&state, uuid).await
+            {
+                tracing::warn!("Unable to clean up session {uuid}: {err}");
+            }
+        }
+
+        if let Err(err) =
+            crate::api::profile::safe_mode::restore_if_idle(profile_path).await
+        {
+            tracing::warn!("Unable to restore mods of {profile_path}: {err}");
+        }
+
+        #[cfg(feature = "launch_middleware")]
+        {
+            let state = crate::State::get().await?;
+            let middlewares = state.launch_middlewares.read().await;
+            middlewares
+                .exited(profile_path, uuid, exit_status.and_then(|x| x.code()))
+                .await;
+        }
+
+        // If in tauri, window should show itself again after the last game exits if it was hidden
+        #[cfg(feature = "tauri")]
+        if !smoke_test {
+            let state = crate::State::get().await?;
+            let policy = state.settings.read().await.post_launch_policy();
+            let running = state.children.read().await.running_keys().await?;
+            if running.iter().all(|x| *x == uuid) {
+                crate::EventState::apply_window_action(policy.on_exit).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     // Returns a ref to the child
     pub fn get(&self, uuid: &Uuid) -> Option<Arc<RwLock<MinecraftChild>>> {
         self.0.get(uuid).cloned()
@@ -234,11 +581,11 @@ impl Children {
     }
 
     // Get exit status of a child by PID
-    // Returns None if the child is still running
+    // Returns None if the child is still running, and an unknown status for reattached children
     pub async fn exit_status(
         &self,
         uuid: &Uuid,
-    ) -> crate::Result<Option<std::process::ExitStatus>> {
+    ) -> crate::Result<Option<Option<std::process::ExitStatus>>> {
         if let Some(child) = self.get(uuid) {
             let child = child.write().await;
             let status = child
@@ -275,6 +622,19 @@ impl Children {
         Ok(keys)
     }
 
+    // Counts the children whose process manager hasn't finished, which
+    // tracks their exit without checking on the processes again
+    pub async fn running_count(&self) -> usize {
+        let mut count = 0;
+        for child in self.0.values() {
+            let child = child.read().await;
+            if child.manager.as_ref().map_or(false, |x| !x.is_finished()) {
+                count += 1;
+            }
+        }
+        count
+    }
+
     // Gets all PID keys of running children with a given profile path
     pub async fn running_keys_with_profile(
         &self,
@@ -361,13 +721,13 @@ impl Default for Children {
 pub struct SharedOutput {
     output: Arc<RwLock<String>>,
     log_file: Arc<RwLock<File>>,
-    censor_strings: HashMap<String, String>,
+    censor: Censor,
 }
 
 impl SharedOutput {
     async fn build(
         log_file_path: &Path,
-        censor_strings: HashMap<String, String>,
+        censor: Censor,
     ) -> crate::Result<Self> {
         Ok(SharedOutput {
             output: Arc::new(RwLock::new(String::new())),
@@ -376,7 +736,32 @@ impl SharedOutput {
                     .await
                     .map_err(|e| IOError::with_path(e, log_file_path))?,
             )),
-            censor_strings,
+            censor,
+        })
+    }
+
+    // Continues a log file written by a previous run, keeping what it already holds as output
+    async fn resume(
+        log_file_path: &Path,
+        censor: Censor,
+    ) -> crate::Result<Self> {
+        let mut log_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(log_file_path)
+            .await
+            .map_err(|e| IOError::with_path(e, log_file_path))?;
+        let mut output = String::new();
+        log_file
+            .read_to_string(&mut output)
+            .await
+            .map_err(|e| IOError::with_path(e, log_file_path))?;
+
+        Ok(SharedOutput {
+            output: Arc::new(RwLock::new(output)),
+            log_file: Arc::new(RwLock::new(log_file)),
+            censor,
         })
     }
 
@@ -399,20 +784,7 @@ impl SharedOutput {
             .map_err(IOError::from)?
             > 0
         {
-            let val_line = self.censor_log(line.clone());
-
-            {
-                let mut output = self.output.write().await;
-                output.push_str(&val_line);
-            }
-            {
-                let mut log_file = self.log_file.write().await;
-                log_file
-                    .write_all(val_line.as_bytes())
-                    .await
-                    .map_err(IOError::from)?;
-            }
-
+            self.push_line(&line).await?;
             line.clear();
         }
         Ok(())
@@ -431,30 +803,74 @@ impl SharedOutput {
             .map_err(IOError::from)?
             > 0
         {
-            let val_line = self.censor_log(line.clone());
+            self.push_line(&line).await?;
+            line.clear();
+        }
+        Ok(())
+    }
 
-            {
-                let mut output = self.output.write().await;
-                output.push_str(&val_line);
-            }
-            {
-                let mut log_file = self.log_file.write().await;
-                log_file
-                    .write_all(val_line.as_bytes())
+    // Reads the lines appended to a file until the process exits, starting from its current end
+    async fn follow_file(
+        &self,
+        path: &Path,
+        process: Arc<RwLock<ChildProcess>>,
+    ) -> crate::Result<()> {
+        let mut file = File::open(path)
+            .await
+            .map_err(|e| IOError::with_path(e, path))?;
+        file.seek(std::io::SeekFrom::End(0))
+            .await
+            .map_err(|e| IOError::with_path(e, path))?;
+        let mut buf_reader = BufReader::new(file);
+        let mut line = String::new();
+
+        loop {
+            let read = buf_reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| IOError::with_path(e, path))?;
+
+            // Lines still being written are completed by a later read
+            if read == 0 || !line.ends_with('\n') {
+                let exited = process
+                    .write()
                     .await
-                    .map_err(IOError::from)?;
+                    .try_wait()
+                    .map_err(IOError::from)?
+                    .is_some();
+                if exited {
+                    break;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(500))
+                    .await;
+                continue;
             }
 
+            self.push_line(&line).await?;
             line.clear();
         }
+
+        if !line.is_empty() {
+            self.push_line(&line).await?;
+        }
         Ok(())
     }
 
-    fn censor_log(&self, mut val: String) -> String {
-        for (find, replace) in &self.censor_strings {
-            val = val.replace(find, replace);
+    async fn push_line(&self, line: &str) -> crate::Result<()> {
+        let val_line = self.censor.censor(line);
+
+        {
+            let mut output = self.output.write().await;
+            output.push_str(&val_line);
+        }
+        {
+            let mut log_file = self.log_file.write().await;
+            log_file
+                .write_all(val_line.as_bytes())
+                .await
+                .map_err(IOError::from)?;
         }
 
-        val
+        Ok(())
     }
 }
@@ -2,13 +2,61 @@
 use std::fs;
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
-use super::{ProfilePathId, Settings};
+use crate::util::io::{self, PermissionCause};
+
+use super::{Profile, ProfilePathId, Settings, StorageMode};
 
 pub const SETTINGS_FILE_NAME: &str = "settings.json";
 pub const CACHES_FOLDER_NAME: &str = "caches";
 pub const LAUNCHER_LOGS_FOLDER_NAME: &str = "launcher_logs";
+pub const ISOLATED_STORAGE_FOLDER_NAME: &str = ".theseus";
+
+/// Game files directories used to install and launch a profile, which
+/// depend on its storage mode
+#[derive(Debug, Clone)]
+pub struct GameDirectories {
+    pub libraries_dir: PathBuf,
+    pub assets_dir: PathBuf,
+    /// Root of the virtual asset directories, which versions before 1.7
+    /// read assets from by name
+    pub legacy_assets_dir: PathBuf,
+    pub natives_dir: PathBuf,
+}
+
+impl GameDirectories {
+    /// Get the assets index directory
+    #[inline]
+    pub fn assets_index_dir(&self) -> PathBuf {
+        self.assets_dir.join("indexes")
+    }
+
+    /// Get the assets objects directory
+    #[inline]
+    pub fn objects_dir(&self) -> PathBuf {
+        self.assets_dir.join("objects")
+    }
+
+    /// Get the directory for a specific object
+    #[inline]
+    pub fn object_dir(&self, hash: &str) -> PathBuf {
+        self.objects_dir().join(&hash[..2]).join(hash)
+    }
+
+    /// Get the natives directory for a version of Minecraft
+    #[inline]
+    pub fn version_natives_dir(&self, version: &str) -> PathBuf {
+        self.natives_dir.join(version)
+    }
+
+    /// Get the virtual asset directory of a legacy asset index
+    #[inline]
+    pub fn virtual_assets_dir(&self, index: &str) -> PathBuf {
+        self.legacy_assets_dir.join(index)
+    }
+}
 
 #[derive(Debug)]
 pub struct DirectoryInfo {
@@ -17,6 +65,18 @@ pub struct DirectoryInfo {
     pub working_dir: PathBuf,
 }
 
+/// A folder the launcher needs to write to but can't
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WriteAccessIssue {
+    pub path: PathBuf,
+    /// Why it was refused, if it was refused rather than failing otherwise
+    pub cause: Option<PermissionCause>,
+    pub message: String,
+    /// Whether moving the config dir elsewhere fixes it. The settings dir
+    /// can only be moved with the THESEUS_CONFIG_DIR environment variable
+    pub relocatable: bool,
+}
+
 impl DirectoryInfo {
     // Get the settings directory
     // init() is not needed for this function
@@ -72,6 +132,30 @@ impl DirectoryInfo {
         })
     }
 
+    /// Checks the launcher can write to the settings and config dirs
+    #[tracing::instrument(skip(self))]
+    pub async fn check_write_access(&self) -> Vec<WriteAccessIssue> {
+        let config_dir = self.config_dir.read().await.clone();
+        let mut dirs = vec![(self.settings_dir.clone(), false)];
+        if config_dir != self.settings_dir {
+            dirs.push((config_dir, true));
+        }
+
+        let mut issues = Vec::new();
+        for (path, relocatable) in dirs {
+            if let Err(err) = io::check_write_access(&path).await {
+                let cause = err.permission_denied().map(|(_, cause)| cause);
+                issues.push(WriteAccessIssue {
+                    path,
+                    cause,
+                    message: crate::ErrorKind::from(err).to_string(),
+                    relocatable,
+                });
+            }
+        }
+        issues
+    }
+
     /// Get the Minecraft instance metadata directory
     #[inline]
     pub async fn metadata_dir(&self) -> PathBuf {
@@ -129,7 +213,7 @@ impl DirectoryInfo {
     /// Get the Minecraft legacy assets metadata directory
     #[inline]
     pub async fn legacy_assets_dir(&self) -> PathBuf {
-        self.metadata_dir().await.join("resources")
+        self.assets_dir().await.join("virtual")
     }
 
     /// Get the Minecraft legacy assets metadata directory
@@ -144,6 +228,39 @@ impl DirectoryInfo {
         self.natives_dir().await.join(version)
     }
 
+    /// Get the game files directories shared by all profiles
+    pub async fn shared_game_dirs(&self) -> GameDirectories {
+        GameDirectories {
+            libraries_dir: self.libraries_dir().await,
+            assets_dir: self.assets_dir().await,
+            legacy_assets_dir: self.legacy_assets_dir().await,
+            natives_dir: self.natives_dir().await,
+        }
+    }
+
+    /// Get the game files directories of a profile, following its storage
+    /// mode
+    pub async fn game_dirs(
+        &self,
+        profile: &Profile,
+    ) -> crate::Result<GameDirectories> {
+        Ok(match profile.storage_mode {
+            StorageMode::Shared => self.shared_game_dirs().await,
+            StorageMode::Isolated => {
+                let root = profile
+                    .get_profile_full_path()
+                    .await?
+                    .join(ISOLATED_STORAGE_FOLDER_NAME);
+                GameDirectories {
+                    libraries_dir: root.join("libraries"),
+                    assets_dir: root.join("assets"),
+                    legacy_assets_dir: root.join("assets").join("virtual"),
+                    natives_dir: root.join("natives"),
+                }
+            }
+        })
+    }
+
     /// Get the directory containing instance icons
     #[inline]
     pub async fn icon_dir(&self) -> PathBuf {
@@ -171,6 +288,26 @@ impl DirectoryInfo {
             .map(|d| d.join(LAUNCHER_LOGS_FOLDER_NAME))
     }
 
+    /// Get the directory holding what each synced profile looked like when it
+    /// was last synced from this computer
+    #[inline]
+    pub fn sync_state_dir(&self) -> PathBuf {
+        self.settings_dir.join("sync")
+    }
+
+    /// Get the content policy file administrators deploy next to the settings
+    #[inline]
+    pub fn content_policy_file(&self) -> PathBuf {
+        self.settings_dir.join("content_policy.json")
+    }
+
+    /// Get the directory half installed profiles are moved to when their
+    /// install is rolled back, rather than deleted
+    #[inline]
+    pub async fn interrupted_installs_dir(&self) -> PathBuf {
+        self.config_dir.read().await.join("interrupted_installs")
+    }
+
     /// Get the file containing the global database
     #[inline]
     pub async fn database_file(&self) -> PathBuf {
@@ -189,11 +326,24 @@ impl DirectoryInfo {
         self.settings_dir.join(CACHES_FOLDER_NAME)
     }
 
+    /// Get the directory holding the temporary files of game sessions
+    #[inline]
+    pub fn sessions_dir(&self) -> PathBuf {
+        self.caches_dir().join("sessions")
+    }
+
     #[inline]
     pub async fn caches_meta_dir(&self) -> PathBuf {
         self.caches_dir().join("metadata")
     }
 
+    /// Get the directory of cached metadata responses, kept with the
+    /// validators they are revalidated with
+    #[inline]
+    pub fn http_cache_dir(&self) -> PathBuf {
+        self.caches_dir().join("http")
+    }
+
     /// Get path from environment variable
     #[inline]
     fn env_path(name: &str) -> Option<PathBuf> {
@@ -0,0 +1,289 @@
+//! The game's own settings in options.txt, parsed into typed values so the
+//! launcher can force some of them without rewriting the rest
+use crate::util::fetch::write;
+use crate::util::io;
+use crate::State;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+pub(crate) const OPTIONS_FILE: &str = "options.txt";
+
+/// Value of a game option. Values the game writes as JSON lists, such as the
+/// enabled resource packs, are lists
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    List(Vec<String>),
+    Text(String),
+}
+
+impl OptionValue {
+    /// Reads a value as the game wrote it
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "true" => return OptionValue::Bool(true),
+            "false" => return OptionValue::Bool(false),
+            _ => {}
+        }
+        if let Ok(x) = raw.parse::<i64>() {
+            return OptionValue::Int(x);
+        }
+        if let Ok(x) = raw.parse::<f64>() {
+            return OptionValue::Float(x);
+        }
+        if raw.starts_with('[') {
+            if let Ok(x) = serde_json::from_str::<Vec<String>>(raw) {
+                return OptionValue::List(x);
+            }
+        }
+        OptionValue::Text(raw.to_string())
+    }
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Bool(x) => write!(fmt, "{x}"),
+            OptionValue::Int(x) => write!(fmt, "{x}"),
+            // Debug keeps the decimal point of whole numbers, as the game
+            // writes them
+            OptionValue::Float(x) => write!(fmt, "{x:?}"),
+            OptionValue::List(x) => write!(
+                fmt,
+                "{}",
+                serde_json::to_string(x).map_err(|_| fmt::Error)?
+            ),
+            OptionValue::Text(x) => write!(fmt, "{x}"),
+        }
+    }
+}
+
+/// Game settings a profile forces into options.txt every time it launches
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GameOptionOverrides {
+    pub options: BTreeMap<String, OptionValue>,
+    /// Whether the values the options had before are put back after the
+    /// game exits, unless they were changed in game
+    #[serde(default)]
+    pub restore_after_exit: bool,
+}
+
+/// The lines of an options.txt, in order, with values as written. Lines
+/// without a key continue the value above them, as do lines inside a JSON
+/// value that isn't closed yet
+#[derive(Debug, Clone, Default)]
+pub struct GameOptions {
+    entries: Vec<(String, String)>,
+}
+
+// Whether a line starts a new option, rather than continuing the one above
+fn starts_option(line: &str) -> bool {
+    line.split_once(':').map_or(false, |(key, _)| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || "_.-".contains(x))
+    })
+}
+
+// Whether a value opens more JSON brackets than it closes, outside strings
+fn is_open(value: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for x in value.chars() {
+        match x {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '{' if !in_string => depth += 1,
+            ']' | '}' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+impl GameOptions {
+    pub fn parse(text: &str) -> Self {
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for line in text.lines() {
+            let continues = entries.last().map_or(false, |(_, value)| {
+                !starts_option(line) || is_open(value)
+            });
+            if continues {
+                if let Some((_, value)) = entries.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                entries.push((key.to_string(), value.to_string()));
+            }
+        }
+        Self { entries }
+    }
+
+    /// The value of an option as written
+    pub fn raw(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(x, _)| x == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get(&self, key: &str) -> Option<OptionValue> {
+        self.raw(key).map(OptionValue::parse)
+    }
+
+    /// Every option with its value as written, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Every option with its typed value
+    pub fn typed(&self) -> BTreeMap<String, OptionValue> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.clone(), OptionValue::parse(value)))
+            .collect()
+    }
+
+    /// Sets the value of an option as written, adding it at the end if the
+    /// file doesn't have it yet
+    pub fn set_raw(&mut self, key: &str, raw: String) {
+        match self.entries.iter_mut().find(|(x, _)| x == key) {
+            Some((_, value)) => *value = raw,
+            None => self.entries.push((key.to_string(), raw)),
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &OptionValue) {
+        self.set_raw(key, value.to_string());
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.entries.retain(|(x, _)| x != key);
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in &self.entries {
+            text.push_str(key);
+            text.push(':');
+            text.push_str(value);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// An option the launcher forced for a session, with the value it had before
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ForcedOption {
+    pub key: String,
+    /// Unset if the option wasn't in the file
+    pub original: Option<String>,
+    pub forced: String,
+}
+
+/// Checks that options can be written without breaking the file
+pub(crate) fn validate_options(
+    options: &[(String, String)],
+) -> crate::Result<()> {
+    if let Some((key, _)) = options.iter().find(|(key, value)| {
+        !starts_option(&format!("{key}:")) || value.contains('\n')
+    }) {
+        return Err(crate::ErrorKind::InputError(format!(
+            "Invalid game option: {key}"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+pub(crate) async fn read_options(
+    instance_path: &Path,
+) -> crate::Result<GameOptions> {
+    let path = instance_path.join(OPTIONS_FILE);
+    if !path.exists() {
+        return Ok(GameOptions::default());
+    }
+    Ok(GameOptions::parse(&io::read_to_string(&path).await?))
+}
+
+// The file is replaced in one step, so a game reading it never sees half of
+// it
+async fn write_options(
+    instance_path: &Path,
+    options: &GameOptions,
+) -> crate::Result<()> {
+    let state = State::get().await?;
+    write(
+        &instance_path.join(OPTIONS_FILE),
+        options.to_text().as_bytes(),
+        &state.io_semaphore,
+    )
+    .await
+}
+
+/// Sets options in the options.txt of an instance, leaving the others as
+/// written. Returns the values they had, to restore them later
+pub(crate) async fn apply_options(
+    instance_path: &Path,
+    options: &[(String, String)],
+) -> crate::Result<Vec<ForcedOption>> {
+    if options.is_empty() {
+        return Ok(Vec::new());
+    }
+    validate_options(options)?;
+
+    let mut game_options = read_options(instance_path).await?;
+    let forced = options
+        .iter()
+        .map(|(key, value)| ForcedOption {
+            key: key.clone(),
+            original: game_options.raw(key).map(String::from),
+            forced: value.clone(),
+        })
+        .collect::<Vec<_>>();
+    for (key, value) in options {
+        game_options.set_raw(key, value.clone());
+    }
+    write_options(instance_path, &game_options).await?;
+
+    Ok(forced)
+}
+
+/// Puts back the values forced options had, except for the ones changed in
+/// game since
+pub(crate) async fn restore_options(
+    instance_path: &Path,
+    forced: &[ForcedOption],
+) -> crate::Result<()> {
+    let mut game_options = read_options(instance_path).await?;
+    let mut changed = false;
+    for option in forced {
+        if game_options.raw(&option.key) != Some(option.forced.as_str()) {
+            continue;
+        }
+        match &option.original {
+            Some(original) => {
+                game_options.set_raw(&option.key, original.clone())
+            }
+            None => game_options.remove(&option.key),
+        }
+        changed = true;
+    }
+
+    if changed {
+        write_options(instance_path, &game_options).await?;
+    }
+    Ok(())
+}
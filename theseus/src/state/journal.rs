@@ -0,0 +1,317 @@
+//! Journal of operations that leave a profile half installed if the launcher
+//! stops in the middle of them. Whatever is still in it on the next start
+//! was interrupted, and is recovered from before profiles are used
+use super::profiles::PROFILE_JSON_PATH;
+use super::{DirectoryInfo, ProfileInstallStage, ProfilePathId, Profiles};
+use crate::event::emit::emit_coded_warning;
+use crate::util::fetch::{read_json, write, IoSemaphore, BANDWIDTH_LIMITER};
+use crate::util::io;
+use crate::State;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+pub const JOURNAL_FILE_NAME: &str = "journal.json";
+
+lazy_static! {
+    // Operations in flight, written out whenever one starts or ends
+    static ref JOURNAL: Mutex<Vec<JournalEntry>> = Mutex::new(Vec::new());
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// Downloading the game, loader and libraries of a profile
+    Install,
+    /// Installing a modpack into a profile created for it
+    PackInstall,
+    /// Replacing the files of a managed modpack with another version
+    PackUpdate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub profile_path: ProfilePathId,
+    pub kind: OperationKind,
+    pub started: DateTime<Utc>,
+}
+
+/// What is done about an interrupted operation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Recovery {
+    /// The profile is marked not installed, so the next launch or install
+    /// finishes the job, reusing the files already downloaded
+    Resume,
+    /// The half installed profile is moved out of the profiles folder, as a
+    /// failed install would be removed
+    Rollback,
+}
+
+impl OperationKind {
+    fn recovery(&self) -> Recovery {
+        match self {
+            OperationKind::PackInstall => Recovery::Rollback,
+            OperationKind::Install | OperationKind::PackUpdate => {
+                Recovery::Resume
+            }
+        }
+    }
+}
+
+pub struct Journal;
+
+impl Journal {
+    /// Runs an operation on a profile, journaled so it is recovered from if
+    /// the launcher stops before it ends. A profile left installing by one
+    /// that fails is marked not installed right away
+    pub async fn run<T, F>(
+        profile_path: &ProfilePathId,
+        kind: OperationKind,
+        operation: F,
+    ) -> crate::Result<T>
+    where
+        F: Future<Output = crate::Result<T>>,
+    {
+        // Pack installs and updates wait for running games, but not game
+        // installs, which launches depend on
+        if kind != OperationKind::Install && BANDWIDTH_LIMITER.installs_paused()
+        {
+            tracing::info!(
+                "{kind:?} of {profile_path} waits for games to exit"
+            );
+            emit_coded_warning(
+                &format!("Installing {profile_path} once running games exit"),
+                Some("installs_paused"),
+            )
+            .await?;
+            BANDWIDTH_LIMITER.wait_for_installs().await;
+        }
+
+        let id = Self::begin(profile_path, kind).await?;
+        let result = crate::logger::capture(
+            format!("{kind:?} of {profile_path}"),
+            operation,
+        )
+        .await;
+        if result.is_err() {
+            reset_install_stage(profile_path).await;
+        }
+        Self::end(id).await?;
+        result
+    }
+
+    /// Holds off operations from starting or ending while the ones in flight
+    /// are read
+    pub(crate) async fn lock() -> MutexGuard<'static, Vec<JournalEntry>> {
+        JOURNAL.lock().await
+    }
+
+    async fn begin(
+        profile_path: &ProfilePathId,
+        kind: OperationKind,
+    ) -> crate::Result<Uuid> {
+        let entry = JournalEntry {
+            id: Uuid::new_v4(),
+            profile_path: profile_path.clone(),
+            kind,
+            started: Utc::now(),
+        };
+        let id = entry.id;
+
+        let mut journal = JOURNAL.lock().await;
+        journal.push(entry);
+        Self::persist(&journal).await?;
+        Ok(id)
+    }
+
+    async fn end(id: Uuid) -> crate::Result<()> {
+        let mut journal = JOURNAL.lock().await;
+        journal.retain(|x| x.id != id);
+        Self::persist(&journal).await
+    }
+
+    async fn persist(journal: &[JournalEntry]) -> crate::Result<()> {
+        let state = State::get().await?;
+        let path = state
+            .directories
+            .config_dir
+            .read()
+            .await
+            .join(JOURNAL_FILE_NAME);
+        write(&path, &serde_json::to_vec(journal)?, &state.io_semaphore).await
+    }
+
+    /// Recovers from the operations a previous run of the launcher didn't
+    /// finish, while the state is loading. Profiles left installing without
+    /// an entry, from before the journal, are resumed too
+    #[tracing::instrument(skip(profiles, io_semaphore))]
+    pub(crate) async fn recover(
+        dirs: &DirectoryInfo,
+        profiles: &mut Profiles,
+        io_semaphore: &IoSemaphore,
+    ) -> crate::Result<()> {
+        let journal_path = dirs.config_dir.read().await.join(JOURNAL_FILE_NAME);
+        let entries: Vec<JournalEntry> = if journal_path.exists() {
+            read_json(&journal_path, io_semaphore)
+                .await
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        let mut interrupted = entries
+            .iter()
+            .map(|x| (x.profile_path.clone(), Some(x)))
+            .collect::<Vec<_>>();
+        // Nothing runs before the state is loaded, so any profile still
+        // installing was stopped
+        for (profile_path, profile) in &profiles.0 {
+            let installing = matches!(
+                profile.install_stage,
+                ProfileInstallStage::Installing
+                    | ProfileInstallStage::PackInstalling
+            );
+            if installing && !interrupted.iter().any(|x| &x.0 == profile_path) {
+                interrupted.push((profile_path.clone(), None));
+            }
+        }
+
+        // Each operation is recovered from on its own, and those that fail
+        // are kept in the journal to try again on the next start
+        let mut failed = Vec::new();
+        for (profile_path, entry) in interrupted {
+            let kind = entry.map(|x| x.kind);
+            match recover_profile(
+                dirs,
+                profiles,
+                &profile_path,
+                kind,
+                io_semaphore,
+            )
+            .await
+            {
+                Ok(Some(message)) => {
+                    tracing::warn!("{message}");
+                    if let Err(err) = emit_coded_warning(
+                        &message,
+                        Some("interrupted_install"),
+                    )
+                    .await
+                    {
+                        tracing::warn!("Unable to emit warning: {err}");
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::error!(
+                        "Unable to recover {profile_path} from an interrupted \
+                         {kind:?}: {err}"
+                    );
+                    failed.extend(entry.cloned());
+                }
+            }
+        }
+
+        if !failed.is_empty() {
+            write(&journal_path, &serde_json::to_vec(&failed)?, io_semaphore)
+                .await?;
+        } else if journal_path.exists() {
+            io::remove_file(&journal_path).await?;
+        }
+        Ok(())
+    }
+}
+
+// Recovers a profile from an operation that was interrupted, returning what
+// was done about it for the user
+async fn recover_profile(
+    dirs: &DirectoryInfo,
+    profiles: &mut Profiles,
+    profile_path: &ProfilePathId,
+    kind: Option<OperationKind>,
+    io_semaphore: &IoSemaphore,
+) -> crate::Result<Option<String>> {
+    let Some(profile) = profiles.0.get_mut(profile_path) else {
+        return Ok(None);
+    };
+    let profile_dir = dirs.profiles_dir().await.join(&profile.path);
+    let name = profile.metadata.name.clone();
+
+    let recovery = kind.map_or(Recovery::Resume, |x| x.recovery());
+    if recovery == Recovery::Rollback {
+        // The folder is moved aside rather than deleted, in case it holds
+        // anything the user put there. Failing that, the install is resumed
+        let backups_dir = dirs.interrupted_installs_dir().await;
+        let backup_dir = backups_dir.join(format!(
+            "{}-{}",
+            profile.path.display(),
+            Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        let moved = async {
+            io::create_dir_all(&backups_dir).await?;
+            io::rename(&profile_dir, &backup_dir).await
+        }
+        .await;
+        match moved {
+            Ok(()) => {
+                profiles.0.remove(profile_path);
+                return Ok(Some(format!(
+                    "Installing the modpack {name} was interrupted, the half \
+                     installed profile was moved to {}",
+                    backup_dir.display()
+                )));
+            }
+            Err(err) => tracing::warn!(
+                "Unable to move {} aside, resuming its install instead: {err}",
+                profile_dir.display()
+            ),
+        }
+    }
+
+    profile.install_stage = ProfileInstallStage::NotInstalled;
+    write(
+        &profile_dir.join(PROFILE_JSON_PATH),
+        &serde_json::to_vec(&profile)?,
+        io_semaphore,
+    )
+    .await?;
+    Ok(Some(if kind == Some(OperationKind::PackUpdate) {
+        format!(
+            "Updating the modpack of {name} was interrupted, repair the \
+             modpack to restore its files"
+        )
+    } else {
+        format!(
+            "Installing {name} was interrupted, it will finish installing on \
+             next launch"
+        )
+    }))
+}
+
+// Failed operations leave the install stage they set, which would block
+// launching until the launcher is restarted
+async fn reset_install_stage(profile_path: &ProfilePathId) {
+    let res = crate::profile::edit(profile_path, |prof| {
+        if matches!(
+            prof.install_stage,
+            ProfileInstallStage::Installing
+                | ProfileInstallStage::PackInstalling
+        ) {
+            prof.install_stage = ProfileInstallStage::NotInstalled;
+        }
+        async { Ok(()) }
+    })
+    .await;
+
+    // Profiles removed by the failed operation have nothing to reset
+    if res.is_ok() {
+        if let Err(err) = State::sync().await {
+            tracing::warn!("Unable to reset install stage: {err}");
+        }
+    }
+}
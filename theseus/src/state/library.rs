@@ -0,0 +1,68 @@
+//! How profiles are organized in the library: the groups users sort them
+//! into and the order they are listed in
+use super::{DirectoryInfo, Profiles};
+use crate::util::fetch::{read_json, write, IoSemaphore};
+use crate::State;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const LIBRARY_JSON: &str = "library.json";
+
+/// Groups and ordering of the profiles in the launcher. Which groups a
+/// profile is in is stored in its metadata, so it travels with the profile
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ProfileLibrary {
+    /// Groups in the order they are listed, including those without profiles
+    pub groups: Vec<String>,
+    /// Profiles in the order set by the user. Profiles missing from it are
+    /// listed after these, by name
+    pub order: Vec<Uuid>,
+}
+
+impl ProfileLibrary {
+    /// Loads the library, adding the groups of profiles that aren't in it,
+    /// such as those of imported profiles
+    pub async fn init(
+        dirs: &DirectoryInfo,
+        profiles: &Profiles,
+        io_semaphore: &IoSemaphore,
+    ) -> Self {
+        let library_path = dirs.config_dir.read().await.join(LIBRARY_JSON);
+        let mut library: Self = read_json(&library_path, io_semaphore)
+            .await
+            .unwrap_or_default();
+
+        let mut profiles = profiles.0.values().collect::<Vec<_>>();
+        profiles.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        for group in profiles.iter().flat_map(|x| &x.metadata.groups) {
+            if !library.groups.contains(group) {
+                library.groups.push(group.clone());
+            }
+        }
+
+        library
+    }
+
+    pub async fn save(&self) -> crate::Result<()> {
+        let state = State::get().await?;
+        let library_path =
+            state.directories.config_dir.read().await.join(LIBRARY_JSON);
+        write(
+            &library_path,
+            &serde_json::to_vec(self)?,
+            &state.io_semaphore,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Position of a profile in the library, for sorting
+    pub fn position(&self, uuid: &Uuid) -> usize {
+        self.order
+            .iter()
+            .position(|x| x == uuid)
+            .unwrap_or(self.order.len())
+    }
+}
@@ -1,13 +1,14 @@
 //! Theseus metadata
 use crate::data::DirectoryInfo;
-use crate::util::fetch::{read_json, write, IoSemaphore};
+use crate::util::fetch::{
+    fetch_cached, read_json, write, FetchSemaphore, IoSemaphore,
+};
 use crate::State;
 use daedalus::{
-    minecraft::{fetch_version_manifest, VersionManifest as MinecraftManifest},
-    modded::{
-        fetch_manifest as fetch_loader_manifest, Manifest as LoaderManifest,
-    },
+    minecraft::VersionManifest as MinecraftManifest,
+    modded::Manifest as LoaderManifest,
 };
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 const METADATA_URL: &str = "https://meta.modrinth.com";
@@ -18,6 +19,15 @@ pub struct Metadata {
     pub forge: LoaderManifest,
     pub fabric: LoaderManifest,
     pub quilt: LoaderManifest,
+    // Missing from metadata cached before NeoForge was supported
+    #[serde(default = "empty_manifest")]
+    pub neoforge: LoaderManifest,
+}
+
+fn empty_manifest() -> LoaderManifest {
+    LoaderManifest {
+        game_versions: Vec::new(),
+    }
 }
 
 impl Metadata {
@@ -25,24 +35,61 @@ impl Metadata {
         format!("{METADATA_URL}/{name}/v0/manifest.json")
     }
 
-    pub async fn fetch() -> crate::Result<Self> {
-        let (minecraft, forge, fabric, quilt) = tokio::try_join! {
-            async {
-                let url = Self::get_manifest("minecraft");
-                fetch_version_manifest(Some(&url)).await
-            },
-            async {
-                let url = Self::get_manifest("forge");
-                fetch_loader_manifest(&url).await
-            },
-            async {
-                let url = Self::get_manifest("fabric");
-                fetch_loader_manifest(&url).await
-            },
-            async {
-                let url = Self::get_manifest("quilt");
-                fetch_loader_manifest(&url).await
-            }
+    // Manifests are revalidated through the HTTP cache, so unchanged ones
+    // are not downloaded again
+    async fn fetch_manifest<T: DeserializeOwned>(
+        name: &str,
+        dirs: &DirectoryInfo,
+        fetch_semaphore: &FetchSemaphore,
+        io_semaphore: &IoSemaphore,
+    ) -> crate::Result<T> {
+        let bytes = fetch_cached(
+            &Self::get_manifest(name),
+            None,
+            &dirs.http_cache_dir(),
+            fetch_semaphore,
+            io_semaphore,
+        )
+        .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub async fn fetch(
+        dirs: &DirectoryInfo,
+        fetch_semaphore: &FetchSemaphore,
+        io_semaphore: &IoSemaphore,
+    ) -> crate::Result<Self> {
+        let (minecraft, forge, fabric, quilt, neoforge) = tokio::try_join! {
+            Self::fetch_manifest(
+                "minecraft",
+                dirs,
+                fetch_semaphore,
+                io_semaphore
+            ),
+            Self::fetch_manifest(
+                "forge",
+                dirs,
+                fetch_semaphore,
+                io_semaphore
+            ),
+            Self::fetch_manifest(
+                "fabric",
+                dirs,
+                fetch_semaphore,
+                io_semaphore
+            ),
+            Self::fetch_manifest(
+                "quilt",
+                dirs,
+                fetch_semaphore,
+                io_semaphore
+            ),
+            Self::fetch_manifest(
+                "neo",
+                dirs,
+                fetch_semaphore,
+                io_semaphore
+            )
         }?;
 
         Ok(Self {
@@ -50,16 +97,18 @@ impl Metadata {
             forge,
             fabric,
             quilt,
+            neoforge,
         })
     }
 
     // Attempt to fetch metadata and store in sled DB
-    #[tracing::instrument(skip(io_semaphore))]
+    #[tracing::instrument(skip(io_semaphore, fetch_semaphore))]
     #[theseus_macros::debug_pin]
     pub async fn init(
         dirs: &DirectoryInfo,
         fetch_online: bool,
         io_semaphore: &IoSemaphore,
+        fetch_semaphore: &FetchSemaphore,
     ) -> crate::Result<Self> {
         let mut metadata = None;
         let metadata_path = dirs.caches_meta_dir().await.join("metadata.json");
@@ -70,7 +119,8 @@ impl Metadata {
             metadata = Some(metadata_json);
         } else if fetch_online {
             let res = async {
-                let metadata_fetch = Self::fetch().await?;
+                let metadata_fetch =
+                    Self::fetch(dirs, fetch_semaphore, io_semaphore).await?;
 
                 write(
                     &metadata_path,
@@ -104,8 +154,13 @@ impl Metadata {
 
     pub async fn update() {
         let res = async {
-            let metadata_fetch = Metadata::fetch().await?;
             let state = State::get().await?;
+            let metadata_fetch = Metadata::fetch(
+                &state.directories,
+                &state.fetch_semaphore,
+                &state.io_semaphore,
+            )
+            .await?;
 
             let metadata_path = state
                 .directories
@@ -0,0 +1,130 @@
+//! Extensions hooking into the launch of a game
+use crate::launcher::args::JavaArg;
+use crate::state::ProfilePathId;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[cfg(feature = "launch_middleware")]
+use futures::future::BoxFuture;
+#[cfg(feature = "launch_middleware")]
+use std::sync::Arc;
+
+/// The process a launch is about to start
+#[derive(Debug, Clone)]
+pub struct LaunchPlan {
+    pub profile_path: ProfilePathId,
+    /// UUID the game process will be tracked under
+    pub uuid: Uuid,
+    /// Java, or the wrapper command of the profile
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Variables of the launcher's own environment the game doesn't inherit
+    pub env_remove: Vec<String>,
+    pub working_dir: PathBuf,
+    /// LWJGL version substituted for the one the game ships with, if the
+    /// profile overrides it
+    pub lwjgl_override: Option<String>,
+    /// JVM arguments of the pack and user, merged, with where each came
+    /// from. They are part of `args` already, so changing them does nothing
+    pub java_args: Vec<JavaArg>,
+    /// Class the game is started from, which the profile may override. It
+    /// is part of `args` too
+    pub main_class: String,
+    /// Game arguments the profile left out of `args`
+    pub removed_game_args: Vec<String>,
+}
+
+/// Observes or changes launches. Middlewares only need to implement the
+/// callbacks they care about, the rest do nothing
+#[cfg(feature = "launch_middleware")]
+pub trait LaunchMiddleware: Send + Sync {
+    /// Unique id the middleware is registered under
+    fn id(&self) -> &str;
+
+    /// Called before the game is started, in order of registration. An error
+    /// cancels the launch
+    fn before_launch<'a>(
+        &'a self,
+        _plan: &'a mut LaunchPlan,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Called once the game process has been spawned
+    fn launched<'a>(
+        &'a self,
+        _plan: &'a LaunchPlan,
+        _pid: u32,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// Called once the game process has exited. Games reattached to after a
+    /// launcher restart always report an exit code of 0, as the real one
+    /// can't be known
+    fn exited<'a>(
+        &'a self,
+        _profile_path: &'a ProfilePathId,
+        _uuid: Uuid,
+        _exit_code: Option<i32>,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// Middlewares registered with the launcher, in the order they run
+#[cfg(feature = "launch_middleware")]
+#[derive(Default)]
+pub struct LaunchMiddlewares(Vec<Arc<dyn LaunchMiddleware>>);
+
+#[cfg(feature = "launch_middleware")]
+impl LaunchMiddlewares {
+    /// Adds a middleware, replacing any registered under the same id
+    pub fn register(&mut self, middleware: Arc<dyn LaunchMiddleware>) {
+        match self.0.iter().position(|x| x.id() == middleware.id()) {
+            Some(index) => self.0[index] = middleware,
+            None => self.0.push(middleware),
+        }
+    }
+
+    pub fn unregister(&mut self, id: &str) {
+        self.0.retain(|x| x.id() != id);
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.0.iter().map(|x| x.id().to_string()).collect()
+    }
+
+    pub async fn before_launch(
+        &self,
+        plan: &mut LaunchPlan,
+    ) -> crate::Result<()> {
+        for middleware in &self.0 {
+            middleware.before_launch(plan).await.map_err(|err| {
+                crate::ErrorKind::LauncherError(format!(
+                    "Launch middleware {} failed: {err}",
+                    middleware.id()
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    pub async fn launched(&self, plan: &LaunchPlan, pid: u32) {
+        for middleware in &self.0 {
+            middleware.launched(plan, pid).await;
+        }
+    }
+
+    pub async fn exited(
+        &self,
+        profile_path: &ProfilePathId,
+        uuid: Uuid,
+        exit_code: Option<i32>,
+    ) {
+        for middleware in &self.0 {
+            middleware.exited(profile_path, uuid, exit_code).await;
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Versioned formats of the documents the launcher persists, upgraded to the
+//! current format as they are loaded
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A step upgrading a document from one format version to the next
+pub struct Migration {
+    pub from: u32,
+    pub migrate: fn(&mut Value) -> crate::Result<()>,
+}
+
+/// A persisted document that records the version of its format
+pub trait Versioned: DeserializeOwned {
+    /// What the document is called in errors
+    const NAME: &'static str;
+    /// Field of the document holding its format version. Documents from
+    /// before formats were versioned lack it, and are taken as version 1
+    const VERSION_FIELD: &'static str;
+    const CURRENT_VERSION: u32;
+    /// Steps upgrading every older version, each one to the version after
+    /// its `from`
+    const MIGRATIONS: &'static [Migration];
+}
+
+/// Reads a document, upgrading it from older formats. Documents written by
+/// newer releases, which this one can't know how to read, are refused
+pub fn load_versioned<T: Versioned>(bytes: &[u8]) -> crate::Result<T> {
+    let mut value = serde_json::from_slice::<Value>(bytes)?;
+
+    let version = value
+        .get(T::VERSION_FIELD)
+        .map_or(Some(1), |x| x.as_u64().and_then(|x| u32::try_from(x).ok()));
+    let Some(mut version) = version else {
+        return Err(crate::ErrorKind::FormatVersionError(format!(
+            "{} has an invalid format version",
+            T::NAME
+        ))
+        .into());
+    };
+    if version > T::CURRENT_VERSION {
+        return Err(crate::ErrorKind::FormatVersionError(format!(
+            "{} was saved by a newer version of the launcher, in format \
+             {version}. This version reads up to format {}, update the \
+             launcher to load it",
+            T::NAME,
+            T::CURRENT_VERSION
+        ))
+        .into());
+    }
+
+    while version < T::CURRENT_VERSION {
+        let migration = T::MIGRATIONS
+            .iter()
+            .find(|x| x.from == version)
+            .ok_or_else(|| {
+                crate::ErrorKind::FormatVersionError(format!(
+                    "No way to upgrade {} from format {version}",
+                    T::NAME
+                ))
+            })?;
+        (migration.migrate)(&mut value)?;
+        version += 1;
+        tracing::info!("Upgraded {} to format {version}", T::NAME);
+    }
+
+    if let Value::Object(map) = &mut value {
+        map.insert(T::VERSION_FIELD.to_string(), Value::from(version));
+    }
+    Ok(serde_json::from_value(value)?)
+}
@@ -1,5 +1,11 @@
 //! Theseus state management system
-use crate::event::emit::{emit_loading, emit_offline, init_loading_unsafe};
+use crate::event::emit::{
+    emit_coded_warning, emit_loading, emit_offline, emit_ready,
+    init_loading_unsafe,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::path::PathBuf;
 
 use crate::event::LoadingBarType;
@@ -29,9 +35,15 @@ pub use self::profiles::*;
 mod settings;
 pub use self::settings::*;
 
+mod migrations;
+pub use self::migrations::*;
+
 mod projects;
 pub use self::projects::*;
 
+mod mod_index;
+pub use self::mod_index::*;
+
 mod users;
 
 mod children;
@@ -55,12 +67,57 @@ pub use self::discord::*;
 mod mr_auth;
 pub use self::mr_auth::*;
 
+mod sources;
+pub use self::sources::*;
+
+mod middleware;
+pub use self::middleware::*;
+
+mod scheduler;
+pub use self::scheduler::*;
+
+mod journal;
+pub use self::journal::*;
+
+mod library;
+pub use self::library::*;
+
+mod snapshot;
+pub use self::snapshot::*;
+
+pub(crate) mod policy;
+pub use self::policy::{
+    BlockReason, BlockedContent, ContentPolicy, PolicyViolation,
+};
+
+pub(crate) mod game_options;
+pub use self::game_options::{GameOptionOverrides, GameOptions, OptionValue};
+
 // Global state
 // RwLock on state only has concurrent reads, except for config dir change which takes control of the State
 static LAUNCHER_STATE: OnceCell<RwLock<State>> = OnceCell::const_new();
+
+/// Parts of the state loaded by background jobs once the launcher started,
+/// each announced by a `ready` event when done
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum StateComponent {
+    /// Whether the launcher is online was checked
+    Network,
+    /// Launcher metadata was refreshed from the web
+    Metadata,
+    Tags,
+    /// Projects of every profile were looked up, and pack updates checked
+    Projects,
+    /// Java installations were detected
+    Java,
+}
+
 pub struct State {
     /// Whether or not the launcher is currently operating in 'offline mode'
     pub offline: RwLock<bool>,
+    /// Parts of the state the background jobs finished loading
+    pub ready: RwLock<HashSet<StateComponent>>,
 
     /// Information on the location of files used in the launcher
     pub directories: DirectoryInfo,
@@ -88,12 +145,19 @@ pub struct State {
     pub(crate) safety_processes: RwLock<SafeProcesses>,
     /// Launcher user account info
     pub(crate) users: RwLock<Users>,
+    /// Groups and ordering of profiles
+    pub(crate) library: RwLock<ProfileLibrary>,
     /// Authentication flow
     pub auth_flow: RwLock<AuthTask>,
     /// Modrinth Credentials Store
     pub credentials: RwLock<CredentialsStore>,
     /// Modrinth auth flow
     pub modrinth_auth_flow: RwLock<Option<ModrinthAuthFlow>>,
+    /// Registries content can be installed from
+    pub content_sources: RwLock<ContentSources>,
+    /// Extensions hooking into launches
+    #[cfg(feature = "launch_middleware")]
+    pub launch_middlewares: RwLock<LaunchMiddlewares>,
 
     /// Discord RPC
     pub discord_rpc: DiscordGuard,
@@ -142,7 +206,20 @@ impl State {
             Settings::init(&DirectoryInfo::get_initial_settings_file()?)
                 .await?;
 
+        if !crate::logger::filter_from_env() {
+            if let Err(err) =
+                crate::logger::set_filter(&settings.logging.directives())
+            {
+                tracing::warn!("Unable to apply logging settings: {err}");
+            }
+        }
+
         let directories = DirectoryInfo::init(&settings)?;
+        for issue in directories.check_write_access().await {
+            tracing::warn!("{}", issue.message);
+            emit_coded_warning(&issue.message, Some("permission_denied"))
+                .await?;
+        }
 
         emit_loading(&loading_bar, 10.0, None).await?;
 
@@ -154,16 +231,24 @@ impl State {
         let io_semaphore = IoSemaphore(RwLock::new(Semaphore::new(
             settings.max_concurrent_writes,
         )));
+        fetch::BANDWIDTH_LIMITER.set_limits(
+            settings.max_download_speed,
+            settings.max_download_speed_while_running,
+        );
+        fetch::BANDWIDTH_LIMITER
+            .set_running_policy(settings.downloads_while_running);
+        fetch::configure_client(&settings.http)?;
         emit_loading(&loading_bar, 10.0, None).await?;
 
-        let is_offline = !fetch::check_internet(&fetch_semaphore, 3).await;
-
+        // Metadata and tags are read from their caches, and only fetched
+        // when missing. Whether the launcher is online is checked by
+        // `offine_check_loop` afterwards, not to wait on the network here
         let metadata_fut =
-            Metadata::init(&directories, !is_offline, &io_semaphore);
+            Metadata::init(&directories, true, &io_semaphore, &fetch_semaphore);
         let profiles_fut = Profiles::init(&directories, &mut file_watcher);
         let tags_fut = Tags::init(
             &directories,
-            !is_offline,
+            true,
             &io_semaphore,
             &fetch_semaphore,
             &CredentialsStore(None),
@@ -171,7 +256,7 @@ impl State {
         let users_fut = Users::init(&directories, &io_semaphore);
         let creds_fut = CredentialsStore::init(&directories, &io_semaphore);
         // Launcher data
-        let (metadata, profiles, tags, users, creds) = loading_join! {
+        let (metadata, mut profiles, tags, users, creds) = loading_join! {
             Some(&loading_bar), 70.0, Some("Loading metadata");
             metadata_fut,
             profiles_fut,
@@ -180,6 +265,14 @@ impl State {
             creds_fut,
         }?;
 
+        if let Err(err) =
+            Journal::recover(&directories, &mut profiles, &io_semaphore).await
+        {
+            tracing::warn!("Unable to recover interrupted operations: {err}");
+        }
+        let library =
+            ProfileLibrary::init(&directories, &profiles, &io_semaphore).await;
+
         let children = Children::new();
         let auth_flow = AuthTask::new();
         let safety_processes = SafeProcesses::new();
@@ -188,11 +281,19 @@ impl State {
 
         // Starts a loop of checking if we are online, and updating
         Self::offine_check_loop();
+        Users::schedule_validation();
+        Profiles::schedule_update_checks(&settings.update_checks);
+        crate::launcher::session::startup_task();
+        crate::logs::schedule_clean_logs();
+        #[cfg(feature = "tauri")]
+        crate::status::start_status_events();
+        Scheduler::start();
 
         emit_loading(&loading_bar, 10.0, None).await?;
 
         Ok::<RwLock<Self>, crate::Error>(RwLock::new(Self {
-            offline: RwLock::new(is_offline),
+            offline: RwLock::new(false),
+            ready: RwLock::new(HashSet::new()),
             directories,
             fetch_semaphore,
             fetch_semaphore_max: RwLock::new(
@@ -206,6 +307,7 @@ impl State {
             settings: RwLock::new(settings),
             profiles: RwLock::new(profiles),
             users: RwLock::new(users),
+            library: RwLock::new(library),
             children: RwLock::new(children),
             auth_flow: RwLock::new(auth_flow),
             credentials: RwLock::new(creds),
@@ -214,16 +316,24 @@ impl State {
             safety_processes: RwLock::new(safety_processes),
             file_watcher: RwLock::new(file_watcher),
             modrinth_auth_flow: RwLock::new(None),
+            content_sources: RwLock::new(ContentSources::new()),
+            #[cfg(feature = "launch_middleware")]
+            launch_middlewares: RwLock::new(LaunchMiddlewares::default()),
         }))
     }
 
     /// Starts a loop of checking if we are online, and updating
     pub fn offine_check_loop() {
         tokio::task::spawn(async {
+            let mut checked = false;
             loop {
                 let state = Self::get().await;
                 if let Ok(state) = state {
                     let _ = state.refresh_offline().await;
+                    if !checked {
+                        checked = true;
+                        state.set_ready(StateComponent::Network).await;
+                    }
                 }
 
                 // Wait 5 seconds
@@ -232,29 +342,73 @@ impl State {
         });
     }
 
-    /// Updates state with data from the web, if we are online
+    /// Updates state with data from the web, if we are online, and detects
+    /// Java installations. Each part is marked ready once done, see
+    /// `StateComponent`
     pub fn update() {
-        tokio::task::spawn(Metadata::update());
-        tokio::task::spawn(Tags::update());
-        tokio::task::spawn(Profiles::update_projects());
-        tokio::task::spawn(Profiles::update_modrinth_versions());
-        tokio::task::spawn(CredentialsStore::update_creds());
-        tokio::task::spawn(async {
-            if let Ok(state) = crate::State::get().await {
+        Self::spawn_ready(
+            StateComponent::Metadata,
+            Self::when_online(Metadata::update()),
+        );
+        Self::spawn_ready(
+            StateComponent::Tags,
+            Self::when_online(Tags::update()),
+        );
+        Self::spawn_ready(
+            StateComponent::Projects,
+            Self::when_online(async {
+                join!(
+                    Profiles::update_projects(),
+                    Profiles::update_modrinth_versions()
+                );
+            }),
+        );
+        Self::spawn_ready(StateComponent::Java, Settings::update_java());
+        tokio::task::spawn(Self::when_online(CredentialsStore::update_creds()));
+    }
+
+    // Runs a job fetching from the web once the first check of whether we
+    // are online is done, skipping it if we are offline
+    async fn when_online(job: impl Future<Output = ()>) {
+        loop {
+            let Ok(state) = Self::get().await else {
+                return;
+            };
+            if state.is_ready(StateComponent::Network).await {
                 if !*state.offline.read().await {
-                    let res1 = Profiles::update_modrinth_versions();
-                    let res2 = Tags::update();
-                    let res3 = Metadata::update();
-                    let res4 = Profiles::update_projects();
-                    let res5 = Settings::update_java();
-                    let res6 = CredentialsStore::update_creds();
-
-                    let _ = join!(res1, res2, res3, res4, res5, res6);
+                    job.await;
                 }
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Whether a part of the state loaded in the background is ready
+    pub async fn is_ready(&self, component: StateComponent) -> bool {
+        self.ready.read().await.contains(&component)
+    }
+
+    // Runs a background job, marking a part of the state ready once done
+    fn spawn_ready(
+        component: StateComponent,
+        job: impl Future<Output = ()> + Send + 'static,
+    ) {
+        tokio::task::spawn(async move {
+            job.await;
+            if let Ok(state) = Self::get().await {
+                state.set_ready(component).await;
             }
         });
     }
 
+    async fn set_ready(&self, component: StateComponent) {
+        self.ready.write().await.insert(component);
+        if let Err(err) = emit_ready(component).await {
+            tracing::warn!("Unable to send readiness of {component:?}: {err}");
+        }
+    }
+
     #[tracing::instrument]
     #[theseus_macros::debug_pin]
     /// Synchronize in-memory state with persistent state
@@ -355,6 +509,8 @@ pub async fn init_watcher() -> crate::Result<Debouncer<RecommendedWatcher>> {
             match res {
                 Ok(mut events) => {
                     let mut visited_paths = Vec::new();
+                    let mut changed_files: HashMap<ProfilePathId, Vec<_>> =
+                        HashMap::new();
                     // sort events by e.path
                     events.sort_by(|a, b| a.path.cmp(&b.path));
                     events.iter().for_each(|e| {
@@ -392,6 +548,29 @@ pub async fn init_watcher() -> crate::Result<Debouncer<RecommendedWatcher>> {
                                 .unwrap_or(false)
                         {
                             Profile::crash_task(profile_path_id);
+                        } else if e.path.components().any(|x| {
+                            x.as_os_str()
+                                == crate::api::screenshots::SCREENSHOTS_FOLDER
+                        }) {
+                            crate::api::screenshots::screenshot_task(
+                                profile_path_id,
+                                e.path.clone(),
+                            );
+                        } else if ProjectType::get_from_parent_folder(
+                            e.path.clone(),
+                        )
+                        .is_some()
+                            && e.path.parent().and_then(|x| x.parent())
+                                == Some(new_path.as_path())
+                        {
+                            // Files dropped straight into a project folder
+                            // only need themselves looked up
+                            let paths = changed_files
+                                .entry(profile_path_id)
+                                .or_default();
+                            if !paths.contains(&e.path) {
+                                paths.push(e.path.clone());
+                            }
                         } else if !visited_paths.contains(&new_path) {
                             if subfile {
                                 Profile::sync_projects_task(profile_path_id);
@@ -403,6 +582,13 @@ pub async fn init_watcher() -> crate::Result<Debouncer<RecommendedWatcher>> {
                             }
                         }
                     });
+
+                    for (profile_path_id, paths) in changed_files {
+                        Profile::sync_project_files_task(
+                            profile_path_id,
+                            paths,
+                        );
+                    }
                 }
                 Err(errors) => errors.iter().for_each(|err| {
                     tracing::warn!("Unable to watch file: {err}")
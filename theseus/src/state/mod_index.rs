@@ -0,0 +1,397 @@
+//! Metadata read from the jars of projects that aren't on Modrinth, indexed
+//! by the hash of the file so each jar is only opened once
+use crate::util::fetch::{read_json, write, write_cached_icon, IoSemaphore};
+
+use async_zip::tokio::read::fs::ZipFileReader;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+const MOD_INDEX_FILE: &str = "mod_index.json";
+
+lazy_static! {
+    // Metadata of every jar read so far, by SHA-512. Jars without any are
+    // kept as `None`, so they aren't read again either
+    static ref INDEX: Mutex<Option<HashMap<String, Option<JarMetadata>>>> =
+        Mutex::new(None);
+}
+
+/// What a mod or pack says about itself in its jar
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct JarMetadata {
+    pub mod_id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub version: Option<String>,
+    /// Embedded icon, extracted to the icon cache
+    pub icon: Option<PathBuf>,
+    /// Unset for packs, whose type depends on the folder they're in
+    pub project_type: Option<String>,
+}
+
+pub struct ModIndex;
+
+impl ModIndex {
+    /// Gets the metadata of jars by their SHA-512 hashes, reading the ones
+    /// that aren't indexed yet
+    #[tracing::instrument(skip(files, io_semaphore))]
+    pub async fn get_many(
+        files: &[(String, PathBuf)],
+        cache_dir: &Path,
+        io_semaphore: &IoSemaphore,
+    ) -> crate::Result<HashMap<String, Option<JarMetadata>>> {
+        let index_path = cache_dir.join(MOD_INDEX_FILE);
+        let mut index = INDEX.lock().await;
+        if index.is_none() {
+            *index = Some(
+                read_json(&index_path, io_semaphore)
+                    .await
+                    .unwrap_or_default(),
+            );
+        }
+        let index = index.get_or_insert_with(HashMap::new);
+
+        let mut changed = false;
+        let mut found = HashMap::new();
+        for (hash, path) in files {
+            // Icons are gone once the cache is cleared
+            let cached = index.get(hash).filter(|x| match x {
+                Some(JarMetadata {
+                    icon: Some(icon), ..
+                }) => icon.exists(),
+                _ => true,
+            });
+            let metadata = match cached {
+                Some(metadata) => metadata.clone(),
+                None => {
+                    let metadata =
+                        read_jar(path, cache_dir, io_semaphore).await?;
+                    index.insert(hash.clone(), metadata.clone());
+                    changed = true;
+                    metadata
+                }
+            };
+            found.insert(hash.clone(), metadata);
+        }
+
+        if changed {
+            write(&index_path, &serde_json::to_vec(&*index)?, io_semaphore)
+                .await?;
+        }
+        Ok(found)
+    }
+}
+
+/// Reads the metadata of a mod jar from `mods.toml`, `mcmod.info`,
+/// `fabric.mod.json` or `quilt.mod.json`, or of a pack from `pack.mcmeta`
+#[tracing::instrument(skip(io_semaphore))]
+#[theseus_macros::debug_pin]
+pub async fn read_jar(
+    path: &Path,
+    cache_dir: &Path,
+    io_semaphore: &IoSemaphore,
+) -> crate::Result<Option<JarMetadata>> {
+    let Ok(zip_file_reader) = ZipFileReader::new(path.to_path_buf()).await
+    else {
+        return Ok(None);
+    };
+
+    // Forge, and NeoForge which renamed the file in later versions
+    if let Some(file_str) = read_entry(&zip_file_reader, |x| {
+        matches!(x, "META-INF/mods.toml" | "META-INF/neoforge.mods.toml")
+    })
+    .await?
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeModInfo {
+            pub mods: Vec<ForgeMod>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeMod {
+            mod_id: String,
+            version: Option<String>,
+            display_name: Option<String>,
+            description: Option<String>,
+            logo_file: Option<String>,
+            authors: Option<String>,
+        }
+
+        if let Ok(pack) = toml::from_str::<ForgeModInfo>(&file_str) {
+            if let Some(pack) = pack.mods.into_iter().next() {
+                let icon =
+                    read_icon(pack.logo_file, cache_dir, path, io_semaphore)
+                        .await?;
+                return Ok(Some(JarMetadata {
+                    title: Some(
+                        pack.display_name
+                            .unwrap_or_else(|| pack.mod_id.clone()),
+                    ),
+                    mod_id: Some(pack.mod_id),
+                    description: pack.description,
+                    authors: pack.authors.map(|x| vec![x]).unwrap_or_default(),
+                    // Versions filled in from the manifest at build time
+                    // aren't of use
+                    version: pack.version.filter(|x| !x.starts_with("${")),
+                    icon,
+                    project_type: Some("mod".to_string()),
+                }));
+            }
+        }
+    }
+
+    // Forge
+    if let Some(file_str) =
+        read_entry(&zip_file_reader, |x| x == "mcmod.info").await?
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ForgeMod {
+            modid: String,
+            name: String,
+            description: Option<String>,
+            version: Option<String>,
+            author_list: Option<Vec<String>>,
+            logo_file: Option<String>,
+        }
+
+        if let Ok(pack) = serde_json::from_str::<ForgeMod>(&file_str) {
+            let icon = read_icon(pack.logo_file, cache_dir, path, io_semaphore)
+                .await?;
+            return Ok(Some(JarMetadata {
+                title: Some(if pack.name.is_empty() {
+                    pack.modid.clone()
+                } else {
+                    pack.name
+                }),
+                mod_id: Some(pack.modid),
+                description: pack.description,
+                authors: pack.author_list.unwrap_or_default(),
+                version: pack.version,
+                icon,
+                project_type: Some("mod".to_string()),
+            }));
+        }
+    }
+
+    // Fabric
+    if let Some(file_str) =
+        read_entry(&zip_file_reader, |x| x == "fabric.mod.json").await?
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum FabricAuthor {
+            String(String),
+            Object { name: String },
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct FabricMod {
+            id: String,
+            version: String,
+            name: Option<String>,
+            description: Option<String>,
+            #[serde(default)]
+            authors: Vec<FabricAuthor>,
+            icon: Option<String>,
+        }
+
+        if let Ok(pack) = serde_json::from_str::<FabricMod>(&file_str) {
+            let icon =
+                read_icon(pack.icon, cache_dir, path, io_semaphore).await?;
+            return Ok(Some(JarMetadata {
+                title: Some(pack.name.unwrap_or_else(|| pack.id.clone())),
+                mod_id: Some(pack.id),
+                description: pack.description,
+                authors: pack
+                    .authors
+                    .into_iter()
+                    .map(|x| match x {
+                        FabricAuthor::String(name) => name,
+                        FabricAuthor::Object { name } => name,
+                    })
+                    .collect(),
+                version: Some(pack.version),
+                icon,
+                project_type: Some("mod".to_string()),
+            }));
+        }
+    }
+
+    // Quilt
+    if let Some(file_str) =
+        read_entry(&zip_file_reader, |x| x == "quilt.mod.json").await?
+    {
+        #[derive(Deserialize)]
+        struct QuiltMetadata {
+            pub name: Option<String>,
+            pub description: Option<String>,
+            pub contributors: Option<HashMap<String, String>>,
+            pub icon: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct QuiltLoader {
+            id: String,
+            version: String,
+            metadata: Option<QuiltMetadata>,
+        }
+        #[derive(Deserialize)]
+        struct QuiltMod {
+            quilt_loader: QuiltLoader,
+        }
+
+        if let Ok(pack) = serde_json::from_str::<QuiltMod>(&file_str) {
+            let pack = pack.quilt_loader;
+            let metadata = pack.metadata.unwrap_or(QuiltMetadata {
+                name: None,
+                description: None,
+                contributors: None,
+                icon: None,
+            });
+            let icon =
+                read_icon(metadata.icon, cache_dir, path, io_semaphore).await?;
+            return Ok(Some(JarMetadata {
+                title: Some(metadata.name.unwrap_or_else(|| pack.id.clone())),
+                mod_id: Some(pack.id),
+                description: metadata.description,
+                authors: metadata
+                    .contributors
+                    .unwrap_or_default()
+                    .into_keys()
+                    .collect(),
+                version: Some(pack.version),
+                icon,
+                project_type: Some("mod".to_string()),
+            }));
+        }
+    }
+
+    // Resource packs, data packs and shaders
+    if let Some(file_str) =
+        read_entry(&zip_file_reader, |x| x == "pack.mcmeta").await?
+    {
+        #[derive(Deserialize)]
+        struct PackMcmeta {
+            pack: Pack,
+        }
+        #[derive(Deserialize)]
+        struct Pack {
+            description: Option<serde_json::Value>,
+        }
+
+        if let Ok(pack) = serde_json::from_str::<PackMcmeta>(&file_str) {
+            let icon = read_icon(
+                Some("pack.png".to_string()),
+                cache_dir,
+                path,
+                io_semaphore,
+            )
+            .await?;
+            return Ok(Some(JarMetadata {
+                description: pack.pack.description.map(text_component),
+                icon,
+                ..Default::default()
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+// Reads the first entry of an archive whose name matches as text
+async fn read_entry(
+    zip_file_reader: &ZipFileReader,
+    matches: impl Fn(&str) -> bool,
+) -> crate::Result<Option<String>> {
+    let Some(index) = zip_file_reader
+        .file()
+        .entries()
+        .iter()
+        .position(|f| matches(f.entry().filename()))
+    else {
+        return Ok(None);
+    };
+    let entry = zip_file_reader.file().entries()[index].entry();
+
+    let mut file_str = String::new();
+    Ok(zip_file_reader
+        .entry(index)
+        .await?
+        .read_to_string_checked(&mut file_str, entry)
+        .await
+        .ok()
+        .map(|_| file_str))
+}
+
+#[tracing::instrument(skip(io_semaphore))]
+#[theseus_macros::debug_pin]
+async fn read_icon(
+    icon_path: Option<String>,
+    cache_dir: &Path,
+    path: &Path,
+    io_semaphore: &IoSemaphore,
+) -> crate::Result<Option<PathBuf>> {
+    let Some(icon_path) = icon_path else {
+        return Ok(None);
+    };
+    let Ok(zip_file_reader) = ZipFileReader::new(path.to_path_buf()).await
+    else {
+        return Ok(None);
+    };
+    let Some(index) = zip_file_reader
+        .file()
+        .entries()
+        .iter()
+        .position(|f| f.entry().filename() == icon_path)
+    else {
+        return Ok(None);
+    };
+    let entry = zip_file_reader.file().entries()[index].entry();
+
+    let mut bytes = Vec::new();
+    if zip_file_reader
+        .entry(index)
+        .await?
+        .read_to_end_checked(&mut bytes, entry)
+        .await
+        .is_err()
+    {
+        return Ok(None);
+    }
+
+    let path = write_cached_icon(
+        &icon_path,
+        cache_dir,
+        bytes::Bytes::from(bytes),
+        io_semaphore,
+    )
+    .await?;
+    Ok(Some(path))
+}
+
+// Pack descriptions are text components: plain strings, lists of them or
+// objects with text and styling
+fn text_component(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text,
+        serde_json::Value::Array(parts) => {
+            parts.into_iter().map(text_component).collect()
+        }
+        serde_json::Value::Object(mut object) => {
+            let text = object
+                .remove("text")
+                .and_then(|x| x.as_str().map(|x| x.to_string()))
+                .unwrap_or_default();
+            let extra = object
+                .remove("extra")
+                .map(text_component)
+                .unwrap_or_default();
+            text + &extra
+        }
+        other => other.to_string(),
+    }
+}
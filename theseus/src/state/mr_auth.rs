@@ -1,7 +1,8 @@
 use crate::config::MODRINTH_API_URL;
 use crate::state::DirectoryInfo;
 use crate::util::fetch::{
-    fetch_advanced, read_json, write, FetchSemaphore, IoSemaphore,
+    connect_websocket, fetch_advanced, read_json, write, FetchSemaphore,
+    IoSemaphore, WebSocket,
 };
 use crate::State;
 use chrono::{DateTime, Duration, Utc};
@@ -108,14 +109,12 @@ impl CredentialsStore {
 }
 
 pub struct ModrinthAuthFlow {
-    socket: async_tungstenite::WebSocketStream<
-        async_tungstenite::tokio::ConnectStream,
-    >,
+    socket: WebSocket,
 }
 
 impl ModrinthAuthFlow {
     pub async fn new(provider: &str) -> crate::Result<Self> {
-        let (socket, _) = async_tungstenite::tokio::connect_async(format!(
+        let socket = connect_websocket(&format!(
             "wss://api.modrinth.com/v2/auth/ws?provider={provider}"
         ))
         .await?;
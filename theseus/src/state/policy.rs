@@ -0,0 +1,295 @@
+//! Content that can't be installed, for computers managed by schools and LAN
+//! centers
+use super::{fetch_projects, ModrinthProject, ModrinthVersion, ProjectType};
+use crate::config::MODRINTH_API_URL;
+use crate::util::fetch::{fetch_json, sha1_async};
+use crate::util::io::{self, IOError};
+use crate::State;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Projects that can't be installed, read from `content_policy.json` next to
+/// the settings file. Administrators deploy the file, it isn't edited from the
+/// launcher
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ContentPolicy {
+    /// Modrinth projects, by id or slug
+    pub blocked_projects: Vec<String>,
+    /// Modrinth categories, or project types such as `shader`
+    pub blocked_categories: Vec<String>,
+    /// The only Modrinth projects that can be installed, by id or slug, if
+    /// set. Blocked projects stay blocked even if listed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_projects: Option<Vec<String>>,
+}
+
+/// Why the content policy blocks a project
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockReason {
+    Project,
+    Category {
+        category: String,
+    },
+    /// An allowlist is set and doesn't list the project. Files that aren't
+    /// on Modrinth can't be listed
+    NotAllowed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockedContent {
+    /// Title of the project, or path of the file if it isn't on Modrinth
+    pub name: String,
+    pub project_id: Option<String>,
+    pub reason: BlockReason,
+}
+
+/// Everything an install was refused for
+#[derive(Debug, Clone)]
+pub struct PolicyViolation(pub Vec<BlockedContent>);
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let blocked = self
+            .0
+            .iter()
+            .map(|x| match &x.reason {
+                BlockReason::Project => format!("{} (blocked)", x.name),
+                BlockReason::Category { category } => {
+                    format!("{} (blocked category {category})", x.name)
+                }
+                BlockReason::NotAllowed => format!("{} (not allowed)", x.name),
+            })
+            .collect::<Vec<_>>();
+        write!(fmt, "{}", blocked.join("; "))
+    }
+}
+
+/// A file to install, checked against the content policy by the Modrinth
+/// project it is from
+pub(crate) struct PolicyFile {
+    pub path: String,
+    pub project_id: Option<String>,
+    pub sha1: Option<String>,
+}
+
+impl ContentPolicy {
+    /// Reads the content policy, none if there is no policy file. A policy
+    /// that can't be read fails installs rather than allowing everything
+    pub async fn get() -> crate::Result<Option<Self>> {
+        let state = State::get().await?;
+        let path = state.directories.content_policy_file();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let policy = serde_json::from_slice::<Self>(&io::read(&path).await?)
+            .map_err(|err| {
+                crate::ErrorKind::ConfigError(format!(
+                    "Invalid content policy {}: {err}",
+                    path.display()
+                ))
+            })?;
+        Ok(Some(policy))
+    }
+
+    fn lists(list: &[String], project: &ModrinthProject) -> bool {
+        list.iter().any(|x| {
+            x.eq_ignore_ascii_case(&project.id)
+                || project
+                    .slug
+                    .as_ref()
+                    .map_or(false, |slug| x.eq_ignore_ascii_case(slug))
+        })
+    }
+
+    fn check(&self, project: &ModrinthProject) -> Option<BlockReason> {
+        if Self::lists(&self.blocked_projects, project) {
+            return Some(BlockReason::Project);
+        }
+        if let Some(category) = project
+            .categories
+            .iter()
+            .chain(&project.additional_categories)
+            .chain(std::iter::once(&project.project_type))
+            .find(|x| {
+                self.blocked_categories
+                    .iter()
+                    .any(|blocked| blocked.eq_ignore_ascii_case(x))
+            })
+        {
+            return Some(BlockReason::Category {
+                category: category.clone(),
+            });
+        }
+        match &self.allowed_projects {
+            Some(allowed) if !Self::lists(allowed, project) => {
+                Some(BlockReason::NotAllowed)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Fails with a policy violation if any of the Modrinth projects is blocked
+pub(crate) async fn check_projects(
+    project_ids: &[String],
+) -> crate::Result<()> {
+    let files = project_ids
+        .iter()
+        .map(|x| PolicyFile {
+            path: x.clone(),
+            project_id: Some(x.clone()),
+            sha1: None,
+        })
+        .collect::<Vec<_>>();
+    check_files(&files).await
+}
+
+/// Fails with a policy violation if the project of any of the versions is
+/// blocked
+pub(crate) async fn check_versions(
+    versions: &[&ModrinthVersion],
+) -> crate::Result<()> {
+    let ids = versions
+        .iter()
+        .map(|x| x.project_id.clone())
+        .collect::<Vec<_>>();
+    check_projects(&ids).await
+}
+
+/// Fails with a policy violation if any file in the project folders of a
+/// profile, or a folder about to be copied into one, is from a blocked
+/// project. For installs that write files without looking them up first
+pub(crate) async fn check_folder(root: &Path) -> crate::Result<()> {
+    if ContentPolicy::get().await?.is_none() {
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    for project_type in [
+        ProjectType::Mod,
+        ProjectType::DataPack,
+        ProjectType::ResourcePack,
+        ProjectType::ShaderPack,
+    ] {
+        let folder = root.join(project_type.get_folder());
+        if !folder.is_dir() {
+            continue;
+        }
+        let mut read_dir = io::read_dir(&folder).await?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| IOError::with_path(e, &folder))?
+        {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let bytes = io::read(&path).await?;
+            files.push(PolicyFile {
+                path: format!(
+                    "{}/{}",
+                    project_type.get_folder(),
+                    entry.file_name().to_string_lossy()
+                ),
+                project_id: None,
+                sha1: Some(sha1_async(bytes.into()).await?),
+            });
+        }
+    }
+    check_files(&files).await
+}
+
+/// Fails with a policy violation if any of the files is from a blocked
+/// project. Files without a known project are looked up by hash
+pub(crate) async fn check_files(files: &[PolicyFile]) -> crate::Result<()> {
+    let Some(policy) = ContentPolicy::get().await? else {
+        return Ok(());
+    };
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let state = State::get().await?;
+    let creds = state.credentials.read().await;
+
+    let hashes = files
+        .iter()
+        .filter(|x| x.project_id.is_none())
+        .filter_map(|x| x.sha1.clone())
+        .collect::<Vec<_>>();
+    let by_hash = if hashes.is_empty() {
+        HashMap::new()
+    } else {
+        fetch_json::<HashMap<String, ModrinthVersion>>(
+            Method::POST,
+            &format!("{MODRINTH_API_URL}version_files"),
+            None,
+            Some(json!({
+                "hashes": hashes,
+                "algorithm": "sha1",
+            })),
+            &state.fetch_semaphore,
+            &creds,
+        )
+        .await?
+    };
+
+    let files = files
+        .iter()
+        .map(|x| {
+            let project_id = x.project_id.clone().or_else(|| {
+                x.sha1
+                    .as_ref()
+                    .and_then(|sha1| by_hash.get(sha1))
+                    .map(|version| version.project_id.clone())
+            });
+            (x.path.clone(), project_id)
+        })
+        .collect::<Vec<_>>();
+    let ids = files
+        .iter()
+        .filter_map(|(_, id)| id.clone())
+        .collect::<Vec<_>>();
+    let projects = fetch_projects(&ids, &state.fetch_semaphore, &creds).await?;
+    drop(creds);
+
+    let mut blocked = Vec::new();
+    for (path, project_id) in files {
+        // Projects are fetched by id or slug, so both are looked for
+        let project = project_id.as_ref().and_then(|id| {
+            projects
+                .values()
+                .find(|x| x.id == *id || x.slug.as_ref() == Some(id))
+        });
+        let reason = match project {
+            Some(project) => policy.check(project),
+            None if policy.allowed_projects.is_some() => {
+                Some(BlockReason::NotAllowed)
+            }
+            None => None,
+        };
+        let listed = blocked.iter().any(|x: &BlockedContent| {
+            x.project_id.is_some() && x.project_id == project_id
+        });
+        if let (Some(reason), false) = (reason, listed) {
+            blocked.push(BlockedContent {
+                name: project.map_or(path, |x| x.title.clone()),
+                project_id: project.map(|x| x.id.clone()).or(project_id),
+                reason,
+            });
+        }
+    }
+
+    if blocked.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::ErrorKind::PolicyViolation(PolicyViolation(blocked)).into())
+    }
+}
@@ -1,32 +1,56 @@
-use super::settings::{Hooks, MemorySettings, WindowSize};
+use super::settings::{Hooks, MemorySettings, UpdateChecks, WindowSize};
 use crate::config::MODRINTH_API_URL;
 use crate::data::DirectoryInfo;
-use crate::event::emit::{emit_profile, emit_warning};
-use crate::event::ProfilePayloadType;
+use crate::event::emit::{
+    emit_profile, emit_profile_loaded, emit_updates_available, emit_warning,
+};
+use crate::event::{
+    ProfilePayloadType, ProfileUpdates, ProjectUpdate, UpdatesAvailablePayload,
+};
 use crate::prelude::JavaVersion;
 use crate::state::projects::Project;
-use crate::state::{ModrinthVersion, ProjectMetadata, ProjectType};
+use crate::state::{
+    load_versioned, GameOptionOverrides, Migration, ModrinthVersion,
+    ProjectMetadata, ProjectType, Schedule, Scheduler, Versioned,
+};
 use crate::util::fetch::{
     fetch, fetch_json, write, write_cached_icon, IoSemaphore,
 };
 use crate::util::io::{self, IOError};
+use crate::util::platform;
 use crate::State;
 use chrono::{DateTime, Utc};
 use daedalus::get_hash;
 use daedalus::modded::LoaderVersion;
 use futures::prelude::*;
+use lazy_static::lazy_static;
 use notify::{RecommendedWatcher, RecursiveMode};
 use notify_debouncer_mini::Debouncer;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::io::Cursor;
+use std::sync::Mutex;
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
 };
 use uuid::Uuid;
 
-const PROFILE_JSON_PATH: &str = "profile.json";
+pub(crate) const PROFILE_JSON_PATH: &str = "profile.json";
+const PROFILE_FORMAT_VERSION: u32 = 1;
+// Profiles read at once while the launcher starts
+const PROFILE_LOAD_CONCURRENCY: usize = 16;
+
+lazy_static! {
+    // Updates last sent to frontends, so the same ones aren't sent again
+    static ref NOTIFIED_UPDATES: Mutex<Vec<ProfileUpdates>> =
+        Mutex::new(Vec::new());
+    // SHA-1 of the profile.json last written for each profile, so syncing
+    // only writes the profiles that changed
+    static ref WRITTEN_PROFILES: Mutex<HashMap<ProfilePathId, String>> =
+        Mutex::new(HashMap::new());
+}
 
 pub(crate) struct Profiles(pub HashMap<ProfilePathId, Profile>);
 
@@ -46,6 +70,54 @@ pub enum ProfileInstallStage {
     NotInstalled,
 }
 
+/// Where the libraries, assets and natives used by a profile are stored
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageMode {
+    /// Shared with every other profile, in the launcher's metadata directory
+    #[default]
+    Shared,
+    /// Kept inside the profile's own directory, so it can be moved around
+    /// as a portable install
+    Isolated,
+}
+
+/// Graphics card a profile asks the system to run the game on, for laptops
+/// with both an integrated and a discrete one
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuPreference {
+    /// Left to the system, which often picks the integrated card
+    #[default]
+    Default,
+    /// The discrete card, for performance
+    Discrete,
+    /// The integrated card, to save battery
+    Integrated,
+}
+
+/// Display server the game opens its window on, which only matters for
+/// Linux sessions running Wayland
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayServer {
+    /// Left to the game's GLFW, which is X11 through XWayland for the one
+    /// Minecraft ships, but may be Wayland for newer LWJGL versions
+    #[default]
+    Default,
+    /// X11 through XWayland, even for a GLFW that would pick Wayland
+    Xwayland,
+    /// Wayland itself, through the GLFW of the system, for versions on
+    /// LWJGL 3
+    Wayland,
+}
+
 /// newtype wrapper over a Profile path, to be usable as a clear identifier for the kind of path used
 /// eg: for "a/b/c/profiles/My Mod", the ProfilePathId would be "My Mod" (a relative path)
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
@@ -95,6 +167,123 @@ impl std::fmt::Display for ProfilePathId {
     }
 }
 
+/// A way of referring to a profile without knowing its path id beforehand,
+/// for CLI and automation callers
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ProfileSelector {
+    Uuid(Uuid),
+    /// Exact profile name. Falls back to the profile folder name if no
+    /// profile has this name
+    Name(String),
+    /// Either a path id relative to the profiles folder, or an absolute path
+    /// to a profile folder
+    Path(PathBuf),
+}
+
+impl ProfileSelector {
+    /// Resolves the selector against the loaded profiles
+    pub async fn resolve(&self) -> crate::Result<ProfilePathId> {
+        let state = State::get().await?;
+        let profiles = state.profiles.read().await;
+
+        let path = match self {
+            ProfileSelector::Uuid(uuid) => profiles
+                .0
+                .iter()
+                .find(|(_, x)| x.uuid == *uuid)
+                .map(|(path, _)| path.clone()),
+            ProfileSelector::Name(name) => {
+                let matches = profiles
+                    .0
+                    .iter()
+                    .filter(|(_, x)| x.metadata.name == *name)
+                    .map(|(path, _)| path)
+                    .collect::<Vec<_>>();
+
+                match matches.as_slice() {
+                    [] => Some(ProfilePathId::new(Path::new(name)))
+                        .filter(|x| profiles.0.contains_key(x)),
+                    [path] => Some((*path).clone()),
+                    paths => {
+                        return Err(crate::ErrorKind::InputError(format!(
+                            "Profile name {name} is ambiguous, it matches: {}",
+                            paths
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                        .as_error())
+                    }
+                }
+            }
+            ProfileSelector::Path(path) => {
+                let id = ProfilePathId::new(path);
+                // Relative paths that are not a path id are taken from the
+                // working directory, like a CLI argument would be
+                let id = if path.is_absolute()
+                    || (!profiles.0.contains_key(&id) && path.exists())
+                {
+                    let profiles_dir = io::canonicalize(
+                        state.directories.profiles_dir().await,
+                    )?;
+                    io::canonicalize(path)?
+                        .strip_prefix(profiles_dir)
+                        .ok()
+                        .map(ProfilePathId::new)
+                } else {
+                    Some(id)
+                };
+                id.filter(|x| profiles.0.contains_key(x))
+            }
+        };
+
+        path.ok_or_else(|| {
+            crate::ErrorKind::UnmanagedProfileError(self.to_string())
+                .as_error()
+        })
+    }
+}
+
+impl From<ProfilePathId> for ProfileSelector {
+    fn from(path: ProfilePathId) -> Self {
+        ProfileSelector::Path(path.0)
+    }
+}
+
+impl From<Uuid> for ProfileSelector {
+    fn from(uuid: Uuid) -> Self {
+        ProfileSelector::Uuid(uuid)
+    }
+}
+
+impl std::str::FromStr for ProfileSelector {
+    type Err = std::convert::Infallible;
+
+    /// Parses a CLI-style selector: a uuid, a path if the input contains a
+    /// path separator, or a profile name otherwise
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if let Ok(uuid) = Uuid::parse_str(s) {
+            ProfileSelector::Uuid(uuid)
+        } else if s.contains(std::path::is_separator) || s == "." {
+            ProfileSelector::Path(PathBuf::from(s))
+        } else {
+            ProfileSelector::Name(s.to_string())
+        })
+    }
+}
+
+impl std::fmt::Display for ProfileSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileSelector::Uuid(uuid) => uuid.fmt(f),
+            ProfileSelector::Name(name) => name.fmt(f),
+            ProfileSelector::Path(path) => path.display().fmt(f),
+        }
+    }
+}
+
 /// newtype wrapper over a Profile path, to be usable as a clear identifier for the kind of path used
 /// eg: for "a/b/c/profiles/My Mod/mods/myproj", the ProjectPathId would be "mods/myproj"
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
@@ -138,6 +327,9 @@ impl ProjectPathId {
 // Represent a Minecraft instance.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Profile {
+    /// Version of the format of profile.json, see `Versioned`
+    #[serde(default = "Profile::format_version")]
+    pub format_version: u32,
     pub uuid: Uuid, // todo: will be used in restructure to refer to profiles
     #[serde(default)]
     pub install_stage: ProfileInstallStage,
@@ -156,6 +348,443 @@ pub struct Profile {
     pub projects: HashMap<ProjectPathId, Project>,
     #[serde(default)]
     pub modrinth_update_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkOverrides>,
+    #[serde(default)]
+    pub storage_mode: StorageMode,
+    #[serde(default)]
+    pub gpu_preference: GpuPreference,
+    /// Java executable the GPU preference is registered for on Windows, so
+    /// the registration can be removed again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_registered_java: Option<String>,
+    #[serde(default)]
+    pub display_server: DisplayServer,
+    /// LWJGL version from Maven Central the game is launched with, instead
+    /// of the one it ships with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lwjgl_version: Option<String>,
+    /// LWJGL versions for Java runtimes of an architecture, such as
+    /// `arm64`, taking precedence over `lwjgl_version`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub lwjgl_arch_versions: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub development: Option<DevelopmentSettings>,
+    #[serde(default)]
+    pub playtime: Playtime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heap_usage: Option<HeapUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<ArchiveInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<LinkedCollection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub critical_projects: Vec<CriticalProject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_server: Option<PrimaryServer>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_projects: Vec<DisabledProject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader_pins: Option<LoaderPins>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_overrides: Option<LaunchOverrides>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud_sync: Option<CloudSync>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_loader: Option<InstalledLoader>,
+    /// Settings forced into the game's options.txt on every launch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_options: Option<GameOptionOverrides>,
+}
+
+/// Hashes of the libraries and processor outputs a profile's game and loader
+/// version installed, recorded at install time
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LoaderPins {
+    /// Whether reinstalls fail rather than accept files that differ from
+    /// the recorded ones
+    #[serde(default)]
+    pub reproducible: bool,
+    pub game_version: String,
+    pub loader_version: Option<String>,
+    pub recorded: DateTime<Utc>,
+    /// SHA1 of each file, by its path relative to the libraries folder
+    pub files: HashMap<String, String>,
+}
+
+/// Loader and loader version the game of a profile was last installed with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InstalledLoader {
+    pub loader: ModLoader,
+    pub loader_version: Option<String>,
+}
+
+impl InstalledLoader {
+    pub fn of(profile: &Profile) -> Self {
+        Self {
+            loader: profile.metadata.loader,
+            loader_version: profile
+                .metadata
+                .loader_version
+                .as_ref()
+                .map(|x| x.id.clone()),
+        }
+    }
+}
+
+/// A project the user disabled, which stays disabled when a pack update
+/// installs it again
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DisabledProject {
+    /// Path of the project while enabled, relative to the profile
+    pub path: ProjectPathId,
+    /// Modrinth project, so the project is recognized under a new file name
+    pub project_id: Option<String>,
+}
+
+/// The multiplayer server a profile is made for
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrimaryServer {
+    /// Address of the server, as `host` or `host:port`
+    pub address: String,
+    /// Resource pack the server sends to players, as declared in its
+    /// server.properties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_pack: Option<ServerResourcePack>,
+    /// Whether the resource pack is downloaded before every launch, rather
+    /// than by the game while joining
+    #[serde(default)]
+    pub prefetch_resource_pack: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServerResourcePack {
+    pub url: String,
+    pub sha1: Option<String>,
+}
+
+/// A Modrinth project that is brought up to date before every launch, for
+/// servers that require a mod to be current or at an exact version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CriticalProject {
+    pub project_id: String,
+    /// The version that has to be installed, the newest compatible one if
+    /// unset
+    pub version_id: Option<String>,
+}
+
+/// A Modrinth collection a profile keeps its projects in sync with
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkedCollection {
+    pub id: String,
+    pub name: String,
+    /// Projects installed from the collection, by Modrinth project id, so
+    /// they can be removed once they leave it
+    #[serde(default)]
+    pub projects: HashMap<String, ProjectPathId>,
+}
+
+/// Set while the contents of a profile are compressed into its archive, only
+/// its metadata is kept as is
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveInfo {
+    pub date_archived: DateTime<Utc>,
+    /// Size of the contents before they were archived, in bytes
+    pub original_size: u64,
+    pub archive_size: u64,
+}
+
+// How long individual sessions are kept for, cumulative counts are kept forever
+const RECENT_SESSIONS_DAYS: i64 = 30;
+
+/// Playtime accounting of a profile, updated as its game starts and exits
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Playtime {
+    /// Seconds played across every session
+    pub total_seconds: u64,
+    pub launches: u64,
+    /// Sessions where the game exited with an error
+    pub crashes: u64,
+    /// Sessions of the last 30 days, oldest first
+    pub recent_sessions: Vec<PlaySession>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlaySession {
+    pub started: DateTime<Utc>,
+    pub ended: DateTime<Utc>,
+    /// Unknown for games reattached to after a launcher restart, whose exit
+    /// status can't be read
+    pub crashed: Option<bool>,
+}
+
+impl Playtime {
+    pub(crate) fn record_session(&mut self, session: PlaySession) {
+        self.total_seconds +=
+            (session.ended - session.started).num_seconds().max(0) as u64;
+        if session.crashed == Some(true) {
+            self.crashes += 1;
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::days(RECENT_SESSIONS_DAYS);
+        self.recent_sessions.retain(|x| x.ended > cutoff);
+        self.recent_sessions.push(session);
+    }
+
+    /// Seconds played since a point in time, only counting the part of
+    /// sessions that came after it
+    pub fn seconds_since(&self, since: DateTime<Utc>) -> u64 {
+        self.recent_sessions
+            .iter()
+            .filter(|x| x.ended > since)
+            .map(|x| (x.ended - x.started.max(since)).num_seconds().max(0))
+            .sum::<i64>() as u64
+    }
+}
+
+/// Heap the game of a profile used, measured from its garbage collections
+/// while memory is sized automatically. Sizes are in MB
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct HeapUsage {
+    /// Heap still in use after collections. Follows higher measures at once
+    /// and eases towards lower ones
+    pub live_mb: u32,
+    /// Most heap in use before a collection, in the last measured session
+    pub peak_mb: u32,
+    /// Largest the heap grew to, in the last measured session
+    pub heap_mb: u32,
+    pub sessions: u32,
+    pub recorded: DateTime<Utc>,
+}
+
+/// Changes to how the game is started, for custom clients and headless runs
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LaunchOverrides {
+    /// Class started instead of the main class of the version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_class: Option<String>,
+    /// Standard game arguments left out, with their values, by flag such as
+    /// `--demo`. Only those in `REMOVABLE_GAME_ARGS` can be
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_game_args: Vec<String>,
+}
+
+impl LaunchOverrides {
+    /// Game arguments the game starts fine without. The ones naming the
+    /// player, version and directories can't be removed
+    pub const REMOVABLE_GAME_ARGS: [&'static str; 14] = [
+        "--width",
+        "--height",
+        "--fullscreen",
+        "--demo",
+        "--server",
+        "--port",
+        "--quickPlayPath",
+        "--quickPlaySingleplayer",
+        "--quickPlayMultiplayer",
+        "--quickPlayRealms",
+        "--userType",
+        "--versionType",
+        "--clientId",
+        "--xuid",
+    ];
+
+    /// Whether the overrides change nothing
+    pub fn is_empty(&self) -> bool {
+        self.main_class.is_none() && self.removed_game_args.is_empty()
+    }
+
+    /// Checks that the main class is a valid Java class name, and that only
+    /// removable arguments are removed
+    pub fn validate(&self) -> crate::Result<()> {
+        if let Some(main_class) = &self.main_class {
+            let valid = main_class.split('.').all(|part| {
+                let mut chars = part.chars();
+                chars.next().map_or(false, |x| {
+                    x.is_alphabetic() || x == '_' || x == '$'
+                }) && chars.all(|x| x.is_alphanumeric() || x == '_' || x == '$')
+            });
+            if !valid {
+                return Err(crate::ErrorKind::InputError(format!(
+                    "Invalid main class: {main_class}"
+                ))
+                .into());
+            }
+        }
+
+        if let Some(arg) = self
+            .removed_game_args
+            .iter()
+            .find(|x| !Self::REMOVABLE_GAME_ARGS.contains(&x.as_str()))
+        {
+            return Err(crate::ErrorKind::InputError(format!(
+                "Game argument {arg} can't be removed"
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Subfolders of a profile mirrored into the sync folder of the settings, to
+/// carry worlds and settings between computers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CloudSync {
+    /// Folder the profile is mirrored into, inside the sync folder. Must be
+    /// the same on every computer syncing the profile
+    pub remote_name: String,
+    /// Folders of the profile that are synced, relative to it
+    pub folders: Vec<String>,
+}
+
+impl CloudSync {
+    pub const DEFAULT_FOLDERS: [&'static str; 3] =
+        ["saves", "config", "screenshots"];
+
+    /// Checks that the remote folder and synced folders stay inside the sync
+    /// folder and the profile
+    pub fn validate(&self) -> crate::Result<()> {
+        let mut remote = Path::new(&self.remote_name).components();
+        if !matches!(
+            (remote.next(), remote.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        ) {
+            return Err(crate::ErrorKind::InputError(format!(
+                "Invalid sync folder name: {}",
+                self.remote_name
+            ))
+            .into());
+        }
+
+        if self.folders.is_empty() {
+            return Err(crate::ErrorKind::InputError(
+                "No folders to sync".to_string(),
+            )
+            .into());
+        }
+        if let Some(folder) = self.folders.iter().find(|x| {
+            x.is_empty()
+                || !Path::new(x)
+                    .components()
+                    .all(|x| matches!(x, std::path::Component::Normal(_)))
+        }) {
+            return Err(crate::ErrorKind::InputError(format!(
+                "Invalid folder to sync: {folder}"
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Development setup of a Fabric or Quilt profile, turned into the loader's
+/// system properties at launch
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DevelopmentSettings {
+    /// Folder to load mods from instead of the profile's mods folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mods_folder: Option<PathBuf>,
+    /// Mod jars or folders loaded on top of the mods folder, eg: the build
+    /// output of the mod being developed
+    #[serde(default)]
+    pub additional_mods: Vec<PathBuf>,
+    /// Classpath file generated by the build tool listing the entries to
+    /// remap at runtime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remap_classpath_file: Option<PathBuf>,
+    /// Launches with an empty asset index instead of downloading and
+    /// verifying the game's assets, for quicker server-side testing
+    #[serde(default)]
+    pub skip_assets: bool,
+}
+
+impl DevelopmentSettings {
+    /// The JVM arguments enabling development mode for a loader
+    pub fn get_java_args(&self, loader: ModLoader) -> Vec<String> {
+        // Fabric and Quilt use the same properties under different prefixes
+        let prefix = match loader {
+            ModLoader::Fabric => "fabric",
+            ModLoader::Quilt => "loader",
+            _ => return Vec::new(),
+        };
+        let mods_folder_key = match loader {
+            ModLoader::Quilt => "modsDir",
+            _ => "modsFolder",
+        };
+
+        let mut args = vec![format!("-D{prefix}.development=true")];
+        if let Some(mods_folder) = &self.mods_folder {
+            args.push(format!(
+                "-D{prefix}.{mods_folder_key}={}",
+                mods_folder.display()
+            ));
+        }
+        if !self.additional_mods.is_empty() {
+            let separator = if cfg!(windows) { ";" } else { ":" };
+            args.push(format!(
+                "-D{prefix}.addMods={}",
+                self.additional_mods
+                    .iter()
+                    .map(|x| x.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            ));
+        }
+        if let Some(remap_classpath_file) = &self.remap_classpath_file {
+            args.push(format!(
+                "-D{prefix}.remapClasspathFile={}",
+                remap_classpath_file.display()
+            ));
+        }
+        args
+    }
+}
+
+/// Network endpoint overrides of a profile, for testing servers or working
+/// around broken DNS/SRV records
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NetworkOverrides {
+    /// Replacement hosts for Mojang's APIs, passed to the game through the
+    /// `minecraft.api.*.host` system properties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub services_host: Option<String>,
+    /// Servers relayed through a local port while the game is running
+    #[serde(default)]
+    pub server_redirects: Vec<ServerRedirect>,
+}
+
+/// Relays connections made to `127.0.0.1:local_port` to another address
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServerRedirect {
+    pub local_port: u16,
+    /// Address of the real server, as `host:port`
+    pub target: String,
+}
+
+impl NetworkOverrides {
+    /// The JVM arguments overriding the game's API endpoints
+    pub fn get_java_args(&self) -> Vec<String> {
+        [
+            ("auth", &self.auth_host),
+            ("account", &self.account_host),
+            ("session", &self.session_host),
+            ("services", &self.services_host),
+        ]
+        .into_iter()
+        .filter_map(|(name, host)| {
+            host.as_ref()
+                .map(|host| format!("-Dminecraft.api.{name}.host={host}"))
+        })
+        .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -167,6 +796,8 @@ pub struct ProfileMetadata {
     pub icon_url: Option<String>,
     #[serde(default)]
     pub groups: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 
     pub game_version: String,
     #[serde(default)]
@@ -176,6 +807,8 @@ pub struct ProfileMetadata {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub linked_data: Option<LinkedData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_pack: Option<ServerPackData>,
 
     #[serde(default)]
     pub date_created: DateTime<Utc>,
@@ -189,6 +822,70 @@ pub struct ProfileMetadata {
 pub struct LinkedData {
     pub project_id: Option<String>,
     pub version_id: Option<String>,
+    /// Pinned packs stay on their installed version and are skipped when
+    /// checking for updates, so several profiles can track the same pack
+    #[serde(default)]
+    pub pinned: bool,
+    // Locked packs are meant to hold exactly the projects the pack installed.
+    // Manual changes are flagged, and undone by the next pack update
+    #[serde(default)]
+    pub locked: bool,
+    /// Projects installed by the pack, empty if installed before these were
+    /// tracked
+    #[serde(default)]
+    pub pack_files: Vec<ProjectPathId>,
+    /// Projects added or removed by hand while the pack is locked
+    #[serde(default)]
+    pub modifications: Vec<ProjectPathId>,
+    /// SHA-1 of every file the pack installed, overrides included, by path
+    /// relative to the profile. Empty if installed before these were tracked
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub file_hashes: HashMap<String, String>,
+    /// Least stable versions of the pack that updates are offered for
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+    /// Addresses of the servers the pack added to the server list, which
+    /// updates don't add again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pack_servers: Vec<String>,
+    /// JVM arguments the pack recommends, as of its installed version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pack_java_args: Vec<String>,
+}
+
+/// Versions of a pack that are offered as updates. Each channel includes the
+/// more stable ones
+#[derive(
+    Debug, Eq, PartialEq, Clone, Copy, Deserialize, Serialize, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Release,
+    Beta,
+    /// Every version, as packs were updated before channels could be chosen
+    #[default]
+    Alpha,
+}
+
+impl UpdateChannel {
+    /// Whether versions of a Modrinth version type are on the channel
+    pub fn includes(&self, version_type: &str) -> bool {
+        match self {
+            UpdateChannel::Release => version_type == "release",
+            UpdateChannel::Beta => {
+                matches!(version_type, "release" | "beta")
+            }
+            UpdateChannel::Alpha => true,
+        }
+    }
+}
+
+/// Marks a profile imported from a server pack, and how to run it as one
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServerPackData {
+    /// Scripts shipped with the pack that start the server, relative to the
+    /// profile folder
+    pub start_scripts: Vec<PathBuf>,
 }
 
 #[derive(
@@ -201,6 +898,7 @@ pub enum ModLoader {
     Forge,
     Fabric,
     Quilt,
+    NeoForge,
 }
 
 impl std::fmt::Display for ModLoader {
@@ -210,6 +908,7 @@ impl std::fmt::Display for ModLoader {
             Self::Forge => "Forge",
             Self::Fabric => "Fabric",
             Self::Quilt => "Quilt",
+            Self::NeoForge => "NeoForge",
         })
     }
 }
@@ -221,6 +920,7 @@ impl ModLoader {
             Self::Forge => "forge",
             Self::Fabric => "fabric",
             Self::Quilt => "quilt",
+            Self::NeoForge => "neoforge",
         }
     }
 }
@@ -235,7 +935,19 @@ pub struct JavaSettings {
     pub custom_env_args: Option<Vec<(String, String)>>,
 }
 
+impl Versioned for Profile {
+    const NAME: &'static str = "Profile";
+    const VERSION_FIELD: &'static str = "format_version";
+    const CURRENT_VERSION: u32 = PROFILE_FORMAT_VERSION;
+    // Added to as the format changes
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
 impl Profile {
+    fn format_version() -> u32 {
+        PROFILE_FORMAT_VERSION
+    }
+
     #[tracing::instrument]
     pub async fn new(
         uuid: Uuid,
@@ -250,6 +962,7 @@ impl Profile {
         }
 
         Ok(Self {
+            format_version: PROFILE_FORMAT_VERSION,
             uuid,
             install_stage: ProfileInstallStage::NotInstalled,
             path: PathBuf::new().join(&name),
@@ -258,10 +971,12 @@ impl Profile {
                 icon: None,
                 icon_url: None,
                 groups: vec![],
+                tags: vec![],
                 game_version: version,
                 loader: ModLoader::Vanilla,
                 loader_version: None,
                 linked_data: None,
+                server_pack: None,
                 date_created: Utc::now(),
                 date_modified: Utc::now(),
                 last_played: None,
@@ -273,6 +988,26 @@ impl Profile {
             fullscreen: None,
             hooks: None,
             modrinth_update_version: None,
+            network: None,
+            storage_mode: StorageMode::Shared,
+            gpu_preference: GpuPreference::Default,
+            gpu_registered_java: None,
+            display_server: DisplayServer::Default,
+            lwjgl_version: None,
+            lwjgl_arch_versions: HashMap::new(),
+            development: None,
+            playtime: Playtime::default(),
+            heap_usage: None,
+            archived: None,
+            collection: None,
+            critical_projects: Vec::new(),
+            primary_server: None,
+            disabled_projects: Vec::new(),
+            loader_pins: None,
+            launch_overrides: None,
+            cloud_sync: None,
+            installed_loader: None,
+            game_options: None,
         })
     }
 
@@ -282,6 +1017,20 @@ impl Profile {
         ProfilePathId::new(&self.path)
     }
 
+    /// LWJGL version the profile is launched with on a Java runtime, if it
+    /// is overridden
+    pub fn lwjgl_version_for(&self, java_arch: &str) -> Option<&String> {
+        self.lwjgl_arch_versions
+            .get(platform::normalize_arch(java_arch))
+            .or(self.lwjgl_version.as_ref())
+    }
+
+    /// Whether the profile is launched without the game's assets
+    #[inline]
+    pub fn skips_assets(&self) -> bool {
+        self.development.as_ref().map_or(false, |x| x.skip_assets)
+    }
+
     #[tracing::instrument(skip(self, semaphore, icon))]
     pub async fn set_icon<'a>(
         &'a mut self,
@@ -303,8 +1052,11 @@ impl Profile {
                 let profile = crate::api::profile::get(&path, None).await?;
 
                 if let Some(profile) = profile {
-                    // Hide warning if profile is not yet installed
-                    if profile.install_stage == ProfileInstallStage::Installed {
+                    // Hide warning if profile is not yet installed, or is
+                    // being smoke tested, which reports crashes itself
+                    if profile.install_stage == ProfileInstallStage::Installed
+                        && !crate::api::profile::smoke_test::is_testing(&path)
+                    {
                         emit_warning(&format!("Profile {} has crashed! Visit the logs page to see a crash report.", profile.metadata.name)).await?;
                     }
                 }
@@ -339,6 +1091,11 @@ impl Profile {
                 let profile = crate::api::profile::get(&profile_path_id, None).await?;
 
                 if let Some(profile) = profile {
+                    // Archived profiles keep the projects they had
+                    if profile.archived.is_some() {
+                        return Ok(());
+                    }
+
                     let paths = profile.get_profile_full_project_paths().await?;
 
                     let caches_dir = state.directories.caches_dir();
@@ -358,6 +1115,7 @@ impl Profile {
                     if let Some(profile) = new_profiles.0.get_mut(&profile_path_id) {
                         profile.projects = projects;
                     }
+                    drop(new_profiles);
                     emit_profile(
                         profile.uuid,
                         &profile_path_id,
@@ -365,6 +1123,7 @@ impl Profile {
                         ProfilePayloadType::Synced,
                     )
                     .await?;
+                    crate::api::profile::lockfile::update_lockfile(&profile_path_id).await;
                 } else {
                     tracing::warn!(
                         "Unable to fetch single profile projects: path {profile_path_id} invalid",
@@ -383,6 +1142,125 @@ impl Profile {
         });
     }
 
+    /// Updates the projects of a profile for files that were added, changed
+    /// or removed in its project folders, only hashing and looking up those
+    /// files rather than the whole profile
+    pub fn sync_project_files_task(
+        profile_path_id: ProfilePathId,
+        paths: Vec<PathBuf>,
+    ) {
+        tokio::task::spawn(async move {
+            let res = async {
+                let state = State::get().await?;
+                let profile =
+                    crate::api::profile::get(&profile_path_id, None).await?;
+                let Some(profile) = profile else {
+                    return Ok(());
+                };
+                // Archived profiles keep the projects they had
+                if profile.archived.is_some() {
+                    return Ok(());
+                }
+
+                let (existing, removed): (Vec<_>, Vec<_>) =
+                    paths.into_iter().partition(|x| x.is_file());
+                let mut removed_ids = Vec::new();
+                for path in removed {
+                    // The file is gone, so its path can't be canonicalized
+                    let relative = path
+                        .parent()
+                        .and_then(|x| Some((x.file_name()?, path.file_name()?)))
+                        .map(|(folder, name)| Path::new(folder).join(name));
+                    if let Some(relative) = relative {
+                        removed_ids.push(ProjectPathId::new(&relative));
+                    }
+                }
+
+                let projects = if existing.is_empty() {
+                    HashMap::new()
+                } else {
+                    let creds = state.credentials.read().await;
+                    crate::state::infer_data_from_files(
+                        profile.clone(),
+                        existing,
+                        state.directories.caches_dir(),
+                        &state.io_semaphore,
+                        &state.fetch_semaphore,
+                        &creds,
+                    )
+                    .await?
+                };
+
+                {
+                    let mut profiles = state.profiles.write().await;
+                    if let Some(profile) = profiles.0.get_mut(&profile_path_id)
+                    {
+                        for id in &removed_ids {
+                            profile.projects.remove(id);
+                        }
+                        profile.projects.extend(projects);
+                        profile.flag_locked_modifications();
+                    }
+                }
+                emit_profile(
+                    profile.uuid,
+                    &profile_path_id,
+                    &profile.metadata.name,
+                    ProfilePayloadType::Edited,
+                )
+                .await?;
+                crate::api::profile::lockfile::update_lockfile(
+                    &profile_path_id,
+                )
+                .await;
+                Ok::<(), crate::Error>(())
+            }
+            .await;
+            if let Err(err) = res {
+                tracing::warn!(
+                    "Unable to sync changed files of {profile_path_id}: {err}"
+                );
+            }
+        });
+    }
+
+    /// Records how the projects of a locked pack differ from the ones the
+    /// pack installed
+    pub fn flag_locked_modifications(&mut self) {
+        let Some(linked_data) = &mut self.metadata.linked_data else {
+            return;
+        };
+        if !linked_data.locked || linked_data.pack_files.is_empty() {
+            linked_data.modifications.clear();
+            return;
+        }
+
+        let mut modifications = self
+            .projects
+            .keys()
+            .filter(|x| !linked_data.pack_files.contains(x))
+            .chain(
+                linked_data
+                    .pack_files
+                    .iter()
+                    .filter(|x| !self.projects.contains_key(x)),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        modifications.sort_by(|a, b| a.0.cmp(&b.0));
+
+        if !modifications.is_empty()
+            && modifications != linked_data.modifications
+        {
+            tracing::info!(
+                "Locked pack {} was modified by hand: {:?}",
+                self.path,
+                modifications
+            );
+        }
+        linked_data.modifications = modifications;
+    }
+
     // Get full path to profile
     pub async fn get_profile_full_path(&self) -> crate::Result<PathBuf> {
         let state = State::get().await?;
@@ -454,6 +1332,12 @@ impl Profile {
         watch_path(profile_path, watcher, ProjectType::DataPack.get_folder())
             .await?;
         watch_path(profile_path, watcher, "crash-reports").await?;
+        watch_path(
+            profile_path,
+            watcher,
+            crate::api::screenshots::SCREENSHOTS_FOLDER,
+        )
+        .await?;
 
         Ok(())
     }
@@ -476,6 +1360,20 @@ impl Profile {
         )
         .await?;
         drop(creds);
+
+        let path = self.add_project_modrinth_version(&version).await?;
+        Ok((path, version))
+    }
+
+    #[tracing::instrument(skip(self, version))]
+    #[theseus_macros::debug_pin]
+    pub async fn add_project_modrinth_version(
+        &self,
+        version: &ModrinthVersion,
+    ) -> crate::Result<ProjectPathId> {
+        super::policy::check_versions(&[version]).await?;
+
+        let state = State::get().await?;
         let file = if let Some(file) = version.files.iter().find(|x| x.primary)
         {
             file
@@ -504,7 +1402,7 @@ impl Profile {
                 ProjectType::get_from_loaders(version.loaders.clone()),
             )
             .await?;
-        Ok((path, version))
+        Ok(path)
     }
 
     #[tracing::instrument(skip(self, bytes))]
@@ -578,6 +1476,7 @@ impl Profile {
     }
 
     /// Toggle a project's disabled state.
+    /// Disabled projects are remembered, so pack updates keep them disabled
     #[tracing::instrument(skip(self))]
     #[theseus_macros::debug_pin]
     pub async fn toggle_disable_project(
@@ -630,8 +1529,32 @@ impl Profile {
 
             let new_project_path_id = ProjectPathId::new(&new_path);
 
+            // Recognized by its enabled path, or by its Modrinth project under
+            // a new file name
+            let disabled = DisabledProject {
+                path: ProjectPathId::new(if project.disabled {
+                    &relative_path
+                } else {
+                    &new_path
+                }),
+                project_id: match &project.metadata {
+                    ProjectMetadata::Modrinth { project, .. } => {
+                        Some(project.id.clone())
+                    }
+                    _ => None,
+                },
+            };
+
             let mut profiles = state.profiles.write().await;
             if let Some(profile) = profiles.0.get_mut(&self.profile_id()) {
+                profile.disabled_projects.retain(|x| {
+                    x.path != disabled.path
+                        && (x.project_id.is_none()
+                            || x.project_id != disabled.project_id)
+                });
+                if project.disabled {
+                    profile.disabled_projects.push(disabled);
+                }
                 profile
                     .projects
                     .insert(new_project_path_id.clone(), project);
@@ -696,28 +1619,47 @@ impl Profiles {
             .watcher()
             .watch(&profiles_dir, RecursiveMode::NonRecursive)?;
 
+        let mut paths = Vec::new();
         let mut entries = io::read_dir(&dirs.profiles_dir().await).await?;
         while let Some(entry) =
             entries.next_entry().await.map_err(IOError::from)?
         {
             let path = entry.path();
             if path.is_dir() {
-                let prof = match Self::read_profile_from_dir(&path, dirs).await
-                {
-                    Ok(prof) => Some(prof),
-                    Err(err) => {
-                        tracing::warn!(
-                            "Error loading profile: {err}. Skipping..."
-                        );
-                        None
-                    }
-                };
-                if let Some(profile) = prof {
-                    let path = io::canonicalize(path)?;
-                    Profile::watch_fs(&path, file_watcher).await?;
-                    profiles.insert(profile.profile_id(), profile);
+                paths.push(path);
+            }
+        }
+
+        // Each profile is sent to the frontend as soon as it is read, so long
+        // profile lists show up before the launcher finishes starting
+        let mut loaded = stream::iter(paths)
+            .map(|path| async move {
+                let prof = Self::read_profile_from_dir(&path, dirs).await;
+                (path, prof)
+            })
+            .buffer_unordered(PROFILE_LOAD_CONCURRENCY);
+        while let Some((path, prof)) = loaded.next().await {
+            let profile = match prof {
+                Ok(profile) => profile,
+                Err(err) => {
+                    tracing::warn!("Error loading profile: {err}. Skipping...");
+                    continue;
                 }
+            };
+
+            let path = io::canonicalize(path)?;
+            Profile::watch_fs(&path, file_watcher).await?;
+            if let Err(err) = emit_profile_loaded(
+                profile.uuid,
+                &profile.profile_id(),
+                &path,
+                &profile.metadata.name,
+            )
+            .await
+            {
+                tracing::warn!("Unable to send loaded profile: {err}");
             }
+            profiles.insert(profile.profile_id(), profile);
         }
 
         Ok(Self(profiles))
@@ -786,23 +1728,222 @@ impl Profiles {
         };
     }
 
+    /// Checks managed packs and projects for new versions in the background,
+    /// for launchers left open. Registering again with new settings
+    /// replaces the previous schedule
+    pub fn schedule_update_checks(checks: &UpdateChecks) {
+        Scheduler::register(
+            "check_updates",
+            Schedule::Every {
+                seconds: checks.interval_hours.max(1) as u64 * 60 * 60,
+            },
+            false,
+            || Box::pin(Profiles::run_update_checks()),
+        );
+    }
+
+    /// Checks for new versions of packs and projects if the settings allow
+    /// it, then tells frontends about any not told about before
+    #[tracing::instrument]
+    #[theseus_macros::debug_pin]
+    pub async fn run_update_checks() {
+        let res = async {
+            let state = State::get().await?;
+            let checks = state.settings.read().await.update_checks;
+            if !checks.enabled || *state.offline.read().await {
+                return Ok(());
+            }
+            if checks.skip_on_metered
+                && tokio::task::spawn_blocking(platform::is_metered_connection)
+                    .await?
+            {
+                tracing::info!("Skipping update checks, connection is metered");
+                return Ok(());
+            }
+
+            Profiles::update_modrinth_versions().await;
+            Profiles::update_project_versions().await;
+
+            let updates = state.profiles.read().await.updates_available();
+            let changed = {
+                let mut notified = NOTIFIED_UPDATES.lock().unwrap();
+                let changed = *notified != updates;
+                *notified = updates.clone();
+                changed
+            };
+            if changed && !updates.is_empty() {
+                emit_updates_available(&UpdatesAvailablePayload {
+                    profiles: updates,
+                })
+                .await?;
+            }
+
+            Ok::<(), crate::Error>(())
+        }
+        .await;
+
+        if let Err(err) = res {
+            tracing::warn!("Unable to check for updates: {err}")
+        }
+    }
+
+    /// Packs and projects with a newer version, by profile
+    pub fn updates_available(&self) -> Vec<ProfileUpdates> {
+        let mut updates = self
+            .0
+            .iter()
+            .filter_map(|(profile_path, profile)| {
+                let mut projects = profile
+                    .projects
+                    .iter()
+                    .filter_map(|(project_path, project)| {
+                        match &project.metadata {
+                            ProjectMetadata::Modrinth {
+                                project,
+                                update_version: Some(version),
+                                ..
+                            } => Some(ProjectUpdate {
+                                project_path: project_path.clone(),
+                                title: project.title.clone(),
+                                version_id: version.id.clone(),
+                                version_number: version.version_number.clone(),
+                            }),
+                            _ => None,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                projects
+                    .sort_by(|a, b| a.project_path.0.cmp(&b.project_path.0));
+
+                if projects.is_empty()
+                    && profile.modrinth_update_version.is_none()
+                {
+                    return None;
+                }
+                Some(ProfileUpdates {
+                    profile_path_id: profile_path.clone(),
+                    name: profile.metadata.name.clone(),
+                    pack_version: profile.modrinth_update_version.clone(),
+                    projects,
+                })
+            })
+            .collect::<Vec<_>>();
+        updates.sort_by(|a, b| a.profile_path_id.0.cmp(&b.profile_path_id.0));
+        updates
+    }
+
+    /// Refreshes the newest compatible version of every Modrinth project,
+    /// from the hashes already known rather than reading every file again
+    #[tracing::instrument]
+    #[theseus_macros::debug_pin]
+    pub async fn update_project_versions() {
+        let res = async {
+            let state = State::get().await?;
+            let mut requests = Vec::new();
+            {
+                let profiles = state.profiles.read().await;
+                for (profile_path, profile) in profiles.0.iter() {
+                    let hashes = profile
+                        .projects
+                        .values()
+                        .filter(|x| {
+                            matches!(
+                                x.metadata,
+                                ProjectMetadata::Modrinth { .. }
+                            )
+                        })
+                        .map(|x| x.sha512.clone())
+                        .collect::<Vec<_>>();
+                    if !hashes.is_empty() {
+                        requests.push((
+                            profile_path.clone(),
+                            json!({
+                                "hashes": hashes,
+                                "algorithm": "sha512",
+                                "loaders": [profile.metadata.loader],
+                                "game_versions": [profile.metadata.game_version]
+                            }),
+                        ));
+                    }
+                }
+            }
+
+            let updates_url = format!("{MODRINTH_API_URL}version_files/update");
+            future::try_join_all(requests.into_iter().map(
+                |(profile_path, body)| {
+                    let state = state.clone();
+                    let updates_url = &updates_url;
+                    async move {
+                        let creds = state.credentials.read().await;
+                        let updates =
+                            fetch_json::<HashMap<String, ModrinthVersion>>(
+                                Method::POST,
+                                updates_url,
+                                None,
+                                Some(body),
+                                &state.fetch_semaphore,
+                                &creds,
+                            )
+                            .await?;
+                        drop(creds);
+
+                        let mut profiles = state.profiles.write().await;
+                        if let Some(profile) = profiles.0.get_mut(&profile_path)
+                        {
+                            for project in profile.projects.values_mut() {
+                                if let ProjectMetadata::Modrinth {
+                                    version,
+                                    update_version,
+                                    ..
+                                } = &mut project.metadata
+                                {
+                                    *update_version = updates
+                                        .get(&project.sha512)
+                                        .filter(|x| x.id != version.id)
+                                        .map(|x| Box::new(x.clone()));
+                                }
+                            }
+                        }
+
+                        Ok::<(), crate::Error>(())
+                    }
+                },
+            ))
+            .await?;
+
+            {
+                let profiles = state.profiles.read().await;
+                profiles.sync().await?;
+            }
+
+            Ok::<(), crate::Error>(())
+        }
+        .await;
+
+        if let Err(err) = res {
+            tracing::warn!("Unable to check for project updates: {err}")
+        }
+    }
+
     #[tracing::instrument]
     #[theseus_macros::debug_pin]
     pub async fn update_modrinth_versions() {
         let res = async {
             let state = State::get().await?;
-            // Temporarily store all profiles that have modrinth linked data
-            let mut modrinth_updatables: Vec<(ProfilePathId, String)> =
-                Vec::new();
+            // Temporarily store all profiles that have modrinth linked data,
+            // grouped by project so packs installed side by side are only
+            // fetched once
+            let mut modrinth_updatables: HashMap<String, Vec<ProfilePathId>> =
+                HashMap::new();
             {
                 let profiles = state.profiles.read().await;
                 for (profile_path, profile) in profiles.0.iter() {
                     if let Some(linked_data) = &profile.metadata.linked_data {
                         if let Some(linked_project) = &linked_data.project_id {
-                            modrinth_updatables.push((
-                                profile_path.clone(),
-                                linked_project.clone(),
-                            ));
+                            modrinth_updatables
+                                .entry(linked_project.clone())
+                                .or_default()
+                                .push(profile_path.clone());
                         }
                     }
                 }
@@ -810,9 +1951,7 @@ impl Profiles {
 
             // Fetch online from Modrinth each latest version
             future::try_join_all(modrinth_updatables.into_iter().map(
-                |(profile_path, linked_project)| {
-                    let profile_path = profile_path;
-                    let linked_project = linked_project;
+                |(linked_project, profile_paths)| {
                     let state = state.clone();
                     async move {
                         let creds = state.credentials.read().await;
@@ -832,16 +1971,18 @@ impl Profiles {
                         drop(creds);
 
                         // Versions are pre-sorted in labrinth (by versions.sort_by(|a, b| b.inner.date_published.cmp(&a.inner.date_published));)
-                        // so we can just take the first one
+                        // so the first one on the channel is the newest
                         let mut new_profiles = state.profiles.write().await;
-                        if let Some(profile) =
-                            new_profiles.0.get_mut(&profile_path)
-                        {
-                            if let Some(recent_version) = versions.get(0) {
-                                profile.modrinth_update_version =
-                                    Some(recent_version.id.clone());
-                            } else {
-                                profile.modrinth_update_version = None;
+                        for profile_path in profile_paths {
+                            if let Some(profile) =
+                                new_profiles.0.get_mut(&profile_path)
+                            {
+                                profile.modrinth_update_version = profile
+                                    .metadata
+                                    .linked_data
+                                    .as_ref()
+                                    .filter(|x| !x.pinned)
+                                    .and_then(|x| pack_update(&versions, x));
                             }
                         }
                         drop(new_profiles);
@@ -911,18 +2052,25 @@ impl Profiles {
 
     #[tracing::instrument(skip_all)]
     pub async fn sync(&self) -> crate::Result<&Self> {
-        let _state = State::get().await?;
+        let state = State::get().await?;
+        let state = &state;
         stream::iter(self.0.iter())
             .map(Ok::<_, crate::Error>)
-            .try_for_each_concurrent(None, |(_, profile)| async move {
+            .try_for_each_concurrent(None, |(path, profile)| async move {
                 let json = serde_json::to_vec(&profile)?;
+                let hash = sha1::Sha1::from(&json).hexdigest();
+                if WRITTEN_PROFILES.lock().unwrap().get(path) == Some(&hash) {
+                    return Ok(());
+                }
 
                 let json_path = profile
                     .get_profile_full_path()
                     .await?
                     .join(PROFILE_JSON_PATH);
 
-                io::write(&json_path, &json).await?;
+                // Written atomically, so a crash can't leave it half written
+                write(&json_path, &json, &state.io_semaphore).await?;
+                WRITTEN_PROFILES.lock().unwrap().insert(path.clone(), hash);
                 Ok::<_, crate::Error>(())
             })
             .await?;
@@ -935,7 +2083,7 @@ impl Profiles {
         dirs: &DirectoryInfo,
     ) -> crate::Result<Profile> {
         let json = io::read(&path.join(PROFILE_JSON_PATH)).await?;
-        let mut profile = serde_json::from_slice::<Profile>(&json)?;
+        let mut profile = load_versioned::<Profile>(&json)?;
 
         // Get name from stripped path
         profile.path =
@@ -995,3 +2143,23 @@ impl Profiles {
         });
     }
 }
+
+// Newest version of a pack on its update channel, if newer than the one
+// installed
+fn pack_update(
+    versions: &[ModrinthVersion],
+    linked_data: &LinkedData,
+) -> Option<String> {
+    let installed = versions
+        .iter()
+        .find(|x| Some(&x.id) == linked_data.version_id.as_ref());
+    let update = versions
+        .iter()
+        .find(|x| linked_data.update_channel.includes(&x.version_type))?;
+
+    // Going from a beta back to an older release isn't an update
+    if installed.map_or(false, |x| x.date_published >= update.date_published) {
+        return None;
+    }
+    Some(update.id.clone())
+}
@@ -1,21 +1,19 @@
 //! Project management + inference
 
 use crate::config::MODRINTH_API_URL;
-use crate::state::{CredentialsStore, ModrinthUser, Profile};
-use crate::util::fetch::{
-    fetch_json, write_cached_icon, FetchSemaphore, IoSemaphore,
-};
+use crate::state::{CredentialsStore, ModIndex, ModrinthUser, Profile};
+use crate::util::fetch::{fetch_json, FetchSemaphore, IoSemaphore};
 use crate::util::io::IOError;
 
-use async_zip::tokio::read::fs::ZipFileReader;
 use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use reqwest::Method;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha2::Digest;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use tokio::io::AsyncReadExt;
 
 use super::ProjectPathId;
@@ -33,7 +31,7 @@ impl ProjectType {
     pub fn get_from_loaders(loaders: Vec<String>) -> Option<Self> {
         if loaders
             .iter()
-            .any(|x| ["fabric", "forge", "quilt"].contains(&&**x))
+            .any(|x| ["fabric", "forge", "quilt", "neoforge"].contains(&&**x))
         {
             Some(ProjectType::Mod)
         } else if loaders.iter().any(|x| x == "datapack") {
@@ -205,6 +203,8 @@ pub enum ProjectMetadata {
         incompatible: bool,
     },
     Inferred {
+        #[serde(default)]
+        mod_id: Option<String>,
         title: Option<String>,
         description: Option<String>,
         authors: Vec<String>,
@@ -215,55 +215,60 @@ pub enum ProjectMetadata {
     Unknown,
 }
 
-#[tracing::instrument(skip(io_semaphore))]
-#[theseus_macros::debug_pin]
-async fn read_icon_from_file(
-    icon_path: Option<String>,
-    cache_dir: &Path,
-    path: &PathBuf,
-    io_semaphore: &IoSemaphore,
-) -> crate::Result<Option<PathBuf>> {
-    if let Some(icon_path) = icon_path {
-        // we have to repoen the zip twice here :(
-        let zip_file_reader = ZipFileReader::new(path).await;
-        if let Ok(zip_file_reader) = zip_file_reader {
-            // Get index of icon file and open it
-            let zip_index_option = zip_file_reader
-                .file()
-                .entries()
-                .iter()
-                .position(|f| f.entry().filename() == icon_path);
-            if let Some(index) = zip_index_option {
-                let entry = zip_file_reader
-                    .file()
-                    .entries()
-                    .get(index)
-                    .unwrap()
-                    .entry();
-                let mut bytes = Vec::new();
-                if zip_file_reader
-                    .entry(zip_index_option.unwrap())
-                    .await?
-                    .read_to_end_checked(&mut bytes, entry)
-                    .await
-                    .is_ok()
-                {
-                    let bytes = bytes::Bytes::from(bytes);
-                    let path = write_cached_icon(
-                        &icon_path,
-                        cache_dir,
-                        bytes,
-                        io_semaphore,
-                    )
-                    .await?;
-
-                    return Ok(Some(path));
-                }
-            };
-        }
-    }
+// Ids sent per request to the bulk endpoints, which keeps their URLs short
+const BULK_CHUNK_SIZE: usize = 100;
+
+/// Fetches Modrinth versions by id through the bulk endpoint, rather than a
+/// request each. Versions that don't exist are left out
+pub(crate) async fn fetch_versions(
+    ids: &[String],
+    fetch_semaphore: &FetchSemaphore,
+    credentials: &CredentialsStore,
+) -> crate::Result<HashMap<String, ModrinthVersion>> {
+    let versions: Vec<ModrinthVersion> =
+        fetch_bulk("versions", ids, fetch_semaphore, credentials).await?;
+    Ok(versions.into_iter().map(|x| (x.id.clone(), x)).collect())
+}
 
-    Ok(None)
+/// Fetches Modrinth projects by id or slug through the bulk endpoint.
+/// Projects that don't exist are left out
+pub(crate) async fn fetch_projects(
+    ids: &[String],
+    fetch_semaphore: &FetchSemaphore,
+    credentials: &CredentialsStore,
+) -> crate::Result<HashMap<String, ModrinthProject>> {
+    let projects: Vec<ModrinthProject> =
+        fetch_bulk("projects", ids, fetch_semaphore, credentials).await?;
+    Ok(projects.into_iter().map(|x| (x.id.clone(), x)).collect())
+}
+
+async fn fetch_bulk<T>(
+    route: &str,
+    ids: &[String],
+    fetch_semaphore: &FetchSemaphore,
+    credentials: &CredentialsStore,
+) -> crate::Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    let mut items = Vec::new();
+    for chunk in ids.chunks(BULK_CHUNK_SIZE) {
+        items.extend(
+            fetch_json::<Vec<T>>(
+                Method::GET,
+                &format!(
+                    "{MODRINTH_API_URL}{route}?ids={}",
+                    serde_json::to_string(chunk)?
+                ),
+                None,
+                None,
+                fetch_semaphore,
+                credentials,
+            )
+            .await?,
+        );
+    }
+    Ok(items)
 }
 
 // Creates Project data from the existing files in the file system, for a given Profile
@@ -333,38 +338,18 @@ pub async fn infer_data_from_files(
         )
     )?;
 
-    let projects: Vec<ModrinthProject> = fetch_json(
-        Method::GET,
-        &format!(
-            "{}projects?ids={}",
-            MODRINTH_API_URL,
-            serde_json::to_string(
-                &files
-                    .values()
-                    .map(|x| x.project_id.clone())
-                    .collect::<Vec<_>>()
-            )?
-        ),
-        None,
-        None,
-        fetch_semaphore,
-        credentials,
-    )
-    .await?;
-
-    let teams: Vec<ModrinthTeamMember> = fetch_json::<
-        Vec<Vec<ModrinthTeamMember>>,
-    >(
-        Method::GET,
-        &format!(
-            "{}teams?ids={}",
-            MODRINTH_API_URL,
-            serde_json::to_string(
-                &projects.iter().map(|x| x.team.clone()).collect::<Vec<_>>()
-            )?
-        ),
-        None,
-        None,
+    let project_ids = files
+        .values()
+        .map(|x| x.project_id.clone())
+        .collect::<Vec<_>>();
+    let projects: Vec<ModrinthProject> =
+        fetch_bulk("projects", &project_ids, fetch_semaphore, credentials)
+            .await?;
+
+    let team_ids = projects.iter().map(|x| x.team.clone()).collect::<Vec<_>>();
+    let teams: Vec<ModrinthTeamMember> = fetch_bulk::<Vec<ModrinthTeamMember>>(
+        "teams",
+        &team_ids,
         fetch_semaphore,
         credentials,
     )
@@ -431,6 +416,9 @@ pub async fn infer_data_from_files(
         further_analyze_projects.push((hash, path));
     }
 
+    let jar_metadata =
+        ModIndex::get_many(&further_analyze_projects, &cache_dir, io_semaphore)
+            .await?;
     for (hash, path) in further_analyze_projects {
         let file_name = path
             .file_name()
@@ -438,367 +426,30 @@ pub async fn infer_data_from_files(
             .to_string_lossy()
             .to_string();
 
-        let zip_file_reader = if let Ok(zip_file_reader) =
-            ZipFileReader::new(path.clone()).await
-        {
-            zip_file_reader
-        } else {
-            return_projects.push((
-                path.clone(),
-                Project {
-                    sha512: hash,
-                    disabled: file_name.ends_with(".disabled"),
-                    metadata: ProjectMetadata::Unknown,
-                    file_name,
-                },
-            ));
-            continue;
+        let metadata = match jar_metadata.get(&hash).cloned().flatten() {
+            Some(jar) => ProjectMetadata::Inferred {
+                mod_id: jar.mod_id,
+                title: jar.title,
+                description: jar.description,
+                authors: jar.authors,
+                version: jar.version,
+                icon: jar.icon,
+                // Guess the type of packs from the filepath
+                project_type: jar.project_type.or_else(|| {
+                    ProjectType::get_from_parent_folder(path.clone())
+                        .map(|x| x.get_name().to_string())
+                }),
+            },
+            None => ProjectMetadata::Unknown,
         };
 
-        // Forge
-        let zip_index_option = zip_file_reader
-            .file()
-            .entries()
-            .iter()
-            .position(|f| f.entry().filename() == "META-INF/mods.toml");
-        if let Some(index) = zip_index_option {
-            let file = zip_file_reader.file().entries().get(index).unwrap();
-            #[derive(Deserialize)]
-            #[serde(rename_all = "camelCase")]
-            struct ForgeModInfo {
-                pub mods: Vec<ForgeMod>,
-            }
-            #[derive(Deserialize)]
-            #[serde(rename_all = "camelCase")]
-            struct ForgeMod {
-                mod_id: String,
-                version: Option<String>,
-                display_name: Option<String>,
-                description: Option<String>,
-                logo_file: Option<String>,
-                authors: Option<String>,
-            }
-
-            let mut file_str = String::new();
-            if zip_file_reader
-                .entry(index)
-                .await?
-                .read_to_string_checked(&mut file_str, file.entry())
-                .await
-                .is_ok()
-            {
-                if let Ok(pack) = toml::from_str::<ForgeModInfo>(&file_str) {
-                    if let Some(pack) = pack.mods.first() {
-                        let icon = read_icon_from_file(
-                            pack.logo_file.clone(),
-                            &cache_dir,
-                            &path,
-                            io_semaphore,
-                        )
-                        .await?;
-
-                        return_projects.push((
-                            path.clone(),
-                            Project {
-                                sha512: hash,
-                                disabled: file_name.ends_with(".disabled"),
-                                file_name,
-                                metadata: ProjectMetadata::Inferred {
-                                    title: Some(
-                                        pack.display_name
-                                            .clone()
-                                            .unwrap_or_else(|| {
-                                                pack.mod_id.clone()
-                                            }),
-                                    ),
-                                    description: pack.description.clone(),
-                                    authors: pack
-                                        .authors
-                                        .clone()
-                                        .map(|x| vec![x])
-                                        .unwrap_or_default(),
-                                    version: pack.version.clone(),
-                                    icon,
-                                    project_type: Some("mod".to_string()),
-                                },
-                            },
-                        ));
-                        continue;
-                    }
-                }
-            }
-        }
-
-        // Forge
-        let zip_index_option = zip_file_reader
-            .file()
-            .entries()
-            .iter()
-            .position(|f| f.entry().filename() == "mcmod.info");
-        if let Some(index) = zip_index_option {
-            let file = zip_file_reader.file().entries().get(index).unwrap();
-            #[derive(Deserialize)]
-            #[serde(rename_all = "camelCase")]
-            struct ForgeMod {
-                modid: String,
-                name: String,
-                description: Option<String>,
-                version: Option<String>,
-                author_list: Option<Vec<String>>,
-                logo_file: Option<String>,
-            }
-
-            let mut file_str = String::new();
-            if zip_file_reader
-                .entry(index)
-                .await?
-                .read_to_string_checked(&mut file_str, file.entry())
-                .await
-                .is_ok()
-            {
-                if let Ok(pack) = serde_json::from_str::<ForgeMod>(&file_str) {
-                    let icon = read_icon_from_file(
-                        pack.logo_file,
-                        &cache_dir,
-                        &path,
-                        io_semaphore,
-                    )
-                    .await?;
-
-                    return_projects.push((
-                        path.clone(),
-                        Project {
-                            sha512: hash,
-                            disabled: file_name.ends_with(".disabled"),
-                            file_name,
-                            metadata: ProjectMetadata::Inferred {
-                                title: Some(if pack.name.is_empty() {
-                                    pack.modid
-                                } else {
-                                    pack.name
-                                }),
-                                description: pack.description,
-                                authors: pack.author_list.unwrap_or_default(),
-                                version: pack.version,
-                                icon,
-                                project_type: Some("mod".to_string()),
-                            },
-                        },
-                    ));
-                    continue;
-                }
-            }
-        }
-
-        // Fabric
-        let zip_index_option = zip_file_reader
-            .file()
-            .entries()
-            .iter()
-            .position(|f| f.entry().filename() == "fabric.mod.json");
-        if let Some(index) = zip_index_option {
-            let file = zip_file_reader.file().entries().get(index).unwrap();
-            #[derive(Deserialize)]
-            #[serde(untagged)]
-            enum FabricAuthor {
-                String(String),
-                Object { name: String },
-            }
-            #[derive(Deserialize)]
-            #[serde(rename_all = "camelCase")]
-            struct FabricMod {
-                id: String,
-                version: String,
-                name: Option<String>,
-                description: Option<String>,
-                authors: Vec<FabricAuthor>,
-                icon: Option<String>,
-            }
-
-            let mut file_str = String::new();
-            if zip_file_reader
-                .entry(index)
-                .await?
-                .read_to_string_checked(&mut file_str, file.entry())
-                .await
-                .is_ok()
-            {
-                if let Ok(pack) = serde_json::from_str::<FabricMod>(&file_str) {
-                    let icon = read_icon_from_file(
-                        pack.icon,
-                        &cache_dir,
-                        &path,
-                        io_semaphore,
-                    )
-                    .await?;
-
-                    return_projects.push((
-                        path.clone(),
-                        Project {
-                            sha512: hash,
-                            disabled: file_name.ends_with(".disabled"),
-                            file_name,
-                            metadata: ProjectMetadata::Inferred {
-                                title: Some(pack.name.unwrap_or(pack.id)),
-                                description: pack.description,
-                                authors: pack
-                                    .authors
-                                    .into_iter()
-                                    .map(|x| match x {
-                                        FabricAuthor::String(name) => name,
-                                        FabricAuthor::Object { name } => name,
-                                    })
-                                    .collect(),
-                                version: Some(pack.version),
-                                icon,
-                                project_type: Some("mod".to_string()),
-                            },
-                        },
-                    ));
-                    continue;
-                }
-            }
-        }
-
-        // Quilt
-        let zip_index_option = zip_file_reader
-            .file()
-            .entries()
-            .iter()
-            .position(|f| f.entry().filename() == "quilt.mod.json");
-        if let Some(index) = zip_index_option {
-            let file = zip_file_reader.file().entries().get(index).unwrap();
-            #[derive(Deserialize)]
-            struct QuiltMetadata {
-                pub name: Option<String>,
-                pub description: Option<String>,
-                pub contributors: Option<HashMap<String, String>>,
-                pub icon: Option<String>,
-            }
-            #[derive(Deserialize)]
-            struct QuiltMod {
-                id: String,
-                version: String,
-                metadata: Option<QuiltMetadata>,
-            }
-
-            let mut file_str = String::new();
-            if zip_file_reader
-                .entry(index)
-                .await?
-                .read_to_string_checked(&mut file_str, file.entry())
-                .await
-                .is_ok()
-            {
-                if let Ok(pack) = serde_json::from_str::<QuiltMod>(&file_str) {
-                    let icon = read_icon_from_file(
-                        pack.metadata.as_ref().and_then(|x| x.icon.clone()),
-                        &cache_dir,
-                        &path,
-                        io_semaphore,
-                    )
-                    .await?;
-
-                    return_projects.push((
-                        path.clone(),
-                        Project {
-                            sha512: hash,
-                            disabled: file_name.ends_with(".disabled"),
-                            file_name,
-                            metadata: ProjectMetadata::Inferred {
-                                title: Some(
-                                    pack.metadata
-                                        .as_ref()
-                                        .and_then(|x| x.name.clone())
-                                        .unwrap_or(pack.id),
-                                ),
-                                description: pack
-                                    .metadata
-                                    .as_ref()
-                                    .and_then(|x| x.description.clone()),
-                                authors: pack
-                                    .metadata
-                                    .map(|x| {
-                                        x.contributors
-                                            .unwrap_or_default()
-                                            .keys()
-                                            .cloned()
-                                            .collect()
-                                    })
-                                    .unwrap_or_default(),
-                                version: Some(pack.version),
-                                icon,
-                                project_type: Some("mod".to_string()),
-                            },
-                        },
-                    ));
-                    continue;
-                }
-            }
-        }
-
-        // Other
-        let zip_index_option = zip_file_reader
-            .file()
-            .entries()
-            .iter()
-            .position(|f| f.entry().filename() == "pack.mcmeta");
-        if let Some(index) = zip_index_option {
-            let file = zip_file_reader.file().entries().get(index).unwrap();
-            #[derive(Deserialize)]
-            struct Pack {
-                description: Option<String>,
-            }
-
-            let mut file_str = String::new();
-            if zip_file_reader
-                .entry(index)
-                .await?
-                .read_to_string_checked(&mut file_str, file.entry())
-                .await
-                .is_ok()
-            {
-                if let Ok(pack) = serde_json::from_str::<Pack>(&file_str) {
-                    let icon = read_icon_from_file(
-                        Some("pack.png".to_string()),
-                        &cache_dir,
-                        &path,
-                        io_semaphore,
-                    )
-                    .await?;
-
-                    // Guess the project type from the filepath
-                    let project_type =
-                        ProjectType::get_from_parent_folder(path.clone());
-                    return_projects.push((
-                        path.clone(),
-                        Project {
-                            sha512: hash,
-                            disabled: file_name.ends_with(".disabled"),
-                            file_name,
-                            metadata: ProjectMetadata::Inferred {
-                                title: None,
-                                description: pack.description,
-                                authors: Vec::new(),
-                                version: None,
-                                icon,
-                                project_type: project_type
-                                    .map(|x| x.get_name().to_string()),
-                            },
-                        },
-                    ));
-                    continue;
-                }
-            }
-        }
-
         return_projects.push((
             path.clone(),
             Project {
                 sha512: hash,
                 disabled: file_name.ends_with(".disabled"),
                 file_name,
-                metadata: ProjectMetadata::Unknown,
+                metadata,
             },
         ));
     }
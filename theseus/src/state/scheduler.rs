@@ -0,0 +1,249 @@
+//! Background jobs run on a schedule
+//!
+//! Intervals are measured on the monotonic clock, so they aren't thrown off
+//! by the wall clock being changed. That clock stands still while the
+//! computer sleeps on some platforms, so the scheduler watches for the two
+//! clocks drifting apart and recomputes every deadline on wake. Jobs run at
+//! a time of day are computed in local time, for the date they run on, so
+//! they keep their time across DST changes
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, Utc};
+use chrono::{LocalResult, TimeZone};
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+// Longest the scheduler sleeps, which bounds how late it notices a wake
+const TICK: Duration = Duration::from_secs(30);
+// Wall clock time passing this much faster than monotonic time means the
+// computer was asleep
+const WAKE_THRESHOLD: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref SCHEDULER: Scheduler = Scheduler {
+        jobs: Mutex::new(Vec::new()),
+        notify: Notify::new(),
+    };
+}
+
+/// When a job runs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Repeatedly, this many seconds after the previous run finished
+    Every { seconds: u64 },
+    /// Every day at this local time
+    Daily { time: NaiveTime },
+}
+
+/// A scheduled job, as shown in the jobs API
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobInfo {
+    pub name: String,
+    pub schedule: Schedule,
+    pub next_run: DateTime<Local>,
+    pub last_run: Option<DateTime<Local>>,
+    pub running: bool,
+}
+
+type JobFn = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+struct Job {
+    name: String,
+    schedule: Schedule,
+    run: JobFn,
+    deadline: Instant,
+    // Wall clock time of the deadline, what it is recomputed from on wake
+    next_run: DateTime<Utc>,
+    last_run: Option<DateTime<Utc>>,
+    running: bool,
+}
+
+pub struct Scheduler {
+    jobs: Mutex<Vec<Job>>,
+    notify: Notify,
+}
+
+impl Scheduler {
+    /// Adds a job, replacing any of the same name. With `run_now`, it also
+    /// runs right away rather than at its first scheduled time
+    pub fn register<F>(name: &str, schedule: Schedule, run_now: bool, run: F)
+    where
+        F: Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    {
+        let now = Utc::now();
+        let next_run = if run_now {
+            now
+        } else {
+            schedule.next_after(now)
+        };
+
+        let mut jobs = SCHEDULER.jobs.lock().unwrap();
+        jobs.retain(|x| x.name != name);
+        jobs.push(Job {
+            name: name.to_string(),
+            schedule,
+            run: Arc::new(run),
+            deadline: deadline_for(next_run, now),
+            next_run,
+            last_run: None,
+            running: false,
+        });
+        drop(jobs);
+        SCHEDULER.notify.notify_one();
+    }
+
+    /// Lists the scheduled jobs, soonest first
+    pub fn jobs() -> Vec<JobInfo> {
+        let jobs = SCHEDULER.jobs.lock().unwrap();
+        let mut infos = jobs
+            .iter()
+            .map(|x| JobInfo {
+                name: x.name.clone(),
+                schedule: x.schedule,
+                next_run: x.next_run.with_timezone(&Local),
+                last_run: x.last_run.map(|x| x.with_timezone(&Local)),
+                running: x.running,
+            })
+            .collect::<Vec<_>>();
+        infos.sort_by_key(|x| x.next_run);
+        infos
+    }
+
+    /// Moves the next run of a job to now
+    pub fn run_now(name: &str) -> crate::Result<()> {
+        let mut jobs = SCHEDULER.jobs.lock().unwrap();
+        let job =
+            jobs.iter_mut().find(|x| x.name == name).ok_or_else(|| {
+                crate::ErrorKind::InputError(format!("No job named {name}"))
+            })?;
+        job.next_run = Utc::now();
+        job.deadline = Instant::now();
+        drop(jobs);
+        SCHEDULER.notify.notify_one();
+        Ok(())
+    }
+
+    /// Starts the loop running jobs once they are due
+    pub fn start() {
+        tokio::task::spawn(async {
+            let mut wall = Utc::now();
+            let mut monotonic = Instant::now();
+            loop {
+                let now = Instant::now();
+                let wall_now = Utc::now();
+                let wall_elapsed =
+                    (wall_now - wall).to_std().unwrap_or_default();
+                if wall_elapsed > (now - monotonic) + WAKE_THRESHOLD {
+                    tracing::info!("Woke from sleep, rescheduling jobs");
+                    Self::reschedule(wall_now);
+                }
+                wall = wall_now;
+                monotonic = now;
+
+                let sleep_until = Self::run_due(now);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(sleep_until) => {}
+                    _ = SCHEDULER.notify.notified() => {}
+                }
+            }
+        });
+    }
+
+    // Starts every due job, returning when to check again
+    fn run_due(now: Instant) -> Instant {
+        let mut jobs = SCHEDULER.jobs.lock().unwrap();
+        for job in jobs.iter_mut().filter(|x| !x.running && x.deadline <= now) {
+            job.running = true;
+            let name = job.name.clone();
+            let run = job.run.clone();
+            tokio::task::spawn(async move {
+                run().await;
+                Self::finish(&name);
+            });
+        }
+
+        jobs.iter()
+            .filter(|x| !x.running)
+            .map(|x| x.deadline)
+            .chain(std::iter::once(now + TICK))
+            .min()
+            .unwrap_or(now + TICK)
+    }
+
+    fn finish(name: &str) {
+        let mut jobs = SCHEDULER.jobs.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|x| x.name == name) {
+            let now = Utc::now();
+            job.running = false;
+            job.last_run = Some(now);
+            job.next_run = job.schedule.next_after(now);
+            job.deadline = deadline_for(job.next_run, now);
+        }
+        drop(jobs);
+        SCHEDULER.notify.notify_one();
+    }
+
+    // Deadlines measured while asleep are behind, so they are computed again
+    // from the wall clock, and daily runs for the local time zone again
+    fn reschedule(now: DateTime<Utc>) {
+        let mut jobs = SCHEDULER.jobs.lock().unwrap();
+        for job in jobs.iter_mut().filter(|x| !x.running) {
+            if matches!(job.schedule, Schedule::Daily { .. })
+                && job.next_run > now
+            {
+                job.next_run = job.schedule.next_after(now);
+            }
+            job.deadline = deadline_for(job.next_run, now);
+        }
+    }
+}
+
+impl Schedule {
+    /// When the job runs next after a time
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Every { seconds } => {
+                after + ChronoDuration::seconds(*seconds as i64)
+            }
+            Schedule::Daily { time } => next_daily(*time, after),
+        }
+    }
+}
+
+// Monotonic deadline of a wall clock time
+fn deadline_for(next_run: DateTime<Utc>, now: DateTime<Utc>) -> Instant {
+    Instant::now() + (next_run - now).to_std().unwrap_or_default()
+}
+
+fn next_daily(time: NaiveTime, after: DateTime<Utc>) -> DateTime<Utc> {
+    let mut date = after.with_timezone(&Local).date_naive();
+    // A day ahead is always enough, the extra one covers the run of today
+    // being skipped or repeated by a clock change
+    for _ in 0..3 {
+        let local = date.and_time(time);
+        let run = match Local.from_local_datetime(&local) {
+            LocalResult::Single(x) => Some(x),
+            // Clocks were turned back over it, it runs the first time
+            LocalResult::Ambiguous(x, _) => Some(x),
+            // Clocks were turned forward over it, it runs once they have
+            LocalResult::None => Local
+                .from_local_datetime(&(local + ChronoDuration::hours(1)))
+                .earliest(),
+        }
+        .map(|x| x.with_timezone(&Utc));
+
+        match run {
+            Some(run) if run > after => return run,
+            _ => {}
+        }
+        date = match date.succ_opt() {
+            Some(date) => date,
+            None => break,
+        };
+    }
+    after + ChronoDuration::days(1)
+}
@@ -4,14 +4,23 @@ use crate::{
     State,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use super::{DirectoryInfo, JavaGlobals};
+use super::{load_versioned, DirectoryInfo, JavaGlobals, Migration, Versioned};
 
 // TODO: convert to semver?
 const CURRENT_FORMAT_VERSION: u32 = 1;
 
+impl Versioned for Settings {
+    const NAME: &'static str = "Settings";
+    const VERSION_FIELD: &'static str = "version";
+    const CURRENT_VERSION: u32 = CURRENT_FORMAT_VERSION;
+    // Added to as the format changes
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
 // Types
 /// Global Theseus settings
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -28,11 +37,48 @@ pub struct Settings {
     pub hooks: Hooks,
     pub max_concurrent_downloads: usize,
     pub max_concurrent_writes: usize,
+    /// Download speed cap in KB/s, shared by all downloads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_download_speed: Option<u64>,
+    /// Download speed cap in KB/s while a game is running. Falls back to
+    /// `max_download_speed` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_download_speed_while_running: Option<u64>,
+    #[serde(default)]
+    pub downloads_while_running: RunningDownloads,
+    #[serde(default)]
+    pub log_retention: LogRetention,
+    #[serde(default)]
+    pub update_checks: UpdateChecks,
+    #[serde(default)]
+    pub loader_cache_cleanup: LoaderCacheCleanup,
+    #[serde(default)]
+    pub log_censoring: LogCensoring,
+    /// Whether the game is launched without the account's Xbox user id and
+    /// the launcher's client id, which it sends to Mojang's telemetry
+    #[serde(default)]
+    pub hide_launch_identifiers: bool,
+    /// Where logs are shared to, any paste service with the API of mclo.gs.
+    /// mclo.gs itself if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_service_url: Option<String>,
+    /// Verbosity of the launcher's own logs. `RUST_LOG` takes precedence on
+    /// startup
+    #[serde(default)]
+    pub logging: LoggingSettings,
+    #[serde(default)]
+    pub http: HttpSettings,
+    /// Folder profiles opted in to syncing are mirrored into, usually one a
+    /// file syncing service such as Dropbox or Syncthing keeps up to date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_folder: Option<PathBuf>,
     pub version: u32,
     pub collapsed_navigation: bool,
     #[serde(default)]
     pub hide_on_process: bool,
     #[serde(default)]
+    pub post_launch: PostLaunchBehavior,
+    #[serde(default)]
     pub default_page: DefaultPage,
     #[serde(default)]
     pub developer_mode: bool,
@@ -42,6 +88,9 @@ pub struct Settings {
     pub advanced_rendering: bool,
     #[serde(default)]
     pub fully_onboarded: bool,
+    /// Whether launches warn about GPU drivers known to crash the game
+    #[serde(default)]
+    pub check_gpu_drivers: bool,
     #[serde(default = "DirectoryInfo::get_initial_settings_dir")]
     pub loaded_config_dir: Option<PathBuf>,
 }
@@ -58,10 +107,7 @@ impl Settings {
                     ))
                     .as_error()
                 })
-                .and_then(|it| {
-                    serde_json::from_slice::<Settings>(&it)
-                        .map_err(crate::Error::from)
-                })
+                .and_then(|it| load_versioned::<Settings>(&it))
         } else {
             Ok(Self {
                 theme: Theme::Dark,
@@ -75,14 +121,28 @@ impl Settings {
                 hooks: Hooks::default(),
                 max_concurrent_downloads: 10,
                 max_concurrent_writes: 10,
+                max_download_speed: None,
+                max_download_speed_while_running: None,
+                downloads_while_running: RunningDownloads::default(),
+                log_retention: LogRetention::default(),
+                update_checks: UpdateChecks::default(),
+                loader_cache_cleanup: LoaderCacheCleanup::default(),
+                log_censoring: LogCensoring::default(),
+                hide_launch_identifiers: false,
+                paste_service_url: None,
+                logging: LoggingSettings::default(),
+                http: HttpSettings::default(),
+                sync_folder: None,
                 version: CURRENT_FORMAT_VERSION,
                 collapsed_navigation: false,
                 hide_on_process: false,
+                post_launch: PostLaunchBehavior::Keep,
                 default_page: DefaultPage::Home,
                 developer_mode: false,
                 opt_out_analytics: false,
                 advanced_rendering: true,
                 fully_onboarded: false,
+                check_gpu_drivers: false,
 
                 // By default, the config directory is the same as the settings directory
                 loaded_config_dir: DirectoryInfo::get_initial_settings_dir(),
@@ -147,18 +207,312 @@ pub enum Theme {
     Oled,
 }
 
-/// Minecraft memory settings
+/// Minecraft memory settings, in MB
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct MemorySettings {
     pub maximum: u32,
+    /// Initial heap size, left to the JVM when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<u32>,
+    /// Replaces the sizes above with the ones recommended for the system
+    /// memory and the profile's mods at launch
+    #[serde(default)]
+    pub auto: bool,
+    /// Logs garbage collections while `auto` is set, so later
+    /// recommendations follow the heap the profile really used
+    #[serde(default)]
+    pub sample_heap: bool,
 }
 
 impl Default for MemorySettings {
     fn default() -> Self {
-        Self { maximum: 2048 }
+        Self {
+            maximum: 2048,
+            minimum: None,
+            auto: false,
+            sample_heap: false,
+        }
+    }
+}
+
+/// How long the game logs of each profile are kept for
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct LogRetention {
+    /// Logs older than this many days are gzipped
+    pub compress_after_days: Option<u32>,
+    /// Number of logs kept per profile, the oldest are deleted first
+    pub max_count: Option<usize>,
+    /// Size in MB the logs of a profile may take up in total
+    pub max_size: Option<u64>,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            compress_after_days: Some(7),
+            max_count: Some(100),
+            max_size: None,
+        }
+    }
+}
+
+/// Background checks for new versions of packs and projects
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct UpdateChecks {
+    pub enabled: bool,
+    /// Hours between checks
+    pub interval_hours: u32,
+    /// Whether checks wait while the connection is metered
+    pub skip_on_metered: bool,
+}
+
+impl Default for UpdateChecks {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 6,
+            skip_on_metered: true,
+        }
+    }
+}
+
+/// Removal of the caches loaders keep in a profile once its loader or loader
+/// version changes, as caches from the previous one can crash the game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LoaderCacheCleanup {
+    pub enabled: bool,
+    /// Folders removed on top of the known caches, relative to the profile
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_folders: Vec<String>,
+}
+
+impl Default for LoaderCacheCleanup {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            extra_folders: Vec::new(),
+        }
+    }
+}
+
+/// How downloads give way to a running game, so installs in the background
+/// don't make it stutter
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(default)]
+pub struct RunningDownloads {
+    /// Whether modpack installs and updates wait for the last game to exit
+    /// before starting
+    pub pause_installs: bool,
+    /// Downloads at a time while a game is running, on top of
+    /// `max_concurrent_downloads`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_downloads: Option<usize>,
+}
+
+/// What is hidden from game logs, launch reports and crash reports. The
+/// token, name and UUID of the account and the username of the computer
+/// always are
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LogCensoring {
+    /// Off by default, as versions with four parts such as `1.20.1.0` can't
+    /// be told apart from IPv4 addresses
+    pub ip_addresses: bool,
+    pub email_addresses: bool,
+    /// Session arguments and tokens other than the account's own
+    pub session_ids: bool,
+    pub custom_patterns: Vec<CensorPattern>,
+}
+
+impl Default for LogCensoring {
+    fn default() -> Self {
+        Self {
+            ip_addresses: false,
+            email_addresses: true,
+            session_ids: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+/// A regex of text to hide from logs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CensorPattern {
+    pub pattern: String,
+    /// Text matches are replaced with, `{CENSORED}` if unset
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// Which of the launcher's own logs are recorded
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct LoggingSettings {
+    /// Level of the launcher's logs
+    pub level: LogLevel,
+    /// Levels of other modules and crates, such as `theseus::launcher` or
+    /// `reqwest`, overriding `level` for them
+    pub targets: BTreeMap<String, LogLevel>,
+}
+
+impl LoggingSettings {
+    /// The settings as a filter, in the syntax of `RUST_LOG`
+    pub fn directives(&self) -> String {
+        std::iter::once(format!("theseus={}", self.level.as_str()))
+            .chain(
+                self.targets.iter().map(|(target, level)| {
+                    format!("{target}={}", level.as_str())
+                }),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+/// Tuning of the HTTP client every download goes through
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct HttpSettings {
+    /// Seconds to wait for a connection to be established
+    pub connect_timeout: u64,
+    /// Seconds a whole request may take, unlimited if unset
+    pub request_timeout: Option<u64>,
+    /// Idle connections kept open per host. Keeps big installs from opening
+    /// a socket per file
+    pub max_idle_connections: usize,
+    /// Proxy to send requests through. The one of the system, read from the
+    /// usual environment variables, is used if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxySettings>,
+    /// Servers game files, libraries and metadata are also downloaded from.
+    /// Every download is tried from the hosts that answered fastest so far
+    /// first, falling back to the others
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<DownloadMirror>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: 15,
+            request_timeout: None,
+            max_idle_connections: 16,
+            proxy: None,
+            mirrors: Vec::new(),
+        }
     }
 }
 
+/// A server serving the same files as others under other URLs. A meta
+/// server replacing Modrinth's is a mirror of `https://meta.modrinth.com/`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DownloadMirror {
+    pub name: String,
+    /// Disabled mirrors are kept in the settings, but not downloaded from
+    #[serde(default)]
+    pub disabled: bool,
+    pub prefixes: Vec<MirrorPrefix>,
+}
+
+/// URLs starting with `original` are found on the mirror by replacing it
+/// with `mirror`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MirrorPrefix {
+    pub original: String,
+    pub mirror: String,
+}
+
+impl DownloadMirror {
+    /// BMCLAPI, mirroring Mojang's servers and the Mavens of mod loaders
+    pub fn bmclapi() -> Self {
+        const URL: &str = "https://bmclapi2.bangbang93.com/";
+        let prefix = |original: &str, path: &str| MirrorPrefix {
+            original: original.to_string(),
+            mirror: format!("{URL}{path}"),
+        };
+        Self {
+            name: "BMCLAPI".to_string(),
+            disabled: false,
+            prefixes: vec![
+                prefix("https://piston-meta.mojang.com/", ""),
+                prefix("https://piston-data.mojang.com/", ""),
+                prefix("https://launchermeta.mojang.com/", ""),
+                prefix("https://launcher.mojang.com/", ""),
+                prefix("https://resources.download.minecraft.net/", "assets/"),
+                prefix("https://libraries.minecraft.net/", "maven/"),
+                prefix("https://maven.minecraftforge.net/", "maven/"),
+                prefix("https://maven.neoforged.net/releases/", "maven/"),
+                prefix("https://maven.fabricmc.net/", "maven/"),
+            ],
+        }
+    }
+
+    /// Where a URL is found on this mirror, if it mirrors its server
+    pub fn rewrite(&self, url: &str) -> Option<String> {
+        self.prefixes.iter().find_map(|x| {
+            url.strip_prefix(&x.original)
+                .map(|path| format!("{}{path}", x.mirror))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub protocol: ProxyProtocol,
+    pub host: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Hosts connected to directly, as in `NO_PROXY`: domains, which cover
+    /// their subdomains, IP addresses, CIDR ranges, or `*` for every host
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyProtocol {
+    Http,
+    Https,
+    /// Host names are resolved by the proxy, as networks needing one often
+    /// can't resolve outside names themselves
+    Socks5,
+}
+
 /// Game window size
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct WindowSize(pub u16, pub u16);
@@ -181,6 +535,85 @@ pub struct Hooks {
     pub post_exit: Option<String>,
 }
 
+/// What the launcher does with itself once a game has been started
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostLaunchBehavior {
+    Keep,
+    Minimize,
+    MinimizeToTray,
+    /// Quits the launcher and opens it again once the game exits
+    QuitAndRelaunch,
+}
+
+impl Default for PostLaunchBehavior {
+    fn default() -> Self {
+        Self::Keep
+    }
+}
+
+/// Something a frontend does with its window
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAction {
+    None,
+    Minimize,
+    Hide,
+    Close,
+    Restore,
+}
+
+/// How frontends should behave around a game's lifetime, resolved from the
+/// post-launch behavior so every frontend acts the same way
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct PostLaunchPolicy {
+    pub behavior: PostLaunchBehavior,
+    /// Done right after the game has been started
+    pub on_launch: WindowAction,
+    /// Done once the last running game has exited
+    pub on_exit: WindowAction,
+    /// Whether the frontend should stay around until the game exits
+    pub wait_for_exit: bool,
+}
+
+impl PostLaunchPolicy {
+    pub fn new(behavior: PostLaunchBehavior) -> Self {
+        let (on_launch, on_exit, wait_for_exit) = match behavior {
+            PostLaunchBehavior::Keep => {
+                (WindowAction::None, WindowAction::None, true)
+            }
+            PostLaunchBehavior::Minimize => {
+                (WindowAction::Minimize, WindowAction::Restore, true)
+            }
+            PostLaunchBehavior::MinimizeToTray => {
+                (WindowAction::Hide, WindowAction::Restore, true)
+            }
+            PostLaunchBehavior::QuitAndRelaunch => {
+                (WindowAction::Close, WindowAction::Restore, false)
+            }
+        };
+
+        Self {
+            behavior,
+            on_launch,
+            on_exit,
+            wait_for_exit,
+        }
+    }
+}
+
+impl Settings {
+    /// The post-launch policy, honouring the older hide-on-process toggle
+    pub fn post_launch_policy(&self) -> PostLaunchPolicy {
+        if self.post_launch == PostLaunchBehavior::Keep && self.hide_on_process
+        {
+            PostLaunchPolicy::new(PostLaunchBehavior::Minimize)
+        } else {
+            PostLaunchPolicy::new(self.post_launch)
+        }
+    }
+}
+
 /// Opening window to start with
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum DefaultPage {
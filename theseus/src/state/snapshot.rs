@@ -0,0 +1,76 @@
+//! A consistent copy of the launcher's state, for backup and sync tools
+use super::{Journal, JournalEntry, Profile, ProfileLibrary, Settings, State};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// Stands in for values that may hold secrets
+const REDACTED: &str = "{REDACTED}";
+
+/// The profiles, settings and in-flight operations of the launcher at one
+/// point in time
+#[derive(Serialize, Debug, Clone)]
+pub struct StateSnapshot {
+    pub taken: DateTime<Utc>,
+    pub launcher_version: String,
+    /// Settings, with the proxy password and environment variable values
+    /// redacted
+    pub settings: Settings,
+    /// Profiles, with their install stage, and the values of their
+    /// environment variables redacted
+    pub profiles: Vec<Profile>,
+    pub library: ProfileLibrary,
+    /// Operations underway, whose profiles are partly installed
+    pub operations: Vec<JournalEntry>,
+}
+
+/// Copies the launcher's state while holding it still, so profiles can't be
+/// installed, changed or saved halfway through the copy
+#[tracing::instrument]
+pub async fn snapshot() -> crate::Result<StateSnapshot> {
+    let state = State::get().await?;
+
+    let mut snapshot = {
+        // The journal first, as operations starting or ending hold it
+        // without the others
+        let operations = Journal::lock().await;
+        let settings = state.settings.read().await;
+        let profiles = state.profiles.read().await;
+        let library = state.library.read().await;
+
+        StateSnapshot {
+            taken: Utc::now(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            settings: settings.clone(),
+            profiles: profiles.0.values().cloned().collect(),
+            library: library.clone(),
+            operations: operations.clone(),
+        }
+    };
+
+    redact_env(&mut snapshot.settings.custom_env_args);
+    if let Some(proxy) = &mut snapshot.settings.http.proxy {
+        if proxy.password.is_some() {
+            proxy.password = Some(REDACTED.to_string());
+        }
+    }
+    for profile in &mut snapshot.profiles {
+        if let Some(env) = profile
+            .java
+            .as_mut()
+            .and_then(|x| x.custom_env_args.as_mut())
+        {
+            redact_env(env);
+        }
+    }
+    snapshot.profiles.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(snapshot)
+}
+
+/// Hides the values of environment variables, which may hold tokens, in
+/// what is shared for support
+pub(crate) fn redact_env(env: &mut [(String, String)]) {
+    for (_, value) in env {
+        *value = REDACTED.to_string();
+    }
+}
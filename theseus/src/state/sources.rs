@@ -0,0 +1,406 @@
+//! Registries content can be searched for and installed from
+use crate::config::MODRINTH_API_URL;
+use crate::state::{CredentialsStore, ModLoader, ModrinthVersion, ProjectType};
+use crate::util::fetch::{fetch, fetch_json};
+use crate::State;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const MODRINTH_SOURCE: &str = "modrinth";
+pub const URL_SOURCE: &str = "url";
+
+/// Search parameters shared by every source
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ContentQuery {
+    pub query: String,
+    #[serde(default)]
+    pub project_type: Option<ProjectType>,
+    #[serde(default)]
+    pub filter: VersionFilter,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// Restricts versions to the ones a profile can use
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct VersionFilter {
+    pub game_version: Option<String>,
+    pub loader: Option<ModLoader>,
+}
+
+/// A project found through a source
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContentProject {
+    /// Id of the source the project comes from
+    pub source: String,
+    pub id: String,
+    pub slug: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub icon_url: Option<String>,
+    pub downloads: u64,
+}
+
+/// A version of a project, with the files it consists of
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContentVersion {
+    /// Id of the source the version comes from
+    pub source: String,
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub files: Vec<ContentFile>,
+}
+
+impl ContentVersion {
+    /// The file installed for this version, the primary one if marked
+    pub fn primary_file(&self) -> Option<&ContentFile> {
+        self.files
+            .iter()
+            .find(|x| x.primary)
+            .or_else(|| self.files.first())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContentFile {
+    pub url: String,
+    pub filename: String,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
+    pub primary: bool,
+}
+
+impl From<ModrinthVersion> for ContentVersion {
+    fn from(version: ModrinthVersion) -> Self {
+        Self {
+            source: MODRINTH_SOURCE.to_string(),
+            id: version.id,
+            project_id: version.project_id,
+            name: version.name,
+            version_number: version.version_number,
+            game_versions: version.game_versions,
+            loaders: version.loaders,
+            files: version
+                .files
+                .into_iter()
+                .map(|x| ContentFile {
+                    sha1: x.hashes.get("sha1").cloned(),
+                    url: x.url,
+                    filename: x.filename,
+                    size: Some(x.size as u64),
+                    primary: x.primary,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Where content comes from. Sources only need to implement what their
+/// registry supports, the rest defaults to finding nothing
+pub trait ContentSource: Send + Sync {
+    /// Unique id the source is registered under
+    fn id(&self) -> &str;
+
+    fn search<'a>(
+        &'a self,
+        _query: &'a ContentQuery,
+    ) -> BoxFuture<'a, crate::Result<Vec<ContentProject>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Lists the versions of a project, newest first
+    fn get_versions<'a>(
+        &'a self,
+        project_id: &'a str,
+        filter: &'a VersionFilter,
+    ) -> BoxFuture<'a, crate::Result<Vec<ContentVersion>>>;
+
+    /// Downloads a file of a version, verifying its hash when known
+    fn fetch_file<'a>(
+        &'a self,
+        file: &'a ContentFile,
+    ) -> BoxFuture<'a, crate::Result<Bytes>> {
+        Box::pin(async move {
+            let state = State::get().await?;
+            fetch(
+                &file.url,
+                file.sha1.as_deref(),
+                &state.fetch_semaphore,
+                &CredentialsStore(None),
+            )
+            .await
+        })
+    }
+
+    /// Finds the newest version for installed files, keyed by the SHA-512
+    /// hash of the file they would replace
+    fn check_updates<'a>(
+        &'a self,
+        _hashes: &'a [String],
+        _filter: &'a VersionFilter,
+    ) -> BoxFuture<'a, crate::Result<HashMap<String, ContentVersion>>> {
+        Box::pin(async { Ok(HashMap::new()) })
+    }
+}
+
+/// Sources available to the launcher, by id
+pub struct ContentSources(HashMap<String, Arc<dyn ContentSource>>);
+
+impl ContentSources {
+    pub fn new() -> Self {
+        let mut sources = Self(HashMap::new());
+        sources.register(Arc::new(ModrinthSource));
+        sources.register(Arc::new(UrlSource));
+        sources
+    }
+
+    /// Adds a source, replacing any registered under the same id
+    pub fn register(&mut self, source: Arc<dyn ContentSource>) {
+        self.0.insert(source.id().to_string(), source);
+    }
+
+    pub fn get(&self, id: &str) -> crate::Result<Arc<dyn ContentSource>> {
+        self.0.get(id).cloned().ok_or_else(|| {
+            crate::ErrorKind::InputError(format!(
+                "Unknown content source: {id}"
+            ))
+            .as_error()
+        })
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+}
+
+impl Default for ContentSources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The Modrinth API
+pub struct ModrinthSource;
+
+impl ContentSource for ModrinthSource {
+    fn id(&self) -> &str {
+        MODRINTH_SOURCE
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a ContentQuery,
+    ) -> BoxFuture<'a, crate::Result<Vec<ContentProject>>> {
+        #[derive(Deserialize)]
+        struct SearchResults {
+            hits: Vec<SearchHit>,
+        }
+        #[derive(Deserialize)]
+        struct SearchHit {
+            project_id: String,
+            slug: Option<String>,
+            title: String,
+            description: String,
+            icon_url: Option<String>,
+            downloads: u64,
+        }
+
+        Box::pin(async move {
+            let mut facets = Vec::new();
+            if let Some(project_type) = &query.project_type {
+                let project_type = match project_type {
+                    ProjectType::Mod => "mod",
+                    ProjectType::DataPack => "datapack",
+                    ProjectType::ResourcePack => "resourcepack",
+                    ProjectType::ShaderPack => "shader",
+                };
+                facets.push(vec![format!("project_type:{project_type}")]);
+            }
+            if let Some(game_version) = &query.filter.game_version {
+                facets.push(vec![format!("versions:{game_version}")]);
+            }
+            if let Some(loader) = query.filter.loader {
+                if loader != ModLoader::Vanilla {
+                    facets.push(vec![format!(
+                        "categories:{}",
+                        loader.as_api_str()
+                    )]);
+                }
+            }
+
+            let url = url::Url::parse_with_params(
+                &format!("{MODRINTH_API_URL}search"),
+                &[
+                    ("query", query.query.clone()),
+                    ("facets", serde_json::to_string(&facets)?),
+                    ("limit", query.limit.unwrap_or(20).to_string()),
+                ],
+            )
+            .map_err(|err| {
+                crate::ErrorKind::InputError(format!(
+                    "Invalid search query: {err}"
+                ))
+            })?;
+
+            let state = State::get().await?;
+            let creds = state.credentials.read().await;
+            let results = fetch_json::<SearchResults>(
+                Method::GET,
+                url.as_str(),
+                None,
+                None,
+                &state.fetch_semaphore,
+                &creds,
+            )
+            .await?;
+
+            Ok(results
+                .hits
+                .into_iter()
+                .map(|x| ContentProject {
+                    source: MODRINTH_SOURCE.to_string(),
+                    id: x.project_id,
+                    slug: x.slug,
+                    title: x.title,
+                    description: x.description,
+                    icon_url: x.icon_url,
+                    downloads: x.downloads,
+                })
+                .collect())
+        })
+    }
+
+    fn get_versions<'a>(
+        &'a self,
+        project_id: &'a str,
+        filter: &'a VersionFilter,
+    ) -> BoxFuture<'a, crate::Result<Vec<ContentVersion>>> {
+        Box::pin(async move {
+            let mut url =
+                format!("{MODRINTH_API_URL}project/{project_id}/version?");
+            if let Some(game_version) = &filter.game_version {
+                url.push_str(&format!(
+                    "game_versions={}&",
+                    serde_json::to_string(&[game_version])?
+                ));
+            }
+            if let Some(loader) = filter.loader {
+                if loader != ModLoader::Vanilla {
+                    url.push_str(&format!(
+                        "loaders={}",
+                        serde_json::to_string(&[loader.as_api_str()])?
+                    ));
+                }
+            }
+
+            let state = State::get().await?;
+            let creds = state.credentials.read().await;
+            let versions = fetch_json::<Vec<ModrinthVersion>>(
+                Method::GET,
+                &url,
+                None,
+                None,
+                &state.fetch_semaphore,
+                &creds,
+            )
+            .await?;
+
+            Ok(versions.into_iter().map(ContentVersion::from).collect())
+        })
+    }
+
+    fn check_updates<'a>(
+        &'a self,
+        hashes: &'a [String],
+        filter: &'a VersionFilter,
+    ) -> BoxFuture<'a, crate::Result<HashMap<String, ContentVersion>>> {
+        Box::pin(async move {
+            let mut body = json!({
+                "hashes": hashes,
+                "algorithm": "sha512",
+            });
+            if let Some(game_version) = &filter.game_version {
+                body["game_versions"] = json!([game_version]);
+            }
+            if let Some(loader) = filter.loader {
+                body["loaders"] = json!([loader]);
+            }
+
+            let state = State::get().await?;
+            let creds = state.credentials.read().await;
+            let versions = fetch_json::<HashMap<String, ModrinthVersion>>(
+                Method::POST,
+                &format!("{MODRINTH_API_URL}version_files/update"),
+                None,
+                Some(body),
+                &state.fetch_semaphore,
+                &creds,
+            )
+            .await?;
+
+            Ok(versions
+                .into_iter()
+                .map(|(hash, version)| (hash, ContentVersion::from(version)))
+                .collect())
+        })
+    }
+}
+
+/// Files downloaded directly from a URL, which is used as the project id
+pub struct UrlSource;
+
+impl ContentSource for UrlSource {
+    fn id(&self) -> &str {
+        URL_SOURCE
+    }
+
+    fn get_versions<'a>(
+        &'a self,
+        project_id: &'a str,
+        _filter: &'a VersionFilter,
+    ) -> BoxFuture<'a, crate::Result<Vec<ContentVersion>>> {
+        Box::pin(async move {
+            let url = url::Url::parse(project_id).map_err(|err| {
+                crate::ErrorKind::InputError(format!(
+                    "Invalid URL {project_id}: {err}"
+                ))
+            })?;
+            let filename = url
+                .path_segments()
+                .and_then(|x| x.last())
+                .filter(|x| !x.is_empty())
+                .ok_or_else(|| {
+                    crate::ErrorKind::InputError(format!(
+                        "URL {project_id} does not point to a file"
+                    ))
+                })?
+                .to_string();
+
+            Ok(vec![ContentVersion {
+                source: URL_SOURCE.to_string(),
+                id: project_id.to_string(),
+                project_id: project_id.to_string(),
+                name: filename.clone(),
+                version_number: String::new(),
+                game_versions: Vec::new(),
+                loaders: Vec::new(),
+                files: vec![ContentFile {
+                    url: project_id.to_string(),
+                    filename,
+                    sha1: None,
+                    size: None,
+                    primary: true,
+                }],
+            }])
+        })
+    }
+}
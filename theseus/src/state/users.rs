@@ -1,12 +1,17 @@
 //! User login info
 use crate::auth::Credentials;
 use crate::data::DirectoryInfo;
+use crate::launcher::auth::new_client_id;
+use crate::state::{Schedule, Scheduler};
 use crate::util::fetch::{read_json, write, IoSemaphore};
 use crate::State;
 use std::collections::HashMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 const USERS_JSON: &str = "users.json";
+/// How often the tokens of stored accounts are checked
+const VALIDATION_INTERVAL: Duration = Duration::from_secs(30 * 60);
 
 /// The set of users stored in the launcher
 #[derive(Clone)]
@@ -18,15 +23,54 @@ impl Users {
         io_semaphore: &IoSemaphore,
     ) -> crate::Result<Self> {
         let users_path = dirs.caches_meta_dir().await.join(USERS_JSON);
-        let users = read_json(&users_path, io_semaphore).await.ok();
+        let users: Option<HashMap<Uuid, Credentials>> =
+            read_json(&users_path, io_semaphore).await.ok();
 
-        if let Some(users) = users {
+        if let Some(mut users) = users {
+            // Stored right away, so the id doesn't change between starts
+            let mut generated = false;
+            for credentials in users.values_mut() {
+                if credentials.client_id.is_empty() {
+                    credentials.client_id = new_client_id();
+                    generated = true;
+                }
+            }
+            if generated {
+                write(&users_path, &serde_json::to_vec(&users)?, io_semaphore)
+                    .await?;
+            }
             Ok(Self(users))
         } else {
             Ok(Self(HashMap::new()))
         }
     }
 
+    /// Schedules refreshing the tokens of stored accounts before they
+    /// expire
+    pub fn schedule_validation() {
+        Scheduler::register(
+            "validate_accounts",
+            Schedule::Every {
+                seconds: VALIDATION_INTERVAL.as_secs(),
+            },
+            true,
+            || {
+                Box::pin(async {
+                    match crate::auth::validate_accounts().await {
+                        Ok(ids) if !ids.is_empty() => tracing::info!(
+                            "{} accounts need to log in again",
+                            ids.len()
+                        ),
+                        Ok(_) => {}
+                        Err(err) => {
+                            tracing::warn!("Unable to validate accounts: {err}")
+                        }
+                    };
+                })
+            },
+        );
+    }
+
     pub async fn save(&self) -> crate::Result<()> {
         let state = State::get().await?;
         let users_path =
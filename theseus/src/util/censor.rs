@@ -0,0 +1,120 @@
+//! Hiding private details in game output before it is shown, stored or
+//! shared
+use crate::state::LogCensoring;
+use crate::State;
+use regex::{NoExpand, Regex};
+use std::borrow::Cow;
+
+// IPv4 addresses, then full and compressed IPv6 ones. Compressed addresses
+// need a "::", so timestamps aren't mistaken for them
+const IP_ADDRESS_PATTERNS: [&str; 2] = [
+    r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+    r"\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b|\b(?:[0-9a-fA-F]{1,4}:){1,6}:(?:[0-9a-fA-F]{1,4}:){0,5}[0-9a-fA-F]{1,4}\b",
+];
+const EMAIL_ADDRESS_PATTERN: &str =
+    r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b";
+// Legacy session arguments, then the JWTs Microsoft and Mojang tokens are
+const SESSION_ID_PATTERNS: [(&str, &str); 2] = [
+    (r"token:[^:\s]+:[0-9a-fA-F-]{32,36}", "token:{SESSION_ID}"),
+    (
+        r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+        "{SESSION_ID}",
+    ),
+];
+
+/// Replaces private details in text. Literal strings, such as the access
+/// token of an account, are replaced first, then every pattern
+#[derive(Debug, Clone, Default)]
+pub struct Censor {
+    literals: Vec<(String, String)>,
+    patterns: Vec<(Regex, String)>,
+}
+
+impl Censor {
+    /// Censor hiding the username of the computer, along with whatever the
+    /// settings ask for. Invalid custom patterns are skipped
+    pub fn new(settings: &LogCensoring) -> Self {
+        let mut censor = Self::default();
+        let username = whoami::username();
+        censor.literal(format!("/{username}/"), "/{COMPUTER_USERNAME}/");
+        censor.literal(format!("\\{username}\\"), "\\{COMPUTER_USERNAME}\\");
+
+        let mut patterns = Vec::new();
+        if settings.session_ids {
+            patterns.extend(SESSION_ID_PATTERNS);
+        }
+        if settings.ip_addresses {
+            patterns.extend(IP_ADDRESS_PATTERNS.map(|x| (x, "{IP_ADDRESS}")));
+        }
+        if settings.email_addresses {
+            patterns.push((EMAIL_ADDRESS_PATTERN, "{EMAIL_ADDRESS}"));
+        }
+        patterns.extend(settings.custom_patterns.iter().map(|x| {
+            (
+                x.pattern.as_str(),
+                x.replacement.as_deref().unwrap_or("{CENSORED}"),
+            )
+        }));
+
+        for (pattern, replacement) in patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => {
+                    censor.patterns.push((regex, replacement.to_string()))
+                }
+                Err(err) => {
+                    tracing::warn!("Skipping censor pattern {pattern}: {err}")
+                }
+            }
+        }
+
+        censor
+    }
+
+    /// Censor built from the current settings
+    pub async fn from_settings() -> crate::Result<Self> {
+        let state = State::get().await?;
+        let settings = state.settings.read().await;
+        Ok(Self::new(&settings.log_censoring))
+    }
+
+    /// Adds a string to replace wherever it appears
+    pub fn literal(
+        &mut self,
+        find: impl Into<String>,
+        replacement: impl Into<String>,
+    ) {
+        let find = find.into();
+        // An empty string would match between every character
+        if !find.is_empty() {
+            self.literals.push((find, replacement.into()));
+        }
+    }
+
+    /// Checks a custom pattern compiles, before it is saved to the settings
+    pub fn validate_pattern(pattern: &str) -> crate::Result<()> {
+        Regex::new(pattern).map_err(|err| {
+            crate::ErrorKind::InputError(format!(
+                "Invalid censor pattern {pattern}: {err}"
+            ))
+        })?;
+        Ok(())
+    }
+
+    pub fn censor(&self, text: &str) -> String {
+        let mut text = self
+            .literals
+            .iter()
+            .fold(text.to_string(), |x, (find, replacement)| {
+                x.replace(find, replacement)
+            });
+        // Replacements are taken as is, not as references to groups
+        for (regex, replacement) in &self.patterns {
+            if let Cow::Owned(replaced) =
+                regex.replace_all(&text, NoExpand(replacement))
+            {
+                text = replaced;
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,159 @@
+//! Failures injected into downloads and processors on purpose, so that the
+//! retry, resume and rollback paths can be exercised by tests and when
+//! reproducing bugs
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Rules injected from startup, as a JSON array of `FaultRule`s
+const FAULTS_ENV: &str = "THESEUS_FAULTS";
+
+lazy_static! {
+    static ref FAULTS: Mutex<Vec<InjectedFault>> = Mutex::new(from_env());
+}
+
+/// A failure to inject
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fault {
+    /// The request fails as if the connection dropped
+    NetworkError,
+    /// The download doesn't match the hash it is checked against. Downloads
+    /// without a known hash don't count towards the rule
+    HashMismatch,
+    /// The request is held back before it is sent
+    SlowResponse { delay_ms: u64 },
+    /// A mod loader processor exits with the code instead of running
+    ProcessorExit { code: i32 },
+}
+
+/// When to inject a failure
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FaultRule {
+    pub fault: Fault,
+    /// Only injects into URLs, or processor jars, containing this
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Matching calls let through first, so `2` fails from the third on
+    #[serde(default)]
+    pub skip: u32,
+    /// How many times the failure is injected, every time if unset
+    #[serde(default)]
+    pub times: Option<u32>,
+}
+
+/// A rule being injected, and how it fared so far
+#[derive(Serialize, Debug, Clone)]
+pub struct InjectedFault {
+    pub id: Uuid,
+    pub rule: FaultRule,
+    /// Calls the rule matched, let through or failed
+    pub matched: u32,
+    /// Failures injected
+    pub injected: u32,
+}
+
+impl InjectedFault {
+    fn new(rule: FaultRule) -> Self {
+        InjectedFault {
+            id: Uuid::new_v4(),
+            rule,
+            matched: 0,
+            injected: 0,
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.rule.times.map_or(false, |x| self.injected >= x)
+    }
+}
+
+fn from_env() -> Vec<InjectedFault> {
+    let Ok(rules) = std::env::var(FAULTS_ENV) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<FaultRule>>(&rules) {
+        Ok(rules) => {
+            tracing::warn!(
+                "Injecting {} faults from {FAULTS_ENV}",
+                rules.len()
+            );
+            rules.into_iter().map(InjectedFault::new).collect()
+        }
+        Err(err) => {
+            tracing::error!("Unable to read faults from {FAULTS_ENV}: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Starts injecting a failure, returning the id to remove it by
+pub fn inject(rule: FaultRule) -> Uuid {
+    let fault = InjectedFault::new(rule);
+    let id = fault.id;
+    FAULTS.lock().unwrap().push(fault);
+    id
+}
+
+pub fn remove(id: Uuid) {
+    FAULTS.lock().unwrap().retain(|x| x.id != id);
+}
+
+pub fn clear() {
+    FAULTS.lock().unwrap().clear();
+}
+
+pub fn list() -> Vec<InjectedFault> {
+    FAULTS.lock().unwrap().clone()
+}
+
+// Counts a call against the first rule that applies to it and isn't used up,
+// returning the fault if it is to be injected this time
+fn fire(target: &str, applies: impl Fn(&Fault) -> bool) -> Option<Fault> {
+    let mut faults = FAULTS.lock().unwrap();
+    let fault = faults.iter_mut().find(|x| {
+        applies(&x.rule.fault)
+            && !x.exhausted()
+            && x.rule
+                .target
+                .as_ref()
+                .map_or(true, |x| target.contains(x.as_str()))
+    })?;
+
+    fault.matched += 1;
+    if fault.matched <= fault.rule.skip {
+        return None;
+    }
+    fault.injected += 1;
+    tracing::warn!("Injecting {:?} into {target}", fault.rule.fault);
+    Some(fault.rule.fault.clone())
+}
+
+/// Holds back a request if asked to, returning whether it should then fail
+pub(crate) async fn request(url: &str) -> bool {
+    let fault = fire(url, |x| {
+        matches!(x, Fault::NetworkError | Fault::SlowResponse { .. })
+    });
+    match fault {
+        Some(Fault::SlowResponse { delay_ms }) => {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            false
+        }
+        fault => fault.is_some(),
+    }
+}
+
+/// Whether a download should fail its hash check
+pub(crate) fn hash_mismatch(url: &str) -> bool {
+    fire(url, |x| *x == Fault::HashMismatch).is_some()
+}
+
+/// The code a processor should exit with instead of running
+pub(crate) fn processor_exit(jar: &str) -> Option<i32> {
+    match fire(jar, |x| matches!(x, Fault::ProcessorExit { .. })) {
+        Some(Fault::ProcessorExit { code }) => Some(code),
+        _ => None,
+    }
+}
@@ -1,15 +1,23 @@
 //! Functions for fetching infromation from the Internet
 use crate::event::emit::emit_loading;
 use crate::event::LoadingBarId;
-use crate::state::CredentialsStore;
+use crate::state::{
+    CredentialsStore, DirectoryInfo, DownloadMirror, HttpSettings,
+    ProxyProtocol, ProxySettings, RunningDownloads, CACHES_FOLDER_NAME,
+};
 use bytes::Bytes;
 use lazy_static::lazy_static;
-use reqwest::Method;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Method, StatusCode};
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use std::time::{self, Duration};
-use tokio::sync::{RwLock, Semaphore};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{self, Duration, Instant};
+use tokio::sync::{Notify, RwLock, Semaphore};
 use tokio::{fs::File, io::AsyncWriteExt};
 
 use super::io::{self, IOError};
@@ -19,24 +27,612 @@ pub struct IoSemaphore(pub RwLock<Semaphore>);
 #[derive(Debug)]
 pub struct FetchSemaphore(pub RwLock<Semaphore>);
 
+/// Download rate limiter shared by every concurrent fetch
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    // Caps in bytes per second, 0 meaning unlimited
+    limit: AtomicU64,
+    running_limit: AtomicU64,
+    games_running: AtomicUsize,
+    // Byte budget that may be downloaded right away. It goes negative when
+    // fetches get ahead of the cap, and they then sleep until it refills
+    budget: Mutex<(f64, Instant)>,
+    // Bytes downloaded since the start of the window, and the last rate
+    throughput: Mutex<(u64, Instant, u64)>,
+    running_policy: Mutex<RunningDownloads>,
+    manual: Mutex<DownloadOverride>,
+    active_downloads: AtomicUsize,
+    // Woken when a game exits, a download ends or the policy changes
+    changed: Notify,
+}
+
+/// Downloads paused or resumed by hand, regardless of running games
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadOverride {
+    /// Running games pause or throttle downloads as set in the settings
+    #[default]
+    Auto,
+    /// Installs wait until resumed
+    Paused,
+    /// Downloads run at full speed until the last game exits
+    Resumed,
+}
+
+/// Whether downloads are giving way to running games
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct DownloadStatus {
+    pub games_running: usize,
+    pub installs_paused: bool,
+    /// Downloads allowed at a time, if fewer than usual
+    pub concurrency_cap: Option<usize>,
+    /// Download speed cap in bytes per second, if any
+    pub speed_cap: Option<u64>,
+    pub manual: DownloadOverride,
+}
+
+impl BandwidthLimiter {
+    fn new() -> Self {
+        Self {
+            limit: AtomicU64::new(0),
+            running_limit: AtomicU64::new(0),
+            games_running: AtomicUsize::new(0),
+            budget: Mutex::new((0.0, Instant::now())),
+            throughput: Mutex::new((0, Instant::now(), 0)),
+            running_policy: Mutex::new(RunningDownloads::default()),
+            manual: Mutex::new(DownloadOverride::Auto),
+            active_downloads: AtomicUsize::new(0),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Sets the global cap, and the cap used while a game is running, in
+    /// KB/s. Without a separate cap, the global one applies at all times
+    pub fn set_limits(&self, limit: Option<u64>, running_limit: Option<u64>) {
+        let limit = limit.unwrap_or(0) * 1024;
+        self.limit.store(limit, Ordering::Relaxed);
+        self.running_limit.store(
+            running_limit.map_or(limit, |x| x * 1024),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Sets whether running games pause installs and how many downloads
+    /// they leave running
+    pub fn set_running_policy(&self, policy: RunningDownloads) {
+        *self.running_policy.lock().unwrap() = policy;
+        self.changed.notify_waiters();
+    }
+
+    /// Pauses or resumes downloads by hand, or hands them back to the
+    /// running-game policy
+    pub fn set_override(&self, manual: DownloadOverride) {
+        *self.manual.lock().unwrap() = manual;
+        self.changed.notify_waiters();
+    }
+
+    /// Marks a game as running until the returned guard is dropped
+    pub fn game_running(&'static self) -> GameRunningGuard {
+        self.games_running.fetch_add(1, Ordering::Relaxed);
+        GameRunningGuard(self)
+    }
+
+    pub fn status(&self) -> DownloadStatus {
+        DownloadStatus {
+            games_running: self.games_running.load(Ordering::Relaxed),
+            installs_paused: self.installs_paused(),
+            concurrency_cap: self.concurrency_cap(),
+            speed_cap: self.current_limit(),
+            manual: *self.manual.lock().unwrap(),
+        }
+    }
+
+    /// Whether installs are held until the last game exits or downloads are
+    /// resumed
+    pub fn installs_paused(&self) -> bool {
+        match *self.manual.lock().unwrap() {
+            DownloadOverride::Paused => true,
+            DownloadOverride::Resumed => false,
+            DownloadOverride::Auto => {
+                self.running_policy.lock().unwrap().pause_installs
+                    && self.games_running.load(Ordering::Relaxed) > 0
+            }
+        }
+    }
+
+    /// Waits until installs may start
+    pub async fn wait_for_installs(&self) {
+        loop {
+            // Created before checking, so a change in between isn't missed
+            let changed = self.changed.notified();
+            if !self.installs_paused() {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    // Downloads allowed at a time while a game is running, if capped
+    fn concurrency_cap(&self) -> Option<usize> {
+        if self.games_running.load(Ordering::Relaxed) == 0
+            || *self.manual.lock().unwrap() == DownloadOverride::Resumed
+        {
+            return None;
+        }
+        self.running_policy
+            .lock()
+            .unwrap()
+            .max_concurrent_downloads
+            .map(|x| x.max(1))
+    }
+
+    /// Waits for a download to be allowed under the running-game cap. It
+    /// counts against the cap until the returned slot is dropped
+    pub async fn download_slot(&'static self) -> DownloadSlot {
+        loop {
+            let changed = self.changed.notified();
+            let active = self.active_downloads.load(Ordering::Acquire);
+            match self.concurrency_cap() {
+                Some(cap) if active >= cap => changed.await,
+                _ => {
+                    if self
+                        .active_downloads
+                        .compare_exchange(
+                            active,
+                            active + 1,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        )
+                        .is_ok()
+                    {
+                        return DownloadSlot(self);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Current cap in bytes per second, if any
+    pub fn current_limit(&self) -> Option<u64> {
+        let limit = if self.games_running.load(Ordering::Relaxed) > 0 {
+            self.running_limit.load(Ordering::Relaxed)
+        } else {
+            self.limit.load(Ordering::Relaxed)
+        };
+        Some(limit).filter(|x| *x > 0)
+    }
+
+    /// Combined download speed of all fetches in bytes per second, measured
+    /// over the last second
+    pub fn throughput(&self) -> u64 {
+        let throughput = self.throughput.lock().unwrap();
+        if throughput.1.elapsed() > Duration::from_secs(2) {
+            0
+        } else {
+            throughput.2
+        }
+    }
+
+    /// Accounts for downloaded bytes, waiting if the cap has been exceeded
+    pub async fn consume(&self, bytes: usize) {
+        {
+            let mut throughput = self.throughput.lock().unwrap();
+            throughput.0 += bytes as u64;
+            let elapsed = throughput.1.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                throughput.2 =
+                    (throughput.0 as f64 / elapsed.as_secs_f64()) as u64;
+                throughput.0 = 0;
+                throughput.1 = Instant::now();
+            }
+        }
+
+        let Some(limit) = self.current_limit() else {
+            return;
+        };
+        let limit = limit as f64;
+
+        let wait = {
+            let mut budget = self.budget.lock().unwrap();
+            let now = Instant::now();
+            // Allow bursts of up to a second worth of data
+            budget.0 = f64::min(
+                budget.0 + (now - budget.1).as_secs_f64() * limit,
+                limit,
+            );
+            budget.1 = now;
+            budget.0 -= bytes as f64;
+            budget.0
+        };
+
+        if wait < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-wait / limit)).await;
+        }
+    }
+}
+
+/// Keeps the running-game cap of the bandwidth limiter active while alive
+#[derive(Debug)]
+pub struct GameRunningGuard(&'static BandwidthLimiter);
+
+impl Drop for GameRunningGuard {
+    fn drop(&mut self) {
+        let running = self.0.games_running.fetch_sub(1, Ordering::Relaxed);
+        // Downloads resumed by hand follow the policy again with the next game
+        if running == 1 {
+            let mut manual = self.0.manual.lock().unwrap();
+            if *manual == DownloadOverride::Resumed {
+                *manual = DownloadOverride::Auto;
+            }
+        }
+        self.0.changed.notify_waiters();
+    }
+}
+
+/// A download counted against the running-game cap while alive
+#[derive(Debug)]
+pub struct DownloadSlot(&'static BandwidthLimiter);
+
+impl Drop for DownloadSlot {
+    fn drop(&mut self) {
+        self.0.active_downloads.fetch_sub(1, Ordering::AcqRel);
+        self.0.changed.notify_waiters();
+    }
+}
+
+/// Counters of the requests made through the shared client
+#[derive(Debug, Default)]
+struct RequestCounters {
+    requests: AtomicU64,
+    active: AtomicUsize,
+    retries: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Snapshot of the requests made through the shared HTTP client
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct ConnectionMetrics {
+    /// Requests sent, including retries
+    pub requests: u64,
+    /// Requests currently in flight
+    pub active: usize,
+    pub retries: u64,
+    /// Fetches that gave up after every attempt
+    pub failed: u64,
+    /// Combined download speed in bytes per second
+    pub throughput: u64,
+}
+
 lazy_static! {
-    static ref REQWEST_CLIENT: reqwest::Client = {
-        let mut headers = reqwest::header::HeaderMap::new();
-        let header = reqwest::header::HeaderValue::from_str(&format!(
-            "modrinth/theseus/{} (support@modrinth.com)",
-            env!("CARGO_PKG_VERSION")
-        ))
-        .unwrap();
-        headers.insert(reqwest::header::USER_AGENT, header);
-        reqwest::Client::builder()
-            .tcp_keepalive(Some(time::Duration::from_secs(10)))
-            .default_headers(headers)
-            .build()
-            .expect("Reqwest Client Building Failed")
+    pub static ref BANDWIDTH_LIMITER: BandwidthLimiter =
+        BandwidthLimiter::new();
+    static ref REQWEST_CLIENT: std::sync::RwLock<reqwest::Client> =
+        std::sync::RwLock::new(
+            build_client(&HttpSettings::default())
+                .expect("Reqwest Client Building Failed")
+        );
+    static ref REQUEST_COUNTERS: RequestCounters = RequestCounters::default();
+    // Hosts that answered with empty or truncated files, skipped as mirrors
+    // until the launcher restarts
+    static ref BAD_MIRRORS: Mutex<HashSet<String>> =
+        Mutex::new(HashSet::new());
+    static ref MODRINTH_QUOTA: Mutex<ApiQuota> =
+        Mutex::new(ApiQuota::default());
+    static ref MIRRORS: std::sync::RwLock<Vec<DownloadMirror>> =
+        std::sync::RwLock::new(Vec::new());
+    // Moving average of how long each host took to answer, in milliseconds
+    static ref HOST_LATENCY: Mutex<HashMap<String, u64>> =
+        Mutex::new(HashMap::new());
+}
+
+// Requests that didn't get an answer count as taking at least this long, so
+// hosts that can't be reached are tried last
+const FAILED_LATENCY: Duration = Duration::from_secs(30);
+
+fn build_client(settings: &HttpSettings) -> crate::Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    let header = reqwest::header::HeaderValue::from_str(&format!(
+        "modrinth/theseus/{} (support@modrinth.com)",
+        env!("CARGO_PKG_VERSION")
+    ))
+    .unwrap();
+    headers.insert(reqwest::header::USER_AGENT, header);
+
+    let mut builder = reqwest::Client::builder()
+        .tcp_keepalive(Some(time::Duration::from_secs(10)))
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(settings.connect_timeout))
+        .pool_max_idle_per_host(settings.max_idle_connections)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .http2_adaptive_window(true)
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .http2_keep_alive_timeout(Duration::from_secs(10))
+        .http2_keep_alive_while_idle(true)
+        .gzip(true)
+        .brotli(true);
+    if let Some(timeout) = settings.request_timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(proxy) = &settings.proxy {
+        builder = builder.proxy(build_proxy(proxy)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn build_proxy(settings: &ProxySettings) -> crate::Result<reqwest::Proxy> {
+    let scheme = match settings.protocol {
+        ProxyProtocol::Http => "http",
+        ProxyProtocol::Https => "https",
+        ProxyProtocol::Socks5 => "socks5h",
+    };
+    let mut url = url::Url::parse(&format!(
+        "{scheme}://{}:{}",
+        settings.host, settings.port
+    ))?;
+    // Credentials in the url are sent as basic auth to HTTP proxies, and
+    // used to log into SOCKS ones
+    if let Some(username) = &settings.username {
+        let invalid = || {
+            crate::ErrorKind::InputError(format!(
+                "Invalid proxy credentials for {}",
+                settings.host
+            ))
+        };
+        url.set_username(username).map_err(|_| invalid())?;
+        url.set_password(settings.password.as_deref())
+            .map_err(|_| invalid())?;
+    }
+
+    let no_proxy = reqwest::NoProxy::from_string(&settings.no_proxy.join(","));
+    Ok(reqwest::Proxy::all(url)?.no_proxy(no_proxy))
+}
+
+/// Replaces the shared HTTP client with one built from the settings.
+/// Requests already in flight finish on the old one
+pub fn configure_client(settings: &HttpSettings) -> crate::Result<()> {
+    let client = build_client(settings)?;
+    *REQWEST_CLIENT.write().unwrap() = client;
+    *MIRRORS.write().unwrap() = settings.mirrors.clone();
+    Ok(())
+}
+
+/// URLs a file can be downloaded from: its own, and the ones on the enabled
+/// mirrors. Hosts that answered fastest come first. Hosts not measured yet
+/// come before those, mirrors ahead of the original, so each gets measured
+pub fn mirrored_urls(url: &str) -> Vec<String> {
+    let mut urls = MIRRORS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|x| !x.disabled)
+        .filter_map(|x| x.rewrite(url))
+        .collect::<Vec<_>>();
+    if urls.is_empty() {
+        return vec![url.to_string()];
+    }
+    urls.push(url.to_string());
+    urls.dedup();
+
+    let latency = HOST_LATENCY.lock().unwrap();
+    urls.sort_by_key(|x| {
+        mirror_host(x)
+            .and_then(|host| latency.get(&host).copied())
+            .unwrap_or(0)
+    });
+    urls
+}
+
+/// How long each host took to answer on average, in milliseconds
+pub fn host_latencies() -> HashMap<String, u64> {
+    HOST_LATENCY.lock().unwrap().clone()
+}
+
+fn record_latency(url: &str, elapsed: Duration, answered: bool) {
+    let Some(host) = mirror_host(url) else {
+        return;
+    };
+    let sample = if answered {
+        elapsed
+    } else {
+        elapsed.max(FAILED_LATENCY)
     };
+    let sample = sample.as_millis() as u64;
+
+    let mut latency = HOST_LATENCY.lock().unwrap();
+    let average = latency.entry(host).or_insert(sample);
+    // Weighted towards the past, so one slow answer doesn't reorder hosts
+    *average = (*average * 3 + sample) / 4;
+}
+
+/// The HTTP client every request should be made with, so connections are
+/// pooled across the launcher
+pub fn client() -> reqwest::Client {
+    REQWEST_CLIENT.read().unwrap().clone()
+}
+
+/// A websocket opened through the shared HTTP client
+pub type WebSocket = async_tungstenite::WebSocketStream<
+    async_tungstenite::tokio::TokioAdapter<reqwest::Upgraded>,
+>;
+
+/// Opens a websocket to a `wss://` URL. The handshake is sent through the
+/// shared HTTP client, so it goes through the configured proxy like every
+/// other request
+#[tracing::instrument]
+pub async fn connect_websocket(url: &str) -> crate::Result<WebSocket> {
+    use async_tungstenite::tungstenite::{
+        handshake::client::generate_key, protocol::Role,
+    };
+
+    let https_url = url.replacen("wss://", "https://", 1);
+    let response = client()
+        .get(&https_url)
+        .header(reqwest::header::CONNECTION, "Upgrade")
+        .header(reqwest::header::UPGRADE, "websocket")
+        .header(reqwest::header::SEC_WEBSOCKET_VERSION, "13")
+        .header(reqwest::header::SEC_WEBSOCKET_KEY, generate_key())
+        .send()
+        .await?;
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(crate::ErrorKind::InvalidResponseError(
+            url.to_string(),
+            format!("Websocket handshake failed: {}", response.status()),
+        )
+        .into());
+    }
+
+    let upgraded = response.upgrade().await?;
+    Ok(async_tungstenite::WebSocketStream::from_raw_socket(
+        async_tungstenite::tokio::TokioAdapter::new(upgraded),
+        Role::Client,
+        None,
+    )
+    .await)
+}
+
+pub fn connection_metrics() -> ConnectionMetrics {
+    ConnectionMetrics {
+        requests: REQUEST_COUNTERS.requests.load(Ordering::Relaxed),
+        active: REQUEST_COUNTERS.active.load(Ordering::Relaxed),
+        retries: REQUEST_COUNTERS.retries.load(Ordering::Relaxed),
+        failed: REQUEST_COUNTERS.failed.load(Ordering::Relaxed),
+        throughput: BANDWIDTH_LIMITER.throughput(),
+    }
+}
+
+fn record_failure() {
+    REQUEST_COUNTERS.failed.fetch_add(1, Ordering::Relaxed);
+}
+
+// Decrements the in-flight count when a request is done, however it ends
+struct ActiveRequest;
+
+impl ActiveRequest {
+    fn start() -> Self {
+        REQUEST_COUNTERS.requests.fetch_add(1, Ordering::Relaxed);
+        REQUEST_COUNTERS.active.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
 }
+
+impl Drop for ActiveRequest {
+    fn drop(&mut self) {
+        REQUEST_COUNTERS.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 const FETCH_ATTEMPTS: usize = 3;
 
+// Requests rejected by the rate limit are retried this many times, on top of
+// the attempts for failed ones
+const RATE_LIMIT_RETRIES: u32 = 5;
+// Longest wait for a rate limit window to reset, in case a server sends a
+// bogus reset time
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+const RATE_LIMITED_API: &str = "https://api.modrinth.com/";
+
+// What is left of the Modrinth API rate limit, from the headers of the last
+// response. Requests sent since are taken off the remaining count
+#[derive(Default)]
+struct ApiQuota {
+    remaining: Option<u64>,
+    reset: Option<Instant>,
+}
+
+// Waits for the rate limit window to reset once its requests are used up,
+// then reserves one of them
+async fn wait_for_quota() {
+    loop {
+        let wait = {
+            let mut quota = MODRINTH_QUOTA.lock().unwrap();
+            let now = Instant::now();
+            match (quota.remaining, quota.reset) {
+                (_, Some(reset)) if reset <= now => {
+                    *quota = ApiQuota::default();
+                    None
+                }
+                (Some(0), Some(reset)) => Some(reset - now),
+                (Some(remaining), _) => {
+                    quota.remaining = Some(remaining.saturating_sub(1));
+                    None
+                }
+                (None, _) => None,
+            }
+        };
+
+        match wait {
+            Some(wait) => {
+                tracing::debug!(
+                    "Modrinth rate limit reached, waiting {wait:?}"
+                );
+                tokio::time::sleep(wait.min(MAX_RATE_LIMIT_WAIT)).await;
+            }
+            None => return,
+        }
+    }
+}
+
+fn header_secs(
+    headers: &reqwest::header::HeaderMap,
+    name: &str,
+) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+fn update_quota(headers: &reqwest::header::HeaderMap) {
+    let Some(remaining) = header_secs(headers, "x-ratelimit-remaining") else {
+        return;
+    };
+    let reset = header_secs(headers, "x-ratelimit-reset")
+        .map(|x| Instant::now() + Duration::from_secs(x));
+
+    let mut quota = MODRINTH_QUOTA.lock().unwrap();
+    quota.remaining = Some(remaining);
+    quota.reset = reset.or(quota.reset);
+}
+
+// Sends a request, keeping to the rate limit of the Modrinth API and retrying
+// the requests it rejects with backoff. Once out of retries, the rejection is
+// returned as is
+async fn send_request(
+    req: reqwest::RequestBuilder,
+    url: &str,
+) -> reqwest::Result<reqwest::Response> {
+    if !url.starts_with(RATE_LIMITED_API) {
+        return req.send().await;
+    }
+
+    let mut retries = 0;
+    loop {
+        wait_for_quota().await;
+        // Streamed bodies can't be sent twice, those go out once
+        let Some(attempt) = req.try_clone() else {
+            return req.send().await;
+        };
+        let response = attempt.send().await?;
+        update_quota(response.headers());
+        if response.status() != StatusCode::TOO_MANY_REQUESTS
+            || retries >= RATE_LIMIT_RETRIES
+        {
+            return Ok(response);
+        }
+
+        retries += 1;
+        REQUEST_COUNTERS.retries.fetch_add(1, Ordering::Relaxed);
+        let headers = response.headers();
+        let wait = header_secs(headers, "retry-after")
+            .or_else(|| header_secs(headers, "x-ratelimit-reset"))
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_secs(1 << retries))
+            .min(MAX_RATE_LIMIT_WAIT);
+        tracing::warn!("Rate limited by {url}, retrying in {wait:?}");
+        tokio::time::sleep(wait).await;
+    }
+}
+
 #[tracing::instrument(skip(semaphore))]
 pub async fn fetch(
     url: &str,
@@ -98,11 +694,57 @@ pub async fn fetch_advanced(
     semaphore: &FetchSemaphore,
     credentials: &CredentialsStore,
 ) -> crate::Result<Bytes> {
+    fetch_sized(
+        method,
+        url,
+        sha1,
+        None,
+        json_body,
+        header,
+        loading_bar,
+        semaphore,
+        credentials,
+    )
+    .await
+}
+
+// Like fetch_advanced, also rejecting bodies that don't match a size known
+// ahead of the request
+#[allow(clippy::too_many_arguments)]
+async fn fetch_sized(
+    method: Method,
+    url: &str,
+    sha1: Option<&str>,
+    size: Option<u64>,
+    json_body: Option<serde_json::Value>,
+    header: Option<(&str, &str)>,
+    loading_bar: Option<(&LoadingBarId, f64)>,
+    semaphore: &FetchSemaphore,
+    credentials: &CredentialsStore,
+) -> crate::Result<Bytes> {
+    let _slot = BANDWIDTH_LIMITER.download_slot().await;
     let io_semaphore = semaphore.0.read().await;
     let _permit = io_semaphore.acquire().await?;
 
+    let client = client();
     for attempt in 1..=(FETCH_ATTEMPTS + 1) {
-        let mut req = REQWEST_CLIENT.request(method.clone(), url);
+        let _active = ActiveRequest::start();
+        if attempt > 1 {
+            REQUEST_COUNTERS.retries.fetch_add(1, Ordering::Relaxed);
+        }
+        #[cfg(feature = "fault_injection")]
+        if super::fault::request(url).await {
+            if attempt <= 3 {
+                continue;
+            }
+            record_failure();
+            return Err(crate::ErrorKind::InjectedFault(format!(
+                "network error fetching {url}"
+            ))
+            .into());
+        }
+
+        let mut req = client.request(method.clone(), url);
 
         if let Some(body) = json_body.clone() {
             req = req.json(&body);
@@ -118,46 +760,77 @@ pub async fn fetch_advanced(
             }
         }
 
-        let result = req.send().await;
+        let sent = Instant::now();
+        let result = send_request(req, url).await;
+        record_latency(url, sent.elapsed(), result.is_ok());
         match result {
+            Ok(x) if x.status() == StatusCode::TOO_MANY_REQUESTS => {
+                record_failure();
+                return Err(crate::ErrorKind::InvalidResponseError(
+                    url.to_string(),
+                    "rate limited, try again later".to_string(),
+                )
+                .into());
+            }
             Ok(x) => {
-                let bytes = if let Some((bar, total)) = &loading_bar {
-                    let length = x.content_length();
-                    if let Some(total_size) = length {
-                        use futures::StreamExt;
-                        let mut stream = x.bytes_stream();
-                        let mut bytes = Vec::new();
-                        while let Some(item) = stream.next().await {
-                            let chunk = item.or(Err(
-                                crate::error::ErrorKind::NoValueFor(
-                                    "fetch bytes".to_string(),
-                                ),
-                            ))?;
-                            bytes.append(&mut chunk.to_vec());
-                            emit_loading(
-                                bar,
-                                (chunk.len() as f64 / total_size as f64)
-                                    * total,
-                                None,
-                            )
-                            .await?;
+                use futures::StreamExt;
+                let length = x.content_length();
+                let mut stream = x.bytes_stream();
+                let mut bytes = Vec::new();
+                let mut stream_error = None;
+                while let Some(item) = stream.next().await {
+                    let chunk = match item {
+                        Ok(chunk) => chunk,
+                        Err(err) => {
+                            stream_error = Some(err);
+                            break;
                         }
+                    };
+                    BANDWIDTH_LIMITER.consume(chunk.len()).await;
+                    bytes.extend_from_slice(&chunk);
 
-                        Ok(bytes::Bytes::from(bytes))
-                    } else {
-                        x.bytes().await
+                    if let (Some((bar, total)), Some(total_size)) =
+                        (&loading_bar, length)
+                    {
+                        emit_loading(
+                            bar,
+                            (chunk.len() as f64 / total_size as f64) * total,
+                            None,
+                        )
+                        .await?;
                     }
-                } else {
-                    x.bytes().await
+                }
+                let bytes = match stream_error {
+                    Some(err) => Err(err),
+                    None => Ok(Bytes::from(bytes)),
                 };
 
                 if let Ok(bytes) = bytes {
+                    if let Err(err) =
+                        check_body(url, &method, &bytes, size.or(length))
+                    {
+                        if attempt <= 3 {
+                            continue;
+                        } else {
+                            record_failure();
+                            mark_bad_mirror(url);
+                            return Err(err);
+                        }
+                    }
+
                     if let Some(sha1) = sha1 {
                         let hash = sha1_async(bytes.clone()).await?;
+                        #[cfg(feature = "fault_injection")]
+                        let hash = if super::fault::hash_mismatch(url) {
+                            "0".repeat(40)
+                        } else {
+                            hash
+                        };
                         if &*hash != sha1 {
                             if attempt <= 3 {
                                 continue;
                             } else {
+                                record_failure();
                                 return Err(crate::ErrorKind::HashError(
                                     sha1.to_string(),
                                     hash,
@@ -172,11 +845,13 @@ pub async fn fetch_advanced(
                 } else if attempt <= 3 {
                     continue;
                 } else if let Err(err) = bytes {
+                    record_failure();
                     return Err(err.into());
                 }
             }
             Err(_) if attempt <= 3 => continue,
             Err(err) => {
+                record_failure();
                 return Err(err.into());
             }
         }
@@ -185,12 +860,148 @@ pub async fn fetch_advanced(
     unreachable!()
 }
 
-/// Downloads a file from specified mirrors
+/// Validators of a response stored by [`fetch_cached`]
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedResponse {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Fetches a file through the HTTP cache in `cache_dir`. A stored copy is
+/// revalidated with its ETag or Last-Modified date, so unchanged files are
+/// not downloaded again, and is served as is when the server can't be reached
+#[tracing::instrument(skip(semaphore, io_semaphore))]
+#[theseus_macros::debug_pin]
+pub async fn fetch_cached(
+    url: &str,
+    sha1: Option<&str>,
+    cache_dir: &Path,
+    semaphore: &FetchSemaphore,
+    io_semaphore: &IoSemaphore,
+) -> crate::Result<Bytes> {
+    let key = sha1::Sha1::from(url).hexdigest();
+    let body_path = cache_dir.join(&key);
+    let meta_path = cache_dir.join(format!("{key}.json"));
+
+    let cached = if body_path.exists() {
+        read_json::<CachedResponse>(&meta_path, io_semaphore)
+            .await
+            .ok()
+            .filter(|x| x.url == url)
+    } else {
+        None
+    };
+
+    // Mirrors are tried in turn, the file being cached under its own URL
+    let mut response = Ok(None);
+    for source in mirrored_urls(url) {
+        response =
+            request_cached(url, &source, cached.as_ref(), semaphore).await;
+        match &response {
+            Ok(_) => break,
+            Err(err) if source != url => {
+                tracing::warn!("Unable to fetch {url} from {source}: {err}")
+            }
+            Err(_) => {}
+        }
+    }
+
+    match response {
+        Ok(Some((meta, bytes))) => {
+            if let Some(sha1) = sha1 {
+                let hash = sha1_async(bytes.clone()).await?;
+                if &*hash != sha1 {
+                    record_failure();
+                    return Err(crate::ErrorKind::HashError(
+                        sha1.to_string(),
+                        hash,
+                    )
+                    .into());
+                }
+            }
+
+            write(&body_path, &bytes, io_semaphore).await?;
+            write(&meta_path, &serde_json::to_vec(&meta)?, io_semaphore)
+                .await?;
+            tracing::trace!("Cached response of {url}");
+            Ok(bytes)
+        }
+        Ok(None) => {
+            tracing::trace!("Cached response of {url} is up to date");
+            Ok(Bytes::from(io::read(&body_path).await?))
+        }
+        Err(err) if cached.is_some() => {
+            tracing::warn!(
+                "Unable to revalidate {url}, using cached copy: {err}"
+            );
+            Ok(Bytes::from(io::read(&body_path).await?))
+        }
+        Err(err) => {
+            record_failure();
+            Err(err)
+        }
+    }
+}
+
+// Requests a file for `fetch_cached` from one of its sources, revalidating
+// the cached copy. Answers with nothing when the copy is still up to date
+async fn request_cached(
+    url: &str,
+    source: &str,
+    cached: Option<&CachedResponse>,
+    semaphore: &FetchSemaphore,
+) -> crate::Result<Option<(CachedResponse, Bytes)>> {
+    let _slot = BANDWIDTH_LIMITER.download_slot().await;
+    let fetch_semaphore = semaphore.0.read().await;
+    let _permit = fetch_semaphore.acquire().await?;
+    let _active = ActiveRequest::start();
+
+    let mut req = client().get(source);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let sent = Instant::now();
+    let result = req.send().await;
+    record_latency(source, sent.elapsed(), result.is_ok());
+    match result.and_then(|x| x.error_for_status()) {
+        Ok(x) if x.status() == StatusCode::NOT_MODIFIED => Ok(None),
+        Ok(x) => {
+            let header = |name| {
+                x.headers()
+                    .get(name)
+                    .and_then(|x| x.to_str().ok())
+                    .map(|x| x.to_string())
+            };
+            let meta = CachedResponse {
+                url: url.to_string(),
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+            };
+            let length = x.content_length();
+            let bytes = x.bytes().await?;
+            check_body(source, &Method::GET, &bytes, length)?;
+            Ok(Some((meta, bytes)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Downloads a file from specified mirrors, moving on to the next one when
+/// a mirror fails. Mirrors that served empty or truncated files before are
+/// only tried once every other one has failed
 #[tracing::instrument(skip(semaphore))]
 #[theseus_macros::debug_pin]
 pub async fn fetch_mirrors(
     mirrors: &[&str],
     sha1: Option<&str>,
+    size: Option<u64>,
     semaphore: &FetchSemaphore,
     credentials: &CredentialsStore,
 ) -> crate::Result<Bytes> {
@@ -201,15 +1012,88 @@ pub async fn fetch_mirrors(
         .into());
     }
 
-    for (index, mirror) in mirrors.iter().enumerate() {
-        let result = fetch(mirror, sha1, semaphore, credentials).await;
+    // Configured mirrors of each URL are tried right after it
+    let mirrored = mirrors
+        .iter()
+        .flat_map(|x| mirrored_urls(x))
+        .collect::<Vec<_>>();
+    let (mut mirrors, bad): (Vec<&str>, Vec<&str>) = mirrored
+        .iter()
+        .map(|x| x.as_str())
+        .partition(|x| !is_bad_mirror(x));
+    mirrors.extend(bad);
 
-        if result.is_ok() || (result.is_err() && index == (mirrors.len() - 1)) {
-            return result;
+    let mut error = None;
+    for mirror in mirrors {
+        let result = fetch_sized(
+            Method::GET,
+            mirror,
+            sha1,
+            size,
+            None,
+            None,
+            None,
+            semaphore,
+            credentials,
+        )
+        .await;
+        match result {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) => {
+                tracing::warn!(
+                    "Unable to download from mirror {mirror}: {err}"
+                );
+                error = Some(err);
+            }
         }
     }
 
-    unreachable!()
+    match error {
+        Some(err) => Err(err),
+        None => unreachable!(),
+    }
+}
+
+// Rejects bodies a misbehaving server sent instead of an error: empty files,
+// or ones cut off before reaching their expected size
+fn check_body(
+    url: &str,
+    method: &Method,
+    bytes: &Bytes,
+    expected: Option<u64>,
+) -> crate::Result<()> {
+    let received = bytes.len() as u64;
+    if received == 0 && *method == Method::GET && expected != Some(0) {
+        return Err(crate::ErrorKind::InvalidResponseError(
+            url.to_string(),
+            "empty body".to_string(),
+        )
+        .into());
+    }
+    if let Some(expected) = expected.filter(|x| *x != received) {
+        return Err(crate::ErrorKind::InvalidResponseError(
+            url.to_string(),
+            format!("expected {expected} bytes, received {received}"),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn mirror_host(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(|x| x.to_string())
+}
+
+fn mark_bad_mirror(url: &str) {
+    if let Some(host) = mirror_host(url) {
+        tracing::warn!("Skipping {host} as a mirror after a bad response");
+        BAD_MIRRORS.lock().unwrap().insert(host);
+    }
+}
+
+fn is_bad_mirror(url: &str) -> bool {
+    mirror_host(url)
+        .map_or(false, |host| BAD_MIRRORS.lock().unwrap().contains(&host))
 }
 
 /// Using labrinth API, checks if an internet response can be found, with a timeout in seconds
@@ -256,16 +1140,161 @@ pub async fn write<'a>(
         io::create_dir_all(parent).await?;
     }
 
-    let mut file = File::create(path)
-        .await
-        .map_err(|e| IOError::with_path(e, path))?;
-    file.write_all(bytes)
+    // The file is put together in the staging folder and moved in place once
+    // complete, so a cancelled task or a full disk never leaves a truncated
+    // file behind
+    let staging_dir = staging_dir()?;
+    io::create_dir_all(&staging_dir).await?;
+    let staged =
+        StagedFile(staging_dir.join(format!("{}.part", uuid::Uuid::new_v4())));
+
+    let mut file = File::create(&staged.0)
         .await
-        .map_err(|e| IOError::with_path(e, path))?;
+        .map_err(|e| write_error(e, &staged.0))?;
+    for chunk in bytes.chunks(WRITE_CHUNK_SIZE) {
+        file.write_all(chunk)
+            .await
+            .map_err(|e| write_error(e, path))?;
+        // Lets an aborted task stop here rather than after the whole file
+        tokio::task::yield_now().await;
+    }
+    file.sync_all().await.map_err(|e| write_error(e, path))?;
+    drop(file);
+
+    staged.persist(path).await?;
     tracing::trace!("Done writing file {}", path.display());
     Ok(())
 }
 
+// Files are written in chunks of this size, yielding in between
+const WRITE_CHUNK_SIZE: usize = 1 << 20;
+const STAGING_FOLDER_NAME: &str = "staging";
+
+// Staged files this old were left behind by a launcher that was killed
+const STAGING_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+// Resolved without the state, as the state writes files while loading
+fn staging_dir() -> crate::Result<PathBuf> {
+    let settings_dir = DirectoryInfo::get_initial_settings_dir().ok_or(
+        crate::ErrorKind::FSError(
+            "Could not find valid config dir".to_string(),
+        ),
+    )?;
+    Ok(settings_dir
+        .join(CACHES_FOLDER_NAME)
+        .join(STAGING_FOLDER_NAME))
+}
+
+/// Removes files left in the staging folder by writes that never finished
+pub(crate) async fn sweep_staging() -> crate::Result<()> {
+    let staging_dir = staging_dir()?;
+    if !staging_dir.exists() {
+        return Ok(());
+    }
+
+    let mut read_dir = io::read_dir(&staging_dir).await?;
+    while let Some(entry) = read_dir
+        .next_entry()
+        .await
+        .map_err(|e| IOError::with_path(e, &staging_dir))?
+    {
+        let stale = entry
+            .metadata()
+            .await
+            .ok()
+            .and_then(|x| x.modified().ok())
+            .and_then(|x| x.elapsed().ok())
+            .map_or(false, |x| x > STAGING_MAX_AGE);
+        if stale {
+            io::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+// A file in the staging folder, deleted unless it is moved in place. This
+// includes when the future writing it is dropped
+struct StagedFile(PathBuf);
+
+impl StagedFile {
+    async fn persist(mut self, path: &Path) -> crate::Result<()> {
+        if tokio::fs::rename(&self.0, path).await.is_ok() {
+            self.0 = PathBuf::new();
+            return Ok(());
+        }
+
+        // The staging folder is on another drive than the target, so the file
+        // is copied next to it first to still replace it in one step. Writes
+        // to the same file at once each get their own copy
+        let sibling = path.with_file_name(format!(
+            ".{}.{}.part",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            uuid::Uuid::new_v4()
+        ));
+        let mut sibling = StagedFile(sibling);
+        tokio::fs::copy(&self.0, &sibling.0)
+            .await
+            .map_err(|e| write_error(e, path))?;
+        File::open(&sibling.0)
+            .await
+            .map_err(|e| IOError::with_path(e, &sibling.0))?
+            .sync_all()
+            .await
+            .map_err(|e| write_error(e, path))?;
+        io::rename(&sibling.0, path).await?;
+        sibling.0 = PathBuf::new();
+        Ok(())
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        let path = std::mem::take(&mut self.0);
+        if path.as_os_str().is_empty() {
+            return;
+        }
+
+        // Removed off the async threads where there is a runtime to do so
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let _ = tokio::fs::remove_file(path).await;
+                });
+            }
+            Err(_) => {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+// Running out of space or quota gets its own error, as retrying won't help
+fn write_error(err: std::io::Error, path: &Path) -> crate::Error {
+    // ENOSPC and EDQUOT, or ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL and
+    // ERROR_DISK_QUOTA_EXCEEDED on Windows
+    #[cfg(target_os = "linux")]
+    const DISK_FULL_CODES: &[i32] = &[28, 122];
+    #[cfg(target_os = "macos")]
+    const DISK_FULL_CODES: &[i32] = &[28, 69];
+    #[cfg(target_os = "windows")]
+    const DISK_FULL_CODES: &[i32] = &[39, 112, 1295];
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "windows"
+    )))]
+    const DISK_FULL_CODES: &[i32] = &[28];
+
+    if err
+        .raw_os_error()
+        .map_or(false, |x| DISK_FULL_CODES.contains(&x))
+    {
+        crate::ErrorKind::DiskFullError(path.display().to_string()).into()
+    } else {
+        IOError::with_path(err, path).into()
+    }
+}
+
 pub async fn copy(
     src: impl AsRef<std::path::Path>,
     dest: impl AsRef<std::path::Path>,
@@ -290,6 +1319,42 @@ pub async fn copy(
     Ok(())
 }
 
+/// Hard links a file from the shared cache into place, falling back to a copy
+/// when the filesystem (or the platform) does not support hard links
+pub async fn link_or_copy(
+    src: impl AsRef<std::path::Path>,
+    dest: impl AsRef<std::path::Path>,
+    semaphore: &IoSemaphore,
+) -> crate::Result<()> {
+    let src: &Path = src.as_ref();
+    let dest = dest.as_ref();
+
+    let io_semaphore = semaphore.0.read().await;
+    let _permit = io_semaphore.acquire().await?;
+
+    if let Some(parent) = dest.parent() {
+        io::create_dir_all(parent).await?;
+    }
+
+    if dest.exists() {
+        io::remove_file(dest).await?;
+    }
+
+    if let Err(err) = io::hard_link(src, dest).await {
+        tracing::debug!(
+            "Could not hard link {}, copying instead: {err}",
+            src.display()
+        );
+        io::copy(src, dest).await?;
+    }
+    tracing::trace!(
+        "Done linking file {} to {}",
+        src.display(),
+        dest.display()
+    );
+    Ok(())
+}
+
 // Writes a icon to the cache and returns the absolute path of the icon within the cache directory
 #[tracing::instrument(skip(bytes, semaphore))]
 pub async fn write_cached_icon(
@@ -312,7 +1377,7 @@ pub async fn write_cached_icon(
     Ok(path)
 }
 
-async fn sha1_async(bytes: Bytes) -> crate::Result<String> {
+pub async fn sha1_async(bytes: Bytes) -> crate::Result<String> {
     let hash = tokio::task::spawn_blocking(move || {
         sha1::Sha1::from(bytes).hexdigest()
     })
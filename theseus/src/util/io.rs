@@ -1,6 +1,7 @@
 // IO error
 // A wrapper around the tokio IO functions that adds the path to the error message, instead of the uninformative std::io::Error.
 
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 #[derive(Debug, thiserror::Error)]
@@ -30,6 +31,132 @@ impl IOError {
             path: path.to_string_lossy().to_string(),
         }
     }
+
+    /// The path and likely cause, if access to the path was refused
+    pub fn permission_denied(&self) -> Option<(String, PermissionCause)> {
+        match self {
+            IOError::IOPathError { source, path } => {
+                PermissionCause::of(source, Path::new(path))
+                    .map(|cause| (path.clone(), cause))
+            }
+            IOError::IOError(_) => None,
+        }
+    }
+}
+
+/// Likely reason the launcher was refused access to a path, guessed from
+/// the error and where the path is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionCause {
+    /// The drive is mounted read-only
+    ReadOnlyMount,
+    /// The folder is synced by an app like OneDrive, which locks files
+    /// while uploading them and can make folders read-only
+    CloudSync,
+    /// Windows' Controlled Folder Access, which blocks unknown apps from
+    /// writing to Documents, Desktop and the like
+    ControlledFolderAccess,
+    /// Another program, usually antivirus software, has the file open
+    FileLocked,
+    /// Permissions of the path, or antivirus software blocking the launcher
+    Permissions,
+}
+
+// EROFS, or ERROR_WRITE_PROTECT on Windows
+#[cfg(windows)]
+const READ_ONLY_CODES: &[i32] = &[19];
+#[cfg(not(windows))]
+const READ_ONLY_CODES: &[i32] = &[30];
+
+// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+#[cfg(windows)]
+const LOCKED_CODES: &[i32] = &[32, 33];
+#[cfg(not(windows))]
+const LOCKED_CODES: &[i32] = &[];
+
+// Folder names cloud storage apps sync, some followed by an account name
+const CLOUD_SYNC_FOLDERS: &[&str] = &[
+    "OneDrive",
+    "Dropbox",
+    "Google Drive",
+    "iCloud Drive",
+    "Mobile Documents",
+];
+
+impl PermissionCause {
+    /// Why access to a path was refused, or `None` if the error isn't about
+    /// access
+    pub fn of(err: &std::io::Error, path: &Path) -> Option<Self> {
+        let code = err.raw_os_error();
+        if code.map_or(false, |x| READ_ONLY_CODES.contains(&x)) {
+            Some(Self::ReadOnlyMount)
+        } else if code.map_or(false, |x| LOCKED_CODES.contains(&x)) {
+            Some(Self::FileLocked)
+        } else if err.kind() != std::io::ErrorKind::PermissionDenied {
+            None
+        } else if is_cloud_synced(path) {
+            Some(Self::CloudSync)
+        } else if cfg!(windows) && is_controlled_folder(path) {
+            Some(Self::ControlledFolderAccess)
+        } else {
+            Some(Self::Permissions)
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ReadOnlyMount => "the drive it is on is read-only",
+            Self::CloudSync => {
+                "it is in a folder synced by a cloud storage app like OneDrive"
+            }
+            Self::ControlledFolderAccess => {
+                "Windows Controlled Folder Access may be blocking the launcher"
+            }
+            Self::FileLocked => {
+                "another program, likely antivirus software, is using it"
+            }
+            Self::Permissions => {
+                "its permissions or antivirus software don't allow it"
+            }
+        })
+    }
+}
+
+/// Whether a path is in a folder a cloud storage app syncs
+pub fn is_cloud_synced(path: &Path) -> bool {
+    path.components().any(|x| {
+        let name = x.as_os_str().to_string_lossy();
+        CLOUD_SYNC_FOLDERS.iter().any(|x| name.starts_with(x))
+    })
+}
+
+// Folders Controlled Folder Access protects by default
+fn is_controlled_folder(path: &Path) -> bool {
+    [
+        dirs::document_dir(),
+        dirs::desktop_dir(),
+        dirs::picture_dir(),
+        dirs::video_dir(),
+        dirs::audio_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|x| path.starts_with(x))
+}
+
+/// Checks a folder can be written to, by creating a file in it and
+/// removing it again
+pub async fn check_write_access(
+    dir: impl AsRef<std::path::Path>,
+) -> Result<(), IOError> {
+    let dir = dir.as_ref();
+    create_dir_all(dir).await?;
+    let probe = dir.join(format!(".write_test_{}", uuid::Uuid::new_v4()));
+    write(&probe, b"").await?;
+    remove_file(&probe).await
 }
 
 // dunce canonicalize
@@ -164,3 +291,18 @@ pub async fn remove_file(
             path: path.to_string_lossy().to_string(),
         })
 }
+
+// hard_link
+pub async fn hard_link(
+    src: impl AsRef<std::path::Path>,
+    dst: impl AsRef<std::path::Path>,
+) -> Result<(), IOError> {
+    let src = src.as_ref();
+    let dst = dst.as_ref();
+    tokio::fs::hard_link(src, dst)
+        .await
+        .map_err(|e| IOError::IOPathError {
+            source: e,
+            path: src.to_string_lossy().to_string(),
+        })
+}
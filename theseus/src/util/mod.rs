@@ -1,8 +1,16 @@
 //! Theseus utility functions
+pub mod censor;
+#[cfg(feature = "fault_injection")]
+pub mod fault;
 pub mod fetch;
 pub mod io;
 pub mod jre;
+pub mod nbt;
 pub mod platform;
+pub mod process_tree;
+pub mod process_usage;
+#[cfg(feature = "server_proxy")]
+pub mod proxy;
 
 /// Wrap a builder which uses a mut reference into one which outputs an owned value
 macro_rules! wrap_ref_builder {
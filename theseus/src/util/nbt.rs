@@ -0,0 +1,310 @@
+//! Reading and writing uncompressed NBT, the format of files like servers.dat
+
+/// A named binary tag. Compounds keep the order of their entries, so files
+/// are written back the way the game wrote them
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Entry of a compound by name
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => {
+                entries.iter().find(|(x, _)| x == name).map(|(_, x)| x)
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets an entry of a compound, keeping its place if it exists
+    pub fn set(&mut self, name: &str, value: Tag) {
+        if let Tag::Compound(entries) = self {
+            match entries.iter_mut().find(|(x, _)| x == name) {
+                Some((_, x)) => *x = value,
+                None => entries.push((name.to_string(), value)),
+            }
+        }
+    }
+
+    /// Removes an entry of a compound
+    pub fn remove(&mut self, name: &str) {
+        if let Tag::Compound(entries) = self {
+            entries.retain(|(x, _)| x != name);
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> Option<i8> {
+        match self {
+            Tag::Byte(x) => Some(*x),
+            _ => None,
+        }
+    }
+}
+
+/// Reads an uncompressed NBT file into its root tag
+pub fn read(bytes: &[u8]) -> crate::Result<Tag> {
+    let mut reader = Reader { bytes, position: 0 };
+    let id = reader.u8()?;
+    if id != 10 {
+        return Err(nbt_error("root tag is not a compound"));
+    }
+    reader.string()?;
+    reader.payload(id, 0)
+}
+
+/// Writes a root compound as an uncompressed NBT file
+pub fn write(root: &Tag) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.push(root.id());
+    write_string(&mut bytes, "");
+    write_payload(&mut bytes, root);
+    bytes
+}
+
+// Matches the nesting limit of the game
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> crate::Result<&[u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|x| *x <= self.bytes.len())
+            .ok_or_else(|| nbt_error("unexpected end of file"))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> crate::Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> crate::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn len(&mut self) -> crate::Result<usize> {
+        let len = i32::from_be_bytes(self.array()?);
+        usize::try_from(len).map_err(|_| nbt_error("negative length"))
+    }
+
+    fn string(&mut self) -> crate::Result<String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        decode_mutf8(self.take(len)?)
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> crate::Result<Tag> {
+        if depth > MAX_DEPTH {
+            return Err(nbt_error("tags are nested too deep"));
+        }
+
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(self.array()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(
+                    self.take(len)?.iter().map(|x| *x as i8).collect(),
+                )
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_id = self.u8()?;
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item_id, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let entry_id = self.u8()?;
+                    if entry_id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(entry_id, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(i32::from_be_bytes(self.array()?));
+                }
+                Tag::IntArray(items)
+            }
+            12 => {
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(i64::from_be_bytes(self.array()?));
+                }
+                Tag::LongArray(items)
+            }
+            id => return Err(nbt_error(&format!("unknown tag type {id}"))),
+        })
+    }
+}
+
+fn write_payload(bytes: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(x) => bytes.extend(x.to_be_bytes()),
+        Tag::Short(x) => bytes.extend(x.to_be_bytes()),
+        Tag::Int(x) => bytes.extend(x.to_be_bytes()),
+        Tag::Long(x) => bytes.extend(x.to_be_bytes()),
+        Tag::Float(x) => bytes.extend(x.to_be_bytes()),
+        Tag::Double(x) => bytes.extend(x.to_be_bytes()),
+        Tag::ByteArray(items) => {
+            bytes.extend((items.len() as i32).to_be_bytes());
+            bytes.extend(items.iter().map(|x| *x as u8));
+        }
+        Tag::String(x) => write_string(bytes, x),
+        Tag::List(items) => {
+            // Empty lists are written with the end tag as their type
+            bytes.push(items.first().map_or(0, |x| x.id()));
+            bytes.extend((items.len() as i32).to_be_bytes());
+            for item in items {
+                write_payload(bytes, item);
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                bytes.push(value.id());
+                write_string(bytes, name);
+                write_payload(bytes, value);
+            }
+            bytes.push(0);
+        }
+        Tag::IntArray(items) => {
+            bytes.extend((items.len() as i32).to_be_bytes());
+            for item in items {
+                bytes.extend(item.to_be_bytes());
+            }
+        }
+        Tag::LongArray(items) => {
+            bytes.extend((items.len() as i32).to_be_bytes());
+            for item in items {
+                bytes.extend(item.to_be_bytes());
+            }
+        }
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, value: &str) {
+    let encoded = encode_mutf8(value);
+    // Longer strings can't be stored, the game truncates them the same way
+    let len = encoded.len().min(u16::MAX as usize);
+    bytes.extend((len as u16).to_be_bytes());
+    bytes.extend(&encoded[..len]);
+}
+
+// Strings are stored in Java's modified UTF-8, which encodes NUL in two
+// bytes and characters outside the BMP as surrogate pairs
+fn encode_mutf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7F => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+fn decode_mutf8(bytes: &[u8]) -> crate::Result<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().copied();
+    while let Some(byte) = iter.next() {
+        let mut next = || {
+            iter.next()
+                .filter(|x| x & 0xC0 == 0x80)
+                .map(|x| (x & 0x3F) as u16)
+                .ok_or_else(|| nbt_error("invalid string"))
+        };
+        let unit = match byte {
+            0x00..=0x7F => byte as u16,
+            0xC0..=0xDF => (((byte & 0x1F) as u16) << 6) | next()?,
+            0xE0..=0xEF => {
+                (((byte & 0x0F) as u16) << 12) | (next()? << 6) | next()?
+            }
+            _ => return Err(nbt_error("invalid string")),
+        };
+        units.push(unit);
+    }
+    String::from_utf16(&units).map_err(|_| nbt_error("invalid string"))
+}
+
+fn nbt_error(message: &str) -> crate::Error {
+    crate::ErrorKind::InputError(format!("Invalid NBT: {message}")).into()
+}
+
+/// Builds a compound from entries, in order
+pub fn compound<const N: usize>(entries: [(&str, Tag); N]) -> Tag {
+    Tag::Compound(
+        entries
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
@@ -1,6 +1,9 @@
 //! Platform-related code
 use daedalus::minecraft::{Os, OsRule};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_os = "linux"))]
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
 
 // OS detection
 pub trait OsExt {
@@ -13,14 +16,15 @@ pub trait OsExt {
 
 impl OsExt for Os {
     fn native_arch(java_arch: &str) -> Self {
+        let java_arch = normalize_arch(java_arch);
         if std::env::consts::OS == "windows" {
-            if java_arch == "aarch64" {
+            if java_arch == "arm64" {
                 Os::WindowsArm64
             } else {
                 Os::Windows
             }
         } else if std::env::consts::OS == "linux" {
-            if java_arch == "aarch64" {
+            if java_arch == "arm64" {
                 Os::LinuxArm64
             } else if java_arch == "arm" {
                 Os::LinuxArm32
@@ -28,7 +32,7 @@ impl OsExt for Os {
                 Os::Linux
             }
         } else if std::env::consts::OS == "macos" {
-            if java_arch == "aarch64" {
+            if java_arch == "arm64" {
                 Os::OsxArm64
             } else {
                 Os::Osx
@@ -48,19 +52,36 @@ impl OsExt for Os {
     }
 }
 
-// Bit width
-#[cfg(target_pointer_width = "64")]
-pub const ARCH_WIDTH: &str = "64";
+/// Architecture of a Java runtime, from its `os.arch`, in the names of
+/// version rules: `x86_64`, `x86`, `arm64` or `arm`. Others are passed
+/// through
+pub fn normalize_arch(java_arch: &str) -> &str {
+    match java_arch {
+        "amd64" | "x86_64" | "x64" => "x86_64",
+        "x86" | "i386" | "i486" | "i586" | "i686" => "x86",
+        "aarch64" | "arm64" => "arm64",
+        arch if arch.starts_with("arm") => "arm",
+        arch => arch,
+    }
+}
 
-#[cfg(target_pointer_width = "32")]
-pub const ARCH_WIDTH: &str = "32";
+/// Bit width of a Java runtime, which replaces `${arch}` in the classifiers
+/// of natives. It is the runtime's, not the launcher's, as a 32-bit Java
+/// can't load 64-bit natives
+pub fn arch_width(java_arch: &str) -> &'static str {
+    match normalize_arch(java_arch) {
+        "x86" | "arm" => "32",
+        _ => "64",
+    }
+}
 
 // Platform rule handling
 pub fn os_rule(rule: &OsRule, java_arch: &str) -> bool {
     let mut rule_match = true;
 
+    // The architecture is the Java runtime's, named as in the manifests
     if let Some(ref arch) = rule.arch {
-        rule_match &= !matches!(arch.as_str(), "x86" | "arm");
+        rule_match &= arch.as_str() == normalize_arch(java_arch);
     }
 
     if let Some(name) = &rule.name {
@@ -78,6 +99,375 @@ pub fn os_rule(rule: &OsRule, java_arch: &str) -> bool {
     rule_match
 }
 
+/// Whether a process with the given PID currently exists
+pub fn is_process_running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        std::path::Path::new(&format!("/proc/{pid}")).exists()
+    }
+
+    // Looked up by its exact PID rather than by listing processes
+    #[cfg(not(target_os = "linux"))]
+    {
+        let mut system = System::new();
+        system.refresh_process(Pid::from_u32(pid))
+    }
+}
+
+/// When the process with the given PID was started, in a form only meant to
+/// be compared against an earlier reading. Tells a process apart from a later
+/// one that was given the same PID
+pub fn process_start_time(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        // The start time is the 22nd field, counted after the command name,
+        // which is in parentheses and may contain spaces
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(19).map(|x| x.to_string())
+    }
+
+    // Read without spawning a command, as reattached games are polled
+    #[cfg(not(target_os = "linux"))]
+    {
+        let pid = Pid::from_u32(pid);
+        let mut system = System::new();
+        if !system.refresh_process(pid) {
+            return None;
+        }
+        system.process(pid).map(|x| x.start_time().to_string())
+    }
+}
+
+/// A graphics card and the version of its driver
+#[derive(Debug, Clone)]
+pub struct GpuDriver {
+    pub name: String,
+    pub version: String,
+}
+
+/// The graphics cards of the system with their driver versions, as far as
+/// they can be found without extra tools. On Linux, drivers built into the
+/// kernel report its version. None are found on macOS
+pub fn gpu_drivers() -> Vec<GpuDriver> {
+    #[cfg(target_os = "windows")]
+    {
+        let output = hidden_command("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Get-CimInstance Win32_VideoController | ForEach-Object \
+                 { \"$($_.Name)|$($_.DriverVersion)\" }",
+            ])
+            .output();
+        output
+            .map(|x| {
+                String::from_utf8_lossy(&x.stdout)
+                    .lines()
+                    .filter_map(|x| x.trim().split_once('|'))
+                    .filter(|(_, version)| !version.is_empty())
+                    .map(|(name, version)| GpuDriver {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Drivers built into the kernel share its version, only separate
+        // modules such as NVIDIA's report their own
+        let kernel = sys_info::os_release().unwrap_or_default();
+        drm_cards()
+            .into_iter()
+            .map(|card| {
+                let version = card
+                    .driver
+                    .and_then(|x| {
+                        std::fs::read_to_string(format!(
+                            "/sys/module/{x}/version"
+                        ))
+                        .ok()
+                    })
+                    .map_or_else(|| kernel.clone(), |x| x.trim().to_string());
+                GpuDriver {
+                    name: card.name,
+                    version,
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        Vec::new()
+    }
+}
+
+/// A graphics card of the system
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Gpu {
+    pub name: String,
+    /// NVIDIA, AMD, Intel, or the PCI vendor id of other vendors
+    pub vendor: String,
+    /// Whether the card is a dedicated one rather than part of the CPU, as
+    /// guessed from its vendor and name
+    pub discrete: bool,
+}
+
+/// The graphics cards of the system. None are found on macOS, which
+/// switches between them on its own
+pub fn gpus() -> Vec<Gpu> {
+    #[cfg(target_os = "windows")]
+    {
+        let output = hidden_command("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Get-CimInstance Win32_VideoController | ForEach-Object \
+                 { \"$($_.Name)|$($_.AdapterCompatibility)\" }",
+            ])
+            .output();
+        output
+            .map(|x| {
+                String::from_utf8_lossy(&x.stdout)
+                    .lines()
+                    .filter_map(|x| x.trim().split_once('|'))
+                    .map(|(name, vendor)| {
+                        let vendor = gpu_vendor_name(vendor);
+                        Gpu {
+                            discrete: is_discrete_gpu(&vendor, name),
+                            name: name.to_string(),
+                            vendor,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let cards = drm_cards();
+
+        // The card the system booted with is the integrated one of an AMD
+        // laptop with another card
+        let hybrid = cards.len() > 1;
+        cards
+            .into_iter()
+            .map(|card| Gpu {
+                discrete: is_discrete_gpu(&card.vendor, &card.name)
+                    && !(hybrid && card.boot_vga && card.vendor == "AMD"),
+                name: card.name,
+                vendor: card.vendor,
+            })
+            .collect()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        Vec::new()
+    }
+}
+
+// A graphics card as the kernel lists it
+#[cfg(target_os = "linux")]
+struct DrmCard {
+    vendor: String,
+    name: String,
+    boot_vga: bool,
+    // Module driving the card, such as amdgpu or nvidia
+    driver: Option<String>,
+}
+
+// The cards in /sys/class/drm, sorted by name
+#[cfg(target_os = "linux")]
+fn drm_cards() -> Vec<DrmCard> {
+    // Cards are listed as cardN, their outputs as cardN-<connector>
+    let card = Regex::new(r"^card\d+$").unwrap();
+    let mut cards = std::fs::read_dir("/sys/class/drm")
+        .map(|x| {
+            x.filter_map(|x| x.ok())
+                .filter(|x| card.is_match(&x.file_name().to_string_lossy()))
+                .filter_map(|x| {
+                    let device = x.path().join("device");
+                    let read = |name: &str| {
+                        std::fs::read_to_string(device.join(name))
+                            .map(|x| x.trim().to_string())
+                    };
+                    let vendor = gpu_vendor_name(&read("vendor").ok()?);
+                    let name = format!(
+                        "{vendor} {}",
+                        read("device").unwrap_or_default()
+                    );
+                    let driver = std::fs::read_link(device.join("driver"))
+                        .ok()
+                        .and_then(|x| {
+                            Some(x.file_name()?.to_string_lossy().to_string())
+                        });
+                    Some(DrmCard {
+                        boot_vga: read("boot_vga").map_or(false, |x| x == "1"),
+                        vendor,
+                        name,
+                        driver,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    cards.sort_by(|a, b| a.name.cmp(&b.name));
+    cards
+}
+
+// Names the vendors found in hybrid laptops, by their PCI id or the name
+// Windows reports
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn gpu_vendor_name(vendor: &str) -> String {
+    let lower = vendor.to_lowercase();
+    if lower == "0x10de" || lower.contains("nvidia") {
+        "NVIDIA".to_string()
+    } else if lower == "0x1002"
+        || lower.contains("amd")
+        || lower.contains("advanced micro devices")
+        || lower.contains("ati ")
+    {
+        "AMD".to_string()
+    } else if lower == "0x8086" || lower.contains("intel") {
+        "Intel".to_string()
+    } else {
+        vendor.to_string()
+    }
+}
+
+// Intel cards are integrated unless they are Arc ones, and AMD ones named
+// only "Graphics" are the integrated cards of Ryzen CPUs
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn is_discrete_gpu(vendor: &str, name: &str) -> bool {
+    match vendor {
+        "Intel" => name.contains("Arc"),
+        "AMD" => !name.trim_end().ends_with("Graphics"),
+        _ => true,
+    }
+}
+
+/// Whether the system marks the current connection as metered, like a
+/// phone hotspot. Where that can't be told it is assumed not to be
+pub fn is_metered_connection() -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        // The cost type is Unrestricted unless the connection is metered
+        let output = hidden_command("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "[Windows.Networking.Connectivity.NetworkInformation, \
+                 Windows.Networking.Connectivity, \
+                 ContentType = WindowsRuntime]::\
+                 GetInternetConnectionProfile().GetConnectionCost().\
+                 NetworkCostType",
+            ])
+            .output();
+        output.map_or(false, |x| {
+            matches!(
+                String::from_utf8_lossy(&x.stdout).trim(),
+                "Fixed" | "Variable"
+            )
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // NetworkManager's metered state, where 1 is yes and 3 a guessed yes
+        let output = std::process::Command::new("busctl")
+            .args([
+                "get-property",
+                "org.freedesktop.NetworkManager",
+                "/org/freedesktop/NetworkManager",
+                "org.freedesktop.NetworkManager",
+                "Metered",
+            ])
+            .output();
+        output.map_or(false, |x| {
+            matches!(String::from_utf8_lossy(&x.stdout).trim(), "u 1" | "u 3")
+        })
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        false
+    }
+}
+
+/// Display server of the desktop session
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    X11,
+    Wayland,
+    /// Not a Linux desktop, or one the launcher can't tell
+    Unknown,
+}
+
+/// The display server of the desktop session the launcher runs in
+pub fn session_type() -> SessionType {
+    #[cfg(target_os = "linux")]
+    {
+        // Not every session sets its type, but each sets the variables
+        // clients connect with
+        match std::env::var("XDG_SESSION_TYPE").as_deref() {
+            Ok("wayland") => SessionType::Wayland,
+            Ok("x11") => SessionType::X11,
+            _ if std::env::var_os("WAYLAND_DISPLAY").is_some() => {
+                SessionType::Wayland
+            }
+            _ if std::env::var_os("DISPLAY").is_some() => SessionType::X11,
+            _ => SessionType::Unknown,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        SessionType::Unknown
+    }
+}
+
+/// Forcefully stops a process that is not a child of the launcher
+pub fn kill_process(pid: u32) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let status = hidden_command("taskkill")
+        .args(["/F", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .status()?;
+
+    #[cfg(not(target_os = "windows"))]
+    let status = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Could not stop process {pid}"),
+        ))
+    }
+}
+
+// A command that doesn't flash a console window, as the launcher runs
+// without one on Windows
+#[cfg(target_os = "windows")]
+fn hidden_command(program: &str) -> std::process::Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = std::process::Command::new(program);
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
 pub fn classpath_separator(java_arch: &str) -> &'static str {
     match Os::native_arch(java_arch) {
         Os::Osx
@@ -0,0 +1,180 @@
+//! Stopping a launched process together with everything it started
+//!
+//! Wrappers like gamemoderun or prime-run start the game as a child of their
+//! own, so killing only the process the launcher spawned leaves the game
+//! running. On Windows the tree is put in a job object, and on Unix in its
+//! own process group. Neither is tied to the launcher's lifetime, since games
+//! outlive launcher restarts and are reattached afterwards
+use tokio::process::{Child, Command};
+
+/// Gives a command its own process group, so it and its descendants can be
+/// signalled at once. Does nothing on Windows, where the job object is only
+/// created once the process exists
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// A spawned process and all processes it starts
+#[derive(Debug)]
+pub struct ProcessTree {
+    pid: u32,
+    #[cfg(windows)]
+    job: Option<windows::Job>,
+}
+
+impl ProcessTree {
+    /// Tracks the tree of a child spawned from an isolated command
+    pub fn attach(child: &Child, pid: u32) -> Self {
+        #[cfg(windows)]
+        {
+            // The launcher can itself be in a job that doesn't allow nesting,
+            // in which case the tree is killed through taskkill instead
+            let job = windows::Job::new()
+                .and_then(|job| job.assign(child).map(|_| job))
+                .map_err(|err| {
+                    tracing::warn!(
+                        "Unable to put process {pid} in a job object: {err}"
+                    )
+                })
+                .ok();
+            Self { pid, job }
+        }
+
+        #[cfg(not(windows))]
+        {
+            let _ = child;
+            Self { pid }
+        }
+    }
+
+    /// Forcefully stops every process of the tree
+    pub fn kill(&self) -> std::io::Result<()> {
+        #[cfg(windows)]
+        {
+            if let Some(job) = &self.job {
+                return job.terminate();
+            }
+        }
+
+        kill_tree(self.pid)
+    }
+}
+
+/// Forcefully stops a process and its descendants by PID, for processes
+/// spawned by an earlier run of the launcher
+pub fn kill_tree(pid: u32) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let status = std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .status()?;
+
+    // An isolated process leads a group with its own PID as the ID
+    #[cfg(not(windows))]
+    let status = std::process::Command::new("kill")
+        .args(["-9", "--", &format!("-{pid}")])
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        // Processes spawned before the tree was isolated have no group of
+        // their own
+        crate::util::platform::kill_process(pid)
+    }
+}
+
+/// Asks a process and its descendants to exit, as closing the game window
+/// would. Minecraft saves the world before exiting, which killing it skips
+pub fn terminate_tree(pid: u32) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let status = std::process::Command::new("taskkill")
+        .args(["/T", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .status()?;
+
+    #[cfg(not(windows))]
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", "--", &format!("-{pid}")])
+        .status()
+        .and_then(|status| {
+            if status.success() {
+                Ok(status)
+            } else {
+                std::process::Command::new("kill")
+                    .args(["-TERM", &pid.to_string()])
+                    .status()
+            }
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Could not ask process {pid} to exit"),
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject,
+    };
+
+    #[derive(Debug)]
+    pub struct Job(HANDLE);
+
+    // The handle is only used through thread-safe kernel calls
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        pub fn new() -> io::Result<Self> {
+            let handle =
+                unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+            if handle == 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(Job(handle))
+            }
+        }
+
+        // Processes the child starts from now on join the job with it
+        pub fn assign(&self, child: &Child) -> io::Result<()> {
+            let process = child.raw_handle().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Other, "Process already exited")
+            })?;
+            if unsafe { AssignProcessToJobObject(self.0, process as HANDLE) }
+                == 0
+            {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+
+        pub fn terminate(&self) -> io::Result<()> {
+            if unsafe { TerminateJobObject(self.0, 1) } == 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
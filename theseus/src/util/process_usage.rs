@@ -0,0 +1,59 @@
+//! Memory and CPU use of running games
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+
+lazy_static! {
+    // CPU use is measured between two refreshes, so the same system is kept
+    // across samples
+    static ref SYSTEM: Mutex<System> = Mutex::new(System::new());
+}
+
+/// Resources used by a process and everything it started
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct ProcessUsage {
+    /// Resident memory in bytes
+    pub memory: u64,
+    /// Percentage of one core, so above 100 when using several. Zero on the
+    /// first sample, which has nothing to measure against
+    pub cpu_usage: f32,
+}
+
+/// Samples the usage of processes by PID, including their descendants so
+/// games started through wrappers are counted. Processes that have exited
+/// are left out. Blocks while reading the process table
+pub fn sample(pids: &[u32]) -> HashMap<u32, ProcessUsage> {
+    let mut system = SYSTEM.lock().unwrap_or_else(|x| x.into_inner());
+    system.refresh_processes();
+    let processes = system.processes();
+
+    let mut usage = HashMap::new();
+    for (pid, process) in processes {
+        // Walks up to the first of the sampled processes, if any. The depth
+        // is bounded in case the table changed mid-refresh into a cycle
+        let mut current = Some(*pid);
+        let mut root = None;
+        for _ in 0..32 {
+            let Some(pid) = current else {
+                break;
+            };
+            if pids.contains(&pid.as_u32()) {
+                root = Some(pid.as_u32());
+                break;
+            }
+            current = processes.get(&pid).and_then(|x| x.parent());
+        }
+
+        if let Some(root) = root {
+            let entry: &mut ProcessUsage = usage.entry(root).or_default();
+            entry.memory += process.memory();
+            entry.cpu_usage += process.cpu_usage();
+        }
+    }
+
+    // Only the roots themselves tell whether a game is still running
+    usage.retain(|pid, _| processes.contains_key(&Pid::from_u32(*pid)));
+    usage
+}
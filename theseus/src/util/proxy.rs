@@ -0,0 +1,81 @@
+//! Local TCP relays for redirecting server addresses of a profile
+use crate::state::ServerRedirect;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+// Waits after a failed accept, doubled up to the maximum while failures
+// continue, so errors like running out of file descriptors don't spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Running relays, which are shut down when this is dropped
+#[derive(Debug, Default)]
+pub struct ProxyGuard(Vec<JoinHandle<()>>);
+
+impl Drop for ProxyGuard {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// Binds a local listener for each redirect and relays every connection made
+/// to it to the redirect's target
+#[tracing::instrument]
+pub async fn start_redirects(
+    redirects: &[ServerRedirect],
+) -> crate::Result<ProxyGuard> {
+    let mut guard = ProxyGuard::default();
+
+    for redirect in redirects {
+        let listener = TcpListener::bind(("127.0.0.1", redirect.local_port))
+            .await
+            .map_err(|err| {
+                crate::ErrorKind::LauncherError(format!(
+                    "Could not listen on port {} to redirect to {}: {err}",
+                    redirect.local_port, redirect.target
+                ))
+            })?;
+
+        let target = redirect.target.clone();
+        guard.0.push(tokio::task::spawn(async move {
+            let mut backoff = ACCEPT_BACKOFF;
+            loop {
+                let (inbound, _) = match listener.accept().await {
+                    Ok(x) => x,
+                    Err(err) => {
+                        tracing::warn!("Unable to accept connection: {err}");
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                        continue;
+                    }
+                };
+                backoff = ACCEPT_BACKOFF;
+
+                let target = target.clone();
+                tokio::task::spawn(async move {
+                    if let Err(err) = relay(inbound, &target).await {
+                        tracing::warn!(
+                            "Unable to relay connection to {target}: {err}"
+                        );
+                    }
+                });
+            }
+        }));
+        tracing::info!(
+            "Redirecting 127.0.0.1:{} to {}",
+            redirect.local_port,
+            redirect.target
+        );
+    }
+
+    Ok(guard)
+}
+
+async fn relay(mut inbound: TcpStream, target: &str) -> std::io::Result<()> {
+    let mut outbound = TcpStream::connect(target).await?;
+    tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! End to end check of installing and launching a vanilla profile. It
+//! downloads the game, so it only runs when asked for, such as in CI before a
+//! release:
+//!
+//! `cargo test -p theseus --features integration_test -- --ignored`
+//!
+//! The profile is launched with a test account and stopped once the game
+//! finished loading. On Linux machines without a display, the game runs on a
+//! virtual one with `xvfb-run`. `THESEUS_TEST_VERSION` picks another game
+//! version than the default
+#![cfg(feature = "integration_test")]
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use theseus::prelude::*;
+use theseus::profile::smoke_test::test_launch_credentials;
+
+// A small release running on Java 17, which is installed if missing
+const DEFAULT_VERSION: &str = "1.18.2";
+const PROFILE_NAME: &str = "Integration test";
+const LOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Points the launcher at an empty config dir, so the test leaves the
+// settings, Java and profiles of the machine alone. Has to run before the
+// state is first loaded
+fn isolate_config_dir() -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("theseus-integration-{}", uuid::Uuid::new_v4()));
+    std::env::set_var("THESEUS_CONFIG_DIR", &dir);
+    dir
+}
+
+// Finds the Java installations of the machine, installing Java 17 if there
+// is none the default version runs on
+async fn setup_java() -> theseus::Result<()> {
+    let jres = jre::get_all_jre().await?;
+    let java_8 = jre::find_filtered_jres("1.8", jres.clone(), false).await?;
+    let java_17 = jre::find_filtered_jres("1.17", jres.clone(), false).await?;
+    let java_18plus =
+        jre::find_filtered_jres("1.18", jres.clone(), true).await?;
+    let mut java_globals =
+        jre::autodetect_java_globals(java_8, java_17, java_18plus).await?;
+
+    let has_17 = [jre::JAVA_17_KEY, jre::JAVA_18PLUS_KEY]
+        .iter()
+        .any(|key| java_globals.get(&key.to_string()).is_some());
+    if !has_17 {
+        let path = jre::auto_install_java(17).await?;
+        if let Some(java) = jre::check_jre(path).await? {
+            java_globals.insert(jre::JAVA_17_KEY.to_string(), java);
+        }
+    }
+
+    let state = State::get().await?;
+    state.settings.write().await.java_globals = java_globals;
+    State::sync().await
+}
+
+#[tokio::test]
+#[ignore = "downloads and launches the game"]
+async fn vanilla_profile_launches() -> theseus::Result<()> {
+    let config_dir = isolate_config_dir();
+    let _log_guard = theseus::start_logger();
+    setup_java().await?;
+
+    let game_version = std::env::var("THESEUS_TEST_VERSION")
+        .unwrap_or_else(|_| DEFAULT_VERSION.to_string());
+    let headless =
+        cfg!(target_os = "linux") && std::env::var_os("DISPLAY").is_none();
+
+    let profile_path = create::profile_create(
+        PROFILE_NAME.to_string(),
+        game_version,
+        ModLoader::Vanilla,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await?;
+
+    let credentials = Credentials::test_account("IntegrationTest");
+    let result = test_launch_credentials(
+        &profile_path,
+        &credentials,
+        LOAD_TIMEOUT,
+        headless,
+    )
+    .await;
+    profile::remove(&profile_path).await?;
+    let _ = std::fs::remove_dir_all(&config_dir);
+
+    let result = result?;
+    assert!(
+        result.passed(),
+        "game ended as {:?} after {:.1}s: {:#?}",
+        result.outcome,
+        result.seconds,
+        result.failure
+    );
+    Ok(())
+}
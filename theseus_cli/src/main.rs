@@ -14,6 +14,10 @@ mod subcommands;
 #[derive(argh::FromArgs, Debug)]
 /// The official Modrinth CLI
 pub struct Args {
+    #[argh(switch)]
+    /// print results as JSON instead of text, for scripts and CI
+    pub json: bool,
+
     #[argh(subcommand)]
     pub subcommand: subcommands::Subcommand,
 }
@@ -1,45 +1,50 @@
-//! User management subcommand
-use crate::util::{confirm_async, table};
+//! Account management subcommand
+use crate::util::{confirm_async, print_json, table};
 use eyre::Result;
 use paris::*;
+use serde::Serialize;
 use tabled::Tabled;
 use theseus::prelude::*;
 use tokio::sync::oneshot;
 
 #[derive(argh::FromArgs, Debug)]
-#[argh(subcommand, name = "user")]
+#[argh(subcommand, name = "auth")]
 /// manage Minecraft accounts
-pub struct UserCommand {
+pub struct AuthCommand {
     #[argh(subcommand)]
-    action: UserSubcommand,
+    action: AuthSubcommand,
 }
 
 #[derive(argh::FromArgs, Debug)]
 #[argh(subcommand)]
-pub enum UserSubcommand {
-    Add(UserAdd),
-    List(UserList),
-    Remove(UserRemove),
-    SetDefault(UserDefault),
+pub enum AuthSubcommand {
+    Login(AuthLogin),
+    List(AuthList),
+    Remove(AuthRemove),
+    SetDefault(AuthDefault),
 }
 
 #[derive(argh::FromArgs, Debug)]
-/// add a new user to Theseus
-#[argh(subcommand, name = "add")]
-pub struct UserAdd {
+/// log in to a Microsoft account and add it to Theseus
+#[argh(subcommand, name = "login")]
+pub struct AuthLogin {
     #[argh(option)]
     /// the browser to authenticate using
     browser: Option<webbrowser::Browser>,
 }
 
-impl UserAdd {
+impl AuthLogin {
     pub async fn run(
         &self,
-        _args: &crate::Args,
-        _largs: &UserCommand,
+        args: &crate::Args,
+        _largs: &AuthCommand,
     ) -> Result<()> {
-        info!("Adding new user account to Theseus");
-        info!("A browser window will now open, follow the login flow there.");
+        if !args.json {
+            info!("Adding new user account to Theseus");
+            info!(
+                "A browser window will now open, follow the login flow there."
+            );
+        }
 
         let (tx, rx) = oneshot::channel::<url::Url>();
         let flow = tokio::spawn(auth::authenticate(tx));
@@ -52,7 +57,11 @@ impl UserAdd {
 
         let credentials = flow.await??;
         State::sync().await?;
-        success!("Logged in user {}.", credentials.0.username);
+        if args.json {
+            print_json(&UserRow::from(&credentials.0, None))?;
+        } else {
+            success!("Logged in user {}.", credentials.0.username);
+        }
         Ok(())
     }
 }
@@ -60,9 +69,9 @@ impl UserAdd {
 #[derive(argh::FromArgs, Debug)]
 /// list all known users
 #[argh(subcommand, name = "list")]
-pub struct UserList {}
+pub struct AuthList {}
 
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
 struct UserRow<'a> {
     username: &'a str,
     id: uuid::Uuid,
@@ -82,17 +91,20 @@ impl<'a> UserRow<'a> {
     }
 }
 
-impl UserList {
+impl AuthList {
     pub async fn run(
         &self,
-        _args: &crate::Args,
-        _largs: &UserCommand,
+        args: &crate::Args,
+        _largs: &AuthCommand,
     ) -> Result<()> {
         let state = State::get().await?;
         let default = state.settings.read().await.default_user;
 
         let users = auth::users().await?;
         let rows = users.iter().map(|user| UserRow::from(user, default));
+        if args.json {
+            return print_json(&rows.collect::<Vec<_>>());
+        }
 
         let table = table(rows);
         println!("{table}");
@@ -104,17 +116,17 @@ impl UserList {
 #[derive(argh::FromArgs, Debug)]
 /// remove a user
 #[argh(subcommand, name = "remove")]
-pub struct UserRemove {
+pub struct AuthRemove {
     /// the user to remove
     #[argh(positional)]
     user: uuid::Uuid,
 }
 
-impl UserRemove {
+impl AuthRemove {
     pub async fn run(
         &self,
         _args: &crate::Args,
-        _largs: &UserCommand,
+        _largs: &AuthCommand,
     ) -> Result<()> {
         info!("Removing user {}", self.user.as_hyphenated());
 
@@ -137,17 +149,17 @@ impl UserRemove {
 #[derive(argh::FromArgs, Debug)]
 /// set the default user
 #[argh(subcommand, name = "set-default")]
-pub struct UserDefault {
+pub struct AuthDefault {
     /// the user to set as default
     #[argh(positional)]
     user: uuid::Uuid,
 }
 
-impl UserDefault {
+impl AuthDefault {
     pub async fn run(
         &self,
         _args: &crate::Args,
-        _largs: &UserCommand,
+        _largs: &AuthCommand,
     ) -> Result<()> {
         info!("Setting user {} as default", self.user.as_hyphenated());
 
@@ -165,13 +177,13 @@ impl UserDefault {
     }
 }
 
-impl UserCommand {
+impl AuthCommand {
     pub async fn run(&self, args: &crate::Args) -> Result<()> {
         dispatch!(&self.action, (args, self) => {
-            UserSubcommand::Add,
-            UserSubcommand::List,
-            UserSubcommand::Remove,
-            UserSubcommand::SetDefault
+            AuthSubcommand::Login,
+            AuthSubcommand::List,
+            AuthSubcommand::Remove,
+            AuthSubcommand::SetDefault
         })
     }
 }
@@ -1,20 +1,26 @@
 use eyre::Result;
 
+mod auth;
+mod pack;
 mod profile;
-mod user;
+mod snapshot;
 
 #[derive(argh::FromArgs, Debug)]
 #[argh(subcommand)]
 pub enum Subcommand {
+    Auth(auth::AuthCommand),
+    Pack(pack::PackCommand),
     Profile(profile::ProfileCommand),
-    User(user::UserCommand),
+    Snapshot(snapshot::SnapshotCommand),
 }
 
 impl crate::Args {
     pub async fn dispatch(&self) -> Result<()> {
         dispatch!(self.subcommand, (self) => {
+            Subcommand::Auth,
+            Subcommand::Pack,
             Subcommand::Profile,
-            Subcommand::User
+            Subcommand::Snapshot
         })
     }
 }
@@ -0,0 +1,71 @@
+//! Modpack subcommand
+use crate::util::print_json;
+use eyre::Result;
+use paris::*;
+use std::path::Path;
+use theseus::pack::install_from::{self, CreatePackLocation};
+use theseus::pack::install_mrpack::install_zipped_mrpack;
+use theseus::profile::create::profile_create_from_creator;
+
+#[derive(argh::FromArgs, Debug)]
+#[argh(subcommand, name = "pack")]
+/// manage modpacks
+pub struct PackCommand {
+    #[argh(subcommand)]
+    action: PackSubcommand,
+}
+
+#[derive(argh::FromArgs, Debug)]
+#[argh(subcommand)]
+pub enum PackSubcommand {
+    Install(PackInstall),
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// install a modpack as a new profile
+#[argh(subcommand, name = "install")]
+pub struct PackInstall {
+    #[argh(positional)]
+    /// an .mrpack file, or a Modrinth version ID, project ID or project slug
+    pack: String,
+}
+
+impl PackInstall {
+    pub async fn run(
+        &self,
+        args: &crate::Args,
+        _largs: &PackCommand,
+    ) -> Result<()> {
+        let path = Path::new(&self.pack);
+        let location = if path.is_file() {
+            CreatePackLocation::FromFile {
+                path: dunce::canonicalize(path)?,
+            }
+        } else {
+            install_from::location_from_modrinth_id(&self.pack).await?
+        };
+
+        let creator = install_from::get_profile_from_pack(location.clone());
+        if !args.json {
+            info!("Installing pack {}...", creator.name);
+        }
+
+        let profile = profile_create_from_creator(creator).await?;
+        let profile = install_zipped_mrpack(location, profile).await?;
+
+        if args.json {
+            print_json(&profile)?;
+        } else {
+            success!("Installed pack to profile {}", profile);
+        }
+        Ok(())
+    }
+}
+
+impl PackCommand {
+    pub async fn run(&self, args: &crate::Args) -> Result<()> {
+        dispatch!(&self.action, (args, self) => {
+            PackSubcommand::Install
+        })
+    }
+}
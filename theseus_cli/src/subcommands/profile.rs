@@ -1,18 +1,15 @@
 //! Profile management subcommand
 use crate::util::{
-    confirm_async, prompt_async, select_async, table, table_path_display,
+    confirm_async, print_json, prompt_async, select_async, table,
+    table_path_display,
 };
-use daedalus::modded::LoaderVersion;
-use dunce::canonicalize;
-use eyre::{ensure, Result};
+use eyre::Result;
 use futures::prelude::*;
 use paris::*;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use tabled::Tabled;
 use theseus::prelude::*;
 use theseus::profile::create::profile_create;
-use tokio::fs;
-use tokio_stream::wrappers::ReadDirStream;
 
 #[derive(argh::FromArgs, Debug)]
 #[argh(subcommand, name = "profile")]
@@ -25,21 +22,19 @@ pub struct ProfileCommand {
 #[derive(argh::FromArgs, Debug)]
 #[argh(subcommand)]
 pub enum ProfileSubcommand {
-    Init(ProfileInit),
+    Create(ProfileCreate),
+    Export(ProfileExport),
+    Install(ProfileInstall),
     List(ProfileList),
     Remove(ProfileRemove),
     Run(ProfileRun),
 }
 
 #[derive(argh::FromArgs, Debug)]
-#[argh(subcommand, name = "init")]
+#[argh(subcommand, name = "create")]
 /// create a new profile and manage it with Theseus
-pub struct ProfileInit {
-    #[argh(positional, default = "std::env::current_dir().unwrap()")]
-    /// the path of the newly created profile
-    path: PathBuf,
-
-    #[argh(option)]
+pub struct ProfileCreate {
+    #[argh(positional)]
     /// the name of the profile
     name: Option<String>,
 
@@ -56,72 +51,56 @@ pub struct ProfileInit {
     loader_version: Option<String>,
 }
 
-impl ProfileInit {
+impl ProfileCreate {
     pub async fn run(
         &self,
-        _args: &crate::Args,
+        args: &crate::Args,
         _largs: &ProfileCommand,
     ) -> Result<()> {
-        // TODO: validate inputs from args early
-        let state = State::get().await?;
-        let metadata = state.metadata.read().await;
-
-        if self.path.exists() {
-            ensure!(
-                self.path.is_dir(),
-                "Attempted to create profile in something other than a folder!"
-            );
-            ensure!(
-                !self.path.join("profile.json").exists(),
-                "Profile already exists! Perhaps you want `profile add` instead?"
-            );
-            if ReadDirStream::new(fs::read_dir(&self.path).await?)
-                .next()
-                .await
-                .is_some()
-            {
-                warn!("You are trying to create a profile in a non-empty directory. If this is an instance from another launcher, please be sure to properly fill the profile.json fields!");
-                if !confirm_async(
-                    String::from("Do you wish to continue"),
-                    false,
-                )
-                .await?
-                {
-                    eyre::bail!("Aborted!");
-                }
+        // Nothing answers prompts when the output is read as JSON
+        if args.json {
+            let missing = [
+                ("name", self.name.is_none()),
+                ("--game-version", self.game_version.is_none()),
+                ("--modloader", self.modloader.is_none()),
+                (
+                    "--loader-version",
+                    self.loader_version.is_none()
+                        && self.modloader != Some(ModLoader::Vanilla),
+                ),
+            ]
+            .iter()
+            .filter(|(_, missing)| *missing)
+            .map(|(arg, _)| *arg)
+            .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                eyre::bail!(
+                    "Missing {}, which can't be prompted for with --json",
+                    missing.join(", ")
+                );
             }
-        } else {
-            fs::create_dir_all(&self.path).await?;
         }
-        info!(
-            "Creating profile at path {}",
-            &canonicalize(&self.path)?.display()
-        );
 
-        // TODO: abstract default prompting
         let name = match &self.name {
             Some(name) => name.clone(),
-            None => {
-                let default = self.path.file_name().unwrap().to_string_lossy();
-
-                prompt_async(
-                    String::from("Instance name"),
-                    Some(default.into_owned()),
-                )
-                .await?
-            }
+            None => prompt_async(String::from("Instance name"), None).await?,
         };
 
         let game_version = match &self.game_version {
             Some(version) => version.clone(),
             None => {
-                let default = &metadata.minecraft.latest.release;
-
-                prompt_async(
-                    String::from("Game version"),
-                    Some(default.clone()),
-                )
-                .await?
+                let state = State::get().await?;
+                let default = state
+                    .metadata
+                    .read()
+                    .await
+                    .minecraft
+                    .latest
+                    .release
+                    .clone();
+
+                prompt_async(String::from("Game version"), Some(default))
+                    .await?
             }
         };
 
@@ -130,7 +109,7 @@ impl ProfileInit {
             None => {
                 let choice = select_async(
                     "Modloader".to_owned(),
-                    &["vanilla", "fabric", "forge"],
+                    &["vanilla", "fabric", "forge", "quilt", "neoforge"],
                 )
                 .await?;
 
@@ -138,6 +117,8 @@ impl ProfileInit {
                     0 => ModLoader::Vanilla,
                     1 => ModLoader::Fabric,
                     2 => ModLoader::Forge,
+                    3 => ModLoader::Quilt,
+                    4 => ModLoader::NeoForge,
                     _ => eyre::bail!(
                         "Invalid modloader ID: {choice}. This is a bug in the launcher!"
                     ),
@@ -145,8 +126,8 @@ impl ProfileInit {
             }
         };
 
-        let loader = if loader != ModLoader::Vanilla {
-            let version = match &self.loader_version {
+        let loader_version = if loader != ModLoader::Vanilla {
+            Some(match &self.loader_version {
                 Some(version) => String::from(version),
                 None => prompt_async(
                     String::from(
@@ -155,43 +136,16 @@ impl ProfileInit {
                     Some(String::from("latest")),
                 )
                 .await?,
-            };
-
-            let filter = |it: &LoaderVersion| match version.as_str() {
-                "latest" => true,
-                "stable" => it.stable,
-                id => it.id == *id,
-            };
-
-            let loader_data = match loader {
-                ModLoader::Forge => &metadata.forge,
-                ModLoader::Fabric => &metadata.fabric,
-                _ => eyre::bail!("Could not get manifest for loader {loader}. This is a bug in the CLI!"),
-            };
-
-            let loaders = &loader_data.game_versions
-                .iter()
-                .find(|it| it.id == game_version)
-                .ok_or_else(|| eyre::eyre!("Modloader {loader} unsupported for Minecraft version {game_version}"))?
-                .loaders;
-
-            let loader_version =
-                loaders.iter().cloned().find(filter).ok_or_else(|| {
-                    eyre::eyre!(
-                        "Invalid version {version} for modloader {loader}"
-                    )
-                })?;
-
-            Some((loader_version, loader))
+            })
         } else {
             None
         };
 
-        profile_create(
+        let path = profile_create(
             name,
             game_version,
-            loader.clone().map(|x| x.1).unwrap_or(ModLoader::Vanilla),
-            loader.map(|x| x.0.id),
+            loader,
+            loader_version,
             None,
             None,
             None,
@@ -199,9 +153,13 @@ impl ProfileInit {
         )
         .await?;
 
-        success!(
-            "Successfully created instance, it is now available to use with Theseus!"
-        );
+        if args.json {
+            print_json(&path)?;
+        } else {
+            success!(
+                "Successfully created instance {path}, it is now available to use with Theseus!"
+            );
+        }
         Ok(())
     }
 }
@@ -254,10 +212,14 @@ impl<'a> From<&'a Path> for ProfileRow<'a> {
 impl ProfileList {
     pub async fn run(
         &self,
-        _args: &crate::Args,
+        args: &crate::Args,
         _largs: &ProfileCommand,
     ) -> Result<()> {
         let profiles = profile::list(None).await?;
+        if args.json {
+            return print_json(&profiles.values().collect::<Vec<_>>());
+        }
+
         let rows = profiles.values().map(ProfileRow::from);
 
         let table = table(rows).with(
@@ -274,9 +236,12 @@ impl ProfileList {
 /// unmanage a profile
 #[argh(subcommand, name = "remove")]
 pub struct ProfileRemove {
-    #[argh(positional, default = "std::env::current_dir().unwrap()")]
-    /// the profile to get rid of
-    profile: PathBuf,
+    #[argh(
+        positional,
+        default = "ProfileSelector::Path(std::env::current_dir().unwrap())"
+    )]
+    /// the profile to get rid of, by uuid, name or path
+    profile: ProfileSelector,
 }
 
 impl ProfileRemove {
@@ -285,9 +250,8 @@ impl ProfileRemove {
         _args: &crate::Args,
         _largs: &ProfileCommand,
     ) -> Result<()> {
-        let profile =
-            ProfilePathId::from_fs_path(canonicalize(&self.profile)?).await?;
-        info!("Removing profile {} from Theseus", self.profile.display());
+        let profile = profile::resolve(&self.profile).await?;
+        info!("Removing profile {} from Theseus", profile);
 
         if confirm_async(String::from("Do you wish to continue"), true).await? {
             profile::remove(&profile).await?;
@@ -301,13 +265,109 @@ impl ProfileRemove {
     }
 }
 
+#[derive(argh::FromArgs, Debug)]
+/// export a profile as a .mrpack
+#[argh(subcommand, name = "export")]
+pub struct ProfileExport {
+    #[argh(positional)]
+    /// the file to write the pack to, or - for stdout
+    output: String,
+
+    #[argh(
+        option,
+        default = "ProfileSelector::Path(std::env::current_dir().unwrap())"
+    )]
+    /// the profile to export, by uuid, name or path
+    profile: ProfileSelector,
+
+    #[argh(option)]
+    /// the version number of the pack
+    version: Option<String>,
+}
+
+impl ProfileExport {
+    pub async fn run(
+        &self,
+        _args: &crate::Args,
+        _largs: &ProfileCommand,
+    ) -> Result<()> {
+        let profile = profile::resolve(&self.profile).await?;
+        // Everything that isn't downloaded from Modrinth goes in the pack
+        let overrides =
+            profile::get_potential_override_folders(profile.clone())
+                .await?
+                .into_iter()
+                .map(|x| x.to_string_lossy().to_string())
+                .collect::<Vec<_>>();
+
+        // Nothing else may be printed while the pack goes to stdout
+        if self.output == "-" {
+            profile::export_mrpack_to_writer(
+                &profile,
+                &mut tokio::io::stdout(),
+                overrides,
+                self.version.clone(),
+            )
+            .await?;
+        } else {
+            info!("Exporting profile {} to {}", profile, self.output);
+            profile::export_mrpack(
+                &profile,
+                self.output.clone().into(),
+                overrides,
+                self.version.clone(),
+            )
+            .await?;
+            success!("Profile exported!");
+        }
+        Ok(())
+    }
+}
+
+#[derive(argh::FromArgs, Debug)]
+/// install or repair a profile's game files
+#[argh(subcommand, name = "install")]
+pub struct ProfileInstall {
+    #[argh(
+        positional,
+        default = "ProfileSelector::Path(std::env::current_dir().unwrap())"
+    )]
+    /// the profile to install, by uuid, name or path
+    profile: ProfileSelector,
+}
+
+impl ProfileInstall {
+    pub async fn run(
+        &self,
+        args: &crate::Args,
+        _largs: &ProfileCommand,
+    ) -> Result<()> {
+        let profile = profile::resolve(&self.profile).await?;
+        if !args.json {
+            info!("Installing profile {}...", profile);
+        }
+
+        profile::install(&profile).await?;
+
+        if args.json {
+            print_json(&profile)?;
+        } else {
+            success!("Profile installed!");
+        }
+        Ok(())
+    }
+}
+
 #[derive(argh::FromArgs, Debug)]
 /// run a profile
 #[argh(subcommand, name = "run")]
 pub struct ProfileRun {
-    #[argh(positional, default = "std::env::current_dir().unwrap()")]
-    /// the profile to run
-    profile: PathBuf,
+    #[argh(
+        positional,
+        default = "ProfileSelector::Path(std::env::current_dir().unwrap())"
+    )]
+    /// the profile to run, by uuid, name or path
+    profile: ProfileSelector,
 
     #[argh(option)]
     /// the user to authenticate with
@@ -317,11 +377,13 @@ pub struct ProfileRun {
 impl ProfileRun {
     pub async fn run(
         &self,
-        _args: &crate::Args,
+        args: &crate::Args,
         _largs: &ProfileCommand,
     ) -> Result<()> {
-        info!("Starting profile at path {}...", self.profile.display());
-        let path = canonicalize(&self.profile)?;
+        let profile_path_id = profile::resolve(&self.profile).await?;
+        if !args.json {
+            info!("Starting profile {}...", profile_path_id);
+        }
 
         let id = future::ready(self.user.ok_or(()))
             .or_else(|_| async move {
@@ -330,19 +392,31 @@ impl ProfileRun {
 
                 settings.default_user
                     .ok_or(eyre::eyre!(
-                        "Could not find any users, please add one using the `user add` command."
+                        "Could not find any users, please add one using the `auth login` command."
                     ))
             })
             .await?;
-        let credentials = auth::refresh(id).await?;
+        let credentials = auth::refresh_for_launch(id).await?;
 
-        let profile_path_id = ProfilePathId::from_fs_path(path).await?;
         let proc_lock =
             profile::run_credentials(&profile_path_id, &credentials).await?;
+        if !settings::get_post_launch_policy().await?.wait_for_exit {
+            if args.json {
+                print_json(&proc_lock.read().await.uuid)?;
+            } else {
+                success!("Launched Minecraft");
+            }
+            return Ok(());
+        }
+
         let mut proc = proc_lock.write().await;
         process::wait_for(&mut proc).await?;
 
-        success!("Process exited successfully!");
+        if args.json {
+            print_json(&proc.uuid)?;
+        } else {
+            success!("Process exited successfully!");
+        }
         Ok(())
     }
 }
@@ -350,7 +424,9 @@ impl ProfileRun {
 impl ProfileCommand {
     pub async fn run(&self, args: &crate::Args) -> Result<()> {
         dispatch!(&self.action, (args, self) => {
-            ProfileSubcommand::Init,
+            ProfileSubcommand::Create,
+            ProfileSubcommand::Export,
+            ProfileSubcommand::Install,
             ProfileSubcommand::List,
             ProfileSubcommand::Remove,
             ProfileSubcommand::Run
@@ -363,6 +439,8 @@ fn modloader_from_str(it: &str) -> core::result::Result<ModLoader, String> {
         "vanilla" => Ok(ModLoader::Vanilla),
         "forge" => Ok(ModLoader::Forge),
         "fabric" => Ok(ModLoader::Fabric),
-        _ => Err(String::from("Invalid modloader: {it}")),
+        "quilt" => Ok(ModLoader::Quilt),
+        "neoforge" => Ok(ModLoader::NeoForge),
+        _ => Err(format!("Invalid modloader: {it}")),
     }
 }
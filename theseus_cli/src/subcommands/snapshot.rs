@@ -0,0 +1,36 @@
+//! State snapshot subcommand
+use crate::util::print_json;
+use eyre::Result;
+use paris::*;
+use std::path::PathBuf;
+
+#[derive(argh::FromArgs, Debug)]
+#[argh(subcommand, name = "snapshot")]
+/// print a consistent copy of the launcher's profiles and settings as JSON,
+/// with secrets redacted
+pub struct SnapshotCommand {
+    #[argh(option, short = 'o')]
+    /// file to write the snapshot to instead of printing it
+    output: Option<PathBuf>,
+}
+
+impl SnapshotCommand {
+    pub async fn run(&self, args: &crate::Args) -> Result<()> {
+        let snapshot = theseus::snapshot::get().await?;
+        match &self.output {
+            Some(output) => {
+                tokio::fs::write(output, serde_json::to_vec_pretty(&snapshot)?)
+                    .await?;
+                if !args.json {
+                    success!(
+                        "Wrote a snapshot of {} profiles to {}",
+                        snapshot.profiles.len(),
+                        output.display()
+                    );
+                }
+            }
+            None => print_json(&snapshot)?,
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use dialoguer::{Confirm, Input, Select};
 use eyre::Result;
+use serde::Serialize;
 use std::{borrow::Cow, path::Path};
 use tabled::{Table, Tabled};
 
@@ -72,6 +73,12 @@ pub fn table_path_display(path: &Path) -> String {
     res
 }
 
+// Output helpers
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
 // Dispatch macros
 macro_rules! dispatch {
     ($on:expr, $args:tt => {$($option:path),+}) => {
@@ -7,8 +7,11 @@ pub fn init<R: tauri::Runtime>() -> TauriPlugin<R> {
         .invoke_handler(tauri::generate_handler![
             auth_authenticate_begin_flow,
             auth_authenticate_await_completion,
+            auth_reconsent_begin_flow,
             auth_cancel_flow,
+            auth_authenticate_yggdrasil,
             auth_refresh,
+            auth_validate_accounts,
             auth_remove_user,
             auth_has_user,
             auth_users,
@@ -33,11 +36,30 @@ pub async fn auth_authenticate_await_completion(
     Ok(auth::authenticate_await_complete_flow().await?)
 }
 
+/// Sign in again with an account that has to grant permissions again (needs_consent)
+/// Returns a URL to visit, finished by auth_authenticate_await_completion, which updates the account
+// invoke('plugin:auth|auth_reconsent_begin_flow',user)
+#[tauri::command]
+pub async fn auth_reconsent_begin_flow(user: uuid::Uuid) -> Result<url::Url> {
+    Ok(auth::reconsent_begin_flow(user).await?)
+}
+
 #[tauri::command]
 pub async fn auth_cancel_flow() -> Result<()> {
     Ok(auth::cancel_flow().await?)
 }
 
+/// Log in to a third-party Yggdrasil-compatible authentication server
+// invoke('plugin:auth|auth_authenticate_yggdrasil',{serverUrl,username,password})
+#[tauri::command]
+pub async fn auth_authenticate_yggdrasil(
+    server_url: String,
+    username: String,
+    password: String,
+) -> Result<Credentials> {
+    Ok(auth::authenticate_yggdrasil(&server_url, &username, &password).await?)
+}
+
 /// Refresh some credentials using Hydra, if needed
 // invoke('plugin:auth|auth_refresh',user)
 #[tauri::command]
@@ -45,6 +67,14 @@ pub async fn auth_refresh(user: uuid::Uuid) -> Result<Credentials> {
     Ok(auth::refresh(user).await?)
 }
 
+/// Refresh the tokens of every account about to expire
+/// Returns the accounts that need to log in again
+// invoke('plugin:auth|auth_validate_accounts')
+#[tauri::command]
+pub async fn auth_validate_accounts() -> Result<Vec<uuid::Uuid>> {
+    Ok(auth::validate_accounts().await?)
+}
+
 #[tauri::command]
 pub async fn auth_remove_user(user: uuid::Uuid) -> Result<()> {
     Ok(auth::remove_user(user).await?)
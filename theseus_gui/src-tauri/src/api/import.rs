@@ -11,6 +11,7 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
         .invoke_handler(tauri::generate_handler![
             import_get_importable_instances,
             import_import_instance,
+            import_import_server_pack,
             import_is_valid_importable_instance,
             import_get_default_launcher_path,
         ])
@@ -48,6 +49,17 @@ pub async fn import_import_instance(
     Ok(())
 }
 
+/// Import a CurseForge server pack from a zip file or extracted folder
+/// profile_path should be a blank profile for this purpose- if the function fails, it will be deleted
+#[tauri::command]
+pub async fn import_import_server_pack(
+    profile_path: ProfilePathId,
+    server_pack: PathBuf,
+) -> Result<()> {
+    import::import_server_pack(profile_path, server_pack).await?;
+    Ok(())
+}
+
 /// Checks if this instance is valid for importing, given a certain launcher type
 /// eg: is_valid_importable_instance(PathBuf::from("C:/MultiMC/Instance 1"), ImportLauncherType::MultiMC)
 #[tauri::command]
@@ -0,0 +1,22 @@
+use crate::api::Result;
+use theseus::{jobs, prelude::JobInfo};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("jobs")
+        .invoke_handler(tauri::generate_handler![jobs_get_jobs, jobs_run_job])
+        .build()
+}
+
+// Lists background jobs, with their next run in local time
+// invoke('plugin:jobs|jobs_get_jobs')
+#[tauri::command]
+pub async fn jobs_get_jobs() -> Result<Vec<JobInfo>> {
+    Ok(jobs::get_jobs().await?)
+}
+
+// Runs a background job now
+// invoke('plugin:jobs|jobs_run_job')
+#[tauri::command]
+pub async fn jobs_run_job(name: String) -> Result<()> {
+    Ok(jobs::run_job(&name).await?)
+}
@@ -15,6 +15,7 @@ pub fn init<R: tauri::Runtime>() -> TauriPlugin<R> {
             jre_get_jre,
             jre_auto_install_java,
             jre_get_max_memory,
+            jre_recommend_memory,
         ])
         .build()
 }
@@ -72,3 +73,11 @@ pub async fn jre_auto_install_java(java_version: u32) -> Result<PathBuf> {
 pub async fn jre_get_max_memory() -> Result<u64> {
     Ok(jre::get_max_memory().await?)
 }
+
+/// Get the recommended memory settings for a profile
+#[tauri::command]
+pub async fn jre_recommend_memory(
+    path: ProfilePathId,
+) -> Result<MemorySettings> {
+    Ok(jre::recommend_memory(&path).await?)
+}
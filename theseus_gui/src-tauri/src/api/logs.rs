@@ -1,6 +1,8 @@
 use crate::api::Result;
+use chrono::NaiveDateTime;
+use std::path::PathBuf;
 use theseus::{
-    logs::{self, Logs},
+    logs::{self, CrashReport, GameLogFile, LogLine, LogSession, Logs},
     prelude::ProfilePathId,
 };
 
@@ -9,6 +11,7 @@ A log is a struct containing the datetime string, stdout, and stderr, as follows
 
 pub struct Logs {
     pub datetime_string:  String,
+    pub session_uuid: Option<Uuid>,
     pub stdout: String,
     pub stderr: String,
 }
@@ -22,6 +25,14 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             logs_get_output_by_datetime,
             logs_delete_logs,
             logs_delete_logs_by_datetime,
+            logs_get_sessions,
+            logs_get_logs_by_session,
+            logs_delete_logs_by_session,
+            logs_get_crash_reports,
+            logs_get_game_log_files,
+            logs_get_game_log,
+            logs_get_merged_logs,
+            logs_upload,
         ])
         .build()
 }
@@ -80,3 +91,73 @@ pub async fn logs_delete_logs_by_datetime(
 ) -> Result<()> {
     Ok(logs::delete_logs_by_datetime(profile_path, &datetime_string).await?)
 }
+
+/// Get the launches of a profile that have logs, sorted by datetime
+#[tauri::command]
+pub async fn logs_get_sessions(
+    profile_path: ProfilePathId,
+) -> Result<Vec<LogSession>> {
+    Ok(logs::get_sessions(profile_path).await?)
+}
+
+/// Get a Log struct for a profile by profile id and session uuid
+#[tauri::command]
+pub async fn logs_get_logs_by_session(
+    profile_path: ProfilePathId,
+    session_uuid: uuid::Uuid,
+) -> Result<Logs> {
+    Ok(logs::get_logs_by_session(profile_path, session_uuid).await?)
+}
+
+/// Delete a log for a profile by profile id and session uuid
+#[tauri::command]
+pub async fn logs_delete_logs_by_session(
+    profile_path: ProfilePathId,
+    session_uuid: uuid::Uuid,
+) -> Result<()> {
+    Ok(logs::delete_logs_by_session(profile_path, session_uuid).await?)
+}
+
+/// Get the crash reports of a profile, censored and newest first
+#[tauri::command]
+pub async fn logs_get_crash_reports(
+    profile_path: ProfilePathId,
+) -> Result<Vec<CrashReport>> {
+    Ok(logs::get_crash_reports(profile_path).await?)
+}
+
+/// Get the logs the game wrote itself in the profile's logs folder, newest first
+#[tauri::command]
+pub async fn logs_get_game_log_files(
+    profile_path: ProfilePathId,
+) -> Result<Vec<GameLogFile>> {
+    Ok(logs::get_game_log_files(profile_path).await?)
+}
+
+/// Get a log the game wrote by its file name, decompressed and censored
+#[tauri::command]
+pub async fn logs_get_game_log(
+    profile_path: ProfilePathId,
+    file_name: String,
+) -> Result<String> {
+    Ok(logs::get_game_log(profile_path, &file_name).await?)
+}
+
+/// Get the launcher's and the game's logs of a profile merged by time
+#[tauri::command]
+pub async fn logs_get_merged_logs(
+    profile_path: ProfilePathId,
+    since: Option<NaiveDateTime>,
+) -> Result<Vec<LogLine>> {
+    Ok(logs::get_merged_logs(profile_path, since).await?)
+}
+
+/// Share a log or crash report of a profile, by its path in the profile
+/// folder, returning the URL it can be viewed at
+#[tauri::command]
+pub async fn logs_upload(
+    profile_path: ProfilePathId,
+    path: PathBuf,
+) -> Result<String> {
+    Ok(logs::upload(profile_path, &path).await?)
+}
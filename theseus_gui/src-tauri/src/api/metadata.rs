@@ -9,6 +9,8 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             metadata_get_fabric_versions,
             metadata_get_forge_versions,
             metadata_get_quilt_versions,
+            metadata_get_neoforge_versions,
+            metadata_prefetch_version,
         ])
         .build()
 }
@@ -36,3 +38,21 @@ pub async fn metadata_get_forge_versions() -> Result<Manifest> {
 pub async fn metadata_get_quilt_versions() -> Result<Manifest> {
     Ok(theseus::metadata::get_quilt_versions().await?)
 }
+
+/// Gets the neoforge versions from daedalus
+#[tauri::command]
+pub async fn metadata_get_neoforge_versions() -> Result<Manifest> {
+    Ok(theseus::metadata::get_neoforge_versions().await?)
+}
+
+/// Downloads a game version in the background, before any profile uses it
+#[tauri::command]
+pub async fn metadata_prefetch_version(version_id: String) -> Result<()> {
+    tokio::spawn(async move {
+        if let Err(err) = theseus::metadata::prefetch_version(&version_id).await
+        {
+            tracing::warn!("Unable to prefetch {version_id}: {err}");
+        }
+    });
+    Ok(())
+}
@@ -4,6 +4,7 @@ use thiserror::Error;
 
 pub mod auth;
 pub mod import;
+pub mod jobs;
 pub mod jre;
 pub mod logs;
 pub mod metadata;
@@ -12,9 +13,15 @@ pub mod pack;
 pub mod process;
 pub mod profile;
 pub mod profile_create;
+pub mod screenshots;
+pub mod servers;
 pub mod settings;
+pub mod sources;
+pub mod status;
 pub mod tags;
+pub mod user;
 pub mod utils;
+pub mod worlds;
 
 pub type Result<T> = std::result::Result<T, TheseusSerializableError>;
 
@@ -65,9 +72,11 @@ macro_rules! impl_serialize {
                     TheseusSerializableError::Theseus(theseus_error) => {
                         $crate::error::display_tracing_error(theseus_error);
 
-                        let mut state = serializer.serialize_struct("Theseus", 2)?;
+                        // The details let the front end tell errors apart without parsing messages
+                        let mut state = serializer.serialize_struct("Theseus", 3)?;
                         state.serialize_field("field_name", "Theseus")?;
                         state.serialize_field("message", &theseus_error.to_string())?;
+                        state.serialize_field("details", theseus_error.details())?;
                         state.end()
                     }
                     $(
@@ -2,6 +2,7 @@ use crate::api::Result;
 
 use theseus::{
     pack::{
+        drift::DriftReport,
         install_from::{CreatePackLocation, CreatePackProfile},
         install_mrpack::install_zipped_mrpack,
     },
@@ -13,6 +14,7 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
         .invoke_handler(tauri::generate_handler![
             pack_install,
             pack_get_profile_from_pack,
+            pack_drift_report,
         ])
         .build()
 }
@@ -31,3 +33,10 @@ pub fn pack_get_profile_from_pack(
 ) -> Result<CreatePackProfile> {
     Ok(pack::install_from::get_profile_from_pack(location))
 }
+
+// Lists what was changed by hand in a managed pack since it was installed
+// invoke('plugin:pack|pack_drift_report')
+#[tauri::command]
+pub async fn pack_drift_report(profile: ProfilePathId) -> Result<DriftReport> {
+    Ok(pack::drift::drift_report(&profile).await?)
+}
@@ -1,5 +1,6 @@
 use crate::api::Result;
 use theseus::prelude::*;
+use theseus::process::ProcessInfo;
 use uuid::Uuid;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
@@ -15,6 +16,9 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             process_get_output_by_uuid,
             process_kill_by_uuid,
             process_wait_for_by_uuid,
+            process_get_all_info,
+            process_terminate_by_uuid,
+            process_restart_by_uuid,
         ])
         .build()
 }
@@ -83,3 +87,25 @@ pub async fn process_kill_by_uuid(uuid: Uuid) -> Result<()> {
 pub async fn process_wait_for_by_uuid(uuid: Uuid) -> Result<()> {
     Ok(process::wait_for_by_uuid(&uuid).await?)
 }
+
+// Gets every launched process with its profile, uptime and resource use
+#[tauri::command]
+pub async fn process_get_all_info() -> Result<Vec<ProcessInfo>> {
+    Ok(process::get_all_info().await?)
+}
+
+// Asks a process to exit by process UUID, letting the game save
+#[tauri::command]
+pub async fn process_terminate_by_uuid(uuid: Uuid) -> Result<()> {
+    Ok(process::terminate_by_uuid(&uuid).await?)
+}
+
+// Launches the profile of a process again with the same account
+// Returns the UUID of the new process
+#[tauri::command]
+pub async fn process_restart_by_uuid(uuid: Uuid) -> Result<Uuid> {
+    let minecraft_child = process::restart_by_uuid(&uuid).await?;
+    let uuid = minecraft_child.read().await.uuid;
+
+    Ok(uuid)
+}
@@ -1,9 +1,20 @@
 use crate::api::Result;
 use daedalus::modded::LoaderVersion;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use theseus::gpu;
 use theseus::prelude::*;
+use theseus::profile::changelog::UpdateChangelog;
+use theseus::profile::cloud_sync::{SyncReport, SyncSide};
+use theseus::profile::compatibility::CompatibilityReport;
+use theseus::profile::config::{ConfigDocument, ConfigFile};
+use theseus::profile::dependencies::DependencyReport;
+use theseus::profile::library::{LibraryFilter, ProfileGroup};
+use theseus::profile::lockfile::{LockChange, Lockfile};
+use theseus::profile::portable::ExportOptions;
+use theseus::profile::smoke_test::SmokeTestResult;
 use uuid::Uuid;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
@@ -12,26 +23,85 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             profile_remove,
             profile_get,
             profile_get_optimal_jre_key,
+            profile_get_java_args,
             profile_get_full_path,
             profile_list,
             profile_check_installed,
             profile_install,
+            profile_repair,
             profile_update_all,
             profile_update_project,
             profile_add_project_from_version,
+            profile_add_project_with_dependencies,
+            profile_check_compatibility,
+            profile_get_statistics,
+            profile_export_inventory,
+            profile_get_disk_usage,
+            profile_get_development_loader_versions,
+            profile_set_development,
+            profile_set_launch_overrides,
+            profile_set_cloud_sync,
+            profile_cloud_sync,
+            profile_resolve_sync_conflict,
+            profile_get_lockfile,
+            profile_diff_lockfile,
+            profile_preview_updates,
+            profile_install_from_lockfile,
+            profile_change_loader,
+            profile_get_lwjgl_versions,
+            profile_set_lwjgl_version,
+            profile_set_lwjgl_arch_version,
+            profile_get_update_changelogs,
+            profile_get_groups,
+            profile_create_group,
+            profile_rename_group,
+            profile_delete_group,
+            profile_set_group_order,
+            profile_set_groups,
+            profile_set_tags,
+            profile_get_tags,
+            profile_set_order,
+            profile_list_library,
+            profile_archive,
+            profile_unarchive,
+            profile_link_collection,
+            profile_unlink_collection,
+            profile_sync_collection,
+            profile_set_critical_project,
+            profile_remove_critical_project,
+            profile_update_critical_projects,
+            profile_set_primary_server,
+            profile_set_reproducible_install,
+            profile_load_server_properties,
+            profile_list_config_files,
+            profile_read_config_file,
+            profile_write_config_file,
+            profile_get_game_options,
+            profile_set_game_options,
+            profile_get_typed_game_options,
+            profile_set_game_option_overrides,
             profile_add_project_from_path,
             profile_toggle_disable_project,
+            profile_toggle_project,
             profile_remove_project,
             profile_update_managed_modrinth,
             profile_repair_managed_modrinth,
             profile_is_managed_modrinth,
+            profile_set_managed_modrinth_locked,
+            profile_set_managed_modrinth_update_channel,
+            profile_unlock,
+            profile_prepare,
+            profile_test_launch,
             profile_run,
             profile_run_wait,
+            profile_launch_safe_mode,
             profile_run_credentials,
             profile_run_wait_credentials,
             profile_edit,
             profile_edit_icon,
             profile_export_mrpack,
+            profile_export_full,
+            profile_import_full,
             profile_get_potential_override_folders,
         ])
         .build()
@@ -73,6 +143,15 @@ pub async fn profile_get_optimal_jre_key(
     Ok(res)
 }
 
+// Get the JVM arguments of a profile, merged from its pack and the user's
+// invoke('plugin:profile|profile_get_java_args', path)
+#[tauri::command]
+pub async fn profile_get_java_args(
+    path: ProfilePathId,
+) -> Result<Vec<JavaArg>> {
+    Ok(profile::get_java_args(&path).await?)
+}
+
 // Get a copy of the profile set
 // invoke('plugin:profile|profile_list')
 #[tauri::command]
@@ -111,6 +190,13 @@ pub async fn profile_install(path: ProfilePathId) -> Result<()> {
     Ok(())
 }
 
+/// Verifies a profile's game files, downloading corrupt ones again
+/// invoke('plugin:profile|profile_repair')
+#[tauri::command]
+pub async fn profile_repair(path: ProfilePathId) -> Result<Vec<PathBuf>> {
+    Ok(profile::repair(&path).await?)
+}
+
 /// Updates all of the profile's projects
 /// invoke('plugin:profile|profile_update_all')
 #[tauri::command]
@@ -140,6 +226,452 @@ pub async fn profile_add_project_from_version(
     Ok(profile::add_project_from_version(&path, version_id).await?)
 }
 
+// Adds a project to a profile from a version ID, along with its dependencies
+// invoke('plugin:profile|profile_add_project_with_dependencies')
+#[tauri::command]
+pub async fn profile_add_project_with_dependencies(
+    path: ProfilePathId,
+    version_id: String,
+    include_optional: bool,
+) -> Result<DependencyReport> {
+    Ok(profile::add_project_with_dependencies(
+        &path,
+        version_id,
+        include_optional,
+    )
+    .await?)
+}
+
+// Checks the enabled mods of a profile for conflicts before launching
+// invoke('plugin:profile|profile_check_compatibility')
+#[tauri::command]
+pub async fn profile_check_compatibility(
+    path: ProfilePathId,
+) -> Result<CompatibilityReport> {
+    Ok(profile::check_compatibility(&path).await?)
+}
+
+// Gets the playtime statistics of a profile
+// invoke('plugin:profile|profile_get_statistics')
+#[tauri::command]
+pub async fn profile_get_statistics(
+    path: ProfilePathId,
+) -> Result<profile::ProfileStatistics> {
+    Ok(profile::get_statistics(&path).await?)
+}
+
+// Lists every profile as JSON or CSV, for tracking them in other tools
+// invoke('plugin:profile|profile_export_inventory')
+#[tauri::command]
+pub async fn profile_export_inventory(
+    format: profile::inventory::InventoryFormat,
+) -> Result<String> {
+    Ok(profile::export_inventory(format).await?)
+}
+
+// Gets the space a profile takes up, by mods, worlds, packs, logs and more
+// invoke('plugin:profile|profile_get_disk_usage')
+#[tauri::command]
+pub async fn profile_get_disk_usage(
+    path: ProfilePathId,
+    refresh: bool,
+) -> Result<profile::disk_usage::DiskUsage> {
+    Ok(profile::get_disk_usage(&path, refresh).await?)
+}
+
+// Lists the Fabric or Quilt loader versions a development profile can use
+// invoke('plugin:profile|profile_get_development_loader_versions')
+#[tauri::command]
+pub async fn profile_get_development_loader_versions(
+    game_version: String,
+    loader: ModLoader,
+) -> Result<Vec<LoaderVersion>> {
+    Ok(profile::get_development_loader_versions(game_version, loader).await?)
+}
+
+// Sets or clears a profile's development settings, optionally pinning its
+// loader version
+// invoke('plugin:profile|profile_set_development')
+#[tauri::command]
+pub async fn profile_set_development(
+    path: ProfilePathId,
+    development: Option<DevelopmentSettings>,
+    loader_version: Option<String>,
+) -> Result<()> {
+    Ok(profile::set_development(&path, development, loader_version).await?)
+}
+
+// Sets or clears a profile's main class and removed game arguments
+// invoke('plugin:profile|profile_set_launch_overrides')
+#[tauri::command]
+pub async fn profile_set_launch_overrides(
+    path: ProfilePathId,
+    overrides: Option<LaunchOverrides>,
+) -> Result<()> {
+    Ok(profile::set_launch_overrides(&path, overrides).await?)
+}
+
+// Opts a profile in to syncing with the sync folder, or out if null
+// invoke('plugin:profile|profile_set_cloud_sync')
+#[tauri::command]
+pub async fn profile_set_cloud_sync(
+    path: ProfilePathId,
+    sync: Option<CloudSync>,
+) -> Result<()> {
+    Ok(profile::cloud_sync::set(&path, sync).await?)
+}
+
+// Syncs a profile with the sync folder both ways
+// invoke('plugin:profile|profile_cloud_sync')
+#[tauri::command]
+pub async fn profile_cloud_sync(path: ProfilePathId) -> Result<SyncReport> {
+    Ok(profile::cloud_sync::sync(&path).await?)
+}
+
+// Resolves a sync conflict by keeping the file of one side
+// invoke('plugin:profile|profile_resolve_sync_conflict')
+#[tauri::command]
+pub async fn profile_resolve_sync_conflict(
+    path: ProfilePathId,
+    file: String,
+    keep: SyncSide,
+) -> Result<()> {
+    Ok(profile::cloud_sync::resolve_conflict(&path, &file, keep).await?)
+}
+
+// Gets the lockfile of the exact Modrinth versions of a profile, if any
+// invoke('plugin:profile|profile_get_lockfile')
+#[tauri::command]
+pub async fn profile_get_lockfile(
+    path: ProfilePathId,
+) -> Result<Option<Lockfile>> {
+    Ok(profile::lockfile::get(&path).await?)
+}
+
+// Lists the version changes installing from the lockfile would make
+// invoke('plugin:profile|profile_diff_lockfile')
+#[tauri::command]
+pub async fn profile_diff_lockfile(
+    path: ProfilePathId,
+) -> Result<Vec<LockChange>> {
+    Ok(profile::lockfile::diff_lockfile(&path).await?)
+}
+
+// Lists the version changes updating every project would make to the lockfile
+// invoke('plugin:profile|profile_preview_updates')
+#[tauri::command]
+pub async fn profile_preview_updates(
+    path: ProfilePathId,
+) -> Result<Vec<LockChange>> {
+    Ok(profile::lockfile::preview_updates(&path).await?)
+}
+
+// Installs the exact Modrinth projects of the lockfile of a profile
+// invoke('plugin:profile|profile_install_from_lockfile')
+#[tauri::command]
+pub async fn profile_install_from_lockfile(
+    path: ProfilePathId,
+) -> Result<Vec<LockChange>> {
+    Ok(profile::lockfile::install_from_lockfile(&path).await?)
+}
+
+// Switches a profile to another loader or loader version and reinstalls it,
+// returning the mods that were built for a different loader
+// invoke('plugin:profile|profile_change_loader')
+#[tauri::command]
+pub async fn profile_change_loader(
+    path: ProfilePathId,
+    loader: ModLoader,
+    loader_version: Option<String>,
+) -> Result<Vec<ProjectPathId>> {
+    Ok(profile::change_loader(&path, loader, loader_version).await?)
+}
+
+// Lists the LWJGL versions a profile can be launched with, newest first
+// invoke('plugin:profile|profile_get_lwjgl_versions')
+#[tauri::command]
+pub async fn profile_get_lwjgl_versions(
+    path: ProfilePathId,
+) -> Result<Vec<String>> {
+    Ok(profile::get_lwjgl_versions(&path).await?)
+}
+
+// Gets what changed in the updates available to a profile's modpack and mods
+// invoke('plugin:profile|profile_get_update_changelogs')
+#[tauri::command]
+pub async fn profile_get_update_changelogs(
+    path: ProfilePathId,
+) -> Result<Vec<UpdateChangelog>> {
+    Ok(profile::changelog::get_update_changelogs(&path).await?)
+}
+
+// Gets the groups profiles are sorted into
+// invoke('plugin:profile|profile_get_groups')
+#[tauri::command]
+pub async fn profile_get_groups() -> Result<Vec<ProfileGroup>> {
+    Ok(profile::library::get_groups().await?)
+}
+
+// invoke('plugin:profile|profile_create_group', { name })
+#[tauri::command]
+pub async fn profile_create_group(name: String) -> Result<()> {
+    Ok(profile::library::create_group(&name).await?)
+}
+
+// invoke('plugin:profile|profile_rename_group', { name, newName })
+#[tauri::command]
+pub async fn profile_rename_group(
+    name: String,
+    new_name: String,
+) -> Result<()> {
+    Ok(profile::library::rename_group(&name, &new_name).await?)
+}
+
+// Deletes a group, keeping its profiles
+// invoke('plugin:profile|profile_delete_group', { name })
+#[tauri::command]
+pub async fn profile_delete_group(name: String) -> Result<()> {
+    Ok(profile::library::delete_group(&name).await?)
+}
+
+// invoke('plugin:profile|profile_set_group_order', { groups })
+#[tauri::command]
+pub async fn profile_set_group_order(groups: Vec<String>) -> Result<()> {
+    Ok(profile::library::set_group_order(groups).await?)
+}
+
+// Sets the groups a profile is in
+// invoke('plugin:profile|profile_set_groups', { path, groups })
+#[tauri::command]
+pub async fn profile_set_groups(
+    path: ProfilePathId,
+    groups: Vec<String>,
+) -> Result<()> {
+    Ok(profile::library::set_profile_groups(&path, groups).await?)
+}
+
+// invoke('plugin:profile|profile_set_tags', { path, tags })
+#[tauri::command]
+pub async fn profile_set_tags(
+    path: ProfilePathId,
+    tags: Vec<String>,
+) -> Result<()> {
+    Ok(profile::library::set_profile_tags(&path, tags).await?)
+}
+
+// Gets every tag used on a profile
+// invoke('plugin:profile|profile_get_tags')
+#[tauri::command]
+pub async fn profile_get_tags() -> Result<Vec<String>> {
+    Ok(profile::library::get_tags().await?)
+}
+
+// Moves profiles into the order they are listed in
+// invoke('plugin:profile|profile_set_order', { paths })
+#[tauri::command]
+pub async fn profile_set_order(paths: Vec<ProfilePathId>) -> Result<()> {
+    Ok(profile::library::set_order(paths).await?)
+}
+
+// Lists the profiles matching a filter, in the order of the library
+// invoke('plugin:profile|profile_list_library', { filter })
+#[tauri::command]
+pub async fn profile_list_library(
+    filter: LibraryFilter,
+) -> Result<Vec<Profile>> {
+    Ok(profile::library::list(filter).await?)
+}
+
+// Overrides the LWJGL version of a profile, or clears the override
+// invoke('plugin:profile|profile_set_lwjgl_version')
+#[tauri::command]
+pub async fn profile_set_lwjgl_version(
+    path: ProfilePathId,
+    version: Option<String>,
+) -> Result<()> {
+    Ok(profile::set_lwjgl_version(&path, version).await?)
+}
+
+// Overrides the LWJGL version of a profile on Java runtimes of an
+// architecture, or clears that override
+// invoke('plugin:profile|profile_set_lwjgl_arch_version')
+#[tauri::command]
+pub async fn profile_set_lwjgl_arch_version(
+    path: ProfilePathId,
+    java_arch: String,
+    version: Option<String>,
+) -> Result<()> {
+    Ok(profile::set_lwjgl_arch_version(&path, &java_arch, version).await?)
+}
+
+// Compresses the contents of a profile into an archive, keeping it listed
+// invoke('plugin:profile|profile_archive')
+#[tauri::command]
+pub async fn profile_archive(path: ProfilePathId) -> Result<()> {
+    Ok(profile::archive(&path).await?)
+}
+
+// Restores the contents of an archived profile
+// invoke('plugin:profile|profile_unarchive')
+#[tauri::command]
+pub async fn profile_unarchive(path: ProfilePathId) -> Result<()> {
+    Ok(profile::unarchive(&path).await?)
+}
+
+// Links a profile to a Modrinth collection and installs its projects
+// invoke('plugin:profile|profile_link_collection')
+#[tauri::command]
+pub async fn profile_link_collection(
+    path: ProfilePathId,
+    collection_id: String,
+) -> Result<profile::CollectionSyncReport> {
+    Ok(profile::link_collection(&path, collection_id).await?)
+}
+
+// Unlinks a profile from its collection, keeping its projects
+// invoke('plugin:profile|profile_unlink_collection')
+#[tauri::command]
+pub async fn profile_unlink_collection(path: ProfilePathId) -> Result<()> {
+    Ok(profile::unlink_collection(&path).await?)
+}
+
+// Adds and removes projects to match the profile's linked collection
+// invoke('plugin:profile|profile_sync_collection')
+#[tauri::command]
+pub async fn profile_sync_collection(
+    path: ProfilePathId,
+) -> Result<profile::CollectionSyncReport> {
+    Ok(profile::sync_collection(&path).await?)
+}
+
+// Marks a project as critical, keeping it updated or at a version before launch
+// invoke('plugin:profile|profile_set_critical_project')
+#[tauri::command]
+pub async fn profile_set_critical_project(
+    path: ProfilePathId,
+    project_id: String,
+    version_id: Option<String>,
+) -> Result<()> {
+    Ok(profile::set_critical_project(&path, project_id, version_id).await?)
+}
+
+// Stops treating a project as critical
+// invoke('plugin:profile|profile_remove_critical_project')
+#[tauri::command]
+pub async fn profile_remove_critical_project(
+    path: ProfilePathId,
+    project_id: String,
+) -> Result<()> {
+    Ok(profile::remove_critical_project(&path, &project_id).await?)
+}
+
+// Installs the required versions of a profile's critical projects
+// invoke('plugin:profile|profile_update_critical_projects')
+#[tauri::command]
+pub async fn profile_update_critical_projects(
+    path: ProfilePathId,
+) -> Result<Vec<ProjectPathId>> {
+    Ok(profile::update_critical_projects(&path).await?)
+}
+
+// Sets the server a profile is made for, or clears it
+// invoke('plugin:profile|profile_set_primary_server')
+#[tauri::command]
+pub async fn profile_set_primary_server(
+    path: ProfilePathId,
+    server: Option<PrimaryServer>,
+) -> Result<()> {
+    Ok(profile::set_primary_server(&path, server).await?)
+}
+
+// Sets whether reinstalls refuse loader files differing from the recorded ones
+// invoke('plugin:profile|profile_set_reproducible_install')
+#[tauri::command]
+pub async fn profile_set_reproducible_install(
+    path: ProfilePathId,
+    reproducible: bool,
+) -> Result<()> {
+    Ok(profile::set_reproducible_install(&path, reproducible).await?)
+}
+
+// Reads the resource pack of the primary server from its server.properties
+// invoke('plugin:profile|profile_load_server_properties')
+#[tauri::command]
+pub async fn profile_load_server_properties(
+    path: ProfilePathId,
+    source: String,
+) -> Result<Option<ServerResourcePack>> {
+    Ok(profile::load_server_properties(&path, &source).await?)
+}
+
+// Lists the files in the config folder of a profile
+// invoke('plugin:profile|profile_list_config_files')
+#[tauri::command]
+pub async fn profile_list_config_files(
+    path: ProfilePathId,
+) -> Result<Vec<ConfigFile>> {
+    Ok(profile::config::list(&path).await?)
+}
+
+// Reads a file of the config folder, parsed as its format
+// invoke('plugin:profile|profile_read_config_file')
+#[tauri::command]
+pub async fn profile_read_config_file(
+    path: ProfilePathId,
+    file: String,
+) -> Result<ConfigDocument> {
+    Ok(profile::config::read(&path, &file).await?)
+}
+
+// Writes a file of the config folder in its format
+// invoke('plugin:profile|profile_write_config_file')
+#[tauri::command]
+pub async fn profile_write_config_file(
+    path: ProfilePathId,
+    file: String,
+    value: serde_json::Value,
+) -> Result<()> {
+    Ok(profile::config::write(&path, &file, &value).await?)
+}
+
+// Gets the game's settings from options.txt
+// invoke('plugin:profile|profile_get_game_options')
+#[tauri::command]
+pub async fn profile_get_game_options(
+    path: ProfilePathId,
+) -> Result<HashMap<String, String>> {
+    Ok(profile::config::get_game_options(&path).await?)
+}
+
+// Sets some of the game's settings in options.txt
+// invoke('plugin:profile|profile_set_game_options')
+#[tauri::command]
+pub async fn profile_set_game_options(
+    path: ProfilePathId,
+    options: HashMap<String, String>,
+) -> Result<()> {
+    Ok(profile::config::set_game_options(&path, options).await?)
+}
+
+// Gets the game's settings from options.txt as typed values
+// invoke('plugin:profile|profile_get_typed_game_options')
+#[tauri::command]
+pub async fn profile_get_typed_game_options(
+    path: ProfilePathId,
+) -> Result<BTreeMap<String, OptionValue>> {
+    Ok(profile::config::get_typed_game_options(&path).await?)
+}
+
+// Sets or clears the settings a profile forces into options.txt on launch
+// invoke('plugin:profile|profile_set_game_option_overrides')
+#[tauri::command]
+pub async fn profile_set_game_option_overrides(
+    path: ProfilePathId,
+    overrides: Option<GameOptionOverrides>,
+) -> Result<()> {
+    Ok(profile::set_game_option_overrides(&path, overrides).await?)
+}
+
 // Adds a project to a profile from a path
 // invoke('plugin:profile|profile_add_project_from_path')
 #[tauri::command]
@@ -163,6 +695,18 @@ pub async fn profile_toggle_disable_project(
     Ok(profile::toggle_disable_project(&path, &project_path).await?)
 }
 
+// Enables or disables a project, along with the mods it is linked to if
+// cascading
+// invoke('plugin:profile|profile_toggle_project')
+#[tauri::command]
+pub async fn profile_toggle_project(
+    path: ProfilePathId,
+    project_path: ProjectPathId,
+    cascade: bool,
+) -> Result<profile::ToggleReport> {
+    Ok(profile::toggle_project(&path, &project_path, cascade).await?)
+}
+
 // Removes a project from a profile
 // invoke('plugin:profile|profile_remove_project')
 #[tauri::command]
@@ -196,6 +740,36 @@ pub async fn profile_is_managed_modrinth(path: ProfilePathId) -> Result<bool> {
     Ok(profile::is_managed_modrinth_pack(&path).await?)
 }
 
+// Locks a managed pack to the projects it installed, flagging manual changes
+// invoke('plugin:profile|profile_set_managed_modrinth_locked')
+#[tauri::command]
+pub async fn profile_set_managed_modrinth_locked(
+    path: ProfilePathId,
+    locked: bool,
+) -> Result<()> {
+    Ok(profile::set_managed_modrinth_locked(&path, locked).await?)
+}
+
+// Sets whether a managed pack updates to release, beta or alpha versions
+// invoke('plugin:profile|profile_set_managed_modrinth_update_channel')
+#[tauri::command]
+pub async fn profile_set_managed_modrinth_update_channel(
+    path: ProfilePathId,
+    update_channel: UpdateChannel,
+) -> Result<()> {
+    Ok(
+        profile::set_managed_modrinth_update_channel(&path, update_channel)
+            .await?,
+    )
+}
+
+// Converts a managed pack to a regular profile, keeping its files
+// invoke('plugin:profile|profile_unlock')
+#[tauri::command]
+pub async fn profile_unlock(path: ProfilePathId) -> Result<()> {
+    Ok(profile::unlock(&path).await?)
+}
+
 // Exports a profile to a .mrpack file (export_location should end in .mrpack)
 // invoke('profile_export_mrpack')
 #[tauri::command]
@@ -215,6 +789,25 @@ pub async fn profile_export_mrpack(
     Ok(())
 }
 
+// Zips a whole profile, to be imported on another machine
+// invoke('plugin:profile|profile_export_full')
+#[tauri::command]
+pub async fn profile_export_full(
+    path: ProfilePathId,
+    export_location: PathBuf,
+    options: ExportOptions,
+) -> Result<()> {
+    profile::portable::export_full(&path, export_location, options).await?;
+    Ok(())
+}
+
+// Recreates a profile from a zip made by profile_export_full
+// invoke('plugin:profile|profile_import_full')
+#[tauri::command]
+pub async fn profile_import_full(zip_path: PathBuf) -> Result<ProfilePathId> {
+    Ok(profile::portable::import_full(zip_path).await?)
+}
+
 // Given a folder path, populate a Vec of all the subfolders
 // Intended to be used for finding potential override folders
 // profile
@@ -231,6 +824,30 @@ pub async fn profile_get_potential_override_folders(
     Ok(overrides)
 }
 
+// Installs and downloads everything a profile needs to launch ahead of time,
+// so that running it after is quick
+// invoke('plugin:profile|profile_prepare', path)
+#[tauri::command]
+pub async fn profile_prepare(path: ProfilePathId) -> Result<()> {
+    Ok(profile::prepare(&path).await?)
+}
+
+// Launches a profile to check that the game loads, stopping it after
+// invoke('plugin:profile|profile_test_launch', { path, timeoutSecs, headless })
+#[tauri::command]
+pub async fn profile_test_launch(
+    path: ProfilePathId,
+    timeout_secs: u64,
+    headless: bool,
+) -> Result<SmokeTestResult> {
+    Ok(profile::smoke_test::test_launch(
+        &path,
+        Duration::from_secs(timeout_secs),
+        headless,
+    )
+    .await?)
+}
+
 // Run minecraft using a profile using the default credentials
 // Returns the UUID, which can be used to poll
 // for the actual Child in the state.
@@ -242,6 +859,17 @@ pub async fn profile_run(path: ProfilePathId) -> Result<Uuid> {
     Ok(uuid)
 }
 
+// Run Minecraft using a profile with its mods disabled until the game exits
+// Returns the UUID, which can be used to poll
+// for the actual Child in the state.
+// invoke('plugin:profile|profile_launch_safe_mode', path)
+#[tauri::command]
+pub async fn profile_launch_safe_mode(path: ProfilePathId) -> Result<Uuid> {
+    let minecraft_child = profile::launch_safe_mode(&path).await?;
+    let uuid = minecraft_child.read().await.uuid;
+    Ok(uuid)
+}
+
 // Run Minecraft using a profile using the default credentials, and wait for the result
 // invoke('plugin:profile|profile_run_wait', path)
 #[tauri::command]
@@ -286,6 +914,9 @@ pub struct EditProfile {
     pub resolution: Option<WindowSize>,
     pub hooks: Option<Hooks>,
     pub fullscreen: Option<bool>,
+    pub network: Option<NetworkOverrides>,
+    pub gpu_preference: Option<GpuPreference>,
+    pub display_server: Option<DisplayServer>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -296,6 +927,7 @@ pub struct EditProfileMetadata {
     pub loader_version: Option<LoaderVersion>,
     pub linked_data: Option<LinkedData>,
     pub groups: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
 }
 
 // Edits a profile
@@ -322,6 +954,9 @@ pub async fn profile_edit(
             if let Some(groups) = metadata.groups {
                 prof.metadata.groups = groups;
             }
+            if let Some(tags) = metadata.tags {
+                prof.metadata.tags = tags;
+            }
         }
 
         prof.java = edit_profile.java.clone();
@@ -329,6 +964,13 @@ pub async fn profile_edit(
         prof.resolution = edit_profile.resolution;
         prof.fullscreen = edit_profile.fullscreen;
         prof.hooks = edit_profile.hooks.clone();
+        prof.network = edit_profile.network.clone();
+        if let Some(gpu_preference) = edit_profile.gpu_preference {
+            prof.gpu_preference = gpu_preference;
+        }
+        if let Some(display_server) = edit_profile.display_server {
+            prof.display_server = display_server;
+        }
 
         prof.metadata.date_modified = chrono::Utc::now();
 
@@ -337,6 +979,10 @@ pub async fn profile_edit(
     .await?;
     State::sync().await?;
 
+    if edit_profile.gpu_preference == Some(GpuPreference::Default) {
+        gpu::release_preference(&path).await?;
+    }
+
     Ok(())
 }
 
@@ -4,7 +4,11 @@ use theseus::prelude::*;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("profile_create")
-        .invoke_handler(tauri::generate_handler![profile_create,])
+        .invoke_handler(tauri::generate_handler![
+            profile_create,
+            profile_get_definition,
+            profile_install_from_definition,
+        ])
         .build()
 }
 
@@ -31,3 +35,22 @@ pub async fn profile_create(
     .await?;
     Ok(res)
 }
+
+// Fetches the profile definition at a URL, to show before installing it
+// invoke('plugin:profile_create|profile_get_definition',{ url })
+#[tauri::command]
+pub async fn profile_get_definition(
+    url: &str,
+) -> Result<profile::definition::ProfileDefinition> {
+    Ok(profile::definition::get_definition(url).await?)
+}
+
+// Creates a profile from the profile definition at a URL, installing its
+// projects with their dependencies
+// invoke('plugin:profile_create|profile_install_from_definition',{ url })
+#[tauri::command]
+pub async fn profile_install_from_definition(
+    url: &str,
+) -> Result<ProfilePathId> {
+    Ok(profile::definition::install_from_definition(url).await?)
+}
@@ -0,0 +1,60 @@
+use crate::api::Result;
+use theseus::{
+    prelude::ProfilePathId,
+    screenshots::{self, Screenshot},
+};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("screenshots")
+        .invoke_handler(tauri::generate_handler![
+            screenshots_get_screenshots,
+            screenshots_get_screenshot,
+            screenshots_get_screenshot_data,
+            screenshots_delete_screenshot,
+            screenshots_get_screenshots_dir,
+        ])
+        .build()
+}
+
+/// Get all screenshots of a profile, newest first
+#[tauri::command]
+pub async fn screenshots_get_screenshots(
+    profile_path: ProfilePathId,
+) -> Result<Vec<Screenshot>> {
+    Ok(screenshots::get_screenshots(&profile_path).await?)
+}
+
+/// Get a screenshot of a profile by file name
+#[tauri::command]
+pub async fn screenshots_get_screenshot(
+    profile_path: ProfilePathId,
+    name: String,
+) -> Result<Screenshot> {
+    Ok(screenshots::get_screenshot(&profile_path, &name).await?)
+}
+
+/// Get the image bytes of a screenshot by file name
+#[tauri::command]
+pub async fn screenshots_get_screenshot_data(
+    profile_path: ProfilePathId,
+    name: String,
+) -> Result<Vec<u8>> {
+    Ok(screenshots::get_screenshot_data(&profile_path, &name).await?)
+}
+
+/// Delete a screenshot of a profile by file name
+#[tauri::command]
+pub async fn screenshots_delete_screenshot(
+    profile_path: ProfilePathId,
+    name: String,
+) -> Result<()> {
+    Ok(screenshots::delete_screenshot(&profile_path, &name).await?)
+}
+
+/// Get the full path of a profile's screenshots folder
+#[tauri::command]
+pub async fn screenshots_get_screenshots_dir(
+    profile_path: ProfilePathId,
+) -> Result<std::path::PathBuf> {
+    Ok(screenshots::get_screenshots_dir(&profile_path).await?)
+}
@@ -0,0 +1,85 @@
+use crate::api::Result;
+use theseus::{
+    prelude::ProfilePathId,
+    servers::{self, Server, ServerStatus},
+};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("servers")
+        .invoke_handler(tauri::generate_handler![
+            servers_get_servers,
+            servers_add_server,
+            servers_edit_server,
+            servers_remove_server,
+            servers_move_server,
+            servers_set_resource_pack_acceptance,
+            servers_ping_server,
+        ])
+        .build()
+}
+
+/// Get the multiplayer servers of a profile, in the order the game lists them
+#[tauri::command]
+pub async fn servers_get_servers(
+    profile_path: ProfilePathId,
+) -> Result<Vec<Server>> {
+    Ok(servers::get_servers(&profile_path).await?)
+}
+
+/// Add a server to a profile, at the end unless an index is given
+#[tauri::command]
+pub async fn servers_add_server(
+    profile_path: ProfilePathId,
+    server: Server,
+    index: Option<usize>,
+) -> Result<()> {
+    Ok(servers::add_server(&profile_path, server, index).await?)
+}
+
+/// Replace the server at an index
+#[tauri::command]
+pub async fn servers_edit_server(
+    profile_path: ProfilePathId,
+    index: usize,
+    server: Server,
+) -> Result<()> {
+    Ok(servers::edit_server(&profile_path, index, server).await?)
+}
+
+/// Remove the server at an index
+#[tauri::command]
+pub async fn servers_remove_server(
+    profile_path: ProfilePathId,
+    index: usize,
+) -> Result<()> {
+    Ok(servers::remove_server(&profile_path, index).await?)
+}
+
+/// Move a server to another index
+#[tauri::command]
+pub async fn servers_move_server(
+    profile_path: ProfilePathId,
+    from: usize,
+    to: usize,
+) -> Result<()> {
+    Ok(servers::move_server(&profile_path, from, to).await?)
+}
+
+/// Accept or decline the resource pack of a server, or ask when joining
+#[tauri::command]
+pub async fn servers_set_resource_pack_acceptance(
+    profile_path: ProfilePathId,
+    index: usize,
+    accept: Option<bool>,
+) -> Result<()> {
+    Ok(
+        servers::set_resource_pack_acceptance(&profile_path, index, accept)
+            .await?,
+    )
+}
+
+/// Ping a server for its status
+#[tauri::command]
+pub async fn servers_ping_server(address: String) -> Result<ServerStatus> {
+    Ok(servers::ping_server(&address).await?)
+}
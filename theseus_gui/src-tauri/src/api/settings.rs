@@ -1,13 +1,27 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::api::Result;
 use theseus::prelude::*;
+use theseus::settings::DebugCapture;
 
 pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("settings")
         .invoke_handler(tauri::generate_handler![
             settings_get,
             settings_set,
+            settings_get_post_launch_policy,
+            settings_get_content_policy,
+            settings_get_connection_metrics,
+            settings_get_download_status,
+            settings_get_mirror_latencies,
+            settings_bmclapi_mirror,
+            settings_set_download_override,
+            settings_set_logging,
+            settings_capture_debug_log,
+            settings_get_debug_capture,
+            settings_check_write_access,
+            settings_suggest_config_dir,
             settings_change_config_dir
         ])
         .build()
@@ -29,6 +43,91 @@ pub async fn settings_set(settings: Settings) -> Result<()> {
     Ok(())
 }
 
+// Get what the launcher should do around a game's lifetime
+// invoke('plugin:settings|settings_get_post_launch_policy')
+#[tauri::command]
+pub async fn settings_get_post_launch_policy() -> Result<PostLaunchPolicy> {
+    Ok(settings::get_post_launch_policy().await?)
+}
+
+// Get the content policy installs are held to on managed computers, if any
+// invoke('plugin:settings|settings_get_content_policy')
+#[tauri::command]
+pub async fn settings_get_content_policy() -> Result<Option<ContentPolicy>> {
+    Ok(settings::get_content_policy().await?)
+}
+
+// Get request counters of the launcher's HTTP client
+// invoke('plugin:settings|settings_get_connection_metrics')
+#[tauri::command]
+pub async fn settings_get_connection_metrics() -> Result<ConnectionMetrics> {
+    Ok(settings::get_connection_metrics().await?)
+}
+
+// Get whether downloads are paused or throttled for running games
+// invoke('plugin:settings|settings_get_download_status')
+#[tauri::command]
+pub async fn settings_get_download_status() -> Result<DownloadStatus> {
+    Ok(settings::get_download_status().await?)
+}
+
+// Get how long each download host took to answer, in milliseconds
+// invoke('plugin:settings|settings_get_mirror_latencies')
+#[tauri::command]
+pub async fn settings_get_mirror_latencies() -> Result<HashMap<String, u64>> {
+    Ok(settings::get_mirror_latencies().await?)
+}
+
+// Get the BMCLAPI mirror, ready to add to the download mirrors
+// invoke('plugin:settings|settings_bmclapi_mirror')
+#[tauri::command]
+pub fn settings_bmclapi_mirror() -> DownloadMirror {
+    DownloadMirror::bmclapi()
+}
+
+// Pause or resume installs by hand, or follow the running-game policy again
+// invoke('plugin:settings|settings_set_download_override', { manual })
+#[tauri::command]
+pub async fn settings_set_download_override(
+    manual: DownloadOverride,
+) -> Result<DownloadStatus> {
+    Ok(settings::set_download_override(manual).await?)
+}
+
+// Set the verbosity of the launcher's logs, applied right away
+// invoke('plugin:settings|settings_set_logging', { logging })
+#[tauri::command]
+pub async fn settings_set_logging(logging: LoggingSettings) -> Result<()> {
+    Ok(settings::set_logging(logging).await?)
+}
+
+// Record a debug log of the next install or modpack install/update
+// invoke('plugin:settings|settings_capture_debug_log')
+#[tauri::command]
+pub async fn settings_capture_debug_log() -> Result<DebugCapture> {
+    Ok(settings::capture_debug_log().await?)
+}
+
+// invoke('plugin:settings|settings_get_debug_capture')
+#[tauri::command]
+pub async fn settings_get_debug_capture() -> Result<DebugCapture> {
+    Ok(settings::get_debug_capture().await?)
+}
+
+// List the launcher's folders it can't write to
+// invoke('plugin:settings|settings_check_write_access')
+#[tauri::command]
+pub async fn settings_check_write_access() -> Result<Vec<WriteAccessIssue>> {
+    Ok(settings::check_write_access().await?)
+}
+
+// Suggest a writable folder to move the config directory to
+// invoke('plugin:settings|settings_suggest_config_dir')
+#[tauri::command]
+pub async fn settings_suggest_config_dir() -> Result<Option<PathBuf>> {
+    Ok(settings::suggest_config_dir().await?)
+}
+
 // Change config directory
 // Seizes the entire State to do it
 // invoke('plugin:settings|settings_change_config_dir', new_dir)
@@ -0,0 +1,67 @@
+use crate::api::Result;
+use std::collections::HashMap;
+use theseus::{
+    prelude::{
+        ContentProject, ContentQuery, ContentVersion, ProfilePathId,
+        ProjectPathId,
+    },
+    sources,
+};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("sources")
+        .invoke_handler(tauri::generate_handler![
+            sources_get_sources,
+            sources_search,
+            sources_get_versions,
+            sources_install_version,
+            sources_check_updates,
+        ])
+        .build()
+}
+
+/// Get the ids of every registered content source
+#[tauri::command]
+pub async fn sources_get_sources() -> Result<Vec<String>> {
+    Ok(sources::get_sources().await?)
+}
+
+/// Search a content source for projects
+#[tauri::command]
+pub async fn sources_search(
+    source: String,
+    query: ContentQuery,
+) -> Result<Vec<ContentProject>> {
+    Ok(sources::search(&source, &query).await?)
+}
+
+/// Get the versions of a project, filtered for a profile if one is given
+#[tauri::command]
+pub async fn sources_get_versions(
+    source: String,
+    project_id: String,
+    profile_path: Option<ProfilePathId>,
+) -> Result<Vec<ContentVersion>> {
+    Ok(
+        sources::get_versions(&source, &project_id, profile_path.as_ref())
+            .await?,
+    )
+}
+
+/// Download a version through its source and add it to a profile
+#[tauri::command]
+pub async fn sources_install_version(
+    profile_path: ProfilePathId,
+    version: ContentVersion,
+) -> Result<ProjectPathId> {
+    Ok(sources::install_version(&profile_path, &version).await?)
+}
+
+/// Check a content source for updates to the projects of a profile
+#[tauri::command]
+pub async fn sources_check_updates(
+    profile_path: ProfilePathId,
+    source: String,
+) -> Result<HashMap<ProjectPathId, ContentVersion>> {
+    Ok(sources::check_updates(&profile_path, &source).await?)
+}
@@ -0,0 +1,15 @@
+use crate::api::Result;
+use theseus::status::{self, LauncherStatus};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("status")
+        .invoke_handler(tauri::generate_handler![status_get])
+        .build()
+}
+
+// Summary of running instances, downloads, updates and accounts for the tray
+// invoke('plugin:status|status_get')
+#[tauri::command]
+pub async fn status_get() -> Result<LauncherStatus> {
+    Ok(status::get_status().await?)
+}
@@ -0,0 +1,29 @@
+use crate::api::Result;
+use theseus::prelude::*;
+use theseus::user::{self, LibraryEntry};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("user")
+        .invoke_handler(tauri::generate_handler![
+            user_library,
+            user_library_install,
+        ])
+        .build()
+}
+
+// Projects the Modrinth user follows, owns or has installed
+// invoke('plugin:user|user_library')
+#[tauri::command]
+pub async fn user_library() -> Result<Vec<LibraryEntry>> {
+    Ok(user::library().await?)
+}
+
+// Installs a project of the library, as a new profile for modpacks
+// invoke('plugin:user|user_library_install')
+#[tauri::command]
+pub async fn user_library_install(
+    project_id: String,
+    profile: Option<ProfilePathId>,
+) -> Result<ProfilePathId> {
+    Ok(user::install(&project_id, profile.as_ref()).await?)
+}
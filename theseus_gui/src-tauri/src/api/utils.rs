@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
-use theseus::{handler, prelude::CommandPayload, State};
+use theseus::{
+    display, gpu, handler,
+    prelude::{CommandPayload, StateComponent},
+    State,
+};
 
 use crate::api::Result;
 use std::{env, process::Command};
@@ -8,6 +12,8 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
     tauri::plugin::Builder::new("utils")
         .invoke_handler(tauri::generate_handler![
             get_os,
+            get_gpus,
+            get_session_type,
             should_disable_mouseover,
             show_in_folder,
             progress_bars_list,
@@ -15,7 +21,8 @@ pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
             get_opening_command,
             await_sync,
             is_offline,
-            refresh_offline
+            refresh_offline,
+            ready_components
         ])
         .build()
 }
@@ -38,6 +45,20 @@ pub enum OS {
     MacOS,
 }
 
+// Lists the graphics cards of the system, for the GPU preference of profiles
+// invoke('plugin:utils|get_gpus')
+#[tauri::command]
+pub async fn get_gpus() -> Result<Vec<gpu::Gpu>> {
+    Ok(gpu::get_gpus().await?)
+}
+
+// Gets the display server of the session, for the display server of profiles
+// invoke('plugin:utils|get_session_type')
+#[tauri::command]
+pub async fn get_session_type() -> Result<display::SessionType> {
+    Ok(display::get_session_type().await?)
+}
+
 // Lists active progress bars
 // Create a new HashMap with the same keys
 // Values provided should not be used directly, as they are not guaranteed to be up-to-date
@@ -156,6 +177,15 @@ pub async fn is_offline() -> Result<bool> {
     Ok(offline)
 }
 
+/// Lists the parts of the state that finished loading in the background
+// invoke('plugin:utils|ready_components')
+#[tauri::command]
+pub async fn ready_components() -> Result<Vec<StateComponent>> {
+    let state = State::get().await?;
+    let ready = state.ready.read().await.iter().copied().collect();
+    Ok(ready)
+}
+
 /// Refreshes whether or not theseus is in offline mode, and returns the new value
 #[tauri::command]
 pub async fn refresh_offline() -> Result<bool> {
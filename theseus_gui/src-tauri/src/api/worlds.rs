@@ -0,0 +1,86 @@
+use crate::api::Result;
+use std::collections::HashMap;
+use theseus::{
+    prelude::{ModrinthVersion, ProfilePathId},
+    worlds::{self, Datapack, World},
+};
+
+pub fn init<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri::plugin::Builder::new("worlds")
+        .invoke_handler(tauri::generate_handler![
+            worlds_get_worlds,
+            worlds_get_datapacks,
+            worlds_install_datapack,
+            worlds_toggle_disable_datapack,
+            worlds_remove_datapack,
+            worlds_check_datapack_updates,
+            worlds_update_datapack,
+        ])
+        .build()
+}
+
+/// Get all worlds of a profile, most recently played first
+#[tauri::command]
+pub async fn worlds_get_worlds(
+    profile_path: ProfilePathId,
+) -> Result<Vec<World>> {
+    Ok(worlds::get_worlds(&profile_path).await?)
+}
+
+/// Get all datapacks of a world
+#[tauri::command]
+pub async fn worlds_get_datapacks(
+    profile_path: ProfilePathId,
+    world: String,
+) -> Result<Vec<Datapack>> {
+    Ok(worlds::get_datapacks(&profile_path, &world).await?)
+}
+
+/// Install a Modrinth datapack version into a world
+#[tauri::command]
+pub async fn worlds_install_datapack(
+    profile_path: ProfilePathId,
+    world: String,
+    version_id: String,
+) -> Result<String> {
+    Ok(worlds::install_datapack(&profile_path, &world, &version_id).await?)
+}
+
+/// Enable or disable a datapack of a world
+#[tauri::command]
+pub async fn worlds_toggle_disable_datapack(
+    profile_path: ProfilePathId,
+    world: String,
+    name: String,
+) -> Result<String> {
+    Ok(worlds::toggle_disable_datapack(&profile_path, &world, &name).await?)
+}
+
+/// Delete a datapack from a world
+#[tauri::command]
+pub async fn worlds_remove_datapack(
+    profile_path: ProfilePathId,
+    world: String,
+    name: String,
+) -> Result<()> {
+    Ok(worlds::remove_datapack(&profile_path, &world, &name).await?)
+}
+
+/// Get newer versions of the datapacks of a world, by datapack name
+#[tauri::command]
+pub async fn worlds_check_datapack_updates(
+    profile_path: ProfilePathId,
+    world: String,
+) -> Result<HashMap<String, ModrinthVersion>> {
+    Ok(worlds::check_datapack_updates(&profile_path, &world).await?)
+}
+
+/// Update a datapack of a world to its newest version
+#[tauri::command]
+pub async fn worlds_update_datapack(
+    profile_path: ProfilePathId,
+    world: String,
+    name: String,
+) -> Result<String> {
+    Ok(worlds::update_datapack(&profile_path, &world, &name).await?)
+}
@@ -3,7 +3,10 @@
     windows_subsystem = "windows"
 )]
 
-use tauri::Manager;
+use tauri::{
+    CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+};
+use tauri_plugin_window_state::{AppHandleExt, StateFlags};
 use theseus::prelude::*;
 
 mod api;
@@ -38,6 +41,52 @@ async fn toggle_decorations(b: bool, window: tauri::Window) -> api::Result<()> {
     Ok(())
 }
 
+// Whether a game is running that reopens the launcher once it exits
+async fn relaunch_pending() -> bool {
+    let policy = settings::get_post_launch_policy().await;
+    let running = process::get_all_running_uuids().await;
+    match (policy, running) {
+        (Ok(policy), Ok(running)) => {
+            policy.behavior == PostLaunchBehavior::QuitAndRelaunch
+                && !running.is_empty()
+        }
+        _ => false,
+    }
+}
+
+// Tray icon the window can be hidden to after launching a game
+fn system_tray() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("show", "Show Modrinth App"))
+        .add_item(CustomMenuItem::new("quit", "Quit"));
+    SystemTray::new().with_menu(menu)
+}
+
+fn handle_tray_event(app: &tauri::AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => show_window(),
+        SystemTrayEvent::MenuItemClick { id, .. } if id == "show" => {
+            show_window()
+        }
+        SystemTrayEvent::MenuItemClick { id, .. } if id == "quit" => {
+            app.exit(0)
+        }
+        _ => {}
+    }
+}
+
+// Brings the window back, opening it again if it was closed
+fn show_window() {
+    tauri::async_runtime::spawn(async {
+        if let Err(e) =
+            theseus::EventState::apply_window_action(WindowAction::Restore)
+                .await
+        {
+            tracing::error!("Failed to show the window: {}", e);
+        }
+    });
+}
+
 #[derive(Clone, serde::Serialize)]
 struct Payload {
     args: Vec<String>,
@@ -74,6 +123,8 @@ fn main() {
                 .unwrap();
         }))
         .plugin(tauri_plugin_window_state::Builder::default().build())
+        .system_tray(system_tray())
+        .on_system_tray_event(handle_tray_event)
         .setup(|app| {
             // Register deep link handler, allowing reading of modrinth:// links
             if let Err(e) = tauri_plugin_deep_link::register(
@@ -129,6 +180,7 @@ fn main() {
         .plugin(api::auth::init())
         .plugin(api::mr_auth::init())
         .plugin(api::import::init())
+        .plugin(api::jobs::init())
         .plugin(api::logs::init())
         .plugin(api::jre::init())
         .plugin(api::metadata::init())
@@ -136,9 +188,15 @@ fn main() {
         .plugin(api::process::init())
         .plugin(api::profile::init())
         .plugin(api::profile_create::init())
+        .plugin(api::screenshots::init())
+        .plugin(api::servers::init())
         .plugin(api::settings::init())
+        .plugin(api::sources::init())
+        .plugin(api::status::init())
         .plugin(api::tags::init())
+        .plugin(api::user::init())
         .plugin(api::utils::init())
+        .plugin(api::worlds::init())
         .invoke_handler(tauri::generate_handler![
             initialize_state,
             is_dev,
@@ -146,6 +204,28 @@ fn main() {
         ]);
 
     builder
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Closing the window for a game that reopens it on exit must not
+            // quit the app, which still watches the game. The event loop
+            // can't wait on the state, so the app exits from a task instead
+            if let tauri::RunEvent::ExitRequested { api: exit_api, .. } = event
+            {
+                exit_api.prevent_exit();
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if !relaunch_pending().await {
+                        if let Err(e) = app.save_window_state(StateFlags::all())
+                        {
+                            tracing::error!(
+                                "Failed to save the window state: {}",
+                                e
+                            );
+                        }
+                        app.exit(0);
+                    }
+                });
+            }
+        });
 }
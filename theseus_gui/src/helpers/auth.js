@@ -28,10 +28,24 @@ export async function authenticate_await_completion() {
   return await invoke('plugin:auth|auth_authenticate_await_completion')
 }
 
+/// Sign in again with an account whose needs_consent is set, as Microsoft wants it to grant permissions again
+/// This returns a URL to be opened in a browser, then authenticate_await_completion() updates the account
+/// Signing in with another account fails, and the account is kept either way
+export async function reconsent_begin_flow(user) {
+  return await invoke('plugin:auth|auth_reconsent_begin_flow', { user })
+}
+
 export async function cancel_flow() {
   return await invoke('plugin:auth|auth_cancel_flow')
 }
 
+/// Log in to a third-party Yggdrasil-compatible authentication server (the API root, e.g. for authlib-injector)
+/// The account is launched through authlib-injector, pointed at that server
+/// Returns a Credentials object, whose backend is { type: "yggdrasil", server, client_token }
+export async function authenticate_yggdrasil(serverUrl, username, password) {
+  return await invoke('plugin:auth|auth_authenticate_yggdrasil', { serverUrl, username, password })
+}
+
 /// Refresh some credentials using Hydra, if needed
 /// user is UUID
 /// update_name is bool
@@ -40,6 +54,13 @@ export async function refresh(user, update_name) {
   return await invoke('plugin:auth|auth_refresh', { user, update_name })
 }
 
+/// Refresh the tokens of every account about to expire
+/// This also runs in the background, accounts that fail get needs_reauth set on their Credentials
+/// Returns an Array of UUIDs of accounts that need to log in again
+export async function validate_accounts() {
+  return await invoke('plugin:auth|auth_validate_accounts')
+}
+
 /// Remove a user account from the database
 /// user is UUID
 export async function remove_user(user) {
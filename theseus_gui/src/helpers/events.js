@@ -38,6 +38,14 @@ import { listen } from '@tauri-apps/api/event'
         loader_uuid: unique identification of the loading bar
         fraction: number, (as a fraction of 1, how much we've loaded so far). If null, by convention, loading is finished
         message: message to display to the user
+        throughput: number, combined download speed of all downloads, in bytes per second
+        transfer: {
+          total_bytes: number, bytes the loading bar has to download
+          downloaded_bytes: number, bytes downloaded so far
+          current_file: optional, the last file that finished downloading
+          rate: number, download speed of this loading bar, in bytes per second
+          eta: optional, estimated seconds left
+        }, or null if the loading bar doesn't track downloaded bytes
     }
 */
 export async function loading_listener(callback) {
@@ -51,6 +59,7 @@ export async function loading_listener(callback) {
         pid: process ID
         event: event type ("Launched", "Finished")
         message: message to display to the user
+        code: short code of a known problem, to link to help for it (can be null)
     }
 */
 export async function process_listener(callback) {
@@ -64,7 +73,8 @@ export async function process_listener(callback) {
         name: name of the profile
         profile_path: relative path to profile (used for path identification)
         path: path to profile (used for opening the profile in the OS file explorer)
-        event: event type ("Created", "Added", "Edited", "Removed")
+        event: event type ("Created", "Added", "Loaded", "Edited", "Removed")
+        Loaded is sent for each profile as it is read while the launcher starts
     }
 */
 export async function profile_listener(callback) {
@@ -105,3 +115,77 @@ export async function offline_listener(callback) {
     return callback(event.payload)
   })
 }
+
+/// Payload for the 'screenshot' event, only sent while the profile is running
+/*
+  ScreenshotPayload {
+    profile_path_id: relative path to profile (used for path identification)
+    name: file name of the screenshot
+    path: full path to the screenshot
+    event: event type ("added", "removed")
+  }
+*/
+export async function screenshot_listener(callback) {
+  return await listen('screenshot', (event) => callback(event.payload))
+}
+
+/// Payload for the 'account' event
+/*
+  AccountPayload {
+    id: uuid of the Minecraft account
+    username: current name of the account
+    previous_username: name the account had before the update
+    event: event type ("updated", "needs_reauth", "needs_consent")
+    needs_consent means the account has to grant permissions again, see reconsent_begin_flow in auth.js
+  }
+*/
+export async function account_listener(callback) {
+  return await listen('account', (event) => callback(event.payload))
+}
+
+/// Payload for the 'ready' event, sent when a part of the state finishes loading in the background after startup
+/*
+  component: "network", "metadata", "tags", "projects" or "java"
+*/
+export async function ready_listener(callback) {
+  return await listen('ready', (event) => callback(event.payload))
+}
+
+/// Payload for the 'status' event, sent whenever the launcher status changes
+/*
+  LauncherStatus, as returned by get_status in status.js
+*/
+export async function status_listener(callback) {
+  return await listen('status', (event) => callback(event.payload))
+}
+
+/// Payload for the 'updates_available' event, sent when the background update checks find new versions
+/*
+  UpdatesAvailablePayload {
+    profiles: [{
+      profile_path_id: path of the profile,
+      name: name of the profile,
+      pack_version: version id of a newer modpack version (can be null),
+      projects: [{ project_path, title, version_id, version_number }],
+    }]
+  }
+*/
+export async function updates_available_listener(callback) {
+  return await listen('updates_available', (event) => callback(event.payload))
+}
+
+/// Payload for the 'sync' event, sent while a profile is synced with its cloud sync folder
+/*
+  SyncPayload {
+    profile_path_id: path of the profile being synced
+    event: {
+      type: "started", "progress", "conflict" or "finished"
+      done, total: files copied or deleted so far, out of those that changed (for "progress")
+      path: file that changed on both sides and was left as is (for "conflict")
+      uploaded, downloaded, conflicts: counts of the finished sync (for "finished")
+    }
+  }
+*/
+export async function sync_listener(callback) {
+  return await listen('sync', (event) => callback(event.payload))
+}
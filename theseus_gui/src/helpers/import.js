@@ -44,6 +44,18 @@ export async function import_instance(launcherType, basePath, instanceFolder) {
   })
 }
 
+/// Import a CurseForge server pack from a zip file or extracted folder
+/// eg: import_server_pack("My Server", "C:/Downloads/ServerFiles-1.0.zip")
+export async function import_server_pack(name, serverPack) {
+  // create a basic, empty instance (most properties will be filled in by the import process)
+  const profilePath = await create(name, '1.19.4', 'vanilla', 'latest', null)
+
+  return await invoke('plugin:import|import_import_server_pack', {
+    profilePath,
+    serverPack,
+  })
+}
+
 /// Checks if this instance is valid for importing, given a certain launcher type
 /// eg: is_valid_importable_instance("C:/MultiMC/Instance 1", "MultiMC")
 export async function is_valid_importable_instance(instanceFolder, launcherType) {
@@ -0,0 +1,26 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+JobInfo {
+    name: String,
+    schedule: { type: "every", seconds } or { type: "daily", time: "04:00:00" },
+    next_run: local time, RFC 3339
+    last_run: local time, RFC 3339 or null
+    running: bool,
+}
+*/
+
+// Lists the background jobs, soonest first
+export async function get_jobs() {
+  return await invoke('plugin:jobs|jobs_get_jobs')
+}
+
+// Runs a background job now, rather than at its next scheduled time
+export async function run_job(name) {
+  return await invoke('plugin:jobs|jobs_run_job', { name })
+}
@@ -77,3 +77,9 @@ export async function auto_install_java(javaVersion) {
 export async function get_max_memory() {
   return await invoke('plugin:jre|jre_get_max_memory')
 }
+
+// Get the recommended memory settings for a profile
+// Returns { maximum, minimum, auto } in MB
+export async function recommend_memory(path) {
+  return await invoke('plugin:jre|jre_recommend_memory', { path })
+}
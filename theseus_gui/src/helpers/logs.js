@@ -10,6 +10,7 @@ A log is a struct containing the datetime string, stdout, and stderr, as follows
 
 pub struct Logs {
     pub datetime_string:  String,
+    pub session_uuid: Option<Uuid>, uuid of the process that wrote the log (null for old logs)
     pub stdout: String,
     pub stderr: String,
 }
@@ -40,3 +41,47 @@ export async function delete_logs_by_datetime(profilePath, datetimeString) {
 export async function delete_logs(profilePath) {
   return await invoke('plugin:logs|logs_delete_logs', { profilePath })
 }
+
+/// Get the launches of a profile that have logs, as { session_uuid, datetime_string } sorted by datetime_string
+export async function get_sessions(profilePath) {
+  return await invoke('plugin:logs|logs_get_sessions', { profilePath })
+}
+
+/// Get a profile's log by the uuid of the process that wrote it
+export async function get_logs_by_session(profilePath, sessionUuid) {
+  return await invoke('plugin:logs|logs_get_logs_by_session', { profilePath, sessionUuid })
+}
+
+/// Delete a profile's log by the uuid of the process that wrote it
+export async function delete_logs_by_session(profilePath, sessionUuid) {
+  return await invoke('plugin:logs|logs_delete_logs_by_session', { profilePath, sessionUuid })
+}
+
+/// Get the crash reports of a profile as { file_name, output }, newest first, censored for sharing
+export async function get_crash_reports(profilePath) {
+  return await invoke('plugin:logs|logs_get_crash_reports', { profilePath })
+}
+
+/// Get the logs the game wrote itself, such as 'latest.log' and rotated '2024-01-15-1.log.gz'
+/// as { file_name, compressed, size, modified }, newest first
+export async function get_game_log_files(profilePath) {
+  return await invoke('plugin:logs|logs_get_game_log_files', { profilePath })
+}
+
+/// Get a log the game wrote by its file name, decompressed and censored
+export async function get_game_log(profilePath, fileName) {
+  return await invoke('plugin:logs|logs_get_game_log', { profilePath, fileName })
+}
+
+/// Get the launcher's and the game's logs merged into one timeline, oldest first, as
+/// { timestamp, source, origin, thread, level, message }. source is 'launcher' or 'game'
+/// since is an optional local time such as '2024-01-15T12:00:00' to leave out earlier lines
+export async function get_merged_logs(profilePath, since) {
+  return await invoke('plugin:logs|logs_get_merged_logs', { profilePath, since })
+}
+
+/// Share a log or crash report by its path in the profile folder, such as 'crash-reports/crash-<date>-client.txt'
+/// It is censored and uploaded to mclo.gs, or the paste service of the settings, returning its URL
+export async function upload_log(profilePath, path) {
+  return await invoke('plugin:logs|logs_upload', { profilePath, path })
+}
@@ -23,3 +23,15 @@ export async function get_forge_versions() {
 export async function get_quilt_versions() {
   return await invoke('plugin:metadata|metadata_get_quilt_versions')
 }
+
+// Gets the neoforge versions from daedalus
+// Returns Manifest
+export async function get_neoforge_versions() {
+  return await invoke('plugin:metadata|metadata_get_neoforge_versions')
+}
+
+// Downloads a game version, such as an upcoming snapshot, in the background, so that creating a
+// profile for it later is quick. Returns once the download started
+export async function prefetch_version(versionId) {
+  return await invoke('plugin:metadata|metadata_prefetch_version', { versionId })
+}
@@ -43,3 +43,10 @@ export async function install_from_file(path) {
   )
   return await invoke('plugin:pack|pack_install', { location, profile })
 }
+
+// Lists the files added, removed, modified or disabled in a managed pack
+// since it was installed, which updating or repairing it would undo
+// Returns { added, removed, modified, disabled }, paths relative to the profile
+export async function drift_report(profile) {
+  return await invoke('plugin:pack|pack_drift_report', { profile })
+}
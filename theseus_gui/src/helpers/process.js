@@ -57,3 +57,33 @@ export async function get_output_by_uuid(uuid) {
 export async function kill_by_uuid(uuid) {
   return await invoke('plugin:process|process_kill_by_uuid', { uuid })
 }
+
+/// Gets every process launched since the launcher started, newest first
+/// Returns [ProcessInfo]
+/*
+ProcessInfo {
+    uuid: Uuid,
+    profile_path: ProfilePathId,
+    profile_name: String (can be null),
+    pid: u32 (can be null),
+    started: DateTime,
+    running: bool,
+    uptime: i64 seconds (null once exited),
+    exit_code: i32 (can be null),
+    usage: { memory: u64 bytes, cpu_usage: f32 percent of one core } (null once exited),
+}
+*/
+export async function get_all_info() {
+  return await invoke('plugin:process|process_get_all_info')
+}
+
+/// Asks a process to exit by UUID, letting the game save, unlike kill_by_uuid
+export async function terminate_by_uuid(uuid) {
+  return await invoke('plugin:process|process_terminate_by_uuid', { uuid })
+}
+
+/// Launches the profile of a process again with the same account, stopping it first if running
+/// Returns the UUID of the new process
+export async function restart_by_uuid(uuid) {
+  return await invoke('plugin:process|process_restart_by_uuid', { uuid })
+}
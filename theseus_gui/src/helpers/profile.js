@@ -26,6 +26,18 @@ export async function create(name, gameVersion, modloader, loaderVersion, icon)
   })
 }
 
+// Fetch the profile definition at a URL: a JSON file with a game version, loader,
+// Modrinth projects and suggested settings
+export async function get_definition(url) {
+  return await invoke('plugin:profile_create|profile_get_definition', { url })
+}
+
+// Create a profile from the profile definition at a URL, installing its projects with
+// their dependencies. Returns the path of the new profile
+export async function install_from_definition(url) {
+  return await invoke('plugin:profile_create|profile_install_from_definition', { url })
+}
+
 // Remove a profile
 export async function remove(path) {
   return await invoke('plugin:profile|profile_remove', { path })
@@ -49,6 +61,12 @@ export async function get_optimal_jre_key(path) {
   return await invoke('plugin:profile|profile_get_optimal_jre_key', { path })
 }
 
+// Get the JVM arguments a pathed profile launches with, merged from those its pack recommends
+// and those the user set. Each is { flag, value, source }, source being 'pack' or 'user'
+export async function get_java_args(path) {
+  return await invoke('plugin:profile|profile_get_java_args', { path })
+}
+
 // Get a copy of the profile set
 // Returns hashmap of path -> Profile
 export async function list(clearProjects) {
@@ -64,6 +82,13 @@ export async function install(path) {
   return await invoke('plugin:profile|profile_install', { path })
 }
 
+// Verifies a profile's client jar, libraries, natives and assets, downloading
+// missing or corrupt files again and rerunning loader processors
+// Returns the paths of the files that were corrupt
+export async function repair(path) {
+  return await invoke('plugin:profile|profile_repair', { path })
+}
+
 // Updates all of a profile's projects
 export async function update_all(path) {
   return await invoke('plugin:profile|profile_update_all', { path })
@@ -80,6 +105,314 @@ export async function add_project_from_version(path, versionId) {
   return await invoke('plugin:profile|profile_add_project_from_version', { path, versionId })
 }
 
+// Add a project to a profile from a version, resolving its dependencies
+// Returns a report of every project added and why
+export async function add_project_with_dependencies(path, versionId, includeOptional) {
+  return await invoke('plugin:profile|profile_add_project_with_dependencies', {
+    path,
+    versionId,
+    includeOptional,
+  })
+}
+
+// Check the enabled mods of a profile for conflicts
+// Returns a report listing duplicated mod ids, loader mismatches and conflicts
+export async function check_compatibility(path) {
+  return await invoke('plugin:profile|profile_check_compatibility', { path })
+}
+
+// Get the playtime statistics of a profile
+// Returns { total_playtime, last_week_playtime, launches, crashes, last_played }
+// with playtimes in seconds
+export async function get_statistics(path) {
+  return await invoke('plugin:profile|profile_get_statistics', { path })
+}
+
+// Export the list of all profiles, with versions, loaders, mod counts, sizes and play stats
+// format is 'json' or 'csv', returns the exported text
+export async function export_inventory(format) {
+  return await invoke('plugin:profile|profile_export_inventory', { format })
+}
+
+// Get the bytes a profile takes up as { mods, saves, resourcepacks, shaderpacks, logs, screenshots,
+// config, game_files, other, total, computed_at }. Results are reused for a few minutes unless refresh is set
+export async function get_disk_usage(path, refresh) {
+  return await invoke('plugin:profile|profile_get_disk_usage', { path, refresh })
+}
+
+// Get the Fabric or Quilt loader versions available for a development profile
+export async function get_development_loader_versions(gameVersion, loader) {
+  return await invoke('plugin:profile|profile_get_development_loader_versions', {
+    gameVersion,
+    loader,
+  })
+}
+
+// Set or clear the development settings of a profile
+// development: {
+//   mods_folder, additional_mods, remap_classpath_file,
+//   skip_assets, // launch with an empty asset index, skipping asset downloads
+// } or null
+// loaderVersion, if set, pins the profile to that loader version
+export async function set_development(path, development, loaderVersion) {
+  return await invoke('plugin:profile|profile_set_development', {
+    path,
+    development,
+    loaderVersion,
+  })
+}
+
+// Sets or clears how a pathed profile's game is started
+// overrides is {
+//   main_class, // started instead of the version's main class, or null
+//   removed_game_args, // flags left out, such as '--demo' or '--quickPlayPath'
+// } or null
+export async function set_launch_overrides(path, overrides) {
+  return await invoke('plugin:profile|profile_set_launch_overrides', { path, overrides })
+}
+
+// Opts a profile in to syncing with the sync folder of the settings, or out if null
+// sync is {
+//   remote_name, // folder inside the sync folder, the same on every computer
+//   folders, // synced folders of the profile, such as ['saves', 'config', 'screenshots']
+// } or null
+export async function set_cloud_sync(path, sync) {
+  return await invoke('plugin:profile|profile_set_cloud_sync', { path, sync })
+}
+
+// Syncs a profile with the sync folder both ways, also done before launch and after the
+// game exits. Resolves to the files uploaded, downloaded, and those in conflict
+export async function cloud_sync(path) {
+  return await invoke('plugin:profile|profile_cloud_sync', { path })
+}
+
+// Resolves a sync conflict on a file by keeping it as it is on one side, 'local' or 'remote'
+export async function resolve_sync_conflict(path, file, keep) {
+  return await invoke('plugin:profile|profile_resolve_sync_conflict', { path, file, keep })
+}
+
+// Gets the lockfile of the exact Modrinth versions installed in a profile, or null
+// { game_version, loader, loader_version, projects: { [path]: LockedProject } }
+// LockedProject is { project_id, version_id, version_number, file_name, sha512, disabled }
+export async function get_lockfile(path) {
+  return await invoke('plugin:profile|profile_get_lockfile', { path })
+}
+
+// Lists the version changes installing from the lockfile would make to a profile
+// Each is { project_id, from: LockedProject or null, to: LockedProject or null }
+export async function diff_lockfile(path) {
+  return await invoke('plugin:profile|profile_diff_lockfile', { path })
+}
+
+// Lists the version changes updating every project would make, to review before updating
+export async function preview_updates(path) {
+  return await invoke('plugin:profile|profile_preview_updates', { path })
+}
+
+// Installs the exact projects of the lockfile of a profile, removing Modrinth projects it
+// doesn't list. Resolves to the version changes made
+export async function install_from_lockfile(path) {
+  return await invoke('plugin:profile|profile_install_from_lockfile', { path })
+}
+
+// Get the LWJGL versions from Maven Central a profile can be launched with, newest first
+// Empty for game versions shipping LWJGL 2
+export async function get_lwjgl_versions(path) {
+  return await invoke('plugin:profile|profile_get_lwjgl_versions', { path })
+}
+
+// Get what changed in the updates available to a profile, the modpack's first
+// Each is { project_path (null for the modpack), project_id, title, current_version, update_version, versions }
+// with versions newest first, as { version_id, version_number, name, date_published, changelog (markdown), changelog_url }
+export async function get_update_changelogs(path) {
+  return await invoke('plugin:profile|profile_get_update_changelogs', { path })
+}
+
+// Get the groups profiles are sorted into, as { name, profiles (count) }
+export async function get_groups() {
+  return await invoke('plugin:profile|profile_get_groups')
+}
+
+export async function create_group(name) {
+  return await invoke('plugin:profile|profile_create_group', { name })
+}
+
+// Renaming a group to an existing one merges them
+export async function rename_group(name, newName) {
+  return await invoke('plugin:profile|profile_rename_group', { name, newName })
+}
+
+// Delete a group, its profiles are kept
+export async function delete_group(name) {
+  return await invoke('plugin:profile|profile_delete_group', { name })
+}
+
+export async function set_group_order(groups) {
+  return await invoke('plugin:profile|profile_set_group_order', { groups })
+}
+
+// Set the groups a profile is in, creating any that don't exist
+export async function set_groups(path, groups) {
+  return await invoke('plugin:profile|profile_set_groups', { path, groups })
+}
+
+export async function set_tags(path, tags) {
+  return await invoke('plugin:profile|profile_set_tags', { path, tags })
+}
+
+// Get every tag used on a profile, sorted
+export async function get_tags() {
+  return await invoke('plugin:profile|profile_get_tags')
+}
+
+// Move profiles into the order they are listed in, the rest go after them
+export async function set_order(paths) {
+  return await invoke('plugin:profile|profile_set_order', { paths })
+}
+
+// List profiles in the order of the library, without their projects
+// filter is { group, tag, ungrouped }, with every field optional
+export async function list_library(filter) {
+  return await invoke('plugin:profile|profile_list_library', { filter: filter ?? {} })
+}
+
+// Override the LWJGL version of a profile, or go back to the one of the game with null
+// The new libraries are downloaded on next launch
+export async function set_lwjgl_version(path, version) {
+  return await invoke('plugin:profile|profile_set_lwjgl_version', { path, version })
+}
+
+// Override the LWJGL version of a profile on Java runtimes of one architecture,
+// "x86_64", "x86", "arm64" or "arm", or clear it with null
+// It wins over the version set with set_lwjgl_version
+export async function set_lwjgl_arch_version(path, javaArch, version) {
+  return await invoke('plugin:profile|profile_set_lwjgl_arch_version', {
+    path,
+    javaArch,
+    version,
+  })
+}
+
+// Switch a profile to another loader and reinstall it
+// loader: 'vanilla', 'forge', 'neoforge', 'fabric' or 'quilt'
+// loaderVersion: a version id, 'stable', 'latest' or null for the latest
+// Returns the project paths of mods built for a different loader
+export async function change_loader(path, loader, loaderVersion) {
+  return await invoke('plugin:profile|profile_change_loader', {
+    path,
+    loader,
+    loaderVersion,
+  })
+}
+
+// Compress the contents of a profile into a single archive to save disk space
+// The profile stays listed with `archived` set, but can't be launched
+export async function archive(path) {
+  return await invoke('plugin:profile|profile_archive', { path })
+}
+
+// Restore the contents of an archived profile
+export async function unarchive(path) {
+  return await invoke('plugin:profile|profile_unarchive', { path })
+}
+
+// Link a profile to a Modrinth collection and install its projects
+// Returns { added, removed, unavailable, failed }, where unavailable lists the project ids
+// without a version for the profile's game version and loader, and failed maps the ids
+// of projects that could not be added or removed to the error
+export async function link_collection(path, collectionId) {
+  return await invoke('plugin:profile|profile_link_collection', { path, collectionId })
+}
+
+// Unlink a profile from its collection, keeping the projects it installed
+export async function unlink_collection(path) {
+  return await invoke('plugin:profile|profile_unlink_collection', { path })
+}
+
+// Add and remove projects to match the profile's linked collection
+export async function sync_collection(path) {
+  return await invoke('plugin:profile|profile_sync_collection', { path })
+}
+
+// Mark a Modrinth project as critical, so it is updated before every launch
+// versionId pins an exact version (can be null for the newest compatible one)
+// Launching fails if a critical project can't be installed
+export async function set_critical_project(path, projectId, versionId) {
+  return await invoke('plugin:profile|profile_set_critical_project', {
+    path,
+    projectId,
+    versionId,
+  })
+}
+
+// Stop treating a project as critical, leaving it installed
+export async function remove_critical_project(path, projectId) {
+  return await invoke('plugin:profile|profile_remove_critical_project', { path, projectId })
+}
+
+// Install the required versions of a profile's critical projects
+// Returns the paths of the projects that changed
+export async function update_critical_projects(path) {
+  return await invoke('plugin:profile|profile_update_critical_projects', { path })
+}
+
+// Set the server a profile is made for, or clear it with null
+// server is { address, resource_pack: { url, sha1 } (optional), prefetch_resource_pack }
+// With prefetch_resource_pack, the server's resource pack is downloaded before every launch
+export async function set_primary_server(path, server) {
+  return await invoke('plugin:profile|profile_set_primary_server', { path, server })
+}
+
+// Set whether reinstalling a profile fails when its loader libraries or processor outputs
+// differ from the hashes recorded at install time, which are in profile.loader_pins
+export async function set_reproducible_install(path, reproducible) {
+  return await invoke('plugin:profile|profile_set_reproducible_install', { path, reproducible })
+}
+
+// Read the resource pack of a profile's primary server from its server.properties
+// source is a file path or URL, returns { url, sha1 } or null if it has none
+export async function load_server_properties(path, source) {
+  return await invoke('plugin:profile|profile_load_server_properties', { path, source })
+}
+
+// Lists the files in the config folder of a profile
+// Returns [{ path, format, size }], format being toml, json, properties, yaml or text
+export async function list_config_files(path) {
+  return await invoke('plugin:profile|profile_list_config_files', { path })
+}
+
+// Reads a file of the config folder, file being its path inside the folder
+// Returns { format, value }, value being a string for text files
+export async function read_config_file(path, file) {
+  return await invoke('plugin:profile|profile_read_config_file', { path, file })
+}
+
+// Writes a value to a file of the config folder, in the file's format
+export async function write_config_file(path, file, value) {
+  return await invoke('plugin:profile|profile_write_config_file', { path, file, value })
+}
+
+// Gets the game's settings from options.txt, as { key: value }
+export async function get_game_options(path) {
+  return await invoke('plugin:profile|profile_get_game_options', { path })
+}
+
+// Sets some of the game's settings in options.txt, leaving the others as they are
+export async function set_game_options(path, options) {
+  return await invoke('plugin:profile|profile_set_game_options', { path, options })
+}
+
+// Gets the game's settings from options.txt, with booleans, numbers and lists parsed
+export async function get_typed_game_options(path) {
+  return await invoke('plugin:profile|profile_get_typed_game_options', { path })
+}
+
+// Sets or clears the settings a profile forces into options.txt on every launch
+// overrides: { options: { key: value }, restore_after_exit: bool }, null to clear
+export async function set_game_option_overrides(path, overrides) {
+  return await invoke('plugin:profile|profile_set_game_option_overrides', { path, overrides })
+}
+
 // Add a project to a profile from a path + project_type
 // Returns a path to the new project file
 export async function add_project_from_path(path, projectPath, projectType) {
@@ -95,6 +428,13 @@ export async function toggle_disable_project(path, projectPath) {
   return await invoke('plugin:profile|profile_toggle_disable_project', { path, projectPath })
 }
 
+// Toggle a project, cascading to the mods that require it (when disabling)
+// or that it requires (when enabling) if cascade is set
+// Returns { toggled, unmet }, unmet listing the mods left with unmet requirements
+export async function toggle_project(path, projectPath, cascade) {
+  return await invoke('plugin:profile|profile_toggle_project', { path, projectPath, cascade })
+}
+
 // Remove a project
 export async function remove_project(path, projectPath) {
   return await invoke('plugin:profile|profile_remove_project', { path, projectPath })
@@ -115,6 +455,27 @@ export async function is_managed_modrinth(path) {
   return await invoke('plugin:profile|profile_is_managed_modrinth', { path })
 }
 
+// Lock a managed Modrinth profile to the projects its pack installed
+// Projects added or removed by hand are listed in metadata.linked_data.modifications,
+// and restored by the next update of the pack
+export async function set_managed_modrinth_locked(path, locked) {
+  return await invoke('plugin:profile|profile_set_managed_modrinth_locked', { path, locked })
+}
+
+// Set the versions a managed Modrinth profile is offered updates to
+// updateChannel is "release", "beta" or "alpha", each including the more stable ones
+export async function set_managed_modrinth_update_channel(path, updateChannel) {
+  return await invoke('plugin:profile|profile_set_managed_modrinth_update_channel', {
+    path,
+    updateChannel,
+  })
+}
+
+// Convert a managed Modrinth profile to a regular one, keeping its files
+export async function unlock(path) {
+  return await invoke('plugin:profile|profile_unlock', { path })
+}
+
 // Export a profile to .mrpack
 /// included_overrides is an array of paths to override folders to include (ie: 'mods', 'resource_packs')
 // Version id is optional (ie: 1.1.5)
@@ -127,6 +488,19 @@ export async function export_profile_mrpack(path, exportLocation, includedOverri
   })
 }
 
+// Zip a whole profile, to be imported on another machine
+// options: { exclude_saves, exclude_logs, exclude_modrinth_files }, files from Modrinth
+// that are left out are downloaded again on import
+export async function export_full(path, exportLocation, options) {
+  return await invoke('plugin:profile|profile_export_full', { path, exportLocation, options })
+}
+
+// Recreate a profile from a zip made by export_full
+// Returns the path of the new profile
+export async function import_full(zipPath) {
+  return await invoke('plugin:profile|profile_import_full', { zipPath })
+}
+
 // Given a folder path, populate an array of all the subfolders
 // Intended to be used for finding potential override folders
 // profile
@@ -139,12 +513,32 @@ export async function get_potential_override_folders(profilePath) {
   return await invoke('plugin:profile|profile_get_potential_override_folders', { profilePath })
 }
 
+// Installs and downloads everything a pathed profile needs to launch, so that
+// running it afterwards starts the game right away
+export async function prepare(path) {
+  return await invoke('plugin:profile|profile_prepare', { path })
+}
+
+// Launches a pathed profile unattended to check that the game finishes
+// loading, then stops it. Resolves to the outcome and, on failure, the crash
+// report and last lines of output. Headless tests run the game on a virtual display
+export async function test_launch(path, timeoutSecs, headless = false) {
+  return await invoke('plugin:profile|profile_test_launch', { path, timeoutSecs, headless })
+}
+
 // Run Minecraft using a pathed profile
 // Returns PID of child
 export async function run(path) {
   return await invoke('plugin:profile|profile_run', { path })
 }
 
+// Run Minecraft using a pathed profile with all of its mods disabled, to check
+// whether they cause a crash. The mods are restored once the game exits
+// Returns PID of child
+export async function launch_safe_mode(path) {
+  return await invoke('plugin:profile|profile_launch_safe_mode', { path })
+}
+
 // Run Minecraft using a pathed profile
 // Waits for end
 export async function run_wait(path) {
@@ -0,0 +1,53 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+import { showInFolder } from '@/helpers/utils'
+
+/*
+A screenshot is a struct containing the file name, full path, and image metadata, as follows:
+
+pub struct Screenshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub captured: DateTime<Utc>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+*/
+
+/// Get all screenshots of a profile, sorted newest first
+export async function get_screenshots(profilePath) {
+  return await invoke('plugin:screenshots|screenshots_get_screenshots', { profilePath })
+}
+
+/// Get a profile's screenshot by file name
+export async function get_screenshot(profilePath, name) {
+  return await invoke('plugin:screenshots|screenshots_get_screenshot', { profilePath, name })
+}
+
+/// Delete a profile's screenshot by file name
+export async function delete_screenshot(profilePath, name) {
+  return await invoke('plugin:screenshots|screenshots_delete_screenshot', { profilePath, name })
+}
+
+/// Copy a profile's screenshot to the clipboard, as a PNG image
+export async function copy_screenshot_to_clipboard(profilePath, name) {
+  const data = await invoke('plugin:screenshots|screenshots_get_screenshot_data', {
+    profilePath,
+    name,
+  })
+  const blob = new Blob([new Uint8Array(data)], { type: 'image/png' })
+  return await navigator.clipboard.write([new ClipboardItem({ 'image/png': blob })])
+}
+
+/// Open a profile's screenshots folder in the OS file explorer
+export async function show_screenshots_in_folder(profilePath) {
+  const path = await invoke('plugin:screenshots|screenshots_get_screenshots_dir', {
+    profilePath,
+  })
+  return await showInFolder(path)
+}
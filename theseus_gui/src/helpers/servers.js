@@ -0,0 +1,67 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+A server is identified by its index in the profile's server list:
+
+pub struct Server {
+    pub name: String,
+    pub address: String,
+    pub icon: Option<String>, base64 PNG
+    pub accept_resource_pack: Option<bool>, null to ask when joining
+    pub hidden: bool,
+}
+
+pub struct ServerStatus {
+    pub version: Option<String>,
+    pub protocol: Option<i32>,
+    pub players_online: Option<i64>,
+    pub players_max: Option<i64>,
+    pub motd: String,
+    pub favicon: Option<String>, data URL
+    pub latency: u64, ms
+}
+*/
+
+/// Get the multiplayer servers of a profile, in the order the game lists them
+export async function get_servers(profilePath) {
+  return await invoke('plugin:servers|servers_get_servers', { profilePath })
+}
+
+/// Add a server to a profile, at the end unless an index is given
+export async function add_server(profilePath, server, index) {
+  return await invoke('plugin:servers|servers_add_server', { profilePath, server, index })
+}
+
+/// Replace the server at an index
+export async function edit_server(profilePath, index, server) {
+  return await invoke('plugin:servers|servers_edit_server', { profilePath, index, server })
+}
+
+/// Remove the server at an index
+export async function remove_server(profilePath, index) {
+  return await invoke('plugin:servers|servers_remove_server', { profilePath, index })
+}
+
+/// Move a server to another index
+export async function move_server(profilePath, from, to) {
+  return await invoke('plugin:servers|servers_move_server', { profilePath, from, to })
+}
+
+/// Accept (true) or decline (false) the resource pack of a server, or ask when joining (null)
+export async function set_resource_pack_acceptance(profilePath, index, accept) {
+  return await invoke('plugin:servers|servers_set_resource_pack_acceptance', {
+    profilePath,
+    index,
+    accept,
+  })
+}
+
+/// Ping a server for its status, an error if it is offline
+export async function ping_server(address) {
+  return await invoke('plugin:servers|servers_ping_server', { address })
+}
@@ -13,14 +13,44 @@ Settings {
     "game_resolution": [int int],
     "custom_java_args": [String ...],
     "custom_env_args" : [(string, string) ... ]>,
+      Both can use ${PROFILE_DIR}, ${PROFILE_NAME}, ${MC_VERSION}, ${LOADER}, ${LOADER_VERSION},
+      ${NATIVES_DIR}, ${LIBRARIES_DIR}, ${ASSETS_DIR} and ${JAVA_PATH}, as can the profile's own
+      extra_arguments and custom_env_args
     "java_globals": Hash of (string, Path),
     "default_user": Uuid string (can be null),
     "hooks": Hooks,
     "max_concurrent_downloads": uint,
+    "max_download_speed": uint, KB/s (can be null),
+    "max_download_speed_while_running": uint, KB/s (can be null),
+    "downloads_while_running": { pause_installs: bool, max_concurrent_downloads: uint (can be null) },
+      pack installs and updates wait for running games to exit if pause_installs is set,
+    "log_retention": { compress_after_days, max_count, max_size (MB) }, each can be null,
+    "update_checks": { enabled: bool, interval_hours: u32, skip_on_metered: bool },
+    "loader_cache_cleanup": { enabled: bool, extra_folders: [path relative to the profile] },
+      loader caches such as .fabric and .mixin.out are removed when a profile's loader or loader version changes,
+    "log_censoring": { ip_addresses: bool, email_addresses: bool, session_ids: bool, custom_patterns: [{ pattern: regex, replacement (can be null) }] },
+    "hide_launch_identifiers": bool, whether the game is launched without the --xuid and --clientId it sends to Mojang's telemetry,
+    "paste_service_url": string, API logs are shared to, mclo.gs if null,
+    "logging": { level: LogLevel, targets: { [module or crate]: LogLevel } }, ignored on startup if RUST_LOG is set,
     "version": u32,
     "collapsed_navigation": bool,
+    "post_launch": "keep", "minimize", "minimize_to_tray" or "quit_and_relaunch",
+    "check_gpu_drivers": bool, warns before launching with a GPU driver known to crash the game,
+    "http": { connect_timeout (s), request_timeout (s, can be null), max_idle_connections, proxy: ProxySettings (can be null) },
+    "sync_folder": Path, profiles opted in to syncing are mirrored into it, can be null,
 }
 
+ProxySettings {
+    "protocol": "http", "https" or "socks5",
+    "host": String,
+    "port": u16,
+    "username": String (can be null),
+    "password": String (can be null),
+    "no_proxy": [String ...], hosts connected to directly, as in NO_PROXY,
+}
+
+LogLevel is "off", "error", "warn", "info", "debug" or "trace"
+
 Memorysettings {
     "min": u32, can be null,
     "max": u32,
@@ -38,8 +68,126 @@ export async function set(settings) {
   return await invoke('plugin:settings|settings_set', { settings })
 }
 
+// Set the verbosity of the launcher's logs, applied without a restart
+export async function set_logging(logging) {
+  return await invoke('plugin:settings|settings_set_logging', { logging })
+}
+
+// Record a debug log of the next install, modpack install or modpack update,
+// going back to the logging settings when it ends
+/*
+DebugCapture {
+    "armed": bool, whether the next operation will be captured,
+    "operation": String (can be null),
+    "started": DateTime (can be null),
+    "finished": DateTime (can be null while the operation runs),
+    "succeeded": bool (can be null),
+    "log_file": Path of the launcher log holding it (null in development builds),
+}
+*/
+export async function capture_debug_log() {
+  return await invoke('plugin:settings|settings_capture_debug_log')
+}
+
+export async function get_debug_capture() {
+  return await invoke('plugin:settings|settings_get_debug_capture')
+}
+
+// Lists the launcher's folders it can't write to, which it also warns
+// about at startup with the "permission_denied" code
+/*
+WriteAccessIssue {
+    "path": Path,
+    "cause": "read_only_mount", "cloud_sync", "controlled_folder_access", "file_locked" or "permissions" (can be null),
+    "message": String,
+    "relocatable": bool, whether change_config_dir fixes it,
+}
+*/
+export async function check_write_access() {
+  return await invoke('plugin:settings|settings_check_write_access')
+}
+
+// Suggests a writable folder to move the config dir to (can be null)
+export async function suggest_config_dir() {
+  return await invoke('plugin:settings|settings_suggest_config_dir')
+}
+
 // Changes the config dir
 // Seizes the entire application state until its done
 export async function change_config_dir(newConfigDir) {
   return await invoke('plugin:settings|settings_change_config_dir', { newConfigDir })
 }
+
+// Gets what the launcher should do around a game's lifetime
+/*
+PostLaunchPolicy {
+    "behavior": post_launch setting,
+    "on_launch": "none", "minimize", "hide", "close" or "restore",
+    "on_exit": same as on_launch,
+    "wait_for_exit": bool,
+}
+*/
+export async function get_post_launch_policy() {
+  return await invoke('plugin:settings|settings_get_post_launch_policy')
+}
+
+// Gets the content policy installs are held to on managed computers, or null
+// { blocked_projects: [id or slug], blocked_categories: [category or project type],
+//   allowed_projects: [id or slug] or null }
+// Installs of blocked content fail with an error listing it
+export async function get_content_policy() {
+  return await invoke('plugin:settings|settings_get_content_policy')
+}
+
+// Gets request counters of the launcher's HTTP client
+/*
+ConnectionMetrics {
+    "requests": u64,
+    "active": uint,
+    "retries": u64,
+    "failed": u64,
+    "throughput": u64, bytes/s,
+}
+*/
+export async function get_connection_metrics() {
+  return await invoke('plugin:settings|settings_get_connection_metrics')
+}
+
+// Gets whether downloads are giving way to running games
+/*
+DownloadStatus {
+    "games_running": uint,
+    "installs_paused": bool,
+    "concurrency_cap": uint, downloads at a time (can be null),
+    "speed_cap": u64, bytes/s (can be null),
+    "manual": "auto", "paused" or "resumed",
+}
+*/
+export async function get_download_status() {
+  return await invoke('plugin:settings|settings_get_download_status')
+}
+
+// Gets how long each download host took to answer on average, in milliseconds, by host name
+// Downloads are tried from the fastest of a file's mirrors first
+export async function get_mirror_latencies() {
+  return await invoke('plugin:settings|settings_get_mirror_latencies')
+}
+
+// Gets the BMCLAPI mirror, to add to settings.http.mirrors
+/*
+  DownloadMirror {
+    "name": "BMCLAPI",
+    "disabled": false,
+    "prefixes": [{ "original": "https://libraries.minecraft.net/", "mirror": "https://bmclapi2.bangbang93.com/maven/" }, ...],
+  }
+  A custom meta server is a mirror with the prefix "https://meta.modrinth.com/"
+*/
+export async function get_bmclapi_mirror() {
+  return await invoke('plugin:settings|settings_bmclapi_mirror')
+}
+
+// Pauses ("paused") or resumes ("resumed") installs by hand, or follows the settings again ("auto")
+// Resuming lasts until the last running game exits
+export async function set_download_override(manual) {
+  return await invoke('plugin:settings|settings_set_download_override', { manual })
+}
@@ -0,0 +1,56 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+Content sources are registries projects can be installed from, such as 'modrinth',
+or 'url' for files downloaded directly (the URL is used as the project id).
+
+A version found through a source is as follows:
+
+pub struct ContentVersion {
+    pub source: String,
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub files: Vec<ContentFile>, // { url, filename, sha1, size, primary }
+}
+*/
+
+// Get the ids of every registered content source
+export async function get_sources() {
+  return await invoke('plugin:sources|sources_get_sources')
+}
+
+// Search a source for projects
+// query: { query, project_type, filter: { game_version, loader }, limit }
+export async function search(source, query) {
+  return await invoke('plugin:sources|sources_search', { source, query })
+}
+
+// Get the versions of a project, only the ones for a profile if profilePath is set
+export async function get_versions(source, projectId, profilePath) {
+  return await invoke('plugin:sources|sources_get_versions', {
+    source,
+    projectId,
+    profilePath,
+  })
+}
+
+// Download a version through its source and add it to a profile
+// Returns the path of the new project
+export async function install_version(profilePath, version) {
+  return await invoke('plugin:sources|sources_install_version', { profilePath, version })
+}
+
+// Check a source for updates to the projects of a profile
+// Returns a map of project path to the newest version
+export async function check_updates(profilePath, source) {
+  return await invoke('plugin:sources|sources_check_updates', { profilePath, source })
+}
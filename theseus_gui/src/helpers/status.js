@@ -0,0 +1,22 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+LauncherStatus {
+    offline: bool,
+    running_instances: number,
+    downloads: { active, fraction (0 to 1) or null, throughput (bytes per second) },
+    pending_updates: { packs, projects },
+    accounts: { total, needs_reauth },
+}
+*/
+
+// Gets the status of the launcher, cheap enough to poll
+// The 'status' event sends the same value whenever it changes
+export async function get_status() {
+  return await invoke('plugin:status|status_get')
+}
@@ -0,0 +1,26 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+LibraryEntry {
+    project: Modrinth project,
+    sources: ["followed" | "owned" | "installed"],
+    installed_in: [profile paths],
+    target: "new_profile" (modpacks) | "profile",
+}
+*/
+
+// Lists the projects the logged in Modrinth user follows, owns or has installed, by title
+export async function get_library() {
+  return await invoke('plugin:user|user_library')
+}
+
+// Installs a project of the library, into the given profile or as a new profile for modpacks
+// Returns the path of the profile installed to
+export async function install_from_library(projectId, profile) {
+  return await invoke('plugin:user|user_library_install', { projectId, profile })
+}
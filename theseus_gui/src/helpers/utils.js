@@ -16,6 +16,17 @@ export async function getOS() {
   return await invoke('plugin:utils|get_os')
 }
 
+// Graphics cards of the system, as { name, vendor, discrete }
+// Empty on macOS, which switches between cards on its own
+export async function getGpus() {
+  return await invoke('plugin:utils|get_gpus')
+}
+
+// Display server of the session: 'x11', 'wayland' or 'unknown'
+export async function getSessionType() {
+  return await invoke('plugin:utils|get_session_type')
+}
+
 export async function showInFolder(path) {
   return await invoke('plugin:utils|show_in_folder', { path })
 }
@@ -91,3 +102,9 @@ export const refreshOffline = async () => {
 export const isOffline = async () => {
   return await invoke('plugin:utils|is_offline', {})
 }
+
+// Parts of the state loaded in the background since startup
+// ("network", "metadata", "tags", "projects", "java"), see ready_listener in events.js
+export const readyComponents = async () => {
+  return await invoke('plugin:utils|ready_components', {})
+}
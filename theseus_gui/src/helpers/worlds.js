@@ -0,0 +1,64 @@
+/**
+ * All theseus API calls return serialized values (both return values and errors);
+ * So, for example, addDefaultInstance creates a blank Profile object, where the Rust struct is serialized,
+ *  and deserialized into a usable JS object.
+ */
+import { invoke } from '@tauri-apps/api/tauri'
+
+/*
+A world is identified by its folder name within the profile's saves folder:
+
+pub struct World {
+    pub name: String,
+    pub path: PathBuf,
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+A datapack is identified by its file or folder name within the world's datapacks folder:
+
+pub struct Datapack {
+    pub name: String,
+    pub path: PathBuf,
+    pub disabled: bool,
+    pub folder: bool,
+    pub sha512: Option<String>,
+}
+*/
+
+/// Get all worlds of a profile, most recently played first
+export async function get_worlds(profilePath) {
+  return await invoke('plugin:worlds|worlds_get_worlds', { profilePath })
+}
+
+/// Get all datapacks of a world
+export async function get_datapacks(profilePath, world) {
+  return await invoke('plugin:worlds|worlds_get_datapacks', { profilePath, world })
+}
+
+/// Install a Modrinth datapack version into a world
+/// Returns the file name of the installed datapack
+export async function install_datapack(profilePath, world, versionId) {
+  return await invoke('plugin:worlds|worlds_install_datapack', { profilePath, world, versionId })
+}
+
+/// Enable or disable a datapack of a world
+/// Returns the new file name of the datapack
+export async function toggle_disable_datapack(profilePath, world, name) {
+  return await invoke('plugin:worlds|worlds_toggle_disable_datapack', { profilePath, world, name })
+}
+
+/// Delete a datapack from a world
+export async function remove_datapack(profilePath, world, name) {
+  return await invoke('plugin:worlds|worlds_remove_datapack', { profilePath, world, name })
+}
+
+/// Get newer versions of the datapacks of a world, as a map of datapack name to Modrinth version
+export async function check_datapack_updates(profilePath, world) {
+  return await invoke('plugin:worlds|worlds_check_datapack_updates', { profilePath, world })
+}
+
+/// Update a datapack of a world to its newest version
+/// Returns the file name of the updated datapack
+export async function update_datapack(profilePath, world, name) {
+  return await invoke('plugin:worlds|worlds_update_datapack', { profilePath, world, name })
+}
@@ -14,6 +14,9 @@ export const useNotifications = defineStore('notificationsStore', {
   },
 })
 
+// Errors from theseus are { field_name, message, details }, details being
+// { code, recovery, profile_path, java_path, exit_code, url } with the fields
+// that don't apply left out. The code and recovery action don't change between releases
 export const handleError = (err) => {
   const notifs = useNotifications()
   notifs.addNotification({